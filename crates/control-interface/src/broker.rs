@@ -119,6 +119,17 @@ pub mod v1 {
             )
         }
 
+        pub fn drain_provider(
+            topic_prefix: &Option<String>,
+            lattice: &str,
+            host_id: &str,
+        ) -> String {
+            format!(
+                "{}.provider.drain.{host_id}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
+
         pub fn update_component(
             topic_prefix: &Option<String>,
             lattice: &str,
@@ -1,21 +1,33 @@
 //! Control interface client
 
 use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hasher};
+use core::sync::atomic::{AtomicU32, Ordering};
 use core::time::Duration;
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use async_nats::Subscriber;
+use async_nats::{RequestErrorKind, Subscriber};
 use cloudevents::event::Event;
 use futures::{StreamExt, TryFutureExt};
 use serde::de::DeserializeOwned;
 use tokio::sync::mpsc::Receiver;
 use tracing::{debug, error, instrument, trace};
 
+/// Delay before the first retry of a request, doubled on every retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Maximum delay between retries of a request
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+use wasmcloud_core::DrainReport;
+
 use crate::types::link::InterfaceLinkDefinition;
 
 use crate::types::ctl::{
-    CtlResponse, ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
-    UpdateComponentCommand,
+    CtlResponse, DrainProviderCommand, ScaleComponentCommand, StartProviderCommand,
+    StopHostCommand, StopProviderCommand, UpdateComponentCommand,
 };
 use crate::types::host::{Host, HostInventory, HostLabel};
 use crate::types::registry::RegistryCredential;
@@ -36,6 +48,8 @@ pub struct Client {
     pub lattice: String,
     timeout: Duration,
     auction_timeout: Duration,
+    retries: u32,
+    attempts: Arc<AtomicU32>,
 }
 
 impl Debug for Client {
@@ -45,6 +59,7 @@ impl Debug for Client {
             .field("lattice", &self.lattice)
             .field("timeout", &self.timeout)
             .field("auction_timeout", &self.auction_timeout)
+            .field("retries", &self.retries)
             .finish_non_exhaustive()
     }
 }
@@ -56,6 +71,13 @@ impl Client {
     pub fn nats_client(&self) -> async_nats::Client {
         self.nc.clone()
     }
+
+    /// Most attempts any single request made by this client, or a client sharing its attempts
+    /// counter (see [`ClientBuilder::attempts`]), needed. Greater than 1 if a request was retried
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed)
+    }
 }
 
 /// A client builder that can be used to fluently provide configuration settings used to construct
@@ -66,6 +88,8 @@ pub struct ClientBuilder {
     lattice: String,
     timeout: Duration,
     auction_timeout: Duration,
+    retries: u32,
+    attempts: Arc<AtomicU32>,
 }
 
 impl ClientBuilder {
@@ -79,6 +103,8 @@ impl ClientBuilder {
             lattice: "default".to_string(),
             timeout: Duration::from_secs(2),
             auction_timeout: Duration::from_secs(5),
+            retries: 0,
+            attempts: Arc::default(),
         }
     }
 
@@ -119,6 +145,21 @@ impl ClientBuilder {
         }
     }
 
+    /// Sets the number of times requests are retried, with jittered exponential backoff, when no
+    /// host responds to them or they time out. Queries for hosts are retried when no host responds.
+    /// If not set, requests are not retried
+    #[must_use]
+    pub fn retries(self, retries: u32) -> ClientBuilder {
+        ClientBuilder { retries, ..self }
+    }
+
+    /// Sets the counter recording the most attempts any single request needed, so that it can be
+    /// shared by several clients (see [`Client::max_attempts`])
+    #[must_use]
+    pub fn attempts(self, attempts: Arc<AtomicU32>) -> ClientBuilder {
+        ClientBuilder { attempts, ..self }
+    }
+
     /// Constructs the client with the given configuration from the builder
     #[must_use]
     pub fn build(self) -> Client {
@@ -128,6 +169,8 @@ impl ClientBuilder {
             lattice: self.lattice,
             timeout: self.timeout,
             auction_timeout: self.auction_timeout,
+            retries: self.retries,
+            attempts: self.attempts,
         }
     }
 }
@@ -147,29 +190,81 @@ impl Client {
         payload: Vec<u8>,
         timeout: Duration,
     ) -> Result<async_nats::Message> {
-        match tokio::time::timeout(
-            timeout,
-            self.nc.request_with_headers(
-                subject,
-                otel::HeaderInjector::default_with_span().into(),
-                payload.into(),
-            ),
-        )
-        .await
-        {
-            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out").into()),
-            Ok(Ok(message)) => Ok(message),
-            Ok(Err(e)) => Err(e.into()),
+        let payload = bytes::Bytes::from(payload);
+        let mut attempt = 1;
+        loop {
+            let (res, retryable) = match tokio::time::timeout(
+                timeout,
+                self.nc.request_with_headers(
+                    subject.clone(),
+                    otel::HeaderInjector::default_with_span().into(),
+                    payload.clone(),
+                ),
+            )
+            .await
+            {
+                Err(_) => (
+                    Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out").into()),
+                    true,
+                ),
+                Ok(Ok(message)) => (Ok(message), false),
+                Ok(Err(e)) => {
+                    let retryable = matches!(
+                        e.kind(),
+                        RequestErrorKind::NoResponders | RequestErrorKind::TimedOut
+                    );
+                    (Err(e.into()), retryable)
+                }
+            };
+            match res {
+                Err(e) if retryable && attempt <= self.retries => {
+                    self.retry(&subject, attempt, &e.to_string()).await;
+                    attempt += 1;
+                }
+                res => {
+                    self.attempts.fetch_max(attempt, Ordering::Relaxed);
+                    return res;
+                }
+            }
         }
     }
 
+    /// Wait before retrying a request on `subject` which failed for the `attempt`th time
+    async fn retry(&self, subject: &str, attempt: u32, reason: &str) {
+        let backoff = RETRY_BASE_DELAY
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(RETRY_MAX_DELAY);
+        // Wait between half and all of the backoff, so that concurrent clients do not retry in step
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        let delay = backoff / 2 + backoff / 2 * u32::try_from(jitter).unwrap_or_default() / 1000;
+        debug!(
+            subject,
+            attempt,
+            ?delay,
+            reason,
+            "retrying control interface request"
+        );
+        tokio::time::sleep(delay).await;
+    }
+
     /// Queries the lattice for all responsive hosts, waiting for the full period specified by
     /// _timeout_.
     #[instrument(level = "debug", skip_all)]
     pub async fn get_hosts(&self) -> Result<Vec<CtlResponse<Host>>> {
         let subject = broker::v1::queries::hosts(&self.topic_prefix, &self.lattice);
         debug!("get_hosts:publish {}", &subject);
-        self.publish_and_wait(subject, Vec::new()).await
+        let mut attempt = 1;
+        loop {
+            let hosts = self.publish_and_wait(subject.clone(), Vec::new()).await?;
+            // Hosts which just started may not have subscribed to queries yet
+            if hosts.is_empty() && attempt <= self.retries {
+                self.retry(&subject, attempt, "no hosts responded").await;
+                attempt += 1;
+                continue;
+            }
+            self.attempts.fetch_max(attempt, Ordering::Relaxed);
+            return Ok(hosts);
+        }
     }
 
     /// Retrieves the contents of a running host
@@ -588,6 +683,38 @@ impl Client {
         }
     }
 
+    /// Issues a command to a host to drain a provider: the provider stops accepting new invocations
+    /// and waits at most `timeout_ms` for in-flight ones to complete, reporting the outcome in the
+    /// response. The provider keeps running until it is stopped with [`Client::stop_provider`]
+    #[instrument(level = "debug", skip_all)]
+    pub async fn drain_provider(
+        &self,
+        host_id: &str,
+        provider_id: &str,
+        timeout_ms: u64,
+    ) -> Result<CtlResponse<DrainReport>> {
+        let host_id = parse_identifier(&IdentifierKind::HostId, host_id)?;
+
+        let subject = broker::v1::commands::drain_provider(
+            &self.topic_prefix,
+            &self.lattice,
+            host_id.as_str(),
+        );
+        debug!("drain_provider:request {}", &subject);
+        let bytes = json_serialize(DrainProviderCommand {
+            host_id,
+            provider_id: parse_identifier(&IdentifierKind::ComponentId, provider_id)?,
+            timeout_ms,
+        })?;
+
+        // The host replies once the drain completed or timed out
+        let timeout = self.timeout + Duration::from_millis(timeout_ms);
+        match self.request_timeout(subject, bytes, timeout).await {
+            Ok(msg) => Ok(json_deserialize(&msg.payload)?),
+            Err(e) => Err(format!("Did not receive drain provider acknowledgement: {e}").into()),
+        }
+    }
+
     /// Issues a command to a specific host to perform a graceful termination. The target host will
     /// acknowledge receipt of the command before it attempts a shutdown. To deterministically
     /// verify that the host is down, a client should monitor for the "host stopped" event or
@@ -129,6 +129,21 @@ pub struct StopProviderCommand {
     pub provider_id: ComponentId,
 }
 
+/// A request that the given provider on the indicated host stops accepting new invocations and
+/// completes in-flight ones. The host relays the request to the provider, signed with its key
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DrainProviderCommand {
+    /// Host ID on which the provider runs
+    #[serde(default)]
+    pub host_id: String,
+    /// Unique identifier for the provider to drain
+    #[serde(default)]
+    pub provider_id: ComponentId,
+    /// Maximum time in milliseconds to wait for in-flight invocations to complete
+    #[serde(default)]
+    pub timeout_ms: u64,
+}
+
 /// A command instructing a specific host to perform a live update
 /// on the indicated component by supplying a new image reference. Note that
 /// live updates are only possible through image references
@@ -0,0 +1,180 @@
+//! Authentication of the control messages hosts send to capability providers
+//!
+//! Control messages (e.g. shutdown and drain requests and link updates) are published on plain
+//! NATS subjects, so anyone with access to the lattice could forge them. Hosts which supply their
+//! public key in [`HostData::host_signing_key`](crate::HostData::host_signing_key) sign every
+//! control message with their host key, attaching the signature along with a timestamp and a
+//! nonce as headers. The signature covers the subject, timestamp, nonce and payload, so that
+//! signed messages can neither be redirected to another subject nor replayed after
+//! [`CONTROL_MESSAGE_MAX_AGE`].
+
+use core::time::Duration;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, ensure, Context as _};
+use async_nats::HeaderMap;
+use nkeys::KeyPair;
+
+/// Header holding the hex-encoded signature of a control message
+pub const CONTROL_SIGNATURE_HEADER: &str = "wasmcloud-control-signature";
+
+/// Header holding the time a control message was signed at, in milliseconds since the Unix epoch
+pub const CONTROL_TIMESTAMP_HEADER: &str = "wasmcloud-control-timestamp";
+
+/// Header holding the unique nonce of a control message
+pub const CONTROL_NONCE_HEADER: &str = "wasmcloud-control-nonce";
+
+/// Maximum difference between the time a control message was signed at and the time it is
+/// verified at, in either direction to tolerate clock skew
+pub const CONTROL_MESSAGE_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Data covered by the signature of a control message
+fn signed_data(subject: &str, timestamp: &str, nonce: &str, payload: &[u8]) -> Vec<u8> {
+    let mut data =
+        Vec::with_capacity(subject.len() + timestamp.len() + nonce.len() + payload.len() + 3);
+    for part in [subject.as_bytes(), timestamp.as_bytes(), nonce.as_bytes()] {
+        data.extend_from_slice(part);
+        data.push(b'\n');
+    }
+    data.extend_from_slice(payload);
+    data
+}
+
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// Sign a control message published on `subject` with `key`, adding the signature, timestamp and
+/// nonce to `headers`
+pub fn sign_control_message(
+    key: &KeyPair,
+    subject: &str,
+    payload: &[u8],
+    headers: &mut HeaderMap,
+) -> anyhow::Result<()> {
+    let timestamp = unix_millis(SystemTime::now()).to_string();
+    let nonce = ulid::Ulid::new().to_string();
+    let signature = key
+        .sign(&signed_data(subject, &timestamp, &nonce, payload))
+        .context("failed to sign control message")?;
+    headers.insert(CONTROL_SIGNATURE_HEADER, hex::encode(signature).as_str());
+    headers.insert(CONTROL_TIMESTAMP_HEADER, timestamp.as_str());
+    headers.insert(CONTROL_NONCE_HEADER, nonce.as_str());
+    Ok(())
+}
+
+/// Verify that a control message received on `subject` was signed by the host with public key
+/// `public_key` no longer than [`CONTROL_MESSAGE_MAX_AGE`] before `now`, returning its nonce.
+///
+/// Callers are responsible for rejecting nonces they have already seen within
+/// [`CONTROL_MESSAGE_MAX_AGE`]
+pub fn verify_control_message(
+    public_key: &str,
+    subject: &str,
+    payload: &[u8],
+    headers: Option<&HeaderMap>,
+    now: SystemTime,
+) -> anyhow::Result<String> {
+    let header = |name: &str| {
+        headers
+            .and_then(|headers| headers.get(name))
+            .map(|value| value.as_str().to_string())
+            .with_context(|| format!("control message is missing the `{name}` header"))
+    };
+    let signature = header(CONTROL_SIGNATURE_HEADER)?;
+    let timestamp = header(CONTROL_TIMESTAMP_HEADER)?;
+    let nonce = header(CONTROL_NONCE_HEADER)?;
+    ensure!(!nonce.is_empty(), "control message nonce is empty");
+
+    let signature = hex::decode(&signature).context("control message signature is not hex")?;
+    let key = KeyPair::from_public_key(public_key).context("invalid host signing key")?;
+    if key
+        .verify(
+            &signed_data(subject, &timestamp, &nonce, payload),
+            &signature,
+        )
+        .is_err()
+    {
+        bail!("control message signature is invalid");
+    }
+
+    let signed_at: u128 = timestamp
+        .parse()
+        .context("control message timestamp is not a number")?;
+    let age = unix_millis(now).abs_diff(signed_at);
+    ensure!(
+        age <= CONTROL_MESSAGE_MAX_AGE.as_millis(),
+        "control message was signed {age}ms away from the current time"
+    );
+    Ok(nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_control_messages_verify() -> anyhow::Result<()> {
+        let host_key = KeyPair::new_server();
+        let public_key = host_key.public_key();
+        let subject = "wasmbus.rpc.default.provider.default.shutdown";
+        let payload = br#"{"host_id":"host"}"#;
+
+        let mut headers = HeaderMap::new();
+        sign_control_message(&host_key, subject, payload, &mut headers)?;
+        let nonce = verify_control_message(
+            &public_key,
+            subject,
+            payload,
+            Some(&headers),
+            SystemTime::now(),
+        )?;
+        assert_eq!(
+            headers.get(CONTROL_NONCE_HEADER).map(|v| v.as_str()),
+            Some(nonce.as_str())
+        );
+
+        // Unsigned messages, other payloads, subjects and signers are rejected
+        assert!(
+            verify_control_message(&public_key, subject, payload, None, SystemTime::now()).is_err()
+        );
+        assert!(verify_control_message(
+            &public_key,
+            subject,
+            br#"{"host_id":"other"}"#,
+            Some(&headers),
+            SystemTime::now()
+        )
+        .is_err());
+        assert!(verify_control_message(
+            &public_key,
+            "wasmbus.rpc.default.other.default.shutdown",
+            payload,
+            Some(&headers),
+            SystemTime::now()
+        )
+        .is_err());
+        assert!(verify_control_message(
+            &KeyPair::new_server().public_key(),
+            subject,
+            payload,
+            Some(&headers),
+            SystemTime::now()
+        )
+        .is_err());
+
+        // Stale messages are rejected
+        assert!(verify_control_message(
+            &public_key,
+            subject,
+            payload,
+            Some(&headers),
+            SystemTime::now() + CONTROL_MESSAGE_MAX_AGE + Duration::from_secs(1)
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 //! [docs-wasmcloud-hosts]: <https://wasmcloud.com/docs/concepts/hosts>
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
@@ -14,11 +15,42 @@ use crate::wit::{deserialize_wit_map, serialize_wit_map, WitMap};
 /// Environment settings for initializing a capability provider
 pub type HostEnvValues = WitMap<String>;
 
+/// Version of the [`HostData`] schema sent by this version of the host, see
+/// [`HostData::host_data_schema_version`]
+pub const HOST_DATA_SCHEMA_VERSION: u32 = 2;
+
+/// Capability of hosts verifying control messages signed with [`HostData::host_signing_key`]
+pub const HOST_CAPABILITY_SIGNED_CONTROL: &str = "signed-control";
+/// Capability of hosts sending configuration updates to running providers
+pub const HOST_CAPABILITY_CONFIG_UPDATES: &str = "config-updates";
+/// Capability of hosts encrypting secrets sent to providers with xkeys
+pub const HOST_CAPABILITY_SECRETS: &str = "secrets";
+
 /// initialization data for a capability provider
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct HostData {
+    /// Version of the schema of this data. Absent from the data sent by hosts predating it, which
+    /// implies version 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_data_schema_version: Option<u32>,
+    /// Optional features supported by the host, e.g. [`HOST_CAPABILITY_SIGNED_CONTROL`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_capabilities: Vec<String>,
     #[serde(default)]
     pub host_id: String,
+    /// Human-friendly name of the host
+    #[serde(default)]
+    pub host_friendly_name: String,
+    /// Labels of the host at the time the provider was started
+    #[serde(default)]
+    pub host_labels: HashMap<String, String>,
+    /// Version of the host
+    #[serde(default)]
+    pub host_version: String,
+    /// Public key the host signs control messages sent to the provider with, see
+    /// [`control_auth`](crate::control_auth)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_signing_key: Option<String>,
     #[serde(default)]
     pub lattice_rpc_prefix: String,
     #[serde(default)]
@@ -27,11 +59,36 @@ pub struct HostData {
     pub lattice_rpc_user_jwt: String,
     #[serde(default)]
     pub lattice_rpc_user_seed: String,
+    /// Token to authenticate to the lattice RPC NATS server with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_user_token: Option<String>,
+    /// Username to authenticate to the lattice RPC NATS server with, requires a password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_user_name: Option<String>,
+    /// Password to authenticate to the lattice RPC NATS server with, requires a username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_user_password: Option<String>,
+    /// Path to a NATS credentials file to authenticate to the lattice RPC NATS server with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_credsfile: Option<PathBuf>,
+    /// Path to a CA certificate used to verify the lattice RPC NATS server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_tls_ca_file: Option<PathBuf>,
+    /// Path to a client certificate to present to the lattice RPC NATS server, requires a key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_tls_cert_file: Option<PathBuf>,
+    /// Path to the private key of the client certificate, requires a certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_rpc_tls_key_file: Option<PathBuf>,
+    /// True if the connection to the lattice RPC NATS server must use TLS
+    #[serde(default)]
+    pub lattice_rpc_tls_required: bool,
     #[serde(default)]
     pub lattice_rpc_url: String,
     #[serde(default)]
     pub provider_key: String,
     #[serde(
+        default,
         serialize_with = "serialize_wit_map",
         deserialize_with = "deserialize_wit_map"
     )]
@@ -39,6 +96,7 @@ pub struct HostData {
     #[serde(default)]
     pub instance_id: String,
     /// initial list of links for provider
+    #[serde(default)]
     pub link_definitions: Vec<InterfaceLinkDefinition>,
     /// list of cluster issuers.
     #[serde(default)]
@@ -55,5 +113,6 @@ pub struct HostData {
     /// The log level providers should log at
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub log_level: Option<Level>,
+    #[serde(default)]
     pub otel_config: OtelConfig,
 }
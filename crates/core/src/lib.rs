@@ -1,5 +1,6 @@
 #![forbid(clippy::unwrap_used)]
 
+pub mod control_auth;
 pub mod logging;
 pub mod nats;
 pub mod tls;
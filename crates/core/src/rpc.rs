@@ -7,6 +7,8 @@
 //!
 //! [docs-wasmcloud-rpc]: <https://wasmcloud.com/docs/hosts/lattice-protocols/rpc>
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -56,3 +58,153 @@ pub fn health_subject(lattice: &str, provider_key: &str) -> String {
 pub fn shutdown_subject(lattice: &str, provider_key: &str, link_name: &str) -> String {
     format!("wasmbus.rpc.{lattice}.{provider_key}.{link_name}.shutdown")
 }
+
+/// Reason set in the shutdown requests sent on [`shutdown_subject`] when the provider is stopped
+/// to be replaced by a new version, e.g. `{"host_id": "N...", "reason": "update"}`
+pub const SHUTDOWN_REASON_UPDATE: &str = "update";
+
+/// Host ID set in the shutdown requests sent on [`shutdown_subject`] to stop every instance of the
+/// provider in the lattice, whichever host runs it, e.g. `{"host_id": "*"}`
+pub const SHUTDOWN_ALL_HOSTS: &str = "*";
+
+/// Scope set in the shutdown requests sent on [`shutdown_subject`] to stop every instance of the
+/// provider in the lattice, equivalent to a host ID of [`SHUTDOWN_ALL_HOSTS`], e.g.
+/// `{"scope": "lattice"}`
+pub const SHUTDOWN_SCOPE_LATTICE: &str = "lattice";
+
+/// Generate the wasmbus RPC subject for asking a given provider to prepare to shut down
+///
+/// Providers receiving a [`PrepareShutdownRequest`] on this subject stop accepting new invocations
+/// and wait for in-flight ones to complete for at most the requested timeout, then reply with a
+/// [`DrainReport`]. The shutdown itself is requested separately on [`shutdown_subject`]. Hosts
+/// send these requests on behalf of control interface clients, signed with their key.
+#[must_use]
+pub fn prepare_shutdown_subject(lattice: &str, provider_key: &str, link_name: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.{link_name}.prepare_shutdown")
+}
+
+/// Request sent on [`prepare_shutdown_subject`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PrepareShutdownRequest {
+    /// The ID of the host running the provider
+    pub host_id: String,
+    /// Maximum duration in milliseconds to wait for in-flight invocations to complete
+    pub timeout_ms: u64,
+}
+
+/// Outcome of a provider preparing to shut down, sent in reply to a [`PrepareShutdownRequest`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DrainReport {
+    /// Whether all in-flight invocations completed in time
+    pub drained: bool,
+    /// Number of in-flight invocations that did not complete in time, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abandoned: Option<usize>,
+}
+
+/// Generate the wasmbus RPC subject for pushing a configuration update to a given provider
+///
+/// Hosts publish on this subject, signed with their key, whenever the named configuration a
+/// running provider was started with changes. Providers receiving a
+/// [`ConfigUpdateRequest`] on this subject validate the new configuration before applying it, then
+/// reply with a [`ConfigUpdateOutcome`]. A rejected update leaves the configuration of the provider
+/// unchanged.
+#[must_use]
+pub fn config_update_subject(lattice: &str, provider_key: &str, link_name: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.{link_name}.config_update")
+}
+
+/// Request sent on [`config_update_subject`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ConfigUpdateRequest {
+    /// The ID of the host running the provider
+    pub host_id: String,
+    /// The complete configuration of the provider after the update
+    #[serde(default)]
+    pub config: HashMap<String, String>,
+}
+
+/// Reason a provider rejected a configuration update
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConfigRejection {
+    /// Why the configuration was rejected
+    pub reason: String,
+    /// Keys of the configuration which caused the rejection, if known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+}
+
+impl ConfigRejection {
+    /// Reject a configuration update for `reason`
+    #[must_use]
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Name the keys of the configuration which caused the rejection
+    #[must_use]
+    pub fn with_keys(mut self, keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+impl core::fmt::Display for ConfigRejection {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.keys.is_empty() {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(f, "{} (keys: {})", self.reason, self.keys.join(", "))
+        }
+    }
+}
+
+/// Outcome of a configuration update, sent in reply to a [`ConfigUpdateRequest`]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConfigUpdateOutcome {
+    /// Whether the provider applied the configuration
+    pub accepted: bool,
+    /// Why the provider did not apply the configuration, if it was rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<ConfigRejection>,
+}
+
+impl ConfigUpdateOutcome {
+    /// Outcome of an applied configuration update
+    #[must_use]
+    pub fn accepted() -> Self {
+        Self {
+            accepted: true,
+            rejection: None,
+        }
+    }
+
+    /// Outcome of a configuration update rejected for `rejection`
+    #[must_use]
+    pub fn rejected(rejection: ConfigRejection) -> Self {
+        Self {
+            accepted: false,
+            rejection: Some(rejection),
+        }
+    }
+}
+
+/// Generate the subject of the lattice event hosts publish when their labels change
+///
+/// The event is a CloudEvent whose data contains the `host_id` and the complete set of `labels`
+/// of the host after the change.
+#[must_use]
+pub fn host_labels_changed_subject(lattice: &str) -> String {
+    format!("wasmbus.evt.{lattice}.labels_changed")
+}
+
+/// Generate the subject providers forward their logs to, when configured with `forward_logs=true`
+///
+/// Each message is a JSON array of log events, in the order they were emitted.
+#[must_use]
+pub fn provider_log_subject(lattice: &str) -> String {
+    format!("wasmbus.evt.{lattice}.provider_log")
+}
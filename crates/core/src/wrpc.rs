@@ -5,38 +5,108 @@
 //! - Propagate trace context
 //! - Append invocation headers
 //! - Perform invocation validation (where necessary)
+//! - Keep payloads within the max payload of the NATS server
 //!
 //! Most logic is delegated to the underlying `wrpc_transport_nats` client, which provides the
 //! actual NATS-based transport implementation.
 //!
 //! [wrpc-transport]: https://docs.rs/wrpc-transport
 
+use core::fmt;
 use core::future::Future;
 use core::time::Duration;
 
 use std::sync::Arc;
 
 use anyhow::Context as _;
-use async_nats::HeaderMap;
+use async_nats::{HeaderMap, PublishError, PublishErrorKind};
 use bytes::Bytes;
 use tower::ServiceExt;
-use tracing::instrument;
+use tracing::{debug, instrument};
 use wrpc_transport::{AcceptedInvocation, Encode, IncomingInvocation, OutgoingInvocation};
 use wrpc_transport_nats::{Subject, Subscriber, Transmission};
 
+/// Error returned when a payload does not fit in the max payload of the NATS server of the lattice
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayloadTooLarge {
+    /// What the payload is, e.g. `response` or `parameters of wrpc:keyvalue/store.set`
+    pub kind: String,
+    /// Size of the payload in bytes, if known
+    pub size: Option<usize>,
+    /// Max payload of the NATS server in bytes
+    pub max_payload: usize,
+}
+
+impl fmt::Display for PayloadTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(size) = self.size {
+            write!(f, " {}", format_payload_size(size))?;
+        }
+        write!(
+            f,
+            " exceeds lattice max payload {} — consider streaming",
+            format_payload_size(self.max_payload)
+        )
+    }
+}
+
+impl std::error::Error for PayloadTooLarge {}
+
+/// Format a payload size in bytes for humans, e.g. `2.3MiB` or `512KiB`
+#[must_use]
+pub fn format_payload_size(size: usize) -> String {
+    const UNITS: [&str; 3] = ["B", "KiB", "MiB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let value = format!("{value:.1}");
+    let value = value.strip_suffix(".0").unwrap_or(&value);
+    format!("{value}{}", UNITS[unit])
+}
+
+/// Number of bytes `headers` take in a NATS message, which count towards the max payload
+#[must_use]
+pub fn headers_size(headers: &HeaderMap) -> usize {
+    // `NATS/1.0\r\n`, a line per value and a blank line
+    let lines: usize = headers
+        .iter()
+        .map(|(name, values)| {
+            values
+                .iter()
+                .map(|value| name.to_string().len() + value.as_str().len() + 4)
+                .sum::<usize>()
+        })
+        .sum();
+    10 + lines + 2
+}
+
 /// Wrapper around [`wrpc_transport_nats::Transmitter`] that includes a [`async_nats::HeaderMap`] for
 /// passing invocation and trace context.
+///
+/// Payloads which do not fit in the max payload of the NATS server are transmitted in chunks, as
+/// receivers decode values across as many messages as needed
 #[derive(Clone, Debug)]
 pub struct TransmitterWithHeaders {
     inner: wrpc_transport_nats::Transmitter,
     headers: HeaderMap,
+    nats: Arc<async_nats::Client>,
 }
 
 impl TransmitterWithHeaders {
-    pub(crate) fn new(transmitter: wrpc_transport_nats::Transmitter, headers: HeaderMap) -> Self {
+    pub(crate) fn new(
+        transmitter: wrpc_transport_nats::Transmitter,
+        headers: HeaderMap,
+        nats: Arc<async_nats::Client>,
+    ) -> Self {
         Self {
             inner: transmitter,
             headers,
+            nats,
         }
     }
 }
@@ -51,9 +121,29 @@ impl wrpc_transport::Transmitter for TransmitterWithHeaders {
         subject: Self::Subject,
         payload: Bytes,
     ) -> Result<(), Self::PublishError> {
-        self.inner
-            .transmit_with_headers(subject, self.headers.clone(), payload)
-            .await
+        let max_payload = self.nats.server_info().max_payload;
+        // Headers are sent with every chunk and count towards the max payload
+        let chunk_size = max_payload
+            .saturating_sub(headers_size(&self.headers))
+            .max(1);
+        if payload.len() <= chunk_size {
+            return self
+                .inner
+                .transmit_with_headers(subject, self.headers.clone(), payload)
+                .await;
+        }
+        debug!(
+            size = payload.len(),
+            max_payload, "transmitting payload exceeding the max payload in chunks"
+        );
+        let mut payload = payload;
+        while !payload.is_empty() {
+            let chunk = payload.split_to(chunk_size.min(payload.len()));
+            self.inner
+                .transmit_with_headers(subject.clone(), self.headers.clone(), chunk)
+                .await?;
+        }
+        Ok(())
     }
 }
 
@@ -63,9 +153,15 @@ pub struct InvocationWithHeaders {
     inner: wrpc_transport_nats::Invocation,
     headers: HeaderMap,
     timeout: Duration,
+    nats: Arc<async_nats::Client>,
 }
 
 impl InvocationWithHeaders {
+    /// Headers sent with this invocation
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
     /// This function just delegates to the underlying [`wrpc_transport_nats::Invocation::begin`] function,
     /// but since we're consuming `self` it also returns the headers to avoid a clone in [`InvocationWithHeaders::invoke`].
     pub(crate) async fn begin(
@@ -90,21 +186,44 @@ impl wrpc_transport::Invocation for InvocationWithHeaders {
         params: impl Encode,
     ) -> anyhow::Result<(Self::Transmission, Self::TransmissionFailed)> {
         let subject = self.inner.client().static_subject(instance, name);
+        let nats = Arc::clone(&self.nats);
         let (inv, headers, timeout) = self.begin(params).await?;
 
         let (tx, tx_failed) =
             tokio::time::timeout(timeout, inv.invoke_with_headers(subject, headers))
                 .await
-                .context("invocation timed out")??;
+                .context("invocation timed out")?
+                .map_err(|err| {
+                    // Parameters sent with the invocation itself cannot be chunked, surface a
+                    // descriptive error rather than the one of the transport
+                    if is_max_payload_exceeded(&err) {
+                        anyhow::Error::new(PayloadTooLarge {
+                            kind: format!("parameters of `{instance}.{name}`"),
+                            size: None,
+                            max_payload: nats.server_info().max_payload,
+                        })
+                    } else {
+                        err
+                    }
+                })?;
         Ok((tx, Box::new(tx_failed)))
     }
 }
 
+/// Whether `err` was caused by a message exceeding the max payload of the NATS server
+fn is_max_payload_exceeded(err: &anyhow::Error) -> bool {
+    err.chain().any(|err| {
+        err.downcast_ref::<PublishError>()
+            .is_some_and(|err| err.kind() == PublishErrorKind::MaxPayloadExceeded)
+    })
+}
+
 /// Wrapper around [`wrpc_transport_nats::Acceptor`] that includes a [`async_nats::HeaderMap`] for
 /// passing invocation and trace context.
 pub struct AcceptorWithHeaders {
     inner: wrpc_transport_nats::Acceptor,
     headers: HeaderMap,
+    nats: Arc<async_nats::Client>,
 }
 
 impl wrpc_transport::Acceptor for AcceptorWithHeaders {
@@ -123,7 +242,7 @@ impl wrpc_transport::Acceptor for AcceptorWithHeaders {
         Ok((
             result_subject,
             error_subject,
-            TransmitterWithHeaders::new(transmitter, self.headers),
+            TransmitterWithHeaders::new(transmitter, self.headers, self.nats),
         ))
     }
 }
@@ -135,6 +254,7 @@ pub struct Client {
     inner: wrpc_transport_nats::Client,
     headers: HeaderMap,
     timeout: Duration,
+    nats: Arc<async_nats::Client>,
 }
 
 impl Client {
@@ -152,12 +272,36 @@ impl Client {
         headers: HeaderMap,
         timeout: Duration,
     ) -> Self {
+        let nats = nats.into();
         Self {
-            inner: wrpc_transport_nats::Client::new(nats, format!("{lattice}.{component_id}")),
+            inner: wrpc_transport_nats::Client::new(
+                Arc::clone(&nats),
+                format!("{lattice}.{component_id}"),
+            ),
             headers,
             timeout,
+            nats,
         }
     }
+
+    /// Timeout applied to invocations made with this [Client]
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Headers included with each outbound invocation made with this [Client]
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Max payload of the NATS server this [Client] is connected to, in bytes. Parameters of
+    /// invocations must fit in it along with their headers, while larger results and streams are
+    /// transmitted in chunks
+    #[must_use]
+    pub fn max_payload(&self) -> usize {
+        self.nats.server_info().max_payload
+    }
 }
 
 impl wrpc_transport::Client for Client {
@@ -187,19 +331,20 @@ impl wrpc_transport::Client for Client {
             + 'static,
         Fut: Future<Output = anyhow::Result<AcceptedInvocation<Ctx, T, Tx>>> + Send,
     {
+        let nats = Arc::clone(&self.nats);
         self.inner.serve(
             instance,
             name,
             svc.map_request(
-                |IncomingInvocation {
-                     context,
-                     payload,
-                     param_subject,
-                     error_subject,
-                     handshake_subject,
-                     subscriber,
-                     acceptor,
-                 }: IncomingInvocation<Self::Context, _, _>| {
+                move |IncomingInvocation {
+                          context,
+                          payload,
+                          param_subject,
+                          error_subject,
+                          handshake_subject,
+                          subscriber,
+                          acceptor,
+                      }: IncomingInvocation<Self::Context, _, _>| {
                     IncomingInvocation {
                         context: context.clone(),
                         payload,
@@ -210,6 +355,7 @@ impl wrpc_transport::Client for Client {
                         acceptor: AcceptorWithHeaders {
                             inner: acceptor,
                             headers: context.unwrap_or_default(),
+                            nats: Arc::clone(&nats),
                         },
                     }
                 },
@@ -225,6 +371,7 @@ impl wrpc_transport::Client for Client {
             inner: transport_invocation.invocation,
             headers: self.headers.clone(),
             timeout: self.timeout,
+            nats: Arc::clone(&self.nats),
         };
         OutgoingInvocation {
             invocation: invocation_with_headers,
@@ -234,3 +381,43 @@ impl wrpc_transport::Client for Client {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_payload_size, headers_size, PayloadTooLarge};
+    use async_nats::HeaderMap;
+
+    #[test]
+    fn test_payload_too_large() {
+        assert_eq!(format_payload_size(100), "100B");
+        assert_eq!(format_payload_size(32 * 1024), "32KiB");
+        assert_eq!(format_payload_size(1024 * 1024), "1MiB");
+        assert_eq!(
+            PayloadTooLarge {
+                kind: "response".into(),
+                size: Some(2_411_725),
+                max_payload: 1024 * 1024,
+            }
+            .to_string(),
+            "response 2.3MiB exceeds lattice max payload 1MiB — consider streaming"
+        );
+        assert_eq!(
+            PayloadTooLarge {
+                kind: "parameters of `wrpc:keyvalue/store.set`".into(),
+                size: None,
+                max_payload: 32 * 1024,
+            }
+            .to_string(),
+            "parameters of `wrpc:keyvalue/store.set` exceeds lattice max payload 32KiB — consider streaming"
+        );
+    }
+
+    #[test]
+    fn test_headers_size() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(headers_size(&headers), 12);
+        headers.insert("source-id", "provider");
+        // `source-id: provider\r\n`
+        assert_eq!(headers_size(&headers), 12 + 21);
+    }
+}
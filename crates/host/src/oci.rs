@@ -6,6 +6,7 @@ use crate::{par, RegistryConfig};
 use core::str::FromStr;
 
 use std::env::temp_dir;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context as _};
@@ -104,6 +105,22 @@ async fn cache_oci_image(
     Ok(())
 }
 
+/// Whether `registry` (optionally followed by a port) is on the loopback interface. Like other OCI
+/// clients, artifacts are pulled from such registries over HTTP if HTTPS is not available, e.g. from
+/// the ephemeral registry `wash start component` serves local components with
+fn is_loopback_registry(registry: &str) -> bool {
+    let host = registry
+        .rsplit_once(':')
+        .filter(|(_, port)| port.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(registry, |(host, _)| host);
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
 /// OCI artifact fetcher
 #[derive(Clone, Debug)]
 pub struct Fetcher {
@@ -174,7 +191,7 @@ impl Fetcher {
 
         let img = Reference::from_str(img)?;
 
-        let protocol = if self.allow_insecure {
+        let protocol = if self.allow_insecure || is_loopback_registry(img.registry()) {
             ClientProtocol::HttpsExcept(vec![img.registry().to_string()])
         } else {
             ClientProtocol::Https
@@ -261,3 +278,19 @@ impl Fetcher {
             .with_context(|| format!("failed to read `{}`", path.display()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_registries() {
+        assert!(is_loopback_registry("localhost:5000"));
+        assert!(is_loopback_registry("127.0.0.1:41234"));
+        assert!(is_loopback_registry("[::1]:5000"));
+        assert!(is_loopback_registry("localhost"));
+        assert!(!is_loopback_registry("ghcr.io"));
+        assert!(!is_loopback_registry("10.0.0.1:5000"));
+        assert!(!is_loopback_registry("localhost.example.com:5000"));
+    }
+}
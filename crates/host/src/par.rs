@@ -1,13 +1,12 @@
 // Adapted from
 // https://github.com/wasmCloud/wasmcloud-otp/blob/5f13500646d9e077afa1fca67a3fe9c8df5f3381/host_core/native/hostcore_wasmcloud_native/src/par.rs
 
-use std::env::consts::{ARCH, OS};
 use std::env::temp_dir;
 use std::path::{Path, PathBuf};
 use std::str;
 
 use anyhow::{anyhow, Context};
-use provider_archive::ProviderArchive;
+use provider_archive::{native_target, ProviderArchive};
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use wascap::jwt;
@@ -40,10 +39,6 @@ pub(super) async fn create(path: impl AsRef<Path>) -> anyhow::Result<Option<File
         .context("failed to open path")
 }
 
-fn native_target() -> String {
-    format!("{ARCH}-{OS}")
-}
-
 /// Returns the path to the cache file for a provider
 ///
 /// # Arguments
@@ -95,6 +95,20 @@ pub fn component_scale_failed(
     }
 }
 
+pub fn component_invocation_failed(
+    host_id: impl AsRef<str>,
+    image_ref: impl AsRef<str>,
+    component_id: impl AsRef<str>,
+    error: &anyhow::Error,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "image_ref": image_ref.as_ref(),
+        "component_id": component_id.as_ref(),
+        "error": format!("{error:#}"),
+    })
+}
+
 pub fn linkdef_set(
     link: &wasmcloud_control_interface::InterfaceLinkDefinition,
 ) -> serde_json::Value {
@@ -203,6 +217,19 @@ pub fn provider_health_check(
     })
 }
 
+pub fn provider_config_updated(
+    host_id: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+    rejection: Option<&wasmcloud_core::ConfigRejection>,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "provider_id": provider_id.as_ref(),
+        "accepted": rejection.is_none(),
+        "rejection": rejection,
+    })
+}
+
 pub fn config_set(config_name: impl AsRef<str>) -> serde_json::Value {
     json!({
         "config_name": config_name.as_ref(),
@@ -44,12 +44,18 @@ use uuid::Uuid;
 use wascap::{jwt, prelude::ClaimsBuilder};
 use wasmcloud_control_interface::{
     ComponentAuctionAck, ComponentAuctionRequest, ComponentDescription, CtlResponse,
-    DeleteInterfaceLinkDefinitionRequest, HostInventory, HostLabel, InterfaceLinkDefinition,
-    ProviderAuctionAck, ProviderAuctionRequest, ProviderDescription, RegistryCredential,
-    ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
-    UpdateComponentCommand,
+    DeleteInterfaceLinkDefinitionRequest, DrainProviderCommand, HostInventory, HostLabel,
+    InterfaceLinkDefinition, ProviderAuctionAck, ProviderAuctionRequest, ProviderDescription,
+    RegistryCredential, ScaleComponentCommand, StartProviderCommand, StopHostCommand,
+    StopProviderCommand, UpdateComponentCommand,
+};
+use wasmcloud_core::control_auth::sign_control_message;
+use wasmcloud_core::{
+    config_update_subject, prepare_shutdown_subject, ComponentId, ConfigRejection,
+    ConfigUpdateOutcome, ConfigUpdateRequest, DrainReport, HealthCheckResponse, HostData,
+    OtelConfig, PrepareShutdownRequest, CTL_API_VERSION_1, HOST_CAPABILITY_SIGNED_CONTROL,
+    HOST_DATA_SCHEMA_VERSION,
 };
-use wasmcloud_core::{ComponentId, HealthCheckResponse, HostData, OtelConfig, CTL_API_VERSION_1};
 use wasmcloud_runtime::capability::{messaging, IncomingHttp as _, MessagingHandler as _};
 use wasmcloud_runtime::Runtime;
 use wasmcloud_tracing::context::TraceContextInjector;
@@ -66,6 +72,9 @@ use crate::{
 use self::config::{BundleGenerator, ConfigBundle};
 use self::handler::Handler;
 
+/// Minimum interval between two `component_invocation_failed` events of a component
+const INVOCATION_FAILED_EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 struct Queue {
     all_streams: SelectAll<async_nats::Subscriber>,
@@ -234,7 +243,9 @@ impl Component {
         skip(self, context, result_subject, transmitter),
         fields(
             component_id = self.id.as_str(),
-            component_ref = self.image_reference.as_str())
+            component_ref = self.image_reference.as_str(),
+            source_id = tracing::field::Empty,
+            link_name = tracing::field::Empty)
     )]
     async fn handle_invocation(
         &self,
@@ -292,6 +303,16 @@ impl Component {
                 })
                 .collect::<Vec<(String, String)>>();
             wasmcloud_tracing::context::attach_span_context(&trace_context);
+
+            // Identify the link the invocation was made over, when set by the invoking host or
+            // provider
+            let span = tracing::Span::current();
+            if let Some(source_id) = context.get("source-id") {
+                span.record("source_id", source_id.as_str());
+            }
+            if let Some(link_name) = context.get("link-name") {
+                span.record("link_name", link_name.as_str());
+            }
         }
 
         // Instantiate component with expected handlers
@@ -1346,6 +1367,15 @@ impl Host {
 
         let (calls_abort, calls_abort_reg) = AbortHandle::new_pair();
         let max_execution_time = self.max_execution_time;
+        // NOTE: failed invocations are published as events, so that e.g. `wash dev` can detect
+        // crashing components. Events are published off the response path and at most once per
+        // `INVOCATION_FAILED_EVENT_INTERVAL`, so that a component failing under load does not flood
+        // the lattice
+        let last_failure_event = Arc::new(std::sync::Mutex::new(None::<Instant>));
+        let ctl_nats = self.ctl_nats.clone();
+        let event_builder = self.event_builder.clone();
+        let lattice = self.host_config.lattice.clone();
+        let host_id = self.host_key.public_key();
         component.set_max_execution_time(max_execution_time);
         let component = Arc::new(Component {
             component,
@@ -1364,6 +1394,13 @@ impl Host {
                 max_instances.get(),
                 move |invocation| {
                     let component = Arc::clone(&component);
+                    let last_failure_event = Arc::clone(&last_failure_event);
+                    let ctl_nats = ctl_nats.clone();
+                    let event_builder = event_builder.clone();
+                    let lattice = lattice.clone();
+                    let host_id = host_id.clone();
+                    let image_ref = component.image_reference.clone();
+                    let component_id = component.id.clone();
                     async move {
                         let AcceptedInvocation {
                             context,
@@ -1433,6 +1470,37 @@ impl Host {
                                 {
                                     error!(?err, "failed to transmit error to invoker");
                                 }
+                                let publish = last_failure_event.lock().is_ok_and(|mut last| {
+                                    let now = Instant::now();
+                                    if last.is_some_and(|at| {
+                                        now.duration_since(at) < INVOCATION_FAILED_EVENT_INTERVAL
+                                    }) {
+                                        return false;
+                                    }
+                                    *last = Some(now);
+                                    true
+                                });
+                                if publish {
+                                    let data = event::component_invocation_failed(
+                                        &host_id,
+                                        &image_ref,
+                                        &component_id,
+                                        &err,
+                                    );
+                                    spawn(async move {
+                                        if let Err(err) = event::publish(
+                                            &event_builder,
+                                            &ctl_nats,
+                                            &lattice,
+                                            "component_invocation_failed",
+                                            data,
+                                        )
+                                        .await
+                                        {
+                                            error!(?err, "failed to publish invocation failure event");
+                                        }
+                                    });
+                                }
                             }
                             Ok(Ok(Ok(()))) => {}
                         }
@@ -2093,7 +2161,6 @@ impl Host {
             .generate(config)
             .await
             .context("Unable to fetch requested config")?;
-        // TODO(#1648): Implement redelivery of changed configuration when `config.changed()` is true
 
         let host_id = host_id.to_string();
         spawn(async move {
@@ -2125,7 +2192,7 @@ impl Host {
     #[instrument(level = "debug", skip_all)]
     async fn handle_start_provider_task(
         &self,
-        config: ConfigBundle,
+        mut config: ConfigBundle,
         provider_id: &str,
         provider_ref: &str,
         annotations: HashMap<String, String>,
@@ -2226,17 +2293,32 @@ impl Host {
                 .await;
 
             let host_data = HostData {
+                host_data_schema_version: Some(HOST_DATA_SCHEMA_VERSION),
+                host_capabilities: vec![HOST_CAPABILITY_SIGNED_CONTROL.to_string()],
                 host_id: self.host_key.public_key(),
+                host_friendly_name: self.friendly_name.clone(),
+                host_labels: self.labels.read().await.clone(),
+                host_version: self.host_config.version.clone(),
+                host_signing_key: Some(self.host_key.public_key()),
                 lattice_rpc_prefix: self.host_config.lattice.clone(),
                 link_name: "default".to_string(),
                 lattice_rpc_user_jwt: self.host_config.rpc_jwt.clone().unwrap_or_default(),
                 lattice_rpc_user_seed: lattice_rpc_user_seed.unwrap_or_default(),
+                lattice_rpc_user_token: None,
+                lattice_rpc_user_name: None,
+                lattice_rpc_user_password: None,
+                lattice_rpc_credsfile: None,
+                lattice_rpc_tls_ca_file: None,
+                lattice_rpc_tls_cert_file: None,
+                lattice_rpc_tls_key_file: None,
+                lattice_rpc_tls_required: self.host_config.rpc_tls,
                 lattice_rpc_url: self.host_config.rpc_nats_url.to_string(),
                 env_values: vec![],
                 instance_id: Uuid::new_v4().to_string(),
                 provider_key: provider_id.to_string(),
                 link_definitions,
-                config: config.get_config().await.clone(),
+                // Marks the current config as delivered, later changes are pushed to the provider
+                config: config.changed().await.clone(),
                 cluster_issuers: vec![],
                 default_rpc_timeout_ms,
                 log_level: Some(self.host_config.log_level.clone()),
@@ -2291,6 +2373,8 @@ impl Host {
             let health_lattice = self.host_config.lattice.clone();
             let health_host_id = host_id.to_string();
             let health_provider_id = provider_id.to_string();
+            let host_key = Arc::clone(&self.host_key);
+            let config_timeout = self.host_config.rpc_timeout;
             let child = spawn(async move {
                 // Check the health of the provider every 30 seconds
                 let mut health_check = tokio::time::interval(Duration::from_secs(30));
@@ -2381,6 +2465,67 @@ impl Host {
                                     warn!(provider_id = health_provider_id, "failed to request provider health, retrying in 30 seconds");
                                 }
                         }
+                        merged = config.changed() => {
+                            let update = merged.clone();
+                            // Release the config lock before waiting for the provider
+                            drop(merged);
+                            let rejection = match deliver_provider_config(
+                                &rpc_nats,
+                                &host_key,
+                                &health_lattice,
+                                &health_host_id,
+                                &health_provider_id,
+                                update,
+                                config_timeout,
+                            )
+                            .await
+                            {
+                                Ok(ConfigUpdateOutcome { accepted: true, .. }) => {
+                                    info!(provider_id = health_provider_id, "provider applied config update");
+                                    None
+                                }
+                                Ok(ConfigUpdateOutcome { rejection, .. }) => {
+                                    let rejection = rejection
+                                        .unwrap_or_else(|| ConfigRejection::new("no reason given"));
+                                    warn!(
+                                        provider_id = health_provider_id,
+                                        %rejection,
+                                        "provider rejected config update",
+                                    );
+                                    Some(rejection)
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        ?e,
+                                        provider_id = health_provider_id,
+                                        "failed to deliver config update to provider",
+                                    );
+                                    continue;
+                                }
+                            };
+                            let name = if rejection.is_some() {
+                                "provider_config_update_rejected"
+                            } else {
+                                "provider_config_updated"
+                            };
+                            if let Err(e) = event::publish(
+                                &event_builder,
+                                &ctl_nats,
+                                &health_lattice,
+                                name,
+                                event::provider_config_updated(
+                                    &health_host_id,
+                                    &health_provider_id,
+                                    rejection.as_ref(),
+                                ),
+                            ).await {
+                                warn!(
+                                    ?e,
+                                    provider_id = health_provider_id,
+                                    "failed to publish `{name}` event",
+                                );
+                            }
+                        }
                         exit_status = child.wait() => match exit_status {
                             Ok(status) => {
                                 debug!("`{}` exited with `{status:?}`", path.display());
@@ -2444,28 +2589,27 @@ impl Host {
         // Send a request to the provider, requesting a graceful shutdown
         let req = serde_json::to_vec(&json!({ "host_id": host_id }))
             .context("failed to encode provider stop request")?;
+        let subject = format!(
+            "wasmbus.rpc.{}.{provider_id}.default.shutdown",
+            self.host_config.lattice
+        );
+        let headers = self.control_headers(&subject, &req);
         let req = async_nats::Request::new()
             .payload(req.into())
             .timeout(self.host_config.provider_shutdown_delay)
-            .headers(injector_to_headers(
-                &TraceContextInjector::default_with_span(),
-            ));
-        if let Err(e) = self
-            .rpc_nats
-            .send_request(
-                format!(
-                    "wasmbus.rpc.{}.{provider_id}.default.shutdown",
-                    self.host_config.lattice
-                ),
-                req,
-            )
-            .await
-        {
-            warn!(
+            .headers(headers);
+        match self.rpc_nats.send_request(subject, req).await {
+            // The acknowledgement contains the reason the provider gave for shutting down
+            Ok(ack) => info!(
+                provider_id,
+                ack = %String::from_utf8_lossy(&ack.payload),
+                "provider acknowledged shutdown"
+            ),
+            Err(e) => warn!(
                 ?e,
                 provider_id,
                 "provider did not gracefully shut down in time, shutting down forcefully"
-            );
+            ),
         }
         child.abort();
         info!(provider_id, "provider stopped");
@@ -2477,6 +2621,72 @@ impl Host {
         Ok(CtlResponse::success())
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_drain_provider(
+        &self,
+        payload: impl AsRef<[u8]>,
+        host_id: &str,
+    ) -> anyhow::Result<CtlResponse<DrainReport>> {
+        let DrainProviderCommand {
+            provider_id,
+            timeout_ms,
+            ..
+        } = serde_json::from_slice(payload.as_ref())
+            .context("failed to deserialize provider drain command")?;
+
+        debug!(provider_id, timeout_ms, "handling drain provider");
+
+        if !self.providers.read().await.contains_key(&provider_id) {
+            warn!(
+                provider_id,
+                "received request to drain provider that is not running"
+            );
+            return Ok(CtlResponse {
+                success: false,
+                message: "provider with that ID is not running".into(),
+                response: None,
+            });
+        }
+
+        // The request is signed, so that providers verifying control messages accept it
+        let req = serde_json::to_vec(&PrepareShutdownRequest {
+            host_id: host_id.to_string(),
+            timeout_ms,
+        })
+        .context("failed to encode provider drain request")?;
+        let subject = prepare_shutdown_subject(&self.host_config.lattice, &provider_id, "default");
+        let headers = self.control_headers(&subject, &req);
+        let req = async_nats::Request::new()
+            .payload(req.into())
+            .timeout(Some(
+                Duration::from_millis(timeout_ms) + self.host_config.rpc_timeout,
+            ))
+            .headers(headers);
+        match self.rpc_nats.send_request(subject, req).await {
+            Ok(msg) => {
+                let report: DrainReport = serde_json::from_slice(&msg.payload)
+                    .context("provider sent an invalid drain report")?;
+                info!(provider_id, ?report, "provider drained");
+                Ok(CtlResponse::ok(report))
+            }
+            Err(err) if err.kind() == async_nats::RequestErrorKind::TimedOut => {
+                warn!(provider_id, "provider did not report drain outcome in time");
+                Ok(CtlResponse::ok(DrainReport {
+                    drained: false,
+                    abandoned: None,
+                }))
+            }
+            Err(err) => {
+                debug!(?err, provider_id, "provider did not accept drain request");
+                Ok(CtlResponse {
+                    success: false,
+                    message: "provider does not support draining".into(),
+                    response: None,
+                })
+            }
+        }
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_inventory(&self) -> anyhow::Result<CtlResponse<HostInventory>> {
         trace!("handling inventory");
@@ -2864,6 +3074,11 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("provider"), Some("drain"), Some(host_id), None) => self
+                .handle_drain_provider(message.payload, host_id)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             (Some("provider"), Some("stop"), Some(host_id), None) => self
                 .handle_stop_provider(message.payload, host_id)
                 .await
@@ -2992,6 +3207,16 @@ impl Host {
         }
     }
 
+    /// Headers of a control message sent to providers on `subject`, propagating the current trace
+    /// context and signed with the host key so that providers can verify it
+    fn control_headers(&self, subject: &str, payload: &[u8]) -> async_nats::HeaderMap {
+        let mut headers = injector_to_headers(&TraceContextInjector::default_with_span());
+        if let Err(err) = sign_control_message(&self.host_key, subject, payload, &mut headers) {
+            warn!(?err, subject, "failed to sign provider control message");
+        }
+        headers
+    }
+
     /// Publishes a link to the lattice for all instances of a provider to handle
     /// Right now this is publishing _both_ to the source and the target in order to
     /// ensure that the provider is aware of the link. This would cause problems if a provider
@@ -3007,20 +3232,22 @@ impl Host {
         let payload: Bytes = serde_json::to_vec(&provider_link)
             .context("failed to serialize provider link definition")?
             .into();
+        let subject = format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.put");
         let source_provider = self
             .rpc_nats
             .publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.put"),
-                injector_to_headers(&TraceContextInjector::default_with_span()),
+                subject.clone(),
+                self.control_headers(&subject, &payload),
                 payload.clone(),
             )
             .await
             .context("failed to publish provider link definition put");
+        let subject = format!("wasmbus.rpc.{lattice}.{target}.linkdefs.put");
         let target_provider = self
             .rpc_nats
             .publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{target}.linkdefs.put"),
-                injector_to_headers(&TraceContextInjector::default_with_span()),
+                subject.clone(),
+                self.control_headers(&subject, &payload),
                 payload,
             )
             .await
@@ -3044,20 +3271,22 @@ impl Host {
         payload: Bytes,
     ) -> anyhow::Result<()> {
         let lattice = &self.host_config.lattice;
+        let subject = format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.del");
         let source_provider = self
             .rpc_nats
             .publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.del"),
-                injector_to_headers(&TraceContextInjector::default_with_span()),
+                subject.clone(),
+                self.control_headers(&subject, &payload),
                 payload.clone(),
             )
             .await
             .context("failed to publish provider link definition del");
         if let Some(target) = target {
+            let subject = format!("wasmbus.rpc.{lattice}.{target}.linkdefs.del");
             self.rpc_nats
                 .publish_with_headers(
-                    format!("wasmbus.rpc.{lattice}.{target}.linkdefs.del"),
-                    injector_to_headers(&TraceContextInjector::default_with_span()),
+                    subject.clone(),
+                    self.control_headers(&subject, &payload),
                     payload,
                 )
                 .await
@@ -3281,6 +3510,37 @@ impl Host {
     }
 }
 
+/// Push the updated `config` of a running provider to it, signed with the host key, and wait for
+/// the provider to apply or reject it
+async fn deliver_provider_config(
+    rpc_nats: &async_nats::Client,
+    host_key: &KeyPair,
+    lattice: &str,
+    host_id: &str,
+    provider_id: &str,
+    config: HashMap<String, String>,
+    rpc_timeout: Duration,
+) -> anyhow::Result<ConfigUpdateOutcome> {
+    let subject = config_update_subject(lattice, provider_id, "default");
+    let payload = serde_json::to_vec(&ConfigUpdateRequest {
+        host_id: host_id.to_string(),
+        config,
+    })
+    .context("failed to encode config update request")?;
+    let mut headers = injector_to_headers(&TraceContextInjector::default_with_span());
+    sign_control_message(host_key, &subject, &payload, &mut headers)
+        .context("failed to sign config update request")?;
+    let req = async_nats::Request::new()
+        .payload(payload.into())
+        .headers(headers)
+        .timeout(Some(rpc_timeout));
+    let res = rpc_nats
+        .send_request(subject, req)
+        .await
+        .context("provider did not reply to config update request")?;
+    serde_json::from_slice(&res.payload).context("failed to decode config update outcome")
+}
+
 /// Transform a [`wasmcloud_control_interface::InterfaceLinkDefinition`] into a [`wasmcloud_core::InterfaceLinkDefinition`]
 /// by generating the source and target config for the link
 async fn resolve_link_config(
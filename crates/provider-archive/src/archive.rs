@@ -25,16 +25,30 @@ const CLAIMS_JWT_FILE: &str = "claims.jwt";
 
 const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
+/// Mode of the binaries written to an archive
+const BINARY_MODE: u32 = 0o755;
+
+/// Returns the target of the binary a host running on the current machine loads from an archive,
+/// in the format `ARCH-OS` (e.g. `x86_64-linux`)
+#[must_use]
+pub fn native_target() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
 /// A provider archive is a specialized ZIP file that contains a set of embedded and signed claims
 /// (a .JWT file) as well as a list of binary files, one plugin library for each supported
 /// target architecture and OS combination
 pub struct ProviderArchive {
     libraries: HashMap<String, Vec<u8>>,
+    /// Mode of the binary files of the archive, by target, if it was loaded from a file
+    modes: HashMap<String, u32>,
     name: String,
     vendor: String,
     rev: Option<i32>,
     ver: Option<String>,
     claims: Option<Claims<CapabilityProvider>>,
+    /// Encoded claims, as embedded in the archive
+    claims_token: Option<String>,
     json_schema: Option<serde_json::Value>,
 }
 
@@ -44,11 +58,13 @@ impl ProviderArchive {
     pub fn new(name: &str, vendor: &str, rev: Option<i32>, ver: Option<String>) -> ProviderArchive {
         ProviderArchive {
             libraries: HashMap::new(),
+            modes: HashMap::new(),
             name: name.to_string(),
             vendor: vendor.to_string(),
             rev,
             ver,
             claims: None,
+            claims_token: None,
             json_schema: None,
         }
     }
@@ -89,6 +105,20 @@ impl ProviderArchive {
         self.claims.clone()
     }
 
+    /// Returns the encoded claims embedded in this archive, to verify their signature. Like
+    /// [`Self::claims`], only available after the archive has been written or loaded
+    #[must_use]
+    pub fn claims_token(&self) -> Option<&str> {
+        self.claims_token.as_deref()
+    }
+
+    /// Returns the mode of the binary file for a given target, if the archive was loaded from a
+    /// file. Archives written by older versions do not set the executable bits
+    #[must_use]
+    pub fn target_mode(&self, target: &str) -> Option<u32> {
+        self.modes.get(target).copied()
+    }
+
     /// Obtains the JSON schema if one was either set explicitly on the structure or loaded from
     /// claims in the PAR
     #[must_use]
@@ -189,6 +219,7 @@ impl ProviderArchive {
         target: Option<&str>,
     ) -> Result<ProviderArchive> {
         let mut libraries = HashMap::new();
+        let mut modes = HashMap::new();
 
         let mut magic = [0; 2];
         if let Err(e) = input.read_exact(&mut magic).await {
@@ -210,6 +241,7 @@ impl ProviderArchive {
         });
 
         let mut c: Option<Claims<CapabilityProvider>> = None;
+        let mut claims_token = None;
 
         let mut entries = par.entries()?;
 
@@ -224,19 +256,21 @@ impl ProviderArchive {
                 .to_string();
             if file_target == "claims" {
                 tokio::io::copy(&mut entry, &mut bytes).await?;
-                c = Some(Claims::<CapabilityProvider>::decode(std::str::from_utf8(
-                    &bytes,
-                )?)?);
+                let token = std::str::from_utf8(&bytes)?;
+                c = Some(Claims::<CapabilityProvider>::decode(token)?);
+                claims_token = Some(token.to_string());
             } else if let Some(t) = target {
                 // If loading only a specific target, only copy in bytes if it is the target. We still
                 // need to iterate through the rest so we can be sure to find the claims
                 if file_target == t {
                     tokio::io::copy(&mut entry, &mut bytes).await?;
+                    modes.insert(file_target.to_string(), entry.header().mode()?);
                     libraries.insert(file_target.to_string(), bytes);
                 }
                 continue;
             } else {
                 tokio::io::copy(&mut entry, &mut bytes).await?;
+                modes.insert(file_target.to_string(), entry.header().mode()?);
                 libraries.insert(file_target.to_string(), bytes);
             }
         }
@@ -261,11 +295,13 @@ impl ProviderArchive {
 
             Ok(ProviderArchive {
                 libraries,
+                modes,
                 name,
                 vendor,
                 rev,
                 ver,
                 claims: c,
+                claims_token,
                 json_schema,
             })
         } else {
@@ -318,6 +354,7 @@ impl ProviderArchive {
         self.claims = Some(claims.clone());
 
         let claims_file = claims.encode(issuer)?;
+        self.claims_token = Some(claims_file.clone());
 
         let mut header = tokio_tar::Header::new_gnu();
         header.set_path(CLAIMS_JWT_FILE)?;
@@ -331,6 +368,8 @@ impl ProviderArchive {
             let path = format!("{tgt}.bin");
             header.set_path(&path)?;
             header.set_size(lib.len() as u64);
+            header.set_mode(BINARY_MODE);
+            self.modes.insert(tgt.clone(), BINARY_MODE);
             header.set_cksum();
             par.append_data(&mut header, &path, Cursor::new(lib))
                 .await?;
@@ -477,6 +516,13 @@ mod test {
         );
         assert_eq!(arch.claims().unwrap().subject, subject.public_key());
 
+        // Binaries are written executable, with the signed claims
+        assert_eq!(arch2.target_mode("x86_64-linux"), Some(BINARY_MODE));
+        assert_eq!(arch2.claims_token(), arch.claims_token());
+        let validation =
+            wascap::jwt::validate_token::<CapabilityProvider>(arch2.claims_token().unwrap())?;
+        assert!(validation.signature_valid);
+
         // Load just one of the binaries
         let arch2 = ProviderArchive::try_load_target_from_file(&firstpath, "aarch64-linux").await?;
         assert_eq!(
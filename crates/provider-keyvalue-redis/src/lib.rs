@@ -106,7 +106,8 @@ impl KvRedisProvider {
     async fn invocation_conn(&self, context: Option<Context>) -> anyhow::Result<ConnectionManager> {
         let Some(Context {
             component: Some(source_id),
-            tracing,
+            link_name,
+            ..
         }) = context
         else {
             return self.get_default_connection().await.map_err(|err| {
@@ -115,7 +116,7 @@ impl KvRedisProvider {
             });
         };
 
-        let Some(link_name) = tracing.get("link-name") else {
+        let Some(link_name) = link_name else {
             bail!("unexpectedly missing link name on context for invocation");
         };
 
@@ -0,0 +1,133 @@
+//! Propagation of [W3C baggage](https://www.w3.org/TR/baggage/) through provider invocations
+//!
+//! Baggage set by the component invoking a provider (e.g. a tenant or request ID) is extracted
+//! from the [`BAGGAGE_HEADER`] of the invocation into [`Context::baggage`]. Providers can add
+//! entries with [`Context::with_baggage`], and forward the trace context and baggage to the
+//! components they invoke with [`crate::WrpcClient::with_context`].
+//!
+//! Only the [`TRACE_CONTEXT_HEADERS`] and the [`BAGGAGE_HEADER`] are propagated, so that other
+//! invocation headers, such as `source-id` and `target-id`, are never overwritten.
+
+use std::collections::HashMap;
+
+use async_nats::HeaderMap;
+
+use crate::Context;
+
+/// Header carrying the baggage of an invocation
+pub const BAGGAGE_HEADER: &str = "baggage";
+
+/// Headers carrying the W3C trace context of an invocation
+pub const TRACE_CONTEXT_HEADERS: [&str; 2] = ["traceparent", "tracestate"];
+
+impl Context {
+    /// Add a baggage entry, propagated along with the baggage received with the invocation
+    #[must_use]
+    pub fn with_baggage(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.baggage.insert(key.into(), value.into());
+        self
+    }
+
+    /// Headers propagating the trace context and baggage of this context to an outgoing
+    /// invocation
+    #[must_use]
+    pub fn propagation_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for name in TRACE_CONTEXT_HEADERS {
+            if let Some(value) = self.tracing.get(name) {
+                headers.insert(name, value.as_str());
+            }
+        }
+        if !self.baggage.is_empty() {
+            headers.insert(BAGGAGE_HEADER, format_baggage(&self.baggage).as_str());
+        }
+        headers
+    }
+}
+
+/// Parse the value of a [`BAGGAGE_HEADER`], ignoring entry properties and malformed entries
+#[must_use]
+pub fn parse_baggage(header: &str) -> HashMap<String, String> {
+    header
+        .split(',')
+        .filter_map(|member| {
+            let entry = member.split(';').next()?;
+            let (key, value) = entry.split_once('=')?;
+            let key = percent_decode(key.trim())?;
+            (!key.is_empty()).then_some((key, percent_decode(value.trim())?))
+        })
+        .collect()
+}
+
+/// Format `baggage` as the value of a [`BAGGAGE_HEADER`], percent-encoding keys and values
+#[must_use]
+pub fn format_baggage(baggage: &HashMap<String, String>) -> String {
+    let mut entries: Vec<_> = baggage
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect();
+    entries.sort();
+    entries.join(",")
+}
+
+/// Whether `b` may appear unencoded in a baggage value, as defined by the W3C specification
+fn is_baggage_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e) && b != b'%'
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| {
+            if is_baggage_octet(b) {
+                char::from(b).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = tail.get(..2)?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_baggage_round_trip() {
+        let baggage = HashMap::from([
+            ("tenant".to_string(), "acme".to_string()),
+            ("request-id".to_string(), "a b,c;d=e%".to_string()),
+        ]);
+        let header = format_baggage(&baggage);
+        assert_eq!(header, "request-id=a%20b%2Cc%3Bd=e%25,tenant=acme");
+        assert_eq!(parse_baggage(&header), baggage);
+    }
+
+    #[test]
+    fn test_parse_baggage() {
+        assert_eq!(
+            parse_baggage(" tenant = acme ;prop=1, invalid,=empty-key,user=%E2%9C%93"),
+            HashMap::from([
+                ("tenant".to_string(), "acme".to_string()),
+                ("user".to_string(), "✓".to_string()),
+            ])
+        );
+        assert!(parse_baggage("").is_empty());
+        assert!(parse_baggage("bad=%ZZ").is_empty());
+    }
+}
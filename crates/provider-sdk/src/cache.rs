@@ -0,0 +1,473 @@
+//! Opt-in caching of outbound invocation results
+//!
+//! Providers that repeatedly invoke the same function on a linked component with identical
+//! parameters (e.g. configuration or policy lookups) can wrap their [`WrpcClient`] with
+//! [`WrpcClient::with_cache`] to memoize successful responses for a short period of time.
+//!
+//! Cached responses are keyed by target, WIT instance, function and a digest of the encoded
+//! parameters. Entries expire after [`CachePolicy::ttl`], the cache is bounded to
+//! [`CachePolicy::max_entries`] (least recently used entries are evicted first) and responses
+//! larger than [`CachePolicy::max_response_bytes`] are never stored. All caches for a target are
+//! invalidated automatically by the SDK whenever a link involving that target is put or deleted.
+//!
+//! Only unary invocations with fully encoded parameters and results can be cached, streaming
+//! invocations should continue to use the underlying [`WrpcClient`] directly.
+
+use core::future::Future;
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+use tracing::trace;
+
+use crate::WrpcClient;
+
+/// Default maximum size of a response that will be cached, in bytes
+pub const DEFAULT_MAX_CACHED_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// All caches created via [`WrpcClient::with_cache`], used to invalidate entries on link changes
+static CACHE_REGISTRY: Lazy<Mutex<Vec<Weak<Mutex<CacheState>>>>> = Lazy::new(Mutex::default);
+
+/// Policy controlling how outbound invocation results are cached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// How long a cached response remains valid
+    pub ttl: Duration,
+    /// Maximum number of responses to keep, least recently used responses are evicted first
+    pub max_entries: usize,
+    /// Responses larger than this amount of bytes bypass the cache
+    pub max_response_bytes: usize,
+}
+
+impl CachePolicy {
+    /// Create a new [`CachePolicy`] with the default response size threshold
+    #[must_use]
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            max_response_bytes: DEFAULT_MAX_CACHED_RESPONSE_BYTES,
+        }
+    }
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30), 1024)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    target: String,
+    instance: String,
+    func: String,
+    params: [u8; 32],
+}
+
+impl CacheKey {
+    fn new(target: &str, instance: &str, func: &str, params: &[u8]) -> Self {
+        Self {
+            target: target.to_string(),
+            instance: instance.to_string(),
+            func: func.to_string(),
+            params: Sha256::digest(params).into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    response: Bytes,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    policy: CachePolicy,
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Monotonic counter used to track recency of use for LRU eviction
+    tick: u64,
+}
+
+impl CacheState {
+    fn get(&mut self, key: &CacheKey) -> Option<Bytes> {
+        let now = Instant::now();
+        match self.entries.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                self.tick += 1;
+                entry.last_used = self.tick;
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, response: Bytes) {
+        if self.policy.max_entries == 0 || response.len() > self.policy.max_response_bytes {
+            return;
+        }
+        let now = Instant::now();
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.policy.max_entries {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+        }
+        while !self.entries.contains_key(&key) && self.entries.len() >= self.policy.max_entries {
+            let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&lru);
+        }
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                response,
+                expires_at: now + self.policy.ttl,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    fn invalidate(&mut self, target: &str) {
+        self.entries.retain(|key, _| key.target != target);
+    }
+}
+
+/// A cache of outbound invocation results, shared by all clones of a [`CachedWrpcClient`]
+#[derive(Clone, Debug)]
+pub struct InvocationCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl InvocationCache {
+    /// Create a new [`InvocationCache`] with the given policy.
+    ///
+    /// The cache is registered with the SDK so that it is invalidated on link changes.
+    #[must_use]
+    pub fn new(policy: CachePolicy) -> Self {
+        let state = Arc::new(Mutex::new(CacheState {
+            policy,
+            entries: HashMap::new(),
+            tick: 0,
+        }));
+        let mut registry = CACHE_REGISTRY
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        registry.retain(|cache| cache.strong_count() > 0);
+        registry.push(Arc::downgrade(&state));
+        Self { state }
+    }
+
+    /// Returns the cached response for the invocation, or performs it using `invoke` and caches
+    /// the response if it was successful.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Target ID to which the invocation is sent
+    /// * `instance` - WIT instance (e.g. `wasi:keyvalue/store@0.2.0-draft`) being invoked
+    /// * `func` - Name of the function being invoked
+    /// * `params` - Encoded parameters of the invocation
+    /// * `invoke` - Function performing the actual invocation, returning the encoded response
+    pub async fn get_or_invoke<F, Fut>(
+        &self,
+        target: &str,
+        instance: &str,
+        func: &str,
+        params: Bytes,
+        invoke: F,
+    ) -> anyhow::Result<Bytes>
+    where
+        F: FnOnce(Bytes) -> Fut,
+        Fut: Future<Output = anyhow::Result<Bytes>>,
+    {
+        let key = CacheKey::new(target, instance, func, &params);
+        if let Some(response) = self.lock().get(&key) {
+            trace!(
+                target_id = target,
+                instance,
+                func,
+                "returning cached invocation response"
+            );
+            return Ok(response);
+        }
+        let response = invoke(params).await?;
+        self.lock().insert(key, response.clone());
+        Ok(response)
+    }
+
+    /// Remove all cached responses for invocations sent to `target`
+    pub fn invalidate(&self, target: &str) {
+        self.lock().invalidate(target);
+    }
+
+    /// Remove all cached responses
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Number of responses currently held by the cache, including expired ones not yet evicted
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns true if the cache holds no responses
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Invalidate cached responses for `target` in every cache created by this provider
+pub(crate) fn invalidate_target(target: &str) {
+    let mut registry = CACHE_REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    registry.retain(|cache| {
+        let Some(state) = cache.upgrade() else {
+            return false;
+        };
+        state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .invalidate(target);
+        true
+    });
+}
+
+/// A [`WrpcClient`] for a single target paired with an [`InvocationCache`]
+#[derive(Clone, Debug)]
+pub struct CachedWrpcClient {
+    client: WrpcClient,
+    target: String,
+    cache: InvocationCache,
+}
+
+impl CachedWrpcClient {
+    /// The underlying [`WrpcClient`], to be used for invocations which must not be cached,
+    /// such as streaming invocations
+    #[must_use]
+    pub fn client(&self) -> &WrpcClient {
+        &self.client
+    }
+
+    /// The cache used by this client
+    #[must_use]
+    pub fn cache(&self) -> &InvocationCache {
+        &self.cache
+    }
+
+    /// Perform a unary invocation of `func` on `instance`, returning a cached response if one
+    /// is available for the same parameters.
+    ///
+    /// `invoke` is only called on a cache miss, with the underlying client and the encoded
+    /// parameters, and should return the encoded response. Failed invocations are never cached.
+    pub async fn invoke_cached<F, Fut>(
+        &self,
+        instance: &str,
+        func: &str,
+        params: Bytes,
+        invoke: F,
+    ) -> anyhow::Result<Bytes>
+    where
+        F: FnOnce(&WrpcClient, Bytes) -> Fut,
+        Fut: Future<Output = anyhow::Result<Bytes>>,
+    {
+        self.cache
+            .get_or_invoke(&self.target, instance, func, params, |params| {
+                invoke(&self.client, params)
+            })
+            .await
+    }
+
+    /// Remove all cached responses of this client
+    pub fn invalidate(&self) {
+        self.cache.invalidate(&self.target);
+    }
+}
+
+impl WrpcClient {
+    /// Wrap this client in a [`CachedWrpcClient`], memoizing successful unary invocation
+    /// results sent to `target` according to `policy`
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Target ID this client was created for, see [`crate::ProviderConnection::get_wrpc_client`]
+    /// * `policy` - Policy controlling expiry and size of the cache
+    #[must_use]
+    pub fn with_cache(self, target: impl Into<String>, policy: CachePolicy) -> CachedWrpcClient {
+        CachedWrpcClient {
+            client: self,
+            target: target.into(),
+            cache: InvocationCache::new(policy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Mock transport which counts invocations and echoes the parameters back
+    #[derive(Default)]
+    struct CountingTransport {
+        calls: AtomicUsize,
+    }
+
+    impl CountingTransport {
+        async fn invoke(&self, params: Bytes) -> anyhow::Result<Bytes> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(params)
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    async fn invoke(
+        cache: &InvocationCache,
+        transport: &CountingTransport,
+        target: &str,
+        func: &str,
+        params: &'static [u8],
+    ) -> anyhow::Result<Bytes> {
+        cache
+            .get_or_invoke(
+                target,
+                "wasi:keyvalue/store",
+                func,
+                Bytes::from_static(params),
+                |params| transport.invoke(params),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_and_misses() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy::default());
+        let transport = CountingTransport::default();
+
+        assert_eq!(invoke(&cache, &transport, "comp", "get", b"a").await?, "a");
+        assert_eq!(invoke(&cache, &transport, "comp", "get", b"a").await?, "a");
+        assert_eq!(transport.calls(), 1);
+
+        // Different params, function and target are all distinct entries
+        invoke(&cache, &transport, "comp", "get", b"b").await?;
+        invoke(&cache, &transport, "comp", "exists", b"a").await?;
+        invoke(&cache, &transport, "other", "get", b"a").await?;
+        assert_eq!(transport.calls(), 4);
+        assert_eq!(cache.len(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_skips_errors() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy::default());
+        let calls = AtomicUsize::new(0);
+        for _ in 0..2 {
+            let res = cache
+                .get_or_invoke("comp", "wasi:keyvalue/store", "get", Bytes::new(), |_| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    async { anyhow::bail!("invocation failed") }
+                })
+                .await;
+            assert!(res.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl_expiry() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy::new(Duration::from_millis(200), 16));
+        let transport = CountingTransport::default();
+
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        assert_eq!(transport.calls(), 1);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        assert_eq!(transport.calls(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_lru_eviction() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy::new(Duration::from_secs(60), 2));
+        let transport = CountingTransport::default();
+
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        invoke(&cache, &transport, "comp", "get", b"b").await?;
+        // Use `a` so that `b` is the least recently used entry
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        invoke(&cache, &transport, "comp", "get", b"c").await?;
+        assert_eq!(transport.calls(), 3);
+        assert_eq!(cache.len(), 2);
+
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        assert_eq!(transport.calls(), 3);
+        invoke(&cache, &transport, "comp", "get", b"b").await?;
+        assert_eq!(transport.calls(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_skips_large_responses() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy {
+            max_response_bytes: 2,
+            ..CachePolicy::default()
+        });
+        let transport = CountingTransport::default();
+
+        invoke(&cache, &transport, "comp", "get", b"big").await?;
+        invoke(&cache, &transport, "comp", "get", b"big").await?;
+        assert_eq!(transport.calls(), 2);
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_invalidation() -> anyhow::Result<()> {
+        let cache = InvocationCache::new(CachePolicy::default());
+        let transport = CountingTransport::default();
+
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        invoke(&cache, &transport, "invalidated", "get", b"a").await?;
+        cache.invalidate("comp");
+        assert_eq!(cache.len(), 1);
+
+        // Link changes invalidate every registered cache
+        invalidate_target("invalidated");
+        assert!(cache.is_empty());
+
+        invoke(&cache, &transport, "comp", "get", b"a").await?;
+        assert_eq!(transport.calls(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+//! Cache of the wRPC clients of a provider
+//!
+//! Providers invoking many distinct targets would otherwise create a new [`WrpcClient`] (subject
+//! prefix, headers) on every invocation. [`crate::ProviderConnection::get_wrpc_client`] and its
+//! variants return clones of clients cached by target, headers and timeout instead. Cached clients
+//! share the NATS connection of the provider, which reconnects on its own, so they never need to
+//! be rebuilt.
+//!
+//! The cache holds up to [`DEFAULT_WRPC_CLIENT_CACHE_CAPACITY`] clients, or the number set with
+//! [`WRPC_CLIENT_CACHE_CAPACITY_CONFIG_KEY`] in the provider configuration (`0` disables caching),
+//! and evicts the least recently used client when full. The clients of a target are evicted when
+//! a link to or from it is deleted. Providers can create the clients of the targets they are about
+//! to invoke off the hot path with [`crate::ProviderConnection::prewarm`], e.g. once their links
+//! were replayed.
+//!
+//! Hits and misses are reported by [`crate::ProviderConnection::wrpc_client_cache_stats`] and
+//! exported by the metrics endpoint, see [`crate::metrics`].
+
+use core::time::Duration;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::ProviderMetrics;
+use crate::WrpcClient;
+
+/// Provider config key setting the maximum number of cached wRPC clients
+pub const WRPC_CLIENT_CACHE_CAPACITY_CONFIG_KEY: &str = "wrpc_client_cache_capacity";
+
+/// Maximum number of cached wRPC clients unless configured otherwise
+pub const DEFAULT_WRPC_CLIENT_CACHE_CAPACITY: usize = 256;
+
+/// Statistics of a wRPC client cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClientCacheStats {
+    /// Number of clients returned from the cache
+    pub hits: u64,
+    /// Number of clients created as none was cached
+    pub misses: u64,
+    /// Number of clients evicted, either as the cache was full or as their link was deleted
+    pub evictions: u64,
+    /// Number of clients currently cached
+    pub len: usize,
+}
+
+#[derive(Debug)]
+struct CachedClient {
+    headers: BTreeMap<String, String>,
+    timeout: Duration,
+    client: WrpcClient,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct ClientCacheState {
+    /// Cached clients by target, then by headers and timeout
+    clients: HashMap<String, Vec<CachedClient>>,
+    /// Monotonic counter used to track recency of use for LRU eviction
+    tick: u64,
+    stats: ClientCacheStats,
+}
+
+impl ClientCacheState {
+    fn evict_lru(&mut self) {
+        let Some((target, index)) = self
+            .clients
+            .iter()
+            .flat_map(|(target, clients)| {
+                clients
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, cached)| (cached.last_used, target, index))
+            })
+            .min()
+            .map(|(_, target, index)| (target.clone(), index))
+        else {
+            return;
+        };
+        if let Some(clients) = self.clients.get_mut(&target) {
+            clients.swap_remove(index);
+            if clients.is_empty() {
+                self.clients.remove(&target);
+            }
+        }
+        self.stats.len -= 1;
+        self.stats.evictions += 1;
+    }
+}
+
+/// A bounded cache of wRPC clients, shared by all clones
+#[derive(Clone, Debug)]
+pub(crate) struct ClientCache {
+    capacity: usize,
+    state: Arc<Mutex<ClientCacheState>>,
+}
+
+impl ClientCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Arc::default(),
+        }
+    }
+
+    /// Create a cache with the capacity set in the provider `config`, if valid
+    pub(crate) fn from_config(config: &HashMap<String, String>) -> Self {
+        let capacity = config
+            .get(WRPC_CLIENT_CACHE_CAPACITY_CONFIG_KEY)
+            .and_then(|capacity| capacity.trim().parse().ok())
+            .unwrap_or(DEFAULT_WRPC_CLIENT_CACHE_CAPACITY);
+        Self::new(capacity)
+    }
+
+    /// Returns a clone of the client cached for `target`, `headers` and `timeout`, or caches the
+    /// client created by `create` with `headers`
+    pub(crate) fn get_or_create(
+        &self,
+        target: &str,
+        headers: BTreeMap<String, String>,
+        timeout: Duration,
+        create: impl FnOnce(&BTreeMap<String, String>) -> WrpcClient,
+    ) -> WrpcClient {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some(cached) = state.clients.get_mut(target).and_then(|clients| {
+            clients
+                .iter_mut()
+                .find(|cached| cached.timeout == timeout && cached.headers == headers)
+        }) {
+            cached.last_used = tick;
+            let client = cached.client.clone();
+            state.stats.hits += 1;
+            ProviderMetrics::global().record_wrpc_client_cache_lookup(true);
+            return client;
+        }
+        state.stats.misses += 1;
+        ProviderMetrics::global().record_wrpc_client_cache_lookup(false);
+        let client = create(&headers);
+        if self.capacity == 0 {
+            return client;
+        }
+        while state.stats.len >= self.capacity {
+            state.evict_lru();
+        }
+        state
+            .clients
+            .entry(target.to_string())
+            .or_default()
+            .push(CachedClient {
+                headers,
+                timeout,
+                client: client.clone(),
+                last_used: tick,
+            });
+        state.stats.len += 1;
+        client
+    }
+
+    /// Evict all clients cached for `target`, returning how many were evicted
+    pub(crate) fn evict_target(&self, target: &str) -> usize {
+        let mut state = self.lock();
+        let evicted = state
+            .clients
+            .remove(target)
+            .map_or(0, |clients| clients.len());
+        state.stats.len -= evicted;
+        state.stats.evictions += evicted as u64;
+        evicted
+    }
+
+    pub(crate) fn stats(&self) -> ClientCacheStats {
+        self.lock().stats
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ClientCacheState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_nats::HeaderMap;
+
+    use super::*;
+
+    async fn client(target: &str) -> WrpcClient {
+        let nats = async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect("127.0.0.1:1")
+            .await
+            .expect("client should be created without a server");
+        WrpcClient(wasmcloud_core::wrpc::Client::new(
+            nats,
+            "default",
+            target,
+            HeaderMap::new(),
+            Duration::from_secs(1),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let cache = ClientCache::new(2);
+        let prototype = client("target").await;
+        let get = |target: &str| {
+            cache.get_or_create(target, BTreeMap::new(), Duration::from_secs(1), |_| {
+                prototype.clone()
+            })
+        };
+        get("a");
+        get("b");
+        // `a` becomes the most recently used client, so `b` is evicted to make room for `c`
+        get("a");
+        get("c");
+        assert_eq!(
+            cache.stats(),
+            ClientCacheStats {
+                hits: 1,
+                misses: 3,
+                evictions: 1,
+                len: 2,
+            }
+        );
+        get("a");
+        get("b");
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 4);
+
+        // Clients with other headers or timeouts are cached separately
+        cache.get_or_create(
+            "a",
+            BTreeMap::from([("link-name".to_string(), "other".to_string())]),
+            Duration::from_secs(1),
+            |_| prototype.clone(),
+        );
+        cache.get_or_create("a", BTreeMap::new(), Duration::from_secs(2), |_| {
+            prototype.clone()
+        });
+        assert_eq!(cache.stats().misses, 6);
+
+        // Both clients of `a` were kept, as the least recently used clients were evicted
+        assert_eq!(cache.evict_target("a"), 2);
+        assert_eq!(cache.stats().len, 0);
+        assert_eq!(cache.stats().evictions, 6);
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let cache = ClientCache::from_config(&HashMap::from([(
+            WRPC_CLIENT_CACHE_CAPACITY_CONFIG_KEY.to_string(),
+            "0".to_string(),
+        )]));
+        let prototype = client("target").await;
+        for _ in 0..2 {
+            cache.get_or_create("a", BTreeMap::new(), Duration::from_secs(1), |_| {
+                prototype.clone()
+            });
+        }
+        assert_eq!(cache.stats().misses, 2);
+        assert_eq!(cache.stats().len, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_get_and_evict() {
+        const CAPACITY: usize = 8;
+        let cache = ClientCache::new(CAPACITY);
+        let prototype = client("target").await;
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let cache = cache.clone();
+                let prototype = prototype.clone();
+                tokio::spawn(async move {
+                    for i in 0..1000 {
+                        let target = format!("target-{}", (task + i) % 16);
+                        if i % 10 == 0 {
+                            cache.evict_target(&target);
+                        } else {
+                            let client = cache.get_or_create(
+                                &target,
+                                BTreeMap::new(),
+                                Duration::from_secs(1),
+                                |_| prototype.clone(),
+                            );
+                            assert_eq!(client.0.timeout(), Duration::from_secs(1));
+                        }
+                        assert!(cache.stats().len <= CAPACITY);
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.expect("task should not panic");
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 8 * 900);
+        let cached: usize = cache.lock().clients.values().map(Vec::len).sum();
+        assert_eq!(cached, stats.len);
+        assert!(stats.len <= CAPACITY);
+        // Every created client was either evicted or is still cached
+        assert_eq!(stats.misses, stats.evictions + stats.len as u64);
+    }
+}
@@ -0,0 +1,164 @@
+//! Sources of the configuration of a provider and their precedence
+//!
+//! The configuration of a provider is merged from the following sources, each overriding the
+//! values of the same keys in the sources before it:
+//!
+//! 1. `env_values` passed through by the host from its environment
+//! 2. named configuration attached to the provider
+//! 3. configuration of a link, for the handling of that link only, see [`ConfigSources::with_link`]
+//!
+//! The merged configuration of the first two is the effective configuration of the provider, see
+//! [`crate::ProviderConnection::config`]. The sources remain individually accessible for providers
+//! which need to distinguish them, e.g. to reject settings only allowed in named configuration.
+
+use std::collections::HashMap;
+
+use wasmcloud_core::HostData;
+
+/// Sources of the configuration of a provider, see the [module documentation](self)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigSources {
+    env_values: HashMap<String, String>,
+    named: HashMap<String, String>,
+}
+
+impl ConfigSources {
+    /// Create the configuration sources of a provider from the `env_values` passed through by the
+    /// host and its `named` configuration
+    #[must_use]
+    pub fn new(env_values: HashMap<String, String>, named: HashMap<String, String>) -> Self {
+        Self { env_values, named }
+    }
+
+    /// Configuration sources supplied by the host on startup
+    #[must_use]
+    pub fn from_host_data(host_data: &HostData) -> Self {
+        Self::new(
+            host_data.env_values.iter().cloned().collect(),
+            host_data.config.clone(),
+        )
+    }
+
+    /// Values passed through by the host from its environment
+    #[must_use]
+    pub fn env_values(&self) -> &HashMap<String, String> {
+        &self.env_values
+    }
+
+    /// Named configuration attached to the provider
+    #[must_use]
+    pub fn named(&self) -> &HashMap<String, String> {
+        &self.named
+    }
+
+    /// Replace the named configuration, e.g. with a configuration update pushed by the host
+    pub fn set_named(&mut self, named: HashMap<String, String>) {
+        self.named = named;
+    }
+
+    /// Effective configuration of the provider, named configuration overriding `env_values`
+    #[must_use]
+    pub fn merged(&self) -> HashMap<String, String> {
+        let mut config = self.env_values.clone();
+        config.extend(self.named.clone());
+        config
+    }
+
+    /// Configuration for the handling of a link, `link_config` overriding the effective
+    /// configuration of the provider
+    #[must_use]
+    pub fn with_link(&self, link_config: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut config = self.merged();
+        config.extend(link_config.clone());
+        config
+    }
+}
+
+/// Configuration sources with `named` configuration only
+impl From<HashMap<String, String>> for ConfigSources {
+    fn from(named: HashMap<String, String>) -> Self {
+        Self::new(HashMap::new(), named)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wasmcloud_core::InterfaceLinkDefinition;
+
+    use super::*;
+
+    fn host_data() -> HostData {
+        HostData {
+            env_values: vec![
+                ("region".to_string(), "env-region".to_string()),
+                ("pool_size".to_string(), "2".to_string()),
+                ("api_url".to_string(), "https://env.example.com".to_string()),
+            ],
+            config: HashMap::from([
+                ("pool_size".to_string(), "8".to_string()),
+                (
+                    "api_url".to_string(),
+                    "https://named.example.com".to_string(),
+                ),
+            ]),
+            link_definitions: vec![InterfaceLinkDefinition {
+                source_id: "component".to_string(),
+                target: "provider".to_string(),
+                name: "default".to_string(),
+                target_config: HashMap::from([(
+                    "api_url".to_string(),
+                    "https://link.example.com".to_string(),
+                )]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        let host_data = host_data();
+        let sources = ConfigSources::from_host_data(&host_data);
+
+        let config = sources.merged();
+        // Only set in env_values
+        assert_eq!(config.get("region").map(String::as_str), Some("env-region"));
+        // Named configuration overrides env_values
+        assert_eq!(config.get("pool_size").map(String::as_str), Some("8"));
+        assert_eq!(
+            config.get("api_url").map(String::as_str),
+            Some("https://named.example.com")
+        );
+
+        // Link configuration overrides both
+        let link = &host_data.link_definitions[0];
+        let config = sources.with_link(&link.target_config);
+        assert_eq!(
+            config.get("api_url").map(String::as_str),
+            Some("https://link.example.com")
+        );
+        assert_eq!(config.get("pool_size").map(String::as_str), Some("8"));
+        assert_eq!(config.get("region").map(String::as_str), Some("env-region"));
+
+        // The sources remain distinguishable
+        assert_eq!(
+            sources.env_values().get("api_url").map(String::as_str),
+            Some("https://env.example.com")
+        );
+        assert!(!sources.named().contains_key("region"));
+    }
+
+    #[test]
+    fn test_set_named_keeps_env_values() {
+        let mut sources = ConfigSources::from_host_data(&host_data());
+        sources.set_named(HashMap::from([("pool_size".to_string(), "16".to_string())]));
+        assert_eq!(
+            sources.merged(),
+            HashMap::from([
+                ("region".to_string(), "env-region".to_string()),
+                ("pool_size".to_string(), "16".to_string()),
+                ("api_url".to_string(), "https://env.example.com".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Verification of the control messages hosts send to providers
+//!
+//! Shutdown and drain requests, link updates and configuration updates are received on plain NATS
+//! subjects, so anyone with access to the lattice could forge them, e.g. to shut a provider down or
+//! stop it serving. Providers which must only act on messages sent by their host opt into
+//! verification by setting [`REQUIRE_SIGNED_CONTROL_CONFIG_KEY`] to `true` in their configuration.
+//! When the host also
+//! supplies its [signing key](wasmcloud_core::HostData::host_signing_key), the SDK checks the
+//! signature and freshness of every control message and rejects replayed nonces, logging and
+//! dropping unauthenticated messages before they reach the provider. Without the key or the
+//! configuration, control messages are processed as before.
+//!
+//! Requests to prepare for shutdown are verified like any other control message. Clients such as
+//! `wash stop --drain` ask the host to drain a provider, and the host sends the signed request.
+//!
+//! See [`wasmcloud_core::control_auth`] for the signing scheme.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::bail;
+use tracing::warn;
+use wasmcloud_core::control_auth::{verify_control_message, CONTROL_MESSAGE_MAX_AGE};
+
+/// Key of the provider configuration requiring control messages to be signed by the host when set
+/// to `true`
+pub const REQUIRE_SIGNED_CONTROL_CONFIG_KEY: &str = "require_signed_control";
+
+/// Verifier of control messages signed by the host of a provider
+#[derive(Debug)]
+pub(crate) struct ControlVerifier {
+    host_signing_key: String,
+    /// Nonces of verified messages, with the time they were seen at
+    seen: Mutex<HashMap<String, SystemTime>>,
+}
+
+impl ControlVerifier {
+    /// Create a verifier if the provider `config` requires signed control messages and the host
+    /// supplied its signing key
+    pub(crate) fn from_config(
+        host_signing_key: Option<&str>,
+        config: &HashMap<String, String>,
+    ) -> Option<Arc<Self>> {
+        let required = config
+            .get(REQUIRE_SIGNED_CONTROL_CONFIG_KEY)
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+        match (required, host_signing_key) {
+            (true, Some(key)) if !key.is_empty() => Some(Arc::new(Self::new(key))),
+            (true, _) => {
+                warn!(
+                    "`{REQUIRE_SIGNED_CONTROL_CONFIG_KEY}` is set, but the host did not supply a signing key, control messages will not be verified"
+                );
+                None
+            }
+            (false, _) => None,
+        }
+    }
+
+    pub(crate) fn new(host_signing_key: impl Into<String>) -> Self {
+        Self {
+            host_signing_key: host_signing_key.into(),
+            seen: Mutex::default(),
+        }
+    }
+
+    /// Verify the signature, freshness and uniqueness of a control message
+    pub(crate) fn verify(&self, msg: &async_nats::Message) -> anyhow::Result<()> {
+        let now = SystemTime::now();
+        let nonce = verify_control_message(
+            &self.host_signing_key,
+            msg.subject.as_str(),
+            &msg.payload,
+            msg.headers.as_ref(),
+            now,
+        )?;
+        let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+        // Nonces older than the maximum age are rejected as stale, so need not be kept
+        seen.retain(|_, at| {
+            now.duration_since(*at)
+                .map_or(true, |age| age <= CONTROL_MESSAGE_MAX_AGE * 2)
+        });
+        if seen.insert(nonce, now).is_some() {
+            bail!("control message nonce was already used");
+        }
+        Ok(())
+    }
+}
+
+/// Whether the control message `msg` should be processed, logging rejected messages
+pub(crate) fn is_authentic(verifier: Option<&ControlVerifier>, msg: &async_nats::Message) -> bool {
+    let Some(verifier) = verifier else {
+        return true;
+    };
+    match verifier.verify(msg) {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(subject = %msg.subject, %err, "rejected unauthenticated control message");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_nats::HeaderMap;
+    use nkeys::KeyPair;
+    use wasmcloud_core::control_auth::sign_control_message;
+
+    use super::*;
+
+    fn message(
+        subject: &str,
+        payload: &'static [u8],
+        headers: Option<HeaderMap>,
+    ) -> async_nats::Message {
+        async_nats::Message {
+            subject: subject.into(),
+            reply: None,
+            payload: payload.into(),
+            headers,
+            status: None,
+            description: None,
+            length: payload.len(),
+        }
+    }
+
+    #[test]
+    fn test_verifier_rejects_forged_and_replayed_messages() {
+        let host_key = KeyPair::new_server();
+        let verifier = ControlVerifier::new(host_key.public_key());
+        let subject = "wasmbus.rpc.default.provider.linkdefs.put";
+        let payload = b"{}";
+
+        let mut headers = HeaderMap::new();
+        sign_control_message(&host_key, subject, payload, &mut headers).expect("failed to sign");
+        let signed = message(subject, payload, Some(headers.clone()));
+        assert!(is_authentic(Some(&verifier), &signed));
+        // The same message cannot be replayed
+        assert!(!is_authentic(Some(&verifier), &signed));
+
+        assert!(!is_authentic(
+            Some(&verifier),
+            &message(subject, payload, None)
+        ));
+        let mut forged = HeaderMap::new();
+        sign_control_message(&KeyPair::new_server(), subject, payload, &mut forged)
+            .expect("failed to sign");
+        assert!(!is_authentic(
+            Some(&verifier),
+            &message(subject, payload, Some(forged))
+        ));
+
+        // Without a verifier, all messages are processed
+        assert!(is_authentic(None, &message(subject, payload, None)));
+    }
+
+    #[test]
+    fn test_verifier_from_config() {
+        let required = HashMap::from([(
+            REQUIRE_SIGNED_CONTROL_CONFIG_KEY.to_string(),
+            "true".to_string(),
+        )]);
+        let key = KeyPair::new_server().public_key();
+        assert!(ControlVerifier::from_config(Some(&key), &required).is_some());
+        assert!(ControlVerifier::from_config(None, &required).is_none());
+        assert!(ControlVerifier::from_config(Some(&key), &HashMap::new()).is_none());
+    }
+}
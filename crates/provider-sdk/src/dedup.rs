@@ -0,0 +1,224 @@
+//! Deduplication of messages and requests processed by providers
+//!
+//! [`DedupWindow`] answers "has this ID been processed within the last N minutes?" atomically for
+//! all instances of a provider in a lattice. IDs are recorded in the [`DEDUP_BUCKET`] JetStream
+//! key-value bucket using create-only puts, so that of several instances racing on the same ID,
+//! exactly one sees it first.
+//!
+//! JetStream key-value buckets have no per-key TTL, so each ID is stored along with the time until
+//! which it is considered a duplicate. IDs seen again after that time are claimed again with a
+//! compare-and-swap on their revision, and the maximum age of the bucket, [`DEDUP_MAX_TTL`], bounds
+//! how long any ID is kept.
+
+use core::time::Duration;
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context as _};
+use async_nats::jetstream::context::KeyValueErrorKind;
+use async_nats::jetstream::kv::{self, CreateErrorKind, Operation, UpdateErrorKind};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+/// Name of the JetStream key-value bucket holding the IDs seen by providers
+pub const DEDUP_BUCKET: &str = "wasmcloud_provider_dedup";
+
+/// Maximum duration for which IDs are deduplicated, longer TTLs are truncated to it
+pub const DEDUP_MAX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Timeout of JetStream requests made to deduplicate IDs
+pub const DEDUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of [`DedupWindow::check_and_set`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupOutcome {
+    /// The ID was not seen within its TTL, and should be processed
+    FirstSeen,
+    /// The ID was already seen within its TTL, by this or another provider instance
+    Duplicate,
+}
+
+/// Behavior of [`DedupWindow::check_and_set`] when JetStream is unavailable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DedupFailureMode {
+    /// Return the error, so that the ID is not processed (default)
+    #[default]
+    Closed,
+    /// Log the error and report the ID as [`DedupOutcome::FirstSeen`], so that it is processed,
+    /// possibly more than once
+    Open,
+}
+
+/// Window in which IDs of messages or requests are deduplicated, shared by all instances of a
+/// provider in a lattice. See the [module documentation](self) for details.
+#[derive(Clone)]
+pub struct DedupWindow {
+    jetstream: async_nats::jetstream::Context,
+    store: Arc<OnceCell<kv::Store>>,
+    namespace: String,
+    failure_mode: DedupFailureMode,
+}
+
+impl DedupWindow {
+    /// Create a window deduplicating IDs of the provider with ID `provider_id` in `lattice`. The
+    /// bucket is opened, or created, on first use.
+    #[must_use]
+    pub fn new(nats: async_nats::Client, lattice: &str, provider_id: &str) -> Self {
+        let mut jetstream = async_nats::jetstream::new(nats);
+        jetstream.set_timeout(DEDUP_TIMEOUT);
+        Self {
+            jetstream,
+            store: Arc::default(),
+            namespace: format!("{}.{}", key_token(lattice), key_token(provider_id)),
+            failure_mode: DedupFailureMode::default(),
+        }
+    }
+
+    /// Set the behavior when JetStream is unavailable
+    #[must_use]
+    pub fn with_failure_mode(mut self, failure_mode: DedupFailureMode) -> Self {
+        self.failure_mode = failure_mode;
+        self
+    }
+
+    /// Record `id` as seen for `ttl`, returning whether it was already seen within the TTL it
+    /// was last recorded with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if JetStream is unavailable and the failure mode is
+    /// [`DedupFailureMode::Closed`]
+    pub async fn check_and_set(&self, id: &str, ttl: Duration) -> anyhow::Result<DedupOutcome> {
+        match self.try_check_and_set(id, ttl).await {
+            Ok(outcome) => Ok(outcome),
+            Err(err) if self.failure_mode == DedupFailureMode::Open => {
+                warn!(?err, id, "failed to deduplicate ID, processing it");
+                Ok(DedupOutcome::FirstSeen)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn try_check_and_set(&self, id: &str, ttl: Duration) -> anyhow::Result<DedupOutcome> {
+        let store = self.store().await?;
+        let key = self.key(id);
+        let now = unix_millis(SystemTime::now());
+        let seen_until = now.saturating_add(as_millis(ttl.min(DEDUP_MAX_TTL)));
+        let value = seen_until.to_string();
+
+        match store.create(&key, value.clone().into()).await {
+            Ok(_) => return Ok(DedupOutcome::FirstSeen),
+            Err(err) if err.kind() == CreateErrorKind::AlreadyExists => {}
+            Err(err) => return Err(anyhow!(err).context("failed to record ID")),
+        }
+
+        // The ID was seen before, claim it again if its TTL has elapsed
+        let entry = store
+            .entry(&key)
+            .await
+            .context("failed to read recorded ID")?
+            .filter(|entry| entry.operation == Operation::Put);
+        let Some(entry) = entry else {
+            // The ID expired in the meantime, race for it again
+            return match store.create(&key, value.into()).await {
+                Ok(_) => Ok(DedupOutcome::FirstSeen),
+                Err(err) if err.kind() == CreateErrorKind::AlreadyExists => {
+                    Ok(DedupOutcome::Duplicate)
+                }
+                Err(err) => Err(anyhow!(err).context("failed to record ID")),
+            };
+        };
+        let recorded_until = std::str::from_utf8(&entry.value)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_default();
+        if recorded_until > now {
+            return Ok(DedupOutcome::Duplicate);
+        }
+        match store.update(&key, value.into(), entry.revision).await {
+            Ok(_) => Ok(DedupOutcome::FirstSeen),
+            // Another instance claimed the ID first
+            Err(err) if err.kind() == UpdateErrorKind::WrongLastRevision => {
+                Ok(DedupOutcome::Duplicate)
+            }
+            Err(err) => Err(anyhow!(err).context("failed to record ID")),
+        }
+    }
+
+    async fn store(&self) -> anyhow::Result<&kv::Store> {
+        self.store
+            .get_or_try_init(|| async {
+                match self.jetstream.get_key_value(DEDUP_BUCKET).await {
+                    Ok(store) => Ok(store),
+                    Err(err) if err.kind() == KeyValueErrorKind::GetBucket => self
+                        .jetstream
+                        .create_key_value(kv::Config {
+                            bucket: DEDUP_BUCKET.to_string(),
+                            description: "IDs seen by providers".to_string(),
+                            history: 1,
+                            max_age: DEDUP_MAX_TTL,
+                            ..Default::default()
+                        })
+                        .await
+                        .context("failed to create dedup bucket"),
+                    Err(err) => Err(anyhow!(err).context("failed to open dedup bucket")),
+                }
+            })
+            .await
+    }
+
+    /// Key of `id` in the bucket. IDs are hashed, as they may contain characters not allowed in
+    /// keys
+    fn key(&self, id: &str) -> String {
+        format!("{}.{:x}", self.namespace, Sha256::digest(id))
+    }
+}
+
+/// Replace characters not allowed in key-value keys
+pub(crate) fn key_token(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '=') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `duration` in milliseconds, saturating at [`u64::MAX`]
+pub(crate) fn as_millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+/// Milliseconds since the UNIX epoch at `time`, `0` for times before the epoch
+pub(crate) fn unix_millis(time: SystemTime) -> u64 {
+    as_millis(time.duration_since(UNIX_EPOCH).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keys_are_namespaced() {
+        let nats = async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect("127.0.0.1:1")
+            .await
+            .expect("client should be created without a server");
+        let window = DedupWindow::new(nats.clone(), "default", "wasmcloud:messaging/nats");
+        let key = window.key("order.created/42");
+        assert!(key.starts_with("default.wasmcloud_messaging_nats."));
+        assert_eq!(key.len(), "default.wasmcloud_messaging_nats.".len() + 64);
+        assert_eq!(key, window.key("order.created/42"));
+        assert_ne!(key, window.key("order.created/43"));
+        assert_ne!(
+            key,
+            DedupWindow::new(nats, "other", "wasmcloud:messaging/nats").key("order.created/42")
+        );
+    }
+}
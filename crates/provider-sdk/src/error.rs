@@ -1,7 +1,19 @@
 //! Error types for interacting with a provider
 
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use wasmcloud_core::wrpc::PayloadTooLarge;
+use wrpc_transport::Transmitter;
+
+#[deprecated(
+    since = "0.7.0",
+    note = "return `ProviderInvocationResult` from invocation handlers so callers can match on error codes"
+)]
 pub type InvocationResult<T> = Result<T, InvocationError>;
 pub type ProviderInitResult<T> = Result<T, ProviderInitError>;
+pub type ProviderInvocationResult<T> = Result<T, ProviderInvocationError>;
 
 /// All errors that that can be returned by a provider when it is being initialized,
 /// primarily from internal provider-sdk code
@@ -105,3 +117,223 @@ pub enum NetworkError {
     #[error(transparent)]
     Request(#[from] async_nats::RequestError),
 }
+
+/// Structured error returned by a provider when handling an invocation.
+///
+/// Errors are sent to the caller over wRPC in their wire representation (see
+/// [`ProviderInvocationError::to_wire`]), which preserves the error [code](ProviderInvocationError::code)
+/// so that components and other providers can tell bad input apart from an unavailable backend or
+/// a bug in the provider. Any [`anyhow::Error`] converts into [`ProviderInvocationError::Internal`],
+/// unless it wraps a [`ProviderInvocationError`] or a received wire representation of one.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ProviderInvocationError {
+    /// The parameters of the invocation were invalid
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    /// The caller is not allowed to perform the invocation
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    /// A backend required to handle the invocation is unavailable, the caller may retry after
+    /// `retry_after` if set
+    #[error("unavailable: {message}")]
+    Unavailable {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The provider failed to handle the invocation
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+/// Wire representation of a [`ProviderInvocationError`]
+#[derive(Deserialize, Serialize)]
+struct WireError {
+    code: String,
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+}
+
+impl ProviderInvocationError {
+    /// Code of an [`ProviderInvocationError::InvalidInput`] error
+    pub const INVALID_INPUT: &'static str = "invalid-input";
+    /// Code of an [`ProviderInvocationError::Unauthorized`] error
+    pub const UNAUTHORIZED: &'static str = "unauthorized";
+    /// Code of an [`ProviderInvocationError::Unavailable`] error
+    pub const UNAVAILABLE: &'static str = "unavailable";
+    /// Code of an [`ProviderInvocationError::Internal`] error
+    pub const INTERNAL: &'static str = "internal";
+
+    /// Stable code identifying the kind of error on the wire
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidInput(_) => Self::INVALID_INPUT,
+            Self::Unauthorized(_) => Self::UNAUTHORIZED,
+            Self::Unavailable { .. } => Self::UNAVAILABLE,
+            Self::Internal(_) => Self::INTERNAL,
+        }
+    }
+
+    /// Human-readable message describing the error
+    #[must_use]
+    pub fn message(&self) -> &str {
+        match self {
+            Self::InvalidInput(message)
+            | Self::Unauthorized(message)
+            | Self::Unavailable { message, .. }
+            | Self::Internal(message) => message,
+        }
+    }
+
+    /// Encode this error into the payload sent to the caller on the invocation error subject
+    #[must_use]
+    pub fn to_wire(&self) -> String {
+        let retry_after_ms = match self {
+            Self::Unavailable {
+                retry_after: Some(retry_after),
+                ..
+            } => Some(u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX)),
+            _ => None,
+        };
+        serde_json::to_string(&WireError {
+            code: self.code().to_string(),
+            message: self.message().to_string(),
+            retry_after_ms,
+        })
+        .unwrap_or_else(|_| self.message().to_string())
+    }
+
+    /// Decode an error payload received from a provider.
+    ///
+    /// Payloads which are not a wire representation of a [`ProviderInvocationError`] (e.g. errors
+    /// sent by providers using raw error strings) are returned as [`ProviderInvocationError::Internal`].
+    #[must_use]
+    pub fn from_wire(payload: &str) -> Self {
+        Self::try_from_wire(payload).unwrap_or_else(|| Self::Internal(payload.to_string()))
+    }
+
+    fn try_from_wire(payload: &str) -> Option<Self> {
+        let WireError {
+            code,
+            message,
+            retry_after_ms,
+        } = serde_json::from_str(payload).ok()?;
+        match code.as_str() {
+            Self::INVALID_INPUT => Some(Self::InvalidInput(message)),
+            Self::UNAUTHORIZED => Some(Self::Unauthorized(message)),
+            Self::UNAVAILABLE => Some(Self::Unavailable {
+                message,
+                retry_after: retry_after_ms.map(Duration::from_millis),
+            }),
+            // Unknown codes from newer providers are treated as internal errors
+            _ => Some(Self::Internal(message)),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ProviderInvocationError {
+    fn from(err: anyhow::Error) -> Self {
+        if let Some(err) = err.downcast_ref::<Self>() {
+            return err.clone();
+        }
+        if let Some(err) = err
+            .chain()
+            .find_map(|err| err.downcast_ref::<PayloadTooLarge>())
+        {
+            return err.clone().into();
+        }
+        // Errors returned by an invocation wrap the payload received from the error subject
+        Self::try_from_wire(&err.root_cause().to_string())
+            .unwrap_or_else(|| Self::Internal(format!("{err:#}")))
+    }
+}
+
+impl From<PayloadTooLarge> for ProviderInvocationError {
+    fn from(err: PayloadTooLarge) -> Self {
+        Self::InvalidInput(err.to_string())
+    }
+}
+
+/// Transmit `err` to the caller of an invocation on `error_subject` in its wire representation
+pub async fn transmit_invocation_error<Tx: Transmitter>(
+    transmitter: &Tx,
+    error_subject: Tx::Subject,
+    err: impl Into<ProviderInvocationError>,
+) {
+    let err = err.into();
+    if let Err(err) = transmitter
+        .transmit_static(error_subject, err.to_wire())
+        .await
+    {
+        error!(?err, "failed to transmit invocation error");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context as _;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn all_variants() -> Vec<ProviderInvocationError> {
+        vec![
+            ProviderInvocationError::InvalidInput("missing key".to_string()),
+            ProviderInvocationError::Unauthorized("bad token".to_string()),
+            ProviderInvocationError::Unavailable {
+                message: "database down".to_string(),
+                retry_after: Some(Duration::from_millis(1500)),
+            },
+            ProviderInvocationError::Unavailable {
+                message: "try again".to_string(),
+                retry_after: None,
+            },
+            ProviderInvocationError::Internal("oops".to_string()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_over_transport() -> anyhow::Result<()> {
+        // In-memory stand-in for the invocation error subject
+        let (tx, mut rx) = mpsc::channel::<String>(1);
+        for err in all_variants() {
+            tx.send(err.to_wire()).await?;
+            let payload = rx.recv().await.context("transport closed")?;
+            // Callers receive the payload as the root cause of the invocation error
+            let received = ProviderInvocationError::from(
+                anyhow::anyhow!(payload).context("failed to invoke `wasi:keyvalue/store.get`"),
+            );
+            assert_eq!(received.code(), err.code());
+            assert_eq!(received, err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_anyhow_conversion() {
+        let err = ProviderInvocationError::from(anyhow::anyhow!("connection refused"));
+        assert_eq!(err.code(), ProviderInvocationError::INTERNAL);
+        assert_eq!(err.message(), "connection refused");
+
+        let err = ProviderInvocationError::from(anyhow::Error::from(
+            ProviderInvocationError::InvalidInput("bad key".to_string()),
+        ));
+        assert_eq!(
+            err,
+            ProviderInvocationError::InvalidInput("bad key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_raw_and_unknown_payloads() {
+        assert_eq!(
+            ProviderInvocationError::from_wire("some raw error"),
+            ProviderInvocationError::Internal("some raw error".to_string())
+        );
+        assert_eq!(
+            ProviderInvocationError::from_wire(r#"{"code":"not-a-code","message":"hi"}"#),
+            ProviderInvocationError::Internal("hi".to_string())
+        );
+    }
+}
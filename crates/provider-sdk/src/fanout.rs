@@ -0,0 +1,429 @@
+//! Delivery of a single invocation to every component the provider is linked to
+//!
+//! Providers acting as invocation sources (e.g. messaging providers delivering an inbound message)
+//! often need to send the same invocation to all targets of their source links. [`FanOut`] keeps
+//! a [`WrpcClient`] for each current target and performs a [`FanOut::broadcast`] according to a
+//! [`FanOutPolicy`], reporting the outcome for every target.
+//!
+//! The set of targets is synchronized with the links of the [`ProviderConnection`] at the start of
+//! every broadcast, so a target is never invoked once its link has been deleted and clients of
+//! deleted links are dropped immediately.
+
+use core::fmt;
+use core::future::Future;
+use core::time::Duration;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt as _;
+use tracing::{debug, warn};
+
+use crate::{ProviderConnection, WrpcClient};
+
+/// Default maximum number of targets invoked concurrently by a [`FanOut::broadcast`]
+pub const DEFAULT_FAN_OUT_CONCURRENCY: usize = 16;
+
+/// Policy controlling how a [`FanOut::broadcast`] invokes its targets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FanOutPolicy {
+    /// Maximum number of targets invoked concurrently, values below 1 are treated as 1
+    pub concurrency: usize,
+    /// Maximum duration of the invocation of a single target, unbounded (apart from the timeout
+    /// of the [`WrpcClient`]) if unset
+    pub timeout: Option<Duration>,
+    /// Whether to stop invoking targets after the first failure. Targets which were not invoked,
+    /// or whose invocation was still in progress, are reported as [`FanOutOutcome::Skipped`]
+    pub stop_on_failure: bool,
+}
+
+impl Default for FanOutPolicy {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_FAN_OUT_CONCURRENCY,
+            timeout: None,
+            stop_on_failure: false,
+        }
+    }
+}
+
+/// Outcome of the invocation of a single target of a [`FanOut::broadcast`]
+#[derive(Debug)]
+pub enum FanOutOutcome<T> {
+    /// The invocation succeeded
+    Ok(T),
+    /// The invocation failed
+    Failed(anyhow::Error),
+    /// The invocation did not complete within [`FanOutPolicy::timeout`]
+    TimedOut,
+    /// The target was not invoked, or its invocation was abandoned, because another target failed
+    /// and [`FanOutPolicy::stop_on_failure`] is set
+    Skipped,
+}
+
+impl<T> FanOutOutcome<T> {
+    /// Returns true if the invocation succeeded
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok(..))
+    }
+}
+
+impl<T> From<anyhow::Result<T>> for FanOutOutcome<T> {
+    fn from(res: anyhow::Result<T>) -> Self {
+        match res {
+            Ok(v) => Self::Ok(v),
+            Err(err) => Self::Failed(err),
+        }
+    }
+}
+
+/// Result of the invocation of a single target of a [`FanOut::broadcast`]
+#[derive(Debug)]
+pub struct FanOutResult<T> {
+    /// Target ID of the invoked component
+    pub target: String,
+    /// Outcome of the invocation
+    pub outcome: FanOutOutcome<T>,
+}
+
+/// Invokes all targets of the source links of a provider, see the [module documentation](self)
+#[derive(Clone)]
+pub struct FanOut {
+    connection: ProviderConnection,
+    /// Clients of the current source link targets, indexed by target ID
+    clients: Arc<Mutex<HashMap<String, WrpcClient>>>,
+}
+
+impl fmt::Debug for FanOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FanOut")
+            .field("provider_id", &self.connection.provider_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl FanOut {
+    /// Create a new [`FanOut`] delivering invocations to the source link targets of `connection`
+    #[must_use]
+    pub fn new(connection: &ProviderConnection) -> Self {
+        Self {
+            connection: connection.clone(),
+            clients: Arc::default(),
+        }
+    }
+
+    /// Returns the current targets along with their clients, creating clients for new targets
+    /// and dropping those of deleted links
+    async fn clients(&self) -> Vec<(String, WrpcClient)> {
+        let targets = self.connection.source_link_targets().await;
+        let mut clients = self
+            .clients
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        clients.retain(|target, _| targets.contains(target));
+        targets
+            .into_iter()
+            .map(|target| {
+                let client = clients
+                    .entry(target.clone())
+                    .or_insert_with(|| self.connection.get_wrpc_client(&target))
+                    .clone();
+                (target, client)
+            })
+            .collect()
+    }
+
+    /// Returns the IDs of the components currently targeted by a broadcast, in the order in which
+    /// their results are reported
+    pub async fn targets(&self) -> Vec<String> {
+        self.clients()
+            .await
+            .into_iter()
+            .map(|(target, _)| target)
+            .collect()
+    }
+
+    /// Invoke `func` of `instance` on every current source link target according to `policy`,
+    /// returning one result per target, ordered by target ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - WIT instance (e.g. `wasmcloud:messaging/handler@0.2.0`) being invoked
+    /// * `func` - Name of the function being invoked
+    /// * `params` - Encoded parameters of the invocation, shared by all targets
+    /// * `policy` - Policy controlling concurrency, timeouts and failure handling
+    /// * `invoke` - Function performing the invocation of a single target, called with the target
+    ///   ID, its client and the parameters
+    pub async fn broadcast<T, F, Fut>(
+        &self,
+        instance: &str,
+        func: &str,
+        params: Bytes,
+        policy: FanOutPolicy,
+        invoke: F,
+    ) -> Vec<FanOutResult<T>>
+    where
+        F: Fn(String, WrpcClient, Bytes) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let clients = self.clients().await;
+        let targets: Vec<String> = clients.iter().map(|(target, _)| target.clone()).collect();
+        let mut pending = clients.into_iter();
+        let mut running = FuturesUnordered::new();
+        let mut outcomes = BTreeMap::new();
+        let invoke = &invoke;
+        loop {
+            while running.len() < policy.concurrency.max(1) {
+                let Some((target, client)) = pending.next() else {
+                    break;
+                };
+                let params = params.clone();
+                running.push(async move {
+                    let call = invoke(target.clone(), client, params);
+                    let outcome: FanOutOutcome<T> = match policy.timeout {
+                        Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                            Ok(res) => res.into(),
+                            Err(_) => FanOutOutcome::TimedOut,
+                        },
+                        None => call.await.into(),
+                    };
+                    (target, outcome)
+                });
+            }
+            let Some((target, outcome)) = running.next().await else {
+                break;
+            };
+            match &outcome {
+                FanOutOutcome::Failed(err) => {
+                    warn!(target_id = %target, instance, func, ?err, "fan-out invocation failed");
+                }
+                FanOutOutcome::TimedOut => {
+                    warn!(target_id = %target, instance, func, "fan-out invocation timed out");
+                }
+                FanOutOutcome::Ok(..) | FanOutOutcome::Skipped => {}
+            }
+            let failed = !outcome.is_ok();
+            outcomes.insert(target, outcome);
+            if failed && policy.stop_on_failure {
+                debug!(instance, func, "stopping fan-out after failure");
+                break;
+            }
+        }
+        // Abandon invocations still in progress after a failure
+        drop(running);
+        targets
+            .into_iter()
+            .map(|target| {
+                let outcome = outcomes.remove(&target).unwrap_or(FanOutOutcome::Skipped);
+                FanOutResult { target, outcome }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use wasmcloud_core::InterfaceLinkDefinition;
+
+    use super::*;
+    use crate::{ConfigSources, HostInfo, LinkReadiness};
+
+    async fn test_connection() -> ProviderConnection {
+        let nats = async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect("127.0.0.1:1")
+            .await
+            .expect("client should be created without a server");
+        ProviderConnection::new(
+            Arc::new(nats),
+            "provider".into(),
+            HostInfo {
+                host_id: "host".into(),
+                lattice: "default".into(),
+                ..Default::default()
+            },
+            ConfigSources::default(),
+            Duration::from_secs(2),
+            LinkReadiness::from_config(&HashMap::new()),
+        )
+        .expect("connection should be created")
+    }
+
+    fn link(target: &str) -> InterfaceLinkDefinition {
+        InterfaceLinkDefinition {
+            source_id: "provider".into(),
+            target: target.into(),
+            name: "default".into(),
+            ..Default::default()
+        }
+    }
+
+    /// Fan-out over three mock targets: `a` echoes the parameters, `b` fails shortly after being
+    /// invoked and `c` is slow
+    async fn broadcast(fan_out: &FanOut, policy: FanOutPolicy) -> Vec<(String, String)> {
+        fan_out
+            .broadcast(
+                "wasmcloud:messaging/handler",
+                "handle-message",
+                Bytes::from_static(b"msg"),
+                policy,
+                |target, _client, params| async move {
+                    match target.as_str() {
+                        "b" => {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            anyhow::bail!("target failed")
+                        }
+                        "c" => tokio::time::sleep(Duration::from_millis(500)).await,
+                        _ => {}
+                    }
+                    Ok(params)
+                },
+            )
+            .await
+            .into_iter()
+            .map(|FanOutResult { target, outcome }| {
+                let outcome = match outcome {
+                    FanOutOutcome::Ok(response) => {
+                        String::from_utf8(response.to_vec()).expect("response should be UTF-8")
+                    }
+                    FanOutOutcome::Failed(err) => err.to_string(),
+                    FanOutOutcome::TimedOut => "timed out".to_string(),
+                    FanOutOutcome::Skipped => "skipped".to_string(),
+                };
+                (target, outcome)
+            })
+            .collect()
+    }
+
+    fn results(results: &[(&str, &str)]) -> Vec<(String, String)> {
+        results
+            .iter()
+            .map(|(target, outcome)| (target.to_string(), outcome.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_policies() {
+        let connection = test_connection().await;
+        for target in ["a", "b", "c"] {
+            connection.put_link(link(target)).await;
+        }
+        // Links to the provider are not fan-out targets
+        connection
+            .put_link(InterfaceLinkDefinition {
+                source_id: "d".into(),
+                target: "provider".into(),
+                ..Default::default()
+            })
+            .await;
+        let fan_out = FanOut::new(&connection);
+        assert_eq!(fan_out.targets().await, vec!["a", "b", "c"]);
+
+        // By default every target is invoked and failures are attributed to their target
+        assert_eq!(
+            broadcast(&fan_out, FanOutPolicy::default()).await,
+            results(&[("a", "msg"), ("b", "target failed"), ("c", "msg")])
+        );
+
+        // Slow targets time out without affecting the others
+        let policy = FanOutPolicy {
+            timeout: Some(Duration::from_millis(100)),
+            ..FanOutPolicy::default()
+        };
+        assert_eq!(
+            broadcast(&fan_out, policy).await,
+            results(&[("a", "msg"), ("b", "target failed"), ("c", "timed out")])
+        );
+
+        // Invoking targets one by one in order, the failure of `b` prevents invoking `c`
+        let policy = FanOutPolicy {
+            concurrency: 1,
+            stop_on_failure: true,
+            ..FanOutPolicy::default()
+        };
+        assert_eq!(
+            broadcast(&fan_out, policy).await,
+            results(&[("a", "msg"), ("b", "target failed"), ("c", "skipped")])
+        );
+
+        // Invoking all targets concurrently, the slow invocation of `c` is abandoned
+        let policy = FanOutPolicy {
+            stop_on_failure: true,
+            ..FanOutPolicy::default()
+        };
+        let started = tokio::time::Instant::now();
+        assert_eq!(
+            broadcast(&fan_out, policy).await,
+            results(&[("a", "msg"), ("b", "target failed"), ("c", "skipped")])
+        );
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_concurrency() {
+        let connection = test_connection().await;
+        for target in 0..8 {
+            connection.put_link(link(&format!("target-{target}"))).await;
+        }
+        let fan_out = FanOut::new(&connection);
+
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let policy = FanOutPolicy {
+            concurrency: 3,
+            ..FanOutPolicy::default()
+        };
+        let results = fan_out
+            .broadcast("test:test/test", "f", Bytes::new(), policy, |_, _, _| {
+                let (running, max_running) = (&running, &max_running);
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+            .await;
+        assert_eq!(results.len(), 8);
+        assert!(results.iter().all(|result| result.outcome.is_ok()));
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_follows_links() {
+        let connection = test_connection().await;
+        connection.put_link(link("a")).await;
+        connection.put_link(link("b")).await;
+        let fan_out = FanOut::new(&connection);
+        assert_eq!(fan_out.targets().await, vec!["a", "b"]);
+        assert_eq!(fan_out.clients.lock().unwrap().len(), 2);
+
+        // Deleted links are never invoked, and their clients are dropped right away
+        connection.delete_link("provider", "a").await;
+        let results = fan_out
+            .broadcast(
+                "test:test/test",
+                "f",
+                Bytes::new(),
+                FanOutPolicy::default(),
+                |target, _, _| async move { Ok(target) },
+            )
+            .await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].target, "b");
+        assert!(matches!(&results[0].outcome, FanOutOutcome::Ok(target) if target == "b"));
+        assert_eq!(
+            fan_out.clients.lock().unwrap().keys().collect::<Vec<_>>(),
+            vec!["b"]
+        );
+
+        connection.put_link(link("c")).await;
+        assert_eq!(fan_out.targets().await, vec!["b", "c"]);
+    }
+}
@@ -0,0 +1,272 @@
+//! Tolerant parsing of the [`HostData`] hosts send to providers on startup
+//!
+//! Hosts and providers are released independently, so a provider may receive data from a host
+//! older or newer than the SDK it was built with. Fields unknown to the SDK are ignored, and fields
+//! missing from the data of older hosts are defaulted, with a warning for each of them. Hosts state
+//! the version of the schema they send in [`HostData::host_data_schema_version`]: data of a newer
+//! version than [`HOST_DATA_SCHEMA_VERSION`] is still accepted, with a single warning listing the
+//! fields and capabilities the provider ignores.
+//!
+//! The optional features supported by the host are reported to providers as
+//! [`HostCapabilities`], see
+//! [`ProviderInitConfig::host_capabilities`](crate::ProviderInitConfig::host_capabilities).
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+use wasmcloud_core::{
+    HostData, HOST_CAPABILITY_CONFIG_UPDATES, HOST_CAPABILITY_SECRETS,
+    HOST_CAPABILITY_SIGNED_CONTROL, HOST_DATA_SCHEMA_VERSION,
+};
+
+/// Capabilities known to this version of the SDK
+const KNOWN_CAPABILITIES: &[&str] = &[
+    HOST_CAPABILITY_SIGNED_CONTROL,
+    HOST_CAPABILITY_CONFIG_UPDATES,
+    HOST_CAPABILITY_SECRETS,
+];
+
+/// Optional features supported by the host a provider is connected to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HostCapabilities {
+    /// The host encrypts secrets sent to the provider with xkeys
+    pub secrets: bool,
+    /// The host sends configuration updates to the running provider
+    pub config_updates: bool,
+    /// The host signs the control messages it sends to the provider, see
+    /// [`control_auth`](crate::control_auth)
+    pub signed_control: bool,
+}
+
+impl HostCapabilities {
+    /// Capabilities advertised in `host_data`
+    #[must_use]
+    pub fn from_host_data(host_data: &HostData) -> Self {
+        let advertised = |capability: &str| {
+            host_data
+                .host_capabilities
+                .iter()
+                .any(|c| c.as_str() == capability)
+        };
+        Self {
+            secrets: advertised(HOST_CAPABILITY_SECRETS),
+            config_updates: advertised(HOST_CAPABILITY_CONFIG_UPDATES),
+            // Hosts predating capabilities supply their signing key when they sign control messages
+            signed_control: advertised(HOST_CAPABILITY_SIGNED_CONTROL)
+                || host_data
+                    .host_signing_key
+                    .as_deref()
+                    .is_some_and(|key| !key.is_empty()),
+        }
+    }
+}
+
+/// Names of the top-level fields of a JSON object
+fn field_names(value: &Value) -> BTreeSet<&str> {
+    value
+        .as_object()
+        .into_iter()
+        .flat_map(|fields| {
+            fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, _)| name.as_str())
+        })
+        .collect()
+}
+
+/// Parse the JSON-encoded host data in `bytes`, returning it along with warnings about the fields
+/// that were defaulted or ignored
+pub(crate) fn parse_host_data(bytes: &[u8]) -> serde_json::Result<(HostData, Vec<String>)> {
+    let supplied: Value = serde_json::from_slice(bytes)?;
+    let host_data: HostData = serde_json::from_value(supplied.clone())?;
+    let defaults = serde_json::to_value(HostData::default())?;
+    let parsed = serde_json::to_value(&host_data)?;
+    let (supplied_fields, default_fields, parsed_fields) = (
+        field_names(&supplied),
+        field_names(&defaults),
+        field_names(&parsed),
+    );
+
+    let mut warnings: Vec<_> = default_fields
+        .difference(&supplied_fields)
+        .map(|field| format!("host did not supply `{field}` in host data, using its default value"))
+        .collect();
+
+    let ignored_fields: Vec<_> = supplied_fields
+        .iter()
+        .filter(|field| !default_fields.contains(*field) && !parsed_fields.contains(*field))
+        .collect();
+    let ignored_capabilities: Vec<_> = host_data
+        .host_capabilities
+        .iter()
+        .filter(|c| !KNOWN_CAPABILITIES.contains(&c.as_str()))
+        .collect();
+    let version = host_data.host_data_schema_version.unwrap_or(1);
+    if version > HOST_DATA_SCHEMA_VERSION {
+        warnings.push(format!(
+            "host data schema version {version} is newer than version {HOST_DATA_SCHEMA_VERSION} supported by this provider, ignoring fields {ignored_fields:?} and capabilities {ignored_capabilities:?}"
+        ));
+    } else if !ignored_fields.is_empty() || !ignored_capabilities.is_empty() {
+        warnings.push(format!(
+            "ignoring unknown fields {ignored_fields:?} and capabilities {ignored_capabilities:?} in host data"
+        ));
+    }
+    Ok((host_data, warnings))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Host data sent by hosts predating the schema version, without several later fields
+    const LEGACY_HOST_DATA: &str = r#"{
+        "host_id": "NLEGACYHOST",
+        "lattice_rpc_prefix": "default",
+        "link_name": "default",
+        "lattice_rpc_user_jwt": "",
+        "lattice_rpc_user_seed": "",
+        "lattice_rpc_url": "nats://127.0.0.1:4222",
+        "provider_key": "VPROVIDER",
+        "env_values": {},
+        "instance_id": "6f1a3c3e-0c5d-4a07-9c55-7e1f8d0b3a11",
+        "link_definitions": [],
+        "cluster_issuers": [],
+        "config": {"key": "value"},
+        "structured_logging": false,
+        "otel_config": {"enable_observability": false}
+    }"#;
+
+    /// Host data sent by hosts of the version supported by the SDK
+    const CURRENT_HOST_DATA: &str = r#"{
+        "host_data_schema_version": 2,
+        "host_capabilities": ["signed-control"],
+        "host_id": "NCURRENTHOST",
+        "host_friendly_name": "quiet-sun",
+        "host_labels": {"region": "eu"},
+        "host_version": "1.1.0",
+        "host_signing_key": "NCURRENTHOST",
+        "lattice_rpc_prefix": "default",
+        "link_name": "default",
+        "lattice_rpc_user_jwt": "",
+        "lattice_rpc_user_seed": "",
+        "lattice_rpc_tls_required": false,
+        "lattice_rpc_url": "nats://127.0.0.1:4222",
+        "provider_key": "VPROVIDER",
+        "env_values": {},
+        "instance_id": "6f1a3c3e-0c5d-4a07-9c55-7e1f8d0b3a11",
+        "link_definitions": [],
+        "cluster_issuers": [],
+        "config": {},
+        "default_rpc_timeout_ms": 2000,
+        "structured_logging": true,
+        "log_level": "info",
+        "otel_config": {"enable_observability": false}
+    }"#;
+
+    /// Host data sent by hosts newer than the SDK, with fields and capabilities unknown to it
+    const FUTURE_HOST_DATA: &str = r#"{
+        "host_data_schema_version": 7,
+        "host_capabilities": ["signed-control", "secrets", "link-streaming"],
+        "host_id": "NFUTUREHOST",
+        "host_friendly_name": "loud-moon",
+        "host_labels": {},
+        "host_version": "9.0.0",
+        "host_xkey_public_key": "XFUTUREHOST",
+        "host_signing_key": "NFUTUREHOST",
+        "lattice_rpc_prefix": "default",
+        "link_name": "default",
+        "lattice_rpc_user_jwt": "",
+        "lattice_rpc_user_seed": "",
+        "lattice_rpc_tls_required": false,
+        "lattice_rpc_url": "nats://127.0.0.1:4222",
+        "provider_key": "VPROVIDER",
+        "env_values": {},
+        "instance_id": "6f1a3c3e-0c5d-4a07-9c55-7e1f8d0b3a11",
+        "link_definitions": [],
+        "cluster_issuers": [],
+        "config": {},
+        "structured_logging": false,
+        "otel_config": {"enable_observability": false},
+        "secrets": {"api_key": {"kind": "string", "value": "encrypted"}}
+    }"#;
+
+    #[test]
+    fn test_parse_legacy_host_data() {
+        let (host_data, warnings) =
+            parse_host_data(LEGACY_HOST_DATA.as_bytes()).expect("legacy host data should parse");
+        assert_eq!(host_data.host_id, "NLEGACYHOST");
+        assert_eq!(
+            host_data.config.get("key").map(String::as_str),
+            Some("value")
+        );
+        assert_eq!(host_data.host_data_schema_version, None);
+        assert!(host_data.host_friendly_name.is_empty());
+        // Each missing field is reported
+        for field in [
+            "host_friendly_name",
+            "host_labels",
+            "host_version",
+            "lattice_rpc_tls_required",
+        ] {
+            assert!(
+                warnings.iter().any(|w| w.contains(&format!("`{field}`"))),
+                "missing `{field}` should be reported: {warnings:?}"
+            );
+        }
+        assert!(!warnings.iter().any(|w| w.contains("ignoring")));
+        assert_eq!(
+            HostCapabilities::from_host_data(&host_data),
+            HostCapabilities::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_current_host_data() {
+        let (host_data, warnings) =
+            parse_host_data(CURRENT_HOST_DATA.as_bytes()).expect("current host data should parse");
+        assert!(warnings.is_empty(), "unexpected warnings: {warnings:?}");
+        assert_eq!(
+            host_data.host_data_schema_version,
+            Some(HOST_DATA_SCHEMA_VERSION)
+        );
+        assert_eq!(host_data.default_rpc_timeout_ms, Some(2000));
+        assert_eq!(
+            HostCapabilities::from_host_data(&host_data),
+            HostCapabilities {
+                secrets: false,
+                config_updates: false,
+                signed_control: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_future_host_data() {
+        let (host_data, warnings) =
+            parse_host_data(FUTURE_HOST_DATA.as_bytes()).expect("future host data should parse");
+        assert_eq!(host_data.host_id, "NFUTUREHOST");
+        let [warning] = warnings.as_slice() else {
+            panic!("expected a single warning, got {warnings:?}");
+        };
+        assert!(warning.contains("schema version 7"), "{warning}");
+        for ignored in ["host_xkey_public_key", "secrets", "link-streaming"] {
+            assert!(warning.contains(&format!("\"{ignored}\"")), "{warning}");
+        }
+        assert!(!warning.contains("\"signed-control\""), "{warning}");
+        assert_eq!(
+            HostCapabilities::from_host_data(&host_data),
+            HostCapabilities {
+                secrets: true,
+                config_updates: false,
+                signed_control: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_host_data() {
+        assert!(parse_host_data(br#"{"host_id": 42}"#).is_err());
+        assert!(parse_host_data(br#""host data""#).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Typed client of `wrpc:blobstore` blobstores implemented by other providers or components
+
+use anyhow::Context as _;
+use bytes::Bytes;
+use futures::{Stream, StreamExt as _};
+use wrpc_interface_blobstore::{Blobstore as _, ObjectId, ObjectMetadata};
+
+use crate::{ProviderInvocationError, ProviderInvocationResult, WrpcClient};
+
+/// Client of the `wrpc:blobstore/blobstore` interface exported by the target of a [`WrpcClient`],
+/// e.g. `BlobstoreClient::new(connection.get_wrpc_client(target)).read(container, object, 0, end)`.
+///
+/// Object data is streamed in both directions, so objects do not need to be buffered in full
+#[derive(Clone, Debug)]
+pub struct BlobstoreClient {
+    wrpc: WrpcClient,
+}
+
+/// Convert an error returned by a blobstore function into a [`ProviderInvocationError`]
+fn function_error(err: String) -> ProviderInvocationError {
+    ProviderInvocationError::from_wire(&err)
+}
+
+fn object_id(container: &str, object: &str) -> ObjectId {
+    ObjectId {
+        container: container.to_string(),
+        object: object.to_string(),
+    }
+}
+
+impl BlobstoreClient {
+    /// Create a client invoking the target of `wrpc`
+    #[must_use]
+    pub fn new(wrpc: WrpcClient) -> Self {
+        Self { wrpc }
+    }
+
+    /// Create `container`
+    pub async fn create_container(&self, container: &str) -> ProviderInvocationResult<()> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_create_container(container)
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.create-container`")?;
+        res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(())
+    }
+
+    /// Check whether `container` exists
+    pub async fn container_exists(&self, container: &str) -> ProviderInvocationResult<bool> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_container_exists(container)
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.container-exists`")?;
+        let exists = res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(exists)
+    }
+
+    /// Delete `container` along with all of its objects
+    pub async fn delete_container(&self, container: &str) -> ProviderInvocationResult<()> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_delete_container(container)
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.delete-container`")?;
+        res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(())
+    }
+
+    /// Check whether `object` exists in `container`
+    pub async fn has_object(
+        &self,
+        container: &str,
+        object: &str,
+    ) -> ProviderInvocationResult<bool> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_has_object(&object_id(container, object))
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.has-object`")?;
+        let has = res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(has)
+    }
+
+    /// Get the metadata of `object` in `container`
+    pub async fn object_info(
+        &self,
+        container: &str,
+        object: &str,
+    ) -> ProviderInvocationResult<ObjectMetadata> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_get_object_info(&object_id(container, object))
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.get-object-info`")?;
+        let info = res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(info)
+    }
+
+    /// Delete `object` from `container`
+    pub async fn delete_object(
+        &self,
+        container: &str,
+        object: &str,
+    ) -> ProviderInvocationResult<()> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_delete_objects(container, [object])
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.delete-objects`")?;
+        res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(())
+    }
+
+    /// Read the bytes `start..=end` of `object` in `container`, as a stream of chunks
+    pub async fn read(
+        &self,
+        container: &str,
+        object: &str,
+        start: u64,
+        end: u64,
+    ) -> ProviderInvocationResult<impl Stream<Item = ProviderInvocationResult<Bytes>>> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_get_container_data(&object_id(container, object), start, end)
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.get-container-data`")?;
+        let data = res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(data.map(|chunk| chunk.map_err(ProviderInvocationError::from)))
+    }
+
+    /// Write `object` in `container`, replacing it if it exists, streaming its contents from `data`
+    pub async fn write(
+        &self,
+        container: &str,
+        object: &str,
+        data: impl Stream<Item = Bytes> + Send + 'static,
+    ) -> ProviderInvocationResult<()> {
+        let (res, tx) = self
+            .wrpc
+            .0
+            .invoke_write_container_data(&object_id(container, object), data)
+            .await
+            .context("failed to invoke `wrpc:blobstore/blobstore.write-container-data`")?;
+        res.map_err(function_error)?;
+        tx.await.context("failed to transmit parameters")?;
+        Ok(())
+    }
+}
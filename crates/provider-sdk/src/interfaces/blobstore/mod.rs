@@ -4,13 +4,19 @@ use core::pin::pin;
 use anyhow::Context as _;
 use bytes::Bytes;
 use futures::{Stream, StreamExt as _};
-use tokio::{select, spawn};
+use tokio::select;
 use tracing::{debug, error, instrument, warn};
 use wrpc_interface_blobstore::{BlobstoreInvocations, ObjectId};
 use wrpc_transport::{AcceptedInvocation, Transmitter};
 
+use crate::isolation::spawn_invocation;
 use crate::{get_connection, run_provider, Context, Provider};
 
+#[cfg(feature = "interfaces-blobstore")]
+mod client;
+#[cfg(feature = "interfaces-blobstore")]
+pub use client::BlobstoreClient;
+
 /// `wrpc:blobstore/blobstore` provider
 pub trait Blobstore: Send {
     fn serve_clear_container<Tx: Transmitter + Send>(
@@ -121,7 +127,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_clear_container(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "clear-container", invocation, move |invocation| async move {
+                                provider.serve_clear_container(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.clear-container` invocation");
@@ -136,7 +144,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_container_exists(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "container-exists", invocation, move |invocation| async move {
+                                provider.serve_container_exists(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.container-exists` invocation") ;
@@ -151,7 +161,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_create_container(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "create-container", invocation, move |invocation| async move {
+                                provider.serve_create_container(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.container-exists` invocation") ;
@@ -166,7 +178,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_delete_container(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "delete-container", invocation, move |invocation| async move {
+                                provider.serve_delete_container(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.delete-container` invocation") ;
@@ -181,7 +195,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_get_container_info(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "get-container-info", invocation, move |invocation| async move {
+                                provider.serve_get_container_info(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.get-container-info` invocation") ;
@@ -196,7 +212,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_list_container_objects(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "list-container-objects", invocation, move |invocation| async move {
+                                provider.serve_list_container_objects(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.list-container-objects` invocation") ;
@@ -211,7 +229,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_copy_object(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "copy-object", invocation, move |invocation| async move {
+                                provider.serve_copy_object(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.copy-object` invocation") ;
@@ -226,7 +246,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_delete_object(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "delete-object", invocation, move |invocation| async move {
+                                provider.serve_delete_object(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.delete-object` invocation") ;
@@ -241,7 +263,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_delete_objects(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "delete-objects", invocation, move |invocation| async move {
+                                provider.serve_delete_objects(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.delete-objects` invocation") ;
@@ -256,7 +280,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_get_container_data(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "get-container-data", invocation, move |invocation| async move {
+                                provider.serve_get_container_data(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.get-container-data` invocation") ;
@@ -271,7 +297,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_get_object_info(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "get-object-info", invocation, move |invocation| async move {
+                                provider.serve_get_object_info(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.get-object-info` invocation") ;
@@ -286,7 +314,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_has_object(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "has-object", invocation, move |invocation| async move {
+                                provider.serve_has_object(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.has-object` invocation") ;
@@ -301,7 +331,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_move_object(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "move-object", invocation, move |invocation| async move {
+                                provider.serve_move_object(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.move-object` invocation") ;
@@ -316,7 +348,9 @@ pub async fn serve_blobstore(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_write_container_data(invocation).await });
+                            spawn_invocation("wrpc:blobstore/blobstore", "write-container-data", invocation, move |invocation| async move {
+                                provider.serve_write_container_data(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:blobstore/blobstore.write-container-data` invocation") ;
@@ -3,11 +3,12 @@ use core::pin::pin;
 
 use anyhow::Context as _;
 use futures::StreamExt as _;
-use tokio::{select, spawn};
+use tokio::select;
 use tracing::{debug, error, instrument, warn};
 use wrpc_interface_http::{IncomingRequestHttp, RequestOptions};
 use wrpc_transport::{AcceptedInvocation, Transmitter};
 
+use crate::isolation::spawn_invocation;
 use crate::{get_connection, run_provider, Context, Provider};
 
 /// `wrpc:http/outgoing-handler` provider
@@ -44,7 +45,9 @@ pub async fn serve_outgoing_handler(
                     match invocation {
                         Some(Ok(invocation)) => {
                             let provider = provider.clone();
-                            spawn(async move { provider.serve_handle(invocation).await });
+                            spawn_invocation("wrpc:http/outgoing-handler", "handle", invocation, move |invocation| async move {
+                                provider.serve_handle(invocation).await
+                            }).await;
                         },
                         Some(Err(err)) => {
                             error!(?err, "failed to accept `wrpc:http/outgoing-handler.handle` invocation");
@@ -0,0 +1,125 @@
+//! Typed client of `wrpc:keyvalue` stores implemented by other providers or components
+
+use anyhow::Context as _;
+
+use crate::{ProviderInvocationError, ProviderInvocationResult, WrpcClient};
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({ world: "keyvalue-client" });
+}
+
+use bindings::wrpc::keyvalue::{atomics, store};
+
+pub use bindings::wrpc::keyvalue::store::{Error as StoreError, KeyResponse};
+
+impl From<StoreError> for ProviderInvocationError {
+    fn from(err: StoreError) -> Self {
+        match err {
+            StoreError::NoSuchStore => Self::InvalidInput("no such store".to_string()),
+            StoreError::AccessDenied => Self::Unauthorized("access denied".to_string()),
+            StoreError::Other(message) => Self::from_wire(&message),
+        }
+    }
+}
+
+/// Client of the `wrpc:keyvalue/store` and `wrpc:keyvalue/atomics` interfaces exported by the
+/// target of a [`WrpcClient`], e.g.
+/// `KeyvalueClient::new(connection.get_wrpc_client(target)).get(bucket, key)`
+#[derive(Clone, Debug)]
+pub struct KeyvalueClient {
+    wrpc: WrpcClient,
+}
+
+impl KeyvalueClient {
+    /// Create a client invoking the target of `wrpc`
+    #[must_use]
+    pub fn new(wrpc: WrpcClient) -> Self {
+        Self { wrpc }
+    }
+
+    /// Get the value of `key` in `bucket`, if set
+    pub async fn get(&self, bucket: &str, key: &str) -> ProviderInvocationResult<Option<Vec<u8>>> {
+        let res = store::get(&self.wrpc.0, bucket, key)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/store.get`")?;
+        Ok(res?)
+    }
+
+    /// Set the value of `key` in `bucket`
+    pub async fn set(&self, bucket: &str, key: &str, value: &[u8]) -> ProviderInvocationResult<()> {
+        let res = store::set(&self.wrpc.0, bucket, key, value)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/store.set`")?;
+        Ok(res?)
+    }
+
+    /// Delete `key` from `bucket`
+    pub async fn delete(&self, bucket: &str, key: &str) -> ProviderInvocationResult<()> {
+        let res = store::delete(&self.wrpc.0, bucket, key)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/store.delete`")?;
+        Ok(res?)
+    }
+
+    /// Check whether `key` is set in `bucket`
+    pub async fn exists(&self, bucket: &str, key: &str) -> ProviderInvocationResult<bool> {
+        let res = store::exists(&self.wrpc.0, bucket, key)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/store.exists`")?;
+        Ok(res?)
+    }
+
+    /// List a page of the keys of `bucket`, starting at `cursor`
+    pub async fn list_keys(
+        &self,
+        bucket: &str,
+        cursor: Option<u64>,
+    ) -> ProviderInvocationResult<KeyResponse> {
+        let res = store::list_keys(&self.wrpc.0, bucket, cursor)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/store.list-keys`")?;
+        Ok(res?)
+    }
+
+    /// Atomically increment the value of `key` in `bucket` by `delta`, returning the new value
+    pub async fn increment(
+        &self,
+        bucket: &str,
+        key: &str,
+        delta: u64,
+    ) -> ProviderInvocationResult<u64> {
+        let res = atomics::increment(&self.wrpc.0, bucket, key, delta)
+            .await
+            .context("failed to invoke `wrpc:keyvalue/atomics.increment`")?;
+        Ok(res?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_store_error() {
+        assert_eq!(
+            ProviderInvocationError::from(StoreError::NoSuchStore).code(),
+            ProviderInvocationError::INVALID_INPUT
+        );
+        assert_eq!(
+            ProviderInvocationError::from(StoreError::AccessDenied).code(),
+            ProviderInvocationError::UNAUTHORIZED
+        );
+        assert_eq!(
+            ProviderInvocationError::from(StoreError::Other("redis is down".to_string())),
+            ProviderInvocationError::Internal("redis is down".to_string())
+        );
+        let unavailable = ProviderInvocationError::Unavailable {
+            message: "redis is down".to_string(),
+            retry_after: None,
+        };
+        assert_eq!(
+            ProviderInvocationError::from(StoreError::Other(unavailable.to_wire())),
+            unavailable
+        );
+    }
+}
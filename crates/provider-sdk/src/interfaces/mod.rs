@@ -1,2 +1,4 @@
 pub mod blobstore;
 pub mod http;
+#[cfg(feature = "interfaces-keyvalue")]
+pub mod keyvalue;
@@ -0,0 +1,337 @@
+//! Panic isolation for invocation handlers
+//!
+//! Every invocation accepted by the serving loops in [`crate::interfaces`] is handled in its own
+//! task. A panic in a handler would otherwise tear down that task silently, leaving the caller to
+//! wait for its timeout (or abort the whole provider process when built with `panic = "abort"`).
+//! Instead, panics are caught per invocation, logged, counted and reported back to the caller as
+//! an [internal error](crate::ProviderInvocationError::Internal) on the wRPC transport.
+//!
+//! A provider which keeps panicking is likely wedged, so after [`max_consecutive_panics`]
+//! consecutive panicking invocations the provider shuts itself down. The threshold is read from
+//! the [`MAX_CONSECUTIVE_PANICS_KEY`] provider configuration key at startup.
+
+use core::any::Any;
+use core::future::Future;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+use futures::FutureExt as _;
+use once_cell::sync::Lazy;
+use tokio::spawn;
+use tracing::{debug, error, warn};
+use wasmcloud_tracing::{Counter, KeyValue};
+use wrpc_transport::{AcceptedInvocation, Transmitter};
+
+use crate::error::{transmit_invocation_error, ProviderInvocationError};
+use crate::metrics::ProviderMetrics;
+use crate::serve::{admit_inbound, RejectInvocation, THROTTLED_ERROR};
+use crate::{get_connection, Context, ShutdownReason};
+
+/// Default number of consecutive panicking invocations after which the provider shuts down
+pub const DEFAULT_MAX_CONSECUTIVE_PANICS: usize = 10;
+
+/// Provider configuration key setting the number of consecutive panicking invocations after which
+/// the provider shuts down, `0` disables the shutdown
+pub const MAX_CONSECUTIVE_PANICS_KEY: &str = "max_consecutive_panics";
+
+/// Maximum length of a panic message included in logs and error responses
+const MAX_PANIC_MESSAGE_LEN: usize = 256;
+
+static PANIC_GUARD: PanicGuard = PanicGuard::new(DEFAULT_MAX_CONSECUTIVE_PANICS);
+
+static PANIC_COUNTER: Lazy<Counter<u64>> = Lazy::new(|| {
+    wasmcloud_tracing::global::meter("wasmcloud-provider-sdk")
+        .u64_counter("wasmcloud_provider.invocation.panics")
+        .with_description("Number of provider invocation handlers that panicked")
+        .init()
+});
+
+/// Set the number of consecutive panicking invocations after which the provider triggers its own
+/// shutdown. Setting this to `0` disables the shutdown.
+pub fn set_max_consecutive_panics(max: usize) {
+    PANIC_GUARD.max_consecutive.store(max, Ordering::Relaxed);
+}
+
+/// Number of consecutive panicking invocations after which the provider triggers its own shutdown
+#[must_use]
+pub fn max_consecutive_panics() -> usize {
+    PANIC_GUARD.max_consecutive.load(Ordering::Relaxed)
+}
+
+/// Number of consecutive panicking invocations after which the provider shuts down according to
+/// the provider `config`, see [`MAX_CONSECUTIVE_PANICS_KEY`]. Missing or invalid values fall back to
+/// [`DEFAULT_MAX_CONSECUTIVE_PANICS`]
+#[must_use]
+pub fn max_consecutive_panics_from_config(config: &HashMap<String, String>) -> usize {
+    config
+        .get(MAX_CONSECUTIVE_PANICS_KEY)
+        .and_then(|value| match value.trim().parse() {
+            Ok(max) => Some(max),
+            Err(_) => {
+                warn!(
+                    key = MAX_CONSECUTIVE_PANICS_KEY,
+                    value, "ignoring invalid max consecutive panics"
+                );
+                None
+            }
+        })
+        .unwrap_or(DEFAULT_MAX_CONSECUTIVE_PANICS)
+}
+
+/// Total number of invocation handlers that panicked since the provider started
+#[must_use]
+pub fn panic_count() -> u64 {
+    PANIC_GUARD.total.load(Ordering::Relaxed)
+}
+
+/// Tracks panicking invocations to decide when a provider should shut down
+#[derive(Debug)]
+struct PanicGuard {
+    max_consecutive: AtomicUsize,
+    consecutive: AtomicUsize,
+    total: AtomicU64,
+}
+
+impl PanicGuard {
+    const fn new(max_consecutive: usize) -> Self {
+        Self {
+            max_consecutive: AtomicUsize::new(max_consecutive),
+            consecutive: AtomicUsize::new(0),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    fn record_success(&self) {
+        self.consecutive.store(0, Ordering::Relaxed);
+    }
+
+    /// Records a panic, returning `true` if the consecutive panic threshold has been reached
+    fn record_panic(&self) -> bool {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        let consecutive = self.consecutive.fetch_add(1, Ordering::Relaxed) + 1;
+        let max = self.max_consecutive.load(Ordering::Relaxed);
+        max > 0 && consecutive >= max
+    }
+}
+
+/// Extract a printable message from a panic payload, truncated to a reasonable length
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    };
+    if message.len() > MAX_PANIC_MESSAGE_LEN {
+        let mut end = MAX_PANIC_MESSAGE_LEN;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &message[..end])
+    } else {
+        message
+    }
+}
+
+/// Run `fut` to completion, catching any panic and returning its (truncated) message
+async fn run_isolated(
+    guard: &PanicGuard,
+    fut: impl Future<Output = ()>,
+) -> Result<(), (String, bool)> {
+    match AssertUnwindSafe(fut).catch_unwind().await {
+        Ok(()) => {
+            guard.record_success();
+            Ok(())
+        }
+        Err(payload) => Err((panic_message(payload.as_ref()), guard.record_panic())),
+    }
+}
+
+/// Spawn a task handling a single accepted invocation of `func` on `instance`, isolating panics.
+///
+/// The invocation is subject to the inbound limits of [`crate::serve`]: this waits for a free
+/// invocation slot, throttled invocations are rejected with an error and stuck invocations are
+/// detected, and aborted if configured.
+///
+/// If `handler` panics, an error is sent back to the caller on the invocation's error subject and
+/// the provider is shut down once [`max_consecutive_panics`] is reached.
+pub(crate) async fn spawn_invocation<T, Tx, F, Fut>(
+    instance: &'static str,
+    func: &'static str,
+    invocation: AcceptedInvocation<Option<Context>, T, Tx>,
+    handler: F,
+) where
+    T: Send + 'static,
+    Tx: Transmitter + Clone + Send + 'static,
+    Tx::Subject: Clone + Send + 'static,
+    F: FnOnce(AcceptedInvocation<Option<Context>, T, Tx>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let error_subject = invocation.error_subject.clone();
+    let transmitter = invocation.transmitter.clone();
+    let source = invocation
+        .context
+        .as_ref()
+        .and_then(|context| context.component.clone());
+    let Some((permit, watchdog)) = admit_inbound(source.as_deref()).await else {
+        debug!(instance, func, ?source, "throttling invocation");
+        spawn(async move {
+            transmit_invocation_error(
+                &transmitter,
+                error_subject,
+                ProviderInvocationError::Unavailable {
+                    message: THROTTLED_ERROR.to_string(),
+                    retry_after: None,
+                },
+            )
+            .await;
+        });
+        return;
+    };
+    let reject: RejectInvocation = {
+        let transmitter = transmitter.clone();
+        let error_subject = error_subject.clone();
+        Box::new(move |message| {
+            Box::pin(async move {
+                transmit_invocation_error(
+                    &transmitter,
+                    error_subject,
+                    ProviderInvocationError::Internal(message.to_string()),
+                )
+                .await;
+            })
+        })
+    };
+    let metrics = ProviderMetrics::global();
+    let started = metrics.start_invocation();
+    let panicked = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let panicked = Arc::clone(&panicked);
+        async move {
+            let Err((message, shutdown)) = run_isolated(&PANIC_GUARD, handler(invocation)).await
+            else {
+                return;
+            };
+            panicked.store(true, Ordering::Relaxed);
+            error!(instance, func, message, "invocation handler panicked");
+            PANIC_COUNTER.add(
+                1,
+                &[
+                    KeyValue::new("instance", instance),
+                    KeyValue::new("function", func),
+                ],
+            );
+            transmit_invocation_error(
+                &transmitter,
+                error_subject,
+                ProviderInvocationError::Internal(format!(
+                    "provider panicked while handling `{instance}.{func}`: {message}"
+                )),
+            )
+            .await;
+            if shutdown {
+                warn!(
+                    max_consecutive_panics = max_consecutive_panics(),
+                    "too many consecutive invocation handler panics, shutting down provider"
+                );
+                get_connection().request_shutdown(ShutdownReason::InternalError(
+                    "too many consecutive invocation handler panics".to_string(),
+                ));
+            }
+        }
+    };
+    spawn(async move {
+        watchdog
+            .watch(
+                Box::pin(handle),
+                Some(reject),
+                started,
+                instance,
+                func,
+                source.as_deref(),
+            )
+            .await;
+        metrics.finish_invocation(instance, func, started, panicked.load(Ordering::Relaxed));
+        drop(permit);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_panic_is_isolated() {
+        let guard = PanicGuard::new(3);
+
+        let res = run_isolated(&guard, async { panic!("handler exploded") }).await;
+        assert_eq!(res, Err(("handler exploded".to_string(), false)));
+        assert_eq!(guard.total.load(Ordering::Relaxed), 1);
+
+        // Non-panicking invocations keep working and reset the consecutive count
+        assert_eq!(run_isolated(&guard, async {}).await, Ok(()));
+        assert_eq!(guard.consecutive.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_consecutive_panic_threshold() {
+        let guard = PanicGuard::new(2);
+        let panicking = || async { panic!("{}", String::from("boom")) };
+
+        assert_eq!(
+            run_isolated(&guard, panicking()).await,
+            Err(("boom".to_string(), false))
+        );
+        assert_eq!(
+            run_isolated(&guard, panicking()).await,
+            Err(("boom".to_string(), true))
+        );
+
+        let guard = PanicGuard::new(0);
+        for _ in 0..5 {
+            assert!(matches!(
+                run_isolated(&guard, panicking()).await,
+                Err((_, false))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_max_consecutive_panics_from_config() {
+        assert_eq!(
+            max_consecutive_panics_from_config(&HashMap::default()),
+            DEFAULT_MAX_CONSECUTIVE_PANICS
+        );
+        let config = |value: &str| {
+            HashMap::from([(MAX_CONSECUTIVE_PANICS_KEY.to_string(), value.to_string())])
+        };
+        assert_eq!(max_consecutive_panics_from_config(&config(" 3 ")), 3);
+        assert_eq!(max_consecutive_panics_from_config(&config("0")), 0);
+        assert_eq!(
+            max_consecutive_panics_from_config(&config("many")),
+            DEFAULT_MAX_CONSECUTIVE_PANICS
+        );
+        assert_eq!(
+            max_consecutive_panics_from_config(&config("-1")),
+            DEFAULT_MAX_CONSECUTIVE_PANICS
+        );
+    }
+
+    #[test]
+    fn test_panic_message_truncated() {
+        let long = "é".repeat(MAX_PANIC_MESSAGE_LEN);
+        let message = panic_message(&long);
+        assert!(message.ends_with("..."));
+        assert!(message.len() <= MAX_PANIC_MESSAGE_LEN + 3);
+
+        let payload: Box<dyn Any + Send> = Box::new(42);
+        assert_eq!(
+            panic_message(payload.as_ref()),
+            "<non-string panic payload>"
+        );
+    }
+}
@@ -5,25 +5,78 @@ use std::collections::HashMap;
 
 use anyhow::Context as _;
 use async_nats::{ConnectOptions, Event};
+use bytes::Bytes;
+use link_readiness::LinkGate;
 use provider::invocation_context;
 use provider::ProviderInitState;
 use tower::ServiceExt;
 use tracing::{error, info, warn};
 use wrpc_transport::{AcceptedInvocation, IncomingInvocation, OutgoingInvocation};
 
+pub mod baggage;
+pub mod cache;
+pub mod client_cache;
+pub mod config_sources;
+pub mod control_auth;
+pub mod dedup;
 pub mod error;
+pub mod fanout;
+pub mod host_data;
 pub mod interfaces;
+pub mod isolation;
+pub mod link_cache;
+pub mod link_readiness;
+pub mod log_forwarding;
+pub mod metrics;
+pub mod native_deps;
 pub mod provider;
+pub mod sampling;
+pub mod serve;
+pub mod shared_resources;
+pub mod single_instance;
+pub mod state_snapshot;
+pub mod subscriptions;
+pub mod tasks;
+pub mod templates;
 
 #[cfg(feature = "otel")]
 pub mod otel;
 
-pub use provider::{get_connection, load_host_data, run_provider, ProviderConnection};
+pub use cache::{CachePolicy, CachedWrpcClient, InvocationCache};
+pub use client_cache::{ClientCacheStats, WRPC_CLIENT_CACHE_CAPACITY_CONFIG_KEY};
+pub use config_sources::ConfigSources;
+pub use control_auth::REQUIRE_SIGNED_CONTROL_CONFIG_KEY;
+pub use dedup::{DedupFailureMode, DedupOutcome, DedupWindow};
+pub use error::{ProviderInvocationError, ProviderInvocationResult};
+pub use fanout::{FanOut, FanOutOutcome, FanOutPolicy, FanOutResult};
+pub use host_data::HostCapabilities;
+pub use link_readiness::{LinkReadiness, LinkWait, LINK_READINESS_TIMEOUT_MS_KEY};
+pub use log_forwarding::{
+    log_forwarding, DroppedLogEvents, LogForwarder, LogForwardingLayer, ProviderLogEvent,
+};
+pub use metrics::ProviderMetrics;
+pub use native_deps::{NativeDependencies, NativeDependencyError};
+pub use provider::{
+    get_connection, load_host_data, run_provider, HostInfo, LinkEvent, ProviderConnection,
+    MAX_PAYLOAD_BYTES_KEY,
+};
+pub use sampling::SdkSpanSampler;
+pub use serve::{
+    serve_provider_exports_dynamic, set_inbound_limits, InboundInvocation, ServeHandle,
+    ServeLimits, STUCK_INVOCATION_ERROR, THROTTLED_ERROR,
+};
+pub use shared_resources::SharedResourceManager;
+pub use single_instance::{LockAcquisition, ProviderLock};
+pub use state_snapshot::StateSnapshotStore;
+pub use subscriptions::{SubscriptionCounts, SubscriptionManager};
+pub use tasks::{TaskGroup, TASK_DRAIN_TIMEOUT};
+pub use templates::{ConfigValue, SecretValue, TemplateError, TEMPLATE_ENV_ALLOWLIST_CONFIG_KEY};
 pub use wasmcloud_core as core;
 /// Re-export of types from [`wasmcloud_core`]
 pub use wasmcloud_core::{
-    HealthCheckRequest, HealthCheckResponse, InterfaceLinkDefinition, WitFunction, WitInterface,
-    WitNamespace, WitPackage,
+    wrpc::PayloadTooLarge, ConfigRejection, ConfigUpdateOutcome, DrainReport, HealthCheckRequest,
+    HealthCheckResponse, InterfaceLinkDefinition, WitFunction, WitInterface, WitNamespace,
+    WitPackage,
 };
 pub use wasmcloud_tracing;
 
@@ -82,26 +135,66 @@ pub const DEFAULT_RPC_TIMEOUT_MILLIS: Duration = Duration::from_millis(2000);
 /// helper method to add logging to a nats connection. Logs disconnection (warn level), reconnection (info level), error (error), slow consumer, and lame duck(warn) events.
 #[must_use]
 pub fn with_connection_event_logging(opts: ConnectOptions) -> ConnectOptions {
-    opts.event_callback(|event| async move {
-        match event {
-            Event::Disconnected => warn!("nats client disconnected"),
-            Event::Connected => info!("nats client connected"),
-            Event::ClientError(err) => error!("nats client error: '{:?}'", err),
-            Event::ServerError(err) => error!("nats server error: '{:?}'", err),
-            Event::SlowConsumer(val) => warn!("nats slow consumer detected ({})", val),
-            Event::LameDuckMode => warn!("nats lame duck mode"),
-        }
-    })
+    opts.event_callback(log_connection_event)
+}
+
+/// Log a nats connection event, see [`with_connection_event_logging`]
+pub(crate) async fn log_connection_event(event: Event) {
+    match event {
+        Event::Disconnected => warn!("nats client disconnected"),
+        Event::Connected => info!("nats client connected"),
+        Event::ClientError(err) => error!("nats client error: '{:?}'", err),
+        Event::ServerError(err) => error!("nats server error: '{:?}'", err),
+        Event::SlowConsumer(val) => warn!("nats slow consumer detected ({})", val),
+        Event::LameDuckMode => warn!("nats lame duck mode"),
+    }
 }
 
 /// Context - message passing metadata used by wasmCloud Capability Providers
+///
+/// Construct a context with [`Context::default`] or [`Context::from_component`] and the `with_*`
+/// methods, so that adding fields does not break callers.
 #[derive(Default, Debug, Clone)]
+#[non_exhaustive]
 pub struct Context {
     /// Messages received by a Provider will have component set to the component's ID
     pub component: Option<String>,
 
+    /// Name of the link the invocation was made over, if supplied by the invoker. Together with
+    /// [`Self::component`], this identifies the link of the invocation when the component is linked
+    /// to the provider several times, see [`ProviderConnection::lookup_link`]
+    pub link_name: Option<String>,
+
     /// A map of tracing context information
     pub tracing: HashMap<String, String>,
+
+    /// W3C baggage entries of the invocation, see [`baggage`]
+    pub baggage: HashMap<String, String>,
+}
+
+impl Context {
+    /// Context of an invocation made by `component`
+    #[must_use]
+    pub fn from_component(component: impl Into<String>) -> Self {
+        Self {
+            component: Some(component.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Set the name of the link the invocation was made over
+    #[must_use]
+    pub fn with_link_name(mut self, link_name: impl Into<String>) -> Self {
+        self.link_name = Some(link_name.into());
+        self
+    }
+
+    /// Set the tracing context information
+    #[must_use]
+    pub fn with_tracing(mut self, tracing: HashMap<String, String>) -> Self {
+        self.tracing = tracing;
+        self
+    }
 }
 
 /// Configuration of a link that is passed to a provider
@@ -125,6 +218,121 @@ pub struct LinkConfig<'a> {
     pub wit_metadata: (&'a WitNamespace, &'a WitPackage, &'a Vec<WitInterface>),
 }
 
+impl LinkConfig<'_> {
+    /// Returns the key identifying the link
+    #[must_use]
+    pub fn key(&self) -> LinkKey {
+        LinkKey::new(self.source_id, self.target_id, self.link_name)
+    }
+
+    /// Returns the configuration with its `{{secret:NAME}}`, `{{config:KEY}}` and `{{env:VAR}}`
+    /// placeholders resolved, see [`templates`]. Resolving is opt-in, [`LinkConfig::config`] holds
+    /// the values as set on the link.
+    ///
+    /// `secrets` holds the decrypted secrets of the link, and `provider_config` the configuration of
+    /// the provider listing the environment variables placeholders may reference.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TemplateError`] if a placeholder is malformed, nested or cannot be resolved
+    pub fn resolve_templates(
+        &self,
+        secrets: &HashMap<String, SecretValue>,
+        provider_config: &HashMap<String, String>,
+    ) -> Result<HashMap<String, ConfigValue>, TemplateError> {
+        templates::resolve_templates(self.config, secrets, provider_config)
+    }
+}
+
+/// Key identifying a link, by its source, target and name
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LinkKey {
+    /// ID of the source of the link
+    pub source_id: String,
+    /// ID of the target of the link
+    pub target_id: String,
+    /// Name of the link
+    pub link_name: String,
+}
+
+impl LinkKey {
+    /// Create a key for the link named `link_name` from `source_id` to `target_id`
+    pub fn new(
+        source_id: impl Into<String>,
+        target_id: impl Into<String>,
+        link_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            source_id: source_id.into(),
+            target_id: target_id.into(),
+            link_name: link_name.into(),
+        }
+    }
+}
+
+impl From<&InterfaceLinkDefinition> for LinkKey {
+    fn from(ld: &InterfaceLinkDefinition) -> Self {
+        Self::new(&ld.source_id, &ld.target, &ld.name)
+    }
+}
+
+impl ::core::fmt::Display for LinkKey {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(
+            f,
+            "{}->{} ({})",
+            self.source_id, self.target_id, self.link_name
+        )
+    }
+}
+
+/// Reason for which a provider is being shut down
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownReason {
+    /// The host running the provider requested a graceful stop
+    HostRequested {
+        /// ID of the host that requested the shutdown
+        host_id: String,
+    },
+    /// The host running the provider stopped it to replace it with a new version, see
+    /// [`state_snapshot`]
+    Update {
+        /// ID of the host that requested the shutdown
+        host_id: String,
+    },
+    /// Every instance of the provider in the lattice was requested to stop, regardless of the host
+    /// running it
+    LatticeRequested,
+    /// The provider has nothing left to do (e.g. all of its links were deleted)
+    Idle,
+    /// The provider hit an unrecoverable internal error
+    InternalError(String),
+    /// The provider lost its connection to the lattice
+    LatticeDisconnected,
+}
+
+impl ShutdownReason {
+    /// Whether the provider would like to be restarted after shutting down for this reason
+    #[must_use]
+    pub fn restart_requested(&self) -> bool {
+        matches!(self, Self::InternalError(_) | Self::LatticeDisconnected)
+    }
+}
+
+impl ::core::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        match self {
+            Self::HostRequested { host_id } => write!(f, "shutdown requested by host {host_id}"),
+            Self::Update { host_id } => write!(f, "update requested by host {host_id}"),
+            Self::LatticeRequested => write!(f, "shutdown requested for the lattice"),
+            Self::Idle => write!(f, "provider is idle"),
+            Self::InternalError(err) => write!(f, "internal error: {err}"),
+            Self::LatticeDisconnected => write!(f, "disconnected from the lattice"),
+        }
+    }
+}
+
 /// Configuration object is made available when a provider is started, to assist in init
 ///
 /// This trait exists to both obscure the underlying implementation and control what information
@@ -139,7 +347,26 @@ pub trait ProviderInitConfig: Send + Sync {
     ///
     /// This normally consists of named configuration that were set for the provider,
     /// merged, and received from the host *before* the provider has started initialization.
+    /// Values passed through by the host from its environment are merged in as well, overridden
+    /// by named configuration, see [`config_sources`]
     fn get_config(&self) -> &HashMap<String, String>;
+
+    /// Retrieve the individual sources [`ProviderInitConfig::get_config`] is merged from, see
+    /// [`config_sources`]
+    fn get_config_sources(&self) -> Option<&ConfigSources> {
+        None
+    }
+
+    /// Retrieve the verified native dependencies shipped alongside the provider, if any, see
+    /// [`native_deps`]
+    fn get_native_dependencies(&self) -> Option<&NativeDependencies> {
+        None
+    }
+
+    /// Optional features supported by the host the provider is connected to, see [`host_data`]
+    fn host_capabilities(&self) -> HostCapabilities {
+        HostCapabilities::default()
+    }
 }
 
 impl ProviderInitConfig for &ProviderInitState {
@@ -150,6 +377,18 @@ impl ProviderInitConfig for &ProviderInitState {
     fn get_config(&self) -> &HashMap<String, String> {
         &self.config
     }
+
+    fn get_config_sources(&self) -> Option<&ConfigSources> {
+        Some(&self.config_sources)
+    }
+
+    fn get_native_dependencies(&self) -> Option<&NativeDependencies> {
+        self.native_dependencies.as_ref()
+    }
+
+    fn host_capabilities(&self) -> HostCapabilities {
+        self.host_capabilities
+    }
 }
 
 /// Capability Provider handling of messages from host
@@ -221,6 +460,29 @@ pub trait Provider<E = anyhow::Error>: Sync {
         async { Ok(()) }
     }
 
+    /// Validate a configuration update pushed by the host before it is applied. `update` is the
+    /// complete configuration of the provider after the update. A rejected update is not passed
+    /// to [`Provider::on_config_update`], and the rejection is reported back to the host.
+    /// Default implementation accepts every update
+    fn validate_config_update(
+        &self,
+        update: &HashMap<String, String>,
+    ) -> impl Future<Output = Result<(), ConfigRejection>> + Send {
+        let _ = update;
+        async { Ok(()) }
+    }
+
+    /// Apply a configuration update pushed by the host, once it passed
+    /// [`Provider::validate_config_update`]. [`ProviderConnection::config`] reflects the update
+    /// once this returns successfully
+    fn on_config_update(
+        &self,
+        update: &HashMap<String, String>,
+    ) -> impl Future<Output = Result<(), E>> + Send {
+        let _ = update;
+        async { Ok(()) }
+    }
+
     /// Perform health check. Called at regular intervals by host
     /// Default implementation always returns healthy
     fn health_request(
@@ -235,15 +497,80 @@ pub trait Provider<E = anyhow::Error>: Sync {
         }
     }
 
+    /// Prepare for shutdown, requested by `wash stop --drain` before the provider is stopped.
+    ///
+    /// The provider should stop accepting new invocations and wait at most `timeout` for in-flight
+    /// ones to complete, e.g. using [`serve::ServeHandle::drain`]. The shutdown itself follows
+    /// separately. Default implementation reports the provider as drained immediately
+    fn prepare_shutdown(
+        &self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<DrainReport, E>> + Send {
+        let _ = timeout;
+        async {
+            Ok(DrainReport {
+                drained: true,
+                abandoned: None,
+            })
+        }
+    }
+
+    /// Snapshot the in-memory state of the provider before the host replaces it with a new version,
+    /// called before [`Provider::shutdown_with_reason`] when enabled, see [`state_snapshot`].
+    /// Default implementation returns an empty snapshot, which is not stored
+    fn snapshot_state(&self) -> impl Future<Output = Result<Bytes, E>> + Send {
+        async { Ok(Bytes::new()) }
+    }
+
+    /// Restore the in-memory state of the provider from a snapshot taken by the version it
+    /// replaces, called after [`Provider::init`] and before links are replayed, see
+    /// [`state_snapshot`]
+    fn restore_state(&self, state: Bytes) -> impl Future<Output = Result<(), E>> + Send {
+        let _ = state;
+        async { Ok(()) }
+    }
+
     /// Handle system shutdown message
     fn shutdown(&self) -> impl Future<Output = Result<(), E>> + Send {
         async { Ok(()) }
     }
+
+    /// Handle system shutdown message, with the reason for which the provider is shutting down.
+    /// Default implementation calls [`Provider::shutdown`]
+    fn shutdown_with_reason(
+        &self,
+        reason: &ShutdownReason,
+    ) -> impl Future<Output = Result<(), E>> + Send {
+        let _ = reason;
+        self.shutdown()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct WrpcClient(pub wasmcloud_core::wrpc::Client);
 
+impl WrpcClient {
+    /// Max payload of the NATS server of the lattice, in bytes. Parameters of invocations must fit
+    /// in it, while larger results and streams are transmitted in chunks
+    #[must_use]
+    pub fn max_payload(&self) -> usize {
+        self.0.max_payload()
+    }
+
+    /// Propagate the trace context and baggage of `context`, including entries added with
+    /// [`Context::with_baggage`], on all invocations made with this client
+    #[must_use]
+    pub fn with_context(mut self, context: &Context) -> Self {
+        let headers = self.0.headers_mut();
+        for (name, values) in context.propagation_headers().iter() {
+            for value in values {
+                headers.insert(name.to_string(), value.as_str());
+            }
+        }
+        self
+    }
+}
+
 impl wrpc_transport::Client for WrpcClient {
     type Context = Option<Context>;
     type Subject = <wasmcloud_core::wrpc::Client as wrpc_transport::Client>::Subject;
@@ -270,6 +597,19 @@ impl wrpc_transport::Client for WrpcClient {
             + 'static,
         Fut: Future<Output = Result<AcceptedInvocation<Ctx, T, Tx>, anyhow::Error>> + Send,
     {
+        // Park invocations from sources with a link being processed, see [`link_readiness`]
+        let svc = LinkGate::new(
+            svc,
+            provider::CONNECTION
+                .get()
+                .map(|connection| connection.link_readiness().clone()),
+            |invocation: &IncomingInvocation<Self::Context, Self::Subscriber, Self::Acceptor>| {
+                invocation
+                    .context
+                    .as_ref()
+                    .and_then(|context| context.component.clone())
+            },
+        );
         self.0.serve(
             instance,
             name,
@@ -300,6 +640,27 @@ impl wrpc_transport::Client for WrpcClient {
     fn new_invocation(
         &self,
     ) -> OutgoingInvocation<Self::Invocation, Self::Subscriber, Self::Subject> {
-        self.0.new_invocation()
+        #[allow(unused_mut)]
+        let mut invocation = self.0.new_invocation();
+        // Propagate the context of the current span, unless set with `with_context`
+        #[cfg(feature = "otel")]
+        {
+            use wasmcloud_tracing::context::{current_baggage, TraceContextInjector};
+
+            let headers = invocation.invocation.headers_mut();
+            if headers.get(baggage::TRACE_CONTEXT_HEADERS[0]).is_none() {
+                for (name, value) in TraceContextInjector::default_with_span().iter() {
+                    headers.insert(name.as_str(), value.as_str());
+                }
+            }
+            let current = current_baggage();
+            if headers.get(baggage::BAGGAGE_HEADER).is_none() && !current.is_empty() {
+                headers.insert(
+                    baggage::BAGGAGE_HEADER,
+                    baggage::format_baggage(&current).as_str(),
+                );
+            }
+        }
+        invocation
     }
 }
@@ -0,0 +1,125 @@
+//! Persisted cache of the links of a provider
+//!
+//! When a provider is restarted, it has no links until the host replays them, so invocations
+//! received in the meantime fail. Providers opt into caching their links by setting
+//! [`LINK_CACHE_PATH_CONFIG`] in their configuration: the SDK then writes the links of the provider
+//! to that path whenever they change, and restores them on startup before processing the links
+//! supplied by the host. Restored links which the host neither supplies at startup nor puts again
+//! within [`LINK_CACHE_GRACE_PERIOD`] are deleted.
+//!
+//! Only link definitions are cached, which do not contain secrets.
+
+use core::time::Duration;
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+
+use tokio::sync::Mutex;
+use tracing::warn;
+use wasmcloud_core::InterfaceLinkDefinition;
+
+use crate::LinkKey;
+
+/// Key of the provider configuration holding the path of the link cache
+pub const LINK_CACHE_PATH_CONFIG: &str = "link_cache_path";
+
+/// Duration after startup for the host to confirm links restored from the cache
+pub const LINK_CACHE_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub(crate) struct LinkCache {
+    path: PathBuf,
+    /// Held while writing, so that writes of concurrent link changes do not interleave
+    write: Mutex<()>,
+    /// Links restored from the cache which the host did not confirm yet
+    unconfirmed: std::sync::Mutex<HashSet<LinkKey>>,
+}
+
+impl LinkCache {
+    /// Returns the link cache configured in the provider `config`, if any
+    pub(crate) fn from_config(config: &HashMap<String, String>) -> Option<Self> {
+        config
+            .get(LINK_CACHE_PATH_CONFIG)
+            .filter(|path| !path.is_empty())
+            .map(|path| Self {
+                path: PathBuf::from(path),
+                write: Mutex::default(),
+                unconfirmed: std::sync::Mutex::default(),
+            })
+    }
+
+    fn unconfirmed(&self) -> MutexGuard<'_, HashSet<LinkKey>> {
+        self.unconfirmed
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Read the cached links. A missing or unreadable cache is treated as empty
+    pub(crate) async fn load(&self) -> Vec<InterfaceLinkDefinition> {
+        let buf = match tokio::fs::read(&self.path).await {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                warn!(error = %e, path = %self.path.display(), "failed to read link cache");
+                return Vec::new();
+            }
+        };
+        serde_json::from_slice(&buf).unwrap_or_else(|e| {
+            warn!(error = %e, path = %self.path.display(), "failed to parse link cache");
+            Vec::new()
+        })
+    }
+
+    /// Replace the cached links with the links returned by `links`, which is awaited while no
+    /// other write is in progress
+    pub(crate) async fn store(
+        &self,
+        links: impl core::future::Future<Output = Vec<InterfaceLinkDefinition>>,
+    ) {
+        let _write = self.write.lock().await;
+        let links = links.await;
+        if let Err(e) = write_atomic(&self.path, &links).await {
+            warn!(error = %e, path = %self.path.display(), "failed to write link cache");
+        }
+    }
+
+    /// Track the `restored` links which are not among the links supplied by the host at startup
+    pub(crate) fn set_unconfirmed(
+        &self,
+        restored: &[InterfaceLinkDefinition],
+        host_links: &[InterfaceLinkDefinition],
+    ) {
+        let host_links: HashSet<_> = host_links.iter().map(LinkKey::from).collect();
+        *self.unconfirmed() = restored
+            .iter()
+            .map(LinkKey::from)
+            .filter(|key| !host_links.contains(key))
+            .collect();
+    }
+
+    /// Record that the host put or deleted the link identified by `key`
+    pub(crate) fn confirm(&self, key: &LinkKey) {
+        self.unconfirmed().remove(key);
+    }
+
+    /// Returns the keys of all restored links the host did not confirm, which are no longer
+    /// tracked afterwards
+    pub(crate) fn take_unconfirmed(&self) -> HashSet<LinkKey> {
+        std::mem::take(&mut *self.unconfirmed())
+    }
+}
+
+/// Write `links` to `path` by writing a temporary file first and renaming it, so that the cache is
+/// never partially written
+async fn write_atomic(path: &Path, links: &[InterfaceLinkDefinition]) -> io::Result<()> {
+    let buf = serde_json::to_vec(links)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    tokio::fs::write(&tmp, buf).await?;
+    tokio::fs::rename(&tmp, path).await
+}
@@ -0,0 +1,357 @@
+//! Ordering of link puts and the invocations made over the links
+//!
+//! The host may route an invocation to a provider right after putting a link, before the provider
+//! has finished processing it in [`crate::Provider::receive_link_config_as_target`], so that the
+//! first invocations from the source of the link fail as if it was not linked. The SDK marks the
+//! source of a link as pending from the moment the link put is received, or from startup for the
+//! links supplied by the host, until the provider has processed the link. Invocations served
+//! through [`crate::WrpcClient`] from a pending source are parked until the link is processed, then
+//! delivered.
+//!
+//! Invocations are parked at most [`LINK_READINESS_TIMEOUT_MS_KEY`] (5 seconds by default), and at
+//! most [`MAX_PARKED_INVOCATIONS`] at once. Invocations which are not parked, or are still parked
+//! once the timeout elapses, are delivered regardless and fail as they would without the gate if
+//! no link materializes.
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context as TaskContext, Poll};
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::{watch, Semaphore};
+use tracing::{debug, warn};
+
+/// Key of the provider configuration holding how long invocations wait for the link they are made
+/// over to be processed, in milliseconds
+pub const LINK_READINESS_TIMEOUT_MS_KEY: &str = "link_readiness_timeout_ms";
+
+/// Default time invocations wait for the link they are made over to be processed
+pub const DEFAULT_LINK_READINESS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of invocations waiting for a link to be processed at once
+pub const MAX_PARKED_INVOCATIONS: usize = 1024;
+
+/// Outcome of waiting for the links of a source to be processed, see [`LinkReadiness::wait`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkWait {
+    /// No link from the source was being processed
+    Ready,
+    /// The links from the source were processed while waiting
+    Completed,
+    /// A link from the source was still being processed after the timeout
+    TimedOut,
+    /// Too many invocations were already waiting, so the invocation did not wait
+    Overflow,
+}
+
+/// Sources of the links a provider is processing, shared by the link handling of the provider and
+/// the invocations it serves. Cheap to clone, all clones refer to the same sources
+#[derive(Clone, Debug)]
+pub struct LinkReadiness {
+    /// Number of links being processed by source
+    pending: Arc<watch::Sender<HashMap<String, usize>>>,
+    parked: Arc<Semaphore>,
+    max_wait: Duration,
+}
+
+impl Default for LinkReadiness {
+    fn default() -> Self {
+        Self::new(DEFAULT_LINK_READINESS_TIMEOUT)
+    }
+}
+
+impl LinkReadiness {
+    /// Create a gate parking invocations at most `max_wait`
+    #[must_use]
+    pub fn new(max_wait: Duration) -> Self {
+        Self {
+            pending: Arc::new(watch::Sender::new(HashMap::new())),
+            parked: Arc::new(Semaphore::new(MAX_PARKED_INVOCATIONS)),
+            max_wait,
+        }
+    }
+
+    /// Create a gate with the timeout set in the provider `config`, see
+    /// [`LINK_READINESS_TIMEOUT_MS_KEY`]. Invalid values leave the default timeout
+    #[must_use]
+    pub fn from_config(config: &HashMap<String, String>) -> Self {
+        let max_wait = config
+            .get(LINK_READINESS_TIMEOUT_MS_KEY)
+            .and_then(|value| match value.trim().parse::<u64>() {
+                Ok(ms) => Some(Duration::from_millis(ms)),
+                Err(_) => {
+                    warn!(
+                        key = LINK_READINESS_TIMEOUT_MS_KEY,
+                        value, "ignoring invalid link readiness timeout"
+                    );
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_LINK_READINESS_TIMEOUT);
+        Self::new(max_wait)
+    }
+
+    /// Longest time an invocation waits for the links of its source to be processed
+    #[must_use]
+    pub fn max_wait(&self) -> Duration {
+        self.max_wait
+    }
+
+    /// Mark a link from `source_id` as being processed until the returned guard is dropped
+    #[must_use]
+    pub fn begin(&self, source_id: impl Into<String>) -> PendingLink {
+        let source_id = source_id.into();
+        self.pending.send_modify(|pending| {
+            *pending.entry(source_id.clone()).or_default() += 1;
+        });
+        PendingLink {
+            pending: Arc::clone(&self.pending),
+            source_id,
+        }
+    }
+
+    /// Whether a link from `source_id` is being processed
+    #[must_use]
+    pub fn is_pending(&self, source_id: &str) -> bool {
+        self.pending.borrow().contains_key(source_id)
+    }
+
+    /// Wait until no link from `source_id` is being processed, at most [`Self::max_wait`]
+    pub async fn wait(&self, source_id: &str) -> LinkWait {
+        if !self.is_pending(source_id) {
+            return LinkWait::Ready;
+        }
+        let Ok(_parked) = self.parked.try_acquire() else {
+            return LinkWait::Overflow;
+        };
+        let mut pending = self.pending.subscribe();
+        match tokio::time::timeout(
+            self.max_wait,
+            pending.wait_for(|pending| !pending.contains_key(source_id)),
+        )
+        .await
+        {
+            Ok(_) => LinkWait::Completed,
+            Err(_) => LinkWait::TimedOut,
+        }
+    }
+}
+
+/// A link being processed, marking its source as pending until dropped, see
+/// [`LinkReadiness::begin`]
+#[derive(Debug)]
+pub struct PendingLink {
+    pending: Arc<watch::Sender<HashMap<String, usize>>>,
+    source_id: String,
+}
+
+impl Drop for PendingLink {
+    fn drop(&mut self) {
+        self.pending.send_modify(|pending| {
+            if let Some(count) = pending.get_mut(&self.source_id) {
+                *count -= 1;
+                if *count == 0 {
+                    pending.remove(&self.source_id);
+                }
+            }
+        });
+    }
+}
+
+/// Service parking requests from a source with a link being processed before passing them to the
+/// inner service, see the [module documentation](self)
+#[derive(Clone)]
+pub(crate) struct LinkGate<S, F> {
+    inner: S,
+    readiness: Option<LinkReadiness>,
+    source: F,
+}
+
+impl<S, F> LinkGate<S, F> {
+    /// Gate requests to `inner` on `readiness`, if any, using `source` to get the source of each
+    /// request
+    pub(crate) fn new(inner: S, readiness: Option<LinkReadiness>, source: F) -> Self {
+        Self {
+            inner,
+            readiness,
+            source,
+        }
+    }
+}
+
+impl<S, F, R> tower::Service<R> for LinkGate<S, F>
+where
+    S: tower::Service<R> + Clone + Send + 'static,
+    S::Future: Send,
+    F: Fn(&R) -> Option<String>,
+    R: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        // Call the service which was polled ready, leaving a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = core::mem::replace(&mut self.inner, clone);
+        let source = self
+            .readiness
+            .clone()
+            .zip((self.source)(&req))
+            .filter(|(readiness, source)| readiness.is_pending(source));
+        Box::pin(async move {
+            if let Some((readiness, source)) = source {
+                match readiness.wait(&source).await {
+                    LinkWait::Ready | LinkWait::Completed => {
+                        debug!(
+                            source,
+                            "delivering invocation parked until its link was processed"
+                        );
+                    }
+                    LinkWait::TimedOut => warn!(
+                        source,
+                        timeout = ?readiness.max_wait(),
+                        "link of invocation still processing after timeout, delivering anyway"
+                    ),
+                    LinkWait::Overflow => warn!(
+                        source,
+                        "too many invocations waiting for their link, delivering without waiting"
+                    ),
+                }
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use anyhow::anyhow;
+    use tokio::sync::RwLock;
+    use tower::{service_fn, Service as _, ServiceExt as _};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_pending_link() {
+        let readiness = LinkReadiness::new(Duration::from_secs(5));
+        assert_eq!(readiness.wait("component").await, LinkWait::Ready);
+
+        let first = readiness.begin("component");
+        let second = readiness.begin("component");
+        assert!(readiness.is_pending("component"));
+        assert!(!readiness.is_pending("other"));
+        drop(first);
+        assert!(readiness.is_pending("component"));
+
+        let waiting = tokio::spawn({
+            let readiness = readiness.clone();
+            async move { readiness.wait("component").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        drop(second);
+        assert_eq!(
+            waiting.await.expect("wait should not panic"),
+            LinkWait::Completed
+        );
+        assert!(!readiness.is_pending("component"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_is_bounded() {
+        let readiness = LinkReadiness::new(Duration::from_millis(50));
+        let _pending = readiness.begin("component");
+        assert_eq!(readiness.wait("component").await, LinkWait::TimedOut);
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = |value: &str| {
+            HashMap::from([(LINK_READINESS_TIMEOUT_MS_KEY.to_string(), value.to_string())])
+        };
+        assert_eq!(
+            LinkReadiness::from_config(&config("250")).max_wait(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            LinkReadiness::from_config(&config("soon")).max_wait(),
+            DEFAULT_LINK_READINESS_TIMEOUT
+        );
+        assert_eq!(
+            LinkReadiness::from_config(&HashMap::new()).max_wait(),
+            DEFAULT_LINK_READINESS_TIMEOUT
+        );
+    }
+
+    /// Invocations made right after a link put, while the provider is still processing it, are
+    /// delivered once the link is processed rather than failing
+    #[tokio::test]
+    async fn test_invocations_interleaved_with_link_puts() {
+        let readiness = LinkReadiness::new(Duration::from_secs(5));
+        let linked = Arc::new(RwLock::new(HashSet::new()));
+        let mut gate = LinkGate::new(
+            service_fn({
+                let linked = Arc::clone(&linked);
+                move |source: String| {
+                    let linked = Arc::clone(&linked);
+                    async move {
+                        if linked.read().await.contains(&source) {
+                            Ok(())
+                        } else {
+                            Err(anyhow!("unknown source {source}"))
+                        }
+                    }
+                }
+            }),
+            Some(readiness.clone()),
+            |source: &String| Some(source.clone()),
+        );
+
+        let mut invocations = Vec::new();
+        for i in 0..50 {
+            let source = format!("component-{i}");
+            // The link put is received, then processed while the invocation is served
+            let pending = readiness.begin(source.clone());
+            tokio::spawn({
+                let linked = Arc::clone(&linked);
+                let source = source.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    linked.write().await.insert(source);
+                    drop(pending);
+                }
+            });
+            let invocation = gate
+                .ready()
+                .await
+                .expect("gate should be ready")
+                .call(source);
+            invocations.push(tokio::spawn(invocation));
+        }
+        for invocation in invocations {
+            invocation
+                .await
+                .expect("invocation should not panic")
+                .expect("invocation should be delivered after its link");
+        }
+
+        // Invocations from sources without a link still fail
+        let err = gate
+            .ready()
+            .await
+            .expect("gate should be ready")
+            .call("unlinked".to_string())
+            .await;
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Forwarding of provider logs to the lattice
+//!
+//! Providers configured with `forward_logs=true` add a [`LogForwardingLayer`] to their tracing
+//! subscriber, which ships log events to [`provider_log_subject`] so that they can be watched
+//! from the lattice, along with the logs written to the provider's stderr.
+//!
+//! Events are sent to a [`LogForwarder`] through a bounded buffer, and published as JSON arrays of
+//! at most [`LOG_BATCH_SIZE`] events, at least every [`LOG_FLUSH_INTERVAL`]. Events are never
+//! waited on: events that do not fit in the buffer, or whose batch could not be published in time,
+//! are dropped and counted in [`DroppedLogEvents`], so that a slow or unavailable NATS server does
+//! not slow down the provider. Batches exceeding the max payload of the NATS server are split, and
+//! single events exceeding it are dropped.
+
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+use tokio::time::{interval, timeout, MissedTickBehavior};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use wasmcloud_core::rpc::provider_log_subject;
+
+use crate::dedup::unix_millis;
+
+/// Configuration key enabling log forwarding, if set to `true`
+pub const FORWARD_LOGS_CONFIG_KEY: &str = "forward_logs";
+
+/// Configuration key of the minimum level of forwarded log events, `info` by default
+pub const FORWARD_LOGS_LEVEL_CONFIG_KEY: &str = "forward_logs_level";
+
+/// Maximum number of log events published in a single message
+pub const LOG_BATCH_SIZE: usize = 100;
+
+/// Maximum duration log events are buffered before being published
+pub const LOG_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Maximum number of log events buffered before new events are dropped
+const LOG_BUFFER_CAPACITY: usize = 10 * LOG_BATCH_SIZE;
+
+/// Timeout of publishing a batch of log events
+const LOG_PUBLISH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Log event forwarded to the lattice
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ProviderLogEvent {
+    /// ID of the provider which emitted the event
+    pub provider_id: String,
+    /// ID of the host running the provider
+    pub host_id: String,
+    /// Time the event was emitted, in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Level of the event, e.g. `INFO`
+    pub level: String,
+    /// Target of the event, usually the module it was emitted from
+    pub target: String,
+    /// Message of the event
+    #[serde(default)]
+    pub message: String,
+    /// Fields of the event, other than the message
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+    /// Spans the event was emitted in, from the root span
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<ProviderLogSpan>,
+}
+
+/// Span a forwarded log event was emitted in
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ProviderLogSpan {
+    /// Name of the span
+    pub name: String,
+    /// Fields of the span
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// Number of log events dropped instead of being forwarded
+#[derive(Clone, Debug, Default)]
+pub struct DroppedLogEvents(Arc<AtomicU64>);
+
+impl DroppedLogEvents {
+    /// Number of log events dropped so far
+    #[must_use]
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, n: usize) {
+        self.0
+            .fetch_add(n.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+}
+
+/// Create a [`LogForwardingLayer`] forwarding the log events of the provider with ID `provider_id`
+/// at `level` or above, and the [`LogForwarder`] publishing them
+#[must_use]
+pub fn log_forwarding(
+    provider_id: impl Into<String>,
+    host_id: impl Into<String>,
+    level: Level,
+) -> (LogForwardingLayer, LogForwarder) {
+    let (tx, rx) = mpsc::channel(LOG_BUFFER_CAPACITY);
+    let dropped = DroppedLogEvents::default();
+    (
+        LogForwardingLayer {
+            tx,
+            provider_id: provider_id.into(),
+            host_id: host_id.into(),
+            level,
+            dropped: dropped.clone(),
+        },
+        LogForwarder { rx, dropped },
+    )
+}
+
+/// [`Layer`] sending log events to a [`LogForwarder`]
+pub struct LogForwardingLayer {
+    tx: mpsc::Sender<ProviderLogEvent>,
+    provider_id: String,
+    host_id: String,
+    level: Level,
+    dropped: DroppedLogEvents,
+}
+
+impl LogForwardingLayer {
+    /// Whether events of `target` are forwarded. Events emitted while forwarding are not, as
+    /// publishing them could emit more events
+    fn forwards_target(target: &str) -> bool {
+        !target.starts_with(module_path!()) && !target.starts_with("async_nats")
+    }
+}
+
+/// Fields of a span, recorded in its extensions
+struct SpanFields(Map<String, Value>);
+
+impl<S> Layer<S> for LogForwardingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        span.extensions_mut().insert(SpanFields(fields.fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = FieldVisitor {
+                fields: std::mem::take(fields),
+                ..FieldVisitor::default()
+            };
+            values.record(&mut visitor);
+            *fields = visitor.fields;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > self.level || !Self::forwards_target(metadata.target()) {
+            return;
+        }
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+        let spans = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| ProviderLogSpan {
+                name: span.name().to_string(),
+                fields: span
+                    .extensions()
+                    .get::<SpanFields>()
+                    .map(|SpanFields(fields)| fields.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        let event = ProviderLogEvent {
+            provider_id: self.provider_id.clone(),
+            host_id: self.host_id.clone(),
+            timestamp_ms: unix_millis(SystemTime::now()),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: fields.message.unwrap_or_default(),
+            fields: fields.fields,
+            spans,
+        };
+        if self.tx.try_send(event).is_err() {
+            self.dropped.add(1);
+        }
+    }
+}
+
+/// Publisher of the log events sent by a [`LogForwardingLayer`]
+pub struct LogForwarder {
+    rx: mpsc::Receiver<ProviderLogEvent>,
+    dropped: DroppedLogEvents,
+}
+
+impl LogForwarder {
+    /// Counter of the log events dropped by this forwarder and its layer
+    #[must_use]
+    pub fn dropped_events(&self) -> DroppedLogEvents {
+        self.dropped.clone()
+    }
+
+    /// Publish log events to the provider log subject of `lattice`, until the layer is dropped
+    pub async fn run(mut self, nats: async_nats::Client, lattice: String) {
+        let subject = provider_log_subject(&lattice);
+        let mut batch = Vec::with_capacity(LOG_BATCH_SIZE);
+        let mut flush = interval(LOG_FLUSH_INTERVAL);
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                event = self.rx.recv() => {
+                    let Some(event) = event else {
+                        self.publish(&nats, &subject, &mut batch).await;
+                        return;
+                    };
+                    batch.push(event);
+                    if batch.len() >= LOG_BATCH_SIZE {
+                        self.publish(&nats, &subject, &mut batch).await;
+                        flush.reset();
+                    }
+                }
+                _ = flush.tick() => self.publish(&nats, &subject, &mut batch).await,
+            }
+        }
+    }
+
+    /// Publish `batch`, if not empty, counting its events as dropped if it could not be published.
+    /// Parts of the batch exceeding the max payload of the NATS server are published separately
+    async fn publish(
+        &self,
+        nats: &async_nats::Client,
+        subject: &str,
+        batch: &mut Vec<ProviderLogEvent>,
+    ) {
+        let max_payload = nats.server_info().max_payload;
+        let mut parts = vec![batch.as_slice()];
+        while let Some(part) = parts.pop() {
+            if part.is_empty() {
+                continue;
+            }
+            let payload = match serde_json::to_vec(part) {
+                Ok(payload) if payload.len() > max_payload && part.len() > 1 => {
+                    let (first, second) = part.split_at(part.len() / 2);
+                    parts.push(second);
+                    parts.push(first);
+                    continue;
+                }
+                Ok(payload) if payload.len() <= max_payload => payload,
+                // A single event exceeding the max payload cannot be published
+                Ok(_) | Err(_) => {
+                    self.dropped.add(part.len());
+                    continue;
+                }
+            };
+            let published = timeout(LOG_PUBLISH_TIMEOUT, async {
+                nats.publish(subject.to_string(), payload.into()).await?;
+                nats.flush().await?;
+                anyhow::Ok(())
+            })
+            .await
+            .is_ok_and(|res| res.is_ok());
+            if !published {
+                self.dropped.add(part.len());
+            }
+        }
+        batch.clear();
+    }
+}
+
+/// [`Visit`]or collecting the fields of events and spans as JSON
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &Field, value: Value) {
+        match (field.name(), value) {
+            ("message", Value::String(message)) => self.message = Some(message),
+            (name, value) => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, format!("{value:?}").into());
+    }
+}
@@ -0,0 +1,522 @@
+//! Prometheus scrape endpoint of a provider
+//!
+//! Providers export their metrics through OTLP when configured to, which requires running a
+//! collector. As a simpler alternative, providers configured with a
+//! [`METRICS_LISTEN_ADDR_CONFIG_KEY`] serve their metrics on `/metrics` at that address, in the
+//! Prometheus text exposition format:
+//!
+//! - `wasmcloud_provider_invocations_total`, `wasmcloud_provider_invocation_panics_total` and the
+//!   `wasmcloud_provider_invocation_duration_seconds` histogram, per exported function
+//! - `wasmcloud_provider_uptime_seconds`, `wasmcloud_provider_links`,
+//!   `wasmcloud_provider_invocations_in_flight` and `wasmcloud_provider_stuck_invocations`
+//! - `wasmcloud_provider_wrpc_client_cache_hits_total` and
+//!   `wasmcloud_provider_wrpc_client_cache_misses_total`, see [`crate::client_cache`]
+//!
+//! The endpoint is bound once the provider is initialized and shut down along with the provider.
+//! A provider whose endpoint fails to bind (e.g. as the address is already in use) keeps running,
+//! and reports the failure in its health check responses instead.
+
+use core::fmt::Write as _;
+use core::future::Future;
+use core::time::Duration;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use once_cell::sync::Lazy;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+/// Provider config key setting the address the metrics endpoint listens on, e.g. `0.0.0.0:9090`.
+/// The endpoint is disabled unless set.
+pub const METRICS_LISTEN_ADDR_CONFIG_KEY: &str = "metrics_listen_addr";
+
+/// Content type of the responses of the metrics endpoint
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of the buckets of the invocation duration histogram, in seconds
+const DURATION_BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Maximum size of the request head read from scrapers
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+
+/// Maximum duration to wait for a scraper to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+static METRICS: Lazy<ProviderMetrics> = Lazy::new(ProviderMetrics::new);
+
+/// Error which prevented the metrics endpoint from starting, reported in health check responses
+static LISTENER_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Returns the address to serve metrics on set in the provider `config`, if any
+#[must_use]
+pub fn metrics_listen_addr_from_config(
+    config: &std::collections::HashMap<String, String>,
+) -> Option<&str> {
+    config
+        .get(METRICS_LISTEN_ADDR_CONFIG_KEY)
+        .map(|addr| addr.trim())
+        .filter(|addr| !addr.is_empty())
+}
+
+/// Returns the error which prevented the metrics endpoint of the provider from starting, if any
+#[must_use]
+pub fn metrics_listener_error() -> Option<String> {
+    LISTENER_ERROR
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+fn set_metrics_listener_error(error: Option<String>) {
+    *LISTENER_ERROR
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = error;
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct InvocationStats {
+    count: u64,
+    panics: u64,
+    /// Number of invocations per bucket of [`DURATION_BUCKETS`], not cumulative
+    buckets: [u64; DURATION_BUCKETS.len()],
+    duration_sum: f64,
+}
+
+/// Metrics of the invocations handled by a provider
+#[derive(Debug)]
+pub struct ProviderMetrics {
+    started: Instant,
+    in_flight: AtomicU64,
+    /// Number of in-flight invocations that have been running for longer than expected
+    stuck: AtomicU64,
+    /// Number of wRPC clients returned from the client cache
+    wrpc_client_cache_hits: AtomicU64,
+    /// Number of wRPC clients created as none was cached
+    wrpc_client_cache_misses: AtomicU64,
+    /// Statistics of the invocations of each exported function, by instance and function name
+    invocations: Mutex<BTreeMap<(String, String), InvocationStats>>,
+}
+
+impl Default for ProviderMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderMetrics {
+    /// Create metrics with no invocations recorded
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            in_flight: AtomicU64::new(0),
+            stuck: AtomicU64::new(0),
+            wrpc_client_cache_hits: AtomicU64::new(0),
+            wrpc_client_cache_misses: AtomicU64::new(0),
+            invocations: Mutex::default(),
+        }
+    }
+
+    /// Metrics of the invocations handled by this provider, recorded by the serving loops of the
+    /// SDK
+    #[must_use]
+    pub fn global() -> &'static Self {
+        &METRICS
+    }
+
+    /// Record the start of an invocation, returning the time it started at to pass to
+    /// [`Self::finish_invocation`]
+    pub fn start_invocation(&self) -> Instant {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        Instant::now()
+    }
+
+    /// Record the end of an invocation of `func` on `instance` started at `started`
+    pub fn finish_invocation(&self, instance: &str, func: &str, started: Instant, panicked: bool) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        let duration = started.elapsed().as_secs_f64();
+        let mut invocations = self
+            .invocations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let stats = invocations
+            .entry((instance.to_string(), func.to_string()))
+            .or_default();
+        stats.count += 1;
+        if panicked {
+            stats.panics += 1;
+        }
+        stats.duration_sum += duration;
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|le| duration <= *le) {
+            stats.buckets[bucket] += 1;
+        }
+    }
+
+    /// Number of invocations currently being handled
+    #[must_use]
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Record that an in-flight invocation is stuck, i.e. has been running for longer than
+    /// expected. Call [`Self::finish_stuck_invocation`] once it completes or is aborted
+    pub fn start_stuck_invocation(&self) {
+        self.stuck.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that a stuck invocation has completed or was aborted
+    pub fn finish_stuck_invocation(&self) {
+        self.stuck.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Number of in-flight invocations which are stuck
+    #[must_use]
+    pub fn stuck_invocations(&self) -> u64 {
+        self.stuck.load(Ordering::Relaxed)
+    }
+
+    /// Record a lookup in the wRPC client cache, which either returned a cached client (`hit`) or
+    /// created a new one
+    pub fn record_wrpc_client_cache_lookup(&self, hit: bool) {
+        if hit {
+            self.wrpc_client_cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.wrpc_client_cache_misses
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format, along with the number of
+    /// `links` of the provider
+    #[must_use]
+    pub fn render(&self, links: usize) -> String {
+        let mut out = String::new();
+        // Writing to a `String` does not fail
+        let _ = self.write_text(&mut out, links);
+        out
+    }
+
+    fn write_text(&self, out: &mut String, links: usize) -> core::fmt::Result {
+        let invocations = self
+            .invocations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        let labels = |(instance, func): &(String, String)| {
+            format!(
+                "instance=\"{}\",function=\"{}\"",
+                escape_label(instance),
+                escape_label(func)
+            )
+        };
+        writeln!(out, "# HELP wasmcloud_provider_invocations_total Number of invocations handled by the provider")?;
+        writeln!(out, "# TYPE wasmcloud_provider_invocations_total counter")?;
+        for (key, stats) in &invocations {
+            writeln!(
+                out,
+                "wasmcloud_provider_invocations_total{{{}}} {}",
+                labels(key),
+                stats.count
+            )?;
+        }
+        writeln!(out, "# HELP wasmcloud_provider_invocation_panics_total Number of invocation handlers of the provider that panicked")?;
+        writeln!(
+            out,
+            "# TYPE wasmcloud_provider_invocation_panics_total counter"
+        )?;
+        for (key, stats) in &invocations {
+            writeln!(
+                out,
+                "wasmcloud_provider_invocation_panics_total{{{}}} {}",
+                labels(key),
+                stats.panics
+            )?;
+        }
+        writeln!(out, "# HELP wasmcloud_provider_invocation_duration_seconds Duration of the invocations handled by the provider")?;
+        writeln!(
+            out,
+            "# TYPE wasmcloud_provider_invocation_duration_seconds histogram"
+        )?;
+        for (key, stats) in &invocations {
+            let labels = labels(key);
+            let mut cumulative = 0;
+            for (le, count) in DURATION_BUCKETS.iter().zip(stats.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "wasmcloud_provider_invocation_duration_seconds_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+                )?;
+            }
+            writeln!(
+                out,
+                "wasmcloud_provider_invocation_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                stats.count
+            )?;
+            writeln!(
+                out,
+                "wasmcloud_provider_invocation_duration_seconds_sum{{{labels}}} {}",
+                stats.duration_sum
+            )?;
+            writeln!(
+                out,
+                "wasmcloud_provider_invocation_duration_seconds_count{{{labels}}} {}",
+                stats.count
+            )?;
+        }
+        writeln!(
+            out,
+            "# HELP wasmcloud_provider_uptime_seconds Time since the provider started"
+        )?;
+        writeln!(out, "# TYPE wasmcloud_provider_uptime_seconds gauge")?;
+        writeln!(
+            out,
+            "wasmcloud_provider_uptime_seconds {}",
+            self.started.elapsed().as_secs_f64()
+        )?;
+        writeln!(
+            out,
+            "# HELP wasmcloud_provider_links Number of links of the provider"
+        )?;
+        writeln!(out, "# TYPE wasmcloud_provider_links gauge")?;
+        writeln!(out, "wasmcloud_provider_links {links}")?;
+        writeln!(out, "# HELP wasmcloud_provider_invocations_in_flight Number of invocations currently handled by the provider")?;
+        writeln!(out, "# TYPE wasmcloud_provider_invocations_in_flight gauge")?;
+        writeln!(
+            out,
+            "wasmcloud_provider_invocations_in_flight {}",
+            self.in_flight()
+        )?;
+        writeln!(out, "# HELP wasmcloud_provider_stuck_invocations Number of in-flight invocations running for longer than expected")?;
+        writeln!(out, "# TYPE wasmcloud_provider_stuck_invocations gauge")?;
+        writeln!(
+            out,
+            "wasmcloud_provider_stuck_invocations {}",
+            self.stuck_invocations()
+        )?;
+        writeln!(out, "# HELP wasmcloud_provider_wrpc_client_cache_hits_total Number of wRPC clients returned from the client cache")?;
+        writeln!(
+            out,
+            "# TYPE wasmcloud_provider_wrpc_client_cache_hits_total counter"
+        )?;
+        writeln!(
+            out,
+            "wasmcloud_provider_wrpc_client_cache_hits_total {}",
+            self.wrpc_client_cache_hits.load(Ordering::Relaxed)
+        )?;
+        writeln!(out, "# HELP wasmcloud_provider_wrpc_client_cache_misses_total Number of wRPC clients created as none was cached")?;
+        writeln!(
+            out,
+            "# TYPE wasmcloud_provider_wrpc_client_cache_misses_total counter"
+        )?;
+        writeln!(
+            out,
+            "wasmcloud_provider_wrpc_client_cache_misses_total {}",
+            self.wrpc_client_cache_misses.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// Escape a label value of the Prometheus text exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Bind the metrics endpoint to `addr`, failing if the address is already in use
+pub async fn bind_metrics_listener(addr: &str) -> anyhow::Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind metrics endpoint to [{addr}]"))
+}
+
+/// Serve `metrics` to scrapers connecting to `listener` until `cancel` is cancelled. The number of
+/// links of the provider is looked up by `links` on every scrape.
+pub async fn serve_metrics<F, Fut>(
+    listener: TcpListener,
+    metrics: &'static ProviderMetrics,
+    links: F,
+    cancel: CancellationToken,
+) where
+    F: Fn() -> Fut,
+    Fut: Future<Output = usize>,
+{
+    loop {
+        let stream = tokio::select! {
+            () = cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!(?err, "failed to accept metrics scrape connection");
+                    continue;
+                }
+            },
+        };
+        let body = metrics.render(links().await);
+        tokio::spawn(async move {
+            if let Err(err) = respond(stream, body).await {
+                debug!(?err, "failed to serve metrics scrape");
+            }
+        });
+    }
+}
+
+/// Bind the metrics endpoint of the provider to `addr` and serve [`ProviderMetrics::global`] until
+/// `cancel` is cancelled. Failing to bind is logged and reported by [`metrics_listener_error`].
+pub(crate) async fn run_metrics_endpoint<F, Fut>(addr: String, links: F, cancel: CancellationToken)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = usize>,
+{
+    let listener = match bind_metrics_listener(&addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!(?err, "metrics endpoint is disabled");
+            set_metrics_listener_error(Some(format!("{err:#}")));
+            return;
+        }
+    };
+    set_metrics_listener_error(None);
+    debug!(addr, "serving metrics");
+    serve_metrics(listener, ProviderMetrics::global(), links, cancel).await;
+}
+
+/// Respond to a single HTTP request of a scraper with `body`, which is only served on `/metrics`
+async fn respond(mut stream: TcpStream, body: String) -> anyhow::Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 || head.len() + n > MAX_REQUEST_HEAD_LEN {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        anyhow::Ok(())
+    })
+    .await
+    .context("timed out reading request")??;
+
+    let request_line = String::from_utf8_lossy(&head);
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) if path.split('?').next() == Some("/metrics") => {
+            ("200 OK", PROMETHEUS_CONTENT_TYPE, body)
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    async fn scrape(addr: std::net::SocketAddr, path: &str) -> anyhow::Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_scrape_metrics() -> anyhow::Result<()> {
+        let metrics: &'static ProviderMetrics = Box::leak(Box::default());
+        for panicked in [false, true] {
+            let started = metrics.start_invocation();
+            metrics.finish_invocation("wasi:keyvalue/store@0.2.0-draft", "get", started, panicked);
+        }
+        let _pending = metrics.start_invocation();
+        metrics.start_stuck_invocation();
+        for hit in [false, true, true] {
+            metrics.record_wrpc_client_cache_lookup(hit);
+        }
+
+        let listener = bind_metrics_listener("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let cancel = CancellationToken::new();
+        let server = tokio::spawn(serve_metrics(
+            listener,
+            metrics,
+            || async { 2 },
+            cancel.clone(),
+        ));
+
+        let response = scrape(addr, "/metrics").await?;
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .context("invalid response")?;
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains(&format!("Content-Type: {PROMETHEUS_CONTENT_TYPE}")));
+        let labels = r#"instance="wasi:keyvalue/store@0.2.0-draft",function="get""#;
+        for line in [
+            format!("wasmcloud_provider_invocations_total{{{labels}}} 2"),
+            format!("wasmcloud_provider_invocation_panics_total{{{labels}}} 1"),
+            format!(
+                "wasmcloud_provider_invocation_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 2"
+            ),
+            format!("wasmcloud_provider_invocation_duration_seconds_count{{{labels}}} 2"),
+            "wasmcloud_provider_links 2".to_string(),
+            "wasmcloud_provider_invocations_in_flight 1".to_string(),
+            "wasmcloud_provider_stuck_invocations 1".to_string(),
+            "wasmcloud_provider_wrpc_client_cache_hits_total 2".to_string(),
+            "wasmcloud_provider_wrpc_client_cache_misses_total 1".to_string(),
+        ] {
+            assert!(
+                body.lines().any(|l| l == line),
+                "missing `{line}` in {body}"
+            );
+        }
+        assert!(body.contains("# TYPE wasmcloud_provider_uptime_seconds gauge"));
+
+        let response = scrape(addr, "/").await?;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+
+        cancel.cancel();
+        tokio::time::timeout(Duration::from_secs(1), server).await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bound_address_is_refused() -> anyhow::Result<()> {
+        let listener = bind_metrics_listener("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let err = bind_metrics_listener(&addr).await.unwrap_err();
+        assert!(format!("{err:#}").contains(&addr));
+
+        // The provider keeps running and reports the error instead
+        run_metrics_endpoint(addr.clone(), || async { 0 }, CancellationToken::new()).await;
+        assert!(metrics_listener_error().is_some_and(|err| err.contains(&addr)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label("a\nb"), r"a\nb");
+    }
+}
@@ -0,0 +1,322 @@
+//! Native library dependencies shipped alongside a provider
+//!
+//! Providers wrapping native libraries (e.g. image processing libraries) bundle shared libraries
+//! in their provider archive. The SDK locates them before [`Provider::init`](crate::Provider::init)
+//! runs, in the directory set with [`NATIVE_DEPS_DIR_CONFIG_KEY`] in the provider configuration or
+//! otherwise in the [`NATIVE_DEPS_DIR`] directory next to the provider binary. The directory holds
+//! a [`NATIVE_DEPS_MANIFEST`] listing every dependency with its SHA-256 digest, for example:
+//!
+//! ```json
+//! {
+//!   "libvips.so.42": "sha256:4f1c…",
+//!   "lib/libglib-2.0.so.0": "9a0d…"
+//! }
+//! ```
+//!
+//! Every listed dependency must exist with a matching digest, otherwise the provider fails to
+//! initialize. The directory is then prepended to the library search path of the processes the
+//! provider spawns, and the resolved paths are available to the provider through
+//! [`ProviderInitConfig::get_native_dependencies`](crate::ProviderInitConfig::get_native_dependencies).
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest as _, Sha256};
+use tracing::debug;
+
+/// Key of the provider configuration holding the path of the directory of native dependencies,
+/// relative to the directory of the provider binary unless absolute
+pub const NATIVE_DEPS_DIR_CONFIG_KEY: &str = "native_deps_dir";
+
+/// Directory of native dependencies next to the provider binary, used when
+/// [`NATIVE_DEPS_DIR_CONFIG_KEY`] is not set
+pub const NATIVE_DEPS_DIR: &str = "deps";
+
+/// Name of the manifest listing the native dependencies in their directory
+pub const NATIVE_DEPS_MANIFEST: &str = "manifest.json";
+
+/// Environment variable holding the search path of shared libraries on this platform
+#[cfg(target_os = "macos")]
+pub const LIBRARY_PATH_ENV: &str = "DYLD_LIBRARY_PATH";
+/// Environment variable holding the search path of shared libraries on this platform
+#[cfg(not(target_os = "macos"))]
+pub const LIBRARY_PATH_ENV: &str = "LD_LIBRARY_PATH";
+
+/// Errors that occur while loading native dependencies
+#[derive(Debug, thiserror::Error)]
+pub enum NativeDependencyError {
+    /// The manifest could not be read or is invalid
+    #[error("invalid native dependency manifest [{}]: {reason}", path.display())]
+    Manifest {
+        /// Path of the manifest
+        path: PathBuf,
+        /// Why the manifest is invalid
+        reason: String,
+    },
+    /// A dependency listed in the manifest does not exist
+    #[error("native dependency `{name}` is missing at [{}]", path.display())]
+    Missing {
+        /// Name of the dependency in the manifest
+        name: String,
+        /// Path the dependency was expected at
+        path: PathBuf,
+    },
+    /// A dependency listed in the manifest could not be read
+    #[error("failed to read native dependency `{name}` at [{}]: {source}", path.display())]
+    Read {
+        /// Name of the dependency in the manifest
+        name: String,
+        /// Path of the dependency
+        path: PathBuf,
+        /// Error reading the dependency
+        source: io::Error,
+    },
+    /// The digest of a dependency does not match the manifest
+    #[error(
+        "native dependency `{name}` at [{}] has digest sha256:{actual}, expected sha256:{expected}",
+        path.display()
+    )]
+    DigestMismatch {
+        /// Name of the dependency in the manifest
+        name: String,
+        /// Path of the dependency
+        path: PathBuf,
+        /// Hex-encoded SHA-256 digest listed in the manifest
+        expected: String,
+        /// Hex-encoded SHA-256 digest of the dependency
+        actual: String,
+    },
+}
+
+/// Verified native dependencies of a provider
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NativeDependencies {
+    dir: PathBuf,
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl NativeDependencies {
+    /// Load the native dependencies declared in the provider `config`, or found in the
+    /// [`NATIVE_DEPS_DIR`] directory next to the provider binary. Returns `None` if the provider
+    /// declares no native dependencies
+    pub fn from_config(
+        config: &HashMap<String, String>,
+    ) -> Result<Option<Self>, NativeDependencyError> {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        if let Some(dir) = config.get(NATIVE_DEPS_DIR_CONFIG_KEY) {
+            let dir = match &exe_dir {
+                Some(exe_dir) => exe_dir.join(dir),
+                None => PathBuf::from(dir),
+            };
+            return Self::load(dir).map(Some);
+        }
+        match exe_dir.map(|exe_dir| exe_dir.join(NATIVE_DEPS_DIR)) {
+            Some(dir) if dir.join(NATIVE_DEPS_MANIFEST).is_file() => Self::load(dir).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Load and verify the native dependencies listed in the manifest of `dir`
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, NativeDependencyError> {
+        let manifest_path = dir.as_ref().join(NATIVE_DEPS_MANIFEST);
+        let manifest_error = |reason: String| NativeDependencyError::Manifest {
+            path: manifest_path.clone(),
+            reason,
+        };
+        let dir = std::fs::canonicalize(dir.as_ref())
+            .map_err(|err| manifest_error(format!("failed to resolve directory: {err}")))?;
+        let manifest =
+            std::fs::read(&manifest_path).map_err(|err| manifest_error(err.to_string()))?;
+        let manifest: BTreeMap<String, String> =
+            serde_json::from_slice(&manifest).map_err(|err| manifest_error(err.to_string()))?;
+
+        let mut files = BTreeMap::new();
+        for (name, digest) in manifest {
+            // Dependencies must be within the directory
+            if !Path::new(&name)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(manifest_error(format!(
+                    "dependency `{name}` is not a relative path within the directory"
+                )));
+            }
+            let expected = digest.strip_prefix("sha256:").unwrap_or(&digest);
+            if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(manifest_error(format!(
+                    "digest of dependency `{name}` is not a SHA-256 digest"
+                )));
+            }
+            let path = dir.join(&name);
+            let actual = sha256_file(&path).map_err(|source| match source.kind() {
+                ErrorKind::NotFound => NativeDependencyError::Missing {
+                    name: name.clone(),
+                    path: path.clone(),
+                },
+                _ => NativeDependencyError::Read {
+                    name: name.clone(),
+                    path: path.clone(),
+                    source,
+                },
+            })?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(NativeDependencyError::DigestMismatch {
+                    name,
+                    path,
+                    expected: expected.to_ascii_lowercase(),
+                    actual,
+                });
+            }
+            debug!(name, path = %path.display(), "verified native dependency");
+            files.insert(name, path);
+        }
+        Ok(Self { dir, files })
+    }
+
+    /// Absolute path of the directory of native dependencies
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Absolute path of the dependency listed in the manifest as `name`
+    #[must_use]
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.files.get(name).map(PathBuf::as_path)
+    }
+
+    /// Names and absolute paths of all dependencies
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.files
+            .iter()
+            .map(|(name, path)| (name.as_str(), path.as_path()))
+    }
+
+    /// Value of [`LIBRARY_PATH_ENV`] with the dependency directory prepended to `current`
+    #[must_use]
+    pub fn library_path(&self, current: Option<OsString>) -> OsString {
+        let paths = std::iter::once(self.dir.clone())
+            .chain(current.iter().flat_map(std::env::split_paths))
+            .filter(|path| !path.as_os_str().is_empty());
+        // Joining only fails for paths containing the separator, which then cannot be searched
+        std::env::join_paths(paths).unwrap_or_else(|_| self.dir.clone().into_os_string())
+    }
+
+    /// Prepend the dependency directory to [`LIBRARY_PATH_ENV`] of this process, so that it is
+    /// inherited by the processes the provider spawns
+    pub fn export_library_path(&self) {
+        let path = self.library_path(std::env::var_os(LIBRARY_PATH_ENV));
+        std::env::set_var(LIBRARY_PATH_ENV, path);
+    }
+}
+
+/// Hex-encoded SHA-256 digest of the file at `path`
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn digest(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    fn write_manifest(dir: &Path, entries: &[(&str, String)]) {
+        let manifest: BTreeMap<_, _> = entries.iter().cloned().collect();
+        std::fs::write(
+            dir.join(NATIVE_DEPS_MANIFEST),
+            serde_json::to_vec(&manifest).expect("failed to serialize manifest"),
+        )
+        .expect("failed to write manifest");
+    }
+
+    #[test]
+    fn test_load_native_dependencies() {
+        let dir = tempfile::tempdir().expect("failed to create dir");
+        std::fs::create_dir(dir.path().join("lib")).expect("failed to create lib dir");
+        std::fs::write(dir.path().join("libgood.so"), b"good").expect("failed to write");
+        std::fs::write(dir.path().join("lib/libcorrupt.so"), b"corrupted")
+            .expect("failed to write");
+
+        write_manifest(
+            dir.path(),
+            &[
+                ("libgood.so", format!("sha256:{}", digest(b"good"))),
+                ("lib/libcorrupt.so", digest(b"original")),
+            ],
+        );
+        match NativeDependencies::load(dir.path()) {
+            Err(NativeDependencyError::DigestMismatch {
+                name,
+                expected,
+                actual,
+                ..
+            }) => {
+                assert_eq!(name, "lib/libcorrupt.so");
+                assert_eq!(expected, digest(b"original"));
+                assert_eq!(actual, digest(b"corrupted"));
+            }
+            res => panic!("corrupted dependency should be rejected, got {res:?}"),
+        }
+
+        write_manifest(
+            dir.path(),
+            &[
+                ("libgood.so", digest(b"good")),
+                ("libmissing.so", digest(b"missing")),
+            ],
+        );
+        assert!(matches!(
+            NativeDependencies::load(dir.path()),
+            Err(NativeDependencyError::Missing { name, .. }) if name == "libmissing.so"
+        ));
+
+        write_manifest(dir.path(), &[("../libgood.so", digest(b"good"))]);
+        assert!(matches!(
+            NativeDependencies::load(dir.path()),
+            Err(NativeDependencyError::Manifest { .. })
+        ));
+
+        write_manifest(dir.path(), &[("libgood.so", digest(b"good"))]);
+        let deps = NativeDependencies::load(dir.path()).expect("dependencies should load");
+        let resolved = std::fs::canonicalize(dir.path()).expect("failed to canonicalize");
+        assert_eq!(deps.dir(), resolved);
+        assert_eq!(
+            deps.path("libgood.so"),
+            Some(resolved.join("libgood.so").as_path())
+        );
+        assert!(deps.path("lib/libcorrupt.so").is_none());
+    }
+
+    #[test]
+    fn test_library_path() {
+        let dir = tempfile::tempdir().expect("failed to create dir");
+        write_manifest(dir.path(), &[]);
+        let deps = NativeDependencies::load(dir.path()).expect("dependencies should load");
+
+        assert_eq!(
+            deps.library_path(None),
+            deps.dir().as_os_str().to_os_string()
+        );
+        let current = std::env::join_paths(["/usr/lib", "/opt/lib"]).expect("valid paths");
+        let paths: Vec<_> = std::env::split_paths(&deps.library_path(Some(current))).collect();
+        assert_eq!(
+            paths,
+            [
+                deps.dir().to_path_buf(),
+                PathBuf::from("/usr/lib"),
+                PathBuf::from("/opt/lib")
+            ]
+        );
+    }
+}
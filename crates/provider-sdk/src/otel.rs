@@ -1,5 +1,6 @@
 /// Instrument a given [`provider_sdk::Context`], injecting current `tracing`-generated metadata
-/// if one isn't present.
+/// if one isn't present. The baggage of the context is attached as well, so that it is propagated
+/// on outgoing invocations.
 ///
 /// This functionality is exposed as a macro since the context for trace injection
 /// should be at the *call site* of this macro (ex. inside some method annotated with `#[instrument]`)
@@ -8,7 +9,9 @@
 #[macro_export]
 macro_rules! propagate_trace_for_ctx {
     ($ctx:ident) => {{
-        use $crate::wasmcloud_tracing::context::{attach_span_context, TraceContextInjector};
+        use $crate::wasmcloud_tracing::context::{
+            attach_span_context_with_baggage, current_baggage, TraceContextInjector,
+        };
         let trace_ctx = match $ctx {
             Some(ref ctx) if !ctx.tracing.is_empty() => ctx
                 .tracing
@@ -21,6 +24,10 @@ macro_rules! propagate_trace_for_ctx {
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect(),
         };
-        attach_span_context(&trace_ctx);
+        let baggage = match $ctx {
+            Some(ref ctx) => ctx.baggage.clone(),
+            None => current_baggage(),
+        };
+        attach_span_context_with_baggage(&trace_ctx, &baggage);
     }};
 }
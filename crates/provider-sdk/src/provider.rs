@@ -3,42 +3,82 @@ use core::fmt::Formatter;
 use core::future::Future;
 
 use core::time::Duration;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::BufRead;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{bail, Result};
 use async_nats::subject::ToSubject;
 use async_nats::HeaderMap;
 use base64::Engine;
+use bytes::Bytes;
+use futures::future::{self, Either};
 use futures::StreamExt;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
-use tokio::task::spawn_blocking;
-use tokio::{select, spawn, try_join};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Notify, RwLock};
+use tokio::task::{spawn_blocking, AbortHandle, JoinHandle};
+use tokio::{select, spawn};
 use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
 use wasmcloud_core::nats::convert_header_map_to_hashmap;
-use wasmcloud_core::rpc::{health_subject, link_del_subject, link_put_subject, shutdown_subject};
+use wasmcloud_core::rpc::{
+    config_update_subject, health_subject, host_labels_changed_subject, link_del_subject,
+    link_put_subject, prepare_shutdown_subject, shutdown_subject, ConfigRejection,
+    ConfigUpdateOutcome, ConfigUpdateRequest, PrepareShutdownRequest, SHUTDOWN_ALL_HOSTS,
+    SHUTDOWN_REASON_UPDATE, SHUTDOWN_SCOPE_LATTICE,
+};
+use wasmcloud_core::wrpc::{format_payload_size, PayloadTooLarge};
 use wasmcloud_core::{
-    HealthCheckRequest, HealthCheckResponse, HostData, InterfaceLinkDefinition, LatticeTarget,
+    DrainReport, HealthCheckRequest, HealthCheckResponse, HostData, InterfaceLinkDefinition,
+    LatticeTarget,
 };
 
 #[cfg(feature = "otel")]
 use wasmcloud_core::TraceContext;
 #[cfg(feature = "otel")]
-use wasmcloud_tracing::context::attach_span_context;
+use wasmcloud_tracing::context::attach_span_context_with_baggage;
 
-use crate::error::{ProviderInitError, ProviderInitResult};
+use crate::baggage::{parse_baggage, BAGGAGE_HEADER};
+use crate::cache;
+use crate::client_cache::{ClientCache, ClientCacheStats};
+use crate::config_sources::ConfigSources;
+use crate::control_auth::{is_authentic, ControlVerifier};
+use crate::dedup::DedupWindow;
+use crate::error::{
+    ProviderInitError, ProviderInitResult, ProviderInvocationError, ProviderInvocationResult,
+};
+use crate::host_data::{parse_host_data, HostCapabilities};
+use crate::isolation::{max_consecutive_panics_from_config, set_max_consecutive_panics};
+use crate::link_cache::{LinkCache, LINK_CACHE_GRACE_PERIOD};
+use crate::link_readiness::LinkReadiness;
+use crate::log_forwarding::{
+    log_forwarding, LogForwarder, FORWARD_LOGS_CONFIG_KEY, FORWARD_LOGS_LEVEL_CONFIG_KEY,
+};
+use crate::metrics::{
+    metrics_listen_addr_from_config, metrics_listener_error, run_metrics_endpoint, ProviderMetrics,
+};
+use crate::native_deps::NativeDependencies;
+use crate::sampling::{SdkSpanSampler, OTEL_SAMPLING_RATIO_CONFIG_KEY};
+use crate::serve::{set_inbound_limits, ServeLimits};
+use crate::single_instance::{
+    single_instance_from_config, LockAcquisition, ProviderLock, PROVIDER_LOCK_TTL,
+};
+use crate::state_snapshot::{state_snapshot_max_age_from_config, StateSnapshotStore};
+use crate::tasks::{TaskGroup, TASK_DRAIN_TIMEOUT};
 use crate::{
-    with_connection_event_logging, Context, LinkConfig, Provider, WrpcClient, DEFAULT_NATS_ADDR,
+    log_connection_event, Context, LinkConfig, LinkKey, Provider, ShutdownReason, WrpcClient,
+    DEFAULT_NATS_ADDR,
 };
 
 /// Name of the header that should be passed for invocations that identifies the source
 const WRPC_SOURCE_ID_HEADER_NAME: &str = "source-id";
+/// Name of the header identifying the link over which an invocation is made
+const WRPC_LINK_NAME_HEADER_NAME: &str = "link-name";
 
 static HOST_DATA: OnceCell<HostData> = OnceCell::new();
-static CONNECTION: OnceCell<ProviderConnection> = OnceCell::new();
+static HOST_DATA_WARNINGS: OnceCell<Vec<String>> = OnceCell::new();
+pub(crate) static CONNECTION: OnceCell<ProviderConnection> = OnceCell::new();
 
 /// Retrieves the currently configured connection to the lattice. DO NOT call this method until
 /// after the provider is running (meaning [`start_provider`] or [`run_provider`] have been called)
@@ -90,13 +130,15 @@ fn _load_host_data() -> ProviderInitResult<HostData> {
              {e}"
         ))
         })?;
-    let host_data: HostData = serde_json::from_slice(&bytes).map_err(|e| {
+    let (host_data, warnings) = parse_host_data(&bytes).map_err(|e| {
         ProviderInitError::Initialization(format!(
             "parsing host data: {}:\n{}",
             e,
             String::from_utf8_lossy(&bytes)
         ))
     })?;
+    // Logging is only configured once host data is loaded, so warnings are logged later
+    let _ = HOST_DATA_WARNINGS.set(warnings);
     Ok(host_data)
 }
 
@@ -104,50 +146,163 @@ pub type QuitSignal = broadcast::Receiver<()>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct ShutdownMessage {
-    /// The ID of the host that sent the message
+    /// The ID of the host that sent the message, or [`SHUTDOWN_ALL_HOSTS`] to stop every instance
+    /// of the provider in the lattice
+    #[serde(default)]
     pub host_id: String,
+    /// Why the host stops the provider, e.g. [`SHUTDOWN_REASON_UPDATE`]
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// [`SHUTDOWN_SCOPE_LATTICE`] to stop every instance of the provider in the lattice
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Link name of the instances to stop, all of them if unset
+    #[serde(default)]
+    pub link_name: Option<String>,
+}
+
+impl ShutdownMessage {
+    /// Whether the request stops every instance of the provider in the lattice
+    fn is_lattice_wide(&self) -> bool {
+        self.host_id == SHUTDOWN_ALL_HOSTS || self.scope.as_deref() == Some(SHUTDOWN_SCOPE_LATTICE)
+    }
+
+    /// Whether the request targets the instance of the provider running on `host_id` with
+    /// `link_name`. Requests target the instance of a single host unless they are lattice-wide
+    fn targets(&self, host_id: &str, link_name: &str) -> bool {
+        (self.is_lattice_wide() || self.host_id == host_id)
+            && self
+                .link_name
+                .as_deref()
+                .map_or(true, |name| name == link_name)
+    }
+
+    fn reason(&self) -> ShutdownReason {
+        if self.is_lattice_wide() {
+            ShutdownReason::LatticeRequested
+        } else if self.reason.as_deref() == Some(SHUTDOWN_REASON_UPDATE) {
+            ShutdownReason::Update {
+                host_id: self.host_id.clone(),
+            }
+        } else {
+            ShutdownReason::HostRequested {
+                host_id: self.host_id.clone(),
+            }
+        }
+    }
+}
+
+/// Identity of the running instance of the provider, which shutdown requests are matched against
+#[derive(Debug, Clone, Copy)]
+struct ProviderInstance {
+    host_id: &'static str,
+    instance_id: &'static str,
+    link_name: &'static str,
+}
+
+/// Acknowledgement sent back to the host once the provider has shut down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShutdownAck {
+    pub message: String,
+    /// Human-readable reason for which the provider shut down
+    pub reason: String,
+    /// Whether the provider would like to be restarted
+    pub restart: bool,
+    /// ID of the host running the instance acknowledging the shutdown, so that the responses to
+    /// lattice-wide requests can be told apart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_id: Option<String>,
+    /// ID of the instance acknowledging the shutdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+}
+
+impl From<&ShutdownReason> for ShutdownAck {
+    fn from(reason: &ShutdownReason) -> Self {
+        Self {
+            message: "shutting down".to_string(),
+            reason: reason.to_string(),
+            restart: reason.restart_requested(),
+            host_id: None,
+            instance_id: None,
+        }
+    }
+}
+
+/// `labels_changed` lattice event published by hosts, see [`host_labels_changed_subject`]
+#[derive(Debug, Deserialize)]
+struct LabelsChangedEvent {
+    data: LabelsChanged,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelsChanged {
+    host_id: String,
+    labels: HashMap<String, String>,
+}
+
+/// Parse a `labels_changed` event, returning the new labels if the event was published by the
+/// host with ID `host_id`
+fn parse_labels_changed(payload: &[u8], host_id: &str) -> Option<HashMap<String, String>> {
+    match serde_json::from_slice::<LabelsChangedEvent>(payload) {
+        Ok(LabelsChangedEvent { data }) if data.host_id == host_id => Some(data.labels),
+        Ok(_) => None,
+        Err(err) => {
+            warn!(%err, "received invalid labels_changed event");
+            None
+        }
+    }
 }
 
 #[doc(hidden)]
 /// Process subscription, until closed or exhausted, or value is received on the channel.
-/// `sub` is a mutable Subscriber (regular or queue subscription)
+/// `sub` is a mutable Subscriber (regular or queue subscription), owned by the task running the
+/// loop so that aborting the task unsubscribes
 /// `channel` may be either tokio mpsc::Receiver or broadcast::Receiver, and is considered signaled
 /// when a value is sent or the chanel is closed.
 /// `msg` is the variable name to be used in the handler
 /// `on_item` is an async handler
 macro_rules! process_until_quit {
     ($sub:ident, $channel:ident, $msg:ident, $on_item:tt) => {
-        spawn(async move {
-            loop {
-                select! {
-                    _ = $channel.recv() => {
-                        let _ = $sub.unsubscribe().await;
-                        break;
-                    },
-                    __msg = $sub.next() => {
-                        match __msg {
-                            None => break,
-                            Some($msg) => $on_item
-                        }
+        loop {
+            select! {
+                _ = $channel.recv() => {
+                    let _ = $sub.unsubscribe().await;
+                    break;
+                },
+                __msg = $sub.next() => {
+                    match __msg {
+                        None => break,
+                        Some($msg) => $on_item
                     }
                 }
             }
-        })
+        }
     };
 }
 
+/// Subscribe to `subject`, naming it in the error if the subscription fails
+async fn subscribe(
+    nats: &async_nats::Client,
+    subject: String,
+) -> ProviderInitResult<async_nats::Subscriber> {
+    nats.subscribe(subject.clone()).await.map_err(|e| {
+        ProviderInitError::Initialization(format!("failed to subscribe to [{subject}]: {e}"))
+    })
+}
+
 async fn subscribe_health(
     nats: Arc<async_nats::Client>,
     mut quit: broadcast::Receiver<()>,
     lattice: &str,
     provider_key: &str,
-) -> ProviderInitResult<mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>>
-{
-    let mut sub = nats
-        .subscribe(health_subject(lattice, provider_key))
-        .await?;
+) -> ProviderInitResult<(
+    mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>,
+    JoinHandle<()>,
+)> {
+    let mut sub = subscribe(&nats, health_subject(lattice, provider_key)).await?;
     let (health_tx, health_rx) = mpsc::channel(1);
-    spawn({
+    let task = spawn({
         let nats = Arc::clone(&nats);
         async move {
             process_until_quit!(sub, quit, msg, {
@@ -176,24 +331,34 @@ async fn subscribe_health(
         }
         .instrument(tracing::debug_span!("subscribe_health"))
     });
-    Ok(health_rx)
+    Ok((health_rx, task))
 }
 
+/// Subscribe to shutdown requests. Requests target the instance of the provider running on a single
+/// host, unless they are lattice-wide (see [`SHUTDOWN_ALL_HOSTS`] and [`SHUTDOWN_SCOPE_LATTICE`]),
+/// and can be narrowed to the instances with a given link name
 async fn subscribe_shutdown(
     nats: Arc<async_nats::Client>,
     quit: broadcast::Sender<()>,
     lattice: &str,
     provider_key: &str,
-    host_id: &'static str,
-) -> ProviderInitResult<mpsc::Receiver<oneshot::Sender<()>>> {
-    let mut sub = nats
-        .subscribe(shutdown_subject(lattice, provider_key, "default"))
-        .await?;
+    instance: ProviderInstance,
+    verifier: Option<Arc<ControlVerifier>>,
+) -> ProviderInitResult<(
+    mpsc::Receiver<(ShutdownReason, oneshot::Sender<()>)>,
+    JoinHandle<()>,
+)> {
+    let mut sub = subscribe(&nats, shutdown_subject(lattice, provider_key, "default")).await?;
     let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
-    spawn({
+    let task = spawn({
         async move {
             loop {
                 let msg = sub.next().await;
+                if let Some(msg) = &msg {
+                    if !is_authentic(verifier.as_deref(), msg) {
+                        continue;
+                    }
+                }
                 // Check if we really need to shut down
                 if let Some(async_nats::Message {
                     reply: Some(reply_to),
@@ -201,15 +366,19 @@ async fn subscribe_shutdown(
                     ..
                 }) = msg
                 {
-                    let ShutdownMessage {
-                        host_id: ref req_host_id,
-                    } = serde_json::from_slice(&payload).unwrap_or_default();
-                    if req_host_id == host_id {
-                        info!("Received termination signal and stopping");
+                    let req: ShutdownMessage = serde_json::from_slice(&payload).unwrap_or_default();
+                    if req.targets(instance.host_id, instance.link_name) {
+                        let reason = req.reason();
+                        info!(%reason, "Received termination signal and stopping");
+                        let ack = ShutdownAck {
+                            host_id: Some(instance.host_id.to_string()),
+                            instance_id: Some(instance.instance_id.to_string()),
+                            ..ShutdownAck::from(&reason)
+                        };
                         // Tell provider to shutdown - before we shut down nats subscriptions,
                         // in case it needs to do any message passing during shutdown
                         let (tx, rx) = oneshot::channel();
-                        match shutdown_tx.send(tx).await {
+                        match shutdown_tx.send((reason, tx)).await {
                             Ok(()) => {
                                 if let Err(err) = rx.await {
                                     error!(%err, "failed to await shutdown");
@@ -217,8 +386,13 @@ async fn subscribe_shutdown(
                             }
                             Err(err) => error!(%err, "failed to send shutdown"),
                         }
-                        if let Err(err) = nats.publish(reply_to, "shutting down".into()).await {
-                            warn!(%err, "failed to send shutdown ack");
+                        match serde_json::to_vec(&ack) {
+                            Ok(ack) => {
+                                if let Err(err) = nats.publish(reply_to, ack.into()).await {
+                                    warn!(%err, "failed to send shutdown ack");
+                                }
+                            }
+                            Err(err) => error!(%err, "failed to serialize shutdown ack"),
                         }
                         // unsubscribe from shutdown topic
                         if let Err(err) = sub.unsubscribe().await {
@@ -230,13 +404,151 @@ async fn subscribe_shutdown(
                         }
                         break;
                     }
-                    trace!("Ignoring termination signal (request targeted for different host)");
+                    trace!("Ignoring termination signal (request targeted for different instance)");
                 }
             }
         }
         .instrument(tracing::debug_span!("shutdown_subscriber"))
     });
-    Ok(shutdown_rx)
+    Ok((shutdown_rx, task))
+}
+
+/// Subscribe to requests to prepare for shutdown, sent by `wash stop --drain` before the provider
+/// is stopped. Requests targeted at a different host are ignored
+async fn subscribe_prepare_shutdown(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    lattice: &str,
+    provider_key: &str,
+    host_id: &'static str,
+    verifier: Option<Arc<ControlVerifier>>,
+) -> ProviderInitResult<(
+    mpsc::Receiver<(Duration, oneshot::Sender<DrainReport>)>,
+    JoinHandle<()>,
+)> {
+    let mut sub = subscribe(
+        &nats,
+        prepare_shutdown_subject(lattice, provider_key, "default"),
+    )
+    .await?;
+    let (prepare_tx, prepare_rx) = mpsc::channel(1);
+    let task = spawn(
+        async move {
+            process_until_quit!(sub, quit, msg, {
+                if !is_authentic(verifier.as_deref(), &msg) {
+                    continue;
+                }
+                let Some(reply_to) = msg.reply else {
+                    continue;
+                };
+                let req: PrepareShutdownRequest = match serde_json::from_slice(&msg.payload) {
+                    Ok(req) => req,
+                    Err(err) => {
+                        error!(%err, "received invalid prepare shutdown request");
+                        continue;
+                    }
+                };
+                if req.host_id != host_id {
+                    trace!("Ignoring prepare shutdown request targeted for different host");
+                    continue;
+                }
+                let (tx, rx) = oneshot::channel();
+                if let Err(err) = prepare_tx
+                    .send((Duration::from_millis(req.timeout_ms), tx))
+                    .await
+                {
+                    error!(%err, "failed to send prepare shutdown request");
+                    continue;
+                }
+                match rx.await.as_ref().map(serde_json::to_vec) {
+                    Err(err) => {
+                        error!(%err, "failed to receive drain report");
+                    }
+                    Ok(Ok(report)) => {
+                        if let Err(err) = nats.publish(reply_to, report.into()).await {
+                            error!(%err, "failed sending drain report");
+                        }
+                    }
+                    Ok(Err(err)) => {
+                        error!(%err, "failed serializing DrainReport");
+                    }
+                }
+            });
+        }
+        .instrument(tracing::debug_span!("subscribe_prepare_shutdown")),
+    );
+    Ok((prepare_rx, task))
+}
+
+/// Subscribe to configuration updates pushed by the host. Requests targeted at a different host
+/// are ignored, the outcome of the others is sent to the reply subject, if any
+async fn subscribe_config_update(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    lattice: &str,
+    provider_key: &str,
+    host_id: &'static str,
+    verifier: Option<Arc<ControlVerifier>>,
+) -> ProviderInitResult<(
+    mpsc::Receiver<(
+        HashMap<String, String>,
+        oneshot::Sender<ConfigUpdateOutcome>,
+    )>,
+    JoinHandle<()>,
+)> {
+    let mut sub = subscribe(
+        &nats,
+        config_update_subject(lattice, provider_key, "default"),
+    )
+    .await?;
+    let (config_tx, config_rx) = mpsc::channel(1);
+    let task = spawn(
+        async move {
+            process_until_quit!(sub, quit, msg, {
+                if !is_authentic(verifier.as_deref(), &msg) {
+                    continue;
+                }
+                let req: ConfigUpdateRequest = match serde_json::from_slice(&msg.payload) {
+                    Ok(req) => req,
+                    Err(err) => {
+                        error!(%err, "received invalid config update request");
+                        continue;
+                    }
+                };
+                if req.host_id != host_id {
+                    trace!("Ignoring config update request targeted for different host");
+                    continue;
+                }
+                let (tx, rx) = oneshot::channel();
+                if let Err(err) = config_tx.send((req.config, tx)).await {
+                    error!(%err, "failed to send config update request");
+                    continue;
+                }
+                let outcome = match rx.await {
+                    Ok(outcome) => outcome,
+                    Err(err) => {
+                        error!(%err, "failed to receive config update outcome");
+                        continue;
+                    }
+                };
+                let Some(reply_to) = msg.reply else {
+                    continue;
+                };
+                match serde_json::to_vec(&outcome) {
+                    Ok(outcome) => {
+                        if let Err(err) = nats.publish(reply_to, outcome.into()).await {
+                            error!(%err, "failed sending config update outcome");
+                        }
+                    }
+                    Err(err) => {
+                        error!(%err, "failed serializing ConfigUpdateOutcome");
+                    }
+                }
+            });
+        }
+        .instrument(tracing::debug_span!("subscribe_config_update")),
+    );
+    Ok((config_rx, task))
 }
 
 async fn subscribe_link_put(
@@ -244,13 +556,20 @@ async fn subscribe_link_put(
     mut quit: broadcast::Receiver<()>,
     lattice: &str,
     provider_key: &str,
-) -> ProviderInitResult<mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>> {
-    let mut sub = nats
-        .subscribe(link_put_subject(lattice, provider_key))
-        .await?;
+    verifier: Option<Arc<ControlVerifier>>,
+    link_readiness: LinkReadiness,
+) -> ProviderInitResult<(
+    mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
+    JoinHandle<()>,
+)> {
+    let mut sub = subscribe(&nats, link_put_subject(lattice, provider_key)).await?;
     let (link_put_tx, link_put_rx) = mpsc::channel(1);
-    spawn(async move {
+    let provider_key = provider_key.to_string();
+    let task = spawn(async move {
         process_until_quit!(sub, quit, msg, {
+            if !is_authentic(verifier.as_deref(), &msg) {
+                continue;
+            }
             match serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload) {
                 Ok(ld) => {
                     let span = tracing::Span::current();
@@ -263,6 +582,9 @@ async fn subscribe_link_put(
                         &tracing::field::display(&ld.interfaces.join(",")),
                     );
                     span.record("link_name", &tracing::field::display(&ld.name));
+                    // Park invocations from the source until the link is processed
+                    let _pending = (ld.target == provider_key)
+                        .then(|| link_readiness.begin(ld.source_id.clone()));
                     let (tx, rx) = oneshot::channel();
                     if let Err(err) = link_put_tx.send((ld, tx)).await {
                         error!(%err, "failed to send link put request");
@@ -278,7 +600,7 @@ async fn subscribe_link_put(
             }
         });
     });
-    Ok(link_put_rx)
+    Ok((link_put_rx, task))
 }
 
 async fn subscribe_link_del(
@@ -286,15 +608,22 @@ async fn subscribe_link_del(
     mut quit: broadcast::Receiver<()>,
     lattice: &str,
     provider_key: &str,
-) -> ProviderInitResult<mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>> {
-    let subject = link_del_subject(lattice, provider_key).to_subject();
+    verifier: Option<Arc<ControlVerifier>>,
+) -> ProviderInitResult<(
+    mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
+    JoinHandle<()>,
+)> {
+    let subject = link_del_subject(lattice, provider_key);
     debug!(%subject, "subscribing for link del");
-    let mut sub = nats.subscribe(subject.clone()).await?;
-    let (link_del_tx, link_del_rx) = mpsc::channel(1);
     let span = tracing::trace_span!("subscribe_link_del", %subject);
-    spawn(
+    let mut sub = subscribe(&nats, subject).await?;
+    let (link_del_tx, link_del_rx) = mpsc::channel(1);
+    let task = spawn(
         async move {
             process_until_quit!(sub, quit, msg, {
+                if !is_authentic(verifier.as_deref(), &msg) {
+                    continue;
+                }
                 if let Ok(ld) = serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload) {
                     let (tx, rx) = oneshot::channel();
                     if let Err(err) = link_del_tx.send((ld, tx)).await {
@@ -309,14 +638,351 @@ async fn subscribe_link_del(
         }
         .instrument(span),
     );
-    Ok(link_del_rx)
+    Ok((link_del_rx, task))
+}
+
+/// Subscribe to label changes of the host running the provider. Hosts publish these as lattice
+/// events, which are received as long as the provider's lattice RPC connection can see them
+/// (i.e. the host uses the same NATS cluster for RPC and control interface traffic)
+async fn subscribe_host_labels(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    lattice: &str,
+    host_id: &'static str,
+) -> ProviderInitResult<(mpsc::Receiver<HashMap<String, String>>, JoinHandle<()>)> {
+    let mut sub = subscribe(&nats, host_labels_changed_subject(lattice)).await?;
+    let (host_labels_tx, host_labels_rx) = mpsc::channel(1);
+    let task = spawn(
+        async move {
+            process_until_quit!(sub, quit, msg, {
+                if let Some(labels) = parse_labels_changed(&msg.payload, host_id) {
+                    if let Err(err) = host_labels_tx.send(labels).await {
+                        error!(%err, "failed to send host labels");
+                    }
+                }
+            });
+        }
+        .instrument(tracing::trace_span!("subscribe_host_labels")),
+    );
+    Ok((host_labels_rx, task))
+}
+
+/// Subjects the NATS server denied the provider subscribing to. Permission violations are reported
+/// asynchronously as server errors, the subscribe request itself succeeds.
+#[derive(Clone)]
+pub(crate) struct SubscriptionViolations(Arc<watch::Sender<Vec<String>>>);
+
+impl Default for SubscriptionViolations {
+    fn default() -> Self {
+        let (denied, _) = watch::channel(Vec::new());
+        Self(Arc::new(denied))
+    }
+}
+
+impl SubscriptionViolations {
+    /// Record the subject of `event` if it reports a denied subscription
+    fn record(&self, event: &async_nats::Event) {
+        let async_nats::Event::ServerError(async_nats::ServerError::Other(err)) = event else {
+            return;
+        };
+        if let Some(subject) = denied_subscription_subject(err) {
+            self.0
+                .send_modify(|denied| denied.push(subject.to_string()));
+        }
+    }
+
+    /// Whether subscribing to `subject` was denied
+    fn is_denied(&self, subject: &str) -> bool {
+        self.0.borrow().iter().any(|s| s == subject)
+    }
+
+    /// Receiver notified of every subject denied from now on
+    fn subscribe(&self) -> watch::Receiver<Vec<String>> {
+        self.0.subscribe()
+    }
+}
+
+/// Subject of a `Permissions Violation for Subscription to "<subject>"` server error
+fn denied_subscription_subject(err: &str) -> Option<&str> {
+    const VIOLATION: &str = "permissions violation for subscription to ";
+    let start = err.to_ascii_lowercase().find(VIOLATION)? + VIOLATION.len();
+    let subject = err[start..].trim_start_matches(['"', '\'']);
+    subject
+        .split(['"', '\'', ' '])
+        .next()
+        .filter(|s| !s.is_empty())
 }
 
 pub(crate) struct ProviderCommandReceivers {
     pub health: mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>,
-    pub shutdown: mpsc::Receiver<oneshot::Sender<()>>,
+    pub shutdown: mpsc::Receiver<(ShutdownReason, oneshot::Sender<()>)>,
+    pub prepare_shutdown: mpsc::Receiver<(Duration, oneshot::Sender<DrainReport>)>,
+    pub config_update: mpsc::Receiver<(
+        HashMap<String, String>,
+        oneshot::Sender<ConfigUpdateOutcome>,
+    )>,
     pub link_put: mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
     pub link_del: mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
+    pub host_labels: mpsc::Receiver<HashMap<String, String>>,
+    /// Tasks forwarding the messages of each subscription to the receivers
+    pub tasks: Vec<JoinHandle<()>>,
+}
+
+impl ProviderCommandReceivers {
+    /// Subscribe to the commands sent to the provider. If a core subscription fails or is denied by
+    /// the NATS server, the subscriptions already made are torn down before the error, naming the
+    /// failed subject, is returned. Optional subscriptions (shutdown preparation, config updates
+    /// and host label changes) only disable their feature with a warning.
+    #[allow(clippy::too_many_arguments)]
+    async fn new(
+        nats: &Arc<async_nats::Client>,
+        violations: &SubscriptionViolations,
+        quit_tx: &broadcast::Sender<()>,
+        lattice: &str,
+        provider_key: &str,
+        instance: ProviderInstance,
+        verifier: Option<Arc<ControlVerifier>>,
+        link_readiness: LinkReadiness,
+    ) -> ProviderInitResult<Self> {
+        let host_id = instance.host_id;
+        let mut tasks = Vec::with_capacity(8);
+        let mut subscriptions = Subscriptions::default();
+        let subscribed = async {
+            let (health, task) =
+                subscribe_health(Arc::clone(nats), quit_tx.subscribe(), lattice, provider_key)
+                    .await?;
+            subscriptions.core(health_subject(lattice, provider_key), &task);
+            tasks.push(task);
+            let (shutdown, task) = subscribe_shutdown(
+                Arc::clone(nats),
+                quit_tx.clone(),
+                lattice,
+                provider_key,
+                instance,
+                verifier.clone(),
+            )
+            .await?;
+            subscriptions.core(shutdown_subject(lattice, provider_key, "default"), &task);
+            tasks.push(task);
+            let prepare_shutdown = subscriptions.optional(
+                &mut tasks,
+                "shutdown preparation",
+                prepare_shutdown_subject(lattice, provider_key, "default"),
+                subscribe_prepare_shutdown(
+                    Arc::clone(nats),
+                    quit_tx.subscribe(),
+                    lattice,
+                    provider_key,
+                    host_id,
+                    verifier.clone(),
+                )
+                .await,
+            );
+            let config_update = subscriptions.optional(
+                &mut tasks,
+                "config updates",
+                config_update_subject(lattice, provider_key, "default"),
+                subscribe_config_update(
+                    Arc::clone(nats),
+                    quit_tx.subscribe(),
+                    lattice,
+                    provider_key,
+                    host_id,
+                    verifier.clone(),
+                )
+                .await,
+            );
+            let (link_put, task) = subscribe_link_put(
+                Arc::clone(nats),
+                quit_tx.subscribe(),
+                lattice,
+                provider_key,
+                verifier.clone(),
+                link_readiness,
+            )
+            .await?;
+            subscriptions.core(link_put_subject(lattice, provider_key), &task);
+            tasks.push(task);
+            let (link_del, task) = subscribe_link_del(
+                Arc::clone(nats),
+                quit_tx.subscribe(),
+                lattice,
+                provider_key,
+                verifier,
+            )
+            .await?;
+            subscriptions.core(link_del_subject(lattice, provider_key), &task);
+            tasks.push(task);
+            let host_labels = subscriptions.optional(
+                &mut tasks,
+                "host label changes",
+                host_labels_changed_subject(lattice),
+                subscribe_host_labels(Arc::clone(nats), quit_tx.subscribe(), lattice, host_id)
+                    .await,
+            );
+
+            // Denied subscriptions are only reported by the server once they are flushed. Denials
+            // reported by then fail initialization, later ones are handled by `watch_violations`
+            nats.flush().await.map_err(|e| {
+                ProviderInitError::Initialization(format!("failed to flush subscriptions: {e}"))
+            })?;
+            if let Some(subject) = subscriptions.denied_core(violations) {
+                return Err(ProviderInitError::Initialization(format!(
+                    "failed to subscribe to [{subject}]: permission denied"
+                )));
+            }
+            Ok((
+                health,
+                shutdown,
+                prepare_shutdown,
+                config_update,
+                link_put,
+                link_del,
+                host_labels,
+            ))
+        }
+        .await;
+        match subscribed {
+            Ok((
+                health,
+                shutdown,
+                prepare_shutdown,
+                config_update,
+                link_put,
+                link_del,
+                host_labels,
+            )) => {
+                tasks.push(watch_violations(
+                    violations.subscribe(),
+                    quit_tx.subscribe(),
+                    subscriptions,
+                ));
+                Ok(Self {
+                    health,
+                    shutdown,
+                    prepare_shutdown,
+                    config_update,
+                    link_put,
+                    link_del,
+                    host_labels,
+                    tasks,
+                })
+            }
+            Err(err) => {
+                close_subscriptions(nats, tasks).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Tear down the subscriptions, so that no subscription task outlives a failed initialization
+    pub async fn close(self, nats: &async_nats::Client) {
+        close_subscriptions(nats, self.tasks).await;
+    }
+}
+
+/// Subscriptions of the provider, by subject, so that they can be stopped once the NATS server
+/// reports denying them
+#[derive(Default)]
+struct Subscriptions {
+    /// Subscriptions the provider cannot run without
+    core: Vec<(String, AbortHandle)>,
+    /// Subscriptions of optional features, along with the name of the feature
+    optional: Vec<(String, &'static str, AbortHandle)>,
+}
+
+impl Subscriptions {
+    fn core(&mut self, subject: String, task: &JoinHandle<()>) {
+        self.core.push((subject, task.abort_handle()));
+    }
+
+    /// Receiver of the optional `feature` subscribed to `subject`. If the subscription failed, the
+    /// feature is disabled with a warning and the returned receiver is closed.
+    fn optional<T>(
+        &mut self,
+        tasks: &mut Vec<JoinHandle<()>>,
+        feature: &'static str,
+        subject: String,
+        subscribed: ProviderInitResult<(mpsc::Receiver<T>, JoinHandle<()>)>,
+    ) -> mpsc::Receiver<T> {
+        match subscribed {
+            Ok((rx, task)) => {
+                self.optional.push((subject, feature, task.abort_handle()));
+                tasks.push(task);
+                rx
+            }
+            Err(err) => {
+                warn!(%err, "{feature} disabled");
+                mpsc::channel(1).1
+            }
+        }
+    }
+
+    /// First core subject already reported as denied in `violations`
+    fn denied_core(&self, violations: &SubscriptionViolations) -> Option<&str> {
+        self.core
+            .iter()
+            .map(|(subject, _)| subject.as_str())
+            .find(|subject| violations.is_denied(subject))
+    }
+
+    /// Stop the subscription to the denied `subject`. Stopping a core subscription closes its
+    /// receiver, which shuts the provider down.
+    fn deny(&self, subject: &str) {
+        if let Some((_, task)) = self.core.iter().find(|(s, _)| s == subject) {
+            error!(subject, "NATS server denied subscription, shutting down");
+            task.abort();
+        } else if let Some((_, feature, task)) = self.optional.iter().find(|(s, ..)| s == subject) {
+            warn!(
+                subject,
+                "NATS server denied subscription, {feature} disabled"
+            );
+            task.abort();
+        }
+    }
+}
+
+/// Stop the `subscriptions` the NATS server reports denying, until `quit` is received
+fn watch_violations(
+    mut denied: watch::Receiver<Vec<String>>,
+    mut quit: broadcast::Receiver<()>,
+    subscriptions: Subscriptions,
+) -> JoinHandle<()> {
+    spawn(async move {
+        let mut handled = 0;
+        loop {
+            let subjects = {
+                let denied = denied.borrow_and_update();
+                let subjects = denied[handled..].to_vec();
+                handled = denied.len();
+                subjects
+            };
+            for subject in subjects {
+                subscriptions.deny(&subject);
+            }
+            select! {
+                _ = quit.recv() => break,
+                changed = denied.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Abort the subscription `tasks`, which unsubscribes as their subscriptions are dropped
+async fn close_subscriptions(nats: &async_nats::Client, tasks: Vec<JoinHandle<()>>) {
+    for task in &tasks {
+        task.abort();
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+    if let Err(err) = nats.flush().await {
+        warn!(%err, "failed to flush unsubscriptions");
+    }
 }
 
 /// State of provider initialization
@@ -324,49 +990,274 @@ pub(crate) struct ProviderInitState {
     pub nats: Arc<async_nats::Client>,
     pub quit_rx: broadcast::Receiver<()>,
     pub quit_tx: broadcast::Sender<()>,
-    pub host_id: String,
-    pub lattice_rpc_prefix: String,
     pub provider_key: String,
+    pub instance_id: String,
     pub link_definitions: Vec<InterfaceLinkDefinition>,
     pub commands: ProviderCommandReceivers,
+    /// Effective configuration of the provider, merged from [`Self::config_sources`]
     pub config: HashMap<String, String>,
+    pub config_sources: ConfigSources,
+    pub default_rpc_timeout: Duration,
+    pub host_info: HostInfo,
+    pub native_dependencies: Option<NativeDependencies>,
+    pub host_capabilities: HostCapabilities,
+    pub link_readiness: LinkReadiness,
+}
+
+/// Timeout of wRPC clients used when the host does not supply a default RPC timeout
+const DEFAULT_WRPC_TIMEOUT: Duration = Duration::from_secs(10);
+/// Bounds for the default RPC timeout supplied by the host, guarding against zero or absurd values
+const MIN_RPC_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_RPC_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Determine the default timeout of wRPC clients from the RPC timeout supplied by the host
+fn default_rpc_timeout(host_data: &HostData) -> Duration {
+    let Some(timeout_ms) = host_data.default_rpc_timeout_ms else {
+        return DEFAULT_WRPC_TIMEOUT;
+    };
+    let timeout = Duration::from_millis(timeout_ms);
+    let clamped = timeout.clamp(MIN_RPC_TIMEOUT, MAX_RPC_TIMEOUT);
+    if clamped != timeout {
+        warn!(
+            ?timeout,
+            ?clamped,
+            "default RPC timeout supplied by the host is out of bounds, clamping"
+        );
+    }
+    clamped
+}
+
+/// Authentication scheme used to connect to the lattice RPC NATS server
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum LatticeRpcAuth {
+    None,
+    Jwt { jwt: String, seed: String },
+    Token(String),
+    UserPassword { user: String, password: String },
+    CredsFile(PathBuf),
+}
+
+/// TLS settings used to connect to the lattice RPC NATS server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct LatticeRpcTls {
+    ca_file: Option<PathBuf>,
+    /// Client certificate and key files
+    client_cert: Option<(PathBuf, PathBuf)>,
+    required: bool,
+}
+
+/// Options used to connect to the lattice RPC NATS server, as supplied by the host
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LatticeRpcConnectOptions {
+    auth: LatticeRpcAuth,
+    tls: LatticeRpcTls,
+}
+
+/// Returns the trimmed value if it is set and not blank
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+impl LatticeRpcConnectOptions {
+    /// Select the authentication scheme and TLS settings from the fields set in the host data,
+    /// returning an error if the fields are incomplete or more than one scheme is configured
+    fn from_host_data(host_data: &HostData) -> ProviderInitResult<Self> {
+        let jwt = non_empty(Some(host_data.lattice_rpc_user_jwt.as_str()));
+        let seed = non_empty(Some(host_data.lattice_rpc_user_seed.as_str()));
+        let token = non_empty(host_data.lattice_rpc_user_token.as_deref());
+        let user = non_empty(host_data.lattice_rpc_user_name.as_deref());
+        let password = non_empty(host_data.lattice_rpc_user_password.as_deref());
+        let creds = host_data.lattice_rpc_credsfile.as_ref();
+
+        let mut schemes = Vec::new();
+        if jwt.is_some() || seed.is_some() {
+            schemes.push("jwt and seed");
+        }
+        if token.is_some() {
+            schemes.push("token");
+        }
+        if user.is_some() || password.is_some() {
+            schemes.push("username and password");
+        }
+        if creds.is_some() {
+            schemes.push("credentials file");
+        }
+        if schemes.len() > 1 {
+            return Err(ProviderInitError::Initialization(format!(
+                "conflicting lattice RPC authentication supplied by the host, only one of [{}] may be set",
+                schemes.join(", ")
+            )));
+        }
+
+        let auth = match (jwt, seed, token, user, password, creds) {
+            (Some(jwt), Some(seed), ..) => LatticeRpcAuth::Jwt {
+                jwt: jwt.to_string(),
+                seed: seed.to_string(),
+            },
+            (Some(_), None, ..) | (None, Some(_), ..) => {
+                return Err(ProviderInitError::Initialization(
+                    "lattice RPC JWT and seed must be supplied together".to_string(),
+                ))
+            }
+            (_, _, Some(token), ..) => LatticeRpcAuth::Token(token.to_string()),
+            (_, _, _, Some(user), Some(password), _) => LatticeRpcAuth::UserPassword {
+                user: user.to_string(),
+                password: password.to_string(),
+            },
+            (_, _, _, Some(_), None, _) | (_, _, _, None, Some(_), _) => {
+                return Err(ProviderInitError::Initialization(
+                    "lattice RPC username and password must be supplied together".to_string(),
+                ))
+            }
+            (.., Some(creds)) => LatticeRpcAuth::CredsFile(creds.clone()),
+            _ => LatticeRpcAuth::None,
+        };
+
+        let client_cert = match (
+            host_data.lattice_rpc_tls_cert_file.as_ref(),
+            host_data.lattice_rpc_tls_key_file.as_ref(),
+        ) {
+            (Some(cert), Some(key)) => Some((cert.clone(), key.clone())),
+            (None, None) => None,
+            _ => {
+                return Err(ProviderInitError::Initialization(
+                    "lattice RPC TLS client certificate and key must be supplied together"
+                        .to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            auth,
+            tls: LatticeRpcTls {
+                ca_file: host_data.lattice_rpc_tls_ca_file.clone(),
+                client_cert,
+                required: host_data.lattice_rpc_tls_required,
+            },
+        })
+    }
+
+    /// Build the NATS connect options, with connection events logged and denied subscriptions
+    /// recorded in `violations`
+    async fn into_connect_options(
+        self,
+        violations: SubscriptionViolations,
+    ) -> ProviderInitResult<async_nats::ConnectOptions> {
+        let opts = match self.auth {
+            LatticeRpcAuth::None => async_nats::ConnectOptions::default(),
+            LatticeRpcAuth::Jwt { jwt, seed } => {
+                let key_pair = Arc::new(nkeys::KeyPair::from_seed(&seed).map_err(|e| {
+                    ProviderInitError::Initialization(format!("invalid lattice RPC seed: {e}"))
+                })?);
+                async_nats::ConnectOptions::with_jwt(jwt, move |nonce| {
+                    let key_pair = key_pair.clone();
+                    async move { key_pair.sign(&nonce).map_err(async_nats::AuthError::new) }
+                })
+            }
+            LatticeRpcAuth::Token(token) => async_nats::ConnectOptions::with_token(token),
+            LatticeRpcAuth::UserPassword { user, password } => {
+                async_nats::ConnectOptions::with_user_and_password(user, password)
+            }
+            LatticeRpcAuth::CredsFile(path) => {
+                async_nats::ConnectOptions::with_credentials_file(path.clone())
+                    .await
+                    .map_err(|e| {
+                        ProviderInitError::Initialization(format!(
+                            "failed to read lattice RPC credentials file [{}]: {e}",
+                            path.display()
+                        ))
+                    })?
+            }
+        };
+        let mut opts = opts.require_tls(self.tls.required);
+        if let Some(ca_file) = self.tls.ca_file {
+            opts = opts.add_root_certificates(ca_file);
+        }
+        if let Some((cert, key)) = self.tls.client_cert {
+            opts = opts.add_client_certificate(cert, key);
+        }
+        Ok(opts.event_callback(move |event| {
+            violations.record(&event);
+            log_connection_event(event)
+        }))
+    }
 }
 
 #[instrument]
 async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
+    let host_data = spawn_blocking(load_host_data).await.map_err(|e| {
+        ProviderInitError::Initialization(format!("failed to load host data: {e}"))
+    })??;
+    let default_rpc_timeout = default_rpc_timeout(host_data);
+    let connect_options = LatticeRpcConnectOptions::from_host_data(host_data)?;
     let HostData {
+        host_data_schema_version: _,
+        host_capabilities: _,
         host_id,
+        host_friendly_name,
+        host_labels,
+        host_version,
+        host_signing_key,
         lattice_rpc_prefix,
-        lattice_rpc_user_jwt,
-        lattice_rpc_user_seed,
         lattice_rpc_url,
         provider_key,
         env_values: _,
         cluster_issuers: _,
         instance_id,
         link_definitions,
-        config,
+        config: _,
         default_rpc_timeout_ms: _,
         structured_logging,
         log_level,
         otel_config,
-        link_name: _link_name,
-    } = spawn_blocking(load_host_data).await.map_err(|e| {
-        ProviderInitError::Initialization(format!("failed to load host data: {e}"))
-    })??;
+        link_name,
+        lattice_rpc_user_jwt: _,
+        lattice_rpc_user_seed: _,
+        lattice_rpc_user_token: _,
+        lattice_rpc_user_name: _,
+        lattice_rpc_user_password: _,
+        lattice_rpc_credsfile: _,
+        lattice_rpc_tls_ca_file: _,
+        lattice_rpc_tls_cert_file: _,
+        lattice_rpc_tls_key_file: _,
+        lattice_rpc_tls_required: _,
+    } = host_data;
+    // Values passed through from the environment of the host are overridden by named config
+    let config_sources = ConfigSources::from_host_data(host_data);
+    let config = &config_sources.merged();
 
-    let res = wasmcloud_tracing::configure_observability(
+    let (log_layer, log_forwarder) = log_forwarding_from_config(provider_key, host_id, config)?;
+    let layers: Vec<wasmcloud_tracing::BoxedLayer> = sampler_from_config(config)?
+        .map(|sampler| Box::new(sampler) as wasmcloud_tracing::BoxedLayer)
+        .into_iter()
+        .chain(log_layer)
+        .collect();
+    let res = wasmcloud_tracing::configure_observability_with_layer(
         name,
         otel_config,
         *structured_logging,
         None::<&str>,
         log_level.as_ref(),
+        (!layers.is_empty()).then(|| Box::new(layers) as wasmcloud_tracing::BoxedLayer),
     );
     if let Err(err) = res {
         error!(?err, "failed to configure tracing");
     }
+    for warning in HOST_DATA_WARNINGS.get().into_iter().flatten() {
+        warn!("{warning}");
+    }
+    let host_capabilities = HostCapabilities::from_host_data(host_data);
+
+    let native_dependencies = NativeDependencies::from_config(config)
+        .map_err(|e| ProviderInitError::Initialization(e.to_string()))?;
+    if let Some(deps) = &native_dependencies {
+        deps.export_library_path();
+    }
+    set_inbound_limits(ServeLimits::from_config(config));
+    set_max_consecutive_panics(max_consecutive_panics_from_config(config));
 
     let (quit_tx, quit_rx) = broadcast::channel(1);
+    let verifier = ControlVerifier::from_config(host_signing_key.as_deref(), config);
 
     info!(
         "Starting capability provider {provider_key} instance {instance_id} with nats url {lattice_rpc_url}"
@@ -377,67 +1268,95 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
     } else {
         DEFAULT_NATS_ADDR
     };
-    let nats = with_connection_event_logging(
-        match (lattice_rpc_user_jwt.trim(), lattice_rpc_user_seed.trim()) {
-            ("", "") => async_nats::ConnectOptions::default(),
-            (rpc_jwt, rpc_seed) => {
-                let key_pair = Arc::new(nkeys::KeyPair::from_seed(rpc_seed).unwrap());
-                let jwt = rpc_jwt.to_owned();
-                async_nats::ConnectOptions::with_jwt(jwt, move |nonce| {
-                    let key_pair = key_pair.clone();
-                    async move { key_pair.sign(&nonce).map_err(async_nats::AuthError::new) }
-                })
-            }
+    let violations = SubscriptionViolations::default();
+    let nats = connect_options
+        .into_connect_options(violations.clone())
+        .await?
+        .connect(nats_addr)
+        .await?;
+    let nats = Arc::new(nats);
+    if let Some(log_forwarder) = log_forwarder {
+        spawn(log_forwarder.run((*nats).clone(), lattice_rpc_prefix.clone()));
+    }
+    let link_readiness = LinkReadiness::from_config(config);
+    let commands = ProviderCommandReceivers::new(
+        &nats,
+        &violations,
+        &quit_tx,
+        lattice_rpc_prefix,
+        provider_key,
+        ProviderInstance {
+            host_id,
+            instance_id,
+            link_name,
         },
+        verifier,
+        link_readiness.clone(),
     )
-    .connect(nats_addr)
     .await?;
-    let nats = Arc::new(nats);
-    let (health, shutdown, link_put, link_del) = try_join!(
-        subscribe_health(
-            Arc::clone(&nats),
-            quit_tx.subscribe(),
-            lattice_rpc_prefix,
-            provider_key
-        ),
-        subscribe_shutdown(
-            Arc::clone(&nats),
-            quit_tx.clone(),
-            lattice_rpc_prefix,
-            provider_key,
-            host_id
-        ),
-        subscribe_link_put(
-            Arc::clone(&nats),
-            quit_tx.subscribe(),
-            lattice_rpc_prefix,
-            provider_key,
-        ),
-        subscribe_link_del(
-            Arc::clone(&nats),
-            quit_tx.subscribe(),
-            lattice_rpc_prefix,
-            provider_key,
-        ),
-    )?;
     Ok(ProviderInitState {
         nats,
         quit_rx,
         quit_tx,
-        host_id: host_id.clone(),
-        lattice_rpc_prefix: lattice_rpc_prefix.clone(),
         provider_key: provider_key.clone(),
+        instance_id: instance_id.clone(),
         link_definitions: link_definitions.clone(),
         config: config.clone(),
-        commands: ProviderCommandReceivers {
-            health,
-            shutdown,
-            link_put,
-            link_del,
+        config_sources,
+        default_rpc_timeout,
+        host_info: HostInfo {
+            host_id: host_id.clone(),
+            friendly_name: host_friendly_name.clone(),
+            labels: host_labels.clone(),
+            version: host_version.clone(),
+            lattice: lattice_rpc_prefix.clone(),
         },
+        native_dependencies,
+        host_capabilities,
+        link_readiness,
+        commands,
     })
 }
 
+/// Create the log forwarding layer and forwarder of the provider, if enabled in its `config`
+fn log_forwarding_from_config(
+    provider_id: &str,
+    host_id: &str,
+    config: &HashMap<String, String>,
+) -> ProviderInitResult<(Option<wasmcloud_tracing::BoxedLayer>, Option<LogForwarder>)> {
+    let enabled = config
+        .get(FORWARD_LOGS_CONFIG_KEY)
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    if !enabled {
+        return Ok((None, None));
+    }
+    let level = match config.get(FORWARD_LOGS_LEVEL_CONFIG_KEY) {
+        Some(level) => level.parse().map_err(|_| {
+            ProviderInitError::Initialization(format!(
+                "invalid `{FORWARD_LOGS_LEVEL_CONFIG_KEY}` configuration [{level}]"
+            ))
+        })?,
+        None => tracing::Level::INFO,
+    };
+    let (layer, forwarder) = log_forwarding(provider_id, host_id, level);
+    Ok((Some(Box::new(layer)), Some(forwarder)))
+}
+
+/// Create the sampler of SDK spans of the provider, if a sampling ratio is set in its `config`
+fn sampler_from_config(
+    config: &HashMap<String, String>,
+) -> ProviderInitResult<Option<SdkSpanSampler>> {
+    let Some(ratio) = config.get(OTEL_SAMPLING_RATIO_CONFIG_KEY) else {
+        return Ok(None);
+    };
+    match ratio.trim().parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(Some(SdkSpanSampler::new(ratio))),
+        _ => Err(ProviderInitError::Initialization(format!(
+            "invalid `{OTEL_SAMPLING_RATIO_CONFIG_KEY}` configuration [{ratio}], expected a number between 0 and 1"
+        ))),
+    }
+}
+
 /// Appropriately receive a link (depending on if it's source/target) for a provider
 async fn receive_link_for_provider<P>(
     provider: &P,
@@ -470,7 +1389,11 @@ where
     } else {
         bail!("received link put where provider was neither source nor target");
     } {
-        Ok(()) => connection.put_link(ld).await,
+        Ok(()) => {
+            // Link configuration may have changed, so previously cached responses are stale
+            invalidate_link_caches(connection, &ld);
+            connection.put_link(ld).await;
+        }
         Err(e) => {
             warn!(error = %e, "receiving link failed");
         }
@@ -495,10 +1418,162 @@ where
             error!(error = %e, source = &ld.source_id, "failed to delete link from component");
         }
     }
-    connection.delete_link(&ld.source_id, &ld.target).await;
+    invalidate_link_caches(connection, &ld);
+    connection
+        .delete_named_link(&ld.source_id, &ld.target, &ld.name)
+        .await;
     Ok(())
 }
 
+/// Restore the links cached by a previous instance of the provider, see [`crate::link_cache`].
+/// Returns the restored links
+async fn restore_cached_links<P>(
+    provider: &P,
+    connection: &ProviderConnection,
+    host_links: &[InterfaceLinkDefinition],
+) -> Vec<InterfaceLinkDefinition>
+where
+    P: Provider,
+{
+    let Some(cache) = connection.link_cache.as_deref() else {
+        return Vec::new();
+    };
+    let mut restored = Vec::new();
+    for ld in cache.load().await {
+        if connection
+            .is_linked_with_name(&ld.source_id, &ld.target, &ld.name)
+            .await
+        {
+            continue;
+        }
+        if let Err(e) = receive_link_for_provider(provider, connection, ld.clone()).await {
+            error!(error = %e, "failed to restore cached link");
+            continue;
+        }
+        if connection
+            .is_linked_with_name(&ld.source_id, &ld.target, &ld.name)
+            .await
+        {
+            restored.push(ld);
+        }
+    }
+    cache.set_unconfirmed(&restored, host_links);
+    restored
+}
+
+/// Delete the links restored from the cache which the host did not confirm
+async fn prune_stale_links<P>(provider: &P, connection: &ProviderConnection)
+where
+    P: Provider,
+{
+    let Some(cache) = connection.link_cache.as_deref() else {
+        return;
+    };
+    let stale = cache.take_unconfirmed();
+    for ld in connection.snapshot_links().await {
+        if stale.contains(&LinkKey::from(&ld)) {
+            info!(
+                source = &ld.source_id,
+                target = &ld.target,
+                link_name = &ld.name,
+                "deleting cached link which the host no longer reports"
+            );
+            if let Err(e) = delete_link_for_provider(provider, connection, ld).await {
+                error!(error = %e, "failed to delete stale cached link");
+            }
+        }
+    }
+}
+
+/// Invalidate cached invocation responses for the component on the other end of a link
+fn invalidate_link_caches(connection: &ProviderConnection, ld: &InterfaceLinkDefinition) {
+    if ld.source_id == connection.provider_id {
+        cache::invalidate_target(&ld.target);
+    } else {
+        cache::invalidate_target(&ld.source_id);
+    }
+}
+
+/// Store a snapshot of the state of the provider, if enabled, see [`crate::state_snapshot`]
+async fn snapshot_provider_state(provider: &impl Provider, connection: &ProviderConnection) {
+    if connection.state_snapshot_max_age.is_none() {
+        return;
+    }
+    let state = match provider.snapshot_state().await {
+        Ok(state) if state.is_empty() => return,
+        Ok(state) => state,
+        Err(e) => {
+            error!(error = %e, "failed to snapshot provider state");
+            return;
+        }
+    };
+    let stored = async {
+        StateSnapshotStore::open(
+            (*connection.nats).clone(),
+            &connection.lattice,
+            &connection.provider_id,
+        )
+        .await?
+        .store(&state)
+        .await
+    };
+    match stored.await {
+        Ok(()) => info!(size = state.len(), "stored provider state snapshot"),
+        Err(err) => error!(?err, "failed to store provider state snapshot"),
+    }
+}
+
+/// Restore the state of the provider from the snapshot stored by the version it replaces, if
+/// enabled and not expired, see [`crate::state_snapshot`]. The provider starts cold on failure
+async fn restore_provider_state(provider: &impl Provider, connection: &ProviderConnection) {
+    let Some(max_age) = connection.state_snapshot_max_age else {
+        return;
+    };
+    let snapshot = async {
+        StateSnapshotStore::open(
+            (*connection.nats).clone(),
+            &connection.lattice,
+            &connection.provider_id,
+        )
+        .await?
+        .take(max_age)
+        .await
+    };
+    let state = match snapshot.await {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(err) => {
+            warn!(
+                ?err,
+                "failed to load provider state snapshot, starting cold"
+            );
+            return;
+        }
+    };
+    let size = state.len();
+    match provider.restore_state(state).await {
+        Ok(()) => info!(size, "restored provider state snapshot"),
+        Err(e) => warn!(error = %e, "failed to restore provider state snapshot, starting cold"),
+    }
+}
+
+/// Shut down the provider, cancelling its background tasks and waiting for them to finish
+async fn shutdown_provider(
+    provider: &impl Provider,
+    connection: &ProviderConnection,
+    reason: &ShutdownReason,
+) {
+    // The state is snapshotted before background tasks stop updating it
+    if matches!(reason, ShutdownReason::Update { .. }) {
+        snapshot_provider_state(provider, connection).await;
+    }
+    connection.tasks.cancel();
+    if let Err(e) = provider.shutdown_with_reason(reason).await {
+        error!(error = %e, "failed to shutdown provider");
+    }
+    connection.tasks.drain(TASK_DRAIN_TIMEOUT).await;
+}
+
 /// Handle provider commands in a loop.
 async fn handle_provider_commands(
     provider: impl Provider,
@@ -508,22 +1583,45 @@ async fn handle_provider_commands(
     ProviderCommandReceivers {
         mut health,
         mut shutdown,
+        mut prepare_shutdown,
+        mut config_update,
         mut link_put,
         mut link_del,
+        mut host_labels,
+        tasks: _,
     }: ProviderCommandReceivers,
 ) {
+    // Links restored from the cache are deleted unless the host confirms them in time
+    let mut prune_pending = connection.link_cache.is_some();
+    let prune_deadline = tokio::time::sleep(LINK_CACHE_GRACE_PERIOD);
+    tokio::pin!(prune_deadline);
     loop {
         select! {
+            () = &mut prune_deadline, if prune_pending => {
+                prune_pending = false;
+                prune_stale_links(&provider, connection).await;
+            }
             // run until we receive a shutdown request from host
             _ = quit_rx.recv() => {
                 // flush async_nats client
                 connection.flush().await;
                 return
             }
+            // run until the provider requests its own shutdown (e.g. it is wedged)
+            () = connection.shutdown_requested.notified() => {
+                let reason = connection.take_shutdown_reason();
+                info!(%reason, "provider requested its own shutdown");
+                shutdown_provider(&provider, connection, &reason).await;
+                if quit_tx.send(()).is_err() {
+                    error!("failed to send quit");
+                };
+                connection.flush().await;
+                return
+            }
             req = health.recv() => {
                 if let Some((req, tx)) = req {
                     let res = match provider.health_request(&req).await {
-                        Ok(v) => v,
+                        Ok(v) => with_stuck_invocations(with_metrics_listener_error(v)),
                         Err(e) => {
                             error!(error = %e, "provider health request failed");
                             return;
@@ -534,9 +1632,8 @@ async fn handle_provider_commands(
                     }
                 } else {
                     error!("failed to handle health check, shutdown");
-                    if let Err(e) = provider.shutdown().await {
-                        error!(error = %e, "failed to shutdown provider");
-                    }
+                    shutdown_provider(&provider, connection, &ShutdownReason::LatticeDisconnected)
+                        .await;
                     if quit_tx.send(()).is_err() {
                         error!("failed to send quit");
                     };
@@ -544,29 +1641,59 @@ async fn handle_provider_commands(
                 };
             }
             req = shutdown.recv() => {
-                if let Some(tx) = req {
-                    if let Err(e) = provider.shutdown().await {
-                        error!(error = %e, "failed to shutdown provider");
-                    }
+                if let Some((reason, tx)) = req {
+                    shutdown_provider(&provider, connection, &reason).await;
                     if tx.send(()).is_err() {
                         error!("failed to send shutdown response");
                     }
                 } else {
                     error!("failed to handle shutdown, shutdown");
-                    if let Err(e) = provider.shutdown().await {
-                        error!(error = %e, "failed to shutdown provider");
-                    }
+                    shutdown_provider(&provider, connection, &ShutdownReason::LatticeDisconnected)
+                        .await;
                     if quit_tx.send(()).is_err() {
                         error!("failed to send quit");
                     };
                     return
                 };
             }
+            Some((timeout, tx)) = prepare_shutdown.recv() => {
+                info!(?timeout, "preparing provider for shutdown");
+                let prepared = tokio::time::timeout(timeout, provider.prepare_shutdown(timeout));
+                let report = match prepared.await {
+                    Ok(Ok(report)) => report,
+                    Ok(Err(e)) => {
+                        error!(error = %e, "failed to prepare provider for shutdown");
+                        DrainReport { drained: false, abandoned: None }
+                    }
+                    Err(_) => {
+                        warn!(?timeout, "provider did not finish preparing for shutdown in time");
+                        DrainReport { drained: false, abandoned: None }
+                    }
+                };
+                if tx.send(report).is_err() {
+                    error!("failed to send drain report");
+                }
+            }
+            Some((config, tx)) = config_update.recv() => {
+                let outcome = update_provider_config(&provider, connection, config).await;
+                if tx.send(outcome).is_err() {
+                    error!("failed to send config update outcome");
+                }
+            }
             req = link_put.recv() => {
                 if let Some((ld, tx)) = req {
-                    // If the link has already been put, return early
-                    if connection.is_linked(&ld.source_id, &ld.target).await {
-                        warn!(source = &ld.source_id, target = &ld.target, "Ignoring duplicate link put");
+                    connection.confirm_cached_link(&ld.source_id, &ld.target, &ld.name);
+                    // If the link has already been put under the same name, return early
+                    if connection
+                        .is_linked_with_name(&ld.source_id, &ld.target, &ld.name)
+                        .await
+                    {
+                        warn!(
+                            source = &ld.source_id,
+                            target = &ld.target,
+                            link_name = &ld.name,
+                            "Ignoring duplicate link put"
+                        );
                     } else {
                         info!("Linking component with provider");
                         if let Err(e) = receive_link_for_provider(&provider, connection, ld).await {
@@ -578,9 +1705,8 @@ async fn handle_provider_commands(
                     }
                 } else {
                     error!("failed to handle link put, shutdown");
-                    if let Err(e) = provider.shutdown().await {
-                        error!(error = %e, "failed to shutdown provider");
-                    }
+                    shutdown_provider(&provider, connection, &ShutdownReason::LatticeDisconnected)
+                        .await;
                     if quit_tx.send(()).is_err() {
                         error!("failed to send quit");
                     };
@@ -589,6 +1715,7 @@ async fn handle_provider_commands(
             }
             req = link_del.recv() => {
                 if let Some((ld, tx)) = req {
+                    connection.confirm_cached_link(&ld.source_id, &ld.target, &ld.name);
                     // notify provider that link is deleted
                     if let Err(e) = delete_link_for_provider(&provider, connection, ld).await {
                         error!(error = %e, "failed to delete link for provider");
@@ -599,60 +1726,259 @@ async fn handle_provider_commands(
                     }
                 } else {
                     error!("failed to handle link del, shutdown");
-                    if let Err(e) = provider.shutdown().await {
-                        error!(error = %e, "failed to shutdown provider");
-                    }
+                    shutdown_provider(&provider, connection, &ShutdownReason::LatticeDisconnected)
+                        .await;
                     if quit_tx.send(()).is_err() {
                         error!("failed to send quit");
                     };
                     return
                 };
             }
+            Some(labels) = host_labels.recv() => {
+                debug!(?labels, "host labels changed");
+                connection.update_host_labels(labels);
+            }
+        }
+    }
+}
+
+/// Validate the named configuration `config` with the provider, then apply it. The effective
+/// configuration of the connection is only replaced once the provider applied the update
+async fn update_provider_config(
+    provider: &impl Provider,
+    connection: &ProviderConnection,
+    config: HashMap<String, String>,
+) -> ConfigUpdateOutcome {
+    let mut config_sources = connection.config_sources();
+    config_sources.set_named(config);
+    let config = config_sources.merged();
+    if let Err(rejection) = provider.validate_config_update(&config).await {
+        warn!(%rejection, "provider rejected config update");
+        return ConfigUpdateOutcome::rejected(rejection);
+    }
+    if let Err(e) = provider.on_config_update(&config).await {
+        error!(error = %e, "provider failed to apply config update");
+        return ConfigUpdateOutcome::rejected(ConfigRejection::new(format!(
+            "failed to apply config update: {e}"
+        )));
+    }
+    info!("applied config update");
+    set_inbound_limits(ServeLimits::from_config(&config));
+    connection.set_config_sources(config_sources);
+    ConfigUpdateOutcome::accepted()
+}
+
+/// Single instance lock of a provider, see [`acquire_single_instance_lock`]
+enum SingleInstance {
+    /// The provider is not configured to run as a single instance
+    Unrestricted,
+    /// The provider holds the lock of its single instance
+    Locked(ProviderLock),
+    /// Another live instance holds the lock, so the provider must not serve
+    HeldElsewhere,
+}
+
+/// Acquire the lock of the provider in the lattice if it is configured to run as a single instance,
+/// see [`crate::single_instance`]
+async fn acquire_single_instance_lock(
+    state: &ProviderInitState,
+) -> ProviderInitResult<SingleInstance> {
+    if !single_instance_from_config(&state.config) {
+        return Ok(SingleInstance::Unrestricted);
+    }
+    match ProviderLock::acquire(
+        (*state.nats).clone(),
+        &state.host_info.lattice,
+        &state.provider_key,
+        &state.instance_id,
+        PROVIDER_LOCK_TTL,
+    )
+    .await
+    {
+        Ok(LockAcquisition::Acquired(lock)) => {
+            info!(
+                provider_id = state.provider_key,
+                "acquired single instance lock"
+            );
+            Ok(SingleInstance::Locked(lock))
         }
+        Ok(LockAcquisition::HeldBy(holder)) => {
+            error!(
+                provider_id = state.provider_key,
+                holder,
+                "provider is configured to run as a single instance and is already running in the lattice, shutting down without serving"
+            );
+            Ok(SingleInstance::HeldElsewhere)
+        }
+        Err(e) => Err(ProviderInitError::Initialization(format!(
+            "failed to acquire single instance lock: {e:#}"
+        ))),
     }
 }
 
+/// Shut the provider down if it loses its single instance lock, and release the lock when the
+/// provider shuts down
+fn watch_single_instance_lock(connection: &'static ProviderConnection, lock: ProviderLock) {
+    connection
+        .tasks()
+        .spawn("single-instance-lock", |cancel| async move {
+            let lost = select! {
+                () = lock.lost() => true,
+                () = cancel.cancelled() => false,
+            };
+            if lost {
+                connection.request_shutdown(ShutdownReason::InternalError(
+                    "lost the single instance lock".to_string(),
+                ));
+            } else if let Err(err) = lock.release().await {
+                warn!(?err, "failed to release single instance lock");
+            }
+        });
+}
+
+/// Serve the metrics of the provider on the address set in its config, if any, until the provider
+/// shuts down, see [`crate::metrics`]
+fn serve_provider_metrics(connection: &'static ProviderConnection) {
+    let Some(addr) = metrics_listen_addr_from_config(&connection.config()) else {
+        return;
+    };
+    let addr = addr.to_string();
+    connection.tasks().spawn("metrics-listener", |cancel| {
+        run_metrics_endpoint(
+            addr,
+            || async { connection.snapshot_links().await.len() },
+            cancel,
+        )
+    });
+}
+
+/// Report the failure to start the metrics endpoint, if any, in a health check response
+fn with_metrics_listener_error(res: HealthCheckResponse) -> HealthCheckResponse {
+    match metrics_listener_error() {
+        Some(err) => with_health_message(res, format!("metrics endpoint is disabled: {err}")),
+        None => res,
+    }
+}
+
+/// Report the number of stuck invocations, if any, in a health check response
+fn with_stuck_invocations(res: HealthCheckResponse) -> HealthCheckResponse {
+    match ProviderMetrics::global().stuck_invocations() {
+        0 => res,
+        stuck => with_health_message(res, format!("{stuck} invocation(s) stuck")),
+    }
+}
+
+/// Append `message` to the message of a health check response
+fn with_health_message(mut res: HealthCheckResponse, message: String) -> HealthCheckResponse {
+    res.message = Some(match res.message.take() {
+        Some(existing) if !existing.is_empty() => format!("{existing}; {message}"),
+        _ => message,
+    });
+    res
+}
+
 /// Runs the provider handler. You can use this method instead of [`start_provider`] if you are already in
 /// an async context and want to manually manage RPC serving functionality.
+///
+/// A provider configured to run as a single instance which finds another live instance in the
+/// lattice neither initializes nor handles commands, the returned future is then already complete.
 pub async fn run_provider(
     provider: impl Provider,
     friendly_name: &str,
 ) -> ProviderInitResult<impl Future<Output = ()>> {
     let init_state = init_provider(friendly_name).await?;
+    // No subscription task outlives a failed initialization
+    let lock = match acquire_single_instance_lock(&init_state).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            init_state.commands.close(&init_state.nats).await;
+            return Err(e);
+        }
+    };
+    let serving = !matches!(lock, SingleInstance::HeldElsewhere);
 
     // Run user-implemented provider-internal specific initialization
-    if let Err(e) = provider.init(&init_state).await {
-        return Err(ProviderInitError::Initialization(format!(
-            "provider init failed: {e}"
-        )));
+    if serving {
+        if let Err(e) = provider.init(&init_state).await {
+            init_state.commands.close(&init_state.nats).await;
+            return Err(ProviderInitError::Initialization(format!(
+                "provider init failed: {e}"
+            )));
+        }
     }
 
     let ProviderInitState {
         nats,
         quit_rx,
         quit_tx,
-        host_id,
-        lattice_rpc_prefix,
         provider_key,
+        instance_id: _,
         link_definitions,
         commands,
-        config,
+        config: _,
+        config_sources,
+        default_rpc_timeout,
+        host_info,
+        native_dependencies: _,
+        host_capabilities: _,
+        link_readiness,
     } = init_state;
 
-    let connection = ProviderConnection::new(
+    // Invocations over the links supplied by the host are parked until the links are replayed
+    let replaying: Vec<_> = link_definitions
+        .iter()
+        .map(|ld| (ld.target == provider_key).then(|| link_readiness.begin(ld.source_id.clone())))
+        .collect();
+
+    let connection = match ProviderConnection::new(
         Arc::clone(&nats),
         provider_key,
-        lattice_rpc_prefix.clone(),
-        host_id,
-        config,
-    )?;
-    CONNECTION.set(connection).map_err(|_| {
-        ProviderInitError::Initialization("Provider connection was already initialized".to_string())
-    })?;
+        host_info,
+        config_sources,
+        default_rpc_timeout,
+        link_readiness,
+    ) {
+        Ok(connection) => connection,
+        Err(e) => {
+            commands.close(&nats).await;
+            return Err(e);
+        }
+    };
+    if CONNECTION.set(connection).is_err() {
+        commands.close(&nats).await;
+        return Err(ProviderInitError::Initialization(
+            "Provider connection was already initialized".to_string(),
+        ));
+    }
     let connection = get_connection();
+    match lock {
+        SingleInstance::Unrestricted => {}
+        SingleInstance::Locked(lock) => watch_single_instance_lock(connection, lock),
+        // The connection is still set, so that callers serving the provider stop right away
+        SingleInstance::HeldElsewhere => {
+            commands.close(&nats).await;
+            return Ok(Either::Left(future::ready(())));
+        }
+    }
+    serve_provider_metrics(connection);
+
+    // State carried over from the version of the provider being updated, if any, is restored before
+    // links are replayed
+    restore_provider_state(&provider, connection).await;
+
+    // Links cached by a previous instance of the provider are live until the host confirms them
+    let restored = restore_cached_links(&provider, connection, &link_definitions).await;
 
     // Provide all links to the provider at startup to establish the initial state
-    for ld in link_definitions {
+    for (ld, _replaying) in link_definitions.into_iter().zip(replaying) {
+        // Links restored with the same definition were already received
+        if restored.contains(&ld)
+            && connection
+                .is_linked_with_name(&ld.source_id, &ld.target, &ld.name)
+                .await
+        {
+            continue;
+        }
         if let Err(e) = receive_link_for_provider(&provider, connection, ld).await {
             error!(
                 error = %e,
@@ -662,13 +1988,53 @@ pub async fn run_provider(
     }
 
     debug!(?friendly_name, "provider finished initialization");
-    Ok(handle_provider_commands(
+    Ok(Either::Right(handle_provider_commands(
         provider, connection, quit_rx, quit_tx, commands,
-    ))
+    )))
 }
 
 /// Source ID for a link
 type SourceId = String;
+type LinkName = String;
+
+/// Number of link events buffered for each subscriber of [`ProviderConnection::link_events`]
+const LINK_EVENTS_CAPACITY: usize = 256;
+
+/// Number of label changes buffered for each subscriber of [`ProviderConnection::watch_host_labels`]
+const HOST_LABELS_CAPACITY: usize = 16;
+
+/// Configuration key of the largest payload, in bytes, the provider sends in a single message.
+/// Payloads are limited by the max payload of the NATS server regardless, see
+/// [`ProviderConnection::max_payload`]
+pub const MAX_PAYLOAD_BYTES_KEY: &str = "max_payload_bytes";
+
+/// Information about the host running the provider and its lattice, see [`ProviderConnection::host_info`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostInfo {
+    /// ID (public key) of the host
+    pub host_id: String,
+    /// Human-friendly name of the host
+    pub friendly_name: String,
+    /// Current labels of the host, e.g. `hostcore.region`
+    pub labels: HashMap<String, String>,
+    /// Version of the host
+    pub version: String,
+    /// Name of the lattice the host and provider belong to
+    pub lattice: String,
+}
+
+/// Change to the set of links of a provider, see [`ProviderConnection::link_events`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkEvent {
+    /// A link was established with the provider as either source or target
+    Put(InterfaceLinkDefinition),
+    /// A link was deleted
+    Deleted {
+        source_id: String,
+        target: String,
+        name: String,
+    },
+}
 
 #[derive(Clone)]
 pub struct ProviderConnection {
@@ -676,8 +2042,8 @@ pub struct ProviderConnection {
     /// source of the link. Indexed by the component ID of the target
     source_links: Arc<RwLock<HashMap<LatticeTarget, InterfaceLinkDefinition>>>,
     /// Links from other components to the provider, aka where the provider is the
-    /// target of the link. Indexed by the component ID of the source
-    target_links: Arc<RwLock<HashMap<SourceId, InterfaceLinkDefinition>>>,
+    /// target of the link. Indexed by the component ID of the source, then by link name
+    target_links: Arc<RwLock<HashMap<SourceId, HashMap<LinkName, InterfaceLinkDefinition>>>>,
 
     /// NATS client used for performing RPCs
     nats: Arc<async_nats::Client>,
@@ -687,9 +2053,45 @@ pub struct ProviderConnection {
     host_id: String,
     provider_id: String,
 
-    // TODO: Reference this field to get static config
-    #[allow(unused)]
-    config: HashMap<String, String>,
+    /// Sources of the configuration of the provider, the named configuration of which is replaced
+    /// by the config updates it accepts
+    config: Arc<std::sync::RwLock<ConfigSources>>,
+
+    /// Timeout of wRPC clients for which no explicit timeout is given
+    default_timeout: Duration,
+
+    /// Notified when the provider should shut itself down
+    shutdown_requested: Arc<Notify>,
+    /// Reason passed to the latest shutdown request
+    shutdown_reason: Arc<std::sync::Mutex<Option<ShutdownReason>>>,
+
+    /// Sender of changes to the links of the provider
+    link_events: broadcast::Sender<LinkEvent>,
+
+    /// Information about the host, kept up to date with label changes
+    host_info: Arc<std::sync::RwLock<HostInfo>>,
+    /// Sender of changes to the labels of the host
+    host_labels: broadcast::Sender<HashMap<String, String>>,
+
+    /// Background tasks shut down along with the provider
+    tasks: TaskGroup,
+
+    /// Persisted cache of the links, if configured, see [`crate::link_cache`]
+    link_cache: Option<Arc<LinkCache>>,
+
+    /// Largest payload the provider sends in a single message, if configured with
+    /// [`MAX_PAYLOAD_BYTES_KEY`]
+    max_payload: Option<usize>,
+
+    /// Max age of the state snapshot restored on startup, if state snapshots are enabled, see
+    /// [`crate::state_snapshot`]
+    state_snapshot_max_age: Option<Duration>,
+
+    /// Sources of the links being processed, see [`crate::link_readiness`]
+    link_readiness: LinkReadiness,
+
+    /// Cached wRPC clients, see [`crate::client_cache`]
+    wrpc_clients: ClientCache,
 }
 
 impl fmt::Debug for ProviderConnection {
@@ -702,22 +2104,31 @@ impl fmt::Debug for ProviderConnection {
     }
 }
 
-/// Extracts trace context from incoming headers
+/// Extracts trace context and baggage from incoming headers
 pub fn invocation_context(headers: &HeaderMap) -> Context {
+    let baggage = headers
+        .get(BAGGAGE_HEADER)
+        .map(|header| parse_baggage(header.as_str()))
+        .unwrap_or_default();
     #[cfg(feature = "otel")]
     {
         let trace_context: TraceContext = convert_header_map_to_hashmap(headers)
             .into_iter()
             .collect::<Vec<(String, String)>>();
-        attach_span_context(&trace_context);
+        attach_span_context_with_baggage(&trace_context, &baggage);
     }
     // Determine source ID for the invocation
     let source_id = headers
         .get(WRPC_SOURCE_ID_HEADER_NAME)
         .map_or_else(|| "<unknown>".into(), ToString::to_string);
+    let link_name = headers
+        .get(WRPC_LINK_NAME_HEADER_NAME)
+        .map(ToString::to_string);
     Context {
         component: Some(source_id),
+        link_name,
         tracing: convert_header_map_to_hashmap(headers),
+        baggage,
     }
 }
 
@@ -725,18 +2136,48 @@ impl ProviderConnection {
     pub(crate) fn new(
         nats: Arc<async_nats::Client>,
         provider_id: String,
-        lattice: String,
-        host_id: String,
-        config: HashMap<String, String>,
+        host_info: HostInfo,
+        config_sources: ConfigSources,
+        default_timeout: Duration,
+        link_readiness: LinkReadiness,
     ) -> ProviderInitResult<ProviderConnection> {
+        let config = config_sources.merged();
+        let link_cache = LinkCache::from_config(&config).map(Arc::new);
+        let wrpc_clients = ClientCache::from_config(&config);
+        let state_snapshot_max_age = state_snapshot_max_age_from_config(&config);
+        let max_payload = config
+            .get(MAX_PAYLOAD_BYTES_KEY)
+            .and_then(|max_payload| max_payload.parse().ok());
+        let server_max_payload = nats.server_info().max_payload;
+        if let Some(max_payload) =
+            max_payload.filter(|max_payload| *max_payload > server_max_payload)
+        {
+            warn!(
+                max_payload = format_payload_size(max_payload),
+                server_max_payload = format_payload_size(server_max_payload),
+                "configured max payload exceeds the max payload of the NATS server, payloads are limited to the latter"
+            );
+        }
         Ok(ProviderConnection {
             source_links: Arc::default(),
             target_links: Arc::default(),
             nats,
-            lattice,
-            host_id,
+            lattice: host_info.lattice.clone(),
+            host_id: host_info.host_id.clone(),
             provider_id,
-            config,
+            config: Arc::new(std::sync::RwLock::new(config_sources)),
+            default_timeout,
+            shutdown_requested: Arc::default(),
+            shutdown_reason: Arc::default(),
+            link_events: broadcast::channel(LINK_EVENTS_CAPACITY).0,
+            host_info: Arc::new(std::sync::RwLock::new(host_info)),
+            host_labels: broadcast::channel(HOST_LABELS_CAPACITY).0,
+            tasks: TaskGroup::default(),
+            link_cache,
+            max_payload,
+            state_snapshot_max_age,
+            link_readiness,
+            wrpc_clients,
         })
     }
 
@@ -751,25 +2192,39 @@ impl ProviderConnection {
     }
 
     /// Retrieve a wRPC client that can be used based on the NATS client of this connection,
-    /// customized with headers and duration
+    /// customized with headers and duration. Clients are cached by target, headers and timeout,
+    /// see [`crate::client_cache`]
     ///
     /// # Arguments
     ///
     /// * `target` - Target ID to which invocations will be sent
     /// * `headers` - Additional headers (other than `source-id`, `target-id`) to be placed on the client
-    /// * `timeout` - Timeout to be set on the client (by default if this is unset it will be 10 seconds)
+    /// * `timeout` - Timeout to be set on the client (if this is unset it will be [`Self::default_timeout`])
     #[must_use]
     pub fn get_wrpc_client_custom(
         &self,
         target: &str,
         headers: Option<HashMap<String, String>>,
         timeout: Option<Duration>,
+    ) -> WrpcClient {
+        let headers = headers.unwrap_or_default().into_iter().collect();
+        let timeout = timeout.unwrap_or(self.default_timeout);
+        self.wrpc_clients
+            .get_or_create(target, headers, timeout, |headers| {
+                self.new_wrpc_client(target, headers, timeout)
+            })
+    }
+
+    /// Create a wRPC client invoking `target` with `headers` and `timeout`, bypassing the cache
+    fn new_wrpc_client(
+        &self,
+        target: &str,
+        headers: &BTreeMap<String, String>,
+        timeout: Duration,
     ) -> WrpcClient {
         let mut hmap = HeaderMap::new();
-        if let Some(values) = headers {
-            for (k, v) in &values {
-                hmap.insert(k.as_str(), v.as_str());
-            }
+        for (k, v) in headers {
+            hmap.insert(k.as_str(), v.as_str());
         }
         hmap.insert("source-id", self.provider_id.as_str());
         hmap.insert("target-id", target);
@@ -778,10 +2233,90 @@ impl ProviderConnection {
             &self.lattice,
             target,
             hmap,
-            timeout.unwrap_or_else(|| Duration::from_secs(10)),
+            timeout,
         ))
     }
 
+    /// Create and cache the default wRPC clients of `targets` ahead of time, so that the first
+    /// invocations of each target do not pay for it, e.g. once links were replayed on startup.
+    /// See [`Self::get_wrpc_client`]
+    pub fn prewarm(&self, targets: &[&str]) {
+        for target in targets {
+            let _ = self.get_wrpc_client(target);
+        }
+    }
+
+    /// Statistics of the cache of wRPC clients, see [`crate::client_cache`]
+    #[must_use]
+    pub fn wrpc_client_cache_stats(&self) -> ClientCacheStats {
+        self.wrpc_clients.stats()
+    }
+
+    /// Retrieve a wRPC client invoking the target of the link `ld`. Invocations carry the name of
+    /// the link in a `link-name` header, so that the target can tell several links from this
+    /// provider apart, see [`Context::link_name`]
+    #[must_use]
+    pub fn get_wrpc_client_for_link(&self, ld: &InterfaceLinkDefinition) -> WrpcClient {
+        self.get_wrpc_client_custom(
+            &ld.target,
+            Some(HashMap::from([(
+                WRPC_LINK_NAME_HEADER_NAME.to_string(),
+                ld.name.clone(),
+            )])),
+            None,
+        )
+    }
+
+    /// Default timeout of wRPC clients retrieved from this connection, as supplied by the host.
+    /// Providers can use this to derive deadlines for their own operations
+    #[must_use]
+    pub fn default_timeout(&self) -> Duration {
+        self.default_timeout
+    }
+
+    /// Sources of the links the provider is processing, invocations from which are parked until
+    /// the links are processed, see [`crate::link_readiness`]
+    #[must_use]
+    pub fn link_readiness(&self) -> &LinkReadiness {
+        &self.link_readiness
+    }
+
+    /// Max payload of a single message sent by the provider, in bytes: the max payload of the NATS
+    /// server, lowered to the value of [`MAX_PAYLOAD_BYTES_KEY`] if configured. Invocation
+    /// parameters and events larger than this should be streamed
+    #[must_use]
+    pub fn max_payload(&self) -> usize {
+        let server_max_payload = self.nats.server_info().max_payload;
+        self.max_payload.map_or(server_max_payload, |max_payload| {
+            max_payload.min(server_max_payload)
+        })
+    }
+
+    /// Publish an event with `payload` on `subject`, failing with a descriptive error rather than
+    /// one of the transport if it exceeds [`Self::max_payload`]
+    pub async fn publish_event(
+        &self,
+        subject: impl ToSubject,
+        payload: impl Into<Bytes>,
+    ) -> ProviderInvocationResult<()> {
+        let payload = payload.into();
+        let max_payload = self.max_payload();
+        if payload.len() > max_payload {
+            return Err(PayloadTooLarge {
+                kind: "event".to_string(),
+                size: Some(payload.len()),
+                max_payload,
+            }
+            .into());
+        }
+        self.nats.publish(subject, payload).await.map_err(|err| {
+            ProviderInvocationError::Unavailable {
+                message: format!("failed to publish event: {err}"),
+                retry_after: None,
+            }
+        })
+    }
+
     /// Get the provider key that was assigned to this host at startup
     #[must_use]
     pub fn provider_key(&self) -> &str {
@@ -795,26 +2330,147 @@ impl ProviderConnection {
             self.source_links
                 .write()
                 .await
-                .insert(ld.target.to_string(), ld);
+                .insert(ld.target.to_string(), ld.clone());
         } else {
             self.target_links
                 .write()
                 .await
-                .insert(ld.source_id.to_string(), ld);
+                .entry(ld.source_id.to_string())
+                .or_default()
+                .insert(ld.name.to_string(), ld.clone());
         }
+        self.persist_links().await;
+        // There may not be any subscribers, which is fine
+        let _ = self.link_events.send(LinkEvent::Put(ld));
     }
 
     /// Deletes link from the [ProviderConnection], either a source link or target link
-    /// based on if the provider is the source or target of the link
+    /// based on if the provider is the source or target of the link. All links from `source_id`
+    /// to `target` are deleted, whatever their name
     pub async fn delete_link(&self, source_id: &str, target: &str) {
-        if source_id == self.provider_id {
-            self.source_links.write().await.remove(source_id);
+        self.remove_links(source_id, target, None).await;
+    }
+
+    /// Deletes the link named `link_name` from `source_id` to `target` from the
+    /// [ProviderConnection], keeping other links between them
+    pub async fn delete_named_link(&self, source_id: &str, target: &str, link_name: &str) {
+        self.remove_links(source_id, target, Some(link_name)).await;
+    }
+
+    async fn remove_links(&self, source_id: &str, target: &str, link_name: Option<&str>) {
+        let deleted: Vec<_> = if source_id == self.provider_id {
+            let mut source_links = self.source_links.write().await;
+            match source_links.get(target) {
+                Some(ld) if link_name.map_or(true, |name| name == ld.name) => {
+                    source_links.remove(target).into_iter().collect()
+                }
+                _ => Vec::new(),
+            }
         } else if target == self.provider_id {
-            self.target_links.write().await.remove(target);
+            let mut target_links = self.target_links.write().await;
+            let Some(links) = target_links.get_mut(source_id) else {
+                return;
+            };
+            let deleted = match link_name {
+                Some(name) => links.remove(name).into_iter().collect(),
+                None => links.drain().map(|(_, ld)| ld).collect(),
+            };
+            if links.is_empty() {
+                target_links.remove(source_id);
+            }
+            deleted
+        } else {
+            Vec::new()
+        };
+        if deleted.is_empty() {
+            return;
+        }
+        self.persist_links().await;
+        for ld in deleted {
+            // Cached clients invoking the other end of the link are recreated if still needed
+            let other = if ld.source_id == self.provider_id {
+                &ld.target
+            } else {
+                &ld.source_id
+            };
+            self.wrpc_clients.evict_target(other);
+            let _ = self.link_events.send(LinkEvent::Deleted {
+                source_id: ld.source_id,
+                target: ld.target,
+                name: ld.name,
+            });
+        }
+    }
+
+    /// Returns the link named `link_name` from `source_id` to the provider, e.g. to find the
+    /// configuration applying to an invocation from its [`Context::component`] and
+    /// [`Context::link_name`]. When no link name is given, the link is only returned if it is the
+    /// only link from `source_id`
+    pub async fn lookup_link(
+        &self,
+        source_id: &str,
+        link_name: Option<&str>,
+    ) -> Option<InterfaceLinkDefinition> {
+        let target_links = self.target_links.read().await;
+        let links = target_links.get(source_id)?;
+        match link_name {
+            Some(name) => links.get(name).cloned(),
+            None if links.len() == 1 => links.values().next().cloned(),
+            None => None,
+        }
+    }
+
+    /// Subscribe to changes to the links of the provider.
+    ///
+    /// Events are sent after the link maps of this [ProviderConnection] have been updated, so
+    /// [`Self::snapshot_links`] always reflects an event by the time it is received. This also
+    /// applies to the links the provider receives at startup, which are replayed as [`LinkEvent::Put`]
+    /// events after [`get_connection`] becomes available.
+    ///
+    /// To track links without missing any changes, subscribe first and then take a snapshot:
+    /// events received afterwards may already be reflected in the snapshot, but none are lost.
+    /// Subscribers which fall behind by more than 256 events receive a
+    /// [`broadcast::error::RecvError::Lagged`] error and should take a new snapshot.
+    #[must_use]
+    pub fn link_events(&self) -> broadcast::Receiver<LinkEvent> {
+        self.link_events.subscribe()
+    }
+
+    /// Returns a consistent copy of all links of the provider, both where it is the source and the target
+    pub async fn snapshot_links(&self) -> Vec<InterfaceLinkDefinition> {
+        let source_links = self.source_links.read().await;
+        let target_links = self.target_links.read().await;
+        source_links
+            .values()
+            .chain(target_links.values().flat_map(HashMap::values))
+            .cloned()
+            .collect()
+    }
+
+    /// Write the links to the link cache, if configured
+    async fn persist_links(&self) {
+        if let Some(cache) = &self.link_cache {
+            cache.store(self.snapshot_links()).await;
         }
     }
 
-    /// Returns true if the source is linked to this provider or if the provider is linked to the target
+    /// Record that the host put or deleted the link named `link_name` from `source_id` to
+    /// `target_id`, so that it is not deleted as stale if it was restored from the link cache
+    fn confirm_cached_link(&self, source_id: &str, target_id: &str, link_name: &str) {
+        if let Some(cache) = &self.link_cache {
+            cache.confirm(&LinkKey::new(source_id, target_id, link_name));
+        }
+    }
+
+    /// Returns the targets of all links where the provider is the source
+    pub(crate) async fn source_link_targets(&self) -> Vec<LatticeTarget> {
+        let mut targets: Vec<_> = self.source_links.read().await.keys().cloned().collect();
+        targets.sort();
+        targets
+    }
+
+    /// Returns true if the source is linked to this provider or if the provider is linked to the
+    /// target, under any link name
     pub async fn is_linked(&self, source_id: &str, target_id: &str) -> bool {
         // Provider is the source of the link, so we check if the target is linked
         if self.provider_id == source_id {
@@ -828,6 +2484,129 @@ impl ProviderConnection {
         }
     }
 
+    /// Returns true if the link named `link_name` from the source to the target is stored, other
+    /// links between them under different names are not taken into account
+    pub async fn is_linked_with_name(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        link_name: &str,
+    ) -> bool {
+        if self.provider_id == source_id {
+            self.source_links
+                .read()
+                .await
+                .get(target_id)
+                .is_some_and(|ld| ld.name == link_name)
+        } else if self.provider_id == target_id {
+            self.target_links
+                .read()
+                .await
+                .get(source_id)
+                .is_some_and(|links| links.contains_key(link_name))
+        } else {
+            false
+        }
+    }
+
+    /// Returns information about the host running the provider. Labels reflect the latest
+    /// changes published by the host
+    #[must_use]
+    pub fn host_info(&self) -> HostInfo {
+        self.host_info
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the effective configuration of the provider: the named configuration supplied on
+    /// startup, or by the latest config update the provider accepted, merged over the values
+    /// passed through by the host from its environment, see [`crate::config_sources`]
+    #[must_use]
+    pub fn config(&self) -> HashMap<String, String> {
+        self.config
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .merged()
+    }
+
+    /// Returns the sources the effective configuration of the provider is merged from
+    #[must_use]
+    pub fn config_sources(&self) -> ConfigSources {
+        self.config
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the sources of the effective configuration of the provider
+    pub(crate) fn set_config_sources(&self, config_sources: ConfigSources) {
+        *self
+            .config
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = config_sources;
+    }
+
+    /// Subscribe to changes to the labels of the host running the provider. Each event contains
+    /// the complete set of labels after the change, and is sent after [`Self::host_info`] has
+    /// been updated.
+    #[must_use]
+    pub fn watch_host_labels(&self) -> broadcast::Receiver<HashMap<String, String>> {
+        self.host_labels.subscribe()
+    }
+
+    /// Replace the labels of the host, notifying watchers if they changed
+    pub(crate) fn update_host_labels(&self, labels: HashMap<String, String>) {
+        {
+            let mut host_info = self
+                .host_info
+                .write()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if host_info.labels == labels {
+                return;
+            }
+            host_info.labels = labels.clone();
+        }
+        // There may not be any subscribers, which is fine
+        let _ = self.host_labels.send(labels);
+    }
+
+    /// Background tasks of the provider. Tasks spawned here are cancelled when the provider begins
+    /// to shut down, and the shutdown is acknowledged to the host once they finished, see
+    /// [`TaskGroup`]
+    #[must_use]
+    pub fn tasks(&self) -> &TaskGroup {
+        &self.tasks
+    }
+
+    /// Window deduplicating IDs of messages or requests processed by all instances of this
+    /// provider in the lattice, see [`DedupWindow`]
+    #[must_use]
+    pub fn dedup_window(&self) -> DedupWindow {
+        DedupWindow::new((*self.nats).clone(), &self.lattice, &self.provider_id)
+    }
+
+    /// Request the provider to shut itself down, as if the host had sent a shutdown command.
+    ///
+    /// The reason is passed to [`Provider::shutdown_with_reason`], e.g. [`ShutdownReason::Idle`]
+    /// for a provider which has no links left to serve.
+    pub fn request_shutdown(&self, reason: ShutdownReason) {
+        *self
+            .shutdown_reason
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(reason);
+        self.shutdown_requested.notify_one();
+    }
+
+    /// Take the reason passed to the latest shutdown request
+    fn take_shutdown_reason(&self) -> ShutdownReason {
+        self.shutdown_reason
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take()
+            .unwrap_or_else(|| ShutdownReason::InternalError("unknown".to_string()))
+    }
+
     /// flush nats - called before main process exits
     pub(crate) async fn flush(&self) {
         if let Err(err) = self.nats.flush().await {
@@ -835,3 +2614,1001 @@ impl ProviderConnection {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    async fn test_connection() -> ProviderConnection {
+        test_connection_with_timeout(DEFAULT_WRPC_TIMEOUT).await
+    }
+
+    async fn test_connection_with_timeout(default_timeout: Duration) -> ProviderConnection {
+        test_connection_with_config(default_timeout, HashMap::default()).await
+    }
+
+    async fn test_connection_with_config(
+        default_timeout: Duration,
+        config: impl Into<ConfigSources>,
+    ) -> ProviderConnection {
+        let nats = async_nats::ConnectOptions::new()
+            .retry_on_initial_connect()
+            .connect("127.0.0.1:1")
+            .await
+            .expect("client should be created without a server");
+        let config: ConfigSources = config.into();
+        let link_readiness = LinkReadiness::from_config(&config.merged());
+        ProviderConnection::new(
+            Arc::new(nats),
+            "provider".into(),
+            HostInfo {
+                host_id: "host".into(),
+                lattice: "default".into(),
+                ..Default::default()
+            },
+            config,
+            default_timeout,
+            link_readiness,
+        )
+        .expect("connection should be created")
+    }
+
+    fn link(source_id: &str, target: &str) -> InterfaceLinkDefinition {
+        InterfaceLinkDefinition {
+            source_id: source_id.into(),
+            target: target.into(),
+            name: "default".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_link_events_follow_map_updates() {
+        let connection = test_connection().await;
+        let mut events = connection.link_events();
+
+        let source = link("provider", "component-a");
+        let target = link("component-b", "provider");
+        connection.put_link(source.clone()).await;
+        connection.put_link(target.clone()).await;
+
+        // Each event is only observable once the link maps reflect it
+        assert_eq!(events.recv().await.unwrap(), LinkEvent::Put(source.clone()));
+        assert!(connection.is_linked("provider", "component-a").await);
+        assert_eq!(events.recv().await.unwrap(), LinkEvent::Put(target.clone()));
+        let mut links = connection.snapshot_links().await;
+        links.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+        assert_eq!(links, vec![target.clone(), source.clone()]);
+
+        connection.delete_link("provider", "component-a").await;
+        assert_eq!(
+            events.recv().await.unwrap(),
+            LinkEvent::Deleted {
+                source_id: "provider".into(),
+                target: "component-a".into(),
+                name: "default".into(),
+            }
+        );
+        assert!(!connection.is_linked("provider", "component-a").await);
+        assert_eq!(connection.snapshot_links().await, vec![target]);
+
+        // Deleting a link that does not exist changes nothing and emits no event
+        connection.delete_link("provider", "component-a").await;
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_link_by_link_name() {
+        let connection = test_connection().await;
+        let named_link = |name: &str, url: &str| InterfaceLinkDefinition {
+            name: name.into(),
+            target_config: HashMap::from([("url".to_string(), url.to_string())]),
+            ..link("component-a", "provider")
+        };
+        let primary = named_link("primary", "redis://primary");
+        let replica = named_link("replica", "redis://replica");
+        connection.put_link(primary.clone()).await;
+        connection.put_link(replica.clone()).await;
+        assert_eq!(connection.snapshot_links().await.len(), 2);
+
+        // Invocations resolve the configuration of the link named in their headers
+        for ld in [&primary, &replica] {
+            let mut headers = connection
+                .get_wrpc_client_for_link(&InterfaceLinkDefinition {
+                    source_id: "provider".into(),
+                    target: "component-a".into(),
+                    name: ld.name.clone(),
+                    ..Default::default()
+                })
+                .0
+                .headers_mut()
+                .clone();
+            headers.insert(WRPC_SOURCE_ID_HEADER_NAME, "component-a");
+            let context = invocation_context(&headers);
+            assert_eq!(context.link_name.as_deref(), Some(ld.name.as_str()));
+            let resolved = connection
+                .lookup_link(
+                    context.component.as_deref().unwrap(),
+                    context.link_name.as_deref(),
+                )
+                .await
+                .expect("link should be found");
+            assert_eq!(resolved.target_config, ld.target_config);
+        }
+        // Without a link name, the link is ambiguous
+        assert!(connection.lookup_link("component-a", None).await.is_none());
+        assert!(connection
+            .lookup_link("component-b", Some("primary"))
+            .await
+            .is_none());
+
+        // Deleting one link keeps the other
+        connection
+            .delete_named_link("component-a", "provider", "primary")
+            .await;
+        assert!(connection.is_linked("component-a", "provider").await);
+        assert_eq!(
+            connection.lookup_link("component-a", None).await,
+            Some(replica)
+        );
+        connection.delete_link("component-a", "provider").await;
+        assert!(!connection.is_linked("component-a", "provider").await);
+    }
+
+    #[tokio::test]
+    async fn test_named_links_from_link_put() {
+        struct TestProvider;
+        impl Provider for TestProvider {}
+
+        let connection = test_connection().await;
+        let (quit_tx, quit_rx) = broadcast::channel(1);
+        let (_health_tx, health) = mpsc::channel(1);
+        let (_shutdown_tx, shutdown) = mpsc::channel(1);
+        let (_prepare_shutdown_tx, prepare_shutdown) = mpsc::channel(1);
+        let (_config_update_tx, config_update) = mpsc::channel(1);
+        let (link_put_tx, link_put) = mpsc::channel(1);
+        let (_link_del_tx, link_del) = mpsc::channel(1);
+        let (_host_labels_tx, host_labels) = mpsc::channel(1);
+        let receivers = ProviderCommandReceivers {
+            health,
+            shutdown,
+            prepare_shutdown,
+            config_update,
+            link_put,
+            link_del,
+            host_labels,
+            tasks: Vec::new(),
+        };
+
+        let named_link = |name: &str| InterfaceLinkDefinition {
+            name: name.into(),
+            ..link("component-a", "provider")
+        };
+        let commands = handle_provider_commands(
+            TestProvider,
+            &connection,
+            quit_rx,
+            quit_tx.clone(),
+            receivers,
+        );
+        let put_links = async {
+            for ld in [
+                named_link("primary"),
+                named_link("replica"),
+                named_link("primary"),
+            ] {
+                let (tx, rx) = oneshot::channel();
+                link_put_tx
+                    .send((ld, tx))
+                    .await
+                    .expect("link put should be sent");
+                rx.await.expect("link put should be handled");
+            }
+            quit_tx.send(()).expect("quit should be sent");
+        };
+        tokio::join!(commands, put_links);
+
+        // A second link from the same source under another name is not a duplicate
+        let mut names: Vec<_> = connection
+            .snapshot_links()
+            .await
+            .into_iter()
+            .map(|ld| ld.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["primary", "replica"]);
+        for name in ["primary", "replica"] {
+            assert!(
+                connection
+                    .is_linked_with_name("component-a", "provider", name)
+                    .await
+            );
+            assert_eq!(
+                connection.lookup_link("component-a", Some(name)).await,
+                Some(named_link(name))
+            );
+        }
+        assert!(
+            !connection
+                .is_linked_with_name("component-a", "provider", "other")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_startup_links_are_replayed() {
+        struct TestProvider;
+        impl Provider for TestProvider {}
+
+        let connection = test_connection().await;
+        let mut events = connection.link_events();
+
+        let startup_links = vec![
+            link("provider", "component-a"),
+            link("component-b", "provider"),
+        ];
+        for ld in startup_links.clone() {
+            receive_link_for_provider(&TestProvider, &connection, ld)
+                .await
+                .expect("link should be received");
+        }
+
+        for ld in startup_links {
+            assert_eq!(events.recv().await.unwrap(), LinkEvent::Put(ld));
+        }
+        assert_eq!(connection.snapshot_links().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_links_are_restored_from_cache() {
+        struct TestProvider;
+        impl Provider for TestProvider {}
+
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let path = dir.path().join("cache").join("links.json");
+        let config = HashMap::from([(
+            crate::link_cache::LINK_CACHE_PATH_CONFIG.to_string(),
+            path.to_string_lossy().into_owned(),
+        )]);
+
+        let kept = link("component-a", "provider");
+        let stale = link("provider", "component-b");
+        let reput = link("component-c", "provider");
+        // Same source and target as `reput`, but under a name the host does not put again
+        let stale_named = InterfaceLinkDefinition {
+            name: "other".into(),
+            ..reput.clone()
+        };
+        let previous = test_connection_with_config(DEFAULT_WRPC_TIMEOUT, config.clone()).await;
+        for ld in [&kept, &stale, &reput, &stale_named] {
+            previous.put_link(ld.clone()).await;
+        }
+        drop(previous);
+
+        // A restarted provider has the cached links before the host replays its links
+        let connection = test_connection_with_config(DEFAULT_WRPC_TIMEOUT, config).await;
+        let restored = restore_cached_links(&TestProvider, &connection, &[kept.clone()]).await;
+        assert_eq!(restored.len(), 4);
+        assert!(connection.is_linked("component-a", "provider").await);
+        assert!(connection.is_linked("provider", "component-b").await);
+        assert!(
+            connection
+                .is_linked_with_name("component-c", "provider", "other")
+                .await
+        );
+
+        // Links the host neither supplied at startup nor put again are pruned after the grace period
+        connection.confirm_cached_link("component-c", "provider", "default");
+        prune_stale_links(&TestProvider, &connection).await;
+        assert!(connection.is_linked("component-a", "provider").await);
+        assert!(!connection.is_linked("provider", "component-b").await);
+        assert!(
+            connection
+                .is_linked_with_name("component-c", "provider", "default")
+                .await
+        );
+        assert!(
+            !connection
+                .is_linked_with_name("component-c", "provider", "other")
+                .await
+        );
+
+        let cached: Vec<InterfaceLinkDefinition> =
+            serde_json::from_slice(&std::fs::read(&path).expect("cache should be written"))
+                .expect("cache should parse");
+        assert_eq!(cached.len(), 2);
+        assert!(cached.contains(&kept) && cached.contains(&reput));
+        assert!(!dir.path().join("cache").join("links.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_host_requested_shutdown_ack() {
+        let reason = ShutdownReason::HostRequested {
+            host_id: "NHOST".to_string(),
+        };
+        let ack = serde_json::to_vec(&ShutdownAck::from(&reason)).expect("ack should serialize");
+        let ack: serde_json::Value = serde_json::from_slice(&ack).expect("ack should deserialize");
+        assert_eq!(ack["message"], "shutting down");
+        assert_eq!(ack["reason"], "shutdown requested by host NHOST");
+        assert_eq!(ack["restart"], false);
+
+        let ack = ShutdownAck::from(&ShutdownReason::InternalError("wedged".to_string()));
+        assert_eq!(ack.reason, "internal error: wedged");
+        assert!(ack.restart);
+
+        let ack = ShutdownAck::from(&ShutdownReason::Update {
+            host_id: "NHOST".to_string(),
+        });
+        assert_eq!(ack.reason, "update requested by host NHOST");
+        assert!(!ack.restart);
+    }
+
+    #[test]
+    fn test_shutdown_message_targets() {
+        let parse = |payload: serde_json::Value| -> ShutdownMessage {
+            serde_json::from_value(payload).expect("shutdown message should parse")
+        };
+
+        // Requests target a single host by default
+        let req = parse(serde_json::json!({ "host_id": "NHOST" }));
+        assert!(req.targets("NHOST", "default"));
+        assert!(!req.targets("NOTHER", "default"));
+        assert_eq!(
+            req.reason(),
+            ShutdownReason::HostRequested {
+                host_id: "NHOST".to_string()
+            }
+        );
+
+        for req in [
+            parse(serde_json::json!({ "host_id": SHUTDOWN_ALL_HOSTS })),
+            parse(serde_json::json!({ "scope": SHUTDOWN_SCOPE_LATTICE })),
+        ] {
+            assert!(req.targets("NHOST", "default"));
+            assert!(req.targets("NOTHER", "other"));
+            assert_eq!(req.reason(), ShutdownReason::LatticeRequested);
+        }
+
+        let req = parse(serde_json::json!({ "host_id": SHUTDOWN_ALL_HOSTS, "link_name": "other" }));
+        assert!(req.targets("NHOST", "other"));
+        assert!(!req.targets("NHOST", "default"));
+        let req = parse(serde_json::json!({ "host_id": "NHOST", "link_name": "other" }));
+        assert!(!req.targets("NOTHER", "other"));
+
+        // Payloads without a host ID target no host
+        assert!(!parse(serde_json::json!({})).targets("NHOST", "default"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_with_reason_defaults_to_shutdown() {
+        #[derive(Default)]
+        struct TestProvider {
+            shutdown: AtomicBool,
+        }
+
+        impl Provider for TestProvider {
+            async fn shutdown(&self) -> Result<()> {
+                self.shutdown.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+
+        let provider = TestProvider::default();
+        provider
+            .shutdown_with_reason(&ShutdownReason::Idle)
+            .await
+            .expect("shutdown should succeed");
+        assert!(provider.shutdown.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_tasks_are_drained_before_shutdown_ack() {
+        struct TestProvider;
+        impl Provider for TestProvider {}
+
+        let connection = test_connection().await;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        connection
+            .tasks()
+            .spawn_interval("poller", Duration::from_millis(10), || async {});
+        connection.tasks().spawn("watcher", {
+            let cancelled = Arc::clone(&cancelled);
+            |cancel| async move {
+                cancel.cancelled().await;
+                // Cleanup of the task is awaited before the shutdown is acknowledged
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                cancelled.store(true, Ordering::SeqCst);
+            }
+        });
+        assert_eq!(connection.tasks().status(), vec!["poller", "watcher"]);
+
+        let (_health_tx, health) = mpsc::channel(1);
+        let (shutdown_tx, shutdown) = mpsc::channel(1);
+        let (_prepare_shutdown_tx, prepare_shutdown) = mpsc::channel(1);
+        let (_config_update_tx, config_update) = mpsc::channel(1);
+        let (_link_put_tx, link_put) = mpsc::channel(1);
+        let (_link_del_tx, link_del) = mpsc::channel(1);
+        let (_host_labels_tx, host_labels) = mpsc::channel(1);
+        let (quit_tx, quit_rx) = broadcast::channel(1);
+        let receivers = ProviderCommandReceivers {
+            health,
+            shutdown,
+            prepare_shutdown,
+            config_update,
+            link_put,
+            link_del,
+            host_labels,
+            tasks: vec![],
+        };
+
+        let (ack_tx, ack_rx) = oneshot::channel();
+        shutdown_tx
+            .send((
+                ShutdownReason::HostRequested {
+                    host_id: "host".into(),
+                },
+                ack_tx,
+            ))
+            .await
+            .expect("shutdown request should be sent");
+        select! {
+            () = handle_provider_commands(TestProvider, &connection, quit_rx, quit_tx, receivers) => {
+                panic!("command handling should not stop without quit");
+            }
+            res = ack_rx => {
+                res.expect("shutdown should be acknowledged");
+            }
+        }
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(connection.tasks().is_cancelled());
+        assert!(connection.tasks().status().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prepare_shutdown_reports_timeout() {
+        struct TestProvider;
+        impl Provider for TestProvider {
+            async fn prepare_shutdown(&self, timeout: Duration) -> Result<DrainReport> {
+                // Wedged provider, never finishes draining
+                tokio::time::sleep(timeout * 10).await;
+                Ok(DrainReport {
+                    drained: true,
+                    abandoned: Some(0),
+                })
+            }
+        }
+
+        let connection = test_connection().await;
+        let (_health_tx, health) = mpsc::channel(1);
+        let (_shutdown_tx, shutdown) = mpsc::channel(1);
+        let (prepare_shutdown_tx, prepare_shutdown) = mpsc::channel(1);
+        let (_config_update_tx, config_update) = mpsc::channel(1);
+        let (_link_put_tx, link_put) = mpsc::channel(1);
+        let (_link_del_tx, link_del) = mpsc::channel(1);
+        let (_host_labels_tx, host_labels) = mpsc::channel(1);
+        let (quit_tx, quit_rx) = broadcast::channel(1);
+        let receivers = ProviderCommandReceivers {
+            health,
+            shutdown,
+            prepare_shutdown,
+            config_update,
+            link_put,
+            link_del,
+            host_labels,
+            tasks: vec![],
+        };
+
+        let (report_tx, report_rx) = oneshot::channel();
+        prepare_shutdown_tx
+            .send((Duration::from_millis(50), report_tx))
+            .await
+            .expect("prepare shutdown request should be sent");
+        select! {
+            () = handle_provider_commands(TestProvider, &connection, quit_rx, quit_tx, receivers) => {
+                panic!("command handling should not stop without quit");
+            }
+            res = report_rx => {
+                assert_eq!(
+                    res.expect("drain report should be sent"),
+                    DrainReport {
+                        drained: false,
+                        abandoned: None,
+                    }
+                );
+            }
+        }
+        // Preparing for shutdown does not stop the provider
+        assert!(!connection.tasks().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_env_values_in_effective_config() {
+        let connection = test_connection_with_config(
+            Duration::from_secs(1),
+            ConfigSources::from_host_data(&HostData {
+                env_values: vec![
+                    ("region".to_string(), "eu-west-1".to_string()),
+                    ("pool_size".to_string(), "2".to_string()),
+                ],
+                config: HashMap::from([("pool_size".to_string(), "8".to_string())]),
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(
+            connection.config(),
+            HashMap::from([
+                ("region".to_string(), "eu-west-1".to_string()),
+                ("pool_size".to_string(), "8".to_string()),
+            ])
+        );
+        assert_eq!(
+            connection.config_sources().env_values().get("pool_size"),
+            Some(&"2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_update_validated_before_apply() {
+        struct TestProvider {
+            applied: Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>,
+        }
+        impl Provider for TestProvider {
+            async fn validate_config_update(
+                &self,
+                update: &HashMap<String, String>,
+            ) -> Result<(), ConfigRejection> {
+                match update.get("pool_size").map(|size| size.parse::<u32>()) {
+                    Some(Ok(0) | Err(_)) => {
+                        Err(ConfigRejection::new("pool_size must be a positive integer")
+                            .with_keys(["pool_size"]))
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            async fn on_config_update(&self, update: &HashMap<String, String>) -> Result<()> {
+                self.applied
+                    .lock()
+                    .expect("applied updates should be lockable")
+                    .push(update.clone());
+                Ok(())
+            }
+        }
+
+        let config = |pool_size: &str| {
+            HashMap::from([
+                ("url".to_string(), "postgres://db".to_string()),
+                ("pool_size".to_string(), pool_size.to_string()),
+            ])
+        };
+        let applied = Arc::default();
+        let provider = TestProvider {
+            applied: Arc::clone(&applied),
+        };
+        let connection = test_connection_with_config(Duration::from_secs(1), config("4")).await;
+        let (_health_tx, health) = mpsc::channel(1);
+        let (_shutdown_tx, shutdown) = mpsc::channel(1);
+        let (_prepare_shutdown_tx, prepare_shutdown) = mpsc::channel(1);
+        let (config_update_tx, config_update) = mpsc::channel(1);
+        let (_link_put_tx, link_put) = mpsc::channel(1);
+        let (_link_del_tx, link_del) = mpsc::channel(1);
+        let (_host_labels_tx, host_labels) = mpsc::channel(1);
+        let (quit_tx, quit_rx) = broadcast::channel(1);
+        let receivers = ProviderCommandReceivers {
+            health,
+            shutdown,
+            prepare_shutdown,
+            config_update,
+            link_put,
+            link_del,
+            host_labels,
+            tasks: vec![],
+        };
+
+        let updates = async {
+            let (valid_tx, valid_rx) = oneshot::channel();
+            config_update_tx
+                .send((config("8"), valid_tx))
+                .await
+                .expect("config update should be sent");
+            let valid = valid_rx.await.expect("config update should be answered");
+            let (invalid_tx, invalid_rx) = oneshot::channel();
+            config_update_tx
+                .send((config("0"), invalid_tx))
+                .await
+                .expect("config update should be sent");
+            let invalid = invalid_rx.await.expect("config update should be answered");
+            (valid, invalid)
+        };
+        let (valid, invalid) = select! {
+            () = handle_provider_commands(provider, &connection, quit_rx, quit_tx, receivers) => {
+                panic!("command handling should not stop without quit");
+            }
+            outcomes = updates => outcomes,
+        };
+
+        assert_eq!(valid, ConfigUpdateOutcome::accepted());
+        assert_eq!(
+            invalid,
+            ConfigUpdateOutcome::rejected(
+                ConfigRejection::new("pool_size must be a positive integer")
+                    .with_keys(["pool_size"])
+            )
+        );
+        // Only the valid update is applied and becomes the effective config
+        assert_eq!(
+            *applied.lock().expect("applied updates should be lockable"),
+            vec![config("8")]
+        );
+        assert_eq!(connection.config(), config("8"));
+
+        // The rejection is sent to the reply subject as is
+        let reply: serde_json::Value =
+            serde_json::to_value(&invalid).expect("outcome should serialize");
+        assert_eq!(
+            reply,
+            serde_json::json!({
+                "accepted": false,
+                "rejection": {
+                    "reason": "pool_size must be a positive integer",
+                    "keys": ["pool_size"],
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_host_labels_changed_event() {
+        let connection = test_connection().await;
+        assert_eq!(connection.host_info().host_id, "host");
+        let mut labels_rx = connection.watch_host_labels();
+
+        let event = serde_json::to_vec(&serde_json::json!({
+            "specversion": "1.0",
+            "id": "01J0000000000000000000000",
+            "type": "com.wasmcloud.lattice.labels_changed",
+            "source": "host",
+            "datacontenttype": "application/json",
+            "data": {
+                "host_id": "host",
+                "labels": { "hostcore.region": "eu-west-1" },
+            },
+        }))
+        .expect("event should serialize");
+        // Label changes of other hosts in the lattice are ignored
+        assert_eq!(parse_labels_changed(&event, "other"), None);
+        let labels = parse_labels_changed(&event, "host").expect("event should be parsed");
+
+        connection.update_host_labels(labels.clone());
+        assert_eq!(
+            labels_rx.recv().await.expect("watcher should be notified"),
+            labels
+        );
+        assert_eq!(
+            connection.host_info().labels.get("hostcore.region"),
+            Some(&"eu-west-1".to_string())
+        );
+
+        // Watchers are not notified if the labels did not change
+        connection.update_host_labels(labels);
+        assert!(matches!(
+            labels_rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_host_rpc_timeout_is_wrpc_client_default() {
+        let host_data = HostData {
+            default_rpc_timeout_ms: Some(45_000),
+            ..Default::default()
+        };
+        let connection = test_connection_with_timeout(default_rpc_timeout(&host_data)).await;
+        assert_eq!(connection.default_timeout(), Duration::from_secs(45));
+        assert_eq!(
+            connection.get_wrpc_client("target").0.timeout(),
+            Duration::from_secs(45)
+        );
+
+        // An explicit timeout still takes precedence
+        let client =
+            connection.get_wrpc_client_custom("target", None, Some(Duration::from_secs(3)));
+        assert_eq!(client.0.timeout(), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_wrpc_clients_cached_until_link_deleted() {
+        let connection = test_connection_with_timeout(Duration::from_secs(1)).await;
+        connection.put_link(link("provider", "component")).await;
+        connection.prewarm(&["component", "other"]);
+        assert_eq!(connection.wrpc_client_cache_stats().misses, 2);
+
+        let _ = connection.get_wrpc_client("component");
+        let _ = connection.get_wrpc_client("other");
+        let stats = connection.wrpc_client_cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.len), (2, 2, 2));
+
+        // Deleting the link evicts the clients of its target only
+        connection.delete_link("provider", "component").await;
+        let stats = connection.wrpc_client_cache_stats();
+        assert_eq!((stats.evictions, stats.len), (1, 1));
+        let _ = connection.get_wrpc_client("component");
+        assert_eq!(connection.wrpc_client_cache_stats().misses, 3);
+    }
+
+    #[tokio::test]
+    async fn test_cached_wrpc_clients_are_reused() {
+        const TARGETS: usize = 10;
+        const ROUNDS: usize = 10;
+        let connection = test_connection_with_timeout(Duration::from_secs(1)).await;
+        let targets: Vec<String> = (0..TARGETS).map(|i| format!("component-{i}")).collect();
+        let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+        connection.prewarm(&targets);
+
+        // Repeated retrievals reuse the prewarmed clients instead of creating new ones
+        for _ in 0..ROUNDS {
+            for target in &targets {
+                let _ = connection.get_wrpc_client(target);
+            }
+        }
+        let stats = connection.wrpc_client_cache_stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.len),
+            ((TARGETS * ROUNDS) as u64, TARGETS as u64, TARGETS)
+        );
+
+        // Clients with other headers are cached separately
+        let ld = link("provider", "component-0");
+        let _ = connection.get_wrpc_client_for_link(&ld);
+        let _ = connection.get_wrpc_client_for_link(&ld);
+        let stats = connection.wrpc_client_cache_stats();
+        assert_eq!((stats.misses, stats.len), (TARGETS as u64 + 1, TARGETS + 1));
+    }
+
+    #[test]
+    fn test_default_rpc_timeout_is_clamped() {
+        let timeout = |default_rpc_timeout_ms| {
+            default_rpc_timeout(&HostData {
+                default_rpc_timeout_ms,
+                ..Default::default()
+            })
+        };
+        assert_eq!(timeout(None), DEFAULT_WRPC_TIMEOUT);
+        assert_eq!(timeout(Some(0)), MIN_RPC_TIMEOUT);
+        assert_eq!(timeout(Some(u64::MAX)), MAX_RPC_TIMEOUT);
+        assert_eq!(timeout(Some(2_000)), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_subscription_violations() {
+        let violations = SubscriptionViolations::default();
+        let error = |err: &str| {
+            async_nats::Event::ServerError(async_nats::ServerError::Other(err.to_string()))
+        };
+        violations.record(&error(
+            "Permissions Violation for Subscription to \"wasmbus.rpc.default.provider.linkdefs.del\"",
+        ));
+        violations.record(&error(
+            "permissions violation for subscription to \"wasmbus.evt.default.labels_changed\" using queue \"q\"",
+        ));
+        violations.record(&error(
+            "Permissions Violation for Publish to \"wasmbus.rpc.default.provider.health\"",
+        ));
+        violations.record(&async_nats::Event::Disconnected);
+
+        assert!(violations.is_denied("wasmbus.rpc.default.provider.linkdefs.del"));
+        assert!(violations.is_denied("wasmbus.evt.default.labels_changed"));
+        assert!(!violations.is_denied("wasmbus.rpc.default.provider.health"));
+        assert_eq!(
+            denied_subscription_subject("maximum connections exceeded"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_denied_subscriptions_are_stopped() {
+        let error = |subject: &str| {
+            async_nats::Event::ServerError(async_nats::ServerError::Other(format!(
+                "Permissions Violation for Subscription to \"{subject}\""
+            )))
+        };
+        let violations = SubscriptionViolations::default();
+        let (quit_tx, _) = broadcast::channel(1);
+        let core = spawn(std::future::pending());
+        let mut tasks = Vec::new();
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.core("core".to_string(), &core);
+        let (_tx, rx) = mpsc::channel::<()>(1);
+        let _ = subscriptions.optional(
+            &mut tasks,
+            "optional feature",
+            "optional".to_string(),
+            Ok((rx, spawn(std::future::pending()))),
+        );
+        let mut disabled = subscriptions.optional::<()>(
+            &mut tasks,
+            "failed feature",
+            "failed".to_string(),
+            Err(ProviderInitError::Initialization("denied".to_string())),
+        );
+        assert!(disabled.recv().await.is_none());
+        assert_eq!(subscriptions.denied_core(&violations), None);
+
+        // Denials recorded before the watcher starts are handled too
+        violations.record(&error("optional"));
+        let watcher = watch_violations(violations.subscribe(), quit_tx.subscribe(), subscriptions);
+        let optional = tasks.pop().expect("optional subscription task");
+        assert!(optional
+            .await
+            .expect_err("task should be aborted")
+            .is_cancelled());
+        assert!(!core.is_finished());
+
+        violations.record(&error("core"));
+        assert!(core
+            .await
+            .expect_err("task should be aborted")
+            .is_cancelled());
+
+        quit_tx.send(()).expect("watcher should be listening");
+        watcher.await.expect("watcher should stop on quit");
+    }
+
+    #[test]
+    fn test_lattice_rpc_auth_selection() {
+        let auth = |host_data: HostData| {
+            LatticeRpcConnectOptions::from_host_data(&host_data)
+                .expect("host data should be valid")
+                .auth
+        };
+        assert_eq!(auth(HostData::default()), LatticeRpcAuth::None);
+        // Blank values are treated as unset
+        assert_eq!(
+            auth(HostData {
+                lattice_rpc_user_jwt: " ".into(),
+                lattice_rpc_user_token: Some(String::new()),
+                ..Default::default()
+            }),
+            LatticeRpcAuth::None
+        );
+        assert_eq!(
+            auth(HostData {
+                lattice_rpc_user_jwt: "jwt".into(),
+                lattice_rpc_user_seed: " seed\n".into(),
+                ..Default::default()
+            }),
+            LatticeRpcAuth::Jwt {
+                jwt: "jwt".into(),
+                seed: "seed".into()
+            }
+        );
+        assert_eq!(
+            auth(HostData {
+                lattice_rpc_user_token: Some("token".into()),
+                ..Default::default()
+            }),
+            LatticeRpcAuth::Token("token".into())
+        );
+        assert_eq!(
+            auth(HostData {
+                lattice_rpc_user_name: Some("user".into()),
+                lattice_rpc_user_password: Some("password".into()),
+                ..Default::default()
+            }),
+            LatticeRpcAuth::UserPassword {
+                user: "user".into(),
+                password: "password".into()
+            }
+        );
+        assert_eq!(
+            auth(HostData {
+                lattice_rpc_credsfile: Some("/tmp/user.creds".into()),
+                ..Default::default()
+            }),
+            LatticeRpcAuth::CredsFile("/tmp/user.creds".into())
+        );
+    }
+
+    #[test]
+    fn test_lattice_rpc_auth_conflicts() {
+        let err = |host_data: HostData| match LatticeRpcConnectOptions::from_host_data(&host_data) {
+            Err(ProviderInitError::Initialization(err)) => err,
+            other => panic!("expected an initialization error, got {other:?}"),
+        };
+        assert_eq!(
+            err(HostData {
+                lattice_rpc_user_jwt: "jwt".into(),
+                lattice_rpc_user_seed: "seed".into(),
+                lattice_rpc_user_token: Some("token".into()),
+                lattice_rpc_credsfile: Some("/tmp/user.creds".into()),
+                ..Default::default()
+            }),
+            "conflicting lattice RPC authentication supplied by the host, only one of [jwt and seed, token, credentials file] may be set"
+        );
+        assert!(err(HostData {
+            lattice_rpc_user_jwt: "jwt".into(),
+            ..Default::default()
+        })
+        .contains("JWT and seed"));
+        assert!(err(HostData {
+            lattice_rpc_user_password: Some("password".into()),
+            ..Default::default()
+        })
+        .contains("username and password"));
+        assert!(err(HostData {
+            lattice_rpc_tls_cert_file: Some("/tmp/cert.pem".into()),
+            ..Default::default()
+        })
+        .contains("certificate and key"));
+    }
+
+    #[test]
+    fn test_lattice_rpc_tls() {
+        let tls = |host_data: HostData| {
+            LatticeRpcConnectOptions::from_host_data(&host_data)
+                .expect("host data should be valid")
+                .tls
+        };
+        assert_eq!(tls(HostData::default()), LatticeRpcTls::default());
+        // TLS settings combine with any authentication scheme
+        let options = LatticeRpcConnectOptions::from_host_data(&HostData {
+            lattice_rpc_user_token: Some("token".into()),
+            lattice_rpc_tls_ca_file: Some("/tmp/ca.pem".into()),
+            lattice_rpc_tls_cert_file: Some("/tmp/cert.pem".into()),
+            lattice_rpc_tls_key_file: Some("/tmp/key.pem".into()),
+            lattice_rpc_tls_required: true,
+            ..Default::default()
+        })
+        .expect("host data should be valid");
+        assert_eq!(options.auth, LatticeRpcAuth::Token("token".into()));
+        assert_eq!(
+            options.tls,
+            LatticeRpcTls {
+                ca_file: Some("/tmp/ca.pem".into()),
+                client_cert: Some(("/tmp/cert.pem".into(), "/tmp/key.pem".into())),
+                required: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lattice_rpc_connect_options_errors() {
+        let connect_options = |auth| {
+            LatticeRpcConnectOptions {
+                auth,
+                tls: LatticeRpcTls::default(),
+            }
+            .into_connect_options()
+        };
+        assert!(matches!(
+            connect_options(LatticeRpcAuth::Jwt {
+                jwt: "jwt".into(),
+                seed: "not-a-seed".into()
+            })
+            .await,
+            Err(ProviderInitError::Initialization(_))
+        ));
+        assert!(matches!(
+            connect_options(LatticeRpcAuth::CredsFile(
+                "/nonexistent/wasmcloud/user.creds".into()
+            ))
+            .await,
+            Err(ProviderInitError::Initialization(_))
+        ));
+        let seed = nkeys::KeyPair::new_user()
+            .seed()
+            .expect("user key should have a seed");
+        assert!(connect_options(LatticeRpcAuth::Jwt {
+            jwt: "jwt".into(),
+            seed
+        })
+        .await
+        .is_ok());
+        assert!(connect_options(LatticeRpcAuth::Token("token".into()))
+            .await
+            .is_ok());
+    }
+}
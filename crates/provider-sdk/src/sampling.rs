@@ -0,0 +1,70 @@
+//! Sampling of the spans created by the provider SDK
+//!
+//! Providers configured with [`OTEL_SAMPLING_RATIO_CONFIG_KEY`] only record that ratio of the
+//! spans created by the SDK itself (e.g. for serving invocations and handling links), so that busy
+//! providers can reduce their tracing volume. Spans created by the provider and events are not
+//! sampled.
+
+use rand::Rng as _;
+use tracing::subscriber::Interest;
+use tracing::{Metadata, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// Configuration key of the ratio of SDK spans recorded, between `0` (none) and `1` (all, default)
+pub const OTEL_SAMPLING_RATIO_CONFIG_KEY: &str = "otel_sampling_ratio";
+
+/// [`Layer`] recording only a ratio of the spans created by the SDK, and disabling the others
+#[derive(Clone, Copy, Debug)]
+pub struct SdkSpanSampler {
+    ratio: f64,
+}
+
+impl SdkSpanSampler {
+    /// Create a sampler recording `ratio` of the SDK spans, clamped between `0` and `1`
+    #[must_use]
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio: if ratio.is_nan() {
+                1.0
+            } else {
+                ratio.clamp(0.0, 1.0)
+            },
+        }
+    }
+
+    /// Ratio of the SDK spans recorded
+    #[must_use]
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    fn is_sdk_span(metadata: &Metadata<'_>) -> bool {
+        metadata.is_span() && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn sample(&self) -> bool {
+        if self.ratio >= 1.0 {
+            return true;
+        }
+        if self.ratio <= 0.0 {
+            return false;
+        }
+        rand::thread_rng().gen_bool(self.ratio)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SdkSpanSampler {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // Sampled spans are enabled one by one, and interest is shared by all subscribers
+        if !Self::is_sdk_span(metadata) || self.ratio >= 1.0 {
+            Interest::always()
+        } else {
+            Interest::sometimes()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        !Self::is_sdk_span(metadata) || self.sample()
+    }
+}
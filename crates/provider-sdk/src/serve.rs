@@ -0,0 +1,1410 @@
+//! Dynamic serving of provider exports
+//!
+//! Most providers know which interfaces they export at startup and serve them with the `serve`
+//! function generated by `wit-bindgen-wrpc`. Providers that only learn what they can serve at
+//! runtime (e.g. after loading backend plugins once a link is established) can instead use
+//! [`serve_provider_exports_dynamic`] and add or remove exported functions through the returned
+//! [`ServeHandle`].
+//!
+//! All invocation streams are multiplexed onto a single task. Removing an export stops accepting
+//! new invocations for it immediately (dropping the underlying subscription, so callers get a
+//! "no responders" error rather than waiting for a timeout), while invocations that were already
+//! accepted are allowed to complete.
+//!
+//! Inbound invocations are subject to [`ServeLimits`]: at most [`ServeLimits::max_in_flight`]
+//! invocations run at once (once reached, no further invocations are accepted from the transport
+//! until one completes), and optional global and per-source rate limits reject excess invocations
+//! with [`THROTTLED_ERROR`] instead of queueing them. Limits are usually read from the provider
+//! configuration with [`ServeLimits::from_config`] and can be updated at runtime with
+//! [`ServeHandle::set_limits`].
+//!
+//! Invocations still running after a multiple of their deadline (see
+//! [`InboundInvocation::with_deadline`]), or after [`ServeLimits::stuck_invocation_timeout`] when
+//! no deadline is known, are considered stuck: a warning is logged at most once per
+//! [`STUCK_WARNING_INTERVAL`] while they keep running and they are counted by
+//! [`ProviderMetrics::stuck_invocations`]. With [`ServeLimits::abort_stuck_invocations`], stuck
+//! invocations are aborted instead and their callers receive [`STUCK_INVOCATION_ERROR`].
+//!
+//! Before the provider is stopped, [`ServeHandle::drain`] stops serving all exports and waits a
+//! bounded time for in-flight invocations, usually from [`crate::Provider::prepare_shutdown`].
+//!
+//! The same limits and stuck invocation detection apply to invocations served by the serving loops
+//! of [`crate::interfaces`], e.g. [`crate::interfaces::blobstore::serve_blobstore`]. Their limits
+//! are read from the provider configuration at startup and on every configuration update, or set
+//! with [`set_inbound_limits`]. Throttled invocations receive an
+//! [unavailable](crate::ProviderInvocationError::Unavailable) error with [`THROTTLED_ERROR`] as
+//! message.
+
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context as _};
+use futures::stream::{self, AbortHandle, SelectAll};
+use futures::{Stream, StreamExt as _};
+use once_cell::sync::Lazy;
+use tokio::select;
+use tokio::sync::{mpsc, oneshot, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{debug, error, instrument, warn};
+use wasmcloud_core::DrainReport;
+
+use crate::metrics::ProviderMetrics;
+
+type ExportKey = (String, String);
+
+pub(crate) type BoxInvocation = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+pub(crate) type RejectInvocation = Box<dyn FnOnce(&'static str) -> BoxInvocation + Send>;
+
+/// Item of the multiplexed invocation streams, `None` marks the end of an export's stream
+type TaggedInvocation = (ExportKey, Option<anyhow::Result<InboundInvocation>>);
+
+/// Error returned to callers whose invocations are rejected by the inbound rate limits
+pub const THROTTLED_ERROR: &str = "invocation throttled: too many invocations";
+
+/// Error returned to callers whose invocations are aborted as they are stuck
+pub const STUCK_INVOCATION_ERROR: &str =
+    "internal error: invocation aborted after running for too long";
+
+/// Default value of [`ServeLimits::max_in_flight`]
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 1024;
+
+/// Default value of [`ServeLimits::stuck_invocation_timeout`]
+pub const DEFAULT_STUCK_INVOCATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Default value of [`ServeLimits::stuck_invocation_deadline_multiple`]
+pub const DEFAULT_STUCK_INVOCATION_DEADLINE_MULTIPLE: u32 = 2;
+
+/// Interval at which warnings about an invocation that is still stuck are repeated
+pub const STUCK_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Provider config key setting [`ServeLimits::max_in_flight`]
+pub const MAX_CONCURRENT_INVOCATIONS_KEY: &str = "max_concurrent_invocations";
+/// Provider config key setting [`ServeLimits::max_invocations_per_sec`]
+pub const MAX_INVOCATIONS_PER_SEC_KEY: &str = "max_invocations_per_sec";
+/// Provider config key setting [`ServeLimits::per_source_max_invocations_per_sec`]
+pub const PER_SOURCE_MAX_INVOCATIONS_PER_SEC_KEY: &str = "per_source_max_invocations_per_sec";
+/// Provider config key setting [`ServeLimits::stuck_invocation_timeout`], in seconds
+pub const STUCK_INVOCATION_TIMEOUT_SECS_KEY: &str = "stuck_invocation_timeout_secs";
+/// Provider config key setting [`ServeLimits::stuck_invocation_deadline_multiple`]
+pub const STUCK_INVOCATION_DEADLINE_MULTIPLE_KEY: &str = "stuck_invocation_deadline_multiple";
+/// Provider config key setting [`ServeLimits::abort_stuck_invocations`]
+pub const ABORT_STUCK_INVOCATIONS_KEY: &str = "abort_stuck_invocations";
+
+/// Number of per-source rate limits kept before idle ones are dropped
+const MAX_TRACKED_SOURCES: usize = 1024;
+
+/// Limits applied to inbound invocations served through a [`ServeHandle`] or by the serving loops
+/// of [`crate::interfaces`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServeLimits {
+    /// Maximum number of invocations running concurrently across all exports
+    pub max_in_flight: usize,
+    /// Maximum number of invocations accepted per second across all sources
+    pub max_invocations_per_sec: Option<u32>,
+    /// Maximum number of invocations accepted per second from a single source component
+    pub per_source_max_invocations_per_sec: Option<u32>,
+    /// Time after which invocations without a known deadline are considered stuck
+    pub stuck_invocation_timeout: Duration,
+    /// Multiple of the deadline of an invocation after which it is considered stuck
+    pub stuck_invocation_deadline_multiple: u32,
+    /// Whether to abort stuck invocations, responding to the caller with
+    /// [`STUCK_INVOCATION_ERROR`], rather than only warning about them
+    pub abort_stuck_invocations: bool,
+}
+
+impl Default for ServeLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            max_invocations_per_sec: None,
+            per_source_max_invocations_per_sec: None,
+            stuck_invocation_timeout: DEFAULT_STUCK_INVOCATION_TIMEOUT,
+            stuck_invocation_deadline_multiple: DEFAULT_STUCK_INVOCATION_DEADLINE_MULTIPLE,
+            abort_stuck_invocations: false,
+        }
+    }
+}
+
+impl ServeLimits {
+    /// Read limits from provider configuration, see [`MAX_CONCURRENT_INVOCATIONS_KEY`],
+    /// [`MAX_INVOCATIONS_PER_SEC_KEY`], [`PER_SOURCE_MAX_INVOCATIONS_PER_SEC_KEY`],
+    /// [`STUCK_INVOCATION_TIMEOUT_SECS_KEY`], [`STUCK_INVOCATION_DEADLINE_MULTIPLE_KEY`] and
+    /// [`ABORT_STUCK_INVOCATIONS_KEY`].
+    /// Missing, zero or invalid values leave the respective limit at its default.
+    #[must_use]
+    pub fn from_config(config: &HashMap<String, String>) -> Self {
+        let limit = |key: &str| {
+            let value = config.get(key)?;
+            match value.trim().parse::<u32>() {
+                Ok(0) | Err(_) => {
+                    warn!(key, value, "ignoring invalid invocation limit");
+                    None
+                }
+                Ok(limit) => Some(limit),
+            }
+        };
+        Self {
+            max_in_flight: limit(MAX_CONCURRENT_INVOCATIONS_KEY)
+                .map_or(DEFAULT_MAX_IN_FLIGHT, |limit| limit as usize),
+            max_invocations_per_sec: limit(MAX_INVOCATIONS_PER_SEC_KEY),
+            per_source_max_invocations_per_sec: limit(PER_SOURCE_MAX_INVOCATIONS_PER_SEC_KEY),
+            stuck_invocation_timeout: limit(STUCK_INVOCATION_TIMEOUT_SECS_KEY)
+                .map_or(DEFAULT_STUCK_INVOCATION_TIMEOUT, |secs| {
+                    Duration::from_secs(secs.into())
+                }),
+            stuck_invocation_deadline_multiple: limit(STUCK_INVOCATION_DEADLINE_MULTIPLE_KEY)
+                .unwrap_or(DEFAULT_STUCK_INVOCATION_DEADLINE_MULTIPLE),
+            abort_stuck_invocations: config
+                .get(ABORT_STUCK_INVOCATIONS_KEY)
+                .is_some_and(|value| match value.trim().parse::<bool>() {
+                    Ok(abort) => abort,
+                    Err(_) => {
+                        warn!(
+                            key = ABORT_STUCK_INVOCATIONS_KEY,
+                            value, "ignoring invalid invocation limit"
+                        );
+                        false
+                    }
+                }),
+        }
+    }
+}
+
+/// An accepted invocation to be served through [`ServeHandle::add_inbound`]
+pub struct InboundInvocation {
+    source: Option<String>,
+    deadline: Option<Duration>,
+    handle: BoxInvocation,
+    reject: Option<RejectInvocation>,
+}
+
+impl InboundInvocation {
+    /// Create an invocation handled by `handle`
+    pub fn new(handle: impl Future<Output = ()> + Send + 'static) -> Self {
+        Self {
+            source: None,
+            deadline: None,
+            handle: Box::pin(handle),
+            reject: None,
+        }
+    }
+
+    /// Set the ID of the component that sent the invocation, used for per-source rate limiting
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Set how long the caller waits for a response, relative to when the invocation is accepted.
+    /// Used to detect stuck invocations, see [`ServeLimits::stuck_invocation_deadline_multiple`]
+    #[must_use]
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set how to respond to the caller if the invocation is throttled or aborted as it is stuck,
+    /// usually by transmitting the error passed to `reject` on the invocation's error subject.
+    /// Throttled invocations without a rejection are dropped, leaving the caller to time out.
+    #[must_use]
+    pub fn with_reject<F, Fut>(mut self, reject: F) -> Self
+    where
+        F: FnOnce(&'static str) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.reject = Some(Box::new(move |err| Box::pin(reject(err)) as BoxInvocation));
+        self
+    }
+}
+
+enum Command {
+    Add {
+        key: ExportKey,
+        stream: Pin<Box<dyn Stream<Item = TaggedInvocation> + Send>>,
+        abort: AbortHandle,
+        result: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Remove {
+        key: ExportKey,
+        result: oneshot::Sender<bool>,
+    },
+    List {
+        result: oneshot::Sender<Vec<ExportKey>>,
+    },
+    SetLimits {
+        limits: ServeLimits,
+    },
+    Drain {
+        deadline: Instant,
+        result: oneshot::Sender<DrainReport>,
+    },
+}
+
+/// An export being served by [`serve_provider_exports_dynamic`]
+struct Export {
+    abort: AbortHandle,
+    /// Invocations accepted for this export which may still be running
+    tasks: JoinSet<()>,
+    /// Set once the export is being removed, notified after in-flight invocations have completed
+    removed: Option<oneshot::Sender<bool>>,
+}
+
+/// Handle to add and remove exports served by [`serve_provider_exports_dynamic`].
+///
+/// The handle is cheap to clone, all clones refer to the same set of exports.
+#[derive(Clone, Debug)]
+pub struct ServeHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    done: watch::Receiver<bool>,
+}
+
+impl ServeHandle {
+    /// Start serving invocations of function `name` of `instance` received from `invocations`.
+    ///
+    /// Each item of `invocations` is a future handling a single accepted invocation, usually
+    /// obtained by mapping the stream returned by [`wrpc_transport::Client::serve`], which is
+    /// spawned as its own task. Use [`ServeHandle::add_inbound`] to rate limit invocations per
+    /// source and respond to throttled callers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function is already being served or the exports have been shut down
+    pub async fn add<S, Fut>(
+        &self,
+        instance: impl Into<String>,
+        name: impl Into<String>,
+        invocations: S,
+    ) -> anyhow::Result<()>
+    where
+        S: Stream<Item = anyhow::Result<Fut>> + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add_inbound(
+            instance,
+            name,
+            invocations.map(|invocation| invocation.map(InboundInvocation::new)),
+        )
+        .await
+    }
+
+    /// Start serving invocations of function `name` of `instance` received from `invocations`,
+    /// like [`ServeHandle::add`], with the source of each invocation and how to reject it when
+    /// throttled described by [`InboundInvocation`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function is already being served or the exports have been shut down
+    pub async fn add_inbound<S>(
+        &self,
+        instance: impl Into<String>,
+        name: impl Into<String>,
+        invocations: S,
+    ) -> anyhow::Result<()>
+    where
+        S: Stream<Item = anyhow::Result<InboundInvocation>> + Send + 'static,
+    {
+        let key = (instance.into(), name.into());
+        let (invocations, abort) = stream::abortable(invocations);
+        let stream_key = key.clone();
+        let end_key = key.clone();
+        let stream = invocations
+            .map(move |invocation| (stream_key.clone(), Some(invocation)))
+            .chain(stream::once(async move { (end_key, None) }));
+        let (result, rx) = oneshot::channel();
+        self.send(Command::Add {
+            key,
+            stream: Box::pin(stream),
+            abort,
+            result,
+        })?;
+        rx.await.context("provider exports are no longer served")?
+    }
+
+    /// Stop serving function `name` of `instance`, waiting for invocations that were already
+    /// accepted to complete. Returns `false` if the function was not being served.
+    pub async fn remove(&self, instance: impl Into<String>, name: impl Into<String>) -> bool {
+        let (result, rx) = oneshot::channel();
+        if self
+            .send(Command::Remove {
+                key: (instance.into(), name.into()),
+                result,
+            })
+            .is_err()
+        {
+            return false;
+        }
+        rx.await.unwrap_or(false)
+    }
+
+    /// List the `(instance, name)` pairs of all functions currently being served
+    pub async fn exports(&self) -> Vec<(String, String)> {
+        let (result, rx) = oneshot::channel();
+        if self.send(Command::List { result }).is_err() {
+            return Vec::new();
+        }
+        let mut exports = rx.await.unwrap_or_default();
+        exports.sort();
+        exports
+    }
+
+    /// Replace the limits applied to inbound invocations, for example after the provider
+    /// configuration changed. Rate limits start over with full buckets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exports have been shut down
+    pub fn set_limits(&self, limits: ServeLimits) -> anyhow::Result<()> {
+        self.send(Command::SetLimits { limits })
+    }
+
+    /// Stop serving all exports and wait at most `timeout` for in-flight invocations to complete.
+    /// Invocations still running afterwards are abandoned: they keep running, but are no longer
+    /// awaited, and are counted in the returned report.
+    pub async fn drain(&self, timeout: Duration) -> DrainReport {
+        let (result, rx) = oneshot::channel();
+        let drained = DrainReport {
+            drained: true,
+            abandoned: Some(0),
+        };
+        if self
+            .send(Command::Drain {
+                deadline: Instant::now() + timeout,
+                result,
+            })
+            .is_err()
+        {
+            return drained;
+        }
+        rx.await.unwrap_or(drained)
+    }
+
+    /// Wait until serving has stopped and all in-flight invocations have completed
+    pub async fn closed(&self) {
+        let mut done = self.done.clone();
+        let _ = done.wait_for(|done| *done).await;
+    }
+
+    fn send(&self, command: Command) -> anyhow::Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| anyhow!("provider exports are no longer served"))
+    }
+}
+
+/// Serve provider exports that can be added and removed at runtime through the returned
+/// [`ServeHandle`], until `shutdown` completes.
+///
+/// On shutdown, all exports stop accepting invocations and in-flight invocations are drained
+/// before [`ServeHandle::closed`] resolves. Invocations are subject to the default
+/// [`ServeLimits`] until [`ServeHandle::set_limits`] is called.
+pub fn serve_provider_exports_dynamic(
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> ServeHandle {
+    let (commands, rx) = mpsc::unbounded_channel();
+    let (done_tx, done) = watch::channel(false);
+    tokio::spawn(async move {
+        multiplex_exports(rx, shutdown).await;
+        let _ = done_tx.send(true);
+    });
+    ServeHandle { commands, done }
+}
+
+#[instrument(level = "debug", skip_all)]
+async fn multiplex_exports(
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    shutdown: impl Future<Output = ()>,
+) {
+    let mut shutdown = pin!(shutdown);
+    let mut exports: HashMap<ExportKey, Export> = HashMap::new();
+    let mut invocations: SelectAll<Pin<Box<dyn Stream<Item = TaggedInvocation> + Send>>> =
+        SelectAll::new();
+    let mut limits = ServeLimits::default();
+    let mut limiter = RateLimiter::new(&limits, Instant::now());
+    let in_flight = Arc::new(Semaphore::new(limits.max_in_flight));
+    let mut drain = None;
+    loop {
+        select! {
+            Some(command) = commands.recv() => match command {
+                Command::Add { key, stream, abort, result } => {
+                    if exports.contains_key(&key) {
+                        let _ = result.send(Err(anyhow!("`{}.{}` is already being served", key.0, key.1)));
+                        continue;
+                    }
+                    debug!(instance = %key.0, name = %key.1, "serving export");
+                    exports.insert(key, Export { abort, tasks: JoinSet::new(), removed: None });
+                    invocations.push(stream);
+                    let _ = result.send(Ok(()));
+                }
+                Command::Remove { key, result } => match exports.get_mut(&key) {
+                    Some(export) if export.removed.is_none() => {
+                        debug!(instance = %key.0, name = %key.1, "removing export");
+                        // The export is dropped once its stream reports it has ended
+                        export.abort.abort();
+                        export.removed = Some(result);
+                    }
+                    _ => {
+                        let _ = result.send(false);
+                    }
+                },
+                Command::List { result } => {
+                    let _ = result.send(
+                        exports
+                            .iter()
+                            .filter(|(_, export)| export.removed.is_none())
+                            .map(|(key, _)| key.clone())
+                            .collect(),
+                    );
+                }
+                Command::SetLimits { limits: new } => {
+                    debug!(?new, "updating invocation limits");
+                    let max_in_flight = new.max_in_flight.clamp(1, Semaphore::MAX_PERMITS);
+                    resize_in_flight(&in_flight, limits.max_in_flight, max_in_flight);
+                    limits = ServeLimits { max_in_flight, ..new };
+                    limiter = RateLimiter::new(&limits, Instant::now());
+                }
+                Command::Drain { deadline, result } => {
+                    debug!("drain requested, draining in-flight invocations");
+                    drain = Some((deadline, result));
+                    break;
+                }
+            },
+            Some((key, invocation, permit)) = next_invocation(&mut invocations, &in_flight), if !invocations.is_empty() => {
+                match invocation {
+                    Some(Ok(invocation)) => {
+                        let Some(export) = exports.get_mut(&key) else {
+                            continue;
+                        };
+                        // Reap invocations that have completed since the last one was accepted
+                        while export.tasks.try_join_next().is_some() {}
+                        let InboundInvocation { source, deadline, handle, reject } = invocation;
+                        if limiter.allow(source.as_deref(), Instant::now()) {
+                            let metrics = ProviderMetrics::global();
+                            let started = metrics.start_invocation();
+                            let (instance, func) = key.clone();
+                            let watchdog = Watchdog::new(&limits, deadline);
+                            export.tasks.spawn(async move {
+                                watchdog
+                                    .watch(handle, reject, started, &instance, &func, source.as_deref())
+                                    .await;
+                                metrics.finish_invocation(&instance, &func, started, false);
+                                drop(permit);
+                            });
+                        } else {
+                            debug!(instance = %key.0, name = %key.1, ?source, "throttling invocation");
+                            if let Some(reject) = reject {
+                                export.tasks.spawn(async move {
+                                    reject(THROTTLED_ERROR).await;
+                                    drop(permit);
+                                });
+                            }
+                        }
+                    }
+                    Some(Err(err)) => {
+                        error!(?err, instance = %key.0, name = %key.1, "failed to accept invocation");
+                    }
+                    None => {
+                        let Some(export) = exports.remove(&key) else {
+                            continue;
+                        };
+                        if export.removed.is_none() {
+                            warn!(instance = %key.0, name = %key.1, "invocation stream unexpectedly finished");
+                        }
+                        tokio::spawn(drain_export(export));
+                    }
+                }
+            }
+            () = &mut shutdown => {
+                debug!("shutdown received, draining in-flight invocations");
+                break;
+            }
+        }
+    }
+
+    // Stop accepting invocations and wait for all accepted ones to complete, or until the drain
+    // deadline
+    drop(invocations);
+    let Some((deadline, result)) = drain else {
+        for export in exports.into_values() {
+            export.abort.abort();
+            drain_export(export).await;
+        }
+        return;
+    };
+    let mut abandoned = 0;
+    for export in exports.into_values() {
+        export.abort.abort();
+        abandoned += drain_export_until(export, deadline).await;
+    }
+    let _ = result.send(DrainReport {
+        drained: abandoned == 0,
+        abandoned: Some(abandoned),
+    });
+}
+
+/// Wait for a free invocation slot, then for the next invocation of any export. Invocation
+/// streams are not polled while all slots are taken, applying backpressure to the transport.
+async fn next_invocation(
+    invocations: &mut SelectAll<Pin<Box<dyn Stream<Item = TaggedInvocation> + Send>>>,
+    in_flight: &Arc<Semaphore>,
+) -> Option<(
+    ExportKey,
+    Option<anyhow::Result<InboundInvocation>>,
+    OwnedSemaphorePermit,
+)> {
+    let permit = Arc::clone(in_flight).acquire_owned().await.ok()?;
+    let (key, invocation) = invocations.next().await?;
+    Some((key, invocation, permit))
+}
+
+/// Limits of the invocations served by the serving loops of [`crate::interfaces`]
+static INBOUND: Lazy<InboundLimits> = Lazy::new(InboundLimits::default);
+
+/// Rate limits and invocation slots shared by the serving loops of [`crate::interfaces`]
+struct InboundLimits {
+    state: Mutex<(ServeLimits, RateLimiter)>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl Default for InboundLimits {
+    fn default() -> Self {
+        let limits = ServeLimits::default();
+        Self {
+            state: Mutex::new((limits, RateLimiter::new(&limits, Instant::now()))),
+            in_flight: Arc::new(Semaphore::new(limits.max_in_flight)),
+        }
+    }
+}
+
+impl InboundLimits {
+    fn set(&self, new: ServeLimits) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let max_in_flight = new.max_in_flight.clamp(1, Semaphore::MAX_PERMITS);
+        resize_in_flight(&self.in_flight, state.0.max_in_flight, max_in_flight);
+        let limits = ServeLimits {
+            max_in_flight,
+            ..new
+        };
+        *state = (limits, RateLimiter::new(&limits, Instant::now()));
+    }
+
+    async fn admit(&self, source: Option<&str>) -> Option<(OwnedSemaphorePermit, Watchdog)> {
+        let permit = Arc::clone(&self.in_flight).acquire_owned().await.ok()?;
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let (limits, limiter) = &mut *state;
+        limiter
+            .allow(source, Instant::now())
+            .then(|| (permit, Watchdog::new(limits, None)))
+    }
+}
+
+/// Apply `limits` to the invocations served by the serving loops of [`crate::interfaces`]. This
+/// is done from the provider configuration at startup and on every configuration update, see
+/// [`ServeLimits::from_config`]
+pub fn set_inbound_limits(limits: ServeLimits) {
+    debug!(?limits, "updating inbound invocation limits");
+    INBOUND.set(limits);
+}
+
+/// Wait for a free invocation slot of the serving loops of [`crate::interfaces`], then check the
+/// rate limits for an invocation from `source`. Returns the slot, to be held until the invocation
+/// completes, along with the watchdog of the invocation, or `None` if the invocation is throttled
+pub(crate) async fn admit_inbound(
+    source: Option<&str>,
+) -> Option<(OwnedSemaphorePermit, Watchdog)> {
+    INBOUND.admit(source).await
+}
+
+/// Change the number of invocation slots from `current` to `max`. Slots held by in-flight
+/// invocations are only given up once those invocations complete.
+fn resize_in_flight(in_flight: &Arc<Semaphore>, current: usize, max: usize) {
+    if max > current {
+        in_flight.add_permits(max - current);
+    } else if max < current {
+        let excess = u32::try_from(current - max).unwrap_or(u32::MAX);
+        let in_flight = Arc::clone(in_flight);
+        tokio::spawn(async move {
+            if let Ok(permits) = in_flight.acquire_many_owned(excess).await {
+                permits.forget();
+            }
+        });
+    }
+}
+
+/// Token bucket allowing `rate` invocations per second, in bursts of up to `rate` invocations
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, now: Instant) -> Self {
+        Self {
+            rate: rate.into(),
+            tokens: rate.into(),
+            last: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.rate
+    }
+}
+
+/// Global and per-source rate limits of inbound invocations
+#[derive(Debug)]
+struct RateLimiter {
+    global: Option<TokenBucket>,
+    per_source_rate: Option<u32>,
+    per_source: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new(limits: &ServeLimits, now: Instant) -> Self {
+        Self {
+            global: limits
+                .max_invocations_per_sec
+                .map(|rate| TokenBucket::new(rate, now)),
+            per_source_rate: limits.per_source_max_invocations_per_sec,
+            per_source: HashMap::new(),
+        }
+    }
+
+    /// Returns true and consumes a token if an invocation from `source` is within the limits.
+    /// Invocations of unknown source only count towards the global limit.
+    fn allow(&mut self, source: Option<&str>, now: Instant) -> bool {
+        if let Some(global) = &mut self.global {
+            global.refill(now);
+            if global.tokens < 1.0 {
+                return false;
+            }
+        }
+        let source_bucket = match (self.per_source_rate, source) {
+            (Some(rate), Some(source)) => {
+                if self.per_source.len() >= MAX_TRACKED_SOURCES {
+                    // Buckets that have been idle long enough to be full again are equivalent to
+                    // new ones
+                    self.per_source.retain(|_, bucket| {
+                        bucket.refill(now);
+                        !bucket.is_full()
+                    });
+                }
+                let bucket = self
+                    .per_source
+                    .entry(source.to_string())
+                    .or_insert_with(|| TokenBucket::new(rate, now));
+                bucket.refill(now);
+                if bucket.tokens < 1.0 {
+                    return false;
+                }
+                Some(bucket)
+            }
+            _ => None,
+        };
+        if let Some(bucket) = source_bucket {
+            bucket.tokens -= 1.0;
+        }
+        if let Some(global) = &mut self.global {
+            global.tokens -= 1.0;
+        }
+        true
+    }
+}
+
+/// Detects invocations which run for longer than expected
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Watchdog {
+    stuck_after: Duration,
+    abort: bool,
+}
+
+impl Watchdog {
+    fn new(limits: &ServeLimits, deadline: Option<Duration>) -> Self {
+        let stuck_after = deadline.map_or(limits.stuck_invocation_timeout, |deadline| {
+            deadline.saturating_mul(limits.stuck_invocation_deadline_multiple)
+        });
+        Self {
+            stuck_after,
+            abort: limits.abort_stuck_invocations,
+        }
+    }
+
+    /// Run the invocation `handle` started at `started`, warning about it while it is stuck or
+    /// aborting it and responding to the caller through `reject`
+    pub(crate) async fn watch(
+        self,
+        mut handle: BoxInvocation,
+        reject: Option<RejectInvocation>,
+        started: Instant,
+        instance: &str,
+        func: &str,
+        source: Option<&str>,
+    ) {
+        if tokio::time::timeout(
+            self.stuck_after.saturating_sub(started.elapsed()),
+            &mut handle,
+        )
+        .await
+        .is_ok()
+        {
+            return;
+        }
+        let _stuck = StuckInvocation::new(ProviderMetrics::global());
+        loop {
+            warn!(
+                instance,
+                function = func,
+                ?source,
+                elapsed = ?started.elapsed(),
+                abort = self.abort,
+                "invocation is stuck"
+            );
+            if self.abort {
+                drop(handle);
+                if let Some(reject) = reject {
+                    reject(STUCK_INVOCATION_ERROR).await;
+                }
+                return;
+            }
+            if tokio::time::timeout(STUCK_WARNING_INTERVAL, &mut handle)
+                .await
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Counts an invocation as stuck in [`ProviderMetrics`] until dropped, which also covers
+/// invocation tasks that are aborted
+struct StuckInvocation(&'static ProviderMetrics);
+
+impl StuckInvocation {
+    fn new(metrics: &'static ProviderMetrics) -> Self {
+        metrics.start_stuck_invocation();
+        Self(metrics)
+    }
+}
+
+impl Drop for StuckInvocation {
+    fn drop(&mut self) {
+        self.0.finish_stuck_invocation();
+    }
+}
+
+/// Wait for all in-flight invocations of a removed export to complete
+async fn drain_export(mut export: Export) {
+    while let Some(res) = export.tasks.join_next().await {
+        if let Err(err) = res {
+            error!(?err, "invocation task failed");
+        }
+    }
+    if let Some(removed) = export.removed {
+        let _ = removed.send(true);
+    }
+}
+
+/// Wait for in-flight invocations of an export to complete until `deadline`, returning the number
+/// of invocations which are still running and are no longer awaited
+async fn drain_export_until(mut export: Export, deadline: Instant) -> usize {
+    let _ = tokio::time::timeout_at(deadline, async {
+        while let Some(res) = export.tasks.join_next().await {
+            if let Err(err) = res {
+                error!(?err, "invocation task failed");
+            }
+        }
+    })
+    .await;
+    let abandoned = export.tasks.len();
+    export.tasks.detach_all();
+    if let Some(removed) = export.removed {
+        let _ = removed.send(true);
+    }
+    abandoned
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::time::Duration;
+
+    use anyhow::bail;
+
+    use super::*;
+
+    /// In-memory stand-in for an export subscription: invocations carry their parameter and a
+    /// channel to respond on. Sending fails as soon as the export's stream has been dropped,
+    /// mirroring the "no responders" error NATS returns for a subject nobody subscribes to
+    type Invocation = (u32, oneshot::Sender<u32>);
+
+    fn export(
+        handler: fn(u32) -> u32,
+    ) -> (
+        mpsc::Sender<Invocation>,
+        impl Stream<Item = anyhow::Result<impl Future<Output = ()>>>,
+    ) {
+        let (tx, rx) = mpsc::channel::<Invocation>(16);
+        let stream = stream::unfold(rx, |mut rx| async move {
+            let (param, respond) = rx.recv().await?;
+            Some((
+                Ok(async move {
+                    let _ = respond.send(handler(param));
+                }),
+                rx,
+            ))
+        });
+        (tx, stream)
+    }
+
+    async fn invoke(export: &mpsc::Sender<Invocation>, param: u32) -> anyhow::Result<u32> {
+        let (respond, rx) = oneshot::channel();
+        if export.send((param, respond)).await.is_err() {
+            bail!("no such export");
+        }
+        tokio::time::timeout(Duration::from_secs(1), rx)
+            .await
+            .context("invocation timed out")?
+            .context("invocation dropped")
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_exports() -> anyhow::Result<()> {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let handle = serve_provider_exports_dynamic(async {
+            let _ = shutdown_rx.await;
+        });
+
+        let (double, stream) = export(|x| x * 2);
+        handle.add("test:math/ops", "double", stream).await?;
+        assert_eq!(invoke(&double, 2).await?, 4);
+
+        // A link arrives and the provider learns it can serve another function
+        let (square, stream) = export(|x| x * x);
+        handle.add("test:math/ops", "square", stream).await?;
+        assert_eq!(invoke(&square, 3).await?, 9);
+        assert_eq!(
+            handle.exports().await,
+            vec![
+                ("test:math/ops".to_string(), "double".to_string()),
+                ("test:math/ops".to_string(), "square".to_string()),
+            ]
+        );
+
+        // Serving the same function twice is an error
+        let (_, stream) = export(|x| x);
+        assert!(handle.add("test:math/ops", "square", stream).await.is_err());
+
+        assert!(handle.remove("test:math/ops", "square").await);
+        assert!(!handle.remove("test:math/ops", "square").await);
+        let err = tokio::time::timeout(Duration::from_millis(100), invoke(&square, 3))
+            .await
+            .context("invocation of removed export should fail quickly")?
+            .expect_err("invocation of removed export should fail");
+        assert_eq!(err.to_string(), "no such export");
+
+        // Other exports are unaffected
+        assert_eq!(invoke(&double, 5).await?, 10);
+
+        let _ = shutdown_tx.send(());
+        tokio::time::timeout(Duration::from_secs(1), handle.closed()).await?;
+        assert!(invoke(&double, 1).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_drains_in_flight_invocations() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+
+        let (started_tx, mut started_rx) = mpsc::channel::<()>(1);
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+        let (completed_tx, completed_rx) = oneshot::channel::<()>();
+        let slow = stream::once(async move {
+            Ok(async move {
+                let _ = started_tx.send(()).await;
+                let _ = finish_rx.await;
+                let _ = completed_tx.send(());
+            })
+        })
+        .chain(stream::pending());
+        handle.add("test:slow/ops", "wait", slow).await?;
+        started_rx
+            .recv()
+            .await
+            .context("invocation did not start")?;
+
+        let remove = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.remove("test:slow/ops", "wait").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            !remove.is_finished(),
+            "removal waits for in-flight invocations"
+        );
+        assert!(handle.exports().await.is_empty());
+
+        let _ = finish_tx.send(());
+        assert!(remove.await?);
+        completed_rx
+            .await
+            .context("in-flight invocation was not completed")?;
+        Ok(())
+    }
+
+    type Outcome = (&'static str, Result<(), &'static str>);
+
+    /// Export whose invocations carry their source and take `delay` to complete, recording the
+    /// highest number of invocations running at once in `max_running`. The outcome of every
+    /// invocation, including throttled ones, is reported on `outcomes`
+    fn slow_export(
+        delay: Duration,
+        outcomes: mpsc::UnboundedSender<Outcome>,
+        max_running: Arc<AtomicUsize>,
+    ) -> (
+        mpsc::Sender<&'static str>,
+        impl Stream<Item = anyhow::Result<InboundInvocation>>,
+    ) {
+        let (tx, rx) = mpsc::channel::<&'static str>(1024);
+        let running = Arc::new(AtomicUsize::new(0));
+        let stream = stream::unfold(rx, move |mut rx| {
+            let outcomes = outcomes.clone();
+            let running = Arc::clone(&running);
+            let max_running = Arc::clone(&max_running);
+            async move {
+                let source = rx.recv().await?;
+                let rejected = outcomes.clone();
+                let invocation = InboundInvocation::new(async move {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    let _ = outcomes.send((source, Ok(())));
+                })
+                .with_source(source)
+                .with_reject(move |err| async move {
+                    let _ = rejected.send((source, Err(err)));
+                });
+                Some((Ok(invocation), rx))
+            }
+        });
+        (tx, stream)
+    }
+
+    async fn collect_outcomes(
+        outcomes: &mut mpsc::UnboundedReceiver<Outcome>,
+        count: usize,
+    ) -> anyhow::Result<Vec<Outcome>> {
+        let mut collected = Vec::with_capacity(count);
+        for _ in 0..count {
+            let outcome = tokio::time::timeout(Duration::from_secs(5), outcomes.recv())
+                .await
+                .context("invocation did not complete")?
+                .context("export stopped")?;
+            collected.push(outcome);
+        }
+        Ok(collected)
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_invocations_are_capped() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+        handle.set_limits(ServeLimits {
+            max_in_flight: 4,
+            ..Default::default()
+        })?;
+
+        let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let (export, stream) = slow_export(
+            Duration::from_millis(20),
+            outcomes_tx,
+            Arc::clone(&max_running),
+        );
+        handle.add_inbound("test:slow/ops", "wait", stream).await?;
+
+        for _ in 0..32 {
+            export.send("component").await?;
+        }
+        let outcomes = collect_outcomes(&mut outcomes, 32).await?;
+        assert!(outcomes.iter().all(|(_, outcome)| outcome.is_ok()));
+        assert_eq!(max_running.load(Ordering::SeqCst), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drain_completes_in_flight_invocations() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+
+        let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+        let running = Arc::new(AtomicUsize::new(0));
+        let (tx, stream) = slow_export(Duration::from_secs(2), outcomes_tx, Arc::clone(&running));
+        handle.add_inbound("test:slow/ops", "slow", stream).await?;
+        tx.send("first").await?;
+        tx.send("second").await?;
+        while running.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let report = handle.drain(Duration::from_secs(5)).await;
+        assert_eq!(
+            report,
+            DrainReport {
+                drained: true,
+                abandoned: Some(0),
+            }
+        );
+        let mut completed = collect_outcomes(&mut outcomes, 2).await?;
+        completed.sort_by_key(|(source, _)| *source);
+        assert!(matches!(
+            completed.as_slice(),
+            [("first", Ok(())), ("second", Ok(()))]
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drain_abandons_invocations_after_timeout() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+
+        let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+        let fast_running = Arc::new(AtomicUsize::new(0));
+        let (fast, stream) = slow_export(
+            Duration::from_millis(50),
+            outcomes_tx.clone(),
+            Arc::clone(&fast_running),
+        );
+        handle.add_inbound("test:slow/ops", "fast", stream).await?;
+        let stuck_running = Arc::new(AtomicUsize::new(0));
+        let (stuck, stream) = slow_export(
+            Duration::from_secs(60),
+            outcomes_tx,
+            Arc::clone(&stuck_running),
+        );
+        handle.add_inbound("test:slow/ops", "stuck", stream).await?;
+
+        fast.send("fast").await?;
+        fast.send("fast").await?;
+        stuck.send("stuck").await?;
+        // Wait for all invocations to be accepted
+        while fast_running.load(Ordering::SeqCst) < 2 || stuck_running.load(Ordering::SeqCst) < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let started = Instant::now();
+        let report = handle.drain(Duration::from_millis(300)).await;
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(
+            report,
+            DrainReport {
+                drained: false,
+                abandoned: Some(1),
+            }
+        );
+        // Invocations completed in time and no new ones are accepted
+        assert_eq!(collect_outcomes(&mut outcomes, 2).await?.len(), 2);
+        assert!(fast.send("fast").await.is_err());
+        tokio::time::timeout(Duration::from_secs(1), handle.closed()).await?;
+
+        // Draining exports which are no longer served completes immediately
+        assert_eq!(
+            handle.drain(Duration::from_secs(1)).await,
+            DrainReport {
+                drained: true,
+                abandoned: Some(0),
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invocations_are_throttled() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+        handle.set_limits(ServeLimits::from_config(&HashMap::from([
+            (
+                PER_SOURCE_MAX_INVOCATIONS_PER_SEC_KEY.to_string(),
+                "5".to_string(),
+            ),
+            (MAX_INVOCATIONS_PER_SEC_KEY.to_string(), "100".to_string()),
+        ])))?;
+
+        let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+        let (export, stream) = slow_export(
+            Duration::from_millis(10),
+            outcomes_tx,
+            Arc::new(AtomicUsize::new(0)),
+        );
+        handle.add_inbound("test:slow/ops", "wait", stream).await?;
+
+        for _ in 0..20 {
+            export.send("noisy").await?;
+        }
+        export.send("quiet").await?;
+        let outcomes = collect_outcomes(&mut outcomes, 21).await?;
+
+        let noisy_ok = outcomes
+            .iter()
+            .filter(|(source, outcome)| *source == "noisy" && outcome.is_ok())
+            .count();
+        let throttled = outcomes
+            .iter()
+            .filter(|(_, outcome)| *outcome == Err(THROTTLED_ERROR))
+            .count();
+        // The burst allowance is used up, and few tokens are refilled while hammering
+        assert!(
+            (5..10).contains(&noisy_ok),
+            "{noisy_ok} invocations were allowed"
+        );
+        assert_eq!(noisy_ok + throttled, 20);
+        // Other sources are not affected by a noisy one
+        assert!(outcomes.contains(&("quiet", Ok(()))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_limits_from_config() {
+        assert_eq!(
+            ServeLimits::from_config(&HashMap::default()),
+            ServeLimits::default()
+        );
+        let config = HashMap::from([
+            (MAX_CONCURRENT_INVOCATIONS_KEY.to_string(), "8".to_string()),
+            (MAX_INVOCATIONS_PER_SEC_KEY.to_string(), "0".to_string()),
+            (
+                PER_SOURCE_MAX_INVOCATIONS_PER_SEC_KEY.to_string(),
+                " 50 ".to_string(),
+            ),
+            (
+                STUCK_INVOCATION_TIMEOUT_SECS_KEY.to_string(),
+                "30".to_string(),
+            ),
+            (
+                STUCK_INVOCATION_DEADLINE_MULTIPLE_KEY.to_string(),
+                "twice".to_string(),
+            ),
+            (ABORT_STUCK_INVOCATIONS_KEY.to_string(), "true".to_string()),
+        ]);
+        assert_eq!(
+            ServeLimits::from_config(&config),
+            ServeLimits {
+                max_in_flight: 8,
+                max_invocations_per_sec: None,
+                per_source_max_invocations_per_sec: Some(50),
+                stuck_invocation_timeout: Duration::from_secs(30),
+                stuck_invocation_deadline_multiple: DEFAULT_STUCK_INVOCATION_DEADLINE_MULTIPLE,
+                abort_stuck_invocations: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inbound_limits() {
+        let inbound = InboundLimits::default();
+        inbound.set(ServeLimits {
+            max_in_flight: 2,
+            per_source_max_invocations_per_sec: Some(1),
+            ..ServeLimits::default()
+        });
+
+        let (first, _) = inbound.admit(Some("a")).await.expect("invocation admitted");
+        assert!(
+            inbound.admit(Some("a")).await.is_none(),
+            "invocation exceeding the per-source rate limit must be throttled"
+        );
+        let (_second, _) = inbound.admit(Some("b")).await.expect("invocation admitted");
+        // All slots are taken, so further invocations wait for one to be released
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), inbound.admit(Some("c")))
+                .await
+                .is_err(),
+            "invocation must wait for a free slot"
+        );
+        drop(first);
+        assert!(inbound.admit(Some("c")).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_inbound_stuck_invocation_aborted() {
+        let inbound = InboundLimits::default();
+        inbound.set(ServeLimits {
+            stuck_invocation_timeout: Duration::from_millis(20),
+            abort_stuck_invocations: true,
+            ..ServeLimits::default()
+        });
+
+        let (_permit, watchdog) = inbound.admit(None).await.expect("invocation admitted");
+        let (rejected_tx, rejected_rx) = oneshot::channel();
+        let reject: RejectInvocation = Box::new(move |message| {
+            Box::pin(async move {
+                let _ = rejected_tx.send(message);
+            })
+        });
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            watchdog.watch(
+                Box::pin(std::future::pending()),
+                Some(reject),
+                Instant::now(),
+                "test:stuck/ops",
+                "wait",
+                None,
+            ),
+        )
+        .await
+        .expect("stuck invocation must be aborted");
+        assert_eq!(rejected_rx.await.ok(), Some(STUCK_INVOCATION_ERROR));
+    }
+
+    /// Collects the warnings logged while it is the default subscriber, with their fields
+    #[derive(Clone, Default)]
+    struct Warnings(Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl Warnings {
+        fn matching(&self, message: &str) -> Vec<String> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|warning| warning.contains(message))
+                .cloned()
+                .collect()
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Warnings {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct Fields(String);
+            impl tracing::field::Visit for Fields {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn core::fmt::Debug,
+                ) {
+                    self.0.push_str(&format!("{}={value:?} ", field.name()));
+                }
+            }
+            if *event.metadata().level() == tracing::Level::WARN {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+        }
+    }
+
+    /// Export serving a single invocation from `source`, which never completes. Returns a
+    /// receiver of the error the invocation is rejected with, and one that resolves once the
+    /// invocation's handler is dropped
+    fn stuck_export(
+        source: &'static str,
+        deadline: Option<Duration>,
+    ) -> (
+        impl Stream<Item = anyhow::Result<InboundInvocation>>,
+        oneshot::Receiver<&'static str>,
+        oneshot::Receiver<()>,
+    ) {
+        let (rejected_tx, rejected) = oneshot::channel();
+        let (dropped_tx, dropped) = oneshot::channel::<()>();
+        let mut invocation = InboundInvocation::new(async move {
+            let _dropped = dropped_tx;
+            std::future::pending::<()>().await;
+        })
+        .with_source(source)
+        .with_reject(move |err| async move {
+            let _ = rejected_tx.send(err);
+        });
+        if let Some(deadline) = deadline {
+            invocation = invocation.with_deadline(deadline);
+        }
+        let stream = stream::once(async move { Ok(invocation) }).chain(stream::pending());
+        (stream, rejected, dropped)
+    }
+
+    #[tokio::test]
+    async fn test_stuck_invocations_are_reported() -> anyhow::Result<()> {
+        use tracing_subscriber::layer::SubscriberExt as _;
+
+        let warnings = Warnings::default();
+        let _subscriber =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(warnings.clone()));
+
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+        handle.set_limits(ServeLimits {
+            stuck_invocation_timeout: Duration::from_millis(50),
+            ..Default::default()
+        })?;
+        let (stream, mut rejected, mut dropped) = stuck_export("stuck-source", None);
+        handle.add_inbound("test:stuck/ops", "hang", stream).await?;
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while warnings.matching("invocation is stuck").is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .context("stuck invocation was not reported")?;
+        let warning = warnings.matching("invocation is stuck").remove(0);
+        for field in [
+            r#"instance="test:stuck/ops""#,
+            r#"function="hang""#,
+            r#"source=Some("stuck-source")"#,
+            "elapsed=",
+            "abort=false",
+        ] {
+            assert!(warning.contains(field), "missing `{field}` in {warning}");
+        }
+        assert!(ProviderMetrics::global().stuck_invocations() >= 1);
+
+        // The warning is not repeated within a minute, and the invocation keeps running
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(warnings.matching("invocation is stuck").len(), 1);
+        assert!(rejected.try_recv().is_err());
+        assert!(matches!(
+            dropped.try_recv(),
+            Err(oneshot::error::TryRecvError::Empty)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stuck_invocations_are_aborted() -> anyhow::Result<()> {
+        let handle = serve_provider_exports_dynamic(std::future::pending());
+        handle.set_limits(ServeLimits {
+            stuck_invocation_timeout: Duration::from_secs(3600),
+            abort_stuck_invocations: true,
+            ..Default::default()
+        })?;
+        // The deadline of the invocation takes precedence over the timeout
+        let (stream, rejected, dropped) =
+            stuck_export("stuck-source", Some(Duration::from_millis(20)));
+        handle.add_inbound("test:stuck/ops", "hang", stream).await?;
+
+        let err = tokio::time::timeout(Duration::from_secs(5), rejected)
+            .await
+            .context("stuck invocation was not aborted")?
+            .context("stuck invocation was not rejected")?;
+        assert_eq!(err, STUCK_INVOCATION_ERROR);
+        tokio::time::timeout(Duration::from_secs(5), dropped)
+            .await
+            .context("handler of the aborted invocation was not dropped")?
+            .expect_err("handler does not complete");
+        Ok(())
+    }
+}
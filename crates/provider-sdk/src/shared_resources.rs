@@ -0,0 +1,456 @@
+//! Resources shared by the links of a provider
+//!
+//! Providers commonly create a client or connection pool for each link, although many links use
+//! the same backend with the same credentials. [`SharedResourceManager`] shares such resources
+//! between links: the provider supplies how to derive the key of the resource a link uses from its
+//! configuration (e.g. a normalized connection string), and how to construct and destroy a resource
+//! for a key. Links with equal keys share the same resource.
+//!
+//! Resources are constructed on first use by [`SharedResourceManager::get`], once for all links
+//! requesting the same resource concurrently. A failed construction is reported to the links
+//! waiting for it, and retried by the next request. A resource is destroyed once the last link
+//! using it is deleted, and all resources are destroyed when the provider begins to shut down, see
+//! [`SharedResourceManager::attach`].
+
+use core::fmt::Debug;
+use core::future::Future;
+use core::hash::Hash;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use futures::future::BoxFuture;
+use futures::FutureExt as _;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{LinkConfig, LinkKey, TaskGroup};
+
+type ResourceKey<K> = Box<dyn Fn(&LinkConfig<'_>) -> anyhow::Result<K> + Send + Sync>;
+type ConstructResource<K, R> =
+    Box<dyn Fn(K) -> BoxFuture<'static, anyhow::Result<R>> + Send + Sync>;
+type DestroyResource<R> = Box<dyn Fn(Arc<R>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Resource of a key, constructed on first use
+#[derive(Debug)]
+struct Slot<R> {
+    resource: Option<Arc<R>>,
+    /// Set once the last link using the resource was deleted, after which it is not constructed
+    /// anymore
+    removed: bool,
+}
+
+#[derive(Debug)]
+struct Entry<R> {
+    /// Number of links using the resource
+    links: usize,
+    /// Locked while the resource is constructed or destroyed
+    slot: Arc<Mutex<Slot<R>>>,
+}
+
+#[derive(Debug)]
+struct Resources<K, R> {
+    /// Key of the resource used by each link
+    links: HashMap<LinkKey, K>,
+    entries: HashMap<K, Entry<R>>,
+    /// Set once all resources were destroyed on shutdown
+    closed: bool,
+}
+
+impl<K: Eq + Hash, R> Resources<K, R> {
+    /// Stop tracking `link`, returning the slot of its resource if no other link uses it
+    fn remove_link(&mut self, link: &LinkKey) -> Option<Arc<Mutex<Slot<R>>>> {
+        let key = self.links.remove(link)?;
+        let entry = self.entries.get_mut(&key)?;
+        entry.links = entry.links.saturating_sub(1);
+        if entry.links > 0 {
+            return None;
+        }
+        self.entries.remove(&key).map(|entry| entry.slot)
+    }
+}
+
+struct SharedResourceManagerState<K, R> {
+    key: ResourceKey<K>,
+    construct: ConstructResource<K, R>,
+    destroy: DestroyResource<R>,
+    resources: Mutex<Resources<K, R>>,
+}
+
+/// Resources shared by the links of a provider, see the [module documentation](self)
+pub struct SharedResourceManager<K, R> {
+    state: Arc<SharedResourceManagerState<K, R>>,
+}
+
+impl<K, R> Clone for SharedResourceManager<K, R> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<K, R> Debug for SharedResourceManager<K, R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SharedResourceManager")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, R> SharedResourceManager<K, R>
+where
+    K: Clone + Debug + Eq + Hash + Send + Sync + 'static,
+    R: Send + Sync + 'static,
+{
+    /// Create a manager deriving the key of the resource of a link from its configuration with
+    /// `resource_key`, constructing resources with `construct_resource` and destroying them with
+    /// `destroy_resource`
+    pub fn new<F, C, CFut, D, DFut>(
+        resource_key: F,
+        construct_resource: C,
+        destroy_resource: D,
+    ) -> Self
+    where
+        F: Fn(&LinkConfig<'_>) -> anyhow::Result<K> + Send + Sync + 'static,
+        C: Fn(K) -> CFut + Send + Sync + 'static,
+        CFut: Future<Output = anyhow::Result<R>> + Send + 'static,
+        D: Fn(Arc<R>) -> DFut + Send + Sync + 'static,
+        DFut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            state: Arc::new(SharedResourceManagerState {
+                key: Box::new(resource_key),
+                construct: Box::new(move |key| construct_resource(key).boxed()),
+                destroy: Box::new(move |resource| destroy_resource(resource).boxed()),
+                resources: Mutex::new(Resources {
+                    links: HashMap::new(),
+                    entries: HashMap::new(),
+                    closed: false,
+                }),
+            }),
+        }
+    }
+
+    /// Destroy all resources once the provider owning `tasks` begins to shut down, after which no
+    /// resources are constructed anymore
+    pub fn attach(&self, tasks: &TaskGroup) {
+        let manager = self.clone();
+        tasks.spawn("shared-resource-manager", |cancel| async move {
+            cancel.cancelled().await;
+            manager.shutdown().await;
+        });
+    }
+
+    /// Handle a put of a link, which uses the resource of the key derived from its configuration
+    /// from now on. The resource is constructed on first use, see [`Self::get`].
+    ///
+    /// A link whose key changed stops using its previous resource, which is destroyed if no other
+    /// link uses it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no key could be derived from the configuration of the link, in which
+    /// case the link uses no resource, or if the manager was shut down
+    pub async fn put_link(&self, link: &LinkConfig<'_>) -> anyhow::Result<()> {
+        let link_key = link.key();
+        let key = (self.state.key)(link);
+        let mut resources = self.state.resources.lock().await;
+        if resources.closed {
+            bail!("shared resource manager is shut down");
+        }
+        if let Ok(key) = &key {
+            if resources.links.get(&link_key) == Some(key) {
+                debug!(?link_key, "link already uses its shared resource");
+                return Ok(());
+            }
+        }
+        if let Some(slot) = resources.remove_link(&link_key) {
+            debug!(
+                ?link_key,
+                "destroying shared resource no longer used by link"
+            );
+            self.destroy(slot).await;
+        }
+        let key =
+            key.with_context(|| format!("failed to derive shared resource of link {link_key}"))?;
+        resources
+            .entries
+            .entry(key.clone())
+            .or_insert_with(|| Entry {
+                links: 0,
+                slot: Arc::new(Mutex::new(Slot {
+                    resource: None,
+                    removed: false,
+                })),
+            })
+            .links += 1;
+        resources.links.insert(link_key, key);
+        Ok(())
+    }
+
+    /// Returns the resource used by the link identified by `link`, constructing it if needed.
+    /// Concurrent requests for a resource being constructed wait for the construction to finish.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the link uses no resource, or if constructing the resource failed, in
+    /// which case it is constructed again on the next request
+    pub async fn get(&self, link: &LinkKey) -> anyhow::Result<Arc<R>> {
+        let (key, slot) = {
+            let resources = self.state.resources.lock().await;
+            if resources.closed {
+                bail!("shared resource manager is shut down");
+            }
+            let key = resources
+                .links
+                .get(link)
+                .with_context(|| format!("link {link} uses no shared resource"))?;
+            let entry = resources
+                .entries
+                .get(key)
+                .with_context(|| format!("link {link} uses no shared resource"))?;
+            (key.clone(), Arc::clone(&entry.slot))
+        };
+        let mut slot = slot.lock().await;
+        if slot.removed {
+            bail!("link {link} was deleted");
+        }
+        if let Some(resource) = &slot.resource {
+            return Ok(Arc::clone(resource));
+        }
+        debug!(?key, "constructing shared resource");
+        let resource = (self.state.construct)(key.clone())
+            .await
+            .map(Arc::new)
+            .with_context(|| {
+                format!("failed to construct shared resource {key:?} of link {link}")
+            })?;
+        slot.resource = Some(Arc::clone(&resource));
+        Ok(resource)
+    }
+
+    /// Handle a delete of the link identified by `link`, destroying its resource if no other link
+    /// uses it
+    pub async fn delete_link(&self, link: &LinkKey) {
+        let mut resources = self.state.resources.lock().await;
+        if let Some(slot) = resources.remove_link(link) {
+            debug!(?link, "destroying shared resource of deleted link");
+            self.destroy(slot).await;
+        }
+    }
+
+    /// Handle a delete of all links from `source_id`, as reported by
+    /// [`crate::Provider::delete_link_as_target`]
+    pub async fn delete_links_from(&self, source_id: &str) {
+        let mut resources = self.state.resources.lock().await;
+        let links: Vec<_> = resources
+            .links
+            .keys()
+            .filter(|link| link.source_id == source_id)
+            .cloned()
+            .collect();
+        for link in links {
+            if let Some(slot) = resources.remove_link(&link) {
+                debug!(?link, "destroying shared resource of deleted link");
+                self.destroy(slot).await;
+            }
+        }
+    }
+
+    /// Destroy all resources, after which no resources are constructed anymore
+    pub async fn shutdown(&self) {
+        let mut resources = self.state.resources.lock().await;
+        resources.closed = true;
+        resources.links.clear();
+        for (key, entry) in std::mem::take(&mut resources.entries) {
+            debug!(?key, "destroying shared resource on shutdown");
+            self.destroy(entry.slot).await;
+        }
+    }
+
+    /// Returns the number of resources used by links, whether constructed yet or not
+    pub async fn len(&self) -> usize {
+        self.state.resources.lock().await.entries.len()
+    }
+
+    /// Returns true if no link uses a resource
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Destroy the resource of `slot` once any construction in progress finished
+    async fn destroy(&self, slot: Arc<Mutex<Slot<R>>>) {
+        let mut slot = slot.lock().await;
+        slot.removed = true;
+        if let Some(resource) = slot.resource.take() {
+            (self.state.destroy)(resource).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::TASK_DRAIN_TIMEOUT;
+
+    /// Backend counting the pools constructed and destroyed through it
+    #[derive(Clone, Default)]
+    struct CountingBackend {
+        constructed: Arc<AtomicUsize>,
+        destroyed: Arc<AtomicUsize>,
+        /// Number of constructions left to fail
+        failures: Arc<AtomicUsize>,
+    }
+
+    impl CountingBackend {
+        fn manager(&self) -> SharedResourceManager<String, String> {
+            let constructed = Arc::clone(&self.constructed);
+            let failures = Arc::clone(&self.failures);
+            let destroyed = Arc::clone(&self.destroyed);
+            SharedResourceManager::new(
+                |link: &LinkConfig<'_>| {
+                    link.config
+                        .get("url")
+                        .map(|url| url.trim_end_matches('/').to_lowercase())
+                        .context("missing url")
+                },
+                move |url| {
+                    let constructed = Arc::clone(&constructed);
+                    let failures = Arc::clone(&failures);
+                    async move {
+                        // Give concurrent requests the opportunity to construct the pool too
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        if failures
+                            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                            .is_ok()
+                        {
+                            bail!("backend is unavailable");
+                        }
+                        constructed.fetch_add(1, Ordering::SeqCst);
+                        Ok(format!("pool for {url}"))
+                    }
+                },
+                move |_pool| {
+                    let destroyed = Arc::clone(&destroyed);
+                    async move {
+                        destroyed.fetch_add(1, Ordering::SeqCst);
+                    }
+                },
+            )
+        }
+
+        fn counts(&self) -> (usize, usize) {
+            (
+                self.constructed.load(Ordering::SeqCst),
+                self.destroyed.load(Ordering::SeqCst),
+            )
+        }
+    }
+
+    async fn put_link(
+        manager: &SharedResourceManager<String, String>,
+        source: &str,
+        url: &str,
+    ) -> anyhow::Result<LinkKey> {
+        let config = HashMap::from([("url".to_string(), url.to_string())]);
+        let namespace = "wasmcloud".to_string();
+        let package = "postgres".to_string();
+        let interfaces = vec!["query".to_string()];
+        let link = LinkConfig {
+            target_id: "provider",
+            source_id: source,
+            link_name: "default",
+            config: &config,
+            wit_metadata: (&namespace, &package, &interfaces),
+        };
+        manager.put_link(&link).await?;
+        Ok(link.key())
+    }
+
+    #[tokio::test]
+    async fn test_links_share_resources() {
+        let backend = CountingBackend::default();
+        let manager = backend.manager();
+
+        let alice = put_link(&manager, "alice", "postgres://db/").await.unwrap();
+        let bob = put_link(&manager, "bob", "POSTGRES://db").await.unwrap();
+        let carol = put_link(&manager, "carol", "postgres://other")
+            .await
+            .unwrap();
+        assert_eq!(manager.len().await, 2);
+        put_link(&manager, "dave", "postgres://other")
+            .await
+            .unwrap();
+        manager.delete_links_from("dave").await;
+
+        // Resources are constructed once on first use, even when requested concurrently
+        assert_eq!(backend.counts(), (0, 0));
+        let (a, b) = tokio::join!(manager.get(&alice), manager.get(&bob));
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(*a, "pool for postgres://db");
+        assert_eq!(
+            *manager.get(&carol).await.unwrap(),
+            "pool for postgres://other"
+        );
+        assert_eq!(backend.counts(), (2, 0));
+
+        // The shared resource is only destroyed once both links are deleted
+        manager.delete_link(&alice).await;
+        assert!(Arc::ptr_eq(&manager.get(&bob).await.unwrap(), &b));
+        assert_eq!(backend.counts(), (2, 0));
+        manager.delete_link(&bob).await;
+        assert_eq!(backend.counts(), (2, 1));
+        assert!(manager.get(&bob).await.is_err());
+        assert_eq!(manager.len().await, 1);
+
+        // Changing the configuration of a link moves it to another resource
+        put_link(&manager, "carol", "postgres://db").await.unwrap();
+        assert_eq!(backend.counts(), (2, 2));
+        assert_eq!(
+            *manager.get(&carol).await.unwrap(),
+            "pool for postgres://db"
+        );
+        assert_eq!(backend.counts(), (3, 2));
+    }
+
+    #[tokio::test]
+    async fn test_failed_construction_is_retried() {
+        let backend = CountingBackend::default();
+        backend.failures.store(1, Ordering::SeqCst);
+        let manager = backend.manager();
+        let alice = put_link(&manager, "alice", "postgres://db").await.unwrap();
+        let bob = put_link(&manager, "bob", "postgres://db").await.unwrap();
+
+        let err = manager.get(&alice).await.unwrap_err();
+        assert!(format!("{err:#}").contains("backend is unavailable"));
+        assert!(manager.get(&bob).await.is_ok());
+        assert!(manager.get(&alice).await.is_ok());
+        assert_eq!(backend.counts(), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_resources_are_destroyed_on_shutdown() {
+        let backend = CountingBackend::default();
+        let manager = backend.manager();
+        let tasks = TaskGroup::default();
+        manager.attach(&tasks);
+
+        let alice = put_link(&manager, "alice", "postgres://db").await.unwrap();
+        put_link(&manager, "bob", "postgres://other").await.unwrap();
+        manager.get(&alice).await.unwrap();
+
+        tasks.cancel();
+        tasks.drain(TASK_DRAIN_TIMEOUT).await;
+        // Resources which were never constructed are not destroyed
+        assert_eq!(backend.counts(), (1, 1));
+        assert!(manager.is_empty().await);
+        assert!(put_link(&manager, "carol", "postgres://db").await.is_err());
+        assert!(manager.get(&alice).await.is_err());
+    }
+}
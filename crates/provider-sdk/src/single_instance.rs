@@ -0,0 +1,337 @@
+//! Enforcement of a single running instance of a provider in a lattice
+//!
+//! When the same provider ID is started twice (e.g. by an operator, or briefly by wadm during
+//! reconciliation), both instances handle the links and invocations of the provider. Providers
+//! which cannot tolerate duplicate side effects opt into running as a single instance by setting
+//! [`SINGLE_INSTANCE_CONFIG_KEY`] to `true` in their configuration: on startup the SDK acquires a
+//! [`ProviderLock`] shared by all instances of the provider in the lattice, and an instance
+//! finding the lock held by another live instance shuts down without serving, as the future
+//! returned by [`run_provider`](crate::run_provider) is already complete.
+//!
+//! The holder refreshes the lock every third of its TTL ([`PROVIDER_LOCK_TTL`] for the SDK). A
+//! lock which is not refreshed in time, e.g. because its holder died, expires so that a replacement
+//! instance can take over, and a holder which fails to refresh its lock in time shuts down.
+//!
+//! Locks are stored in the [`PROVIDER_LOCK_BUCKET`] JetStream key-value bucket. As buckets have no
+//! per-key TTL, each lock is stored along with the time until which it is held, and expired locks
+//! are taken over with a compare-and-swap on their revision.
+
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context as _};
+use async_nats::jetstream::context::KeyValueErrorKind;
+use async_nats::jetstream::kv::{self, CreateErrorKind, Operation, UpdateErrorKind};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, warn};
+
+use crate::dedup::{as_millis, key_token, unix_millis};
+
+/// Key of the provider configuration enabling single instance enforcement when set to `true`
+pub const SINGLE_INSTANCE_CONFIG_KEY: &str = "single_instance";
+
+/// Name of the JetStream key-value bucket holding the locks of single instance providers
+pub const PROVIDER_LOCK_BUCKET: &str = "wasmcloud_provider_lock";
+
+/// Duration for which the SDK holds the lock of a single instance provider without refreshing it
+pub const PROVIDER_LOCK_TTL: Duration = Duration::from_secs(15);
+
+/// Timeout of JetStream requests made to acquire and refresh locks
+pub const PROVIDER_LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum age of locks in the bucket, bounding how long locks of stopped providers are kept
+const PROVIDER_LOCK_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Number of attempts to acquire a lock when racing with other instances
+const ACQUIRE_ATTEMPTS: usize = 3;
+
+/// Whether the provider `config` enables single instance enforcement
+#[must_use]
+pub fn single_instance_from_config(config: &HashMap<String, String>) -> bool {
+    config
+        .get(SINGLE_INSTANCE_CONFIG_KEY)
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// Value of a lock in the bucket
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LockValue {
+    /// Instance ID of the holder
+    instance_id: String,
+    /// Time until which the lock is held, in milliseconds since the UNIX epoch
+    held_until: u64,
+}
+
+/// Outcome of [`ProviderLock::acquire`]
+#[derive(Debug)]
+pub enum LockAcquisition {
+    /// The lock was acquired, and is held until the [`ProviderLock`] is released or dropped
+    Acquired(ProviderLock),
+    /// The lock is held by another live instance, with the given instance ID
+    HeldBy(String),
+}
+
+/// Lock held by a single instance of a provider in a lattice, see the
+/// [module documentation](self).
+///
+/// The lock is refreshed in the background until it is released. Dropping the lock stops refreshing
+/// it without releasing it, so that it expires after its TTL as if its holder died.
+#[derive(Debug)]
+pub struct ProviderLock {
+    store: kv::Store,
+    key: String,
+    instance_id: String,
+    revision: Arc<AtomicU64>,
+    lost: CancellationToken,
+    heartbeat: JoinHandle<()>,
+}
+
+impl ProviderLock {
+    /// Acquire the lock of the provider with ID `provider_id` in `lattice` for the instance with
+    /// ID `instance_id`, held for `ttl` without refreshing it. The bucket is opened, or created,
+    /// if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if JetStream is unavailable, or if the lock is contended by several other
+    /// instances
+    pub async fn acquire(
+        nats: async_nats::Client,
+        lattice: &str,
+        provider_id: &str,
+        instance_id: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<LockAcquisition> {
+        let mut jetstream = async_nats::jetstream::new(nats);
+        jetstream.set_timeout(PROVIDER_LOCK_TIMEOUT);
+        let store = open_bucket(&jetstream).await?;
+        let key = lock_key(lattice, provider_id);
+        let ttl = ttl.min(PROVIDER_LOCK_MAX_AGE);
+
+        for _ in 0..ACQUIRE_ATTEMPTS {
+            let value = lock_value(instance_id, ttl)?;
+            match store.create(&key, value.clone()).await {
+                Ok(revision) => {
+                    return Ok(LockAcquisition::Acquired(Self::held(
+                        store,
+                        key,
+                        instance_id,
+                        revision,
+                        ttl,
+                    )))
+                }
+                Err(err) if err.kind() == CreateErrorKind::AlreadyExists => {}
+                Err(err) => return Err(anyhow!(err).context("failed to create lock")),
+            }
+
+            let entry = store
+                .entry(&key)
+                .await
+                .context("failed to read lock")?
+                .filter(|entry| entry.operation == Operation::Put);
+            let Some(entry) = entry else {
+                // The lock was deleted in the meantime, race for it again
+                continue;
+            };
+            let holder: LockValue = serde_json::from_slice(&entry.value).unwrap_or_default();
+            if holder.held_until > unix_millis(SystemTime::now())
+                && holder.instance_id != instance_id
+            {
+                return Ok(LockAcquisition::HeldBy(holder.instance_id));
+            }
+            // The lock expired, take it over unless another instance does so first
+            match store.update(&key, value, entry.revision).await {
+                Ok(revision) => {
+                    return Ok(LockAcquisition::Acquired(Self::held(
+                        store,
+                        key,
+                        instance_id,
+                        revision,
+                        ttl,
+                    )))
+                }
+                Err(err) if err.kind() == UpdateErrorKind::WrongLastRevision => {}
+                Err(err) => return Err(anyhow!(err).context("failed to take over lock")),
+            }
+        }
+        bail!("lock is contended by other instances")
+    }
+
+    /// Create a lock held at `revision`, and start refreshing it
+    fn held(
+        store: kv::Store,
+        key: String,
+        instance_id: &str,
+        revision: u64,
+        ttl: Duration,
+    ) -> Self {
+        let revision = Arc::new(AtomicU64::new(revision));
+        let lost = CancellationToken::new();
+        let heartbeat = tokio::spawn(refresh(
+            store.clone(),
+            key.clone(),
+            instance_id.to_string(),
+            Arc::clone(&revision),
+            lost.clone(),
+            ttl,
+        ));
+        Self {
+            store,
+            key,
+            instance_id: instance_id.to_string(),
+            revision,
+            lost,
+            heartbeat,
+        }
+    }
+
+    /// Instance ID of the holder of the lock
+    #[must_use]
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Whether the lock was lost, i.e. taken over by another instance or not refreshed in time
+    #[must_use]
+    pub fn is_lost(&self) -> bool {
+        self.lost.is_cancelled()
+    }
+
+    /// Wait until the lock is lost
+    pub async fn lost(&self) {
+        self.lost.cancelled().await;
+    }
+
+    /// Stop refreshing the lock and release it, so that another instance can acquire it right away
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock could not be released, in which case it expires after its TTL
+    pub async fn release(self) -> anyhow::Result<()> {
+        self.heartbeat.abort();
+        if self.is_lost() {
+            return Ok(());
+        }
+        let value = serde_json::to_vec(&LockValue::default())
+            .context("failed to serialize lock")?
+            .into();
+        self.store
+            .update(&self.key, value, self.revision.load(Ordering::Acquire))
+            .await
+            .map(|_| ())
+            .map_err(|err| anyhow!(err).context("failed to release lock"))
+    }
+}
+
+impl Drop for ProviderLock {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+    }
+}
+
+/// Refresh the lock held at `revision` every third of `ttl`, cancelling `lost` once it is lost
+async fn refresh(
+    store: kv::Store,
+    key: String,
+    instance_id: String,
+    revision: Arc<AtomicU64>,
+    lost: CancellationToken,
+    ttl: Duration,
+) {
+    let mut held_until = unix_millis(SystemTime::now()).saturating_add(as_millis(ttl));
+    let mut interval = tokio::time::interval(ttl / 3);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately, the lock was just acquired
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let refreshed = match lock_value(&instance_id, ttl) {
+            Ok(value) => store
+                .update(&key, value, revision.load(Ordering::Acquire))
+                .await
+                .map_err(|err| {
+                    if err.kind() == UpdateErrorKind::WrongLastRevision {
+                        error!(%key, "lock was taken over by another instance");
+                        None
+                    } else {
+                        Some(anyhow!(err))
+                    }
+                }),
+            Err(err) => Err(Some(err)),
+        };
+        match refreshed {
+            Ok(new_revision) => {
+                revision.store(new_revision, Ordering::Release);
+                held_until = unix_millis(SystemTime::now()).saturating_add(as_millis(ttl));
+            }
+            Err(None) => break,
+            Err(Some(err)) if unix_millis(SystemTime::now()) >= held_until => {
+                error!(?err, %key, "failed to refresh lock before it expired");
+                break;
+            }
+            Err(Some(err)) => warn!(?err, %key, "failed to refresh lock, retrying"),
+        }
+    }
+    lost.cancel();
+}
+
+async fn open_bucket(jetstream: &async_nats::jetstream::Context) -> anyhow::Result<kv::Store> {
+    match jetstream.get_key_value(PROVIDER_LOCK_BUCKET).await {
+        Ok(store) => Ok(store),
+        Err(err) if err.kind() == KeyValueErrorKind::GetBucket => jetstream
+            .create_key_value(kv::Config {
+                bucket: PROVIDER_LOCK_BUCKET.to_string(),
+                description: "Locks of single instance providers".to_string(),
+                history: 1,
+                max_age: PROVIDER_LOCK_MAX_AGE,
+                ..Default::default()
+            })
+            .await
+            .context("failed to create lock bucket"),
+        Err(err) => Err(anyhow!(err).context("failed to open lock bucket")),
+    }
+}
+
+/// Key of the lock of the provider with ID `provider_id` in `lattice`
+fn lock_key(lattice: &str, provider_id: &str) -> String {
+    format!(
+        "provider_lock.{}.{}",
+        key_token(lattice),
+        key_token(provider_id)
+    )
+}
+
+/// Value of the lock held by `instance_id` for `ttl` from now
+fn lock_value(instance_id: &str, ttl: Duration) -> anyhow::Result<bytes::Bytes> {
+    let value = LockValue {
+        instance_id: instance_id.to_string(),
+        held_until: unix_millis(SystemTime::now()).saturating_add(as_millis(ttl)),
+    };
+    Ok(serde_json::to_vec(&value)
+        .context("failed to serialize lock")?
+        .into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lock_key() {
+        assert_eq!(
+            lock_key("default", "wasmcloud:messaging/nats"),
+            "provider_lock.default.wasmcloud_messaging_nats"
+        );
+        assert!(!single_instance_from_config(&HashMap::new()));
+        assert!(single_instance_from_config(&HashMap::from([(
+            SINGLE_INSTANCE_CONFIG_KEY.to_string(),
+            "TRUE".to_string()
+        )])));
+    }
+}
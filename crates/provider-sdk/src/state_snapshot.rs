@@ -0,0 +1,252 @@
+//! Snapshots of the in-memory state of a provider, carried over when the provider is updated
+//!
+//! When the host replaces a provider with a new version, the in-memory state of the provider
+//! (caches, sequence cursors) is lost, and some providers take minutes to rebuild it. Providers
+//! opt into carrying their state over by implementing [`Provider::snapshot_state`] and
+//! [`Provider::restore_state`], and setting [`STATE_SNAPSHOT_CONFIG_KEY`] to `true` in their
+//! configuration.
+//!
+//! When the host shuts the provider down to update it ([`ShutdownReason::Update`]), the SDK stores
+//! the snapshot returned by `snapshot_state` in the [`STATE_SNAPSHOT_BUCKET`] JetStream object
+//! store, keyed by lattice and provider ID. On startup, a snapshot younger than the max age set
+//! with [`STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY`] ([`DEFAULT_STATE_SNAPSHOT_MAX_AGE`] by default) is
+//! passed to `restore_state` before links are replayed, then deleted.
+//!
+//! Snapshots are opaque to the SDK, their format and size are up to the provider. Failing to take,
+//! store or restore a snapshot is logged, and the provider starts cold.
+//!
+//! [`Provider::snapshot_state`]: crate::Provider::snapshot_state
+//! [`Provider::restore_state`]: crate::Provider::restore_state
+//! [`ShutdownReason::Update`]: crate::ShutdownReason::Update
+
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Context as _};
+use async_nats::jetstream::object_store::{self, GetErrorKind, ObjectStore};
+use bytes::Bytes;
+use tokio::io::AsyncReadExt as _;
+use tracing::warn;
+
+use crate::dedup::{key_token, unix_millis};
+
+/// Key of the provider configuration enabling state snapshots when set to `true`
+pub const STATE_SNAPSHOT_CONFIG_KEY: &str = "state_snapshot";
+
+/// Key of the provider configuration holding the max age, in seconds, of the snapshot restored on
+/// startup
+pub const STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY: &str = "state_snapshot_max_age_secs";
+
+/// Max age of the snapshot restored on startup when not configured
+pub const DEFAULT_STATE_SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Name of the JetStream object store holding the state snapshots of providers
+pub const STATE_SNAPSHOT_BUCKET: &str = "wasmcloud_provider_state";
+
+/// Timeout of JetStream requests made to store and restore snapshots
+pub const STATE_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum age of snapshots in the bucket, bounding how long snapshots that are never restored
+/// are kept
+const STATE_SNAPSHOT_BUCKET_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Length of the header preceding the state in stored snapshots, holding the time the snapshot was
+/// taken in milliseconds since the UNIX epoch
+const HEADER_LEN: usize = 8;
+
+/// Max age of the snapshot restored on startup if the provider `config` enables state snapshots
+#[must_use]
+pub fn state_snapshot_max_age_from_config(config: &HashMap<String, String>) -> Option<Duration> {
+    let enabled = config
+        .get(STATE_SNAPSHOT_CONFIG_KEY)
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    if !enabled {
+        return None;
+    }
+    let max_age = match config.get(STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY) {
+        Some(max_age) => match max_age.trim().parse() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(_) => {
+                warn!(
+                    max_age,
+                    "invalid `{STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY}` configuration, using the default"
+                );
+                DEFAULT_STATE_SNAPSHOT_MAX_AGE
+            }
+        },
+        None => DEFAULT_STATE_SNAPSHOT_MAX_AGE,
+    };
+    Some(max_age.min(STATE_SNAPSHOT_BUCKET_MAX_AGE))
+}
+
+/// Snapshot of the state of a provider in a lattice, see the [module documentation](self).
+pub struct StateSnapshotStore {
+    store: ObjectStore,
+    name: String,
+}
+
+impl StateSnapshotStore {
+    /// Open the store of the snapshot of the provider with ID `provider_id` in `lattice`. The
+    /// bucket is created if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if JetStream is unavailable
+    pub async fn open(
+        nats: async_nats::Client,
+        lattice: &str,
+        provider_id: &str,
+    ) -> anyhow::Result<Self> {
+        let mut jetstream = async_nats::jetstream::new(nats);
+        jetstream.set_timeout(STATE_SNAPSHOT_TIMEOUT);
+        let store = match jetstream.get_object_store(STATE_SNAPSHOT_BUCKET).await {
+            Ok(store) => store,
+            Err(_) => jetstream
+                .create_object_store(object_store::Config {
+                    bucket: STATE_SNAPSHOT_BUCKET.to_string(),
+                    description: Some("State snapshots of providers being updated".to_string()),
+                    max_age: STATE_SNAPSHOT_BUCKET_MAX_AGE,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to create state snapshot bucket")?,
+        };
+        Ok(Self {
+            store,
+            name: snapshot_name(lattice, provider_id),
+        })
+    }
+
+    /// Store `state` as the snapshot of the provider, replacing any previous snapshot
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot could not be stored
+    pub async fn store(&self, state: &[u8]) -> anyhow::Result<()> {
+        let snapshot = encode(state, SystemTime::now());
+        self.store
+            .put(self.name.as_str(), &mut snapshot.as_slice())
+            .await
+            .map(|_| ())
+            .map_err(|err| anyhow!(err).context("failed to store state snapshot"))
+    }
+
+    /// Take the snapshot of the provider if it is younger than `max_age`. The snapshot is deleted
+    /// from the store, even if it is too old to be returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot could not be read or deleted
+    pub async fn take(&self, max_age: Duration) -> anyhow::Result<Option<Bytes>> {
+        let mut object = match self.store.get(self.name.as_str()).await {
+            Ok(object) => object,
+            Err(err) if err.kind() == GetErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(anyhow!(err).context("failed to get state snapshot")),
+        };
+        let mut snapshot = Vec::new();
+        object
+            .read_to_end(&mut snapshot)
+            .await
+            .context("failed to read state snapshot")?;
+        self.store
+            .delete(self.name.as_str())
+            .await
+            .map_err(|err| anyhow!(err).context("failed to delete state snapshot"))?;
+        Ok(decode(&snapshot, SystemTime::now(), max_age))
+    }
+}
+
+/// Name of the snapshot of the provider with ID `provider_id` in `lattice`
+fn snapshot_name(lattice: &str, provider_id: &str) -> String {
+    format!(
+        "provider_state.{}.{}",
+        key_token(lattice),
+        key_token(provider_id)
+    )
+}
+
+/// Snapshot of `state` taken at `taken_at`
+fn encode(state: &[u8], taken_at: SystemTime) -> Vec<u8> {
+    let mut snapshot = Vec::with_capacity(HEADER_LEN + state.len());
+    snapshot.extend_from_slice(&unix_millis(taken_at).to_be_bytes());
+    snapshot.extend_from_slice(state);
+    snapshot
+}
+
+/// State of `snapshot` if it is younger than `max_age` at `now`
+fn decode(snapshot: &[u8], now: SystemTime, max_age: Duration) -> Option<Bytes> {
+    let (header, state) = snapshot.split_first_chunk::<HEADER_LEN>()?;
+    let age = unix_millis(now).saturating_sub(u64::from_be_bytes(*header));
+    if Duration::from_millis(age) > max_age {
+        warn!(
+            age = ?Duration::from_millis(age),
+            ?max_age,
+            "discarding expired state snapshot"
+        );
+        return None;
+    }
+    Some(Bytes::copy_from_slice(state))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_encoding() {
+        let taken_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let snapshot = encode(b"cursor=42", taken_at);
+        assert_eq!(
+            snapshot_name("default", "wasmcloud:messaging/nats"),
+            "provider_state.default.wasmcloud_messaging_nats"
+        );
+
+        let max_age = Duration::from_secs(60);
+        assert_eq!(
+            decode(&snapshot, taken_at + Duration::from_secs(30), max_age).as_deref(),
+            Some(b"cursor=42".as_slice())
+        );
+        assert_eq!(
+            decode(&snapshot, taken_at + Duration::from_secs(61), max_age),
+            None
+        );
+        // Snapshots from hosts with clocks ahead are restored
+        assert!(decode(&snapshot, taken_at - Duration::from_secs(5), max_age).is_some());
+        assert_eq!(
+            decode(&encode(b"", taken_at), taken_at, max_age).as_deref(),
+            Some(b"".as_slice())
+        );
+        assert_eq!(decode(b"short", taken_at, max_age), None);
+    }
+
+    #[test]
+    fn test_state_snapshot_config() {
+        let config = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(state_snapshot_max_age_from_config(&config(&[])), None);
+        assert_eq!(
+            state_snapshot_max_age_from_config(&config(&[(STATE_SNAPSHOT_CONFIG_KEY, "TRUE")])),
+            Some(DEFAULT_STATE_SNAPSHOT_MAX_AGE)
+        );
+        assert_eq!(
+            state_snapshot_max_age_from_config(&config(&[
+                (STATE_SNAPSHOT_CONFIG_KEY, "true"),
+                (STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY, "30"),
+            ])),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            state_snapshot_max_age_from_config(&config(&[
+                (STATE_SNAPSHOT_CONFIG_KEY, "true"),
+                (STATE_SNAPSHOT_MAX_AGE_CONFIG_KEY, "soon"),
+            ])),
+            Some(DEFAULT_STATE_SNAPSHOT_MAX_AGE)
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Management of subscriptions created for the source links of a provider
+//!
+//! Messaging providers create a subscription (a consumer, a subscriber, ...) for the topics listed
+//! in the configuration of each of their source links, and deliver the messages received on it to
+//! the target of the link. [`SubscriptionManager`] keeps track of these subscriptions: the provider
+//! supplies how to create and destroy a subscription, and forwards link puts and deletes to the
+//! manager, which creates and destroys subscriptions so that exactly one subscription exists for
+//! each link with topics.
+//!
+//! The topics of a link are read from the [`SUBSCRIPTIONS_CONFIG`] (or [`TOPICS_CONFIG`]) key of
+//! its configuration, as a comma-separated list. All subscriptions are destroyed when the provider
+//! begins to shut down, see [`SubscriptionManager::attach`].
+
+use core::future::Future;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use futures::future::BoxFuture;
+use futures::FutureExt as _;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{LinkConfig, LinkKey, TaskGroup};
+
+/// Key of the link configuration holding the comma-separated topics to subscribe to
+pub const SUBSCRIPTIONS_CONFIG: &str = "subscriptions";
+
+/// Alternative key of the link configuration holding the comma-separated topics to subscribe to,
+/// used if [`SUBSCRIPTIONS_CONFIG`] is not set
+pub const TOPICS_CONFIG: &str = "topics";
+
+/// Returns the topics listed in the link `config`, without empty and duplicate entries
+#[must_use]
+pub fn parse_topics(config: &HashMap<String, String>) -> Vec<String> {
+    let Some(topics) = config
+        .get(SUBSCRIPTIONS_CONFIG)
+        .or_else(|| config.get(TOPICS_CONFIG))
+    else {
+        return Vec::new();
+    };
+    let mut parsed: Vec<String> = Vec::new();
+    for topic in topics.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !parsed.iter().any(|t| t == topic) {
+            parsed.push(topic.to_string());
+        }
+    }
+    parsed
+}
+
+type CreateSubscription<T> =
+    Box<dyn Fn(LinkKey, Vec<String>) -> BoxFuture<'static, anyhow::Result<T>> + Send + Sync>;
+type DestroySubscription<T> = Box<dyn Fn(T) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Number of subscriptions managed by a [`SubscriptionManager`], e.g. for health reporting
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionCounts {
+    /// Number of links with a subscription
+    pub links: usize,
+    /// Total number of topics subscribed to, over all links
+    pub topics: usize,
+}
+
+#[derive(Debug)]
+struct Subscription<T> {
+    topics: Vec<String>,
+    subscription: T,
+}
+
+#[derive(Debug)]
+struct Subscriptions<T> {
+    active: BTreeMap<LinkKey, Subscription<T>>,
+    /// Set once all subscriptions were destroyed on shutdown
+    closed: bool,
+}
+
+struct SubscriptionManagerState<T> {
+    create: CreateSubscription<T>,
+    destroy: DestroySubscription<T>,
+    /// Held while subscriptions are created or destroyed, so that changes of a link are applied in
+    /// order
+    subscriptions: Mutex<Subscriptions<T>>,
+}
+
+/// Subscriptions of the source links of a provider, see the [module documentation](self)
+pub struct SubscriptionManager<T> {
+    state: Arc<SubscriptionManagerState<T>>,
+}
+
+impl<T> Clone for SubscriptionManager<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for SubscriptionManager<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SubscriptionManager")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + 'static> SubscriptionManager<T> {
+    /// Create a manager subscribing to the topics of a link with `create_subscription` and
+    /// unsubscribing with `destroy_subscription`
+    pub fn new<C, CFut, D, DFut>(create_subscription: C, destroy_subscription: D) -> Self
+    where
+        C: Fn(LinkKey, Vec<String>) -> CFut + Send + Sync + 'static,
+        CFut: Future<Output = anyhow::Result<T>> + Send + 'static,
+        D: Fn(T) -> DFut + Send + Sync + 'static,
+        DFut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            state: Arc::new(SubscriptionManagerState {
+                create: Box::new(move |link, topics| create_subscription(link, topics).boxed()),
+                destroy: Box::new(move |subscription| destroy_subscription(subscription).boxed()),
+                subscriptions: Mutex::new(Subscriptions {
+                    active: BTreeMap::new(),
+                    closed: false,
+                }),
+            }),
+        }
+    }
+
+    /// Destroy all subscriptions once the provider owning `tasks` begins to shut down, after which
+    /// no subscriptions are created anymore
+    pub fn attach(&self, tasks: &TaskGroup) {
+        let manager = self.clone();
+        tasks.spawn("subscription-manager", |cancel| async move {
+            cancel.cancelled().await;
+            manager.shutdown().await;
+        });
+    }
+
+    /// Handle a put of a source link, subscribing to the topics in its configuration.
+    ///
+    /// Putting a link with the topics it is already subscribed to does nothing, while a link whose
+    /// topics changed is resubscribed to the new topics. A link without topics is unsubscribed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if creating the subscription failed, in which case the link has no
+    /// subscription, or if the manager was shut down
+    pub async fn put_link(&self, link: &LinkConfig<'_>) -> anyhow::Result<()> {
+        let key = link.key();
+        let topics = parse_topics(link.config);
+        let mut subscriptions = self.state.subscriptions.lock().await;
+        if subscriptions.closed {
+            bail!("subscription manager is shut down");
+        }
+        if let Some(existing) = subscriptions.active.get(&key) {
+            if existing.topics == topics {
+                debug!(?key, "link is already subscribed to its topics");
+                return Ok(());
+            }
+        }
+        if let Some(existing) = subscriptions.active.remove(&key) {
+            debug!(?key, topics = ?existing.topics, "destroying outdated subscription");
+            (self.state.destroy)(existing.subscription).await;
+        }
+        if topics.is_empty() {
+            return Ok(());
+        }
+        debug!(?key, ?topics, "creating subscription");
+        let subscription = (self.state.create)(key.clone(), topics.clone())
+            .await
+            .with_context(|| format!("failed to subscribe link {key} to {topics:?}"))?;
+        subscriptions.active.insert(
+            key,
+            Subscription {
+                topics,
+                subscription,
+            },
+        );
+        Ok(())
+    }
+
+    /// Handle a delete of the link identified by `key`, destroying its subscription, if any
+    pub async fn delete_link(&self, key: &LinkKey) {
+        let mut subscriptions = self.state.subscriptions.lock().await;
+        if let Some(existing) = subscriptions.active.remove(key) {
+            debug!(?key, "destroying subscription of deleted link");
+            (self.state.destroy)(existing.subscription).await;
+        }
+    }
+
+    /// Handle a delete of all links targeting `target`, as reported by
+    /// [`crate::Provider::delete_link_as_source`]
+    pub async fn delete_links_to(&self, target: &str) {
+        let mut subscriptions = self.state.subscriptions.lock().await;
+        let keys: Vec<_> = subscriptions
+            .active
+            .keys()
+            .filter(|key| key.target_id == target)
+            .cloned()
+            .collect();
+        for key in keys {
+            if let Some(existing) = subscriptions.active.remove(&key) {
+                debug!(?key, "destroying subscription of deleted link");
+                (self.state.destroy)(existing.subscription).await;
+            }
+        }
+    }
+
+    /// Destroy all subscriptions, after which no subscriptions are created anymore
+    pub async fn shutdown(&self) {
+        let mut subscriptions = self.state.subscriptions.lock().await;
+        subscriptions.closed = true;
+        for (key, existing) in std::mem::take(&mut subscriptions.active) {
+            debug!(?key, "destroying subscription on shutdown");
+            (self.state.destroy)(existing.subscription).await;
+        }
+    }
+
+    /// Returns the topics the link identified by `key` is subscribed to, if it has a subscription
+    pub async fn topics(&self, key: &LinkKey) -> Option<Vec<String>> {
+        let subscriptions = self.state.subscriptions.lock().await;
+        subscriptions
+            .active
+            .get(key)
+            .map(|existing| existing.topics.clone())
+    }
+
+    /// Returns the number of managed subscriptions
+    pub async fn counts(&self) -> SubscriptionCounts {
+        let subscriptions = self.state.subscriptions.lock().await;
+        SubscriptionCounts {
+            links: subscriptions.active.len(),
+            topics: subscriptions
+                .active
+                .values()
+                .map(|existing| existing.topics.len())
+                .sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex as StdMutex;
+
+    use super::*;
+    use crate::TASK_DRAIN_TIMEOUT;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum TransportEvent {
+        Subscribed(u64, String, Vec<String>),
+        Unsubscribed(u64),
+    }
+
+    /// Transport recording the subscriptions created and destroyed through it
+    #[derive(Clone, Default)]
+    struct FakeTransport {
+        next_id: Arc<AtomicU64>,
+        events: Arc<StdMutex<Vec<TransportEvent>>>,
+    }
+
+    impl FakeTransport {
+        fn manager(&self) -> SubscriptionManager<u64> {
+            let created = self.clone();
+            let destroyed = self.clone();
+            SubscriptionManager::new(
+                move |link: LinkKey, topics| {
+                    let id = created.next_id.fetch_add(1, Ordering::SeqCst);
+                    created
+                        .events
+                        .lock()
+                        .unwrap()
+                        .push(TransportEvent::Subscribed(id, link.target_id, topics));
+                    async move { Ok(id) }
+                },
+                move |id| {
+                    let events = Arc::clone(&destroyed.events);
+                    async move {
+                        events
+                            .lock()
+                            .unwrap()
+                            .push(TransportEvent::Unsubscribed(id))
+                    }
+                },
+            )
+        }
+
+        fn take_events(&self) -> Vec<TransportEvent> {
+            std::mem::take(&mut self.events.lock().unwrap())
+        }
+    }
+
+    fn topics(topics: &[&str]) -> Vec<String> {
+        topics.iter().map(ToString::to_string).collect()
+    }
+
+    async fn put_link(
+        manager: &SubscriptionManager<u64>,
+        target: &str,
+        config: &[(&str, &str)],
+    ) -> anyhow::Result<()> {
+        let config = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let interfaces = vec!["handler".to_string()];
+        manager
+            .put_link(&LinkConfig {
+                target_id: target,
+                source_id: "provider",
+                link_name: "default",
+                config: &config,
+                wit_metadata: (
+                    &"wasmcloud".to_string(),
+                    &"messaging".to_string(),
+                    &interfaces,
+                ),
+            })
+            .await
+    }
+
+    fn key(target: &str) -> LinkKey {
+        LinkKey::new("provider", target, "default")
+    }
+
+    #[test]
+    fn test_parse_topics() {
+        let config = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            parse_topics(&config(&[(SUBSCRIPTIONS_CONFIG, " a, b,,a ,c ")])),
+            topics(&["a", "b", "c"])
+        );
+        assert_eq!(
+            parse_topics(&config(&[(TOPICS_CONFIG, "a")])),
+            topics(&["a"])
+        );
+        assert_eq!(
+            parse_topics(&config(&[
+                (SUBSCRIPTIONS_CONFIG, "a"),
+                (TOPICS_CONFIG, "b")
+            ])),
+            topics(&["a"])
+        );
+        assert!(parse_topics(&config(&[("other", "a")])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_follow_links() {
+        use TransportEvent::{Subscribed, Unsubscribed};
+
+        let transport = FakeTransport::default();
+        let manager = transport.manager();
+        let tasks = TaskGroup::default();
+        manager.attach(&tasks);
+
+        put_link(&manager, "alice", &[(SUBSCRIPTIONS_CONFIG, "a,b")])
+            .await
+            .unwrap();
+        put_link(&manager, "bob", &[(TOPICS_CONFIG, "c")])
+            .await
+            .unwrap();
+        assert_eq!(
+            transport.take_events(),
+            vec![
+                Subscribed(0, "alice".into(), topics(&["a", "b"])),
+                Subscribed(1, "bob".into(), topics(&["c"])),
+            ]
+        );
+        assert_eq!(
+            manager.counts().await,
+            SubscriptionCounts {
+                links: 2,
+                topics: 3
+            }
+        );
+
+        // Duplicate link puts do not resubscribe
+        put_link(&manager, "alice", &[(SUBSCRIPTIONS_CONFIG, "a, b")])
+            .await
+            .unwrap();
+        assert!(transport.take_events().is_empty());
+
+        // Changing the topics replaces the subscription, removing them only unsubscribes
+        put_link(&manager, "alice", &[(SUBSCRIPTIONS_CONFIG, "d")])
+            .await
+            .unwrap();
+        assert_eq!(manager.topics(&key("alice")).await, Some(topics(&["d"])));
+        put_link(&manager, "bob", &[]).await.unwrap();
+        assert_eq!(manager.topics(&key("bob")).await, None);
+        assert_eq!(
+            transport.take_events(),
+            vec![
+                Unsubscribed(0),
+                Subscribed(2, "alice".into(), topics(&["d"])),
+                Unsubscribed(1),
+            ]
+        );
+
+        // Deleting a link unsubscribes it
+        put_link(&manager, "carol", &[(SUBSCRIPTIONS_CONFIG, "e")])
+            .await
+            .unwrap();
+        manager.delete_link(&key("carol")).await;
+        manager.delete_link(&key("carol")).await;
+        assert_eq!(
+            transport.take_events(),
+            vec![
+                Subscribed(3, "carol".into(), topics(&["e"])),
+                Unsubscribed(3)
+            ]
+        );
+
+        // Shutting down the provider unsubscribes all remaining links
+        tasks.cancel();
+        tasks.drain(TASK_DRAIN_TIMEOUT).await;
+        assert_eq!(transport.take_events(), vec![Unsubscribed(2)]);
+        assert_eq!(manager.counts().await, SubscriptionCounts::default());
+        assert!(put_link(&manager, "alice", &[(SUBSCRIPTIONS_CONFIG, "a")])
+            .await
+            .is_err());
+        assert!(transport.take_events().is_empty());
+    }
+}
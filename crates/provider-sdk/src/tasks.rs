@@ -0,0 +1,208 @@
+//! Background tasks tied to the lifecycle of a provider
+//!
+//! Providers commonly run tasks next to serving invocations, such as pollers or connection
+//! keepalives. Tasks spawned through the [`TaskGroup`] of the provider (see
+//! [`crate::ProviderConnection::tasks`]) are shut down along with the provider: when shutdown
+//! begins, the [`CancellationToken`] passed to every task is cancelled, and the shutdown is only
+//! acknowledged to the host once all tasks finished or [`TASK_DRAIN_TIMEOUT`] elapsed.
+//!
+//! Panics of tasks are logged with the name of the task and do not affect the provider.
+
+use core::future::Future;
+use core::panic::AssertUnwindSafe;
+use core::time::Duration;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use futures::FutureExt as _;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, warn, Instrument as _};
+
+/// Maximum duration to wait for tasks to finish after they were cancelled during shutdown
+pub const TASK_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+struct Tasks {
+    next_id: u64,
+    /// Name and handle of all running tasks, in the order they were spawned
+    running: BTreeMap<u64, (String, JoinHandle<()>)>,
+}
+
+#[derive(Debug, Default)]
+struct TaskGroupState {
+    cancel: CancellationToken,
+    tasks: Mutex<Tasks>,
+}
+
+impl TaskGroupState {
+    fn lock(&self) -> MutexGuard<'_, Tasks> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Group of named background tasks, shut down along with the provider, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, Default)]
+pub struct TaskGroup {
+    state: Arc<TaskGroupState>,
+}
+
+impl TaskGroup {
+    /// Spawn a task named `name`, created by `task` from the token cancelled when the provider
+    /// begins to shut down. The task is expected to return soon after the token is cancelled.
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, task: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        let task = task(self.state.cancel.child_token());
+        // The lock is held until the task is registered, so that it cannot deregister itself first
+        let mut tasks = self.state.lock();
+        let id = tasks.next_id;
+        tasks.next_id += 1;
+        let state = Arc::downgrade(&self.state);
+        let handle = tokio::spawn(run_task(name.clone(), id, state, task));
+        tasks.running.insert(id, (name, handle));
+    }
+
+    /// Spawn a task named `name` calling `f` every `period` until the provider begins to shut
+    /// down. An invocation of `f` still in progress when shutdown begins is cancelled.
+    pub fn spawn_interval<F, Fut>(&self, name: impl Into<String>, period: Duration, mut f: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn(name, move |cancel| async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    () = cancel.cancelled() => return,
+                    _ = interval.tick() => {}
+                }
+                tokio::select! {
+                    () = cancel.cancelled() => return,
+                    () = f() => {}
+                }
+            }
+        });
+    }
+
+    /// Returns the names of all running tasks, in the order they were spawned
+    #[must_use]
+    pub fn status(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .running
+            .values()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Returns true once the provider began to shut down
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancel.is_cancelled()
+    }
+
+    /// Cancel all tasks, including tasks spawned from now on
+    pub(crate) fn cancel(&self) {
+        self.state.cancel.cancel();
+    }
+
+    /// Wait for all tasks to finish for at most `timeout`, aborting those which do not finish in time
+    pub(crate) async fn drain(&self, timeout: Duration) {
+        let running = std::mem::take(&mut self.state.lock().running);
+        let deadline = Instant::now() + timeout;
+        for (name, handle) in running.into_values() {
+            let abort = handle.abort_handle();
+            if tokio::time::timeout_at(deadline, handle).await.is_err() {
+                warn!(task = %name, "provider task did not finish in time, aborting");
+                abort.abort();
+            }
+        }
+    }
+}
+
+/// Run a task, logging panics and deregistering it once it finished
+async fn run_task(
+    name: String,
+    id: u64,
+    state: Weak<TaskGroupState>,
+    task: impl Future<Output = ()> + Send + 'static,
+) {
+    let span = tracing::debug_span!("provider_task", task = %name);
+    if let Err(panic) = AssertUnwindSafe(task).catch_unwind().instrument(span).await {
+        let panic = panic
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        error!(task = %name, %panic, "provider task panicked");
+    }
+    if let Some(state) = state.upgrade() {
+        state.lock().running.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tasks_are_cancelled_and_drained() {
+        let tasks = TaskGroup::default();
+        let ticks = Arc::new(AtomicUsize::new(0));
+        tasks.spawn_interval("poller", Duration::from_millis(10), {
+            let ticks = Arc::clone(&ticks);
+            move || {
+                ticks.fetch_add(1, Ordering::SeqCst);
+                async {}
+            }
+        });
+        tasks.spawn("keepalive", |cancel| async move {
+            cancel.cancelled().await;
+            // Cleanup taking some time is awaited
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        tasks.spawn("stuck", |_| std::future::pending());
+        assert_eq!(tasks.status(), vec!["poller", "keepalive", "stuck"]);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(ticks.load(Ordering::SeqCst) > 0);
+
+        tasks.cancel();
+        assert!(tasks.is_cancelled());
+        let started = Instant::now();
+        tasks.drain(Duration::from_millis(200)).await;
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(tasks.status().is_empty());
+
+        // Tasks stopped ticking once cancelled
+        let ticked = ticks.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), ticked);
+    }
+
+    #[tokio::test]
+    async fn test_task_panics_are_contained() {
+        let tasks = TaskGroup::default();
+        tasks.spawn("panicking", |_| async { panic!("task failed") });
+        tasks.spawn("healthy", |cancel| async move { cancel.cancelled().await });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(tasks.status(), vec!["healthy"]);
+
+        tasks.cancel();
+        tasks.drain(TASK_DRAIN_TIMEOUT).await;
+        assert!(tasks.status().is_empty());
+    }
+}
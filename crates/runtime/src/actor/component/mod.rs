@@ -419,7 +419,10 @@ where
                         let target = handler
                             .identify_interface_target(&target)
                             .await
-                            .context("failed to identify interface target")?;
+                            .with_context(|| {
+                                let (namespace, package, interface) = target.as_parts();
+                                format!("unknown `{namespace}:{package}/{interface}` target")
+                            })?;
                         let result_values = handler
                             .call(target, &instance_name, &func_name, params)
                             .await
@@ -5,7 +5,9 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 
+use opentelemetry::baggage::BaggageExt;
 use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::KeyValue;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use tracing::span::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
@@ -128,3 +130,31 @@ pub fn attach_span_context(trace_context: &TraceContext) {
     let parent_ctx = ctx_propagator.extract(&extractor);
     Span::current().set_parent(parent_ctx);
 }
+
+/// Like [`attach_span_context`], also attaching `baggage` to the parent context, so that it is
+/// available from [`current_baggage`] in the current span and its children
+#[allow(clippy::module_name_repetitions)]
+pub fn attach_span_context_with_baggage(
+    trace_context: &TraceContext,
+    baggage: &HashMap<String, String>,
+) {
+    let ctx_propagator = TraceContextPropagator::new();
+    let extractor = TraceContextExtractor::new(trace_context);
+    let parent_ctx = ctx_propagator.extract(&extractor).with_baggage(
+        baggage
+            .iter()
+            .map(|(k, v)| KeyValue::new(k.clone(), v.clone())),
+    );
+    Span::current().set_parent(parent_ctx);
+}
+
+/// Baggage of the current span, as attached by [`attach_span_context_with_baggage`]
+#[must_use]
+pub fn current_baggage() -> HashMap<String, String> {
+    Span::current()
+        .context()
+        .baggage()
+        .iter()
+        .map(|(k, (v, _))| (k.to_string(), v.to_string()))
+        .collect()
+}
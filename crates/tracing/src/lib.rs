@@ -17,13 +17,34 @@ mod traces;
 
 mod metrics;
 
-#[cfg(not(feature = "otel"))]
+pub use traces::BoxedLayer;
+
+/// Configures observability for each type of signal
 pub fn configure_observability(
+    service_name: &str,
+    otel_config: &OtelConfig,
+    use_structured_logging: bool,
+    flame_graph: Option<impl AsRef<Path>>,
+    log_level_override: Option<&Level>,
+) -> anyhow::Result<traces::FlushGuard> {
+    configure_observability_with_layer(
+        service_name,
+        otel_config,
+        use_structured_logging,
+        flame_graph,
+        log_level_override,
+        None,
+    )
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn configure_observability_with_layer(
     _: &str,
     _: &OtelConfig,
     use_structured_logging: bool,
     flame_graph: Option<impl AsRef<Path>>,
     log_level_override: Option<&Level>,
+    layer: Option<BoxedLayer>,
 ) -> anyhow::Result<traces::FlushGuard> {
     // if OTEL is not enabled, explicitly do not emit observability
     let otel_config = OtelConfig::default();
@@ -33,17 +54,20 @@ pub fn configure_observability(
         use_structured_logging,
         flame_graph,
         log_level_override,
+        layer,
     )
 }
 
-/// Configures observability for each type of signal
+/// Configures observability for each type of signal, adding `layer`, if any, to the global tracing
+/// subscriber
 #[cfg(feature = "otel")]
-pub fn configure_observability(
+pub fn configure_observability_with_layer(
     service_name: &str,
     otel_config: &OtelConfig,
     use_structured_logging: bool,
     flame_graph: Option<impl AsRef<Path>>,
     log_level_override: Option<&Level>,
+    layer: Option<BoxedLayer>,
 ) -> anyhow::Result<traces::FlushGuard> {
     let normalized_service_name = service_name.to_kebab_case();
 
@@ -57,5 +81,6 @@ pub fn configure_observability(
         use_structured_logging,
         flame_graph,
         log_level_override,
+        layer,
     )
 }
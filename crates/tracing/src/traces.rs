@@ -54,6 +54,11 @@ where
     }
 }
 
+/// A type-erased [`Layer`](tracing_subscriber::Layer) added to the global tracing subscriber, in
+/// addition to the layers configured by this crate
+pub type BoxedLayer =
+    Box<dyn tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync + 'static>;
+
 pub struct FlushGuard {
     _stderr: tracing_appender::non_blocking::WorkerGuard,
     _flame: Option<tracing_flame::FlushGuard<BufWriter<File>>>,
@@ -61,6 +66,7 @@ pub struct FlushGuard {
 
 /// Configures a global tracing subscriber, which includes:
 /// - A level filter, which forms the base and applies to all other layers
+/// - The given layer, if any
 /// - A local logging layer, which is either plaintext or structured (JSON)
 ///
 /// # Errors
@@ -74,10 +80,12 @@ pub fn configure_tracing(
     use_structured_logging: bool,
     flame_graph: Option<impl AsRef<Path>>,
     log_level_override: Option<&Level>,
+    layer: Option<BoxedLayer>,
 ) -> anyhow::Result<FlushGuard> {
     let flame = flame_graph.map(FlameLayer::with_file).transpose()?;
     let (flame, flame_guard) = flame.map(|(l, g)| (Some(l), Some(g))).unwrap_or_default();
     let reg = tracing_subscriber::Registry::default()
+        .with(layer)
         .with(get_level_filter(log_level_override))
         .with(flame);
     let stderr = std::io::stderr();
@@ -111,6 +119,7 @@ pub fn configure_tracing(
 /// Configures a global tracing subscriber, which includes:
 /// - A level filter, which forms the base and applies to all other layers
 /// - OTEL tracing and logging layers, if OTEL configuration is provided
+/// - The given layer, if any
 /// - A local logging layer, which is either plaintext or structured (JSON)
 ///
 /// # Errors
@@ -124,6 +133,7 @@ pub fn configure_tracing(
     use_structured_logging: bool,
     flame_graph: Option<impl AsRef<Path>>,
     log_level_override: Option<&Level>,
+    layer: Option<BoxedLayer>,
 ) -> anyhow::Result<FlushGuard> {
     let service_name = Arc::from(service_name);
 
@@ -138,6 +148,7 @@ pub fn configure_tracing(
     let flame = flame_graph.map(FlameLayer::with_file).transpose()?;
     let (flame, flame_guard) = flame.map(|(l, g)| (Some(l), Some(g))).unwrap_or_default();
     let reg = tracing_subscriber::Registry::default()
+        .with(layer)
         .with(get_level_filter(log_level_override))
         .with(traces)
         .with(logs)
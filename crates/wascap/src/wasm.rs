@@ -91,6 +91,15 @@ pub fn embed_claims(
     Ok(bytes)
 }
 
+/// Removes any claims embedded in the bytecode of a WebAssembly module or component, returning
+/// the bytecode as it was before [`embed_claims`] was called
+///
+/// # Errors
+/// Will return an error if the bytecode cannot be parsed
+pub fn strip_claims(bytecode: &[u8]) -> Result<Vec<u8>> {
+    strip_custom_section(bytecode)
+}
+
 /// Sign a buffer containing bytes for a WebAssembly component
 /// with provided claims
 #[allow(clippy::too_many_arguments)]
@@ -255,6 +264,10 @@ mod test {
         let modified_bytecode = embed_claims(&buffer, &claims, &kp).unwrap();
 
         super::strip_custom_section(&modified_bytecode).unwrap();
+
+        let stripped = strip_claims(&modified_bytecode).unwrap();
+        assert!(extract_claims(&stripped).unwrap().is_none());
+        assert_eq!(stripped, strip_claims(&buffer).unwrap());
     }
 
     #[test]
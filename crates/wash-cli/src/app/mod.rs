@@ -1,23 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{bail, Context};
 use clap::{Args, Subcommand};
+use console::style;
 use serde_json::json;
 use wadm_client::Result;
 use wadm_types::api::ModelSummary;
 use wadm_types::validation::{validate_manifest_file, ValidationFailure, ValidationOutput};
-use wash_lib::app::{load_app_manifest, AppManifest};
+use wash_lib::app::{
+    load_app_manifest, load_app_manifest_with_variables, rollback_target_version, AppManifest,
+    ManifestVariables, DEFAULT_ROLLBACK_TIMEOUT,
+};
+use wash_lib::cli::app_instances::AppInstances;
+use wash_lib::cli::manifest_lint::lint_manifest_file;
+use wash_lib::cli::preflight::{
+    host_targets, manifest_images, preflight_images, ImageKind, ImagePreflight, PreflightStatus,
+};
+use wash_lib::cli::snapshot::{snapshot_manifest, LatticeSnapshot, SnapshotManifest};
+use wash_lib::cli::table::TableOpts;
 use wash_lib::cli::{CliConnectionOpts, CommandOutput, OutputKind};
+use wash_lib::common::{find_host_id, get_all_inventories};
 use wash_lib::config::WashConnectionOptions;
+use wash_lib::generate::emoji;
+use wash_lib::registry::registries_with_env;
 
 use crate::appearance::spinner::Spinner;
+use watch::{watch_status, WatchUntil};
 
 mod output;
+mod watch;
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum AppCliCommand {
     /// List all applications available within the lattice
+    ///
+    /// Columns: `name`, `version`, `deployed-version`, `status`, `description`, and with `--detailed`,
+    /// `instances` and `provisioning`
     #[clap(name = "list")]
     List(ListCommand),
     /// Get the application manifest for a specific version of an application
@@ -29,6 +49,9 @@ pub enum AppCliCommand {
     /// Get the version history of a given application
     #[clap(name = "history")]
     History(HistoryCommand),
+    /// Roll an application back to a previously stored version and wait for it to be deployed
+    #[clap(name = "rollback")]
+    Rollback(RollbackCommand),
     /// Delete an application version
     #[clap(name = "delete", alias = "del")]
     Delete(DeleteCommand),
@@ -41,15 +64,26 @@ pub enum AppCliCommand {
     /// Undeploy an application, removing it from the lattice
     #[clap(name = "undeploy")]
     Undeploy(UndeployCommand),
-    /// Validate an application manifest
+    /// Validate an application manifest without deploying it, exiting with an error if it is invalid
     #[clap(name = "validate")]
     Validate(ValidateCommand),
+    /// Capture the components, providers and links running in the lattice as an application manifest
+    #[clap(name = "snapshot")]
+    Snapshot(SnapshotCommand),
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct ListCommand {
     #[clap(flatten)]
     opts: CliConnectionOpts,
+
+    /// Query the status and running instances of deployed applications, showing the desired and actual
+    /// instances of each component and flagging under-provisioned applications
+    #[clap(long = "detailed")]
+    detailed: bool,
+
+    #[clap(flatten)]
+    table: TableOpts,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -76,10 +110,62 @@ pub struct DeployCommand {
     #[clap(long = "replace")]
     replace: bool,
 
+    /// Skip checking that the images of the manifest exist and match the kind of their components before deploying it
+    #[clap(long = "skip-preflight")]
+    skip_preflight: bool,
+
+    #[clap(flatten)]
+    variables: ManifestVariablesArgs,
+
     #[clap(flatten)]
     opts: CliConnectionOpts,
 }
 
+/// Arguments resolving the `${NAME}` and `${NAME:-default}` placeholders of application manifests
+#[derive(Args, Debug, Clone, Default)]
+pub struct ManifestVariablesArgs {
+    /// Set the value of a manifest placeholder, e.g. `--set IMAGE_TAG=0.1.0`. Takes precedence over values files and environment variables, and can be specified multiple times
+    #[clap(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Path to a YAML or JSON file mapping manifest placeholders to values. Takes precedence over environment variables
+    #[clap(long = "values-file")]
+    values_file: Option<PathBuf>,
+
+    /// Print the application manifest with its placeholders substituted, without sending it to wadm
+    #[clap(long = "show-rendered")]
+    show_rendered: bool,
+}
+
+impl ManifestVariablesArgs {
+    /// Load the application manifest at `source`, or stdin, substituting its placeholders
+    async fn load_manifest(&self, source: Option<&str>) -> anyhow::Result<AppManifest> {
+        let mut variables = ManifestVariables::from_env();
+        if let Some(values_file) = &self.values_file {
+            variables = variables.with_values_file(values_file)?;
+        }
+        let variables = variables.with_set_values(&self.set)?;
+        load_app_manifest_with_variables(source.unwrap_or("-").parse()?, &variables).await
+    }
+
+    /// Output of `--show-rendered` for `manifest`, if it was requested
+    fn rendered_output(&self, manifest: &AppManifest) -> anyhow::Result<Option<CommandOutput>> {
+        match manifest {
+            AppManifest::SerializedModel(manifest) if self.show_rendered => {
+                let rendered = serde_yaml::to_string(manifest)
+                    .context("failed to convert manifest to string")?;
+                let mut map = HashMap::new();
+                map.insert("manifest".to_string(), json!(rendered));
+                Ok(Some(CommandOutput::new(rendered, map)))
+            }
+            AppManifest::ModelName(name) if self.show_rendered => {
+                bail!("cannot render application [{name}], which is not a manifest")
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct DeleteCommand {
     /// Name of the application to delete, or a path to a Wadm Application Manifest
@@ -99,6 +185,9 @@ pub struct PutCommand {
     /// The source of the application manifest, either a file path, remote file http url, or stdin. If no source is provided (or arg marches '-'), stdin is used.
     source: Option<String>,
 
+    #[clap(flatten)]
+    variables: ManifestVariablesArgs,
+
     #[clap(flatten)]
     opts: CliConnectionOpts,
 }
@@ -123,6 +212,18 @@ pub struct StatusCommand {
     #[clap(name = "name")]
     app_name: String,
 
+    /// Keep watching the status of the application, printing it every time it changes
+    #[clap(long = "watch")]
+    pub watch: bool,
+
+    /// Stop watching once the application reaches this status
+    #[clap(long = "until", value_enum, ignore_case = true, requires = "watch")]
+    until: Option<WatchUntil>,
+
+    /// Exit with an error once the application has been failing for this long, in milliseconds
+    #[clap(long = "until-failed-timeout-ms", requires = "watch")]
+    until_failed_timeout_ms: Option<u64>,
+
     #[clap(flatten)]
     opts: CliConnectionOpts,
 }
@@ -137,6 +238,29 @@ pub struct HistoryCommand {
     opts: CliConnectionOpts,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct RollbackCommand {
+    /// The name of the application
+    #[clap(name = "name")]
+    app_name: String,
+
+    /// Version to roll back to, defaults to the version stored before the currently deployed version
+    #[clap(long = "to-version")]
+    to_version: Option<String>,
+
+    /// By default, the command will wait until wadm reports the rolled back version as deployed.
+    /// If this flag is passed, the command will return as soon as the version has been deployed.
+    #[clap(long = "skip-wait")]
+    skip_wait: bool,
+
+    /// Timeout for waiting for the rolled back version to be deployed, defaults to 60000 milliseconds
+    #[clap(long = "wait-timeout-ms", default_value_t = DEFAULT_ROLLBACK_TIMEOUT.as_millis() as u64)]
+    wait_timeout_ms: u64,
+
+    #[clap(flatten)]
+    opts: CliConnectionOpts,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct ValidateCommand {
     /// Path to the application manifest to validate
@@ -144,6 +268,28 @@ pub struct ValidateCommand {
     application: PathBuf,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct SnapshotCommand {
+    /// Name of the captured application
+    #[clap(long = "name")]
+    name: String,
+
+    /// ID or friendly name of the host to capture, defaults to all hosts of the lattice
+    #[clap(long = "host-id")]
+    host_id: Option<String>,
+
+    /// ID of a component to capture along with the components it is linked with, can be specified multiple times. Defaults to all components
+    #[clap(long = "include-component", value_name = "COMPONENT_ID")]
+    include_component: Vec<String>,
+
+    /// Path of the file to write the manifest to, defaults to stdout
+    #[clap(long = "file", short = 'f')]
+    file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    opts: CliConnectionOpts,
+}
+
 pub async fn handle_command(
     command: AppCliCommand,
     output_kind: OutputKind,
@@ -153,12 +299,24 @@ pub async fn handle_command(
     let out: CommandOutput = match command {
         List(cmd) => {
             sp.update_spinner_message("Listing applications ...".to_string());
-            get_applications(cmd).await?
+            get_applications(cmd, output_kind).await?
         }
         Get(cmd) => {
             sp.update_spinner_message("Getting application manifest ... ".to_string());
             get_manifest(cmd).await?
         }
+        Status(cmd) if cmd.watch => {
+            sp.finish_and_clear();
+            watch_status(
+                cmd.app_name,
+                cmd.opts,
+                cmd.until,
+                cmd.until_failed_timeout_ms.map(Duration::from_millis),
+                output_kind,
+            )
+            .await?;
+            CommandOutput::default()
+        }
         Status(cmd) => {
             sp.update_spinner_message("Getting application status ... ".to_string());
             get_model_status(cmd).await?
@@ -167,6 +325,10 @@ pub async fn handle_command(
             sp.update_spinner_message("Getting application version history ... ".to_string());
             get_application_versions(cmd).await?
         }
+        Rollback(cmd) => {
+            sp.update_spinner_message("Rolling back application ... ".to_string());
+            rollback_model(cmd).await?
+        }
         Delete(cmd) => {
             sp.update_spinner_message("Deleting application version ... ".to_string());
             delete_application_version(cmd).await?
@@ -185,17 +347,62 @@ pub async fn handle_command(
         }
         Validate(cmd) => {
             sp.update_spinner_message("Validating application manifest ... ".to_string());
-            let (_manifest, validation_results) = validate_manifest_file(&cmd.application)
+            let (_manifest, mut validation_results) = validate_manifest_file(&cmd.application)
                 .await
                 .context("failed to validate Wadm manifest")?;
+            validation_results.extend(
+                lint_manifest_file(&cmd.application)
+                    .await
+                    .context("failed to lint Wadm manifest")?,
+            );
             show_validate_manifest_results(validation_results)
         }
+        Snapshot(cmd) => {
+            sp.update_spinner_message("Capturing lattice ... ".to_string());
+            snapshot_lattice(cmd).await?
+        }
     };
     sp.finish_and_clear();
 
     Ok(out)
 }
 
+async fn snapshot_lattice(cmd: SnapshotCommand) -> anyhow::Result<CommandOutput> {
+    let connection_opts =
+        <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let client = connection_opts.into_ctl_client(None).await?;
+
+    let host_id = match cmd.host_id {
+        Some(host) => Some(find_host_id(&host, &client).await?.0),
+        None => None,
+    };
+    let snapshot = LatticeSnapshot::capture(&client, host_id.as_ref()).await?;
+    let SnapshotManifest { manifest, warnings } =
+        snapshot_manifest(&cmd.name, &snapshot, &cmd.include_component);
+    for warning in &warnings {
+        eprintln!("🟨 {warning}");
+    }
+    let yaml = serde_yaml::to_string(&manifest).context("failed to convert manifest to string")?;
+
+    let mut map = HashMap::new();
+    map.insert("warnings".to_string(), json!(warnings));
+    let text = if let Some(file) = &cmd.file {
+        tokio::fs::write(file, &yaml)
+            .await
+            .with_context(|| format!("failed to write manifest to [{}]", file.display()))?;
+        map.insert("file".to_string(), json!(file));
+        format!(
+            "Captured application \"{}\" in [{}]",
+            cmd.name,
+            file.display()
+        )
+    } else {
+        yaml
+    };
+    map.insert("manifest".to_string(), json!(manifest));
+    Ok(CommandOutput::new(text, map))
+}
+
 async fn undeploy_model(cmd: UndeployCommand) -> Result<CommandOutput> {
     let connection_opts =
         <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
@@ -229,17 +436,26 @@ async fn undeploy_model(cmd: UndeployCommand) -> Result<CommandOutput> {
 }
 
 async fn deploy_model(cmd: DeployCommand) -> Result<CommandOutput> {
+    // Placeholders are resolved before anything is sent to wadm
+    let app_manifest = cmd.variables.load_manifest(cmd.app_name.as_deref()).await?;
+    if let Some(output) = cmd.variables.rendered_output(&app_manifest)? {
+        return Ok(output);
+    }
+
     let connection_opts =
         <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
     let lattice = Some(connection_opts.get_lattice());
 
-    let client = connection_opts.into_nats_client().await?;
-
-    let app_manifest = match cmd.app_name {
-        Some(source) => load_app_manifest(source.parse()?).await?,
-        None => load_app_manifest("-".parse()?).await?,
+    // Images are checked before anything is sent to wadm
+    let preflight = match &app_manifest {
+        AppManifest::SerializedModel(manifest) if !cmd.skip_preflight => {
+            Some(preflight_manifest(manifest, connection_opts.clone()).await?)
+        }
+        _ => None,
     };
 
+    let client = connection_opts.into_nats_client().await?;
+
     // If --replace was specified, we should attempt to replace the resources by deleting them beforehand
     if cmd.replace {
         if let (Some(name), version) = (
@@ -254,7 +470,61 @@ async fn deploy_model(cmd: DeployCommand) -> Result<CommandOutput> {
         }
     }
 
-    deploy_model_from_manifest(&client, lattice, app_manifest, cmd.version).await
+    let mut output =
+        deploy_model_from_manifest(&client, lattice, app_manifest, cmd.version).await?;
+    if let Some(preflight) = preflight {
+        output.map.insert("preflight".to_string(), json!(preflight));
+    }
+    Ok(output)
+}
+
+/// Check the images of `manifest`, printing the result of each image and failing if any of them
+/// can't be deployed
+async fn preflight_manifest(
+    manifest: &serde_yaml::Value,
+    opts: WashConnectionOptions,
+) -> anyhow::Result<Vec<ImagePreflight>> {
+    let registries = registries_with_env(&opts.ctx.registries)?;
+    // The lattice is only queried for the architectures provider archives must have a binary for
+    let targets = if manifest_images(manifest)
+        .iter()
+        .any(|image| image.kind == ImageKind::Provider)
+    {
+        let client = opts.into_ctl_client(None).await?;
+        let hosts = client
+            .get_hosts()
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to get hosts: {e}"))?;
+        host_targets(
+            hosts
+                .iter()
+                .filter_map(|host| host.response.as_ref())
+                .map(|host| &host.labels),
+        )
+    } else {
+        BTreeSet::new()
+    };
+
+    let results = preflight_images(manifest, &registries, &targets).await;
+    for result in &results {
+        match result.status {
+            PreflightStatus::Ok => {}
+            PreflightStatus::Warning => {
+                eprintln!("{} {}", emoji::WARN, style(result).bold());
+            }
+            PreflightStatus::Error => {
+                eprintln!("{} {}", emoji::ERROR, style(result).bold());
+            }
+        }
+    }
+    let failed = results
+        .iter()
+        .filter(|result| result.status == PreflightStatus::Error)
+        .count();
+    if failed > 0 {
+        bail!("pre-flight checks failed for {failed} image(s), pass --skip-preflight to deploy anyway");
+    }
+    Ok(results)
 }
 
 pub(crate) async fn deploy_model_from_manifest(
@@ -292,17 +562,18 @@ pub(crate) async fn deploy_model_from_manifest(
 }
 
 async fn put_model(cmd: PutCommand) -> anyhow::Result<CommandOutput> {
+    // Placeholders are resolved before anything is sent to wadm
+    let app_manifest = cmd.variables.load_manifest(cmd.source.as_deref()).await?;
+    if let Some(output) = cmd.variables.rendered_output(&app_manifest)? {
+        return Ok(output);
+    }
+
     let connection_opts =
         <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
     let lattice = Some(connection_opts.get_lattice());
 
     let client = connection_opts.into_nats_client().await?;
 
-    let app_manifest = match &cmd.source {
-        Some(source) => load_app_manifest(source.parse()?).await?,
-        None => load_app_manifest("-".parse()?).await?,
-    };
-
     let (name, version) = match app_manifest {
         AppManifest::SerializedModel(manifest) => wash_lib::app::put_model(
             &client,
@@ -335,11 +606,73 @@ async fn get_application_versions(cmd: HistoryCommand) -> Result<CommandOutput>
 
     let client = connection_opts.into_nats_client().await?;
 
-    let versions = wash_lib::app::get_model_history(&client, lattice, &cmd.app_name).await?;
+    let versions =
+        wash_lib::app::get_model_history(&client, lattice.clone(), &cmd.app_name).await?;
+    // Only the deployed version has a status, so don't bother asking wadm otherwise
+    let status = if versions.iter().any(|v| v.deployed) {
+        Some(wash_lib::app::get_model_status(&client, lattice, &cmd.app_name).await?)
+    } else {
+        None
+    };
+
     let mut map = HashMap::new();
     map.insert("revisions".to_string(), json!(versions));
+    map.insert("status".to_string(), json!(status));
+    Ok(CommandOutput::new(
+        output::list_revisions_table(versions, status.as_ref()),
+        map,
+    ))
+}
+
+async fn rollback_model(cmd: RollbackCommand) -> anyhow::Result<CommandOutput> {
+    let connection_opts =
+        <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let lattice = Some(connection_opts.get_lattice());
+
+    let client = connection_opts.into_nats_client().await?;
+
+    let versions =
+        wash_lib::app::get_model_history(&client, lattice.clone(), &cmd.app_name).await?;
+    let previous_version = versions
+        .iter()
+        .find(|v| v.deployed)
+        .map(|v| v.version.clone());
+    let version = rollback_target_version(&versions, cmd.to_version.as_deref())
+        .with_context(|| format!("failed to roll back application [{}]", cmd.app_name))?;
+
+    wash_lib::app::deploy_model(
+        &client,
+        lattice.clone(),
+        &cmd.app_name,
+        Some(version.clone()),
+    )
+    .await?;
+
+    let status = if cmd.skip_wait {
+        wash_lib::app::get_model_status(&client, lattice, &cmd.app_name).await?
+    } else {
+        wash_lib::app::wait_for_model_deployed(
+            &client,
+            lattice,
+            &cmd.app_name,
+            &version,
+            Duration::from_millis(cmd.wait_timeout_ms),
+        )
+        .await?
+    };
+
+    let mut map = HashMap::new();
+    map.insert("deployed".to_string(), json!(true));
+    map.insert("model_name".to_string(), json!(cmd.app_name));
+    map.insert("model_version".to_string(), json!(version));
+    map.insert("previous_version".to_string(), json!(previous_version));
+    map.insert("status".to_string(), json!(status));
     Ok(CommandOutput::new(
-        output::list_revisions_table(versions),
+        format!(
+            "Rolled back application \"{}\" from version \"{}\" to version \"{version}\"",
+            cmd.app_name,
+            previous_version.as_deref().unwrap_or("N/A"),
+        ),
         map,
     ))
 }
@@ -417,17 +750,55 @@ async fn delete_application_version(cmd: DeleteCommand) -> Result<CommandOutput>
     Ok(CommandOutput::new(message, map))
 }
 
-async fn get_applications(cmd: ListCommand) -> Result<CommandOutput> {
+async fn get_applications(
+    cmd: ListCommand,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
     let connection_opts =
         <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
     let lattice = Some(connection_opts.get_lattice());
 
-    let client = connection_opts.into_nats_client().await?;
-    let models = wash_lib::app::get_models(&client, lattice).await?;
+    let client = connection_opts.clone().into_nats_client().await?;
+    let models = wash_lib::app::get_models(&client, lattice.clone()).await?;
+    if !cmd.detailed {
+        let text = output::list_models_table(&models, &cmd.table, output_kind)?;
+        let mut map = HashMap::new();
+        map.insert("applications".to_string(), json!(models));
+        return Ok(CommandOutput::new(text, map));
+    }
+
+    let ctl_client = connection_opts.into_ctl_client(None).await?;
+    let inventories = get_all_inventories(&ctl_client).await?;
+    let mut instances = Vec::new();
+    for model in &models {
+        let Some(version) = &model.deployed_version else {
+            continue;
+        };
+        let manifest = wash_lib::app::get_model_details(
+            &client,
+            lattice.clone(),
+            &model.name,
+            Some(version.clone()),
+        )
+        .await
+        .with_context(|| format!("failed to get the deployed manifest of [{}]", model.name))?;
+        // The instances are still reported if the status of the application is unavailable
+        let status = wash_lib::app::get_model_status(&client, lattice.clone(), &model.name)
+            .await
+            .ok();
+        instances.push(AppInstances::new(
+            &model.name,
+            &manifest,
+            status.as_ref(),
+            &inventories,
+        ));
+    }
 
+    let text = output::list_detailed_models_table(&models, &instances, &cmd.table, output_kind)?;
     let mut map = HashMap::new();
     map.insert("applications".to_string(), json!(models));
-    Ok(CommandOutput::new(output::list_models_table(models), map))
+    map.insert("instances".to_string(), json!(instances));
+    Ok(CommandOutput::new(text, map))
 }
 
 fn show_validate_manifest_results(messages: impl AsRef<[ValidationFailure]>) -> CommandOutput {
@@ -443,16 +814,16 @@ fn show_validate_manifest_results(messages: impl AsRef<[ValidationFailure]>) ->
         .into_iter()
         .cloned()
         .collect::<Vec<ValidationFailure>>();
-    let message = if valid {
-        "manifest is valid".into()
+    let mut message = if valid {
+        "manifest is valid".to_string()
     } else {
-        format!(
-            r#"invalid manifest:
-warnings: {warnings:#?}
-errors: {errors:#?}
-"#
-        )
+        "invalid manifest".to_string()
     };
+    for (kind, failures) in [("error", &errors), ("warning", &warnings)] {
+        for failure in failures {
+            message.push_str(&format!("\n  {kind}: {}", failure.msg));
+        }
+    }
     let json_output = HashMap::<String, serde_json::Value>::from([
         ("valid".into(), messages.valid().into()),
         ("warnings".into(), json!(warnings)),
@@ -1,63 +1,227 @@
+use anyhow::Result;
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
     Table,
 };
 use wadm_types::api::{Status, VersionInfo};
+use wash_lib::cli::{
+    app_instances::{AppInstances, ComponentInstances},
+    table::{render_table, Column, TableOpts},
+    OutputKind,
+};
 
 use super::ModelSummary;
 
-pub fn list_revisions_table(revisions: Vec<VersionInfo>) -> String {
+pub fn list_revisions_table(revisions: Vec<VersionInfo>, status: Option<&Status>) -> String {
     let mut table = Table::new();
     crate::util::configure_table_style(&mut table);
 
     table.add_row(Row::new(vec![
         TableCell::new_with_alignment("Version", 1, Alignment::Left),
         TableCell::new_with_alignment("Deployed", 1, Alignment::Left),
+        TableCell::new_with_alignment("Deploy Status", 1, Alignment::Left),
+        TableCell::new_with_alignment("Status Message", 1, Alignment::Left),
     ]));
 
     revisions.iter().for_each(|r| {
+        // wadm only tracks the status of the deployed version
+        let (status_type, message) = match status {
+            Some(status) if r.deployed && status.version == r.version => (
+                format!("{:?}", status.info.status_type),
+                status.info.message.clone(),
+            ),
+            _ => ("N/A".to_string(), String::new()),
+        };
         table.add_row(Row::new(vec![
             TableCell::new_with_alignment(r.version.clone(), 1, Alignment::Left),
             TableCell::new_with_alignment(r.deployed, 1, Alignment::Left),
+            TableCell::new_with_alignment(status_type, 1, Alignment::Left),
+            TableCell::new_with_alignment(message, 1, Alignment::Left),
         ]));
     });
 
     table.render()
 }
 
-pub fn list_models_table(models: Vec<ModelSummary>) -> String {
-    let mut table = Table::new();
-    crate::util::configure_table_style(&mut table);
+/// Columns of `wash app list`, all shown by default
+const MODEL_COLUMNS: [&str; 5] = [
+    "name",
+    "version",
+    "deployed-version",
+    "status",
+    "description",
+];
 
-    table.add_row(Row::new(vec![
-        TableCell::new_with_alignment("Name", 1, Alignment::Left),
-        TableCell::new_with_alignment("Latest Version", 1, Alignment::Left),
-        TableCell::new_with_alignment("Deployed Version", 1, Alignment::Left),
-        TableCell::new_with_alignment("Deploy Status", 1, Alignment::Right),
-        TableCell::new_with_alignment("Description", 1, Alignment::Left),
-    ]));
-    models.iter().for_each(|m| {
-        table.add_row(Row::new(vec![
-            TableCell::new_with_alignment(m.name.clone(), 1, Alignment::Left),
-            TableCell::new_with_alignment(m.version.clone(), 1, Alignment::Left),
-            TableCell::new_with_alignment(
-                m.deployed_version
-                    .clone()
-                    .unwrap_or_else(|| "N/A".to_string()),
-                1,
-                Alignment::Left,
-            ),
-            TableCell::new_with_alignment(format!("{:?}", m.status), 1, Alignment::Right),
-            TableCell::new_with_alignment(
-                m.description.clone().unwrap_or_else(|| "N/A".to_string()),
-                1,
-                Alignment::Left,
-            ),
-        ]))
-    });
+/// Columns of `wash app list --detailed` shown by default
+const DETAILED_MODEL_COLUMNS: [&str; 5] = [
+    "name",
+    "deployed-version",
+    "status",
+    "instances",
+    "provisioning",
+];
 
-    table.render()
+/// Columns of the table of the components of deployed applications printed by
+/// `wash app list --detailed`, all shown
+const COMPONENT_INSTANCES_COLUMNS: [&str; 5] =
+    ["app", "component", "kind", "instances", "provisioning"];
+
+/// A row of the `wash app list` table
+trait ModelRow {
+    fn summary(&self) -> &ModelSummary;
+}
+
+impl ModelRow for ModelSummary {
+    fn summary(&self) -> &ModelSummary {
+        self
+    }
+}
+
+/// An application along with its instances, if deployed, as a row of `wash app list --detailed`
+struct DetailedModel<'a> {
+    summary: &'a ModelSummary,
+    instances: Option<&'a AppInstances>,
+}
+
+impl ModelRow for DetailedModel<'_> {
+    fn summary(&self) -> &ModelSummary {
+        self.summary
+    }
+}
+
+/// A component of a deployed application, as a row of `wash app list --detailed`
+struct ComponentRow<'a> {
+    app: &'a str,
+    component: &'a ComponentInstances,
+}
+
+/// Columns of `wash app list`
+fn model_columns<T: ModelRow>() -> Vec<Column<T>> {
+    vec![
+        Column::new("name", "Name", |m: &T| m.summary().name.clone()).identifier(),
+        Column::new("version", "Latest Version", |m: &T| {
+            m.summary().version.clone()
+        }),
+        Column::new("deployed-version", "Deployed Version", |m: &T| {
+            m.summary()
+                .deployed_version
+                .clone()
+                .unwrap_or_else(|| "N/A".to_string())
+        }),
+        Column::new("status", "Deploy Status", |m: &T| {
+            format!("{:?}", m.summary().status)
+        }),
+        Column::new("description", "Description", |m: &T| {
+            m.summary()
+                .description
+                .clone()
+                .unwrap_or_else(|| "N/A".to_string())
+        }),
+    ]
+}
+
+/// Columns of `wash app list --detailed`
+fn detailed_model_columns<'a>() -> Vec<Column<DetailedModel<'a>>> {
+    let mut columns = model_columns();
+    columns.extend([
+        Column::new("instances", "Instances", |m: &DetailedModel| {
+            m.instances.map_or_else(
+                || "N/A".to_string(),
+                |i| format!("{}/{}", i.actual, i.desired),
+            )
+        }),
+        Column::new("provisioning", "Provisioning", |m: &DetailedModel| {
+            m.instances
+                .map_or("N/A", |i| provisioning(i.under_provisioned))
+                .to_string()
+        }),
+    ]);
+    columns
+}
+
+/// Columns of the table of the components of deployed applications
+fn component_instances_columns<'a>() -> Vec<Column<ComponentRow<'a>>> {
+    vec![
+        Column::new("app", "Application", |r: &ComponentRow| r.app.to_string()).identifier(),
+        Column::new("component", "Component", |r: &ComponentRow| {
+            r.component.name.clone()
+        })
+        .identifier(),
+        Column::new("kind", "Kind", |r: &ComponentRow| r.component.kind.clone()),
+        Column::new("instances", "Instances", |r: &ComponentRow| {
+            format!("{}/{}", r.component.actual, r.component.desired)
+        }),
+        Column::new("provisioning", "Provisioning", |r: &ComponentRow| {
+            provisioning(r.component.under_provisioned()).to_string()
+        }),
+    ]
+}
+
+fn provisioning(under_provisioned: bool) -> &'static str {
+    if under_provisioned {
+        "Under-provisioned"
+    } else {
+        "OK"
+    }
+}
+
+pub fn list_models_table(
+    models: &[ModelSummary],
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<String> {
+    render_table(models, &model_columns(), &MODEL_COLUMNS, table, output_kind)
+}
+
+/// Table of `models` with the total instances of the deployed ones, followed by a table of the
+/// instances of their components
+pub fn list_detailed_models_table(
+    models: &[ModelSummary],
+    instances: &[AppInstances],
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<String> {
+    let rows: Vec<DetailedModel> = models
+        .iter()
+        .map(|summary| DetailedModel {
+            summary,
+            instances: instances.iter().find(|i| i.name == summary.name),
+        })
+        .collect();
+    let mut text = render_table(
+        &rows,
+        &detailed_model_columns(),
+        &DETAILED_MODEL_COLUMNS,
+        table,
+        output_kind,
+    )?;
+
+    let components: Vec<ComponentRow> = instances
+        .iter()
+        .flat_map(|i| {
+            i.components.iter().map(|component| ComponentRow {
+                app: &i.name,
+                component,
+            })
+        })
+        .collect();
+    if !components.is_empty() {
+        // `--columns` selects the columns of the table of applications only
+        let component_table = TableOpts {
+            no_headers: table.no_headers,
+            columns: Vec::new(),
+        };
+        text.push_str("\n\n");
+        text.push_str(&render_table(
+            &components,
+            &component_instances_columns(),
+            &COMPONENT_INSTANCES_COLUMNS,
+            &component_table,
+            output_kind,
+        )?);
+    }
+    Ok(text)
 }
 
 pub fn status_table(model_name: String, status: Status) -> String {
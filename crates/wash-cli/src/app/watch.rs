@@ -0,0 +1,286 @@
+use std::io::{ErrorKind, Write};
+use std::time::Duration;
+
+use anyhow::{bail, Context as _, Result};
+use console::{style, Term};
+use serde_json::{json, Value};
+use tokio::time::Instant;
+use wadm_types::api::{Status, StatusType};
+use wash_lib::app::{watch_model_status, StatusWatchMode};
+use wash_lib::cli::{CliConnectionOpts, OutputKind};
+use wash_lib::config::WashConnectionOptions;
+
+/// Status of an application at which `wash app status --watch` stops watching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WatchUntil {
+    Deployed,
+    Undeployed,
+    Failed,
+}
+
+impl WatchUntil {
+    fn reached(self, status: &StatusType) -> bool {
+        matches!(
+            (self, status),
+            (Self::Deployed, StatusType::Deployed)
+                | (Self::Undeployed, StatusType::Undeployed)
+                | (Self::Failed, StatusType::Failed)
+        )
+    }
+}
+
+/// Print the status of an application every time it changes until interrupted, `until` is
+/// reached or the application has been failing for `failed_timeout`. Text output re-renders the
+/// status tree in place, JSON output is emitted as one snapshot per change (NDJSON)
+pub async fn watch_status(
+    app_name: String,
+    opts: CliConnectionOpts,
+    until: Option<WatchUntil>,
+    failed_timeout: Option<Duration>,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let connection_opts = <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(opts)?;
+    let lattice = Some(connection_opts.get_lattice());
+    let client = connection_opts.into_nats_client().await?;
+
+    tokio::select! {
+        res = print_status_changes(&client, lattice, &app_name, until, failed_timeout, output_kind) => {
+            match res {
+                // The reader went away (e.g. `wash app status my-app --watch -o json | head -n1`)
+                Err(e) if e
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) => Ok(()),
+                res => res,
+            }
+        }
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+async fn print_status_changes(
+    client: &async_nats::Client,
+    lattice: Option<String>,
+    app_name: &str,
+    until: Option<WatchUntil>,
+    failed_timeout: Option<Duration>,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let mut watch = watch_model_status(client, lattice, app_name).await?;
+    let header = match watch.mode() {
+        StatusWatchMode::Events => format!("wash app status {app_name} --watch (status events)"),
+        StatusWatchMode::Polling => format!("wash app status {app_name} --watch (polling)"),
+    };
+    let term = Term::stdout();
+    let mut previous: Option<StatusNode> = None;
+    let mut failing_since: Option<Instant> = None;
+    loop {
+        let failed_deadline = failing_since.zip(failed_timeout).map(|(t, d)| t + d);
+        let update = tokio::select! {
+            update = watch.next() => update.context("application status watch ended")?,
+            () = sleep_until(failed_deadline) => {
+                bail!(
+                    "application [{app_name}] has been failing for over {}ms",
+                    failed_timeout.unwrap_or_default().as_millis()
+                )
+            }
+        };
+        let frame = match update {
+            Ok(status) => {
+                let tree = StatusNode::from_status(app_name, &status);
+                let frame = match output_kind {
+                    OutputKind::Json => serde_json::to_string(&json!({
+                        "success": true,
+                        "status_type": format!("{:?}", status.info.status_type),
+                        "status": status,
+                    }))
+                    .context("failed to serialize application status")?,
+                    OutputKind::Text | OutputKind::Wide => tree.render(previous.as_ref()),
+                };
+                previous = Some(tree);
+                if matches!(status.info.status_type, StatusType::Failed) {
+                    failing_since.get_or_insert_with(Instant::now);
+                } else {
+                    failing_since = None;
+                }
+                if until.is_some_and(|until| until.reached(&status.info.status_type)) {
+                    print_frame(&term, output_kind, &header, &frame)?;
+                    return Ok(());
+                }
+                frame
+            }
+            // wadm may be restarting while we watch, so keep watching on errors
+            Err(e) => match output_kind {
+                OutputKind::Json => {
+                    serde_json::to_string(&json!({ "success": false, "error": format!("{e:#}") }))?
+                }
+                OutputKind::Text | OutputKind::Wide => {
+                    format!("Failed to get application status: {e:#}")
+                }
+            },
+        };
+        print_frame(&term, output_kind, &header, &frame)?;
+    }
+}
+
+/// Sleep until `deadline`, or forever if there is none
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Write a frame to stdout, surfacing broken pipes as errors instead of panicking like `println!`.
+/// Text frames replace the previous frame on terminals
+fn print_frame(term: &Term, output_kind: OutputKind, header: &str, frame: &str) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    if output_kind != OutputKind::Json && term.is_term() {
+        term.clear_screen()?;
+        writeln!(stdout, "{header}\n\n{frame}")?;
+    } else if output_kind != OutputKind::Json {
+        writeln!(stdout, "{frame}\n")?;
+    } else {
+        writeln!(stdout, "{frame}")?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Node of the status tree of an application: the application, its components (or scalers, with
+/// newer versions of wadm) and their traits
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StatusNode {
+    label: String,
+    status: String,
+    message: String,
+    children: Vec<StatusNode>,
+}
+
+impl StatusNode {
+    /// Build the tree of `status`. The tree is built from the serialized status, so that the
+    /// layouts of all wadm versions are supported
+    fn from_status(app_name: &str, status: &Status) -> Self {
+        let value = serde_json::to_value(status).unwrap_or_default();
+        let children = ["components", "scalers"]
+            .iter()
+            .filter_map(|key| value.get(key).and_then(Value::as_array))
+            .flatten()
+            .map(Self::from_value)
+            .collect();
+        Self {
+            label: format!("{app_name} ({})", status.version),
+            status: format!("{:?}", status.info.status_type),
+            message: status.info.message.clone(),
+            children,
+        }
+    }
+
+    fn from_value(value: &Value) -> Self {
+        let field = |key: &str| value.get(key).and_then(Value::as_str);
+        let kind = field("type").or_else(|| field("kind"));
+        let label = match (field("name").or_else(|| field("id")), kind) {
+            (Some(name), Some(kind)) => format!("{name} [{kind}]"),
+            (Some(label), None) | (None, Some(label)) => label.to_string(),
+            (None, None) => "<unnamed>".to_string(),
+        };
+        let info = value.get("status").or_else(|| value.get("info"));
+        let info_field = |key: &str| {
+            info.and_then(|info| info.get(key))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        Self {
+            label,
+            status: info_field("type"),
+            message: info_field("message"),
+            children: value
+                .get("traits")
+                .and_then(Value::as_array)
+                .map(|traits| traits.iter().map(Self::from_value).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn is_deployed(&self) -> bool {
+        self.status.eq_ignore_ascii_case("deployed")
+    }
+
+    fn line(&self, depth: usize) -> String {
+        let status = if self.is_deployed() {
+            style(&self.status).green()
+        } else if self.status.eq_ignore_ascii_case("failed") {
+            style(&self.status).red()
+        } else {
+            style(&self.status).yellow()
+        };
+        let mut line = format!("{}{}: {status}", "  ".repeat(depth), self.label);
+        if !self.message.is_empty() {
+            line.push_str(&format!(" - {}", self.message));
+        }
+        line
+    }
+
+    /// Render the tree, collapsing the deployed children which did not change since `previous`
+    /// (all deployed children on the first render) so that large applications fit on screen
+    fn render(&self, previous: Option<&StatusNode>) -> String {
+        let mut lines = vec![self.line(0)];
+        let mut collapsed = 0;
+        for child in &self.children {
+            let unchanged = previous.map_or(true, |previous| previous.children.contains(child));
+            if child.is_deployed() && unchanged {
+                collapsed += 1;
+                continue;
+            }
+            lines.push(child.line(1));
+            lines.extend(child.children.iter().map(|trait_| trait_.line(2)));
+        }
+        if collapsed > 0 {
+            lines.push(format!(
+                "  {}",
+                style(format!("... {collapsed} unchanged deployed item(s)")).dim()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node(label: &str, status: &str) -> StatusNode {
+        StatusNode {
+            label: label.to_string(),
+            status: status.to_string(),
+            message: String::new(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_collapses_unchanged_deployed_children() {
+        console::set_colors_enabled(false);
+        let previous = StatusNode {
+            children: (0..30)
+                .map(|i| node(&format!("component-{i}"), "deployed"))
+                .collect(),
+            ..node("app (v1)", "Deployed")
+        };
+        let rendered = previous.render(None);
+        assert_eq!(
+            rendered,
+            "app (v1): Deployed\n  ... 30 unchanged deployed item(s)"
+        );
+
+        let mut current = previous.clone();
+        current.status = "Reconciling".to_string();
+        current.children[3].status = "reconciling".to_string();
+        current.children[3].children = vec![node("spreadscaler", "reconciling")];
+        let rendered = current.render(Some(&previous));
+        assert_eq!(
+            rendered,
+            "app (v1): Reconciling\n  component-3: reconciling\n    spreadscaler: reconciling\n  ... 29 unchanged deployed item(s)"
+        );
+    }
+}
@@ -20,7 +20,7 @@ pub struct Spinner {
 impl Spinner {
     pub fn new(output_kind: &OutputKind) -> Result<Self> {
         match output_kind {
-            OutputKind::Text => {
+            OutputKind::Text | OutputKind::Wide => {
                 let style = ProgressStyle::default_spinner()
                     .tick_strings(DOTS_12)
                     .template("{prefix:.bold.dim} {spinner:.bold.dim} {wide_msg:.bold.dim}")?;
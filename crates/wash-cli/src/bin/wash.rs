@@ -1,30 +1,37 @@
 use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
 use anyhow::bail;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
 use clap::{self, Arg, Command, FromArgMatches, Parser, Subcommand};
 use serde_json::json;
 use tracing_subscriber::EnvFilter;
-use wash_cli::app::{self, AppCliCommand};
+use wash_cli::app::{self, AppCliCommand, StatusCommand};
 use wash_cli::build::{self, BuildCommand};
 use wash_cli::call::{self, CallCli};
 use wash_cli::common;
-use wash_cli::completions::{self, CompletionOpts};
+use wash_cli::completions::{self, CompleteCommand, CompletionOpts};
 use wash_cli::config::{self, ConfigCliCommand};
 use wash_cli::ctx::{self, CtxCommand};
 use wash_cli::dev::{self, DevCommand};
+use wash_cli::doctor::{self, DoctorCommand};
 use wash_cli::down::{self, DownCommand};
 use wash_cli::drain;
 use wash_cli::generate::{self, NewCliCommand};
 use wash_cli::keys::{self, KeysCliCommand};
+use wash_cli::logs::{self, LogsCommand};
 use wash_cli::par::{self, ParCliCommand};
 use wash_cli::plugin::{self, PluginCommand};
+use wash_cli::test::{self, TestCommand};
 use wash_cli::ui::{self, UiCommand};
 use wash_cli::up::{self, UpCommand};
-use wash_cli::util::ensure_plugin_dir;
+use wash_cli::util::{ensure_plugin_dir, plugin_command_output, plugin_context_env};
+use wash_cli::whoami;
+use wash_cli::wit::{self, WitCommand};
 use wash_lib::cli::capture::{CaptureCommand, CaptureSubcommand};
 use wash_lib::cli::claims::ClaimsCliCommand;
-use wash_lib::cli::get::GetCommand;
+use wash_lib::cli::get::{GetCommand, GetHostInventoriesCommand};
 use wash_lib::cli::inspect::InspectCliCommand;
 use wash_lib::cli::label::LabelHostCommand;
 use wash_lib::cli::link::LinkCommand;
@@ -34,9 +41,13 @@ use wash_lib::cli::spy::SpyCommand;
 use wash_lib::cli::start::StartCommand;
 use wash_lib::cli::stop::StopCommand;
 use wash_lib::cli::update::UpdateCommand;
+use wash_lib::cli::whoami::WhoamiCommand;
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wash_lib::drain::Drain as DrainSelection;
-use wash_lib::plugin::subcommand::{DirMapping, SubcommandRunner};
+use wash_lib::plugin::external::{
+    external_plugins, find_external_plugin, run_external_plugin, suggest_plugins,
+};
+use wash_lib::plugin::subcommand::{DirMapping, PluginPermissions, RunOptions, SubcommandRunner};
 
 const HELP: &str = r"
 _________________________________________________________________________________
@@ -56,12 +67,16 @@ Build:
   new          Create a new project from a template
   build        Build (and sign) a wasmCloud component or capability provider
   dev          Start a developer loop to hot-reload a local wasmCloud component
+  test         Run the integration tests of a project against an ephemeral lattice
   inspect      Inspect a capability provider or Wasm component for signing information and interfaces
   par          Create, inspect, and modify capability provider archive files
+  wit          Fetch, compare and check for updates of the WIT dependencies of a project
 
 Run:
   up           Bootstrap a local wasmCloud environment
   down         Tear down a local wasmCloud environment (launched with wash up)
+  logs         Show logs from a local wasmCloud environment (launched with wash up)
+  doctor       Diagnose common problems with a local wasmCloud environment
   app          Manage declarative applications and deployments (wadm)
   spy          Spy on all invocations a component sends and receives
   ui           Serve a web UI for wasmCloud
@@ -85,14 +100,17 @@ Publish:
 Configure:
   completions  Generate shell completions for wash
   ctx          Manage wasmCloud host configuration contexts
+  whoami       Show the identity and permissions wash connects to the lattice with
   drain        Manage contents of local wasmCloud caches
   keys         Utilities for generating and managing signing keys
   claims       Generate and manage JWTs for wasmCloud components and capability providers
   plugin       Manage wash plugins
 
 Options:
-  -o, --output <OUTPUT>  Specify output format (text or json) [default: text]
+  -o, --output <OUTPUT>  Specify output format (text, wide or json) [default: text]
   --experimental         Whether or not to enable experimental features [default: false]
+  --timeout-ms <MS>      Timeout of control interface requests, for all subcommands
+  --no-retry             Do not retry control interface requests when no host responds
   -h, --help             Print help
   -V, --version          Print version
 ";
@@ -104,7 +122,7 @@ struct Cli {
         short = 'o',
         long = "output",
         default_value = "text",
-        help = "Specify output format (text or json)",
+        help = "Specify output format (text, wide or json)",
         global = true
     )]
     pub(crate) output: OutputKind,
@@ -119,6 +137,26 @@ struct Cli {
     )]
     pub(crate) experimental: bool,
 
+    // Global control interface options. Their values are read by subcommands through their own
+    // connection options, which use the same IDs, and take precedence when set
+    #[allow(dead_code)]
+    #[clap(
+        long = "timeout-ms",
+        id = "timeout_ms",
+        help = "Timeout length to await a control interface response, for all subcommands",
+        global = true
+    )]
+    pub(crate) timeout_ms: Option<u64>,
+
+    #[allow(dead_code)]
+    #[clap(
+        long = "no-retry",
+        id = "no_retry",
+        help = "Do not retry control interface requests when no host responds to them or they time out",
+        global = true
+    )]
+    pub(crate) no_retry: bool,
+
     #[clap(subcommand)]
     command: CliCommand,
 }
@@ -142,6 +180,9 @@ enum CliCommand {
     /// Generate shell completions
     #[clap(name = "completions")]
     Completions(CompletionOpts),
+    /// Dynamically complete values (used by shell completion scripts)
+    #[clap(name = "__complete", hide = true)]
+    Complete(CompleteCommand),
     /// Generate and manage JWTs for wasmCloud components and capability providers
     #[clap(name = "claims", subcommand)]
     Claims(ClaimsCliCommand),
@@ -154,6 +195,9 @@ enum CliCommand {
     /// Start a developer loop to hot-reload a local wasmCloud component
     #[clap(name = "dev")]
     Dev(DevCommand),
+    /// Diagnose common problems with a local wasmCloud environment
+    #[clap(name = "doctor")]
+    Doctor(DoctorCommand),
     /// Tear down a wasmCloud environment launched with wash up
     #[clap(name = "down")]
     Down(DownCommand),
@@ -172,6 +216,9 @@ enum CliCommand {
     /// Link one component to another on a set of interfaces
     #[clap(name = "link", alias = "links", subcommand)]
     Link(LinkCommand),
+    /// Show logs from a local wasmCloud environment (launched with wash up)
+    #[clap(name = "logs", alias = "log")]
+    Logs(LogsCommand),
     /// Create a new project from a template
     #[clap(name = "new", subcommand)]
     New(NewCliCommand),
@@ -202,6 +249,9 @@ enum CliCommand {
     /// Label (or un-label) a host with a key=value label pair
     #[clap(name = "label", alias = "tag")]
     Label(LabelHostCommand),
+    /// Run the integration tests of a project against an ephemeral lattice
+    #[clap(name = "test")]
+    Test(TestCommand),
     /// Update a component running in a host to newer image reference
     #[clap(name = "update", subcommand)]
     Update(UpdateCommand),
@@ -211,6 +261,12 @@ enum CliCommand {
     /// Serve a web UI for wasmCloud
     #[clap(name = "ui")]
     Ui(UiCommand),
+    /// Show the identity and permissions wash connects to the lattice with
+    #[clap(name = "whoami")]
+    Whoami(WhoamiCommand),
+    /// Fetch, compare and check for updates of the WIT dependencies of a project
+    #[clap(name = "wit", subcommand)]
+    Wit(WitCommand),
 }
 
 #[tokio::main]
@@ -223,7 +279,8 @@ async fn main() {
 
     let mut command = Cli::command();
     // Load plugins if they are not disabled
-    let plugins = if std::env::var("WASH_DISABLE_PLUGINS").is_err() {
+    let plugins_enabled = std::env::var("WASH_DISABLE_PLUGINS").is_err();
+    let plugins = if plugins_enabled {
         if let Some((plugins, dir)) = load_plugins().await {
             let mut plugin_paths = HashMap::new();
             for plugin in plugins.all_metadata().into_iter().cloned() {
@@ -289,7 +346,10 @@ async fn main() {
 
     command.build();
 
-    let matches = command.get_matches();
+    let matches = match command.try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) => handle_unknown_subcommand(e, plugins_enabled).await,
+    };
 
     let cli = match (Cli::from_arg_matches(&matches), plugins) {
         (Ok(cli), _) => cli,
@@ -301,7 +361,7 @@ async fn main() {
                 }
             };
 
-            let dir = match ensure_plugin_scratch_dir_exists(plugin_dir, id).await {
+            let dir = match ensure_plugin_scratch_dir_exists(&plugin_dir, id).await {
                 Ok(dir) => dir,
                 Err(e) => {
                     eprintln!("Error creating plugin scratch directory: {}", e);
@@ -340,11 +400,42 @@ async fn main() {
             // revisit this later with something if we need to. I did do some basic testing that
             // even if you wrap wash in a shell script, it still works.
             let args: Vec<String> = std::env::args().skip(1).collect();
-            if let Err(e) = plugins.run(id, dir, plugin_dirs, args).await {
-                eprintln!("Error running plugin: {}", e);
-                std::process::exit(1);
-            } else {
-                std::process::exit(0);
+            let permissions = match PluginPermissions::load(&plugin_dir, id).await {
+                Ok(permissions) => permissions,
+                Err(e) => {
+                    eprintln!("Error loading plugin permissions: {e:#}");
+                    std::process::exit(1);
+                }
+            };
+            let options = RunOptions {
+                permissions,
+                env: plugin_context_env(),
+            };
+            let output_kind = matches
+                .get_one::<OutputKind>("output")
+                .copied()
+                .unwrap_or(OutputKind::Text);
+            // Plugins returning structured output leave printing it to wash
+            match plugins
+                .run_with_options(id, dir, plugin_dirs, args, options)
+                .await
+                .and_then(|output| output.map(plugin_command_output).transpose())
+            {
+                Ok(Some(out)) => {
+                    if output_kind == OutputKind::Json {
+                        let mut map = out.map;
+                        map.insert("success".to_string(), json!(true));
+                        println!("{}", serde_json::to_string_pretty(&map).unwrap());
+                    } else {
+                        println!("{}", out.text);
+                    }
+                    std::process::exit(0);
+                }
+                Ok(None) => std::process::exit(0),
+                Err(e) => {
+                    eprintln!("Error running plugin: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
         (Err(e), None) => {
@@ -359,6 +450,25 @@ async fn main() {
         cli.command,
         CliCommand::Config(ConfigCliCommand::GetCommand { .. }),
     );
+    // Validating an invalid manifest succeeds in producing output, but should still fail the command
+    let validate_output = matches!(cli.command, CliCommand::App(AppCliCommand::Validate(_)));
+    // Likewise for a rolling update which rolled back to the running component
+    let update_output = matches!(cli.command, CliCommand::Update(_));
+    // `wash test` exits with the exit code of the tests it ran
+    let test_output = matches!(cli.command, CliCommand::Test(_));
+    // Dynamic completions are consumed by shell scripts, so they're printed as-is
+    let raw_text_output = matches!(cli.command, CliCommand::Complete(_));
+    // Commands that stream their output while running have nothing left to print once interrupted
+    let streamed_output = matches!(
+        &cli.command,
+        CliCommand::App(AppCliCommand::Status(StatusCommand { watch: true, .. }))
+            | CliCommand::Logs(LogsCommand { follow: true, .. })
+            | CliCommand::Get(GetCommand::HostInventories(GetHostInventoriesCommand {
+                watch: true,
+                ..
+            }))
+            | CliCommand::Get(GetCommand::Events(_))
+    );
     let res: anyhow::Result<CommandOutput> = match cli.command {
         CliCommand::App(app_cli) => app::handle_command(app_cli, output_kind).await,
         CliCommand::Build(build_cli) => build::handle_command(build_cli).await,
@@ -378,9 +488,11 @@ async fn main() {
         CliCommand::Completions(completions_cli) => {
             completions::handle_command(completions_cli, Cli::command())
         }
+        CliCommand::Complete(complete_cli) => completions::handle_complete(complete_cli).await,
         CliCommand::Config(config_cli) => config::handle_command(config_cli, output_kind).await,
         CliCommand::Ctx(ctx_cli) => ctx::handle_command(ctx_cli).await,
         CliCommand::Dev(dev_cli) => dev::handle_command(dev_cli, output_kind).await,
+        CliCommand::Doctor(doctor_cli) => doctor::handle_command(doctor_cli).await,
         CliCommand::Down(down_cli) => down::handle_command(down_cli, output_kind).await,
         CliCommand::Drain(drain_cli) => drain::handle_command(drain_cli),
         CliCommand::Get(get_cli) => common::get_cmd::handle_command(get_cli, output_kind).await,
@@ -389,6 +501,7 @@ async fn main() {
         }
         CliCommand::Keys(keys_cli) => keys::handle_command(keys_cli),
         CliCommand::Link(link_cli) => common::link_cmd::handle_command(link_cli, output_kind).await,
+        CliCommand::Logs(logs_cli) => logs::handle_command(logs_cli, output_kind).await,
         CliCommand::New(new_cli) => generate::handle_command(new_cli).await,
         CliCommand::Par(par_cli) => par::handle_command(par_cli, output_kind).await,
         CliCommand::Plugin(plugin_cli) => plugin::handle_command(plugin_cli, output_kind).await,
@@ -415,27 +528,57 @@ async fn main() {
         CliCommand::Label(label_cli) => {
             common::label_cmd::handle_command(label_cli, output_kind).await
         }
+        CliCommand::Test(test_cli) => test::handle_command(test_cli).await,
         CliCommand::Update(update_cli) => {
             common::update_cmd::handle_command(update_cli, output_kind).await
         }
         CliCommand::Up(up_cli) => up::handle_command(up_cli, output_kind).await,
         CliCommand::Ui(ui_cli) => ui::handle_command(ui_cli, output_kind).await,
+        CliCommand::Whoami(whoami_cli) => whoami::handle_command(whoami_cli).await,
+        CliCommand::Wit(wit_cli) => wit::handle_command(wit_cli, output_kind).await,
     };
 
     std::process::exit(match res {
+        Ok(_) if streamed_output => 0,
         Ok(out) => {
+            let test_exit_code = out
+                .map
+                .get("exit_code")
+                .filter(|_| test_output)
+                .and_then(|code| code.as_i64())
+                .filter(|code| *code != 0)
+                .map(|code| i32::try_from(code).unwrap_or(1));
+            let failed = (validate_output && out.map.get("valid") == Some(&json!(false)))
+                || (update_output && out.map.get("decision") == Some(&json!("rolled_back")))
+                || test_exit_code.is_some();
+            let exit_code = test_exit_code.unwrap_or(i32::from(failed));
             match output_kind {
                 OutputKind::Json => {
                     let mut map = out.map;
                     // When we fetch configuration, we don't want to arbitrarily insert a key into the map.
                     // There may be other commands we do this in the future, but for now the special check is fine.
                     if append_json_success {
-                        map.insert("success".to_string(), json!(true));
+                        map.insert("success".to_string(), json!(!failed));
+                    }
+                    // Report retried control interface requests
+                    let attempts = wash_lib::config::ctl_attempts();
+                    if attempts > 1 {
+                        map.insert("attempts".to_string(), json!(attempts));
                     }
                     println!("\n{}", serde_json::to_string_pretty(&map).unwrap());
+                    exit_code
+                }
+                OutputKind::Text | OutputKind::Wide if raw_text_output => {
+                    if !out.text.is_empty() {
+                        println!("{}", out.text);
+                    }
                     0
                 }
-                OutputKind::Text => {
+                OutputKind::Text | OutputKind::Wide if failed => {
+                    println!("\n{}", out.text);
+                    exit_code
+                }
+                OutputKind::Text | OutputKind::Wide => {
                     println!("\n{}", out.text);
                     // on the first non-error, non-json use of wash, print info about shell completions
                     match completions::first_run_suggestion() {
@@ -481,7 +624,7 @@ async fn main() {
 
                     eprintln!("\n{}", serde_json::to_string_pretty(&map).unwrap());
                 }
-                OutputKind::Text => {
+                OutputKind::Text | OutputKind::Wide => {
                     eprintln!("\n{e:?}");
                 }
             }
@@ -518,6 +661,52 @@ async fn load_plugins() -> Option<(SubcommandRunner, PathBuf)> {
     Some((plugins, plugin_dir))
 }
 
+/// Handle a failure to parse the command line. When the subcommand is unknown, run the external
+/// plugin providing it (a `wash-<subcommand>` executable on the `PATH`) with the remaining args and
+/// exit with its exit code. Otherwise exit with the parse error, suggesting similarly named plugins
+async fn handle_unknown_subcommand(mut e: clap::Error, plugins_enabled: bool) -> ! {
+    let name = match (e.kind(), e.get(ContextKind::InvalidSubcommand)) {
+        (ErrorKind::InvalidSubcommand, Some(ContextValue::String(name))) if plugins_enabled => {
+            name.clone()
+        }
+        _ => e.exit(),
+    };
+
+    if let Some(path) = find_external_plugin(&name) {
+        let args: Vec<_> = std::env::args_os()
+            .skip(1)
+            .skip_while(|arg| arg.as_os_str() != OsStr::new(&name))
+            .skip(1)
+            .collect();
+        match run_external_plugin(&path, args, plugin_context_env()).await {
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                eprintln!("Error running plugin: {err:#}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let external = external_plugins();
+    let mut suggested = match e.get(ContextKind::SuggestedSubcommand) {
+        Some(ContextValue::String(suggested)) => vec![suggested.clone()],
+        Some(ContextValue::Strings(suggested)) => suggested.clone(),
+        _ => Vec::new(),
+    };
+    for plugin in suggest_plugins(&name, external.keys().map(String::as_str)) {
+        if !suggested.iter().any(|suggested| suggested == plugin) {
+            suggested.push(plugin.to_string());
+        }
+    }
+    if !suggested.is_empty() {
+        e.insert(
+            ContextKind::SuggestedSubcommand,
+            ContextValue::Strings(suggested),
+        );
+    }
+    e.exit()
+}
+
 async fn ensure_plugin_scratch_dir_exists(
     plugin_dir: impl AsRef<Path>,
     id: &str,
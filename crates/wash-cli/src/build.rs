@@ -51,10 +51,17 @@ pub struct BuildCommand {
     /// Skip building the artifact and only use configuration to sign
     #[clap(long = "sign-only", conflicts_with = "build_only")]
     pub sign_only: bool,
+
+    /// Skip the post-build commands configured in the `[build]` section of wasmcloud.toml
+    #[clap(long = "skip-post")]
+    pub skip_post: bool,
 }
 
 pub async fn handle_command(command: BuildCommand) -> Result<CommandOutput> {
-    let config = get_config(command.config_path, Some(true))?;
+    let mut config = get_config(command.config_path, Some(true))?;
+    if command.skip_post {
+        config.build.post_commands.clear();
+    }
 
     match config.project_type {
         TypeConfig::Component(ref component_config) => {
@@ -144,6 +151,7 @@ mod test {
         assert!(cmd.issuer.is_none());
         assert!(cmd.subject.is_none());
         assert!(cmd.keys_directory.is_none());
+        assert!(!cmd.skip_post);
 
         let cmd: BuildCommand = Parser::try_parse_from([
             "build",
@@ -156,6 +164,7 @@ mod test {
             "/tmp/sub.nk",
             "--keys-directory",
             "/tmp",
+            "--skip-post",
         ])
         .unwrap();
         assert_eq!(cmd.config_path, Some(PathBuf::from("/")));
@@ -163,5 +172,6 @@ mod test {
         assert_eq!(cmd.issuer, Some("/tmp/iss.nk".to_string()));
         assert_eq!(cmd.subject, Some("/tmp/sub.nk".to_string()));
         assert_eq!(cmd.keys_directory, Some(PathBuf::from("/tmp")));
+        assert!(cmd.skip_post);
     }
 }
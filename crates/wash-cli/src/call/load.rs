@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Args;
+use futures::future::join_all;
+use serde::Serialize;
+use serde_json::json;
+use tokio::time::Instant;
+
+use wash_lib::cli::CommandOutput;
+
+/// Maximum number of distinct error messages reported, further errors are reported as
+/// [`OTHER_ERRORS`]
+const MAX_DISTINCT_ERRORS: usize = 32;
+const OTHER_ERRORS: &str = "other errors";
+
+/// Options of `wash call` turning a single invocation into a load test of the component
+#[derive(Debug, Clone, Default, Args)]
+pub struct LoadOpts {
+    /// Number of times to call the function, reporting the throughput and latencies of the calls
+    /// instead of the response
+    #[clap(long = "repeat", conflicts_with = "duration")]
+    pub repeat: Option<u64>,
+
+    /// Duration to call the function for (e.g. `30s`), reporting the throughput and latencies of
+    /// the calls instead of the response
+    #[clap(long = "duration", value_parser = humantime::parse_duration, conflicts_with = "repeat")]
+    pub duration: Option<Duration>,
+
+    /// Maximum number of concurrent calls when using --repeat or --duration
+    #[clap(
+        long = "concurrency",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub concurrency: u64,
+
+    /// Number of initial calls to exclude from the report when using --repeat or --duration.
+    /// Warmup calls are not counted towards --repeat
+    #[clap(long = "warmup", default_value_t = 0)]
+    pub warmup: u64,
+}
+
+impl LoadOpts {
+    /// Whether a load test was requested
+    pub fn is_enabled(&self) -> bool {
+        self.repeat.is_some() || self.duration.is_some()
+    }
+}
+
+/// Histogram of latencies with a fixed number of logarithmic buckets, each spanning 1/16th of a
+/// power of two, so that memory stays bounded regardless of the number of calls while
+/// percentiles are accurate to ~6%
+#[derive(Debug, Clone)]
+struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    max: Duration,
+}
+
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; Self::bucket(u64::MAX) + 1],
+            count: 0,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Index of the bucket of a latency of `micros` microseconds
+    fn bucket(micros: u64) -> usize {
+        if micros < SUB_BUCKETS {
+            return micros as usize;
+        }
+        let exp = u64::BITS - 1 - micros.leading_zeros();
+        let sub = (micros >> (exp - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+        (exp - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS as usize + sub as usize
+    }
+
+    /// Lowest latency in microseconds of the bucket at `index`
+    fn bucket_floor(index: usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKETS {
+            return index;
+        }
+        let exp = index / SUB_BUCKETS - 1 + u64::from(SUB_BUCKET_BITS);
+        (SUB_BUCKETS + index % SUB_BUCKETS) << (exp - u64::from(SUB_BUCKET_BITS))
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.buckets[Self::bucket(micros)] += 1;
+        self.count += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Estimated latency below which the fraction `q` of the calls completed
+    fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                let floor = Duration::from_micros(Self::bucket_floor(index));
+                return Some(floor.min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Outcomes of the measured calls of a load test
+#[derive(Debug, Default)]
+struct LoadStats {
+    /// Start of the first measured call
+    started: Option<Instant>,
+    successes: u64,
+    errors: BTreeMap<String, u64>,
+    latencies: LatencyHistogram,
+}
+
+impl LoadStats {
+    fn record(&mut self, started: Instant, latency: Duration, error: Option<anyhow::Error>) {
+        self.started = Some(self.started.map_or(started, |s| s.min(started)));
+        self.latencies.record(latency);
+        let Some(error) = error else {
+            self.successes += 1;
+            return;
+        };
+        let error = format!("{error:#}");
+        if self.errors.len() < MAX_DISTINCT_ERRORS || self.errors.contains_key(&error) {
+            *self.errors.entry(error).or_default() += 1;
+        } else {
+            *self.errors.entry(OTHER_ERRORS.to_string()).or_default() += 1;
+        }
+    }
+}
+
+/// Latency percentiles of the calls of a load test, in milliseconds
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+/// Report of a load test, returned under the `load` key of the JSON output of `wash call`
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    /// Number of measured calls, excluding warmup calls
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// Number of failed calls by error message
+    pub errors: BTreeMap<String, u64>,
+    /// Number of initial calls excluded from the report
+    pub warmup: u64,
+    /// Time elapsed since the start of the first measured call, in seconds
+    pub elapsed_secs: f64,
+    /// Measured calls per second
+    pub throughput: f64,
+    pub latency: LatencyReport,
+    /// Whether the load test was interrupted before completing
+    pub interrupted: bool,
+}
+
+impl LoadReport {
+    fn new(stats: LoadStats, warmup: u64, finished: Instant, interrupted: bool) -> Self {
+        let failures = stats.errors.values().sum();
+        let requests = stats.successes + failures;
+        let elapsed = stats
+            .started
+            .map(|started| finished.saturating_duration_since(started))
+            .unwrap_or_default();
+        let throughput = if elapsed.is_zero() {
+            0.0
+        } else {
+            requests as f64 / elapsed.as_secs_f64()
+        };
+        let ms = |latency: Option<Duration>| latency.map(|l| l.as_secs_f64() * 1000.0);
+        Self {
+            requests,
+            successes: stats.successes,
+            failures,
+            errors: stats.errors,
+            warmup,
+            elapsed_secs: elapsed.as_secs_f64(),
+            throughput,
+            latency: LatencyReport {
+                p50_ms: ms(stats.latencies.quantile(0.5)),
+                p90_ms: ms(stats.latencies.quantile(0.9)),
+                p99_ms: ms(stats.latencies.quantile(0.99)),
+                max_ms: ms((stats.latencies.count > 0).then_some(stats.latencies.max)),
+            },
+            interrupted,
+        }
+    }
+
+    /// Human readable summary of the report
+    fn to_text(&self, function: &str, component_id: &str) -> String {
+        let ms = |latency: Option<f64>| {
+            latency.map_or_else(|| "-".to_string(), |latency| format!("{latency:.2}ms"))
+        };
+        let mut lines = vec![
+            format!(
+                "Load test of [{function}] on component [{component_id}]{}",
+                if self.interrupted {
+                    " (interrupted)"
+                } else {
+                    ""
+                }
+            ),
+            format!(
+                "  Requests:   {} ({} succeeded, {} failed), {} warmup excluded",
+                self.requests, self.successes, self.failures, self.warmup
+            ),
+            format!(
+                "  Throughput: {:.2} req/s over {:.2}s",
+                self.throughput, self.elapsed_secs
+            ),
+            format!(
+                "  Latency:    p50 {}, p90 {}, p99 {}, max {}",
+                ms(self.latency.p50_ms),
+                ms(self.latency.p90_ms),
+                ms(self.latency.p99_ms),
+                ms(self.latency.max_ms)
+            ),
+        ];
+        if !self.errors.is_empty() {
+            lines.push("  Errors:".to_string());
+            lines.extend(
+                self.errors
+                    .iter()
+                    .map(|(error, count)| format!("    {count}x {error}")),
+            );
+        }
+        lines.join("\n")
+    }
+}
+
+/// Call the function with `invoke` as requested by `opts` with at most `opts.concurrency` calls in
+/// flight, until all calls are made, the duration elapsed or the user interrupts the test
+async fn run_calls<F, Fut>(opts: &LoadOpts, invoke: F) -> (LoadStats, bool)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<CommandOutput>>,
+{
+    let next = AtomicU64::new(0);
+    let stats = Mutex::new(LoadStats::default());
+    let deadline = opts.duration.map(|duration| Instant::now() + duration);
+    let total = opts.repeat.map(|repeat| repeat.saturating_add(opts.warmup));
+    let worker = || async {
+        loop {
+            let call = next.fetch_add(1, Ordering::Relaxed);
+            if total.is_some_and(|total| call >= total)
+                || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return;
+            }
+            let started = Instant::now();
+            let res = invoke().await;
+            let latency = started.elapsed();
+            if call >= opts.warmup {
+                stats
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .record(started, latency, res.err());
+            }
+        }
+    };
+    let interrupted = tokio::select! {
+        _ = join_all((0..opts.concurrency).map(|_| worker())) => false,
+        _ = tokio::signal::ctrl_c() => true,
+    };
+    let stats = stats
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    (stats, interrupted)
+}
+
+/// Load test `function` of the component by repeatedly calling it with `invoke`, see [`LoadOpts`].
+/// The report is printed when interrupted with Ctrl+C as well
+pub(crate) async fn run_load<F, Fut>(
+    opts: &LoadOpts,
+    function: &str,
+    component_id: &str,
+    invoke: F,
+) -> Result<CommandOutput>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<CommandOutput>>,
+{
+    let (stats, interrupted) = run_calls(opts, invoke).await;
+    let report = LoadReport::new(stats, opts.warmup, Instant::now(), interrupted);
+    Ok(CommandOutput::new(
+        report.to_text(function, component_id),
+        HashMap::from([("load".to_string(), json!(report))]),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::bail;
+
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        for micros in [0, 1, 15, 16, 17, 31, 32, 1_000, 123_456, u64::MAX] {
+            let floor = LatencyHistogram::bucket_floor(LatencyHistogram::bucket(micros));
+            assert!(floor <= micros, "{floor} > {micros}");
+            assert!(
+                micros - floor <= micros / 16,
+                "{floor} too far from {micros}"
+            );
+        }
+        for index in 0..LatencyHistogram::bucket(u64::MAX) {
+            let floor = LatencyHistogram::bucket_floor(index);
+            assert_eq!(LatencyHistogram::bucket(floor), index);
+        }
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+        let ms = |q| histogram.quantile(q).unwrap().as_secs_f64() * 1000.0;
+        assert!((47.0..=50.0).contains(&ms(0.5)), "p50 is {}", ms(0.5));
+        assert!((85.0..=90.0).contains(&ms(0.9)), "p90 is {}", ms(0.9));
+        assert!((93.0..=99.0).contains(&ms(0.99)), "p99 is {}", ms(0.99));
+        assert_eq!(histogram.max, Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_run_load() -> Result<()> {
+        let calls = AtomicU64::new(0);
+        let opts = LoadOpts {
+            repeat: Some(20),
+            concurrency: 4,
+            warmup: 3,
+            ..Default::default()
+        };
+        let output = run_load(&opts, "wasi:cli/run.run", "component", || async {
+            if calls.fetch_add(1, Ordering::Relaxed) % 5 == 4 {
+                bail!("boom")
+            }
+            Ok(CommandOutput::from("ok"))
+        })
+        .await?;
+        assert_eq!(calls.load(Ordering::Relaxed), 23);
+
+        let load = &output.map["load"];
+        assert_eq!(load["requests"], 20);
+        assert_eq!(load["warmup"], 3);
+        let failures = load["errors"]["boom"].as_u64().unwrap();
+        assert_eq!(load["failures"], failures);
+        assert_eq!(load["successes"], 20 - failures);
+        assert!(load["latency"]["p99_ms"].is_f64());
+        assert!(output.text.contains("Requests:   20"));
+        Ok(())
+    }
+}
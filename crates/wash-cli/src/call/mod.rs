@@ -21,6 +21,9 @@ use wrpc_transport::Client;
 
 use crate::util::{default_timeout_ms, msgpack_to_json_val};
 
+mod load;
+pub use load::{LatencyReport, LoadOpts, LoadReport};
+
 const DEFAULT_HTTP_SCHEME: &str = "http";
 const DEFAULT_HTTP_HOST: &str = "localhost";
 /// Default port used by wasmCloud HTTP server provider
@@ -116,7 +119,7 @@ pub async fn handle_command(
         function,
         http_handler_invocation_opts,
         http_response_extract_json,
-        ..
+        load,
     }: CallCommand,
 ) -> Result<CommandOutput> {
     ensure!(!component_id.is_empty(), "component ID may not be empty");
@@ -176,6 +179,19 @@ pub async fn handle_command(
                 .to_request()
                 .await
                 .context("failed to invoke handler with HTTP request options")?;
+            if load.is_enabled() {
+                return load::run_load(&load, &function, &component_id, || {
+                    wrpc_invoke_http_handler(
+                        &wrpc_client,
+                        &lattice,
+                        &component_id,
+                        opts.timeout_ms,
+                        clone_request(&request),
+                        http_response_extract_json,
+                    )
+                })
+                .await;
+            }
             wrpc_invoke_http_handler(
                 &wrpc_client,
                 &lattice,
@@ -187,6 +203,19 @@ pub async fn handle_command(
             .await
         }
         // Assume the call is a function that takes no input and produces a string
+        _ if load.is_enabled() => {
+            load::run_load(&load, &function, &component_id, || {
+                wrpc_invoke_simple(
+                    &wrpc_client,
+                    &lattice,
+                    &component_id,
+                    &instance,
+                    &name,
+                    opts.timeout_ms,
+                )
+            })
+            .await
+        }
         _ => {
             wrpc_invoke_simple(
                 &wrpc_client,
@@ -295,6 +324,10 @@ pub struct CallCommand {
     /// Customizable options related to the HTTP handler invocation (HTTP path, method, etc)
     #[clap(flatten)]
     pub http_handler_invocation_opts: HttpHandlerInvocationOpts,
+
+    /// Options to load test the component by calling the function repeatedly
+    #[clap(flatten)]
+    pub load: LoadOpts,
 }
 
 /// Options that customize the HTTP request that is fed to a HTTP handler when using `wash call`
@@ -378,6 +411,15 @@ impl HttpHandlerInvocationOpts {
     }
 }
 
+/// Copy a HTTP request built from [`HttpHandlerInvocationOpts`], to send it repeatedly
+fn clone_request(request: &http::Request<String>) -> http::Request<String> {
+    let mut clone = http::Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
 /// Utility type used mostly for printing HTTP responses to the console as JSON
 #[derive(Debug, Clone, Serialize)]
 struct HttpResponse {
@@ -593,4 +635,43 @@ mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_load_options() -> Result<()> {
+        let cmd: Cmd = Parser::try_parse_from([
+            "call",
+            COMPONENT_ID,
+            "wasi:http/incoming-handler.handle",
+            "--duration",
+            "30s",
+            "--concurrency",
+            "20",
+            "--warmup",
+            "10",
+        ])?;
+        assert!(cmd.command.load.is_enabled());
+        assert_eq!(cmd.command.load.repeat, None);
+        assert_eq!(
+            cmd.command.load.duration,
+            Some(std::time::Duration::from_secs(30))
+        );
+        assert_eq!(cmd.command.load.concurrency, 20);
+        assert_eq!(cmd.command.load.warmup, 10);
+
+        let cmd: Cmd = Parser::try_parse_from(["call", COMPONENT_ID, "wasi:cli/run.run"])?;
+        assert!(!cmd.command.load.is_enabled());
+
+        for args in [
+            ["--repeat", "10", "--duration", "1s"],
+            ["--repeat", "10", "--concurrency", "0"],
+        ] {
+            let res = Cmd::try_parse_from(
+                ["call", COMPONENT_ID, "wasi:cli/run.run"]
+                    .into_iter()
+                    .chain(args),
+            );
+            assert!(res.is_err(), "{args:?} should be rejected");
+        }
+        Ok(())
+    }
 }
@@ -1,28 +1,66 @@
-use anyhow::Result;
+use std::collections::BTreeSet;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use console::{style, Term};
+use futures::StreamExt as _;
+use serde::Serialize;
+use serde_json::json;
 use wash_lib::cli::claims::get_claims;
-use wash_lib::cli::get::{get_host_inventories, get_hosts, GetCommand, GetLinksCommand};
+use wash_lib::cli::events::{event_stream, render_event, EventFilter};
+use wash_lib::cli::get::{
+    get_host_inventories, get_hosts, query_host_inventories, GetCommand, GetEventsCommand,
+    GetHostInventoriesCommand, GetLinksCommand, InventoryEntry,
+};
 use wash_lib::cli::link::{LinkCommand, LinkQueryCommand};
+use wash_lib::cli::table::TableOpts;
 use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::config::WashConnectionOptions;
+use wash_lib::id::ServerId;
+use wasmcloud_control_interface::{Client as CtlClient, HostInventory};
 
 use crate::appearance::spinner::Spinner;
 use crate::common::link_cmd::handle_command as handle_link_command;
-use crate::ctl::{get_claims_output, get_host_inventories_output, get_hosts_output};
+use crate::ctl::{
+    get_claims_output, get_host_inventories_output, get_hosts_output, host_inventories_table,
+    HOST_INVENTORY_COLUMNS,
+};
 
 pub async fn handle_command(command: GetCommand, output_kind: OutputKind) -> Result<CommandOutput> {
     let sp: Spinner = Spinner::new(&output_kind)?;
     let out: CommandOutput = match command {
-        GetCommand::Links(GetLinksCommand { opts }) => {
-            handle_link_command(LinkCommand::Query(LinkQueryCommand { opts }), output_kind).await?
+        GetCommand::Links(GetLinksCommand { opts, table }) => {
+            handle_link_command(
+                LinkCommand::Query(LinkQueryCommand { opts, table }),
+                output_kind,
+            )
+            .await?
         }
         GetCommand::Claims(cmd) => {
             sp.update_spinner_message("Retrieving claims ... ".to_string());
             let claims = get_claims(cmd).await?;
             get_claims_output(claims)
         }
-        GetCommand::Hosts(cmd) => {
+        GetCommand::Hosts(mut cmd) => {
             sp.update_spinner_message(" Retrieving Hosts ...".to_string());
+            let detailed = cmd.detailed;
+            // Counting components and providers requires the inventory of every host
+            cmd.detailed |= output_kind == OutputKind::Wide
+                || cmd
+                    .table
+                    .columns
+                    .iter()
+                    .any(|column| HOST_INVENTORY_COLUMNS.contains(&column.trim()));
+            let table = cmd.table.clone();
             let hosts = get_hosts(cmd).await?;
-            get_hosts_output(hosts)
+            get_hosts_output(hosts, detailed, &table, output_kind)?
+        }
+        GetCommand::HostInventories(cmd) if cmd.watch => {
+            sp.finish_and_clear();
+            watch_host_inventories(cmd, output_kind).await?;
+            CommandOutput::default()
         }
         GetCommand::HostInventories(cmd) => {
             if let Some(id) = cmd.host_id.as_ref() {
@@ -30,10 +68,236 @@ pub async fn handle_command(command: GetCommand, output_kind: OutputKind) -> Res
             } else {
                 sp.update_spinner_message(" Retrieving hosts for inventory query ...".to_string());
             }
+            let table = cmd.table.clone();
             let invs = get_host_inventories(cmd).await?;
-            get_host_inventories_output(invs)
+            get_host_inventories_output(invs, &table, output_kind)?
+        }
+        GetCommand::Events(cmd) => {
+            sp.finish_and_clear();
+            watch_events(cmd, output_kind).await?;
+            CommandOutput::default()
         }
     };
 
     Ok(out)
 }
+
+/// A component or provider running on a host, used to detect inventory changes between polls
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct InventoryItem {
+    host_id: String,
+    kind: &'static str,
+    id: String,
+}
+
+impl std::fmt::Display for InventoryItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} on host {}", self.kind, self.id, self.host_id)
+    }
+}
+
+fn inventory_items(invs: &[HostInventory]) -> BTreeSet<InventoryItem> {
+    invs.iter()
+        .flat_map(|inv| {
+            let components = inv.components.iter().map(|c| InventoryItem {
+                host_id: inv.host_id.clone(),
+                kind: "component",
+                id: c.id.clone(),
+            });
+            let providers = inv.providers.iter().map(|p| InventoryItem {
+                host_id: inv.host_id.clone(),
+                kind: "provider",
+                id: p.id.clone(),
+            });
+            components.chain(providers).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Re-query host inventories every `interval_ms` until interrupted. Text output re-renders the inventory
+/// in place, JSON output is emitted as one document per poll (NDJSON) so that it can be piped
+async fn watch_host_inventories(
+    cmd: GetHostInventoriesCommand,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let wco: WashConnectionOptions = cmd.opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+
+    tokio::select! {
+        res = poll_host_inventories(&client, cmd.host_id.as_ref(), cmd.interval_ms, &cmd.table, output_kind) => {
+            match res {
+                // The reader went away (e.g. `wash get inventory --watch -o json | head -n1`)
+                Err(e) if e
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) => Ok(()),
+                res => res,
+            }
+        }
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+/// How long `wash get events` waits for events before exiting when not following
+const EVENTS_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+async fn watch_events(cmd: GetEventsCommand, output_kind: OutputKind) -> Result<()> {
+    let filter = EventFilter::from(&cmd);
+    // Replaying saved events does not require a lattice
+    let stream = if cmd.replay.is_some() {
+        event_stream(&cmd, None, "", None).await?
+    } else {
+        let wco: WashConnectionOptions = cmd.opts.clone().try_into()?;
+        let lattice = wco.get_lattice();
+        let js_domain = wco.js_domain.clone();
+        let nats = wco.into_nats_client().await?;
+        event_stream(&cmd, Some(nats), &lattice, js_domain).await?
+    };
+
+    // Replayed events are all shown, however long ago they were received
+    let follow = cmd.follow || cmd.replay.is_some();
+    tokio::select! {
+        res = print_events(stream, &filter, cmd.save.as_deref(), follow, output_kind) => {
+            match res {
+                // The reader went away (e.g. `wash get events -f | head -n1`)
+                Err(e) if e
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) => Ok(()),
+                res => res,
+            }
+        }
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+async fn print_events(
+    mut stream: futures::stream::BoxStream<'static, Result<cloudevents::Event>>,
+    filter: &EventFilter,
+    save: Option<&Path>,
+    follow: bool,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let mut save = match save {
+        Some(path) => Some(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open [{}] to save events", path.display()))?,
+        ),
+        None => None,
+    };
+    loop {
+        let event = if follow {
+            stream.next().await
+        } else {
+            match tokio::time::timeout(EVENTS_IDLE_TIMEOUT, stream.next()).await {
+                Ok(event) => event,
+                Err(_) => return Ok(()),
+            }
+        };
+        let event = match event {
+            Some(Ok(event)) => event,
+            // Skip malformed events rather than stopping the stream
+            Some(Err(e)) => {
+                eprintln!("{} {e:#}", style("Skipping event:").yellow());
+                continue;
+            }
+            None => return Ok(()),
+        };
+        if !filter.matches(&event) {
+            continue;
+        }
+        let json = serde_json::to_string(&event).context("failed to serialize event")?;
+        if let Some(file) = save.as_mut() {
+            writeln!(file, "{json}").context("failed to save event")?;
+        }
+        match output_kind {
+            OutputKind::Json => print_frame(&json)?,
+            OutputKind::Text | OutputKind::Wide => print_frame(&render_event(&event))?,
+        }
+    }
+}
+
+async fn poll_host_inventories(
+    client: &CtlClient,
+    host_id: Option<&ServerId>,
+    interval_ms: u64,
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let term = Term::stdout();
+    let mut interval = tokio::time::interval(Duration::from_millis(interval_ms.max(1)));
+    let mut previous: Option<BTreeSet<InventoryItem>> = None;
+    loop {
+        interval.tick().await;
+        let frame = match query_host_inventories(client, host_id).await {
+            Ok(invs) => {
+                let current = inventory_items(&invs);
+                let (added, removed) = match previous.as_ref() {
+                    Some(previous) => (
+                        current.difference(previous).cloned().collect::<Vec<_>>(),
+                        previous.difference(&current).cloned().collect::<Vec<_>>(),
+                    ),
+                    None => (Vec::new(), Vec::new()),
+                };
+                previous = Some(current);
+                render_frame(output_kind, table, invs, &added, &removed)?
+            }
+            // Hosts may be restarting while we watch, so keep polling on errors
+            Err(e) => match output_kind {
+                OutputKind::Json => {
+                    serde_json::to_string(&json!({ "success": false, "error": format!("{e:#}") }))?
+                }
+                OutputKind::Text | OutputKind::Wide => {
+                    format!("Failed to retrieve inventory: {e:#}")
+                }
+            },
+        };
+
+        if output_kind != OutputKind::Json && term.is_term() {
+            term.clear_screen()?;
+            print_frame(&format!(
+                "Every {interval_ms}ms: wash get inventory\n\n{frame}"
+            ))?;
+        } else {
+            print_frame(&frame)?;
+        }
+    }
+}
+
+fn render_frame(
+    output_kind: OutputKind,
+    table: &TableOpts,
+    invs: Vec<HostInventory>,
+    added: &[InventoryItem],
+    removed: &[InventoryItem],
+) -> Result<String> {
+    match output_kind {
+        OutputKind::Json => serde_json::to_string(&json!({
+            "success": true,
+            "items": InventoryEntry::from_inventories(&invs),
+            "inventories": invs,
+            "added": added,
+            "removed": removed,
+        }))
+        .context("failed to serialize inventory"),
+        OutputKind::Text | OutputKind::Wide => {
+            let mut text = host_inventories_table(&invs, table, output_kind)?;
+            for item in added {
+                text.push_str(&format!("\n{}", style(format!("+ {item}")).green()));
+            }
+            for item in removed {
+                text.push_str(&format!("\n{}", style(format!("- {item}")).red()));
+            }
+            Ok(text)
+        }
+    }
+}
+
+/// Write a frame to stdout, surfacing broken pipes as errors instead of panicking like `println!`
+fn print_frame(frame: &str) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{frame}")?;
+    stdout.flush()?;
+    Ok(())
+}
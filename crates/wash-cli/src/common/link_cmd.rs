@@ -1,12 +1,21 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde_json::json;
+use wash_lib::cli::config_schema::{
+    find_provider_image_ref, load_provider_schema, validate_config,
+};
 use wash_lib::cli::link::{
-    delete_link, get_links, put_link, LinkCommand, LinkDelCommand, LinkPutCommand, LinkQueryCommand,
+    delete_link, get_links, missing_configs, put_link, LinkCommand, LinkDelCommand, LinkExport,
+    LinkExportCommand, LinkImportCommand, LinkImportPlan, LinkPutCommand, LinkQueryCommand,
+    LINK_EXPORT_VERSION,
 };
+use wash_lib::cli::table::TableOpts;
 use wash_lib::cli::{CommandOutput, OutputKind};
-use wasmcloud_control_interface::InterfaceLinkDefinition;
+use wash_lib::config::WashConnectionOptions;
+use wash_lib::generate::interactive::user_confirm;
+use wasmcloud_control_interface::{Client as CtlClient, InterfaceLinkDefinition};
 
 use crate::appearance::spinner::Spinner;
 use crate::ctl::{link_del_output, links_table};
@@ -33,11 +42,162 @@ pub fn link_put_output(
     }
 }
 
+/// Validate the named configuration supplied to a link target against the config schema of the
+/// target, if the target is a running provider that can be resolved.
+async fn validate_target_config(
+    ctl_client: &CtlClient,
+    target: &str,
+    target_config: &[String],
+) -> Result<()> {
+    // Targets that aren't running providers (or can't be looked up) are linked without validation
+    let Ok(Some(image_ref)) = find_provider_image_ref(ctl_client, target).await else {
+        return Ok(());
+    };
+    let Some(schema) = load_provider_schema(&image_ref).await.ok().flatten() else {
+        eprintln!("Note: provider {target} does not declare a config schema, skipping validation");
+        return Ok(());
+    };
+
+    let mut values = HashMap::new();
+    for name in target_config {
+        let config = ctl_client
+            .get_config(name)
+            .await
+            .map_err(|e| anyhow!("failed to get configuration {name}: {e}"))?;
+        values.extend(config.response.unwrap_or_default());
+    }
+    validate_config(&schema, &values)
+        .map_err(|e| anyhow!("target configuration is not valid for provider {target}: {e}"))
+}
+
 /// Generate output for the link query command
-pub fn link_query_output(list: Vec<InterfaceLinkDefinition>) -> CommandOutput {
+pub fn link_query_output(
+    list: Vec<InterfaceLinkDefinition>,
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let text = links_table(&list, table, output_kind)?;
     let mut map = HashMap::new();
     map.insert("links".to_string(), json!(list));
-    CommandOutput::new(links_table(list), map)
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Export the links of the lattice to `output_file`, or as the output of the command
+async fn export_links(
+    wco: WashConnectionOptions,
+    output_file: Option<PathBuf>,
+) -> Result<CommandOutput> {
+    let export = LinkExport::new(get_links(wco).await?);
+    let document =
+        serde_json::to_string_pretty(&export).context("failed to serialize exported links")?;
+    let Some(path) = output_file else {
+        let mut map = HashMap::new();
+        map.insert("version".to_string(), json!(export.version));
+        map.insert("links".to_string(), json!(export.links));
+        return Ok(CommandOutput::new(document, map));
+    };
+    tokio::fs::write(&path, document)
+        .await
+        .with_context(|| format!("failed to write exported links to {}", path.display()))?;
+    let mut map = HashMap::new();
+    map.insert("path".to_string(), json!(path));
+    map.insert("links".to_string(), json!(export.links.len()));
+    Ok(CommandOutput::new(
+        format!(
+            "Exported {} links to {}",
+            export.links.len(),
+            path.display()
+        ),
+        map,
+    ))
+}
+
+/// Import the links exported to `file` into the lattice
+async fn import_links(
+    sp: &Spinner,
+    wco: WashConnectionOptions,
+    LinkImportCommand {
+        file,
+        dry_run,
+        prune,
+        yes,
+        ..
+    }: LinkImportCommand,
+) -> Result<CommandOutput> {
+    let document = tokio::fs::read(&file)
+        .await
+        .with_context(|| format!("failed to read links from {}", file.display()))?;
+    let import: LinkExport = serde_json::from_slice(&document)
+        .with_context(|| format!("failed to parse links from {}", file.display()))?;
+    if import.version != LINK_EXPORT_VERSION {
+        bail!(
+            "unsupported links document version {}, expected {LINK_EXPORT_VERSION}",
+            import.version
+        );
+    }
+
+    let ctl_client = wco.into_ctl_client(None).await?;
+    let warnings: Vec<_> = missing_configs(&ctl_client, import.config_names())
+        .await?
+        .into_iter()
+        .map(|name| format!("configuration {name} referenced by links does not exist"))
+        .collect();
+    for warning in &warnings {
+        eprintln!("Warning: {warning}");
+    }
+
+    let existing = ctl_client
+        .get_links()
+        .await
+        .map_err(|e| anyhow!("failed to get links: {e}"))?
+        .response
+        .unwrap_or_default();
+    let plan = LinkImportPlan::new(&existing, &import.links, prune)?;
+
+    if !dry_run && !plan.prune.is_empty() && !yes {
+        sp.finish_and_clear();
+        for link in &plan.prune {
+            eprintln!(
+                "  {} -> {} on {}:{} ({})",
+                link.source_id, link.target, link.wit_namespace, link.wit_package, link.name
+            );
+        }
+        if !user_confirm(&format!(
+            "Delete {} links absent from {}?",
+            plan.prune.len(),
+            file.display()
+        ))? {
+            bail!("import cancelled, no links were changed");
+        }
+    }
+    if !dry_run {
+        plan.apply(&ctl_client).await?;
+    }
+
+    let (created, updated, skipped, deleted) = (
+        plan.create.len(),
+        plan.update.len(),
+        plan.skip.len(),
+        plan.prune.len(),
+    );
+    let mut map = HashMap::new();
+    map.insert("dry_run".to_string(), json!(dry_run));
+    map.insert("created".to_string(), json!(created));
+    map.insert("updated".to_string(), json!(updated));
+    map.insert("skipped".to_string(), json!(skipped));
+    map.insert("deleted".to_string(), json!(deleted));
+    map.insert("warnings".to_string(), json!(warnings));
+    let prefix = if dry_run {
+        "Would import links"
+    } else {
+        "Imported links"
+    };
+    Ok(CommandOutput::new(
+        format!(
+            "{prefix}: {created} created, {updated} updated, {skipped} skipped, {deleted} deleted"
+        ),
+        map,
+    ))
 }
 
 pub async fn handle_command(
@@ -85,9 +245,15 @@ pub async fn handle_command(
             sp.update_spinner_message(format!("Defining link {source_id} -> {target} ... ",));
 
             let name = link_name.unwrap_or_else(|| "default".to_string());
+            let wco: WashConnectionOptions = opts.try_into()?;
+
+            if !target_config.is_empty() {
+                let ctl_client = wco.clone().into_ctl_client(None).await?;
+                validate_target_config(&ctl_client, &target, &target_config).await?;
+            }
 
             let failure = put_link(
-                opts.try_into()?,
+                wco,
                 InterfaceLinkDefinition {
                     source_id: source_id.to_string(),
                     target: target.to_string(),
@@ -104,10 +270,19 @@ pub async fn handle_command(
 
             link_put_output(&source_id, &target, failure)?
         }
-        LinkCommand::Query(LinkQueryCommand { opts }) => {
+        LinkCommand::Query(LinkQueryCommand { opts, table }) => {
             sp.update_spinner_message("Querying Links ... ".to_string());
             let result = get_links(opts.try_into()?).await?;
-            link_query_output(result)
+            link_query_output(result, &table, output_kind)?
+        }
+        LinkCommand::Export(LinkExportCommand { opts, output_file }) => {
+            sp.update_spinner_message("Exporting Links ... ".to_string());
+            export_links(opts.try_into()?, output_file).await?
+        }
+        LinkCommand::Import(cmd) => {
+            sp.update_spinner_message(format!("Importing Links from {} ... ", cmd.file.display()));
+            let wco: WashConnectionOptions = cmd.opts.clone().try_into()?;
+            import_links(&sp, wco, cmd).await?
         }
     };
 
@@ -7,16 +7,19 @@ use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tracing::warn;
 use wash_lib::registry::{
-    identify_artifact, pull_oci_artifact, push_oci_artifact, ArtifactType, OciPullOptions,
-    OciPushOptions,
+    identify_artifact, pull_oci_artifact, push_oci_artifact, registries_with_env,
+    resolve_reference, ArtifactType, OciPullOptions, OciPushOptions, RegistryConfig,
+    ResolvedReference,
 };
 use wash_lib::{
+    build::{build_project, SignConfig},
     cli::{
         input_vec_to_hashmap,
-        registry::{RegistryPullCommand, RegistryPushCommand},
+        registry::{AuthOpts, RegistryPullCommand, RegistryPushCommand},
         CommandOutput, OutputKind,
     },
-    parser::get_config,
+    context::{fs::ContextDir, ContextManager},
+    parser::{get_config, ComponentConfig, ProviderConfig, TypeConfig},
 };
 use wasmcloud_control_interface::RegistryCredential;
 
@@ -31,41 +34,44 @@ pub async fn registry_pull(
     output_kind: OutputKind,
 ) -> Result<CommandOutput> {
     let image: Reference = resolve_artifact_ref(&cmd.url, &cmd.registry.unwrap_or_default(), None)?;
+    let resolved = resolve_reference(&image, &registries_config()?)?;
+    let image = &resolved.reference;
     let spinner = Spinner::new(&output_kind)?;
     spinner.update_spinner_message(format!(" Downloading {} ...", image.whole()));
 
-    let credentials = match (cmd.opts.user, cmd.opts.password) {
-        (Some(user), Some(password)) => Ok(RegistryCredential {
-            username: Some(user),
-            password: Some(password),
-            ..Default::default()
-        }),
-        _ => resolve_registry_credentials(image.registry()).await,
-    }?;
+    let credentials = registry_credentials(&cmd.opts, &resolved).await?;
 
     let artifact = pull_oci_artifact(
-        &image,
+        image,
         OciPullOptions {
             digest: cmd.digest,
             allow_latest: cmd.allow_latest,
             user: credentials.username,
             password: credentials.password,
-            insecure: cmd.opts.insecure,
+            insecure: cmd.opts.insecure || resolved.insecure,
             insecure_skip_tls_verify: cmd.opts.insecure_skip_tls_verify,
+            ca_file: resolved.ca_file.clone(),
         },
     )
     .await?;
 
-    let outfile = write_artifact(&artifact, &image, cmd.destination).await?;
+    let outfile = write_artifact(&artifact, image, cmd.destination).await?;
 
     spinner.finish_and_clear();
 
     let mut map = HashMap::new();
     map.insert("file".to_string(), json!(outfile));
-    Ok(CommandOutput::new(
-        format!("\n{SHOWER_EMOJI} Successfully pulled and validated {outfile}"),
-        map,
-    ))
+    let mut text = format!("\n{SHOWER_EMOJI} Successfully pulled and validated {outfile}");
+    if let Some(original) = &resolved.original {
+        map.insert("url".to_string(), json!(image.whole()));
+        map.insert("original_url".to_string(), json!(original.whole()));
+        text.push_str(&format!(
+            " (pulled {} from mirror {})",
+            original.whole(),
+            image.whole()
+        ));
+    }
+    Ok(CommandOutput::new(text, map))
 }
 
 pub async fn write_artifact(
@@ -101,22 +107,25 @@ pub async fn registry_push(
         &cmd.registry.unwrap_or_default(),
         cmd.config.clone(),
     )?;
-    let artifact_url = image.whole();
-    if artifact_url.starts_with("localhost:") && !cmd.opts.insecure {
+    let resolved = resolve_reference(&image, &registries_config()?)?;
+    let insecure = cmd.opts.insecure || resolved.insecure;
+    let artifact_url = resolved.reference.whole();
+    if artifact_url.starts_with("localhost:") && !insecure {
         warn!(" Unless an SSL certificate has been installed, pushing to localhost without the --insecure option will fail")
     }
 
+    let artifact = match cmd.artifact {
+        Some(artifact) => artifact,
+        None => build_push_artifact(cmd.reproducible)
+            .await?
+            .display()
+            .to_string(),
+    };
+
     let spinner = Spinner::new(&output_kind)?;
-    spinner.update_spinner_message(format!(" Pushing {} to {} ...", cmd.artifact, artifact_url));
+    spinner.update_spinner_message(format!(" Pushing {artifact} to {artifact_url} ..."));
 
-    let credentials = match (cmd.opts.user, cmd.opts.password) {
-        (Some(user), Some(password)) => Ok(RegistryCredential {
-            username: Some(user),
-            password: Some(password),
-            ..Default::default()
-        }),
-        _ => resolve_registry_credentials(image.registry()).await,
-    }?;
+    let credentials = registry_credentials(&cmd.opts, &resolved).await?;
 
     let annotations = match cmd.annotations {
         Some(annotations) => input_vec_to_hashmap(annotations).ok(),
@@ -125,15 +134,18 @@ pub async fn registry_push(
 
     let (maybe_tag, digest) = push_oci_artifact(
         artifact_url.clone(),
-        cmd.artifact,
+        artifact,
         OciPushOptions {
             config: cmd.config.map(PathBuf::from),
             allow_latest: cmd.allow_latest,
             user: credentials.username,
             password: credentials.password,
-            insecure: cmd.opts.insecure,
+            insecure,
             insecure_skip_tls_verify: cmd.opts.insecure_skip_tls_verify,
+            ca_file: resolved.ca_file.clone(),
             annotations,
+            reproducible: cmd.reproducible,
+            expected_digest: cmd.expect_digest,
         },
     )
     .await?;
@@ -144,15 +156,42 @@ pub async fn registry_push(
         ("url".to_string(), json!(artifact_url)),
         ("digest".to_string(), json!(digest)),
     ]);
-    let text = if let Some(tag) = maybe_tag {
+    let mut text = if let Some(tag) = maybe_tag {
         map.insert("tag".to_string(), json!(tag));
         format!("{SHOWER_EMOJI} Successfully pushed {artifact_url}\n{tag}: digest: {digest}")
     } else {
         format!("{SHOWER_EMOJI} Successfully pushed {artifact_url}\ndigest: {digest}")
     };
+    if let Some(original) = &resolved.original {
+        map.insert("original_url".to_string(), json!(original.whole()));
+        text.push_str(&format!("\nmirror of: {}", original.whole()));
+    }
     Ok(CommandOutput::new(text, map))
 }
 
+/// Build the project in the current directory for `wash push --build`, returning the path of the
+/// built artifact
+async fn build_push_artifact(reproducible: bool) -> Result<PathBuf> {
+    let config = get_config(None, Some(true))?;
+    let key_directory = match config.project_type {
+        // Claims are stripped from reproducible components, so there is no point in signing them
+        TypeConfig::Component(_) if reproducible => None,
+        TypeConfig::Component(ComponentConfig {
+            ref key_directory, ..
+        })
+        | TypeConfig::Provider(ProviderConfig {
+            ref key_directory, ..
+        }) => Some(key_directory.clone()),
+    };
+    let sign_config = key_directory.map(|key_directory| SignConfig {
+        keys_directory: Some(key_directory),
+        issuer: None,
+        subject: None,
+        disable_keygen: false,
+    });
+    build_project(&config, sign_config.as_ref()).await
+}
+
 fn resolve_artifact_ref(
     url: &str,
     registry: &str,
@@ -201,6 +240,39 @@ fn resolve_artifact_ref(
     bail!("Unable to resolve artifact url from specified registry and repository")
 }
 
+/// Returns the `registries` configuration of the default wash context, overridden by the
+/// `WASH_REGISTRIES` environment variable
+fn registries_config() -> Result<HashMap<String, RegistryConfig>> {
+    let registries = ContextDir::new()
+        .and_then(|dir| dir.load_default_context())
+        .map(|ctx| ctx.registries)
+        .unwrap_or_default();
+    registries_with_env(&registries)
+}
+
+/// Returns the credentials to authenticate to the registry of the `resolved` reference with, taken
+/// from the command line, the registry configuration or the project credentials file, in that order
+async fn registry_credentials(
+    opts: &AuthOpts,
+    resolved: &ResolvedReference,
+) -> Result<RegistryCredential> {
+    match (&opts.user, &opts.password) {
+        (Some(user), Some(password)) => Ok(RegistryCredential {
+            username: Some(user.clone()),
+            password: Some(password.clone()),
+            ..Default::default()
+        }),
+        _ => match (&resolved.user, &resolved.password) {
+            (Some(user), Some(password)) => Ok(RegistryCredential {
+                username: Some(user.clone()),
+                password: Some(password.clone()),
+                ..Default::default()
+            }),
+            _ => resolve_registry_credentials(resolved.reference.registry()).await,
+        },
+    }
+}
+
 async fn resolve_registry_credentials(registry: &str) -> Result<RegistryCredential> {
     let Ok(project_config) = get_config(None, Some(true)) else {
         return Ok(RegistryCredential::default());
@@ -314,7 +386,7 @@ mod tests {
                 ..
             }) => {
                 assert_eq!(&url, echo_push_basic);
-                assert_eq!(artifact, format!("{TESTDIR}/echopush.wasm"));
+                assert_eq!(artifact, Some(format!("{TESTDIR}/echopush.wasm")));
                 assert!(opts.insecure);
             }
             _ => panic!("`reg push` constructed incorrect command"),
@@ -340,7 +412,7 @@ mod tests {
                 ..
             }) => {
                 assert_eq!(&url, logging_push_all_flags);
-                assert_eq!(artifact, format!("{TESTDIR}/logging.par.gz"));
+                assert_eq!(artifact, Some(format!("{TESTDIR}/logging.par.gz")));
                 assert!(opts.insecure);
                 assert!(allow_latest);
             }
@@ -374,7 +446,7 @@ mod tests {
                 ..
             }) => {
                 assert_eq!(&url, logging_push_all_options);
-                assert_eq!(artifact, format!("{TESTDIR}/logging.par.gz"));
+                assert_eq!(artifact, Some(format!("{TESTDIR}/logging.par.gz")));
                 assert!(opts.insecure);
                 assert!(allow_latest);
                 assert_eq!(
@@ -386,5 +458,46 @@ mod tests {
             }
             _ => panic!("`reg push` constructed incorrect command"),
         };
+
+        // Build and push reproducibly, without an artifact path
+        let echo_push_build = &format!("{LOCAL_REGISTRY}/echo:build");
+        let push_build: Cmd = Parser::try_parse_from([
+            "wash",
+            "push",
+            echo_push_build,
+            "--build",
+            "--reproducible",
+            "--expect-digest",
+            "sha256:0123",
+        ])
+        .unwrap();
+        match push_build.sub {
+            RegistryCommand::Push(RegistryPushCommand {
+                url,
+                artifact,
+                build,
+                reproducible,
+                expect_digest,
+                ..
+            }) => {
+                assert_eq!(&url, echo_push_build);
+                assert_eq!(artifact, None);
+                assert!(build);
+                assert!(reproducible);
+                assert_eq!(expect_digest.as_deref(), Some("sha256:0123"));
+            }
+            _ => panic!("`reg push` constructed incorrect command"),
+        };
+
+        // An artifact is required unless building, and cannot be combined with building
+        assert!(Cmd::try_parse_from(["wash", "push", echo_push_build]).is_err());
+        assert!(Cmd::try_parse_from([
+            "wash",
+            "push",
+            echo_push_build,
+            &format!("{TESTDIR}/echopush.wasm"),
+            "--build",
+        ])
+        .is_err());
     }
 }
@@ -121,6 +121,8 @@ mod test {
             "--link-name",
             "default",
             "--skip-wait",
+            "--config-json",
+            r#"{"address":"0.0.0.0:8081"}"#,
             "wasmcloud.azurecr.io/provider:v1",
             "providerv1",
         ])?;
@@ -134,6 +136,8 @@ mod test {
                 constraints,
                 auction_timeout_ms,
                 config,
+                config_json,
+                config_file,
                 skip_wait,
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
@@ -147,6 +151,11 @@ mod test {
                 assert_eq!(provider_ref, "wasmcloud.azurecr.io/provider:v1".to_string());
                 assert_eq!(provider_id, "providerv1".to_string());
                 assert!(config.is_empty());
+                assert_eq!(
+                    config_json.as_deref(),
+                    Some(r#"{"address":"0.0.0.0:8081"}"#)
+                );
+                assert!(config_file.is_none());
                 assert!(skip_wait);
             }
             cmd => panic!("ctl start provider constructed incorrect command {cmd:?}"),
@@ -18,12 +18,24 @@ pub async fn handle_command(
         }
         StopCommand::Provider(cmd) => {
             let provider_id = &cmd.provider_id.to_string();
-            sp.update_spinner_message(format!(" Stopping provider {provider_id} ... "));
+            if cmd.drain {
+                sp.update_spinner_message(format!(
+                    " Draining and stopping provider {provider_id} ... "
+                ));
+            } else {
+                sp.update_spinner_message(format!(" Stopping provider {provider_id} ... "));
+            }
             stop_provider(cmd).await?
         }
         StopCommand::Host(cmd) => {
             let host_id = &cmd.host_id.to_string();
-            sp.update_spinner_message(format!(" Stopping host {host_id} ... "));
+            if cmd.drain {
+                sp.update_spinner_message(format!(
+                    " Draining providers and stopping host {host_id} ... "
+                ));
+            } else {
+                sp.update_spinner_message(format!(" Stopping host {host_id} ... "));
+            }
             stop_host(cmd).await?
         }
     };
@@ -37,7 +49,9 @@ mod test {
 
     use super::*;
     use clap::Parser;
-    use wash_lib::cli::stop::{StopComponentCommand, StopHostCommand, StopProviderCommand};
+    use wash_lib::cli::stop::{
+        StopComponentCommand, StopHostCommand, StopProviderCommand, DEFAULT_DRAIN_TIMEOUT_MS,
+    };
 
     #[derive(Parser)]
     struct Cmd {
@@ -144,6 +158,9 @@ mod test {
             "--context",
             CONTEXT_PATH,
             "--skip-wait",
+            "--drain",
+            "--drain-timeout-ms",
+            "5000",
         ])?;
         match stop_provider_all.command {
             CtlCliCommand::Stop(StopCommand::Provider(StopProviderCommand {
@@ -151,6 +168,8 @@ mod test {
                 host_id,
                 provider_id,
                 skip_wait,
+                drain,
+                drain_timeout_ms,
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
@@ -159,6 +178,8 @@ mod test {
                 assert_eq!(host_id.unwrap(), HOST_ID);
                 assert_eq!(provider_id.to_string(), PROVIDER_ID);
                 assert!(skip_wait);
+                assert!(drain);
+                assert_eq!(drain_timeout_ms, 5000);
             }
             cmd => panic!("stop provider constructed incorrect command {cmd:?}"),
         }
@@ -196,19 +217,23 @@ mod test {
             CONTEXT_PATH,
             "--host-timeout",
             &HOST_TIMEOUT_MS.to_string(),
+            "--drain",
         ])?;
         match stop_host_all.command {
             CtlCliCommand::Stop(StopCommand::Host(StopHostCommand {
                 opts,
                 host_id,
                 host_shutdown_timeout,
-                ..
+                drain,
+                drain_timeout_ms,
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
                 assert_eq!(&opts.lattice.unwrap(), DEFAULT_LATTICE);
                 assert_eq!(opts.timeout_ms, TIMEOUT_MS);
                 assert_eq!(host_shutdown_timeout, HOST_TIMEOUT_MS);
+                assert!(drain);
+                assert_eq!(drain_timeout_ms, DEFAULT_DRAIN_TIMEOUT_MS);
                 assert_eq!(host_id.to_string(), HOST_ID);
                 assert_eq!(host_id.to_string(), HOST_ID,);
             }
@@ -1,9 +1,15 @@
-use anyhow::Result;
+use core::time::Duration;
 
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use wash_lib::cli::{
-    update::{handle_update_component, UpdateCommand},
+    update::{
+        handle_rolling_update_component, handle_update_component, HealthProbe, UpdateCommand,
+    },
     CommandOutput, OutputKind,
 };
+use wash_lib::config::WashConnectionOptions;
+use wrpc_interface_http::IncomingHandler;
+use wrpc_transport::Client;
 
 use crate::appearance::spinner::Spinner;
 
@@ -13,6 +19,20 @@ pub async fn handle_command(
 ) -> Result<CommandOutput> {
     let sp: Spinner = Spinner::new(&output_kind)?;
     let out = match command {
+        UpdateCommand::Component(cmd) if cmd.rolling => {
+            sp.update_spinner_message(format!(
+                " Rolling update of Component {} to {} ... ",
+                cmd.component_id, cmd.new_component_ref
+            ));
+
+            let wco: WashConnectionOptions = cmd.opts.clone().try_into()?;
+            let lattice = wco.get_lattice();
+            let nats = wco.into_nats_client().await?;
+            handle_rolling_update_component(cmd, |probe, component_id| {
+                probe_component(nats, lattice, component_id, probe)
+            })
+            .await?
+        }
         UpdateCommand::Component(cmd) => {
             sp.update_spinner_message(format!(
                 " Updating Component {} to {} ... ",
@@ -27,3 +47,56 @@ pub async fn handle_command(
 
     Ok(out)
 }
+
+/// Check the health of the component with ID `component_id` over wRPC
+async fn probe_component(
+    nats: async_nats::Client,
+    lattice: String,
+    component_id: String,
+    probe: HealthProbe,
+) -> Result<()> {
+    let mut headers = async_nats::HeaderMap::new();
+    headers.insert("source-id", "wash");
+    let wrpc_client = wasmcloud_core::wrpc::Client::new(
+        nats,
+        &lattice,
+        &component_id,
+        headers,
+        Duration::from_secs(10),
+    );
+    match probe {
+        HealthProbe::Http { path } => {
+            let request = http::Request::get(&path)
+                .body(String::new())
+                .with_context(|| format!("invalid health path [{path}]"))?;
+            match wrpc_client
+                .invoke_handle_http(request)
+                .await
+                .context("failed to invoke HTTP handler")?
+            {
+                (Ok(resp), tx, _body_err) => {
+                    tx.await
+                        .context("failed to wait for transmission to close")?;
+                    ensure!(
+                        resp.status().is_success(),
+                        "health path [{path}] responded with status {}",
+                        resp.status()
+                    );
+                    Ok(())
+                }
+                _ => bail!("unexpected response after HTTP wRPC invocation"),
+            }
+        }
+        HealthProbe::Invoke { instance, function } => {
+            let (values, _tx) = wrpc_client
+                .invoke_dynamic(&instance, &function, (), &[wrpc_types::Type::String])
+                .await
+                .map_err(|e| anyhow!("failed to invoke {instance}.{function}: {e}"))?;
+            ensure!(
+                matches!(values.first(), Some(wrpc_transport::Value::String(_))),
+                "response of {instance}.{function} was not a string"
+            );
+            Ok(())
+        }
+    }
+}
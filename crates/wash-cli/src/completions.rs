@@ -2,14 +2,22 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
-use clap_complete::{generator::generate_to, shells::Shell};
-use wash_lib::cli::CommandOutput;
-use wash_lib::config::cfg_dir;
+use clap_complete::{generate, shells::Shell, Generator};
+use serde::{Deserialize, Serialize};
+use wash_lib::cli::{CliConnectionOpts, CommandOutput};
+use wash_lib::config::{cfg_dir, WashConnectionOptions};
+use wash_lib::context::{fs::ContextDir, ContextManager};
 
 const TOKEN_FILE: &str = ".completion_suggested";
+const CACHE_FILE: &str = ".completion_cache.json";
+/// How long dynamically completed values are reused, to keep TAB latency acceptable
+const CACHE_TTL: Duration = Duration::from_secs(2);
+/// Upper bound on the time spent fetching values from the lattice, so a dead lattice doesn't hang the shell
+const COMPLETION_TIMEOUT: Duration = Duration::from_millis(1500);
 const COMPLETION_DOC_URL: &str =
     "https://github.com/wasmCloud/wasmCloud/blob/main/crates/wash-cli/Completions.md";
 const SLACK_URL: &str = "https://slack.wasmcloud.com";
@@ -84,8 +92,13 @@ pub fn handle_command(
         ShellSelection::PowerShell => Shell::PowerShell,
     };
 
-    match generate_to(shell, &mut command, "wash", &output_dir) {
-        Ok(path) => {
+    let path = output_dir.join(shell.file_name("wash"));
+    let mut script = Vec::new();
+    generate(shell, &mut command, "wash", &mut script);
+    let script = with_dynamic_completions(shell, String::from_utf8_lossy(&script).into_owned());
+
+    match std::fs::write(&path, script) {
+        Ok(()) => {
             let mut map = HashMap::new();
             map.insert(
                 "path".to_string(),
@@ -107,3 +120,445 @@ pub fn handle_command(
         ),
     }
 }
+
+const BASH_DYNAMIC: &str = r#"
+_wash_dynamic() {
+    local IFS=$'\n'
+    local candidates
+    candidates=($(wash __complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+    if [[ ${#candidates[@]} -gt 0 ]]; then
+        COMPREPLY=("${candidates[@]}")
+        return 0
+    fi
+    _wash "$@"
+}
+
+complete -F _wash_dynamic -o nosort -o bashdefault -o default wash
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_wash_dynamic() {
+    local -a candidates
+    candidates=(${(f)"$(wash __complete "${(@)words[2,$CURRENT]}" 2>/dev/null)"})
+    if (( ${#candidates} )); then
+        compadd -a candidates
+    else
+        _wash "$@"
+    fi
+}
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+function __wash_dynamic
+    wash __complete (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null
+end
+
+complete -c wash -f -n 'test -n "$(__wash_dynamic)"' -a '(__wash_dynamic)'
+"#;
+
+/// Extend a statically generated completion script so that IDs and names are completed
+/// dynamically by calling `wash __complete`
+fn with_dynamic_completions(shell: Shell, mut script: String) -> String {
+    match shell {
+        Shell::Bash => {
+            // Replace the registration of the static completion function with the dynamic one
+            script = script
+                .lines()
+                .filter(|line| !line.starts_with("complete -F _wash"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            script.push_str(BASH_DYNAMIC);
+        }
+        Shell::Zsh => {
+            let registration = "if [ \"$funcstack[1]\" = \"_wash\" ]; then";
+            if let Some(idx) = script.rfind(registration) {
+                script.insert_str(idx, ZSH_DYNAMIC.trim_start());
+                script = script
+                    .replace("    _wash \"$@\"\nelse", "    _wash_dynamic \"$@\"\nelse")
+                    .replace("compdef _wash wash", "compdef _wash_dynamic wash");
+            }
+        }
+        Shell::Fish => script.push_str(FISH_DYNAMIC),
+        // PowerShell only supports static completions
+        _ => {}
+    }
+    script
+}
+
+/// Values that can be completed dynamically from the lattice or the local environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompletionKind {
+    Hosts,
+    Components,
+    Providers,
+    Apps,
+    Contexts,
+}
+
+impl CompletionKind {
+    fn cache_key(&self) -> &'static str {
+        match self {
+            CompletionKind::Hosts => "hosts",
+            CompletionKind::Components => "components",
+            CompletionKind::Providers => "providers",
+            CompletionKind::Apps => "apps",
+            CompletionKind::Contexts => "contexts",
+        }
+    }
+}
+
+/// Positional arguments that can be completed, by subcommand path. `None` marks a positional
+/// argument which isn't completed dynamically
+const POSITIONAL_COMPLETIONS: &[(&[&str], &[Option<CompletionKind>])] = &[
+    (&["stop", "component"], &[Some(CompletionKind::Components)]),
+    (&["stop", "provider"], &[Some(CompletionKind::Providers)]),
+    (&["stop", "host"], &[Some(CompletionKind::Hosts)]),
+    (
+        &["scale", "component"],
+        &[
+            Some(CompletionKind::Hosts),
+            None,
+            Some(CompletionKind::Components),
+        ],
+    ),
+    (
+        &["update", "component"],
+        &[Some(CompletionKind::Components)],
+    ),
+    (&["get", "inventory"], &[Some(CompletionKind::Hosts)]),
+    (&["label"], &[Some(CompletionKind::Hosts)]),
+    (&["app", "get"], &[Some(CompletionKind::Apps)]),
+    (&["app", "status"], &[Some(CompletionKind::Apps)]),
+    (&["app", "history"], &[Some(CompletionKind::Apps)]),
+    (&["app", "delete"], &[Some(CompletionKind::Apps)]),
+    (&["app", "deploy"], &[Some(CompletionKind::Apps)]),
+    (&["app", "undeploy"], &[Some(CompletionKind::Apps)]),
+    (&["ctx", "del"], &[Some(CompletionKind::Contexts)]),
+    (&["ctx", "default"], &[Some(CompletionKind::Contexts)]),
+    (&["ctx", "edit"], &[Some(CompletionKind::Contexts)]),
+];
+
+/// Flags whose value is completed dynamically
+const FLAG_COMPLETIONS: &[(&str, CompletionKind)] = &[
+    ("--host-id", CompletionKind::Hosts),
+    ("--context", CompletionKind::Contexts),
+];
+
+/// Flags (other than those in [`FLAG_COMPLETIONS`]) that take a value, which must not be
+/// mistaken for positional arguments
+const VALUE_FLAGS: &[&str] = &[
+    "-r",
+    "--ctl-host",
+    "-p",
+    "--ctl-port",
+    "--ctl-jwt",
+    "--ctl-seed",
+    "--ctl-credsfile",
+    "--ctl-tls-ca-file",
+    "--js-domain",
+    "-x",
+    "--lattice",
+    "--timeout-ms",
+    "--interval-ms",
+    "-o",
+    "--output",
+];
+
+/// Hidden entry point used by the shell completion scripts to dynamically complete values
+#[derive(Debug, Clone, Args)]
+pub struct CompleteCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// Words of the command line (without `wash`), the last word being the one to complete
+    #[clap(name = "words", trailing_var_arg = true, allow_hyphen_values = true)]
+    pub words: Vec<String>,
+}
+
+/// Determine what should be completed for the last word of `words`
+fn completion_kind(words: &[String]) -> Option<CompletionKind> {
+    let (_current, preceding) = words.split_last()?;
+    if let Some(previous) = preceding.last() {
+        if let Some((_, kind)) = FLAG_COMPLETIONS.iter().find(|(flag, _)| flag == previous) {
+            return Some(*kind);
+        }
+    }
+
+    // Collect positional arguments, skipping flags and their values
+    let mut positionals = Vec::new();
+    let mut words = preceding.iter();
+    while let Some(word) = words.next() {
+        if word.starts_with('-') {
+            if !word.contains('=')
+                && (VALUE_FLAGS.contains(&word.as_str())
+                    || FLAG_COMPLETIONS.iter().any(|(flag, _)| flag == word))
+            {
+                words.next();
+            }
+        } else {
+            positionals.push(word.as_str());
+        }
+    }
+
+    POSITIONAL_COMPLETIONS
+        .iter()
+        .find(|(path, _)| positionals.starts_with(path))
+        .and_then(|(path, args)| args.get(positionals.len() - path.len()).copied().flatten())
+}
+
+/// Apply connection flags present in the words being completed, so that completions are fetched
+/// from the lattice the command will run against
+fn apply_connection_flags(opts: &mut CliConnectionOpts, words: &[String]) {
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        let (flag, value) = match word.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (word.as_str(), None),
+        };
+        let target = match flag {
+            "-r" | "--ctl-host" => &mut opts.ctl_host,
+            "-p" | "--ctl-port" => &mut opts.ctl_port,
+            "-x" | "--lattice" => &mut opts.lattice,
+            "--context" => &mut opts.context,
+            _ => continue,
+        };
+        if let Some(value) = value.or_else(|| words.next().cloned()) {
+            target.get_or_insert(value);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CompletionCache {
+    entries: HashMap<String, CachedCompletion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedCompletion {
+    /// Time at which the values were fetched, in milliseconds since the unix epoch
+    fetched_at_ms: u128,
+    values: Vec<String>,
+}
+
+fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+fn load_cache() -> CompletionCache {
+    cfg_dir()
+        .ok()
+        .and_then(|dir| std::fs::read(dir.join(CACHE_FILE)).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn store_cache(cache: &CompletionCache) {
+    if let (Ok(dir), Ok(bytes)) = (cfg_dir(), serde_json::to_vec(cache)) {
+        let _ = std::fs::write(dir.join(CACHE_FILE), bytes);
+    }
+}
+
+/// Fetch all values of a kind, without filtering
+async fn fetch_values(kind: CompletionKind, opts: CliConnectionOpts) -> Result<Vec<String>> {
+    if kind == CompletionKind::Contexts {
+        return ContextDir::new()?.list_contexts();
+    }
+
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let mut values = match kind {
+        CompletionKind::Apps => {
+            let lattice = Some(wco.get_lattice());
+            let client = wco.into_nats_client().await?;
+            wash_lib::app::get_models(&client, lattice)
+                .await?
+                .into_iter()
+                .map(|model| model.name)
+                .collect()
+        }
+        CompletionKind::Hosts | CompletionKind::Components | CompletionKind::Providers => {
+            let client = wco.into_ctl_client(None).await?;
+            let hosts = client
+                .get_hosts()
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?
+                .into_iter()
+                .filter_map(|host| host.response)
+                .map(|host| host.id)
+                .collect::<Vec<_>>();
+            if kind == CompletionKind::Hosts {
+                hosts
+            } else {
+                let inventories = futures::future::join_all(
+                    hosts
+                        .iter()
+                        .map(|host_id| client.get_host_inventory(host_id)),
+                )
+                .await;
+                inventories
+                    .into_iter()
+                    .filter_map(|inv| inv.ok().and_then(|inv| inv.response))
+                    .flat_map(|inv| {
+                        if kind == CompletionKind::Components {
+                            inv.components.into_iter().map(|c| c.id).collect::<Vec<_>>()
+                        } else {
+                            inv.providers.into_iter().map(|p| p.id).collect()
+                        }
+                    })
+                    .collect()
+            }
+        }
+        CompletionKind::Contexts => unreachable!("contexts are listed locally"),
+    };
+    values.sort();
+    values.dedup();
+    Ok(values)
+}
+
+/// Handle `wash __complete`, returning matching values one per line
+pub async fn handle_complete(cmd: CompleteCommand) -> Result<CommandOutput> {
+    let CompleteCommand { mut opts, words } = cmd;
+    let Some(kind) = completion_kind(&words) else {
+        return Ok(CommandOutput::default());
+    };
+    let current = words.last().map(String::as_str).unwrap_or_default();
+    apply_connection_flags(&mut opts, &words);
+    opts.timeout_ms = opts.timeout_ms.min(COMPLETION_TIMEOUT.as_millis() as u64);
+
+    let cache_key = format!(
+        "{}|{}|{}|{}|{}",
+        kind.cache_key(),
+        opts.ctl_host.as_deref().unwrap_or_default(),
+        opts.ctl_port.as_deref().unwrap_or_default(),
+        opts.lattice.as_deref().unwrap_or_default(),
+        opts.context.as_deref().unwrap_or_default(),
+    );
+    let mut cache = load_cache();
+    let values = match cache.entries.get(&cache_key) {
+        Some(cached) if now_ms().saturating_sub(cached.fetched_at_ms) < CACHE_TTL.as_millis() => {
+            cached.values.clone()
+        }
+        _ => {
+            // Failing to complete must never be an error for the shell, just complete nothing
+            let values = tokio::time::timeout(COMPLETION_TIMEOUT, fetch_values(kind, opts))
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            cache.entries.retain(|_, cached| {
+                now_ms().saturating_sub(cached.fetched_at_ms) < CACHE_TTL.as_millis()
+            });
+            cache.entries.insert(
+                cache_key,
+                CachedCompletion {
+                    fetched_at_ms: now_ms(),
+                    values: values.clone(),
+                },
+            );
+            store_cache(&cache);
+            values
+        }
+    };
+
+    let matches = values
+        .into_iter()
+        .filter(|value| value.starts_with(current))
+        .collect::<Vec<_>>();
+    Ok(CommandOutput::new(
+        matches.join("\n"),
+        HashMap::from([("values".to_string(), matches.into())]),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_completion_kind() {
+        assert_eq!(
+            completion_kind(&words(&["stop", "component", ""])),
+            Some(CompletionKind::Components)
+        );
+        assert_eq!(
+            completion_kind(&words(&["stop", "provider", "--ctl-port", "4222", "http"])),
+            Some(CompletionKind::Providers)
+        );
+        assert_eq!(
+            completion_kind(&words(&["stop", "component", "--host-id", "N"])),
+            Some(CompletionKind::Hosts)
+        );
+        assert_eq!(
+            completion_kind(&words(&["scale", "component", "NHOST", "ref", ""])),
+            Some(CompletionKind::Components)
+        );
+        assert_eq!(
+            completion_kind(&words(&["scale", "component", "NHOST", ""])),
+            None
+        );
+        assert_eq!(
+            completion_kind(&words(&["app", "status", ""])),
+            Some(CompletionKind::Apps)
+        );
+        assert_eq!(
+            completion_kind(&words(&["ctx", "del", ""])),
+            Some(CompletionKind::Contexts)
+        );
+        // Only the first positional argument is completed
+        assert_eq!(
+            completion_kind(&words(&["stop", "component", "id", ""])),
+            None
+        );
+        assert_eq!(completion_kind(&words(&["build", ""])), None);
+        assert_eq!(completion_kind(&[]), None);
+    }
+
+    #[test]
+    fn test_apply_connection_flags() {
+        let mut opts = CliConnectionOpts {
+            ctl_port: Some("5000".to_string()),
+            ..Default::default()
+        };
+        apply_connection_flags(
+            &mut opts,
+            &words(&[
+                "stop",
+                "component",
+                "--ctl-port",
+                "4222",
+                "-x=mylattice",
+                "--ctl-host",
+                "nats.local",
+                "",
+            ]),
+        );
+        // Explicit options take precedence over the words being completed
+        assert_eq!(opts.ctl_port.as_deref(), Some("5000"));
+        assert_eq!(opts.lattice.as_deref(), Some("mylattice"));
+        assert_eq!(opts.ctl_host.as_deref(), Some("nats.local"));
+    }
+
+    #[test]
+    fn test_dynamic_scripts() {
+        let bash = with_dynamic_completions(
+            Shell::Bash,
+            "_wash() {\n}\n\ncomplete -F _wash -o nosort -o bashdefault -o default wash\n".into(),
+        );
+        assert!(bash.contains("complete -F _wash_dynamic"));
+        assert!(!bash.contains("complete -F _wash -o"));
+
+        let zsh = with_dynamic_completions(
+            Shell::Zsh,
+            "_wash() {\n}\n\nif [ \"$funcstack[1]\" = \"_wash\" ]; then\n    _wash \"$@\"\nelse\n    compdef _wash wash\nfi\n".into(),
+        );
+        assert!(zsh.contains("_wash_dynamic() {"));
+        assert!(zsh.contains("compdef _wash_dynamic wash"));
+        assert!(zsh.contains("    _wash_dynamic \"$@\"\nelse"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Reading named configuration values from `.env`, JSON and YAML files for `wash config put
+//! --from-file`
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context as _};
+use serde_json::Value;
+
+/// Format of a file configuration values are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFileFormat {
+    /// `KEY=value` lines, as in `.env` files
+    Env,
+    /// A JSON object, nested objects are flattened into dotted keys
+    Json,
+    /// A YAML mapping, nested mappings are flattened into dotted keys
+    Yaml,
+}
+
+impl ConfigFileFormat {
+    /// Detect the format of a file from its extension, treating files named `.env` as env files
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if path.file_name().and_then(|name| name.to_str()) == Some(".env") {
+            return Some(Self::Env);
+        }
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "env" => Some(Self::Env),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Read configuration values from the file at `path`, in the given format or the one detected
+/// from its extension
+pub async fn read_config_file(
+    path: &Path,
+    format: Option<ConfigFileFormat>,
+) -> anyhow::Result<HashMap<String, String>> {
+    let Some(format) = format.or_else(|| ConfigFileFormat::from_path(path)) else {
+        bail!(
+            "unable to detect the format of [{}] from its extension, specify it with --format",
+            path.display()
+        );
+    };
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read configuration file [{}]", path.display()))?;
+    parse_config(&contents, format)
+        .with_context(|| format!("failed to parse configuration file [{}]", path.display()))
+}
+
+/// Parse configuration values in the given format
+pub fn parse_config(
+    contents: &str,
+    format: ConfigFileFormat,
+) -> anyhow::Result<HashMap<String, String>> {
+    let value = match format {
+        ConfigFileFormat::Env => return parse_env(contents),
+        ConfigFileFormat::Json => serde_json::from_str(contents).context("invalid JSON")?,
+        ConfigFileFormat::Yaml => serde_yaml::from_str(contents).context("invalid YAML")?,
+    };
+    let mut values = HashMap::new();
+    match value {
+        Value::Object(_) => flatten("", value, &mut values),
+        // An empty YAML document holds no values
+        Value::Null => {}
+        _ => bail!("configuration values must be a map of keys to values"),
+    }
+    Ok(values)
+}
+
+/// Prefix all keys of `values` with `prefix`, separated by a dot unless the prefix already ends
+/// with one
+#[must_use]
+pub fn prefix_keys(values: HashMap<String, String>, prefix: &str) -> HashMap<String, String> {
+    let prefix = prefix.strip_suffix('.').unwrap_or(prefix);
+    if prefix.is_empty() {
+        return values;
+    }
+    values
+        .into_iter()
+        .map(|(key, value)| (format!("{prefix}.{key}"), value))
+        .collect()
+}
+
+/// Flatten `value` into `values`, joining the keys of nested maps with dots. Lists are kept as
+/// their JSON encoding and nulls as empty strings
+fn flatten(key: &str, value: Value, values: &mut HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                let key = if key.is_empty() {
+                    name
+                } else {
+                    format!("{key}.{name}")
+                };
+                flatten(&key, value, values);
+            }
+        }
+        Value::String(s) => {
+            values.insert(key.to_string(), s);
+        }
+        Value::Null => {
+            values.insert(key.to_string(), String::new());
+        }
+        value => {
+            values.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Parse `KEY=value` lines, skipping blank lines and `#` comments. Lines may start with `export`
+/// and values may be wrapped in matching single or double quotes
+fn parse_env(contents: &str) -> anyhow::Result<HashMap<String, String>> {
+    let mut values = HashMap::new();
+    for (n, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("line {} is not formatted as KEY=value", n + 1);
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("line {} has an empty key", n + 1);
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .into_iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(quote)
+                    .and_then(|value| value.strip_suffix(quote))
+            })
+            .unwrap_or(value);
+        values.insert(key.to_string(), value.to_string());
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        for (path, format) in [
+            ("values.env", Some(ConfigFileFormat::Env)),
+            ("config/.env", Some(ConfigFileFormat::Env)),
+            ("values.json", Some(ConfigFileFormat::Json)),
+            ("values.YAML", Some(ConfigFileFormat::Yaml)),
+            ("values.yml", Some(ConfigFileFormat::Yaml)),
+            ("values.toml", None),
+            ("values", None),
+        ] {
+            assert_eq!(
+                ConfigFileFormat::from_path(Path::new(path)),
+                format,
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_nested_yaml() {
+        let values = parse_config(
+            r"
+database:
+  host: db.internal
+  port: 5432
+  tls:
+    enabled: true
+hosts: [a, b]
+empty:
+",
+            ConfigFileFormat::Yaml,
+        )
+        .expect("valid YAML");
+        assert_eq!(
+            values,
+            HashMap::from([
+                ("database.host".to_string(), "db.internal".to_string()),
+                ("database.port".to_string(), "5432".to_string()),
+                ("database.tls.enabled".to_string(), "true".to_string()),
+                ("hosts".to_string(), r#"["a","b"]"#.to_string()),
+                ("empty".to_string(), String::new()),
+            ])
+        );
+        assert_eq!(
+            prefix_keys(values, "app."),
+            HashMap::from([
+                ("app.database.host".to_string(), "db.internal".to_string()),
+                ("app.database.port".to_string(), "5432".to_string()),
+                ("app.database.tls.enabled".to_string(), "true".to_string()),
+                ("app.hosts".to_string(), r#"["a","b"]"#.to_string()),
+                ("app.empty".to_string(), String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let values = parse_config(r#"{"a": {"b": "c"}, "d": 1.5}"#, ConfigFileFormat::Json)
+            .expect("valid JSON");
+        assert_eq!(
+            values,
+            HashMap::from([
+                ("a.b".to_string(), "c".to_string()),
+                ("d".to_string(), "1.5".to_string()),
+            ])
+        );
+        assert!(parse_config("[1, 2]", ConfigFileFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let values = parse_config(
+            "# comment\n\nexport API_KEY=\"secret value\"\nREGION='us-east-1'\nURL=http://x?a=b\n",
+            ConfigFileFormat::Env,
+        )
+        .expect("valid env file");
+        assert_eq!(
+            values,
+            HashMap::from([
+                ("API_KEY".to_string(), "secret value".to_string()),
+                ("REGION".to_string(), "us-east-1".to_string()),
+                ("URL".to_string(), "http://x?a=b".to_string()),
+            ])
+        );
+        assert!(parse_config("NOT_A_PAIR", ConfigFileFormat::Env).is_err());
+    }
+}
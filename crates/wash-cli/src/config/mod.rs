@@ -0,0 +1,443 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use serde_json::json;
+use tracing::error;
+use wash_lib::{
+    cli::{
+        config_schema::{resolve_provider_schema, validate_config},
+        input_vec_to_hashmap, CliConnectionOpts, CommandOutput, OutputKind,
+    },
+    config::WashConnectionOptions,
+};
+use wasmcloud_control_interface::Client as CtlClient;
+
+use crate::appearance::spinner::Spinner;
+
+mod file;
+pub use file::{parse_config, prefix_keys, read_config_file, ConfigFileFormat};
+
+#[derive(Debug, Clone, Subcommand)]
+#[allow(clippy::enum_variant_names)]
+pub enum ConfigCliCommand {
+    /// Put a named configuration
+    #[clap(name = "put", alias = "create", about = "Put named configuration")]
+    PutCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to put
+        #[clap(name = "name")]
+        name: String,
+        /// The configuration values to put, in the form of `key=value`. Can be specified multiple times, but must be specified at least once unless `--from-file` is used.
+        #[clap(name = "config_value", required_unless_present = "from_file")]
+        config_values: Vec<String>,
+        /// Read configuration values from a `.env`, JSON or YAML file. Nested maps are flattened
+        /// into dotted keys, values given as arguments take precedence over values from the file
+        #[clap(long = "from-file", value_name = "PATH")]
+        from_file: Option<PathBuf>,
+        /// Format of the file given with `--from-file`, detected from its extension by default
+        #[clap(long = "format", value_enum, requires = "from_file")]
+        format: Option<ConfigFileFormat>,
+        /// Namespace all keys being put under this prefix, e.g. `--prefix db` puts `host` as `db.host`
+        #[clap(long = "prefix")]
+        prefix: Option<String>,
+        /// Merge the values into the existing configuration rather than replacing it, which
+        /// removes existing keys that are not being put
+        #[clap(long = "merge")]
+        merge: bool,
+        /// Print the resulting configuration keys and which existing keys would change or be
+        /// removed, without putting the configuration
+        #[clap(long = "dry-run")]
+        dry_run: bool,
+        /// Validate the configuration values against the config schema of a provider before putting
+        /// them. Accepts a path to a provider archive, an OCI reference or the ID of a running provider
+        #[clap(long = "validate-against", value_name = "PROVIDER_REF_OR_ID")]
+        validate_against: Option<String>,
+    },
+    /// Get a named configuration
+    #[clap(name = "get")]
+    GetCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to get
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Delete a named configuration
+    #[clap(name = "del", alias = "delete")]
+    DelCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to delete
+        #[clap(name = "name")]
+        name: String,
+    },
+}
+
+pub async fn handle_command(
+    command: ConfigCliCommand,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    match command {
+        ConfigCliCommand::PutCommand {
+            opts,
+            name,
+            config_values,
+            from_file,
+            format,
+            prefix,
+            merge,
+            dry_run,
+            validate_against,
+        } => {
+            let mut values = match from_file {
+                Some(path) => read_config_file(&path, format).await?,
+                None => HashMap::new(),
+            };
+            values.extend(input_vec_to_hashmap(config_values)?);
+            if let Some(prefix) = prefix {
+                values = prefix_keys(values, &prefix);
+            }
+            put_config(
+                opts,
+                &name,
+                values,
+                PutOptions {
+                    merge,
+                    dry_run,
+                    validate_against: validate_against.as_deref(),
+                },
+                output_kind,
+            )
+            .await
+        }
+        ConfigCliCommand::GetCommand { opts, name } => get_config(opts, &name, output_kind).await,
+        ConfigCliCommand::DelCommand { opts, name } => {
+            delete_config(opts, &name, output_kind).await
+        }
+    }
+}
+
+/// How `wash config put` applies the values being put
+#[derive(Debug, Clone, Copy, Default)]
+struct PutOptions<'a> {
+    /// Keep existing keys that are not being put
+    merge: bool,
+    /// Only report the changes that would be made
+    dry_run: bool,
+    /// Provider to validate the resulting configuration against
+    validate_against: Option<&'a str>,
+}
+
+/// Keys of a named configuration that are added, updated or removed by putting new values
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ConfigChanges {
+    added: BTreeSet<String>,
+    updated: BTreeSet<String>,
+    removed: BTreeSet<String>,
+}
+
+impl ConfigChanges {
+    fn summary(&self) -> String {
+        format!(
+            "{} added, {} updated, {} removed",
+            self.added.len(),
+            self.updated.len(),
+            self.removed.len()
+        )
+    }
+}
+
+/// Compute the configuration resulting from putting `values` over the `existing` configuration,
+/// either merged into it or replacing it, along with the keys that change
+fn plan_config(
+    existing: &HashMap<String, String>,
+    values: HashMap<String, String>,
+    merge: bool,
+) -> (HashMap<String, String>, ConfigChanges) {
+    let mut changes = ConfigChanges::default();
+    for (key, value) in &values {
+        match existing.get(key) {
+            None => {
+                changes.added.insert(key.clone());
+            }
+            Some(old) if old != value => {
+                changes.updated.insert(key.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    if merge {
+        let mut merged = existing.clone();
+        merged.extend(values);
+        return (merged, changes);
+    }
+    changes.removed = existing
+        .keys()
+        .filter(|key| !values.contains_key(*key))
+        .cloned()
+        .collect();
+    (values, changes)
+}
+
+/// Describe the configuration resulting from a dry run, marking added (`+`), updated (`~`) and
+/// removed (`-`) keys
+fn dry_run_text(name: &str, values: &HashMap<String, String>, changes: &ConfigChanges) -> String {
+    let mut keys: Vec<_> = values.keys().chain(&changes.removed).collect();
+    keys.sort();
+    let mut text = format!(
+        "Dry run, configuration {name} would have {} key(s) ({}):",
+        values.len(),
+        changes.summary()
+    );
+    for key in keys {
+        let marker = if changes.added.contains(key) {
+            '+'
+        } else if changes.updated.contains(key) {
+            '~'
+        } else if changes.removed.contains(key) {
+            '-'
+        } else {
+            ' '
+        };
+        text.push_str(&format!("\n  {marker} {key}"));
+    }
+    text
+}
+
+async fn put_config(
+    opts: CliConnectionOpts,
+    name: &str,
+    values: HashMap<String, String>,
+    options: PutOptions<'_>,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    sp.update_spinner_message("Getting existing configuration ...".to_string());
+    let existing = ctl_client
+        .get_config(name)
+        .await
+        .map_err(suggest_run_host_error)?
+        .response
+        .unwrap_or_default();
+    let (values, changes) = plan_config(&existing, values, options.merge);
+
+    let schema_validated = if let Some(provider) = options.validate_against {
+        sp.update_spinner_message(format!("Validating configuration against {provider} ..."));
+        validate_against_provider(&ctl_client, provider, &values).await?
+    } else {
+        false
+    };
+
+    let counts = [
+        ("added".to_string(), json!(changes.added.len())),
+        ("updated".to_string(), json!(changes.updated.len())),
+        ("removed".to_string(), json!(changes.removed.len())),
+        ("schema_validated".to_string(), json!(schema_validated)),
+    ];
+    if options.dry_run {
+        sp.finish_and_clear();
+        let mut keys: Vec<_> = values.keys().collect();
+        keys.sort();
+        let mut json_out = HashMap::from_iter(counts);
+        json_out.insert("dry_run".to_string(), json!(true));
+        json_out.insert("keys".to_string(), json!(keys));
+        json_out.insert(
+            "changes".to_string(),
+            json!({
+                "added": changes.added,
+                "updated": changes.updated,
+                "removed": changes.removed,
+            }),
+        );
+        return Ok(CommandOutput::new(
+            dry_run_text(name, &values, &changes),
+            json_out,
+        ));
+    }
+
+    sp.update_spinner_message("Putting configuration ...".to_string());
+    // Handle no responders by suggesting a host needs to be running
+    let config_response = ctl_client
+        .put_config(name, values)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    let message = if config_response.message.is_empty() && config_response.success {
+        format!(
+            "Configuration {name} put successfully ({}).",
+            changes.summary()
+        )
+    } else {
+        config_response.message
+    };
+    let mut json_out = HashMap::from_iter(counts);
+    json_out.insert("success".to_string(), json!(config_response.success));
+    json_out.insert("message".to_string(), json!(message));
+    let output = CommandOutput::new(message, json_out);
+
+    Ok(output)
+}
+
+/// Validate configuration values against the config schema of a provider, returning whether a
+/// schema was available to validate against
+async fn validate_against_provider(
+    ctl_client: &CtlClient,
+    provider: &str,
+    values: &HashMap<String, String>,
+) -> anyhow::Result<bool> {
+    let Some(schema) = resolve_provider_schema(ctl_client, provider).await? else {
+        eprintln!(
+            "Note: provider {provider} does not declare a config schema, skipping validation"
+        );
+        return Ok(false);
+    };
+    validate_config(&schema, values)
+        .map_err(|e| anyhow::anyhow!("configuration is not valid for provider {provider}: {e}"))?;
+    Ok(true)
+}
+
+async fn get_config(
+    opts: CliConnectionOpts,
+    name: &str,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Getting configuration ...".to_string());
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    let config_response = ctl_client
+        .get_config(name)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    if !config_response.message.is_empty() && !config_response.success {
+        error!("Error getting configuration: {}", config_response.message);
+    };
+
+    if let Some(config) = config_response.response {
+        Ok(CommandOutput::new(
+            format!("{:?}", config),
+            config.into_iter().map(|(k, v)| (k, json!(v))).collect(),
+        ))
+    } else {
+        Err(anyhow::anyhow!("No configuration found for name: {}", name))
+    }
+}
+
+async fn delete_config(
+    opts: CliConnectionOpts,
+    name: &str,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Deleting configuration ...".to_string());
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    let config_response = ctl_client
+        .delete_config(name)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    let message = if config_response.message.is_empty() && config_response.success {
+        format!("Configuration {name} deleted successfully.")
+    } else {
+        config_response.message
+    };
+    let json_out = HashMap::from_iter([
+        ("success".to_string(), json!(config_response.success)),
+        ("message".to_string(), json!(message)),
+    ]);
+    let output = CommandOutput::new(message, json_out);
+
+    Ok(output)
+}
+
+/// Simple helper function to suggest running a host if no responders are found
+fn suggest_run_host_error(e: Box<dyn Error + std::marker::Send + Sync>) -> anyhow::Error {
+    let err_str = e.to_string();
+    if err_str.contains("no responders") {
+        anyhow::anyhow!("No responders found for config put request. Is a host running?")
+    } else {
+        anyhow::anyhow!(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn keys(keys: &[&str]) -> BTreeSet<String> {
+        keys.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_plan_config_replace_and_merge() {
+        let existing = config(&[("db.host", "old"), ("db.port", "5432"), ("stale", "x")]);
+        let values = config(&[("db.host", "new"), ("db.port", "5432"), ("db.user", "app")]);
+
+        let (replaced, changes) = plan_config(&existing, values.clone(), false);
+        assert_eq!(replaced, values);
+        assert_eq!(
+            changes,
+            ConfigChanges {
+                added: keys(&["db.user"]),
+                updated: keys(&["db.host"]),
+                removed: keys(&["stale"]),
+            }
+        );
+
+        let (merged, changes) = plan_config(&existing, values, true);
+        assert_eq!(
+            merged,
+            config(&[
+                ("db.host", "new"),
+                ("db.port", "5432"),
+                ("db.user", "app"),
+                ("stale", "x"),
+            ])
+        );
+        assert_eq!(
+            changes,
+            ConfigChanges {
+                added: keys(&["db.user"]),
+                updated: keys(&["db.host"]),
+                removed: BTreeSet::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_dry_run_text() {
+        let existing = config(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let (values, changes) = plan_config(
+            &existing,
+            config(&[("a", "1"), ("b", "9"), ("d", "4")]),
+            false,
+        );
+        assert_eq!(
+            dry_run_text("cfg", &values, &changes),
+            "Dry run, configuration cfg would have 3 key(s) (1 added, 1 updated, 1 removed):\n    a\n  ~ b\n  - c\n  + d"
+        );
+    }
+}
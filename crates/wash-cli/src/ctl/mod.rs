@@ -154,6 +154,7 @@ mod test {
                 host_id,
                 provider_id,
                 skip_wait,
+                ..
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
@@ -192,7 +193,7 @@ mod test {
             "2001",
         ])?;
         match get_hosts_all.command {
-            CtlCliCommand::Get(CtlGetCommand::Hosts(GetHostsCommand { opts })) => {
+            CtlCliCommand::Get(CtlGetCommand::Hosts(GetHostsCommand { opts, .. })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
                 assert_eq!(&opts.lattice.unwrap(), DEFAULT_LATTICE);
@@ -218,7 +219,10 @@ mod test {
             CtlCliCommand::Get(CtlGetCommand::HostInventories(GetHostInventoriesCommand {
                 opts,
                 host_id,
+                watch,
+                ..
             })) => {
+                assert!(!watch);
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
                 assert_eq!(&opts.lattice.unwrap(), DEFAULT_LATTICE);
@@ -324,6 +328,7 @@ mod test {
                 host_id,
                 component_id,
                 new_component_ref,
+                ..
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
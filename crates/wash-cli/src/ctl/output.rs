@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 use anyhow::{bail, Result};
 use serde_json::json;
@@ -7,21 +8,48 @@ use term_table::{
     table_cell::{Alignment, TableCell},
     Table,
 };
-use wash_lib::{cli::CommandOutput, plugin::subcommand::Metadata};
-use wasmcloud_control_interface::{Host, HostInventory, InterfaceLinkDefinition};
+use wash_lib::{
+    cli::{
+        get::{HostDetails, InventoryEntry},
+        table::{render_table, Column, TableOpts},
+        CommandOutput, OutputKind,
+    },
+    plugin::subcommand::Metadata,
+};
+use wasmcloud_control_interface::{HostInventory, InterfaceLinkDefinition};
 
 use crate::util::format_optional;
 
-pub fn get_hosts_output(hosts: Vec<Host>) -> CommandOutput {
+pub fn get_hosts_output(
+    hosts: Vec<HostDetails>,
+    detailed: bool,
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let defaults: &[&str] = if detailed {
+        &DETAILED_HOST_COLUMNS
+    } else {
+        &HOST_COLUMNS
+    };
+    let text = render_table(&hosts, &host_columns(), defaults, table, output_kind)?;
     let mut map = HashMap::new();
     map.insert("hosts".to_string(), json!(hosts));
-    CommandOutput::new(hosts_table(hosts), map)
+    Ok(CommandOutput::new(text, map))
 }
 
-pub fn get_host_inventories_output(invs: Vec<HostInventory>) -> CommandOutput {
+pub fn get_host_inventories_output(
+    invs: Vec<HostInventory>,
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let text = host_inventories_table(&invs, table, output_kind)?;
     let mut map = HashMap::new();
+    map.insert(
+        "items".to_string(),
+        json!(InventoryEntry::from_inventories(&invs)),
+    );
     map.insert("inventories".to_string(), json!(invs));
-    CommandOutput::new(host_inventories_table(invs), map)
+    Ok(CommandOutput::new(text, map))
 }
 
 pub fn get_claims_output(claims: Vec<HashMap<String, String>>) -> CommandOutput {
@@ -55,147 +83,175 @@ pub fn link_del_output(
     }
 }
 
-/// Helper function to transform a LinkDefinitionList into a table string for printing
-pub fn links_table(list: Vec<InterfaceLinkDefinition>) -> String {
-    let mut table = Table::new();
-    crate::util::configure_table_style(&mut table);
-
-    table.add_row(Row::new(vec![
-        TableCell::new_with_alignment("Source ID", 1, Alignment::Left),
-        TableCell::new_with_alignment("Target", 1, Alignment::Left),
-        TableCell::new_with_alignment("WIT", 1, Alignment::Left),
-        TableCell::new_with_alignment("Interfaces", 1, Alignment::Left),
-    ]));
+/// Columns of `wash get links` shown by default
+const LINK_COLUMNS: [&str; 4] = ["source", "target", "wit", "interfaces"];
 
-    list.iter().for_each(|l| {
-        table.add_row(Row::new(vec![
-            TableCell::new_with_alignment(l.source_id.clone(), 1, Alignment::Left),
-            TableCell::new_with_alignment(l.target.clone(), 1, Alignment::Left),
-            TableCell::new_with_alignment(
-                format!("{}:{}", l.wit_namespace, l.wit_package),
-                1,
-                Alignment::Left,
-            ),
-            TableCell::new_with_alignment(l.interfaces.join(","), 1, Alignment::Left),
-        ]))
-    });
+/// Columns of `wash get links`
+fn link_columns() -> Vec<Column<InterfaceLinkDefinition>> {
+    vec![
+        Column::new("source", "Source ID", |l: &InterfaceLinkDefinition| {
+            l.source_id.clone()
+        })
+        .identifier(),
+        Column::new("target", "Target", |l: &InterfaceLinkDefinition| {
+            l.target.clone()
+        })
+        .identifier(),
+        Column::new("name", "Name", |l: &InterfaceLinkDefinition| l.name.clone()),
+        Column::new("wit", "WIT", |l: &InterfaceLinkDefinition| {
+            format!("{}:{}", l.wit_namespace, l.wit_package)
+        }),
+        Column::new("interfaces", "Interfaces", |l: &InterfaceLinkDefinition| {
+            l.interfaces.join(",")
+        }),
+        Column::new(
+            "source-config",
+            "Source config",
+            |l: &InterfaceLinkDefinition| format_list(&l.source_config),
+        ),
+        Column::new(
+            "target-config",
+            "Target config",
+            |l: &InterfaceLinkDefinition| format_list(&l.target_config),
+        ),
+    ]
+}
 
-    table.render()
+/// Helper function to transform a LinkDefinitionList into a table string for printing
+pub fn links_table(
+    list: &[InterfaceLinkDefinition],
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<String> {
+    render_table(list, &link_columns(), &LINK_COLUMNS, table, output_kind)
 }
 
-/// Helper function to transform a Host list into a table string for printing
-pub fn hosts_table(hosts: Vec<Host>) -> String {
-    let mut table = Table::new();
-    crate::util::configure_table_style(&mut table);
+/// Columns of `wash get hosts` shown by default
+const HOST_COLUMNS: [&str; 3] = ["id", "uptime-seconds", "name"];
 
-    table.add_row(Row::new(vec![
-        TableCell::new_with_alignment("Host ID", 1, Alignment::Left),
-        TableCell::new_with_alignment("Uptime (seconds)", 1, Alignment::Left),
-        TableCell::new_with_alignment("Friendly name", 1, Alignment::Left),
-    ]));
-    hosts.iter().for_each(|h| {
-        table.add_row(Row::new(vec![
-            TableCell::new_with_alignment(h.id.clone(), 1, Alignment::Left),
-            TableCell::new_with_alignment(format!("{}", h.uptime_seconds), 1, Alignment::Left),
-            TableCell::new_with_alignment(h.friendly_name.clone(), 1, Alignment::Left),
-        ]))
-    });
+/// Columns of `wash get hosts --detailed` shown by default
+const DETAILED_HOST_COLUMNS: [&str; 8] = [
+    "id",
+    "name",
+    "uptime",
+    "version",
+    "platform",
+    "components",
+    "providers",
+    "max-instances",
+];
 
-    table.render()
-}
+/// Columns of `wash get hosts` which require querying the inventory of every host
+pub const HOST_INVENTORY_COLUMNS: [&str; 3] = ["components", "providers", "max-instances"];
 
-/// Helper function to transform a HostInventory into a table string for printing
-pub fn host_inventories_table(invs: Vec<HostInventory>) -> String {
-    let mut table = Table::new();
-    crate::util::configure_table_style(&mut table);
+/// Columns of `wash get hosts`
+fn host_columns() -> Vec<Column<HostDetails>> {
+    vec![
+        Column::new("id", "Host ID", |h: &HostDetails| h.host.id.clone()).identifier(),
+        Column::new("name", "Friendly name", |h: &HostDetails| {
+            h.host.friendly_name.clone()
+        }),
+        Column::new("uptime", "Uptime", |h: &HostDetails| {
+            h.host
+                .uptime_human
+                .clone()
+                .unwrap_or_else(|| format!("{}s", h.host.uptime_seconds))
+        }),
+        Column::new("uptime-seconds", "Uptime (seconds)", |h: &HostDetails| {
+            h.host.uptime_seconds.to_string()
+        }),
+        Column::new("version", "Version", |h: &HostDetails| {
+            format_optional(h.host.version.clone())
+        }),
+        Column::new("platform", "OS/Arch", |h: &HostDetails| {
+            format!(
+                "{}/{}",
+                format_optional(h.os.clone()),
+                format_optional(h.arch.clone())
+            )
+        }),
+        Column::new("labels", "Labels", |h: &HostDetails| {
+            let labels: BTreeMap<_, _> = h.host.labels.iter().collect();
+            format_list(
+                &labels
+                    .into_iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>(),
+            )
+        }),
+        Column::new("components", "Components", |h: &HostDetails| {
+            format_optional(h.component_count.map(|n| n.to_string()))
+        }),
+        Column::new("providers", "Providers", |h: &HostDetails| {
+            format_optional(h.provider_count.map(|n| n.to_string()))
+        }),
+        Column::new("max-instances", "Max instances", |h: &HostDetails| {
+            format_optional(h.max_instances.map(|n| n.to_string()))
+        }),
+    ]
+}
 
-    invs.into_iter().for_each(|inv| {
-        table.add_row(Row::new(vec![TableCell::new_with_alignment(
-            format!("Host Inventory ({})", inv.host_id),
-            4,
-            Alignment::Left,
-        )]));
+/// Columns of `wash get inventory` shown by default
+const INVENTORY_COLUMNS: [&str; 7] = [
+    "host",
+    "kind",
+    "id",
+    "name",
+    "image",
+    "max-instances",
+    "app",
+];
 
-        if !inv.labels.is_empty() {
-            table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "",
-                2,
-                Alignment::Left,
-            )]));
-            inv.labels.iter().for_each(|(k, v)| {
-                table.add_row(Row::new(vec![
-                    TableCell::new_with_alignment(k, 1, Alignment::Left),
-                    TableCell::new_with_alignment(v, 1, Alignment::Left),
-                ]))
-            });
-        } else {
-            table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "No labels present",
-                4,
-                Alignment::Center,
-            )]));
-        }
+/// Columns of `wash get inventory`
+fn inventory_columns() -> Vec<Column<InventoryEntry>> {
+    vec![
+        Column::new("host", "Host ID", |e: &InventoryEntry| e.host_id.clone()).identifier(),
+        Column::new("kind", "Kind", |e: &InventoryEntry| e.kind.clone()),
+        Column::new("id", "ID", |e: &InventoryEntry| e.id.clone()).identifier(),
+        Column::new("name", "Name", |e: &InventoryEntry| {
+            format_optional(e.name.clone())
+        }),
+        Column::new("image", "Image Reference", |e: &InventoryEntry| {
+            format_optional(e.image_ref.clone())
+        }),
+        Column::new("max-instances", "Max Count", |e: &InventoryEntry| {
+            format_optional(e.max_instances.map(|n| n.to_string()))
+        }),
+        Column::new("revision", "Revision", |e: &InventoryEntry| {
+            e.revision.to_string()
+        }),
+        Column::new("app", "Application", |e: &InventoryEntry| {
+            format_optional(e.app.clone())
+        }),
+        Column::new("managed-by", "Managed By", |e: &InventoryEntry| {
+            format_optional(e.managed_by.clone())
+        }),
+    ]
+}
 
-        table.add_row(Row::new(vec![TableCell::new_with_alignment(
-            "",
-            4,
-            Alignment::Center,
-        )]));
-        if !inv.components.is_empty() {
-            table.add_row(Row::new(vec![
-                TableCell::new_with_alignment("Component ID", 1, Alignment::Left),
-                TableCell::new_with_alignment("Image Reference", 2, Alignment::Left),
-                TableCell::new_with_alignment("Max Count", 1, Alignment::Left),
-            ]));
-            inv.components.iter().for_each(|a| {
-                let a = a.clone();
-                table.add_row(Row::new(vec![
-                    TableCell::new_with_alignment(a.id, 1, Alignment::Left),
-                    TableCell::new_with_alignment(a.image_ref, 2, Alignment::Left),
-                    TableCell::new_with_alignment(a.max_instances, 1, Alignment::Left),
-                ]))
-            });
-        } else {
-            table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "No components found",
-                4,
-                Alignment::Left,
-            )]));
-        }
-        table.add_row(Row::new(vec![TableCell::new_with_alignment(
-            "",
-            4,
-            Alignment::Left,
-        )]));
-        if !inv.providers.is_empty() {
-            table.add_row(Row::new(vec![
-                TableCell::new_with_alignment("Provider ID", 1, Alignment::Left),
-                TableCell::new_with_alignment("Name", 1, Alignment::Left),
-                TableCell::new_with_alignment("Image Reference", 1, Alignment::Left),
-            ]));
-            inv.providers.iter().for_each(|p| {
-                let p = p.clone();
-                table.add_row(Row::new(vec![
-                    TableCell::new_with_alignment(p.id, 1, Alignment::Left),
-                    TableCell::new_with_alignment(format_optional(p.name), 1, Alignment::Left),
-                    TableCell::new_with_alignment(format_optional(p.image_ref), 1, Alignment::Left),
-                ]))
-            });
-        } else {
-            table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "No providers found",
-                4,
-                Alignment::Left,
-            )]));
-        }
-        table.add_row(Row::new(vec![TableCell::new_with_alignment(
-            "",
-            4,
-            Alignment::Left,
-        )]));
-    });
+/// Helper function to transform host inventories into a table string for printing, with a row per
+/// component and provider
+pub fn host_inventories_table(
+    invs: &[HostInventory],
+    table: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<String> {
+    render_table(
+        &InventoryEntry::from_inventories(invs),
+        &inventory_columns(),
+        &INVENTORY_COLUMNS,
+        table,
+        output_kind,
+    )
+}
 
-    table.render()
+/// Comma-separated `values`, `N/A` if there are none
+fn format_list(values: &[String]) -> String {
+    if values.is_empty() {
+        "N/A".to_string()
+    } else {
+        values.join(",")
+    }
 }
 
 /// Helper function to transform a ClaimsList into a table string for printing
@@ -292,3 +348,21 @@ pub fn plugins_table(list: Vec<&Metadata>) -> String {
 
     table.render()
 }
+
+pub fn external_plugins_table(plugins: &BTreeMap<String, PathBuf>) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("ID", 1, Alignment::Left),
+        TableCell::new_with_alignment("Path", 1, Alignment::Left),
+    ]));
+    plugins.iter().for_each(|(id, path)| {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(id, 1, Alignment::Left),
+            TableCell::new_with_alignment(path.display(), 1, Alignment::Left),
+        ]));
+    });
+
+    table.render()
+}
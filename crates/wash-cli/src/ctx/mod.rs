@@ -5,14 +5,19 @@ use std::{
     process::Command,
 };
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
 use serde_json::json;
 use tracing::warn;
 use wash_lib::{
     cli::CommandOutput,
     config::{DEFAULT_LATTICE, DEFAULT_NATS_HOST, DEFAULT_NATS_PORT, DEFAULT_NATS_TIMEOUT_MS},
-    context::{fs::ContextDir, ContextManager, WashContext, HOST_CONFIG_NAME},
+    context::{
+        bundle::{bundle_passphrase, ContextBundle, PinnedVersions},
+        encryption::{keychain_available, KeySource},
+        fs::ContextDir,
+        ContextManager, WashContext, HOST_CONFIG_NAME,
+    },
     id::ClusterSeed,
 };
 
@@ -21,6 +26,8 @@ use wash_lib::generate::{
     project_variables::StringEntry,
 };
 
+use crate::up::{WADM_VERSION, WASMCLOUD_HOST_VERSION};
+
 pub async fn handle_command(ctx_cmd: CtxCommand) -> Result<CommandOutput> {
     use CtxCommand::*;
     match ctx_cmd {
@@ -29,6 +36,9 @@ pub async fn handle_command(ctx_cmd: CtxCommand) -> Result<CommandOutput> {
         Edit(cmd) => handle_edit(cmd),
         New(cmd) => handle_new(cmd),
         Del(cmd) => handle_del(cmd),
+        Encrypt(cmd) => handle_encrypt(cmd),
+        Export(cmd) => handle_export(cmd),
+        Import(cmd) => handle_import(cmd),
     }
 }
 
@@ -49,6 +59,15 @@ pub enum CtxCommand {
     /// Edit a context directly using a text editor
     #[clap(name = "edit")]
     Edit(EditCommand),
+    /// Encrypt the seeds and JWTs stored in contexts
+    #[clap(name = "encrypt")]
+    Encrypt(EncryptCommand),
+    /// Export a context, with the configuration of its registries, as a bundle to share
+    #[clap(name = "export")]
+    Export(ExportCommand),
+    /// Import a context from a bundle created with `wash ctx export`
+    #[clap(name = "import")]
+    Import(ImportCommand),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -110,6 +129,73 @@ pub struct EditCommand {
     pub editor: String,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct EncryptCommand {
+    /// Location of context files for managing. Defaults to $WASH_CONTEXTS ($HOME/.wash/contexts)
+    #[clap(long = "directory", env = "WASH_CONTEXTS", hide_env_values = true)]
+    directory: Option<PathBuf>,
+
+    /// Name of the context to encrypt, if not supplied the user will be prompted to select a context
+    #[clap(name = "name", conflicts_with = "all")]
+    pub name: Option<String>,
+
+    /// Encrypt all contexts
+    #[clap(long = "all")]
+    pub all: bool,
+
+    /// Derive the encryption key from a passphrase instead of storing it in the OS keychain. The
+    /// passphrase is read from $WASH_CTX_PASSPHRASE, or prompted for
+    #[clap(long = "passphrase")]
+    pub passphrase: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExportCommand {
+    /// Location of context files for managing. Defaults to $WASH_CONTEXTS ($HOME/.wash/contexts)
+    #[clap(long = "directory", env = "WASH_CONTEXTS", hide_env_values = true)]
+    directory: Option<PathBuf>,
+
+    /// Name of the context to export, if not supplied the user will be prompted to select a context
+    #[clap(name = "name")]
+    pub name: Option<String>,
+
+    /// Path of the bundle to write
+    #[clap(long = "bundle")]
+    pub bundle: PathBuf,
+
+    /// Seal the seeds and JWTs of the context into the bundle with a passphrase, instead of leaving
+    /// them out. The passphrase is read from $WASH_BUNDLE_PASSPHRASE, or prompted for
+    #[clap(long = "seal-secrets")]
+    pub seal_secrets: bool,
+
+    /// Version of the wasmCloud host to pin in the bundle
+    #[clap(long = "wasmcloud-version", default_value = WASMCLOUD_HOST_VERSION)]
+    pub wasmcloud_version: String,
+
+    /// Version of wadm to pin in the bundle
+    #[clap(long = "wadm-version", default_value = WADM_VERSION)]
+    pub wadm_version: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ImportCommand {
+    /// Location of context files for managing. Defaults to $WASH_CONTEXTS ($HOME/.wash/contexts)
+    #[clap(long = "directory", env = "WASH_CONTEXTS", hide_env_values = true)]
+    directory: Option<PathBuf>,
+
+    /// Path of the bundle to import
+    #[clap(name = "bundle")]
+    pub bundle: PathBuf,
+
+    /// Name to import the context as, instead of its name in the bundle
+    #[clap(long = "name")]
+    pub name: Option<String>,
+
+    /// Overwrite an existing context with the same name
+    #[clap(long = "force")]
+    pub force: bool,
+}
+
 /// Lists all JSON files found in the context directory, with the exception of `index.json`
 /// Being present in this list does not guarantee a valid context
 fn handle_list(cmd: ListCommand) -> Result<CommandOutput> {
@@ -121,18 +207,30 @@ fn handle_list(cmd: ListCommand) -> Result<CommandOutput> {
     let text_contexts = contexts
         .iter()
         .map(|f| {
-            if f == &default_context_name {
-                format!("{f} (default)")
+            // Encrypted contexts are listed with a lock, without decrypting them
+            let name = if dir.is_context_encrypted(f).unwrap_or_default() {
+                format!("🔒 {f}")
             } else {
                 f.clone()
+            };
+            if f == &default_context_name {
+                format!("{name} (default)")
+            } else {
+                name
             }
         })
         .collect::<Vec<String>>()
         .join("\n");
 
+    let encrypted = contexts
+        .iter()
+        .filter(|f| dir.is_context_encrypted(f).unwrap_or_default())
+        .collect::<Vec<_>>();
+
     let mut map = HashMap::new();
     map.insert("contexts".to_string(), json!(contexts));
     map.insert("default".to_string(), json!(default_context_name));
+    map.insert("encrypted".to_string(), json!(encrypted));
 
     Ok(CommandOutput::new(
         format!(
@@ -182,15 +280,8 @@ fn handle_new(cmd: NewCommand) -> Result<CommandOutput> {
         WashContext::named(cmd.name.unwrap())
     };
 
-    let options = sanitize_filename::Options {
-        truncate: true,
-        windows: true,
-        replacement: "_",
-    };
-
     // Ensure filename doesn't include uphill/downhill\ slashes, or reserved prefixes
-    let sanitized = sanitize_filename::sanitize_with_options(&new_context.name, options);
-    new_context.name = sanitized;
+    new_context.name = sanitize_context_name(&new_context.name);
     dir.save_context(&new_context)?;
     Ok(CommandOutput::from(format!(
         "Created context {} with default values",
@@ -198,6 +289,157 @@ fn handle_new(cmd: NewCommand) -> Result<CommandOutput> {
     )))
 }
 
+/// Sanitizes a context name so that it is a valid filename
+fn sanitize_context_name(name: &str) -> String {
+    let options = sanitize_filename::Options {
+        truncate: true,
+        windows: true,
+        replacement: "_",
+    };
+    sanitize_filename::sanitize_with_options(name, options)
+}
+
+/// Handles exporting a context as a bundle, with its seeds and JWTs sealed with a passphrase if
+/// requested
+fn handle_export(cmd: ExportCommand) -> Result<CommandOutput> {
+    let dir = ContextDir::from_dir(cmd.directory)?;
+
+    let name = if let Some(name) = cmd.name {
+        name
+    } else if let Some(name) = select_context(&dir, "Select a context to export:")? {
+        name
+    } else {
+        bail!("no context selected");
+    };
+    let ctx = dir.load_context(&name)?;
+
+    let passphrase = if cmd.seal_secrets {
+        Some(bundle_passphrase(true)?)
+    } else {
+        None
+    };
+    let bundle = ContextBundle::new(
+        &ctx,
+        PinnedVersions {
+            wasmcloud: Some(cmd.wasmcloud_version),
+            wadm: Some(cmd.wadm_version),
+        },
+        passphrase.as_deref(),
+    )?;
+    std::fs::write(&cmd.bundle, bundle.to_vec()?)
+        .with_context(|| format!("failed to write bundle to `{}`", cmd.bundle.display()))?;
+
+    let mut map = HashMap::new();
+    map.insert("context".to_string(), json!(name));
+    map.insert("bundle".to_string(), json!(cmd.bundle));
+    map.insert("secrets_sealed".to_string(), json!(bundle.has_secrets()));
+    Ok(CommandOutput::new(
+        format!(
+            "Exported context {name} to {}{}",
+            cmd.bundle.display(),
+            if bundle.has_secrets() {
+                ", with its secrets sealed"
+            } else {
+                ", without its secrets"
+            }
+        ),
+        map,
+    ))
+}
+
+/// Handles importing a context from a bundle, prompting for the passphrase of its sealed secrets
+fn handle_import(cmd: ImportCommand) -> Result<CommandOutput> {
+    let dir = ContextDir::from_dir(cmd.directory)?;
+
+    let data = std::fs::read(&cmd.bundle)
+        .with_context(|| format!("failed to read bundle `{}`", cmd.bundle.display()))?;
+    let bundle = ContextBundle::from_slice(&data)?;
+    let versions = bundle.versions.clone();
+    let missing_env = bundle.missing_env();
+    let passphrase = if bundle.has_secrets() {
+        Some(bundle_passphrase(false)?)
+    } else {
+        None
+    };
+    let mut ctx = bundle.into_context(passphrase.as_deref())?;
+    if let Some(name) = cmd.name {
+        ctx.name = name;
+    }
+    ctx.name = sanitize_context_name(&ctx.name);
+    if !cmd.force && dir.get_context_path(&ctx.name)?.is_some() {
+        bail!(
+            "context {} already exists, use --name to import it under another name or --force to overwrite it",
+            ctx.name
+        );
+    }
+    dir.save_context(&ctx)?;
+
+    let mut text = format!("Imported context {}", ctx.name);
+    if let (Some(wasmcloud), Some(wadm)) = (&versions.wasmcloud, &versions.wadm) {
+        text.push_str(&format!(
+            "\nThe lattice runs wasmCloud {wasmcloud} and wadm {wadm}, use `wash up --wasmcloud-version {wasmcloud} --wadm-version {wadm}` to match it"
+        ));
+    }
+    if !missing_env.is_empty() {
+        text.push_str(&format!(
+            "\nSet the following environment variables to authenticate to the registries of the context: {}",
+            missing_env.iter().cloned().collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let mut map = HashMap::new();
+    map.insert("context".to_string(), json!(ctx.name));
+    map.insert("versions".to_string(), json!(versions));
+    map.insert("missing_env".to_string(), json!(missing_env));
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Handles encrypting the sensitive fields of one or all contexts
+fn handle_encrypt(cmd: EncryptCommand) -> Result<CommandOutput> {
+    let dir = ContextDir::from_dir(cmd.directory)?;
+
+    let names = if cmd.all {
+        dir.list_contexts()?
+    } else if let Some(name) = cmd.name {
+        vec![name]
+    } else if let Some(name) = select_context(&dir, "Select a context to encrypt:")? {
+        vec![name]
+    } else {
+        bail!("no context selected");
+    };
+
+    let key_source = if cmd.passphrase {
+        KeySource::new_passphrase()?
+    } else if keychain_available() {
+        KeySource::Keychain
+    } else {
+        warn!("OS keychain is not available, deriving the encryption key from a passphrase");
+        KeySource::new_passphrase()?
+    };
+
+    let mut encrypted = Vec::new();
+    for name in names {
+        if dir.is_context_encrypted(&name)? {
+            continue;
+        }
+        let mut ctx = dir.load_context(&name)?;
+        ctx.encrypt(key_source.clone())?;
+        dir.save_context(&ctx)?;
+        encrypted.push(name);
+    }
+
+    let mut map = HashMap::new();
+    map.insert("encrypted".to_string(), json!(encrypted));
+    Ok(CommandOutput::new(
+        if encrypted.is_empty() {
+            "No context to encrypt, selected contexts are already encrypted".to_string()
+        } else {
+            format!("Encrypted contexts: {}", encrypted.join(", "))
+        },
+        map,
+    ))
+}
+
 /// Handles editing a context by opening the JSON file in the user's text editor of choice
 fn handle_edit(cmd: EditCommand) -> Result<CommandOutput> {
     let dir = ContextDir::from_dir(cmd.directory)?;
@@ -395,6 +637,8 @@ fn prompt_for_context() -> Result<WashContext> {
         rpc_credsfile: rpc_credsfile.map(PathBuf::from),
         rpc_tls_ca_file: rpc_tls_ca_file.map(PathBuf::from),
         rpc_timeout: rpc_timeout.parse()?,
+        registries: HashMap::new(),
+        sealed: None,
     })
 }
 
@@ -470,6 +714,25 @@ mod test {
             _ => panic!("ctx constructed incorrect command"),
         }
 
+        let cmd: Cmd = Parser::try_parse_from([
+            "ctx",
+            "encrypt",
+            "--all",
+            "--passphrase",
+            "--directory",
+            "./contexts",
+        ])
+        .unwrap();
+        match cmd.cmd {
+            CtxCommand::Encrypt(cmd) => {
+                assert_eq!(cmd.directory.unwrap(), PathBuf::from("./contexts"));
+                assert!(cmd.all);
+                assert!(cmd.passphrase);
+                assert_eq!(cmd.name, None);
+            }
+            _ => panic!("ctx constructed incorrect command"),
+        }
+
         let cmd: Cmd =
             Parser::try_parse_from(["ctx", "default", "host_config", "--directory", "./contexts"])
                 .unwrap();
@@ -480,5 +743,53 @@ mod test {
             }
             _ => panic!("ctx constructed incorrect command"),
         }
+
+        let cmd: Cmd = Parser::try_parse_from([
+            "ctx",
+            "export",
+            "team",
+            "--bundle",
+            "team-dev.washbundle",
+            "--seal-secrets",
+            "--wasmcloud-version",
+            "v1.0.0",
+            "--wadm-version",
+            "v0.12.0",
+            "--directory",
+            "./contexts",
+        ])
+        .unwrap();
+        match cmd.cmd {
+            CtxCommand::Export(cmd) => {
+                assert_eq!(cmd.directory.unwrap(), PathBuf::from("./contexts"));
+                assert_eq!(cmd.name.unwrap(), "team");
+                assert_eq!(cmd.bundle, PathBuf::from("team-dev.washbundle"));
+                assert!(cmd.seal_secrets);
+                assert_eq!(cmd.wasmcloud_version, "v1.0.0");
+                assert_eq!(cmd.wadm_version, "v0.12.0");
+            }
+            _ => panic!("ctx constructed incorrect command"),
+        }
+
+        let cmd: Cmd = Parser::try_parse_from([
+            "ctx",
+            "import",
+            "team-dev.washbundle",
+            "--name",
+            "other",
+            "--force",
+            "--directory",
+            "./contexts",
+        ])
+        .unwrap();
+        match cmd.cmd {
+            CtxCommand::Import(cmd) => {
+                assert_eq!(cmd.directory.unwrap(), PathBuf::from("./contexts"));
+                assert_eq!(cmd.bundle, PathBuf::from("team-dev.washbundle"));
+                assert_eq!(cmd.name.unwrap(), "other");
+                assert!(cmd.force);
+            }
+            _ => panic!("ctx constructed incorrect command"),
+        }
     }
 }
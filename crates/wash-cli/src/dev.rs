@@ -1,26 +1,45 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::{path::PathBuf, sync::Arc};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
-use anyhow::{bail, Context, Result};
-use clap::Parser;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
 use console::style;
-use notify::{event::EventKind, Event as NotifyEvent, RecursiveMode, Watcher};
+use futures::StreamExt;
+use notify::{event::EventKind, Event as NotifyEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::task::JoinHandle;
 use tokio::time::{timeout, Duration};
 use tokio::{select, sync::mpsc};
 use wash_lib::{
-    build::{build_project, SignConfig},
-    cli::dev::run_dev_loop,
-    cli::{sanitize_component_id, CommandOutput},
+    build::{build_project, processes::isolate_build_processes, SignConfig},
+    cli::dev::{
+        append_dev_metrics, component_interfaces, deploy_order, dev_build_id, dev_failure_reason,
+        dev_http_address, dev_provider_links, format_dev_iteration, infer_links, interface_changes,
+        last_panic_message, load_dev_metrics, missing_link_hint, publish_crash_loop_event,
+        publish_missing_link_event, put_dev_env_config, put_dev_provider_config,
+        resolve_companions, resolve_env_files, run_dev_loop, stale_links, summarize_dev_metrics,
+        swap_dev_provider, unlinked_interface, ComponentInterfaces, CrashLoopDetector,
+        DevIterationMetrics, InterfaceChange, DEV_CRASH_LOOP_THRESHOLD, DEV_CRASH_LOOP_WINDOW,
+        DEV_ENV_CONFIG_NAME, DEV_FAILURE_EVENTS, DEV_METRICS_PATH, DEV_PROVIDER_CONFIG_NAME,
+    },
+    cli::dev_session::{
+        read_dev_session, AttachedDevSession, DevSession, DevSessionChannel, DevSessionEvent,
+        DevSessionMessage, DevSessionState,
+    },
+    cli::{sanitize_component_id, tunnel::Tunnel, CommandOutput},
     component::{scale_component, ScaleComponentArgs},
-    config::{downloads_dir, WASMCLOUD_PID_FILE},
+    config::{downloads_dir, DEFAULT_LATTICE, WASMCLOUD_PID_FILE},
     generate::emoji,
-    id::{ModuleId, ServerId},
-    parser::get_config,
+    id::ServerId,
+    parser::{get_config, ProjectConfig, TunnelConfig, TypeConfig},
 };
-use wasmcloud_control_interface::Host;
+use wasmcloud_control_interface::{Client as CtlClient, Host, InterfaceLinkDefinition};
+use wasmcloud_core::rpc::provider_log_subject;
+use wasmcloud_provider_sdk::ProviderLogEvent;
 
 use crate::{
     down::{handle_down, DownCommand},
@@ -66,6 +85,84 @@ pub struct DevCommand {
         help = "Run the wasmCloud host in a subprocess (rather than detached mode)"
     )]
     pub use_host_subprocess: bool,
+
+    /// Dotenv-formatted file(s) whose values are exposed to the component as named config (`dev-env`).
+    /// Values are refreshed whenever the file changes, without rebuilding the component.
+    #[clap(long = "env-file", env = "WASH_DEV_ENV_FILE", value_delimiter = ',')]
+    pub env_files: Vec<PathBuf>,
+
+    /// Number of failures of a component within the crash loop window after which it is considered
+    /// crash-looping, which pauses its redeploys until its next successful build
+    #[clap(
+        long = "crash-loop-threshold",
+        env = "WASH_DEV_CRASH_LOOP_THRESHOLD",
+        default_value_t = DEV_CRASH_LOOP_THRESHOLD
+    )]
+    pub crash_loop_threshold: usize,
+
+    /// Window over which the failures of a component are counted to detect crash loops, in seconds
+    #[clap(
+        long = "crash-loop-window-secs",
+        env = "WASH_DEV_CRASH_LOOP_WINDOW_SECS",
+        default_value_t = DEV_CRASH_LOOP_WINDOW.as_secs()
+    )]
+    pub crash_loop_window_secs: u64,
+
+    /// Share the HTTP server of the component through the tunnel configured in the `[dev.tunnel]`
+    /// section of wasmcloud.toml, printing its public URL and a QR code to open it on a phone
+    #[clap(long = "share", env = "WASH_DEV_SHARE", default_value = "false")]
+    pub share: bool,
+
+    /// Local address of the HTTP server to share, instead of the address configured on the
+    /// `wasi:http/incoming-handler` link targeting the component
+    #[clap(
+        long = "share-address",
+        env = "WASH_DEV_SHARE_ADDRESS",
+        requires = "share"
+    )]
+    pub share_address: Option<SocketAddr>,
+
+    /// Attach to the dev session already running for the project, streaming its logs (or the
+    /// channel selected with --events or --status) until it ends, instead of starting a dev loop
+    #[clap(long = "attach")]
+    pub attach: bool,
+
+    /// Stream the log lines of the attached dev session (the default)
+    #[clap(long = "logs", requires = "attach", conflicts_with_all = ["events", "status"])]
+    pub logs: bool,
+
+    /// Stream the events of the attached dev session as JSON lines
+    #[clap(long = "events", requires = "attach", conflicts_with = "status")]
+    pub events: bool,
+
+    /// Stream the status of the attached dev session as JSON lines, starting with its current
+    /// status
+    #[clap(long = "status", requires = "attach")]
+    pub status: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<DevSubcommand>,
+}
+
+impl DevCommand {
+    /// Channel of the dev session followed with `--attach`
+    fn attach_channel(&self) -> DevSessionChannel {
+        if self.events {
+            DevSessionChannel::Events
+        } else if self.status {
+            DevSessionChannel::Status
+        } else {
+            DevSessionChannel::Logs
+        }
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum DevSubcommand {
+    /// Summarize the duration of the phases of previous `wash dev` iterations of the project,
+    /// recorded in `.wash/dev-metrics.ndjson`
+    #[clap(name = "stats")]
+    Stats,
 }
 
 /// Utility struct for holding a wasmCloud host subprocess.
@@ -92,6 +189,21 @@ pub async fn handle_command(
     cmd: DevCommand,
     output_kind: wash_lib::cli::OutputKind,
 ) -> Result<CommandOutput> {
+    let project_path = match cmd.code_dir.clone() {
+        Some(code_dir) => code_dir,
+        None => std::env::current_dir()?,
+    };
+    if let Some(DevSubcommand::Stats) = cmd.command {
+        return dev_stats(&project_path.join(DEV_METRICS_PATH)).await;
+    }
+    if cmd.attach {
+        return attach_dev_session(&project_path, cmd.attach_channel()).await;
+    }
+
+    // Only one dev loop runs per project, other terminals attach to it. The session ends, and its
+    // lock is removed, when this function returns
+    let session = DevSession::start(&project_path, is_process_running).await?;
+
     // Check if host is running
     let pid_file = downloads_dir()?.join(WASMCLOUD_PID_FILE);
     let existing_instance = tokio::fs::metadata(pid_file).await.is_ok();
@@ -163,6 +275,11 @@ pub async fn handle_command(
     }
 
     // Connect to the wasmcloud instance
+    let lattice = cmd
+        .wasmcloud_opts
+        .lattice
+        .clone()
+        .unwrap_or_else(|| DEFAULT_LATTICE.to_string());
     let ctl_client = Arc::new(
         cmd.wasmcloud_opts
             .into_ctl_client(None)
@@ -251,32 +368,876 @@ pub async fn handle_command(
         }
     };
 
-    // Resolve project configuration from the current path
-    let current_dir = std::env::current_dir()?;
-    let project_path = cmd.code_dir.unwrap_or(current_dir);
+    // Resolve project configuration from the project path
     let project_cfg = get_config(Some(project_path.clone()), Some(true))?;
 
-    // Build the project (equivalent to `wash build`)
+    // Expose values from env files (`[dev]` section first, so that the CLI takes precedence) as named config
+    let env_files = resolve_env_files(
+        &project_cfg.common.path,
+        &[project_cfg.dev.env_files.clone(), cmd.env_files].concat(),
+    );
+    let companion_paths = resolve_companions(&project_cfg.common.path, &project_cfg.dev.companions);
+
+    // Handle Ctrl + c and SIGTERM with Tokio. Build processes run in their own process group, so
+    // that the signal is forwarded to them and they are awaited rather than orphaned
+    isolate_build_processes();
+    let stopping = Arc::new(AtomicBool::new(false));
+    let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
+    tokio::spawn({
+        let stopping = stopping.clone();
+        async move {
+            wait_for_stop_signal(&stopping).await?;
+            stop_tx
+                .send(())
+                .await
+                .context("failed to send stop signal after receiving Ctrl + c")?;
+            Result::<_, anyhow::Error>::Ok(())
+        }
+    });
+
+    // Build the project and its companions (equivalent to `wash build`)
     let sign_cfg: Option<SignConfig> = Some(SignConfig {
         keys_directory: None,
         issuer: None,
         subject: None,
         disable_keygen: false,
     });
+    let mut components =
+        match build_dev_component(project_path.clone(), project_cfg, sign_cfg.as_ref()).await {
+            Ok(component) => vec![component],
+            // The build was interrupted by the signal stopping the devloop
+            Err(_) if stopping.load(Ordering::SeqCst) => {
+                return stop_dev(cmd.leave_host_running, host_subprocess, output_kind).await;
+            }
+            Err(e) => return Err(e),
+        };
+    for companion_path in companion_paths {
+        let companion_cfg =
+            get_config(Some(companion_path.clone()), Some(true)).with_context(|| {
+                format!(
+                    "failed to load companion project [{}]",
+                    companion_path.display()
+                )
+            })?;
+        if !matches!(companion_cfg.project_type, TypeConfig::Component(_)) {
+            bail!(
+                "companion project [{}] is not a component",
+                companion_path.display()
+            );
+        }
+        match build_dev_component(companion_path, companion_cfg, sign_cfg.as_ref()).await {
+            Ok(component) => components.push(component),
+            Err(_) if stopping.load(Ordering::SeqCst) => {
+                return stop_dev(cmd.leave_host_running, host_subprocess, output_kind).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let config = if env_files.is_empty() {
+        vec![]
+    } else {
+        let count = put_dev_env_config(&ctl_client, &env_files)
+            .await
+            .context("failed to put config from env files")?;
+        eprintln!(
+            "{} {}",
+            emoji::GREEN_CHECK,
+            style(format!(
+                "Loaded {count} value(s) from env file(s) into config [{DEV_ENV_CONFIG_NAME}]"
+            ))
+            .bold(),
+        );
+        vec![DEV_ENV_CONFIG_NAME.to_string()]
+    };
+
+    // Providers forward their logs to the lattice, so that they can be printed along with ours
+    let provider_config = if components[0].is_provider() {
+        put_dev_provider_config(&ctl_client)
+            .await
+            .context("failed to put dev provider config")?;
+        print_dev_provider_logs(&ctl_client, &lattice, &components[0]).await?;
+        [config.clone(), vec![DEV_PROVIDER_CONFIG_NAME.to_string()]].concat()
+    } else {
+        vec![]
+    };
+
+    // Link components before deploying them, so that they can use the links as soon as they start
+    let links = link_dev_components(&ctl_client, &components).await?;
+
+    // Scale each component to one max replica, deploying dependencies before their dependents
+    let component_ids: Vec<&str> = components.iter().map(|c| c.component_id.as_str()).collect();
+    for idx in deploy_order(&component_ids, &links) {
+        let component = &components[idx];
+        if components.len() > 1 {
+            eprintln!(
+                "{} {}",
+                emoji::WRENCH,
+                style(format!("deploying [{}]...", component.name)).bold(),
+            );
+        }
+        if component.is_provider() {
+            swap_dev_provider(
+                &ctl_client,
+                &host.id,
+                &component.component_id,
+                &component.component_ref,
+                &dev_build_id(&component.artifact_path).await?,
+                provider_config.clone(),
+            )
+            .await
+            .with_context(|| format!("failed to start provider [{}]", component.name))?;
+            continue;
+        }
+        // Env file config is only exposed to the project itself
+        let config = if idx == 0 { config.clone() } else { vec![] };
+        scale_dev_component(&ctl_client, &host.id, component, config, 1).await?;
+    }
+    link_dev_provider(&ctl_client, &components).await?;
+
+    // The tunnel is opened once the project is deployed, and re-pointed after later iterations
+    let mut share = cmd.share.then(|| {
+        DevShare::new(
+            components[0].project_cfg.dev.tunnel.clone(),
+            cmd.share_address,
+        )
+    });
+    if let Some(share) = share.as_mut() {
+        share
+            .refresh(&ctl_client, &components[0].component_id)
+            .await;
+    }
+
+    // Components failing repeatedly are paused until their next successful build
+    let (failure_tx, mut failure_rx) = mpsc::channel::<(usize, String)>(16);
+    watch_dev_failures(&ctl_client, &components, failure_tx).await?;
+    let crash_loop_window = Duration::from_secs(cmd.crash_loop_window_secs);
+    let mut crash_loops: Vec<_> = components
+        .iter()
+        .map(|_| CrashLoopDetector::new(cmd.crash_loop_threshold, crash_loop_window))
+        .collect();
+    // Interfaces components called without a link are reported once per build
+    let mut missing_links: Vec<HashSet<String>> = vec![HashSet::new(); components.len()];
+    // Interfaces of the last build of each component, compared after rebuilds to reconcile links
+    // with changes to their WIT world
+    let mut interfaces = Vec::with_capacity(components.len());
+    for component in &components {
+        interfaces.push(if component.is_provider() {
+            None
+        } else {
+            read_dev_interfaces(component).await.ok()
+        });
+    }
+
+    let (reload_tx, mut reload_rx) = mpsc::channel::<(usize, Instant)>(components.len());
+    let (env_reload_tx, mut env_reload_rx) = mpsc::channel::<()>(1);
+
+    // Enable/disable watching to prevent having the output artifact trigger a rebuild
+    let pause_watch = Arc::new(AtomicBool::new(false));
+
+    // Spawn a file watcher per component to listen for changes and send the index of the changed
+    // component on reload_tx. Env files are only watched along with the project itself
+    let mut watchers = Vec::with_capacity(components.len());
+    for (idx, component) in components.iter().enumerate() {
+        // Companions nested in another component's directory are only rebuilt by their own watcher,
+        // and writing dev metrics does not trigger a rebuild
+        let ignored_paths = components
+            .iter()
+            .filter(|c| c.project_path != component.project_path)
+            .filter(|c| c.project_path.starts_with(&component.project_path))
+            .map(|c| c.project_path.clone())
+            .chain([component.project_path.join(".wash")])
+            .collect();
+        let (watched_env_files, env_reload_tx) = if idx == 0 {
+            (env_files.clone(), Some(env_reload_tx.clone()))
+        } else {
+            (vec![], None)
+        };
+        let mut watcher = watch_dev_component(
+            idx,
+            &component.project_path,
+            ignored_paths,
+            watched_env_files,
+            env_reload_tx,
+            pause_watch.clone(),
+            reload_tx.clone(),
+        )?;
+        if idx == 0 {
+            // Env files may live outside of the project, watch their directories (rather than the files
+            // themselves) so that editors replacing the file on save are handled
+            for dir in env_files.iter().filter_map(|f| f.parent()) {
+                if !dir.starts_with(&component.project_path) {
+                    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+                }
+            }
+        }
+        watchers.push(watcher);
+    }
+
+    // Durations of the phases of every iteration are recorded in the project
+    let metrics_path = components[0].project_path.join(DEV_METRICS_PATH);
+    let mut iteration = 0;
+
+    // Watch FS for changes and listen for Ctrl + C in tandem
+    session.set_state(DevSessionState::Watching);
+    eprintln!("👀 watching for file changes (press Ctrl+c to stop)...");
+    loop {
+        select! {
+            Some((idx, changed_at)) = reload_rx.recv() => {
+                // No new iteration starts once the devloop is stopping
+                if stopping.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let debounce = changed_at.elapsed();
+                let timestamp = SystemTime::now() - debounce;
+                pause_watch.store(true, Ordering::SeqCst);
+                let component = &components[idx];
+                if components.len() > 1 {
+                    eprintln!(
+                        "{} {}",
+                        emoji::CONSTRUCTION_BARRIER,
+                        style(format!("change detected in [{}], rebuilding...", component.name)).bold(),
+                    );
+                }
+                session.event(DevSessionEvent::BuildStarted { component: component.name.clone() });
+                let timings = match run_dev_loop(
+                    &component.project_cfg,
+                    &component.component_id,
+                    &component.component_ref,
+                    ServerId::from_str(&host.id)?,
+                    &ctl_client,
+                    sign_cfg.clone(),
+                    provider_config.clone(),
+                ).await {
+                    Ok(timings) => timings,
+                    // The build was interrupted by the signal stopping the devloop, which is
+                    // received next
+                    Err(_) if stopping.load(Ordering::SeqCst) => continue,
+                    Err(e) => {
+                        session.event(DevSessionEvent::BuildFailed {
+                            component: component.name.clone(),
+                            error: format!("{e:#}"),
+                        });
+                        return Err(e);
+                    }
+                };
+                missing_links[idx].clear();
+                // A successful build of a crash-looping component resumes its redeploys
+                if crash_loops[idx].is_crash_looping() {
+                    crash_loops[idx].reset();
+                    let config = if idx == 0 { config.clone() } else { vec![] };
+                    if component.is_provider() {
+                        eprintln!(
+                            "{} {}",
+                            emoji::GREEN_CHECK,
+                            style(format!("[{}] rebuilt, resuming redeploys", component.name)).bold(),
+                        );
+                    } else if let Err(e) = scale_dev_component(&ctl_client, &host.id, component, config, 1).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to redeploy [{}]: {e:#}", component.name)).bold(),
+                        );
+                    } else {
+                        eprintln!(
+                            "{} {}",
+                            emoji::GREEN_CHECK,
+                            style(format!("[{}] rebuilt and redeployed, resuming redeploys", component.name)).bold(),
+                        );
+                    }
+                }
+                let link_started = Instant::now();
+                if !component.is_provider() {
+                    if let Err(e) = reconcile_dev_interfaces(&ctl_client, &components, idx, &mut interfaces).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to reconcile links of [{}]: {e:#}", component.name)).bold(),
+                        );
+                    }
+                }
+                // Links of providers are put again, so that connected components keep working
+                if component.is_provider() {
+                    if let Err(e) = link_dev_provider(&ctl_client, &components).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to relink provider: {e:#}")).bold(),
+                        );
+                    }
+                }
+                // Imports and exports may have changed with the rebuild
+                if components.len() > 1 {
+                    if let Err(e) = link_dev_components(&ctl_client, &components).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to relink components: {e:#}")).bold(),
+                        );
+                    }
+                }
+                // The HTTP server may have moved to another port
+                if let Some(share) = share.as_mut() {
+                    share.refresh(&ctl_client, &components[0].component_id).await;
+                }
+                iteration += 1;
+                let metrics = DevIterationMetrics {
+                    iteration,
+                    component: component.name.clone(),
+                    timestamp_ms: timestamp
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |t| t.as_millis() as u64),
+                    debounce_ms: debounce.as_millis() as u64,
+                    build_ms: timings.build.as_millis() as u64,
+                    sign_ms: timings.sign.as_millis() as u64,
+                    deploy_ms: timings.deploy.as_millis() as u64,
+                    link_ms: link_started.elapsed().as_millis() as u64,
+                    total_ms: changed_at.elapsed().as_millis() as u64,
+                };
+                session.event(DevSessionEvent::BuildSucceeded {
+                    component: component.name.clone(),
+                    iteration,
+                    total_ms: metrics.total_ms,
+                });
+                if let Err(e) = record_dev_metrics(&metrics_path, &metrics).await {
+                    eprintln!(
+                        "{} {}",
+                        emoji::WARN,
+                        style(format!("failed to record dev metrics: {e:#}")).bold(),
+                    );
+                }
+                pause_watch.store(false, Ordering::SeqCst);
+                eprintln!("👀 watching for file changes (press Ctrl+c to stop)...");
+            },
+            Some((idx, reason)) = failure_rx.recv() => {
+                let component = &components[idx];
+                // Failures are expected until the component is fixed
+                if crash_loops[idx].is_crash_looping() {
+                    continue;
+                }
+                // Missing links are fixed by linking a target rather than by redeploying, so calls
+                // to unlinked interfaces do not count towards crash loops
+                if let Some(interface) = unlinked_interface(&reason) {
+                    if !missing_links[idx].insert(interface.to_string()) {
+                        continue;
+                    }
+                    let hint = missing_link_hint(
+                        interface,
+                        &component.name,
+                        &component.component_id,
+                        &components[0].project_cfg.dev.links,
+                    );
+                    print_missing_link(&component.name, interface, &hint);
+                    session.event(DevSessionEvent::MissingLink {
+                        component: component.name.clone(),
+                        interface: interface.to_string(),
+                        hint: hint.clone(),
+                    });
+                    if let Err(e) = publish_missing_link_event(
+                        &ctl_client,
+                        &lattice,
+                        &component.component_id,
+                        interface,
+                        &hint,
+                    ).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to publish missing link event: {e:#}")).bold(),
+                        );
+                    }
+                    continue;
+                }
+                eprintln!(
+                    "{} {}",
+                    emoji::WARN,
+                    style(format!("[{}] failed: {reason}", component.name)).bold(),
+                );
+                session.event(DevSessionEvent::ComponentFailed {
+                    component: component.name.clone(),
+                    reason: reason.clone(),
+                });
+                let Some(failures) = crash_loops[idx].record_failure(Instant::now()) else {
+                    continue;
+                };
+                // Panic messages are only written to the logs of the host
+                let reason = host_panic_message().await.unwrap_or(reason);
+                if !component.is_provider() {
+                    let config = if idx == 0 { config.clone() } else { vec![] };
+                    if let Err(e) = scale_dev_component(&ctl_client, &host.id, component, config, 0).await {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style(format!("failed to stop [{}]: {e:#}", component.name)).bold(),
+                        );
+                    }
+                }
+                print_crash_loop_banner(&component.name, failures, crash_loop_window, &reason);
+                session.event(DevSessionEvent::CrashLoop {
+                    component: component.name.clone(),
+                    failures,
+                });
+                if let Err(e) = publish_crash_loop_event(
+                    &ctl_client,
+                    &lattice,
+                    &component.component_id,
+                    failures,
+                    &reason,
+                ).await {
+                    eprintln!(
+                        "{} {}",
+                        emoji::WARN,
+                        style(format!("failed to publish crash loop event: {e:#}")).bold(),
+                    );
+                }
+            },
+            _ = env_reload_rx.recv() => {
+                match put_dev_env_config(&ctl_client, &env_files).await {
+                    Ok(count) => {
+                        eprintln!(
+                            "{} {}",
+                            emoji::RECYCLE,
+                            style(format!("refreshed config [{DEV_ENV_CONFIG_NAME}] with {count} value(s) from env file(s)")).bold(),
+                        );
+                        session.event(DevSessionEvent::ConfigRefreshed { values: count });
+                    }
+                    Err(e) => eprintln!(
+                        "{} {}",
+                        emoji::WARN,
+                        style(format!("failed to refresh config from env file(s): {e:#}")).bold(),
+                    ),
+                }
+            },
+            _ = stop_rx.recv() => {
+                pause_watch.store(true, Ordering::SeqCst);
+                session.event(DevSessionEvent::Stopping);
+                drop(share.take());
+                break stop_dev(cmd.leave_host_running, host_subprocess, output_kind).await;
+            },
+        }
+    }
+}
+
+/// Whether the process with ID `pid` is running, used to detect stale dev session locks
+fn is_process_running(pid: u32) -> bool {
+    use sysinfo::{Pid, System, SystemExt};
+
+    System::new().refresh_process(Pid::from(pid as usize))
+}
+
+/// Handle `wash dev --attach`, streaming `channel` of the dev session running for the project at
+/// `project_path` until the session ends
+async fn attach_dev_session(
+    project_path: &Path,
+    channel: DevSessionChannel,
+) -> Result<CommandOutput> {
+    let descriptor = read_dev_session(project_path, is_process_running)
+        .await?
+        .with_context(|| {
+            format!(
+                "no dev session is running for [{}], start one with `wash dev`",
+                project_path.display()
+            )
+        })?;
+    let mut session = AttachedDevSession::connect(&descriptor, channel).await?;
+    eprintln!(
+        "👀 {}",
+        style(format!(
+            "attached to the dev session of [{}] (pid {})",
+            descriptor.project.display(),
+            descriptor.pid
+        ))
+        .bold(),
+    );
+    while let Some(message) = session.next().await? {
+        match message {
+            DevSessionMessage::Logs { line } => println!("{line}"),
+            DevSessionMessage::Events { event } => println!("{}", serde_json::to_string(&event)?),
+            DevSessionMessage::Status { status } => {
+                println!("{}", serde_json::to_string(&status)?);
+            }
+        }
+    }
+    Ok(CommandOutput::from_key_and_text(
+        "result",
+        "dev session ended",
+    ))
+}
+
+/// Stop the devloop once a stop signal was received, stopping the host unless `leave_host_running`
+async fn stop_dev(
+    leave_host_running: bool,
+    host_subprocess: Option<HostSubprocess>,
+    output_kind: wash_lib::cli::OutputKind,
+) -> Result<CommandOutput> {
+    eprintln!("🛑 received Ctrl + c, stopping devloop...");
+    if !leave_host_running {
+        eprintln!("⏳ stopping wasmCloud instance...");
+        handle_down(DownCommand::default(), output_kind)
+            .await
+            .context("down command failed")?;
+        if let Some(handle) = host_subprocess.and_then(HostSubprocess::into_inner) {
+            handle.await?;
+        }
+    }
+    Ok(CommandOutput::default())
+}
+
+/// Time given to build processes to exit once a stop signal is forwarded to them, after which
+/// they are killed
+const BUILD_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait for Ctrl + c (SIGINT) or SIGTERM, then forward the signal to the process groups of the
+/// running builds and wait for them to exit. `stopping` is set before the signal is forwarded,
+/// so that the interrupted builds are not reported as failures
+#[cfg(unix)]
+async fn wait_for_stop_signal(stopping: &AtomicBool) -> Result<()> {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+    use tokio::signal::unix::{signal, SignalKind};
+    use wash_lib::build::processes::running_builds;
+
+    let mut terminate =
+        signal(SignalKind::terminate()).context("failed to wait for SIGTERM signal")?;
+    let signal = select! {
+        res = tokio::signal::ctrl_c() => {
+            res.context("failed to wait for ctrl_c signal")?;
+            Signal::SIGINT
+        }
+        _ = terminate.recv() => Signal::SIGTERM,
+    };
+    stopping.store(true, Ordering::SeqCst);
+
+    let groups: Vec<_> = running_builds()
+        .into_iter()
+        .map(|pgid| Pid::from_raw(pgid as i32))
+        .collect();
+    if groups.is_empty() {
+        return Ok(());
+    }
+    eprintln!("⏳ stopping build...");
+    for pgid in &groups {
+        // The build may have exited in the meantime
+        let _ = killpg(*pgid, signal);
+    }
+    // A process group exists as long as any of its processes, e.g. `rustc` spawned by `cargo`
+    let exited = || async {
+        while groups.iter().any(|pgid| killpg(*pgid, None).is_ok()) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+    if timeout(BUILD_STOP_TIMEOUT, exited()).await.is_err() {
+        eprintln!(
+            "{} {}",
+            emoji::WARN,
+            style("build did not stop in time, killing it").bold(),
+        );
+        for pgid in &groups {
+            let _ = killpg(*pgid, Signal::SIGKILL);
+        }
+        let _ = timeout(Duration::from_secs(5), exited()).await;
+    }
+    Ok(())
+}
+
+/// Wait for Ctrl + c, which is delivered to the build processes as well
+#[cfg(target_family = "windows")]
+async fn wait_for_stop_signal(stopping: &AtomicBool) -> Result<()> {
+    tokio::signal::ctrl_c()
+        .await
+        .context("failed to wait for ctrl_c signal")?;
+    stopping.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Scale a component under development to `max_instances` on the host, annotated as deployed by
+/// `wash dev`. Stopping a component (scaling it to zero) does not wait for the host to do so.
+pub(crate) async fn scale_dev_component(
+    ctl_client: &CtlClient,
+    host_id: &str,
+    component: &DevComponent,
+    config: Vec<String>,
+    max_instances: u32,
+) -> Result<()> {
+    scale_component(ScaleComponentArgs {
+        client: ctl_client,
+        host_id,
+        component_id: &component.component_id,
+        component_ref: &component.component_ref,
+        max_instances,
+        annotations: Some(HashMap::from_iter(vec![(
+            "wash_dev".to_string(),
+            "true".to_string(),
+        )])),
+        config,
+        skip_wait: max_instances == 0,
+        timeout_ms: None,
+    })
+    .await?;
+    Ok(())
+}
+
+/// Send the reasons of the failures of the components under development reported by host events on
+/// `failure_tx`, along with the index of the failed component, for as long as `wash dev` runs
+async fn watch_dev_failures(
+    ctl_client: &CtlClient,
+    components: &[DevComponent],
+    failure_tx: mpsc::Sender<(usize, String)>,
+) -> Result<()> {
+    let mut events = ctl_client
+        .events_receiver(DEV_FAILURE_EVENTS.map(String::from).to_vec())
+        .await
+        .map_err(|e| anyhow!("failed to subscribe to host events: {e}"))?;
+    let ids: Vec<_> = components.iter().map(|c| c.component_id.clone()).collect();
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            for (idx, id) in ids.iter().enumerate() {
+                if let Some(reason) = dev_failure_reason(&event, id) {
+                    if failure_tx.send((idx, reason)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Returns the most recent panic message in the logs of a host started in detached mode, as
+/// components write panic messages to the stderr of the host rather than report them in events
+async fn host_panic_message() -> Option<String> {
+    let log_path = downloads_dir().ok()?.join("wasmcloud.log");
+    let log = tokio::fs::read_to_string(log_path).await.ok()?;
+    last_panic_message(&log)
+}
+
+/// Print a banner reporting that a component is crash-looping, with the reason of its most recent
+/// failure
+fn print_crash_loop_banner(name: &str, failures: usize, window: Duration, reason: &str) {
+    eprintln!();
     eprintln!(
         "{} {}",
-        emoji::CONSTRUCTION_BARRIER,
-        style("Starting project build").bold(),
+        emoji::ERROR,
+        style(format!(
+            "[{name}] failed {failures} times within {}s, redeploys are paused",
+            window.as_secs()
+        ))
+        .red()
+        .bold(),
+    );
+    eprintln!("    {} {reason}", style("most recent failure:").bold());
+    eprintln!(
+        "    {}",
+        style("component is crash-looping — fix and save to retry")
+            .red()
+            .bold()
+    );
+    eprintln!();
+}
+
+/// Print that the component named `name` called `interface` without a link, with a `hint` on how to
+/// fix it
+fn print_missing_link(name: &str, interface: &str, hint: &str) {
+    eprintln!(
+        "{} {}",
+        emoji::WARN,
+        style(format!(
+            "[{name}] called {interface} but no provider is linked — {hint}"
+        ))
+        .yellow()
+        .bold(),
+    );
+}
+
+/// Tunnel sharing the HTTP server of the project under development, see `wash dev --share`.
+/// Failing to open the tunnel is only reported, so that the dev loop keeps running
+struct DevShare {
+    config: Option<TunnelConfig>,
+    /// Local address set with `--share-address`, otherwise resolved from the links of the component
+    address: Option<SocketAddr>,
+    tunnel: Option<Tunnel>,
+    /// Whether the lack of a linked HTTP server was reported
+    reported_unlinked: bool,
+}
+
+impl DevShare {
+    fn new(config: Option<TunnelConfig>, address: Option<SocketAddr>) -> Self {
+        if config.is_none() {
+            eprintln!(
+                "{} {}",
+                emoji::WARN,
+                style("--share requires a [dev.tunnel] section in wasmcloud.toml, not sharing")
+                    .yellow()
+                    .bold(),
+            );
+        }
+        Self {
+            config,
+            address,
+            tunnel: None,
+            reported_unlinked: false,
+        }
+    }
+
+    /// Open the tunnel to the HTTP server of `component_id`, re-pointing it if the server moved
+    async fn refresh(&mut self, ctl_client: &CtlClient, component_id: &str) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        let local_addr = match self.address {
+            Some(addr) => addr,
+            None => match dev_http_address(ctl_client, component_id).await {
+                Ok(Some(addr)) => addr,
+                Ok(None) => {
+                    if !std::mem::replace(&mut self.reported_unlinked, true) {
+                        eprintln!(
+                            "{} {}",
+                            emoji::WARN,
+                            style("no HTTP server is linked to the component, link one or set --share-address to share it").yellow().bold(),
+                        );
+                    }
+                    return;
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} {}",
+                        emoji::WARN,
+                        style(format!(
+                            "failed to resolve the address of the HTTP server: {e:#}"
+                        ))
+                        .bold(),
+                    );
+                    return;
+                }
+            },
+        };
+        if self
+            .tunnel
+            .as_ref()
+            .is_some_and(|tunnel| tunnel.local_addr() == local_addr)
+        {
+            return;
+        }
+        // Close the previous tunnel first, as the new one may use the same remote port
+        if self.tunnel.take().is_some() {
+            eprintln!(
+                "{} {}",
+                emoji::RECYCLE,
+                style(format!(
+                    "HTTP server moved to [{local_addr}], re-pointing tunnel"
+                ))
+                .bold(),
+            );
+        }
+        match Tunnel::open(config, local_addr).await {
+            Ok(tunnel) => {
+                print_share(tunnel.public_url(), local_addr);
+                self.tunnel = Some(tunnel);
+            }
+            Err(e) => eprintln!(
+                "{} {}",
+                emoji::WARN,
+                style(format!("failed to open tunnel to [{local_addr}]: {e:#}")).bold(),
+            ),
+        }
+    }
+}
+
+/// Print the public URL of the tunnel to `local_addr`, along with a QR code to open it on a phone
+fn print_share(public_url: &str, local_addr: SocketAddr) {
+    eprintln!(
+        "{} {}",
+        emoji::SPARKLE,
+        style(format!("sharing [{local_addr}] at {public_url}"))
+            .green()
+            .bold(),
+    );
+    match qrcode::QrCode::new(public_url) {
+        Ok(code) => eprintln!(
+            "{}",
+            code.render::<qrcode::render::unicode::Dense1x2>()
+                .dark_color(qrcode::render::unicode::Dense1x2::Light)
+                .light_color(qrcode::render::unicode::Dense1x2::Dark)
+                .build()
+        ),
+        Err(e) => eprintln!(
+            "{} {}",
+            emoji::WARN,
+            style(format!("failed to render QR code: {e}")).bold(),
+        ),
+    }
+}
+
+/// Append the metrics of an iteration to the file at `path` and print a summary of it
+async fn record_dev_metrics(path: &Path, metrics: &DevIterationMetrics) -> Result<()> {
+    append_dev_metrics(path, metrics).await?;
+    let recorded = load_dev_metrics(path).await?;
+    if let Some(summary) = format_dev_iteration(&recorded) {
+        eprintln!("{} {}", emoji::HOURGLASS_FULL, style(summary).bold());
+    }
+    Ok(())
+}
+
+/// Handle `wash dev stats`, summarizing the metrics recorded in the file at `path`
+async fn dev_stats(path: &Path) -> Result<CommandOutput> {
+    let metrics = load_dev_metrics(path).await?;
+    let summary = summarize_dev_metrics(&metrics);
+    let mut text = format!(
+        "{} iteration(s) recorded in [{}]",
+        summary.count,
+        path.display()
     );
+    for (phase, stats) in &summary.phases {
+        text.push_str(&format!(
+            "\n  {phase:<8} p50 {:>6.1}s  p95 {:>6.1}s",
+            Duration::from_millis(stats.p50_ms).as_secs_f64(),
+            Duration::from_millis(stats.p95_ms).as_secs_f64(),
+        ));
+    }
+    let map = HashMap::from([
+        ("count".to_string(), serde_json::json!(summary.count)),
+        ("phases".to_string(), serde_json::json!(summary.phases)),
+    ]);
+    Ok(CommandOutput::new(text, map))
+}
+
+/// A component deployed by `wash dev`, either the project itself or one of its companions. The
+/// project itself may also be a provider.
+pub(crate) struct DevComponent {
+    /// Name of the project, used to tell components apart in console output
+    pub(crate) name: String,
+    project_path: PathBuf,
+    project_cfg: ProjectConfig,
+    artifact_path: PathBuf,
+    pub(crate) component_id: String,
+    component_ref: String,
+}
 
-    // Build the project
-    let artifact_path = build_project(&project_cfg, sign_cfg.as_ref())
+impl DevComponent {
+    fn is_provider(&self) -> bool {
+        matches!(self.project_cfg.project_type, TypeConfig::Provider(_))
+    }
+}
+
+/// Build a component project for `wash dev`
+pub(crate) async fn build_dev_component(
+    project_path: PathBuf,
+    project_cfg: ProjectConfig,
+    sign_cfg: Option<&SignConfig>,
+) -> Result<DevComponent> {
+    let name = project_cfg.common.name.clone();
+    eprintln!(
+        "{} {}",
+        emoji::CONSTRUCTION_BARRIER,
+        style(format!("Starting build of [{name}]")).bold(),
+    );
+    let artifact_path = build_project(&project_cfg, sign_cfg)
         .await
-        .context("failed to build project")?
+        .with_context(|| format!("failed to build [{name}]"))?
         .canonicalize()
         .context("failed to canonicalize path")?;
     eprintln!(
-        "✅ successfully built project at [{}]",
+        "✅ successfully built [{name}] at [{}]",
         artifact_path.display()
     );
 
@@ -285,65 +1246,311 @@ pub async fn handle_command(
     // Since the only restriction on component_id is that it must be unique, we can just use the artifact path as the component_id
     // to ensure uniqueness
     let component_id = sanitize_component_id(&artifact_path.display().to_string());
-
-    // Scale the component to one max replica
-    scale_component(ScaleComponentArgs {
-        client: &ctl_client,
-        host_id: &host.id,
-        component_id: &component_id,
-        component_ref: &component_ref,
-        max_instances: 1,
-        annotations: Some(HashMap::from_iter(vec![(
-            "wash_dev".to_string(),
-            "true".to_string(),
-        )])),
-        config: vec![],
-        skip_wait: false,
-        timeout_ms: None,
+    Ok(DevComponent {
+        name,
+        project_path: project_path.canonicalize().unwrap_or(project_path),
+        project_cfg,
+        artifact_path,
+        component_id,
+        component_ref,
     })
-    .await?;
+}
 
-    // Set up a oneshot channel to remove
-    let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
-    let (reload_tx, mut reload_rx) = mpsc::channel::<()>(1);
+/// Link the components under development to each other based on their WIT imports and exports,
+/// returning the links that were put
+async fn link_dev_components(
+    ctl_client: &CtlClient,
+    components: &[DevComponent],
+) -> Result<Vec<InterfaceLinkDefinition>> {
+    // Providers are linked according to their `[[dev.links]]` instead
+    let components: Vec<_> = components.iter().filter(|c| !c.is_provider()).collect();
+    if components.len() < 2 {
+        return Ok(vec![]);
+    }
+    let mut interfaces = Vec::with_capacity(components.len());
+    for component in &components {
+        interfaces.push(read_dev_interfaces(component).await?);
+    }
+    let named: Vec<_> = components
+        .iter()
+        .map(|c| c.component_id.as_str())
+        .zip(interfaces.iter())
+        .collect();
+    let links = infer_links(&named);
 
-    // Handle Ctrl + c with Tokio
-    tokio::spawn(async move {
-        tokio::signal::ctrl_c()
+    let name_of = |id: &str| {
+        components
+            .iter()
+            .find(|c| c.component_id == id)
+            .map_or(id.to_string(), |c| c.name.clone())
+    };
+    for link in &links {
+        let ack = ctl_client
+            .put_link(link.clone())
+            .await
+            .map_err(|e| anyhow!("failed to put link: {e}"))?;
+        if !ack.success {
+            bail!("failed to put link: {}", ack.message);
+        }
+        eprintln!(
+            "{} {}",
+            emoji::WRENCH,
+            style(format!(
+                "linked [{}] -> [{}] on {}:{}/{}",
+                name_of(&link.source_id),
+                name_of(&link.target),
+                link.wit_namespace,
+                link.wit_package,
+                link.interfaces.join(",")
+            ))
+            .bold(),
+        );
+    }
+    Ok(links)
+}
+
+/// Read the WIT interfaces of the last build of a component
+async fn read_dev_interfaces(component: &DevComponent) -> Result<ComponentInterfaces> {
+    let wasm = tokio::fs::read(&component.artifact_path)
+        .await
+        .with_context(|| format!("failed to read built component [{}]", component.name))?;
+    component_interfaces(&wasm)
+        .with_context(|| format!("failed to read WIT interfaces of [{}]", component.name))
+}
+
+/// Reconcile the links of the component at `idx` with changes to its WIT world since its previous
+/// build, printing each change: links whose interfaces are no longer imported or exported are
+/// deleted, and added imports are reported with the companion they will be linked to or how to
+/// link them.
+async fn reconcile_dev_interfaces(
+    ctl_client: &CtlClient,
+    components: &[DevComponent],
+    idx: usize,
+    interfaces: &mut [Option<ComponentInterfaces>],
+) -> Result<()> {
+    let component = &components[idx];
+    let current = read_dev_interfaces(component).await?;
+    let Some(previous) = interfaces[idx].replace(current.clone()) else {
+        return Ok(());
+    };
+    let changes = interface_changes(&previous, &current);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    eprintln!(
+        "{} {}",
+        emoji::WRENCH,
+        style(format!("WIT world of [{}] changed", component.name)).bold(),
+    );
+
+    let links = ctl_client
+        .get_links()
+        .await
+        .map_err(|e| anyhow!("failed to get links: {e}"))?
+        .response
+        .unwrap_or_default();
+    let stale = stale_links(&component.component_id, &current, &links);
+    let name_of = |id: &str| {
+        components
+            .iter()
+            .find(|c| c.component_id == id)
+            .map_or(id.to_string(), |c| c.name.clone())
+    };
+    for change in &changes {
+        let detail = match change {
+            InterfaceChange::AddedImport(interface @ (namespace, package, iface)) => {
+                let linked = links.iter().find(|link| {
+                    link.source_id == component.component_id
+                        && link.wit_namespace == *namespace
+                        && link.wit_package == *package
+                        && link.interfaces.contains(iface)
+                });
+                let companion = components.iter().zip(interfaces.iter()).find(|(c, i)| {
+                    c.component_id != component.component_id
+                        && i.as_ref().is_some_and(|i| i.exports.contains(interface))
+                });
+                Some(match (linked, companion) {
+                    (Some(link), _) => format!("linked to [{}]", name_of(&link.target)),
+                    (None, Some((companion, _))) => format!("linking to [{}]", companion.name),
+                    (None, None) => missing_link_hint(
+                        &format!("{namespace}:{package}/{iface}"),
+                        &component.name,
+                        &component.component_id,
+                        &components[0].project_cfg.dev.links,
+                    ),
+                })
+            }
+            InterfaceChange::RemovedImport((namespace, package, iface)) => stale
+                .iter()
+                .find(|link| {
+                    link.source_id == component.component_id
+                        && link.wit_namespace == *namespace
+                        && link.wit_package == *package
+                        && link.interfaces.contains(iface)
+                })
+                .map(|link| format!("unlinking [{}]", name_of(&link.target))),
+            InterfaceChange::RemovedExport((namespace, package, iface)) => stale
+                .iter()
+                .find(|link| {
+                    link.target == component.component_id
+                        && link.wit_namespace == *namespace
+                        && link.wit_package == *package
+                        && link.interfaces.contains(iface)
+                })
+                .map(|link| format!("unlinking [{}]", name_of(&link.source_id))),
+            InterfaceChange::AddedExport(_) => None,
+        };
+        let line = match detail {
+            Some(detail) => format!("{change} → {detail}"),
+            None => change.to_string(),
+        };
+        eprintln!("{} {}", emoji::WRENCH, style(line).bold());
+    }
+
+    for link in stale {
+        let ack = ctl_client
+            .delete_link(
+                &link.source_id,
+                &link.name,
+                &link.wit_namespace,
+                &link.wit_package,
+            )
             .await
-            .context("failed to wait for ctrl_c signal")?;
-        stop_tx
-            .send(())
+            .map_err(|e| anyhow!("failed to delete link: {e}"))?;
+        if !ack.success {
+            bail!("failed to delete link: {}", ack.message);
+        }
+    }
+    Ok(())
+}
+
+/// Put the links declared in the `[[dev.links]]` section of the project, if it is a provider
+async fn link_dev_provider(ctl_client: &CtlClient, components: &[DevComponent]) -> Result<()> {
+    let provider = &components[0];
+    if !provider.is_provider() {
+        return Ok(());
+    }
+    let companions: Vec<_> = components[1..]
+        .iter()
+        .map(|c| (c.name.as_str(), c.component_id.as_str()))
+        .collect();
+    let links = dev_provider_links(
+        &provider.component_id,
+        &provider.project_cfg.dev.links,
+        &companions,
+    )?;
+    for link in links {
+        let description = format!(
+            "[{}] -> [{}] on {}:{}/{}",
+            link.source_id,
+            link.target,
+            link.wit_namespace,
+            link.wit_package,
+            link.interfaces.join(",")
+        );
+        let ack = ctl_client
+            .put_link(link)
             .await
-            .context("failed to send stop signal after receiving Ctrl + c")?;
-        Result::<_, anyhow::Error>::Ok(())
+            .map_err(|e| anyhow!("failed to put link {description}: {e}"))?;
+        if !ack.success {
+            bail!("failed to put link {description}: {}", ack.message);
+        }
+        eprintln!(
+            "{} {}",
+            emoji::WRENCH,
+            style(format!("linked {description}")).bold()
+        );
+    }
+    Ok(())
+}
+
+/// Print the logs the provider under development forwards to the lattice, for as long as `wash dev`
+/// runs
+async fn print_dev_provider_logs(
+    ctl_client: &CtlClient,
+    lattice: &str,
+    provider: &DevComponent,
+) -> Result<()> {
+    let mut subscriber = ctl_client
+        .nats_client()
+        .subscribe(provider_log_subject(lattice))
+        .await
+        .context("failed to subscribe to provider logs")?;
+    let name = provider.name.clone();
+    let provider_id = provider.component_id.clone();
+    tokio::spawn(async move {
+        while let Some(msg) = subscriber.next().await {
+            let Ok(events) = serde_json::from_slice::<Vec<ProviderLogEvent>>(&msg.payload) else {
+                continue;
+            };
+            for event in events.iter().filter(|e| e.provider_id == provider_id) {
+                let fields = event
+                    .fields
+                    .iter()
+                    .map(|(k, v)| format!(" {k}={v}"))
+                    .collect::<String>();
+                eprintln!(
+                    "{} {:>5} {}: {}{fields}",
+                    style(format!("[{name}]")).dim(),
+                    event.level,
+                    event.target,
+                    event.message,
+                );
+            }
+        }
     });
+    Ok(())
+}
 
-    // Enable/disable watching to prevent having the output artifact trigger a rebuild
-    let pause_watch = Arc::new(AtomicBool::new(false));
-    let watcher_paused = pause_watch.clone();
+/// Watch the project directory of a component for changes, sending the index of the component on
+/// `reload_tx` when it needs to be rebuilt and notifying `env_reload_tx` when an env file changes.
+fn watch_dev_component(
+    idx: usize,
+    project_path: &Path,
+    ignored_paths: Vec<PathBuf>,
+    watched_env_files: Vec<PathBuf>,
+    env_reload_tx: Option<mpsc::Sender<()>>,
+    watcher_paused: Arc<AtomicBool>,
+    reload_tx: mpsc::Sender<(usize, Instant)>,
+) -> Result<RecommendedWatcher> {
+    let watched_project_paths = [
+        project_path.to_path_buf(),
+        project_path
+            .canonicalize()
+            .unwrap_or_else(|_| project_path.to_path_buf()),
+    ];
 
-    // Spawn a file watcher to listen for changes and send on reload_tx
     let mut watcher = notify::recommended_watcher(move |res: _| match res {
         Ok(event) => match event {
             NotifyEvent {
-                kind: EventKind::Create(_),
-                ..
-            }
-            | NotifyEvent {
-                kind: EventKind::Modify(_),
-                ..
-            }
-            | NotifyEvent {
-                kind: EventKind::Remove(_),
+                kind: EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_),
+                paths,
                 ..
             } => {
+                // Changes to env files only refresh config, they don't require a rebuild. Other
+                // changes outside of the project (next to env files) are ignored
+                let mut rebuild = paths.is_empty();
+                for path in &paths {
+                    if is_env_file(path, &watched_env_files) {
+                        if let Some(tx) = &env_reload_tx {
+                            let _ = tx.try_send(());
+                        }
+                    } else if watched_project_paths.iter().any(|p| path.starts_with(p))
+                        && !ignored_paths.iter().any(|p| path.starts_with(p))
+                    {
+                        rebuild = true;
+                    }
+                }
+                if !rebuild {
+                    return;
+                }
+
                 // If watch has been paused for any reason, skip notifications
                 if watcher_paused.load(Ordering::SeqCst) {
                     return;
                 }
 
-                let _ = reload_tx.blocking_send(());
+                let _ = reload_tx.blocking_send((idx, Instant::now()));
             }
             _ => {}
         },
@@ -351,39 +1558,12 @@ pub async fn handle_command(
             eprintln!("[error] watch failed: {:?}", e);
         }
     })?;
-    watcher.watch(&project_path.clone(), RecursiveMode::Recursive)?;
-
-    // Watch FS for changes and listen for Ctrl + C in tandem
-    eprintln!("👀 watching for file changes (press Ctrl+c to stop)...");
-    loop {
-        select! {
-            _ = reload_rx.recv() => {
-                pause_watch.store(true, Ordering::SeqCst);
-                run_dev_loop(
-                    &project_cfg,
-                    ModuleId::from_str(&component_id)?,
-                    &component_ref,
-                    ServerId::from_str(&host.id)?,
-                    &ctl_client,
-                    sign_cfg.clone(),
-                ).await?;
-                pause_watch.store(false, Ordering::SeqCst);
-                eprintln!("👀 watching for file changes (press Ctrl+c to stop)...");
-            },
-            _ = stop_rx.recv() => {
-                pause_watch.store(true, Ordering::SeqCst);
-                eprintln!("🛑 received Ctrl + c, stopping devloop...");
-
-                if !cmd.leave_host_running {
-                    eprintln!("⏳ stopping wasmCloud instance...");
-                    handle_down(DownCommand::default(), output_kind).await.context("down command failed")?;
-                    if let Some(handle) = host_subprocess.and_then(|hs| hs.into_inner())  {
-                        handle.await?;
-                    }
-                }
+    watcher.watch(project_path, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
 
-                break Ok(CommandOutput::default());
-            },
-        }
-    }
+/// Whether a changed path is one of the watched env files
+fn is_env_file(path: &Path, env_files: &[PathBuf]) -> bool {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    env_files.iter().any(|f| *f == path)
 }
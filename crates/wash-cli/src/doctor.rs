@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+use serde_json::json;
+use sysinfo::{DiskExt, Pid, System, SystemExt};
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
+use tokio::process::Command;
+use wash_lib::cli::{CliConnectionOpts, CommandOutput};
+use wash_lib::config::{downloads_dir, WashConnectionOptions, WASMCLOUD_PID_FILE};
+use wash_lib::start::{
+    NATS_SERVER_BINARY, NATS_SERVER_PID, WADM_BINARY, WADM_PID, WASMCLOUD_HOST_BIN,
+};
+
+use crate::up::{
+    DEFAULT_NATS_PORT, DEFAULT_NATS_WEBSOCKET_PORT, NATS_SERVER_VERSION, WADM_VERSION,
+    WASMCLOUD_HOST_VERSION,
+};
+
+/// Maximum amount of time a single check (running a binary, connecting to NATS) may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Free space in the wash cache directory below which a warning is reported
+const MIN_FREE_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Known incompatible combinations of wadm and wasmCloud host versions, as
+/// (wadm version requirement, host version requirement, reason)
+const INCOMPATIBLE_VERSIONS: &[(&str, &str, &str)] = &[
+    (
+        "<0.11.0",
+        ">=1.0.0",
+        "wadm versions before v0.11.0 cannot manage wasmCloud 1.0 hosts",
+    ),
+    (
+        ">=0.11.0",
+        "<1.0.0",
+        "wadm v0.11.0 and later only support wasmCloud 1.0 hosts",
+    ),
+];
+
+#[derive(Parser, Debug, Clone)]
+pub struct DoctorCommand {
+    /// Remove stale PID files left behind by processes that are no longer running
+    #[clap(long = "fix")]
+    pub fix: bool,
+
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+}
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Error,
+    /// A problem was found and fixed because `--fix` was passed
+    Fixed,
+}
+
+impl CheckStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Error => "error",
+            Self::Fixed => "fixed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl DoctorCheck {
+    fn new(name: impl Into<String>, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+pub async fn handle_command(cmd: DoctorCommand) -> Result<CommandOutput> {
+    let install_dir = downloads_dir()?;
+
+    let mut checks = check_binaries(&install_dir).await;
+    checks.extend(check_pid_files(&install_dir, cmd.fix).await);
+    checks.extend(check_ports(&install_dir).await);
+    checks.push(check_nats_connectivity(cmd.opts).await);
+    checks.push(check_disk_space(&install_dir));
+    checks.extend(check_versions(&install_dir).await);
+
+    let healthy = !checks.iter().any(|c| c.status == CheckStatus::Error);
+    let mut map = HashMap::new();
+    map.insert("checks".to_string(), json!(checks));
+    map.insert("healthy".to_string(), json!(healthy));
+    Ok(CommandOutput::new(checks_table(&checks), map))
+}
+
+fn checks_table(checks: &[DoctorCheck]) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Check", 1, Alignment::Left),
+        TableCell::new_with_alignment("Status", 1, Alignment::Left),
+        TableCell::new_with_alignment("Details", 1, Alignment::Left),
+    ]));
+    for check in checks {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(&check.name, 1, Alignment::Left),
+            TableCell::new_with_alignment(check.status.as_str().to_uppercase(), 1, Alignment::Left),
+            TableCell::new_with_alignment(&check.message, 1, Alignment::Left),
+        ]));
+    }
+
+    table.render()
+}
+
+/// Run a binary with `--version`, returning the last word it printed (normally the version)
+async fn binary_version(bin: &Path) -> Option<String> {
+    let output = tokio::time::timeout(CHECK_TIMEOUT, Command::new(bin).arg("--version").output())
+        .await
+        .ok()?
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .map(|v| v.trim_start_matches('v').to_string())
+}
+
+/// Versions of the wasmCloud host installed in the wash cache
+async fn installed_host_versions(install_dir: &Path) -> Vec<String> {
+    let mut versions = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(install_dir).await else {
+        return versions;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if tokio::fs::try_exists(entry.path().join(WASMCLOUD_HOST_BIN))
+            .await
+            .is_ok_and(|exists| exists)
+        {
+            versions.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    versions.sort();
+    versions
+}
+
+async fn check_binaries(install_dir: &Path) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    for (name, bin, default_version) in [
+        (
+            "nats-server binary",
+            NATS_SERVER_BINARY,
+            NATS_SERVER_VERSION,
+        ),
+        ("wadm binary", WADM_BINARY, WADM_VERSION),
+    ] {
+        let path = install_dir.join(bin);
+        checks.push(if !path.exists() {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Warn,
+                format!("not installed, wash up will download {default_version}"),
+            )
+        } else if let Some(version) = binary_version(&path).await {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Ok,
+                format!("v{version} at {}", path.display()),
+            )
+        } else {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Error,
+                format!("{} exists but failed to run", path.display()),
+            )
+        });
+    }
+
+    let host_versions = installed_host_versions(install_dir).await;
+    checks.push(if host_versions.is_empty() {
+        DoctorCheck::new(
+            "wasmCloud host binary",
+            CheckStatus::Warn,
+            format!("not installed, wash up will download {WASMCLOUD_HOST_VERSION}"),
+        )
+    } else {
+        DoctorCheck::new(
+            "wasmCloud host binary",
+            CheckStatus::Ok,
+            format!("installed versions: {}", host_versions.join(", ")),
+        )
+    });
+    checks
+}
+
+/// Read the process ID from a PID file, which either contains just the PID or a JSON object
+/// with a `pid` field (as written by `wash up` for the host)
+async fn read_pid(path: &Path) -> Option<Pid> {
+    let contents = tokio::fs::read_to_string(path).await.ok()?;
+    let pid = match serde_json::from_str::<serde_json::Value>(&contents) {
+        Ok(serde_json::Value::Object(value)) => value.get("pid")?.to_string(),
+        _ => contents.trim().to_string(),
+    };
+    pid.parse().ok()
+}
+
+fn is_process_running(pid: Pid) -> bool {
+    let mut sys = System::new();
+    sys.refresh_process(pid)
+}
+
+/// PID files written by `wash up` that exist in the install directory
+async fn pid_files(install_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = [WASMCLOUD_PID_FILE, WADM_PID, NATS_SERVER_PID]
+        .iter()
+        .map(|file| install_dir.join(file))
+        .filter(|path| path.exists())
+        .collect();
+    // PID files of the nodes of a NATS cluster started with `wash up --nats-cluster-size`
+    if let Ok(mut entries) = tokio::fs::read_dir(install_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("nats-") && name.ends_with(".pid") {
+                files.push(entry.path());
+            }
+        }
+    }
+    files
+}
+
+async fn check_pid_files(install_dir: &Path, fix: bool) -> Vec<DoctorCheck> {
+    let files = pid_files(install_dir).await;
+    if files.is_empty() {
+        return vec![DoctorCheck::new(
+            "pid files",
+            CheckStatus::Ok,
+            "no PID files found",
+        )];
+    }
+
+    let mut checks = Vec::new();
+    for path in files {
+        let name = format!(
+            "pid file {}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        let problem = match read_pid(&path).await {
+            Some(pid) if is_process_running(pid) => {
+                checks.push(DoctorCheck::new(
+                    name,
+                    CheckStatus::Ok,
+                    format!("process {pid} is running"),
+                ));
+                continue;
+            }
+            Some(pid) => format!("process {pid} is not running"),
+            None => "file does not contain a valid PID".to_string(),
+        };
+        checks.push(if !fix {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Warn,
+                format!(
+                    "stale PID file {}: {problem}, run `wash doctor --fix` to remove it",
+                    path.display()
+                ),
+            )
+        } else if let Err(e) = tokio::fs::remove_file(&path).await {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Error,
+                format!("failed to remove stale PID file {}: {e}", path.display()),
+            )
+        } else {
+            DoctorCheck::new(
+                name,
+                CheckStatus::Fixed,
+                format!("removed stale PID file {}: {problem}", path.display()),
+            )
+        });
+    }
+    checks
+}
+
+/// Find the name and PID of the process listening on a TCP port, if `lsof` is available
+async fn port_owner(port: u16) -> Option<(String, String)> {
+    let lsof = which::which("lsof").ok()?;
+    let output = tokio::time::timeout(
+        CHECK_TIMEOUT,
+        Command::new(lsof)
+            .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fpc"])
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    // Output consists of lines prefixed with the field name, e.g. `p1234` and `cnats-server`
+    let output = String::from_utf8_lossy(&output.stdout);
+    let pid = output.lines().find_map(|l| l.strip_prefix('p'))?;
+    let command = output.lines().find_map(|l| l.strip_prefix('c'))?;
+    Some((command.to_string(), pid.to_string()))
+}
+
+async fn check_ports(install_dir: &Path) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    for (name, port) in [
+        ("ctl/rpc port", DEFAULT_NATS_PORT),
+        ("websocket port", DEFAULT_NATS_WEBSOCKET_PORT),
+    ] {
+        let name = format!("{name} {port}");
+        let Ok(port) = port.parse::<u16>() else {
+            continue;
+        };
+        let in_use = match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(_) => false,
+            Err(e) => e.kind() == ErrorKind::AddrInUse,
+        };
+        if !in_use {
+            checks.push(DoctorCheck::new(name, CheckStatus::Ok, "available"));
+            continue;
+        }
+
+        let wash_nats_running = match read_pid(&install_dir.join(NATS_SERVER_PID)).await {
+            Some(pid) => is_process_running(pid),
+            None => false,
+        };
+        checks.push(match port_owner(port).await {
+            Some((command, pid)) if command.contains("nats-server") => DoctorCheck::new(
+                name,
+                CheckStatus::Ok,
+                format!("in use by {command} (pid {pid})"),
+            ),
+            Some((command, pid)) => DoctorCheck::new(
+                name,
+                CheckStatus::Warn,
+                format!(
+                    "in use by {command} (pid {pid}), wash up will fail to start NATS on this port"
+                ),
+            ),
+            None if wash_nats_running => DoctorCheck::new(
+                name,
+                CheckStatus::Ok,
+                "in use by the NATS server started by wash up",
+            ),
+            None => DoctorCheck::new(
+                name,
+                CheckStatus::Warn,
+                "in use by an unknown process, wash up will fail to start NATS on this port",
+            ),
+        });
+    }
+    checks
+}
+
+async fn check_nats_connectivity(opts: CliConnectionOpts) -> DoctorCheck {
+    const NAME: &str = "nats connectivity";
+    let connection_opts = match WashConnectionOptions::try_from(opts) {
+        Ok(opts) => opts,
+        Err(e) => return DoctorCheck::new(NAME, CheckStatus::Error, format!("{e:#}")),
+    };
+    let url = format!(
+        "{}:{}",
+        connection_opts
+            .ctl_host
+            .clone()
+            .unwrap_or_else(|| connection_opts.ctx.ctl_host.clone()),
+        connection_opts
+            .ctl_port
+            .clone()
+            .unwrap_or_else(|| connection_opts.ctx.ctl_port.to_string())
+    );
+    let context = connection_opts.ctx.name.clone();
+    match tokio::time::timeout(CHECK_TIMEOUT, connection_opts.into_nats_client()).await {
+        Ok(Ok(_)) => DoctorCheck::new(
+            NAME,
+            CheckStatus::Ok,
+            format!("connected to {url} (context {context})"),
+        ),
+        Ok(Err(e)) => DoctorCheck::new(
+            NAME,
+            CheckStatus::Error,
+            format!("failed to connect to {url} (context {context}): {e:#}"),
+        ),
+        Err(_) => DoctorCheck::new(
+            NAME,
+            CheckStatus::Error,
+            format!("timed out connecting to {url} (context {context})"),
+        ),
+    }
+}
+
+fn check_disk_space(install_dir: &Path) -> DoctorCheck {
+    const NAME: &str = "disk space";
+    // The install directory may not exist yet, so fall back to its closest existing ancestor
+    let Some(dir) = install_dir
+        .ancestors()
+        .find_map(|dir| dir.canonicalize().ok())
+    else {
+        return DoctorCheck::new(NAME, CheckStatus::Warn, "failed to resolve cache directory");
+    };
+
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    let Some(disk) = sys
+        .disks()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return DoctorCheck::new(
+            NAME,
+            CheckStatus::Warn,
+            format!("failed to find the disk containing {}", dir.display()),
+        );
+    };
+
+    let available = disk.available_space();
+    let message = format!(
+        "{:.1} GiB available for {}",
+        available as f64 / (1024.0 * 1024.0 * 1024.0),
+        install_dir.display()
+    );
+    if available < MIN_FREE_DISK_SPACE_BYTES {
+        DoctorCheck::new(NAME, CheckStatus::Warn, message)
+    } else {
+        DoctorCheck::new(NAME, CheckStatus::Ok, message)
+    }
+}
+
+/// Returns the reason the given wadm and host versions are known to be incompatible, if they are
+fn version_incompatibility(wadm_version: &str, host_version: &str) -> Option<&'static str> {
+    let wadm = semver::Version::parse(wadm_version.trim_start_matches('v')).ok()?;
+    let host = semver::Version::parse(host_version.trim_start_matches('v')).ok()?;
+    INCOMPATIBLE_VERSIONS
+        .iter()
+        .find(|(wadm_req, host_req, _)| {
+            semver::VersionReq::parse(wadm_req).is_ok_and(|req| req.matches(&wadm))
+                && semver::VersionReq::parse(host_req).is_ok_and(|req| req.matches(&host))
+        })
+        .map(|(_, _, reason)| *reason)
+}
+
+async fn check_versions(install_dir: &Path) -> Vec<DoctorCheck> {
+    let mut combinations = vec![(
+        "default versions".to_string(),
+        WADM_VERSION.to_string(),
+        WASMCLOUD_HOST_VERSION.to_string(),
+    )];
+    if let Some(wadm_version) = binary_version(&install_dir.join(WADM_BINARY)).await {
+        for host_version in installed_host_versions(install_dir).await {
+            combinations.push((
+                format!("installed host {host_version}"),
+                wadm_version.clone(),
+                host_version,
+            ));
+        }
+    }
+
+    combinations
+        .into_iter()
+        .map(|(name, wadm_version, host_version)| {
+            let name = format!("version compatibility ({name})");
+            match version_incompatibility(&wadm_version, &host_version) {
+                Some(reason) => DoctorCheck::new(
+                    name,
+                    CheckStatus::Error,
+                    format!("wadm {wadm_version} and host {host_version}: {reason}"),
+                ),
+                None => DoctorCheck::new(
+                    name,
+                    CheckStatus::Ok,
+                    format!("wadm {wadm_version} is compatible with host {host_version}"),
+                ),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_doctor_comprehensive() {
+        let cmd: DoctorCommand = Parser::try_parse_from(["doctor"]).unwrap();
+        assert!(!cmd.fix);
+        let cmd: DoctorCommand =
+            Parser::try_parse_from(["doctor", "--fix", "--ctl-port", "4223"]).unwrap();
+        assert!(cmd.fix);
+        assert_eq!(cmd.opts.ctl_port, Some("4223".to_string()));
+    }
+
+    #[test]
+    fn test_version_incompatibility() {
+        assert!(version_incompatibility(WADM_VERSION, WASMCLOUD_HOST_VERSION).is_none());
+        assert!(version_incompatibility("v0.10.0", "v1.0.4").is_some());
+        assert!(version_incompatibility("0.12.1", "0.82.0").is_some());
+        // Unparseable versions are not reported as incompatible
+        assert!(version_incompatibility("latest", "v1.0.4").is_none());
+    }
+}
@@ -14,7 +14,10 @@ use wash_lib::config::{
     WASMCLOUD_PID_FILE,
 };
 use wash_lib::id::ServerId;
-use wash_lib::start::{nats_pid_path, NATS_SERVER_BINARY, WADM_PID};
+use wash_lib::start::{
+    nats_node_pid_path, nats_pid_path, read_nats_cluster_file, NATS_CLUSTER_FILE,
+    NATS_SERVER_BINARY, WADM_PID,
+};
 
 use crate::appearance::spinner::Spinner;
 use crate::up::{
@@ -242,13 +245,49 @@ pub async fn handle_down(cmd: DownCommand, output_kind: OutputKind) -> Result<Co
     Ok(CommandOutput::new(out_text, out_json))
 }
 
-/// Helper function to send the nats-server the stop command
-pub async fn stop_nats<P>(install_dir: P) -> Result<Output>
+/// Helper function to send the nats-server the stop command, stopping all nodes if a NATS cluster
+/// was started by `wash up`
+pub async fn stop_nats<P>(install_dir: P) -> Result<()>
 where
     P: AsRef<Path>,
 {
-    let bin_path = install_dir.as_ref().join(NATS_SERVER_BINARY);
-    let pid_file = nats_pid_path(install_dir);
+    let install_dir = install_dir.as_ref();
+    let Some(nodes) = read_nats_cluster_file(install_dir).await? else {
+        return stop_nats_server(install_dir, &nats_pid_path(install_dir))
+            .await
+            .map(|_| ());
+    };
+
+    let mut errors = Vec::new();
+    for node in nodes {
+        let pid_file = nats_node_pid_path(install_dir, node.port);
+        let stopped = if pid_file.is_file() {
+            stop_nats_server(install_dir, &pid_file).await.map(|_| ())
+        } else if let Some(pid) = node.pid {
+            // Fall back to killing the recorded process if the node didn't write its pid file
+            Command::new("kill")
+                .arg(pid.to_string())
+                .output()
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from)
+        } else {
+            Err(anyhow::anyhow!("no pid recorded"))
+        };
+        if let Err(e) = stopped {
+            errors.push(format!("node on port {}: {e}", node.port));
+        }
+    }
+    let _ = tokio::fs::remove_file(install_dir.join(NATS_CLUSTER_FILE)).await;
+    if !errors.is_empty() {
+        bail!("failed to stop NATS cluster nodes: {}", errors.join(", "));
+    }
+    Ok(())
+}
+
+/// Helper function to send the stop command to the nats-server whose pid is stored in `pid_file`
+async fn stop_nats_server(install_dir: &Path, pid_file: &Path) -> Result<Output> {
+    let bin_path = install_dir.join(NATS_SERVER_BINARY);
     let signal = if pid_file.is_file() {
         format!("stop={}", &pid_file.display())
     } else {
@@ -266,7 +305,7 @@ where
 
     // remove PID file
     if pid_file.is_file() {
-        let _ = tokio::fs::remove_file(&pid_file).await;
+        let _ = tokio::fs::remove_file(pid_file).await;
     }
     output
 }
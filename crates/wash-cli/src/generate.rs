@@ -17,7 +17,7 @@ pub enum NewCliCommand {
 
     /// Generate a new capability provider project
     #[clap(name = "provider")]
-    Provider(NewProjectArgs),
+    Provider(NewProviderArgs),
 }
 
 #[derive(Args, Debug, Default, Clone)]
@@ -64,11 +64,30 @@ pub struct NewProjectArgs {
     pub no_git_init: bool,
 }
 
+#[derive(Args, Debug, Default, Clone)]
+pub struct NewProviderArgs {
+    #[clap(flatten)]
+    pub project: NewProjectArgs,
+
+    /// Path to a WIT file or directory to generate the provider from, instead of a template. The
+    /// generated provider implements the WIT world with stubs for its exports
+    #[clap(long, conflicts_with_all = ["git", "path", "template_name"])]
+    pub wit: Option<PathBuf>,
+
+    /// WIT world to implement, required if the WIT passed with --wit contains multiple worlds
+    #[clap(long, requires = "wit")]
+    pub world: Option<String>,
+}
+
 impl From<NewCliCommand> for Project {
     fn from(cmd: NewCliCommand) -> Project {
-        let (args, kind) = match cmd {
-            NewCliCommand::Component(args) => (args, ProjectKind::Component),
-            NewCliCommand::Provider(args) => (args, ProjectKind::Provider),
+        let (args, kind, wit, world) = match cmd {
+            NewCliCommand::Component(args) => (args, ProjectKind::Component, None, None),
+            NewCliCommand::Provider(NewProviderArgs {
+                project,
+                wit,
+                world,
+            }) => (project, ProjectKind::Provider, wit, world),
         };
 
         Project {
@@ -83,6 +102,8 @@ impl From<NewCliCommand> for Project {
             git: args.git,
             subfolder: args.subfolder,
             branch: args.branch,
+            wit,
+            world,
         }
     }
 }
@@ -8,12 +8,17 @@ pub mod config;
 pub mod ctl;
 pub mod ctx;
 pub mod dev;
+pub mod doctor;
 pub mod down;
 pub mod drain;
 pub mod generate;
 pub mod keys;
+pub mod logs;
 pub mod par;
 pub mod plugin;
+pub mod test;
 pub mod ui;
 pub mod up;
 pub mod util;
+pub mod whoami;
+pub mod wit;
@@ -0,0 +1,777 @@
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use clap::Parser;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::config::downloads_dir;
+use wash_lib::id::ServerId;
+
+/// Name of the log file the wasmCloud host (and the providers it spawns) write to
+const WASMCLOUD_LOG_FILE: &str = "wasmcloud.log";
+/// Name of the log file wadm writes to
+const WADM_LOG_FILE: &str = "wadm.log";
+/// Name of the log file the NATS server writes to
+const NATS_LOG_FILE: &str = "nats.log";
+
+/// How often log files are polled for new lines when following
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Message logged by the host once it has started, used to determine which host wrote a log file
+const HOST_STARTED_MESSAGE: &str = "wasmCloud host started";
+
+#[derive(Parser, Debug, Clone)]
+pub struct LogsCommand {
+    /// Only show logs written by the host with the given ID
+    #[clap(long = "host-id")]
+    pub host_id: Option<ServerId>,
+
+    /// Only show logs from the capability provider with the given ID
+    #[clap(long = "provider")]
+    pub provider_id: Option<String>,
+
+    /// Keep the command running and print new log lines as they are written
+    #[clap(short = 'f', long = "follow")]
+    pub follow: bool,
+
+    /// Only show logs written within the given duration, e.g. `30s`, `10m`, `2h` or `1d`
+    #[clap(long = "since", value_parser = parse_since)]
+    pub since: Option<Duration>,
+
+    /// Only show logs at or above the given level
+    #[clap(long = "level", value_enum)]
+    pub level: Option<LogLevel>,
+}
+
+/// Severity of a log entry, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_uppercase().as_str() {
+            "TRACE" | "TRC" => Some(Self::Trace),
+            "DEBUG" | "DBG" => Some(Self::Debug),
+            "INFO" | "INF" => Some(Self::Info),
+            "WARN" | "WARNING" | "WRN" => Some(Self::Warn),
+            "ERROR" | "ERR" | "FTL" | "FATAL" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+/// The process (or workload running inside the host) that produced a log entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    Host,
+    Provider,
+    Component,
+    Wadm,
+    Nats,
+}
+
+impl std::fmt::Display for LogSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Host => write!(f, "host"),
+            Self::Provider => write!(f, "provider"),
+            Self::Component => write!(f, "component"),
+            Self::Wadm => write!(f, "wadm"),
+            Self::Nats => write!(f, "nats"),
+        }
+    }
+}
+
+/// A single, normalized log entry parsed from either a plaintext or a JSON log line
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub source: LogSource,
+    /// ID of the host, provider or component the entry is attributed to, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<LogLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source_id {
+            Some(id) => write!(f, "[{}:{id}]", self.source)?,
+            None => write!(f, "[{}]", self.source)?,
+        }
+        if let Some(timestamp) = self.timestamp {
+            write!(
+                f,
+                " {}",
+                timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+            )?;
+        }
+        if let Some(level) = self.level {
+            write!(f, " {:>5}", level.as_str())?;
+        }
+        if let Some(target) = &self.target {
+            write!(f, " {target}:")?;
+        }
+        write!(f, " {}", self.message)?;
+        for (key, value) in &self.fields {
+            match value {
+                Value::String(s) => write!(f, " {key}={s}")?,
+                other => write!(f, " {key}={other}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A log file managed by wash and the source its lines are attributed to by default
+#[derive(Debug, Clone)]
+struct LogFile {
+    path: PathBuf,
+    source: LogSource,
+    /// ID of the host that wrote this file, if it could be determined
+    host_id: Option<String>,
+}
+
+/// Filters applied to every parsed log entry
+#[derive(Debug, Clone, Default)]
+struct LogFilter {
+    host_id: Option<String>,
+    provider_id: Option<String>,
+    since: Option<DateTime<Utc>>,
+    level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    fn matches(&self, file: &LogFile, entry: &LogEntry) -> bool {
+        if let Some(host_id) = &self.host_id {
+            // Only the host log is host specific. If we couldn't tell which host wrote it, we
+            // assume it's the one requested since only a single host writes to it at a time.
+            if file.source != LogSource::Host
+                || file.host_id.as_ref().is_some_and(|id| id != host_id)
+            {
+                return false;
+            }
+        }
+        if let Some(provider_id) = &self.provider_id {
+            if entry.source != LogSource::Provider {
+                return false;
+            }
+            let mentions_provider = entry.source_id.as_deref() == Some(provider_id.as_str())
+                || entry.message.contains(provider_id.as_str());
+            if !mentions_provider {
+                return false;
+            }
+        }
+        if let (Some(since), Some(timestamp)) = (self.since, entry.timestamp) {
+            if timestamp < since {
+                return false;
+            }
+        }
+        if let (Some(min), Some(level)) = (self.level, entry.level) {
+            if level < min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub async fn handle_command(cmd: LogsCommand, output_kind: OutputKind) -> Result<CommandOutput> {
+    let install_dir = downloads_dir()?;
+    let files = discover_log_files(&install_dir).await?;
+    if files.is_empty() {
+        bail!(
+            "no log files found in {}, has wasmCloud been started with `wash up --detached`?",
+            install_dir.display()
+        );
+    }
+
+    let filter = LogFilter {
+        host_id: cmd.host_id.map(ServerId::into_string),
+        provider_id: cmd.provider_id,
+        since: cmd
+            .since
+            .map(|since| chrono::Duration::from_std(since).map(|since| Utc::now() - since))
+            .transpose()
+            .context("invalid --since duration")?,
+        level: cmd.level,
+    };
+
+    let mut entries = Vec::new();
+    let mut offsets = HashMap::new();
+    for file in &files {
+        let contents = tokio::fs::read(&file.path)
+            .await
+            .with_context(|| format!("failed to read log file {}", file.path.display()))?;
+        offsets.insert(
+            file.path.clone(),
+            FileOffset::new(&file.path, contents.len()).await,
+        );
+        let mut parser = LineParser::default();
+        entries.extend(
+            String::from_utf8_lossy(&contents)
+                .lines()
+                .filter_map(|line| parser.parse(file, line))
+                .filter(|entry| filter.matches(file, entry)),
+        );
+    }
+    // Merge the entries of all files chronologically. The sort is stable, so lines that share a
+    // timestamp (e.g. multi-line messages) stay in the order they were written in
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    if !cmd.follow {
+        let text = entries
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut map = HashMap::new();
+        map.insert("entries".to_string(), json!(entries));
+        return Ok(CommandOutput::new(text, map));
+    }
+
+    for entry in &entries {
+        print_entry(entry, output_kind);
+    }
+    follow_logs(files, offsets, filter, output_kind).await?;
+    Ok(CommandOutput::default())
+}
+
+/// Find the log files written by a wash-managed install. Providers inherit the output of the host
+/// that started them, so their (and components') logs are read out of the host log file
+async fn discover_log_files(install_dir: &Path) -> Result<Vec<LogFile>> {
+    let mut files = Vec::new();
+    for (name, source) in [
+        (WASMCLOUD_LOG_FILE, LogSource::Host),
+        (WADM_LOG_FILE, LogSource::Wadm),
+        (NATS_LOG_FILE, LogSource::Nats),
+    ] {
+        let path = install_dir.join(name);
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            continue;
+        }
+        let host_id = if source == LogSource::Host {
+            find_host_id(&path).await
+        } else {
+            None
+        };
+        files.push(LogFile {
+            path,
+            source,
+            host_id,
+        });
+    }
+    Ok(files)
+}
+
+/// Look for the "host started" line in a host log file to determine which host wrote it
+async fn find_host_id(path: &Path) -> Option<String> {
+    let contents = tokio::fs::read_to_string(path).await.ok()?;
+    contents
+        .lines()
+        .find(|line| line.contains(HOST_STARTED_MESSAGE))
+        .and_then(|line| {
+            HOST_ID_RE
+                .captures(line)
+                .and_then(|captures| captures.get(1))
+                .map(|id| id.as_str().to_string())
+        })
+}
+
+/// Tracks how much of a log file has been read, so that following can detect rotation
+#[derive(Debug, Clone)]
+struct FileOffset {
+    offset: u64,
+    /// Identity of the file on disk, used to detect the file being replaced by a new one
+    file_id: Option<u64>,
+    /// Bytes of a trailing line that hasn't been terminated yet
+    partial: Vec<u8>,
+}
+
+impl FileOffset {
+    async fn new(path: &Path, offset: usize) -> Self {
+        let file_id = tokio::fs::metadata(path)
+            .await
+            .ok()
+            .and_then(|m| file_id(&m));
+        FileOffset {
+            offset: offset as u64,
+            file_id,
+            partial: Vec::new(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Poll all log files for new lines until interrupted, re-opening files that were rotated or truncated
+async fn follow_logs(
+    files: Vec<LogFile>,
+    mut offsets: HashMap<PathBuf, FileOffset>,
+    filter: LogFilter,
+    output_kind: OutputKind,
+) -> Result<()> {
+    let mut parsers: HashMap<PathBuf, LineParser> = HashMap::new();
+    let mut interval = tokio::time::interval(FOLLOW_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = interval.tick() => {}
+        }
+
+        let mut new_entries = Vec::new();
+        for file in &files {
+            let Ok(metadata) = tokio::fs::metadata(&file.path).await else {
+                // The file may be in the middle of being rotated, try again on the next tick
+                continue;
+            };
+            let state = offsets
+                .entry(file.path.clone())
+                .or_insert_with(|| FileOffset {
+                    offset: 0,
+                    file_id: None,
+                    partial: Vec::new(),
+                });
+            let current_id = file_id(&metadata);
+            if metadata.len() < state.offset || current_id != state.file_id {
+                // The file was truncated or replaced, start reading it from the beginning
+                state.offset = 0;
+                state.file_id = current_id;
+                state.partial.clear();
+            }
+            if metadata.len() == state.offset {
+                continue;
+            }
+
+            let mut handle = tokio::fs::File::open(&file.path)
+                .await
+                .with_context(|| format!("failed to open log file {}", file.path.display()))?;
+            handle.seek(SeekFrom::Start(state.offset)).await?;
+            let mut buf = Vec::new();
+            let read = handle.read_to_end(&mut buf).await?;
+            state.offset += read as u64;
+            state.partial.extend_from_slice(&buf);
+
+            // Only parse complete lines, anything after the last newline is kept for the next tick
+            let Some(last_newline) = state.partial.iter().rposition(|b| *b == b'\n') else {
+                continue;
+            };
+            let complete: Vec<u8> = state.partial.drain(..=last_newline).collect();
+            let parser = parsers.entry(file.path.clone()).or_default();
+            new_entries.extend(
+                String::from_utf8_lossy(&complete)
+                    .lines()
+                    .filter_map(|line| parser.parse(file, line))
+                    .filter(|entry| filter.matches(file, entry)),
+            );
+        }
+
+        new_entries.sort_by_key(|entry| entry.timestamp);
+        for entry in &new_entries {
+            print_entry(entry, output_kind);
+        }
+    }
+}
+
+fn print_entry(entry: &LogEntry, output_kind: OutputKind) {
+    match output_kind {
+        OutputKind::Text | OutputKind::Wide => println!("{entry}"),
+        OutputKind::Json => match serde_json::to_string(entry) {
+            Ok(line) => println!("{line}"),
+            Err(e) => eprintln!("failed to serialize log entry: {e}"),
+        },
+    }
+}
+
+/// Parse a relative duration like `90s`, `10m`, `2h` or `1d`. A bare number is treated as seconds
+fn parse_since(since: &str) -> Result<Duration> {
+    let since = since.trim();
+    let (amount, unit) = since
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or((since, ""), |idx| since.split_at(idx));
+    let amount: u64 = amount
+        .parse()
+        .with_context(|| format!("invalid duration [{since}], expected a value like 10m"))?;
+    let seconds = match unit {
+        "" | "s" => amount,
+        "m" => amount * 60,
+        "h" => amount * 60 * 60,
+        "d" => amount * 60 * 60 * 24,
+        other => bail!("invalid duration unit [{other}], expected one of s, m, h or d"),
+    };
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Matches plaintext log lines produced by `tracing_subscriber`'s default formatter, e.g.
+/// `2024-05-21T18:01:02.123456Z  INFO wasmcloud_host::wasmbus: wasmCloud host started`
+static TRACING_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+(.*)$")
+        .expect("failed to compile tracing log line regex")
+});
+
+/// Matches log lines produced by the NATS server, e.g.
+/// `[12345] 2024/05/21 18:01:02.123456 [INF] Server is ready`
+static NATS_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[\d+\]\s+(\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?)\s+\[(\w{3})\]\s+(.*)$")
+        .expect("failed to compile NATS log line regex")
+});
+
+/// Matches a `tracing` module path used as the target of a log line
+static TARGET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*(::[A-Za-z0-9_]+)*$")
+        .expect("failed to compile log target regex")
+});
+
+/// Matches ANSI escape sequences, which are present when the host was started from a terminal
+static ANSI_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*m").expect("failed to compile ANSI escape regex"));
+
+static HOST_ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"host_id"?[=:]\s*"?(N[A-Z0-9]{55})"#).expect("failed to compile host ID regex")
+});
+
+static COMPONENT_ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\bcomponent_id=(?:"([^"]*)"|(\S+))"#)
+        .expect("failed to compile component ID regex")
+});
+
+static PROVIDER_ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\bprovider_id=(?:"([^"]*)"|(\S+))"#).expect("failed to compile provider ID regex")
+});
+
+/// Stateful parser for the lines of a single log file. Lines that don't carry a timestamp of
+/// their own (like multi-line panic messages) inherit the timestamp of the line before them
+#[derive(Debug, Default)]
+struct LineParser {
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl LineParser {
+    fn parse(&mut self, file: &LogFile, line: &str) -> Option<LogEntry> {
+        let line = ANSI_RE.replace_all(line, "");
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            return None;
+        }
+        let mut entry = if line.starts_with('{') {
+            parse_json_line(file.source, line)
+        } else {
+            None
+        }
+        .or_else(|| parse_tracing_line(file.source, line))
+        .or_else(|| parse_nats_line(file.source, line))
+        .unwrap_or_else(|| LogEntry {
+            timestamp: None,
+            source: file.source,
+            source_id: None,
+            level: None,
+            target: None,
+            message: line.to_string(),
+            fields: Map::new(),
+        });
+
+        if entry.timestamp.is_none() {
+            entry.timestamp = self.last_timestamp;
+        } else {
+            self.last_timestamp = entry.timestamp;
+        }
+        if entry.source == LogSource::Host && entry.source_id.is_none() {
+            entry.source_id = file.host_id.clone();
+        }
+        Some(entry)
+    }
+}
+
+/// Attribute a line from the host log file to the host, a provider, or a component
+fn attribute(
+    default: LogSource,
+    target: Option<&str>,
+    component_id: Option<String>,
+    provider_id: Option<String>,
+) -> (LogSource, Option<String>) {
+    if default != LogSource::Host {
+        return (default, None);
+    }
+    if component_id.is_some() {
+        return (LogSource::Component, component_id);
+    }
+    let from_provider_crate = target.is_some_and(|t| t.starts_with("wasmcloud_provider"));
+    if from_provider_crate || provider_id.is_some() {
+        return (LogSource::Provider, provider_id);
+    }
+    (LogSource::Host, None)
+}
+
+fn parse_json_line(default: LogSource, line: &str) -> Option<LogEntry> {
+    let Value::Object(mut obj) = serde_json::from_str::<Value>(line).ok()? else {
+        return None;
+    };
+    let timestamp = obj
+        .remove("timestamp")
+        .and_then(|ts| ts.as_str().and_then(parse_rfc3339));
+    let level = obj
+        .remove("level")
+        .and_then(|level| level.as_str().and_then(LogLevel::parse));
+    let target = obj
+        .remove("target")
+        .and_then(|target| target.as_str().map(ToString::to_string));
+    // `tracing_subscriber` nests event fields under `fields` unless they were flattened
+    let mut fields = match obj.remove("fields") {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    // Span information is noisy when re-emitted and not needed for attribution
+    obj.remove("span");
+    obj.remove("spans");
+    fields.extend(obj);
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let id_field = |fields: &Map<String, Value>, key: &str| {
+        fields
+            .get(key)
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+    };
+    let (source, source_id) = attribute(
+        default,
+        target.as_deref(),
+        id_field(&fields, "component_id"),
+        id_field(&fields, "provider_id"),
+    );
+    Some(LogEntry {
+        timestamp,
+        source,
+        source_id,
+        level,
+        target,
+        message,
+        fields,
+    })
+}
+
+fn parse_tracing_line(default: LogSource, line: &str) -> Option<LogEntry> {
+    let captures = TRACING_LINE_RE.captures(line)?;
+    let timestamp = parse_rfc3339(captures.get(1)?.as_str());
+    let level = LogLevel::parse(captures.get(2)?.as_str());
+    let (target, message) = split_target(captures.get(3)?.as_str());
+    let capture_id = |re: &Regex| {
+        re.captures(message)
+            .and_then(|c| c.get(1).or_else(|| c.get(2)))
+            .map(|id| id.as_str().to_string())
+    };
+    let (source, source_id) = attribute(
+        default,
+        target,
+        capture_id(&COMPONENT_ID_RE),
+        capture_id(&PROVIDER_ID_RE),
+    );
+    Some(LogEntry {
+        timestamp,
+        source,
+        source_id,
+        level,
+        target: target.map(ToString::to_string),
+        message: message.to_string(),
+        fields: Map::new(),
+    })
+}
+
+fn parse_nats_line(default: LogSource, line: &str) -> Option<LogEntry> {
+    let captures = NATS_LINE_RE.captures(line)?;
+    // NATS logs in local time without an offset
+    let timestamp =
+        NaiveDateTime::parse_from_str(captures.get(1)?.as_str(), "%Y/%m/%d %H:%M:%S%.f")
+            .ok()
+            .and_then(|ts| Local.from_local_datetime(&ts).single())
+            .map(|ts| ts.with_timezone(&Utc));
+    Some(LogEntry {
+        timestamp,
+        source: default,
+        source_id: None,
+        level: LogLevel::parse(captures.get(2)?.as_str()),
+        target: None,
+        message: captures.get(3)?.as_str().to_string(),
+        fields: Map::new(),
+    })
+}
+
+fn parse_rfc3339(ts: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(ts)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Split the `target: message` portion of a plaintext tracing line, skipping any leading span
+/// context like `handle_start_provider{provider_id="x"}:`
+fn split_target(rest: &str) -> (Option<&str>, &str) {
+    let mut remaining = rest;
+    while let Some((head, tail)) = remaining.split_once(": ") {
+        if head.ends_with('}') {
+            remaining = tail;
+            continue;
+        }
+        if TARGET_RE.is_match(head) {
+            return (Some(head), tail);
+        }
+        break;
+    }
+    (None, remaining)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn host_file() -> LogFile {
+        LogFile {
+            path: PathBuf::from(WASMCLOUD_LOG_FILE),
+            source: LogSource::Host,
+            host_id: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_since("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_since("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_since("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_since("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_since("10y").is_err());
+        assert!(parse_since("m").is_err());
+    }
+
+    #[test]
+    fn test_parse_lines_attribution() {
+        let file = host_file();
+        let mut parser = LineParser::default();
+
+        let host = parser
+            .parse(
+                &file,
+                "2024-05-21T18:01:02.123456Z  INFO wasmcloud_host::wasmbus: wasmCloud host started host_id=\"NBZHHCXD2RKCTWVCO6CIB63K2XHADYBF6TBAHNKVGZRV3HAJMRTHMBN6\"",
+            )
+            .expect("host line should parse");
+        assert_eq!(host.source, LogSource::Host);
+        assert_eq!(host.level, Some(LogLevel::Info));
+        assert_eq!(host.target.as_deref(), Some("wasmcloud_host::wasmbus"));
+
+        let provider = parser
+            .parse(
+                &file,
+                "2024-05-21T18:01:03.000000Z  WARN handle{x=1}: wasmcloud_provider_sdk::provider: Ignoring duplicate link put",
+            )
+            .expect("provider line should parse");
+        assert_eq!(provider.source, LogSource::Provider);
+        assert_eq!(provider.message, "Ignoring duplicate link put");
+
+        let component = parser
+            .parse(
+                &file,
+                r#"{"timestamp":"2024-05-21T18:01:04.000000Z","level":"INFO","fields":{"message":"hello","component_id":"echo"},"target":"wasmcloud_host::wasmbus::handler"}"#,
+            )
+            .expect("component line should parse");
+        assert_eq!(component.source, LogSource::Component);
+        assert_eq!(component.source_id.as_deref(), Some("echo"));
+        assert_eq!(component.message, "hello");
+
+        // Continuation lines inherit the previous timestamp
+        let continuation = parser
+            .parse(&file, "    at some/backtrace.rs:12")
+            .expect("continuation line should parse");
+        assert_eq!(continuation.timestamp, component.timestamp);
+    }
+
+    #[test]
+    fn test_parse_nats_line() {
+        let file = LogFile {
+            path: PathBuf::from(NATS_LOG_FILE),
+            source: LogSource::Nats,
+            host_id: None,
+        };
+        let entry = LineParser::default()
+            .parse(
+                &file,
+                "[4821] 2024/05/21 18:01:02.123456 [WRN] Slow consumer",
+            )
+            .expect("nats line should parse");
+        assert_eq!(entry.source, LogSource::Nats);
+        assert_eq!(entry.level, Some(LogLevel::Warn));
+        assert!(entry.timestamp.is_some());
+        assert_eq!(entry.message, "Slow consumer");
+    }
+
+    #[test]
+    fn test_filter() {
+        let file = host_file();
+        let mut parser = LineParser::default();
+        let entry = parser
+            .parse(
+                &file,
+                "2024-05-21T18:01:02.123456Z DEBUG wasmcloud_host::wasmbus: handling start provider provider_id=\"httpserver\"",
+            )
+            .unwrap();
+        assert_eq!(entry.source, LogSource::Provider);
+
+        let filter = LogFilter {
+            provider_id: Some("httpserver".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&file, &entry));
+        let filter = LogFilter {
+            provider_id: Some("other".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&file, &entry));
+        let filter = LogFilter {
+            level: Some(LogLevel::Info),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&file, &entry));
+    }
+}
@@ -10,6 +10,7 @@ use serde_json::json;
 use tracing::warn;
 use wash_lib::cli::par::{
     convert_error, create_provider_archive, detect_arch, insert_provider_binary,
+    validate_provider_archive, ParIssueSeverity, ParValidationLimits, DEFAULT_MAX_ARCHIVE_BYTES,
 };
 use wash_lib::cli::{extract_keypair, inspect, par, CommandOutput, OutputKind};
 
@@ -26,6 +27,9 @@ pub enum ParCliCommand {
     /// Insert a provider into a provider archive file
     #[clap(name = "insert")]
     Insert(InsertCommand),
+    /// Validate a provider archive file the way hosts load it
+    #[clap(name = "validate")]
+    Validate(ValidateCommand),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -42,7 +46,7 @@ pub struct CreateCommand {
     #[clap(long = "version")]
     version: Option<String>,
 
-    /// Optional path to a JSON schema describing the link definition specification for this provider.
+    /// Optional path to a JSON schema describing the configuration accepted by this provider. Used by `wash config put --validate-against` and `wash link put` to validate configuration.
     #[clap(
         short = 'j',
         long = "schema",
@@ -90,6 +94,12 @@ pub struct CreateCommand {
     #[clap(short = 'b', long = "binary")]
     binary: String,
 
+    /// Additional provider binary for another architecture, in format ARCH-OS=PATH (e.g.
+    /// aarch64-linux=./target/aarch64-unknown-linux-gnu/release/provider). Can be specified
+    /// multiple times
+    #[clap(long = "target-binary", value_parser = parse_target_binary)]
+    target_binaries: Vec<(String, PathBuf)>,
+
     /// File output destination path
     #[clap(long = "destination")]
     destination: Option<String>,
@@ -194,6 +204,34 @@ pub struct InsertCommand {
     disable_keygen: bool,
 }
 
+#[derive(Parser, Debug, Clone)]
+pub struct ValidateCommand {
+    /// Path to provider archive
+    #[clap(name = "archive")]
+    archive: PathBuf,
+
+    /// Architecture the archive must contain a binary for, in format ARCH-OS (e.g. x86_64-linux).
+    /// Defaults to the architecture of the current machine
+    #[clap(short = 'a', long = "arch", default_value_t = detect_arch())]
+    arch: String,
+
+    /// Largest size of the archive in bytes
+    #[clap(long = "max-archive-bytes", default_value_t = DEFAULT_MAX_ARCHIVE_BYTES)]
+    max_archive_bytes: u64,
+}
+
+/// Parses an additional binary of `par create`, in format ARCH-OS=PATH
+fn parse_target_binary(value: &str) -> Result<(String, PathBuf), String> {
+    match value.split_once('=') {
+        Some((target, path)) if !target.is_empty() && !path.is_empty() => {
+            Ok((target.to_string(), PathBuf::from(path)))
+        }
+        _ => Err(format!(
+            "invalid target binary `{value}`, expected format ARCH-OS=PATH"
+        )),
+    }
+}
+
 impl From<InspectCommand> for inspect::InspectCliCommand {
     fn from(cmd: InspectCommand) -> Self {
         inspect::InspectCliCommand {
@@ -235,6 +273,7 @@ pub async fn handle_command(
             inspect::handle_command(cmd, output_kind).await
         }
         ParCliCommand::Insert(cmd) => handle_insert(cmd, output_kind).await,
+        ParCliCommand::Validate(cmd) => handle_validate(cmd).await,
     }
 }
 
@@ -277,8 +316,14 @@ pub async fn handle_create(cmd: CreateCommand, output_kind: OutputKind) -> Resul
     };
 
     let compress = cmd.compress;
+    let target_binaries = cmd.target_binaries.clone();
     let mut par = create_provider_archive(cmd.into(), &lib)
         .context("failed to create provider archive with built provider")?;
+    for (target, path) in target_binaries {
+        let lib = std::fs::read(&path)
+            .with_context(|| format!("failed to load binary [{}]", path.display()))?;
+        par = insert_provider_binary(target, &lib, par).await?;
+    }
     par.write(&outfile, &issuer, &subject, compress)
         .await
         .map_err(|e| anyhow!("{e}"))
@@ -346,6 +391,46 @@ pub async fn handle_insert(cmd: InsertCommand, output_kind: OutputKind) -> Resul
     ))
 }
 
+/// Validates a provider archive, failing with every error found
+pub async fn handle_validate(cmd: ValidateCommand) -> Result<CommandOutput> {
+    let archive = std::fs::read(&cmd.archive).with_context(|| {
+        format!(
+            "failed to load provider archive [{}]",
+            cmd.archive.display()
+        )
+    })?;
+    let validation = validate_provider_archive(
+        &archive,
+        ParValidationLimits {
+            max_archive_bytes: cmd.max_archive_bytes,
+        },
+        &cmd.arch,
+    )
+    .await;
+    for warning in validation.messages(ParIssueSeverity::Warning) {
+        warn!("{warning}");
+    }
+    if !validation.valid {
+        bail!(
+            "provider archive [{}] is invalid:\n{}",
+            cmd.archive.display(),
+            validation.messages(ParIssueSeverity::Error).join("\n")
+        );
+    }
+
+    let mut map = HashMap::new();
+    map.insert("file".to_string(), json!(cmd.archive));
+    map.insert("validation".to_string(), json!(validation));
+    Ok(CommandOutput::new(
+        format!(
+            "Provider archive {} is valid for {}",
+            cmd.archive.display(),
+            validation.target
+        ),
+        map,
+    ))
+}
+
 /// Inspects the byte slice for a GZIP header, and returns true if the file is compressed
 fn is_compressed(input: &[u8]) -> Result<bool> {
     if input.len() < 2 {
@@ -395,6 +480,8 @@ mod test {
             SUBJECT,
             "--disable-keygen",
             "--compress",
+            "--target-binary",
+            "aarch64-testrunner=./testrunner-aarch64.so",
         ])
         .unwrap();
         match create_long.par {
@@ -409,12 +496,20 @@ mod test {
                 name,
                 arch,
                 binary,
+                target_binaries,
                 destination,
                 compress,
                 disable_keygen,
             }) => {
                 assert_eq!(arch, "x86_64-testrunner");
                 assert_eq!(binary, "./testrunner.so");
+                assert_eq!(
+                    target_binaries,
+                    vec![(
+                        "aarch64-testrunner".to_string(),
+                        PathBuf::from("./testrunner-aarch64.so")
+                    )]
+                );
                 assert_eq!(directory.unwrap(), PathBuf::from("./tests/fixtures"));
                 assert_eq!(issuer.unwrap(), ISSUER);
                 assert_eq!(subject.unwrap(), SUBJECT);
@@ -466,12 +561,14 @@ mod test {
                 name,
                 arch,
                 binary,
+                target_binaries,
                 destination,
                 compress,
                 disable_keygen,
             }) => {
                 assert_eq!(arch, "x86_64-testrunner");
                 assert_eq!(binary, "./testrunner.so");
+                assert!(target_binaries.is_empty());
                 assert_eq!(directory.unwrap(), PathBuf::from("./tests/fixtures"));
                 assert_eq!(issuer.unwrap(), ISSUER);
                 assert_eq!(subject.unwrap(), SUBJECT);
@@ -649,4 +746,45 @@ mod test {
             cmd => panic!("par inspect constructed incorrect command {cmd:?}"),
         }
     }
+
+    #[test]
+    fn test_par_validate_comprehensive() {
+        let validate: Cmd = clap::Parser::try_parse_from([
+            "par",
+            "validate",
+            "./test.par.gz",
+            "--arch",
+            "aarch64-linux",
+            "--max-archive-bytes",
+            "1024",
+        ])
+        .unwrap();
+        match validate.par {
+            ParCliCommand::Validate(ValidateCommand {
+                archive,
+                arch,
+                max_archive_bytes,
+            }) => {
+                assert_eq!(archive, PathBuf::from("./test.par.gz"));
+                assert_eq!(arch, "aarch64-linux");
+                assert_eq!(max_archive_bytes, 1024);
+            }
+            cmd => panic!("par validate constructed incorrect command {cmd:?}"),
+        }
+
+        assert!(clap::Parser::try_parse_from([
+            "par",
+            "create",
+            "-b",
+            "./testrunner.so",
+            "-n",
+            "CreateTest",
+            "-v",
+            "TestRunner",
+            "--target-binary",
+            "./testrunner-aarch64.so",
+        ])
+        .map(|_: Cmd| ())
+        .is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::Context;
@@ -8,12 +9,16 @@ use sha2::{Digest, Sha256};
 use tokio::io::AsyncWriteExt;
 use wash_lib::{
     cli::{registry::AuthOpts, CommandOutput, OutputKind},
+    plugin::{
+        external::{external_plugins, EXTERNAL_PLUGIN_PREFIX},
+        subcommand::PluginPermissions,
+    },
     registry::{pull_oci_artifact, OciPullOptions},
 };
 
 use crate::{
     appearance::spinner::Spinner,
-    ctl::plugins_table,
+    ctl::{external_plugins_table, plugins_table},
     util::{ensure_plugin_dir, load_plugins},
 };
 
@@ -23,9 +28,10 @@ pub enum PluginCommand {
     #[clap(name = "install")]
     Install(PluginInstallCommand),
     /// Uninstall a plugin
-    #[clap(name = "uninstall", alias = "delete", alias = "rm")]
+    #[clap(name = "uninstall", alias = "delete", alias = "remove", alias = "rm")]
     Uninstall(PluginUninstallCommand),
-    /// List installed plugins
+    /// List installed plugins, along with the `wash-<name>` executables on the PATH that are run
+    /// as wash subcommands
     #[clap(name = "list", alias = "ls")]
     List(PluginListCommand),
 }
@@ -173,6 +179,7 @@ pub async fn handle_install(
                     password: cmd.oci_auth.password,
                     insecure: cmd.oci_auth.insecure,
                     insecure_skip_tls_verify: cmd.oci_auth.insecure_skip_tls_verify,
+                    ca_file: None,
                 },
             )
             .await
@@ -258,7 +265,7 @@ pub async fn handle_uninstall(
     let spinner = Spinner::new(&output_kind)?;
 
     spinner.update_spinner_message(" Loading plugins");
-    let plugins = load_plugins(plugin_dir)
+    let plugins = load_plugins(&plugin_dir)
         .await
         .context("Unable to load plugins")?;
 
@@ -283,6 +290,12 @@ pub async fn handle_uninstall(
     tokio::fs::remove_file(path)
         .await
         .context("Unable to remove plugin")?;
+    let permissions = PluginPermissions::path(&plugin_dir, &cmd.plugin);
+    if tokio::fs::try_exists(&permissions).await.unwrap_or(false) {
+        tokio::fs::remove_file(&permissions)
+            .await
+            .context("Unable to remove plugin permissions")?;
+    }
     spinner.finish_and_clear();
 
     Ok(CommandOutput {
@@ -305,26 +318,49 @@ pub async fn handle_list(
         .await
         .context("Unable to load plugins")?;
 
+    let external = external_plugins();
     spinner.finish_and_clear();
 
     let data = plugins.all_metadata();
 
-    Ok(CommandOutput {
-        text: plugins_table(data.clone()),
-        map: data
-            .into_iter()
-            .map(|m| {
-                (
-                    m.name.clone(),
-                    serde_json::json!({
-                        "version": m.version,
-                        "description": m.description,
-                        "id": m.id,
-                        "name": m.name,
-                        "author": m.author,
-                    }),
-                )
-            })
-            .collect(),
-    })
+    let mut text = plugins_table(data.clone());
+    if !external.is_empty() {
+        text.push_str(&format!(
+            "\nExecutable plugins on the PATH:\n{}",
+            external_plugins_table(&external)
+        ));
+    }
+    let mut map: HashMap<_, _> = data
+        .into_iter()
+        .map(|m| {
+            (
+                m.name.clone(),
+                serde_json::json!({
+                    "version": m.version,
+                    "description": m.description,
+                    "id": m.id,
+                    "name": m.name,
+                    "author": m.author,
+                    "kind": "wasm",
+                }),
+            )
+        })
+        .collect();
+    for (id, path) in external {
+        let name = format!("{EXTERNAL_PLUGIN_PREFIX}{id}");
+        // Installed plugins take precedence over executables of the same name
+        if plugins.metadata(&id).is_some() || map.contains_key(&name) {
+            continue;
+        }
+        map.insert(
+            name.clone(),
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "path": path,
+                "kind": "executable",
+            }),
+        );
+    }
+    Ok(CommandOutput { text, map })
 }
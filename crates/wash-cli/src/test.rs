@@ -0,0 +1,617 @@
+//! `wash test`: run the integration tests of a project against an ephemeral lattice
+//!
+//! The lattice, a single host with its own NATS server (and wadm when deploying a manifest), is
+//! started on random ports in a temporary directory, so it neither conflicts with nor reuses a
+//! lattice started by `wash up`. The project is deployed to it, either from the manifest of the
+//! `[test]` section of wasmcloud.toml or the way `wash dev` does, and the test command runs once
+//! the deployment converged, with the endpoints of the lattice in its environment. The lattice is
+//! torn down afterwards unless asked to keep it running, and `wash test` exits with the exit code
+//! of the test command.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::process::{Child, Command};
+use tokio::time::{timeout, Duration};
+use wash_lib::app::{
+    get_models, load_app_manifest, put_and_deploy_model, wait_for_model_deployed, AppManifest,
+    AppManifestSource,
+};
+use wash_lib::build::SignConfig;
+use wash_lib::cli::dev::dev_http_address;
+use wash_lib::cli::CommandOutput;
+use wash_lib::config::downloads_dir;
+use wash_lib::generate::emoji;
+use wash_lib::parser::{get_config, ProjectConfig, TypeConfig};
+use wash_lib::start::{
+    ensure_nats_server, ensure_wadm, ensure_wasmcloud, start_wadm, start_wasmcloud_host,
+    wait_for_server, NatsConfig, WadmConfig,
+};
+use wasmcloud_control_interface::{Client as CtlClient, ClientBuilder as CtlClientBuilder};
+
+use crate::dev::{build_dev_component, scale_dev_component};
+use crate::up::{
+    start_nats, stop_wasmcloud, DEFAULT_NATS_HOST, NATS_SERVER_VERSION, WADM_VERSION,
+    WASMCLOUD_ALLOW_FILE_LOAD, WASMCLOUD_CTL_HOST, WASMCLOUD_CTL_PORT, WASMCLOUD_HOST_SEED,
+    WASMCLOUD_HOST_VERSION, WASMCLOUD_LATTICE, WASMCLOUD_RPC_HOST, WASMCLOUD_RPC_PORT,
+};
+
+/// Command running the tests when neither `wash test` nor the `[test]` section of wasmcloud.toml
+/// set one
+pub const DEFAULT_TEST_COMMAND: &str = "cargo test -- --ignored";
+/// Time to wait for the deployment to converge when the `[test]` section doesn't set one
+const DEFAULT_DEPLOY_TIMEOUT_MS: u64 = 60_000;
+/// Time to wait for the host and wadm of an ephemeral lattice to start
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// URL of the NATS server of the lattice the tests run against
+pub const WASH_TEST_NATS_URL: &str = "WASH_TEST_NATS_URL";
+/// Name of the lattice the tests run against
+pub const WASH_TEST_LATTICE: &str = "WASH_TEST_LATTICE";
+/// ID of the host of the lattice the tests run against
+pub const WASH_TEST_HOST_ID: &str = "WASH_TEST_HOST_ID";
+/// Address of the HTTP server of the tested component, when it is linked to one
+pub const WASH_TEST_HTTP_ADDRESS: &str = "WASH_TEST_HTTP_ADDRESS";
+
+#[derive(Debug, Clone, Parser)]
+pub struct TestCommand {
+    /// Path to code directory
+    #[clap(name = "code-dir", long = "work-dir", env = "WASH_TEST_CODE_DIR")]
+    pub code_dir: Option<PathBuf>,
+
+    /// Application manifest to deploy, instead of the manifest of the `[test]` section of
+    /// wasmcloud.toml
+    #[clap(long = "manifest", env = "WASH_TEST_MANIFEST")]
+    pub manifest: Option<PathBuf>,
+
+    /// Leave the lattice running after the tests, printing how to reach and stop it
+    #[clap(
+        long = "keep-running",
+        env = "WASH_TEST_KEEP_RUNNING",
+        default_value = "false"
+    )]
+    pub keep_running: bool,
+
+    /// How long to wait for the deployment to converge before running the tests, in milliseconds
+    #[clap(long = "deploy-timeout-ms", env = "WASH_TEST_DEPLOY_TIMEOUT_MS")]
+    pub deploy_timeout_ms: Option<u64>,
+
+    /// NATS server version to download, e.g. `v2.10.7`. See https://github.com/nats-io/nats-server/releases/ for releases
+    #[clap(long = "nats-version", default_value = NATS_SERVER_VERSION, env = "NATS_VERSION")]
+    pub nats_version: String,
+
+    /// wasmCloud host version to download, e.g. `v0.55.0`. See https://github.com/wasmCloud/wasmcloud/releases for releases
+    #[clap(long = "wasmcloud-version", default_value = WASMCLOUD_HOST_VERSION, env = "WASMCLOUD_VERSION")]
+    pub wasmcloud_version: String,
+
+    /// wadm version to download, e.g. `v0.4.0`. See https://github.com/wasmCloud/wadm/releases for releases
+    #[clap(long = "wadm-version", default_value = WADM_VERSION, env = "WADM_VERSION")]
+    pub wadm_version: String,
+
+    /// Command running the tests, e.g. `wash test -- cargo test --test integration`. Defaults to
+    /// the command of the `[test]` section of wasmcloud.toml, or `cargo test -- --ignored`
+    #[clap(last = true)]
+    pub command: Vec<String>,
+}
+
+/// A lattice of a single host, with its own NATS server and optionally wadm, listening on random
+/// ports and keeping its state and logs in a temporary directory. Used by `wash test`, and usable
+/// by tests wanting a lattice of their own.
+pub struct EphemeralLattice {
+    dir: TempDir,
+    /// Name of the lattice
+    pub lattice: String,
+    /// ID of the host of the lattice
+    pub host_id: String,
+    /// Port of the NATS server of the lattice
+    pub nats_port: u16,
+    nats: Option<Child>,
+    wadm: Option<Child>,
+    host: Option<Child>,
+}
+
+impl EphemeralLattice {
+    /// Start a lattice with the given versions of NATS and the host, along with wadm if
+    /// `wadm_version` is set. Binaries are downloaded to the wash downloads directory if missing.
+    pub async fn start(
+        nats_version: &str,
+        wasmcloud_version: &str,
+        wadm_version: Option<&str>,
+    ) -> Result<Self> {
+        let install_dir = downloads_dir()?;
+        let dir = tempfile::Builder::new()
+            .prefix("wash-test-")
+            .tempdir()
+            .context("failed to create lattice directory")?;
+
+        // NATS and wadm write their configuration and pid files next to their binary
+        let nats_binary =
+            copy_binary(&ensure_nats_server(nats_version, &install_dir).await?, &dir).await?;
+        let nats_port = open_port()?;
+        let nats = start_nats(
+            dir.path(),
+            &nats_binary,
+            NatsConfig {
+                host: DEFAULT_NATS_HOST.to_string(),
+                port: nats_port,
+                store_dir: dir.path().join("jetstream"),
+                js_domain: None,
+                remote_url: None,
+                credentials: None,
+                websocket_port: open_port()?,
+                cluster: None,
+            },
+        )
+        .await?;
+
+        let host_seed = nkeys::KeyPair::new_server();
+        let host_id = host_seed.public_key();
+        let mut lattice = Self {
+            lattice: format!("wash-test-{}", host_id[host_id.len() - 8..].to_lowercase()),
+            host_id,
+            nats_port,
+            dir,
+            nats: Some(nats),
+            wadm: None,
+            host: None,
+        };
+        let started = lattice
+            .start_services(
+                &install_dir,
+                wasmcloud_version,
+                wadm_version,
+                host_seed.seed()?,
+            )
+            .await;
+        if let Err(e) = started {
+            lattice.stop().await;
+            return Err(e);
+        }
+        Ok(lattice)
+    }
+
+    async fn start_services(
+        &mut self,
+        install_dir: &Path,
+        wasmcloud_version: &str,
+        wadm_version: Option<&str>,
+        host_seed: String,
+    ) -> Result<()> {
+        let nats_address = format!("{DEFAULT_NATS_HOST}:{}", self.nats_port);
+        wait_for_server(&nats_address, "NATS").await?;
+
+        if let Some(wadm_version) = wadm_version {
+            let wadm_binary =
+                copy_binary(&ensure_wadm(wadm_version, install_dir).await?, &self.dir).await?;
+            self.wadm = Some(
+                start_wadm(
+                    &wadm_binary,
+                    self.log_file("wadm.log").await?,
+                    Some(WadmConfig {
+                        structured_logging: false,
+                        js_domain: None,
+                        nats_server_url: nats_address,
+                        nats_credsfile: None,
+                    }),
+                )
+                .await?,
+            );
+        }
+
+        let env = HashMap::from([
+            (WASMCLOUD_LATTICE.to_string(), self.lattice.clone()),
+            (WASMCLOUD_HOST_SEED.to_string(), host_seed),
+            (
+                WASMCLOUD_RPC_HOST.to_string(),
+                DEFAULT_NATS_HOST.to_string(),
+            ),
+            (WASMCLOUD_RPC_PORT.to_string(), self.nats_port.to_string()),
+            (
+                WASMCLOUD_CTL_HOST.to_string(),
+                DEFAULT_NATS_HOST.to_string(),
+            ),
+            (WASMCLOUD_CTL_PORT.to_string(), self.nats_port.to_string()),
+            (WASMCLOUD_ALLOW_FILE_LOAD.to_string(), "true".to_string()),
+        ]);
+        self.host = Some(
+            start_wasmcloud_host(
+                ensure_wasmcloud(wasmcloud_version, install_dir).await?,
+                Stdio::null(),
+                self.log_file("wasmcloud.log").await?,
+                env,
+            )
+            .await?,
+        );
+
+        let ctl_client = self.ctl_client().await?;
+        timeout(START_TIMEOUT, async {
+            loop {
+                if let Ok(hosts) = ctl_client.get_hosts().await {
+                    if hosts
+                        .iter()
+                        .filter_map(|h| h.response.as_ref())
+                        .any(|h| h.id == self.host_id)
+                    {
+                        break;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(250)).await;
+            }
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "wasmCloud host did not start, see the logs in [{}]",
+                self.dir.path().display()
+            )
+        })?;
+
+        if wadm_version.is_some() {
+            let nats_client = self.nats_client().await?;
+            timeout(START_TIMEOUT, async {
+                while get_models(&nats_client, Some(self.lattice.clone()))
+                    .await
+                    .is_err()
+                {
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                }
+            })
+            .await
+            .context("wadm did not start")?;
+        }
+        Ok(())
+    }
+
+    async fn log_file(&self, name: &str) -> Result<std::fs::File> {
+        Ok(tokio::fs::File::create(self.dir.path().join(name))
+            .await
+            .with_context(|| format!("failed to create {name}"))?
+            .into_std()
+            .await)
+    }
+
+    /// URL of the NATS server of the lattice
+    #[must_use]
+    pub fn nats_url(&self) -> String {
+        format!("nats://{DEFAULT_NATS_HOST}:{}", self.nats_port)
+    }
+
+    /// Directory holding the state and logs of the lattice
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Connect to the NATS server of the lattice
+    pub async fn nats_client(&self) -> Result<async_nats::Client> {
+        async_nats::connect(self.nats_url())
+            .await
+            .context("failed to connect to NATS")
+    }
+
+    /// Control interface client of the lattice
+    pub async fn ctl_client(&self) -> Result<CtlClient> {
+        Ok(CtlClientBuilder::new(self.nats_client().await?)
+            .lattice(self.lattice.clone())
+            .build())
+    }
+
+    /// Environment variables pointing the tests, and wash commands they run, at the lattice
+    #[must_use]
+    pub fn env(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (WASH_TEST_NATS_URL.to_string(), self.nats_url()),
+            (WASH_TEST_LATTICE.to_string(), self.lattice.clone()),
+            (WASH_TEST_HOST_ID.to_string(), self.host_id.clone()),
+            (WASMCLOUD_LATTICE.to_string(), self.lattice.clone()),
+            (
+                WASMCLOUD_CTL_HOST.to_string(),
+                DEFAULT_NATS_HOST.to_string(),
+            ),
+            (WASMCLOUD_CTL_PORT.to_string(), self.nats_port.to_string()),
+        ])
+    }
+
+    /// Stop the host, wadm and NATS, and remove the directory of the lattice
+    pub async fn stop(mut self) {
+        if let Some(host) = self.host.take() {
+            if let Err(e) = stop_wasmcloud(host).await {
+                eprintln!(
+                    "{} {}",
+                    emoji::WARN,
+                    style(format!("failed to stop the wasmCloud host: {e}")).bold()
+                );
+            }
+        }
+        for mut child in [self.wadm.take(), self.nats.take()].into_iter().flatten() {
+            let _ = child.kill().await;
+        }
+    }
+
+    /// Leave the lattice running, keeping its directory, and return a description of how to
+    /// stop it
+    #[must_use]
+    pub fn keep_running(self) -> String {
+        let pids = [&self.host, &self.wadm, &self.nats]
+            .into_iter()
+            .flatten()
+            .filter_map(Child::id)
+            .map(|pid| pid.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let dir = self.dir.into_path();
+        format!(
+            "Lattice [{}] is still running, logs are in [{}]. Stop it with `kill {pids}`",
+            self.lattice,
+            dir.display()
+        )
+    }
+}
+
+/// Copy a binary of the downloads directory to the directory of a lattice
+async fn copy_binary(binary: &Path, dir: &TempDir) -> Result<PathBuf> {
+    let target = dir
+        .path()
+        .join(binary.file_name().context("binary path has no file name")?);
+    tokio::fs::copy(binary, &target)
+        .await
+        .with_context(|| format!("failed to copy [{}]", binary.display()))?;
+    Ok(target)
+}
+
+/// Find a free port on the loopback interface
+fn open_port() -> Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .context("failed to bind random port")?
+        .local_addr()?
+        .port())
+}
+
+pub async fn handle_command(cmd: TestCommand) -> Result<CommandOutput> {
+    let project_path = match cmd.code_dir {
+        Some(code_dir) => code_dir,
+        None => std::env::current_dir()?,
+    };
+    let project_cfg = get_config(Some(project_path.clone()), Some(true))?;
+    let test_cfg = project_cfg.test.clone();
+    let manifest = cmd
+        .manifest
+        .or_else(|| test_cfg.manifest.map(|path| project_path.join(path)));
+    if manifest.is_none() && matches!(project_cfg.project_type, TypeConfig::Provider(_)) {
+        bail!("testing a provider requires an application manifest, set `manifest` in the [test] section of wasmcloud.toml");
+    }
+    let (program, args) = match (cmd.command.split_first(), test_cfg.command.as_deref()) {
+        (Some((program, args)), _) => (program.clone(), args.to_vec()),
+        (None, command) => parse_test_command(command.unwrap_or(DEFAULT_TEST_COMMAND))?,
+    };
+    let deploy_timeout = Duration::from_millis(
+        cmd.deploy_timeout_ms
+            .or(test_cfg.deploy_timeout_ms)
+            .unwrap_or(DEFAULT_DEPLOY_TIMEOUT_MS),
+    );
+
+    eprintln!(
+        "{} {}",
+        emoji::WRENCH,
+        style("Starting an ephemeral lattice...").bold(),
+    );
+    let lattice = EphemeralLattice::start(
+        &cmd.nats_version,
+        &cmd.wasmcloud_version,
+        manifest.is_some().then_some(cmd.wadm_version.as_str()),
+    )
+    .await?;
+
+    let deployed = deploy(
+        &lattice,
+        &project_path,
+        project_cfg,
+        manifest,
+        deploy_timeout,
+    )
+    .await;
+    let http_address = match &deployed {
+        Ok(component_ids) => component_http_address(&lattice, component_ids).await,
+        Err(_) => None,
+    };
+    let result = match deployed {
+        Ok(_) => run_tests(&lattice, &project_path, &program, &args, http_address).await,
+        Err(e) => Err(e),
+    };
+
+    let mut map = HashMap::from([
+        ("lattice".to_string(), json!(lattice.lattice)),
+        ("nats_url".to_string(), json!(lattice.nats_url())),
+        ("host_id".to_string(), json!(lattice.host_id)),
+    ]);
+    if let Some(address) = http_address {
+        map.insert("http_address".to_string(), json!(address));
+    }
+    let teardown = if cmd.keep_running {
+        map.insert("lattice_dir".to_string(), json!(lattice.dir()));
+        Some(lattice.keep_running())
+    } else {
+        lattice.stop().await;
+        None
+    };
+
+    let exit_code = result?;
+    map.insert("exit_code".to_string(), json!(exit_code));
+    let mut text = if exit_code == 0 {
+        format!("{} Tests passed", emoji::GREEN_CHECK)
+    } else {
+        format!("{} Tests failed with exit code {exit_code}", emoji::ERROR)
+    };
+    if let Some(teardown) = teardown {
+        text.push('\n');
+        text.push_str(&teardown);
+    }
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Split a test command on whitespace into the program and its arguments
+fn parse_test_command(command: &str) -> Result<(String, Vec<String>)> {
+    let mut parts = command.split_whitespace().map(String::from);
+    let program = parts.next().context("test command is empty")?;
+    Ok((program, parts.collect()))
+}
+
+/// Deploy the project to the lattice, from `manifest` if set or the way `wash dev` does otherwise,
+/// and wait for the deployment to converge. Returns the IDs of the deployed components.
+async fn deploy(
+    lattice: &EphemeralLattice,
+    project_path: &Path,
+    project_cfg: ProjectConfig,
+    manifest: Option<PathBuf>,
+    deploy_timeout: Duration,
+) -> Result<Vec<String>> {
+    let Some(manifest) = manifest else {
+        let sign_cfg = SignConfig {
+            keys_directory: None,
+            issuer: None,
+            subject: None,
+            disable_keygen: false,
+        };
+        let component =
+            build_dev_component(project_path.to_path_buf(), project_cfg, Some(&sign_cfg)).await?;
+        let ctl_client = lattice.ctl_client().await?;
+        timeout(
+            deploy_timeout,
+            scale_dev_component(&ctl_client, &lattice.host_id, &component, Vec::new(), 1),
+        )
+        .await
+        .with_context(|| format!("timed out waiting for [{}] to start", component.name))??;
+        return Ok(vec![component.component_id]);
+    };
+
+    let AppManifest::SerializedModel(model) = load_app_manifest(AppManifestSource::File(manifest))
+        .await
+        .context("failed to load test manifest")?
+    else {
+        bail!("test manifest should be a file");
+    };
+    let nats_client = lattice.nats_client().await?;
+    let (name, version) = put_and_deploy_model(
+        &nats_client,
+        Some(lattice.lattice.clone()),
+        &serde_yaml::to_string(&model).context("failed to convert manifest to string")?,
+    )
+    .await?;
+    eprintln!(
+        "{} {}",
+        emoji::HOURGLASS_DRAINING,
+        style(format!(
+            "Waiting for application [{name}] version [{version}] to deploy..."
+        ))
+        .bold(),
+    );
+    wait_for_model_deployed(
+        &nats_client,
+        Some(lattice.lattice.clone()),
+        &name,
+        &version,
+        deploy_timeout,
+    )
+    .await?;
+
+    let ctl_client = lattice.ctl_client().await?;
+    let inventory = ctl_client
+        .get_host_inventory(&lattice.host_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to get host inventory: {e}"))?
+        .response
+        .context("received control interface response with empty inventory")?;
+    Ok(inventory
+        .components
+        .into_iter()
+        .map(|component| component.id)
+        .collect())
+}
+
+/// Address of the HTTP server of the first of the components linked to one
+async fn component_http_address(
+    lattice: &EphemeralLattice,
+    component_ids: &[String],
+) -> Option<SocketAddr> {
+    let ctl_client = lattice.ctl_client().await.ok()?;
+    for component_id in component_ids {
+        if let Ok(Some(address)) = dev_http_address(&ctl_client, component_id).await {
+            return Some(address);
+        }
+    }
+    None
+}
+
+/// Run the test command from the project directory, returning its exit code. Interrupting
+/// `wash test` stops the tests, so that the lattice is still torn down.
+async fn run_tests(
+    lattice: &EphemeralLattice,
+    project_path: &Path,
+    program: &str,
+    args: &[String],
+    http_address: Option<SocketAddr>,
+) -> Result<i32> {
+    eprintln!(
+        "{} {}",
+        emoji::GREEN_CHECK,
+        style(format!(
+            "Running `{}` against lattice [{}]",
+            std::iter::once(program)
+                .chain(args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+            lattice.lattice
+        ))
+        .bold(),
+    );
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(project_path)
+        .envs(lattice.env())
+        .kill_on_drop(true);
+    if let Some(address) = http_address {
+        command.env(WASH_TEST_HTTP_ADDRESS, address.to_string());
+    }
+    let mut tests = command
+        .spawn()
+        .with_context(|| format!("failed to run test command `{program}`"))?;
+    tokio::select! {
+        status = tests.wait() => Ok(status
+            .context("failed to wait for test command")?
+            .code()
+            .unwrap_or(1)),
+        _ = tokio::signal::ctrl_c() => {
+            let _ = tests.kill().await;
+            bail!("tests interrupted");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_command() {
+        assert_eq!(
+            parse_test_command(DEFAULT_TEST_COMMAND).expect("command should parse"),
+            (
+                "cargo".to_string(),
+                vec![
+                    "test".to_string(),
+                    "--".to_string(),
+                    "--ignored".to_string()
+                ]
+            )
+        );
+        assert!(parse_test_command("  ").is_err());
+    }
+}
@@ -1,17 +1,20 @@
 mod config;
 pub use config::*;
 
-use std::{io::Cursor, path::PathBuf};
+use std::{convert::Infallible, io::Cursor, path::PathBuf, sync::Arc};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use async_compression::tokio::bufread::GzipDecoder;
 use clap::Parser;
+use serde::Serialize;
 use tokio_tar::Archive;
-use warp::Filter;
+use warp::{http::StatusCode, reply::Response, Filter, Reply};
 use wash_lib::{
-    cli::{CommandOutput, OutputKind},
-    config::downloads_dir,
+    cli::{CliConnectionOpts, CommandOutput, OutputKind},
+    config::{downloads_dir, WashConnectionOptions},
+    generate::emoji,
 };
+use wasmcloud_control_interface::Client as CtlClient;
 use wasmcloud_core::tls;
 
 const DEFAULT_WASHBOARD_VERSION: &str = "v0.3.0";
@@ -19,12 +22,23 @@ const DEFAULT_WASHBOARD_VERSION: &str = "v0.3.0";
 #[derive(Parser, Debug, Clone)]
 pub struct UiCommand {
     /// Which port to run the UI on, defaults to 3030
-    #[clap(short = 'p', long = "port", default_value = DEFAULT_WASH_UI_PORT)]
+    #[clap(long = "port", default_value = DEFAULT_WASH_UI_PORT)]
     pub port: u16,
 
     /// Which version of the UI to run
     #[clap(short = 'v', long = "version", default_value = DEFAULT_WASHBOARD_VERSION)]
     pub version: String,
+
+    /// Directory of washboard assets to serve instead of a downloaded release
+    #[clap(long = "washboard-dir")]
+    pub washboard_dir: Option<PathBuf>,
+
+    /// Open the UI in the default browser once it is running
+    #[clap(long = "open")]
+    pub open: bool,
+
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
 }
 
 pub async fn handle_command(command: UiCommand, output_kind: OutputKind) -> Result<CommandOutput> {
@@ -34,22 +48,120 @@ pub async fn handle_command(command: UiCommand, output_kind: OutputKind) -> Resu
 }
 
 pub async fn handle_ui(cmd: UiCommand, _output_kind: OutputKind) -> Result<()> {
-    let washboard_path = downloads_dir()?.join("washboard");
-    let washboard_assets = ensure_washboard(&cmd.version, washboard_path).await?;
+    let washboard_assets = match cmd.washboard_dir {
+        Some(dir) => dir,
+        None => ensure_washboard(&cmd.version, downloads_dir()?.join("washboard")).await?,
+    };
     let static_files = warp::fs::dir(washboard_assets);
 
+    // Credentials of the context stay in this process, the UI only talks to the API below
+    let wco: WashConnectionOptions = cmd.opts.try_into()?;
+    let lattice = wco.get_lattice();
+    let ctl_client = Arc::new(wco.into_ctl_client(None).await?);
+
     let cors = warp::cors()
         .allow_any_origin()
         .allow_methods(vec!["GET", "POST"])
         .allow_headers(vec!["Content-Type"]);
 
-    eprintln!("Washboard running on http://localhost:{}", cmd.port);
+    let (addr, server) = warp::serve(api(ctl_client).or(static_files).with(cors))
+        .try_bind_with_graceful_shutdown(([127, 0, 0, 1], cmd.port), async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .with_context(|| format!("failed to listen on port {}", cmd.port))?;
+    let url = format!("http://localhost:{}", addr.port());
+
+    eprintln!("Washboard running on {url} for lattice [{lattice}]");
     eprintln!("Hit CTRL-C to stop");
+    if cmd.open {
+        if let Err(e) = open_browser(&url) {
+            eprintln!("{} failed to open browser: {e:#}", emoji::WARN);
+        }
+    }
+
+    server.await;
+    eprintln!("Washboard stopped");
 
-    warp::serve(static_files.with(cors))
-        .run(([127, 0, 0, 1], cmd.port))
-        .await;
+    Ok(())
+}
+
+/// Routes of the API proxying the requests of the UI to the lattice control interface:
+///
+/// - `GET /api/hosts`: hosts of the lattice
+/// - `GET /api/hosts/{host_id}/inventory`: inventory of a host
+/// - `GET /api/links`: links of the lattice
+fn api(
+    ctl_client: Arc<CtlClient>,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    let client = warp::any().map(move || ctl_client.clone());
+    let hosts = warp::path!("api" / "hosts")
+        .and(warp::get())
+        .and(client.clone())
+        .and_then(|client: Arc<CtlClient>| async move {
+            let hosts = client.get_hosts().await.map(|hosts| {
+                hosts
+                    .into_iter()
+                    .filter_map(|host| host.response)
+                    .collect::<Vec<_>>()
+            });
+            api_reply(hosts.map_err(|e| anyhow!("failed to get hosts: {e}")))
+        });
+    let inventory = warp::path!("api" / "hosts" / String / "inventory")
+        .and(warp::get())
+        .and(client.clone())
+        .and_then(|host_id: String, client: Arc<CtlClient>| async move {
+            let inventory = client
+                .get_host_inventory(&host_id)
+                .await
+                .map_err(|e| anyhow!("failed to get inventory of host [{host_id}]: {e}"))
+                .and_then(|resp| {
+                    resp.response
+                        .ok_or_else(|| anyhow!("host [{host_id}] did not return an inventory"))
+                });
+            api_reply(inventory)
+        });
+    let links = warp::path!("api" / "links")
+        .and(warp::get())
+        .and(client)
+        .and_then(|client: Arc<CtlClient>| async move {
+            let links = client
+                .get_links()
+                .await
+                .map(|resp| resp.response.unwrap_or_default())
+                .map_err(|e| anyhow!("failed to get links: {e}"));
+            api_reply(links)
+        });
+    hosts.or(inventory).unify().or(links).unify()
+}
+
+/// Reply with `result` as JSON, or with its error when the lattice could not be queried
+fn api_reply<T: Serialize>(result: Result<T>) -> Result<Response, Infallible> {
+    Ok(match result {
+        Ok(value) => warp::reply::json(&value).into_response(),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": format!("{e:#}") })),
+            StatusCode::BAD_GATEWAY,
+        )
+        .into_response(),
+    })
+}
 
+/// Open `url` in the default browser
+fn open_browser(url: &str) -> Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .context("failed to run browser opener")?;
     Ok(())
 }
 
@@ -23,13 +23,16 @@ use tracing::{error, warn};
 use wash_lib::app::{load_app_manifest, AppManifest, AppManifestSource};
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wash_lib::config::{
-    create_nats_client_from_opts, downloads_dir, DEFAULT_NATS_TIMEOUT_MS, WASMCLOUD_PID_FILE,
+    connect_host, create_nats_client_from_opts, downloads_dir, host_port, parse_listen_addr,
+    DEFAULT_NATS_TIMEOUT_MS, WASMCLOUD_PID_FILE,
 };
 use wash_lib::context::fs::ContextDir;
 use wash_lib::context::ContextManager;
 use wash_lib::start::{
     ensure_nats_server, ensure_wadm, ensure_wasmcloud, find_wasmcloud_binary, nats_pid_path,
-    start_nats_server, start_wadm, start_wasmcloud_host, NatsConfig, WadmConfig, WADM_PID,
+    start_nats_cluster, start_nats_server, start_wadm, start_wasmcloud_host_with_limits,
+    write_nats_cluster_file, NatsClusterNode, NatsConfig, ProcessLimits, WadmConfig,
+    NATS_CLUSTER_FILE, NATS_CLUSTER_MAX_SIZE, NATS_CLUSTER_MIN_SIZE, WADM_PID,
 };
 use wasmcloud_control_interface::{Client as CtlClient, ClientBuilder as CtlClientBuilder};
 
@@ -91,6 +94,20 @@ pub struct NatsOpts {
     #[clap(long = "nats-port", env = "WASMCLOUD_NATS_PORT")]
     pub nats_port: Option<u16>,
 
+    /// IPv4 or IPv6 address the launched NATS server listens on, e.g. `0.0.0.0`, `::` or `[::1]`.
+    /// Takes precedence over `--nats-host`
+    #[clap(
+        long = "nats-listen-addr",
+        env = "WASH_NATS_LISTEN_ADDR",
+        value_parser = parse_listen_addr
+    )]
+    pub nats_listen_addr: Option<String>,
+
+    /// Address wash, wadm and the host connect to NATS on. Defaults to the listen address, or the
+    /// loopback address when NATS listens on all interfaces
+    #[clap(long = "nats-connect-addr", env = "WASH_NATS_CONNECT_ADDR")]
+    pub nats_connect_addr: Option<String>,
+
     /// NATS websocket port to use. TLS is not supported. This is required for the wash ui to connect from localhost
     #[clap(
         long = "nats-websocket-port",
@@ -102,13 +119,40 @@ pub struct NatsOpts {
     /// NATS Server Jetstream domain for extending superclusters
     #[clap(long = "nats-js-domain", env = "NATS_JS_DOMAIN")]
     pub nats_js_domain: Option<String>,
+
+    /// Launch a local NATS cluster of this many nodes (2-5) instead of a single NATS server, listening on
+    /// sequential ports starting at `--nats-port`. The host connects to the first node of the cluster
+    #[clap(
+        long = "nats-cluster-size",
+        env = "WASH_NATS_CLUSTER_SIZE",
+        value_parser = clap::value_parser!(u16).range(NATS_CLUSTER_MIN_SIZE as i64..=NATS_CLUSTER_MAX_SIZE as i64),
+        conflicts_with_all = ["nats_remote_url", "connect_only"]
+    )]
+    pub nats_cluster_size: Option<u16>,
+}
+
+impl NatsOpts {
+    /// Address NATS listens on, falling back to `--nats-host` and then `default`
+    #[must_use]
+    pub fn listen_host(&self, default: &str) -> String {
+        self.nats_listen_addr
+            .clone()
+            .or_else(|| self.nats_host.clone())
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Address to connect to NATS on, falling back to the host reaching the listen address
+    #[must_use]
+    pub fn connect_host(&self, default: &str) -> String {
+        self.nats_connect_addr
+            .clone()
+            .unwrap_or_else(|| connect_host(&self.listen_host(default)))
+    }
 }
 
 impl From<NatsOpts> for NatsConfig {
     fn from(other: NatsOpts) -> NatsConfig {
-        let host = other
-            .nats_host
-            .unwrap_or_else(|| DEFAULT_NATS_HOST.to_string());
+        let host = other.listen_host(DEFAULT_NATS_HOST);
         let port = other.nats_port.unwrap_or_else(|| {
             DEFAULT_NATS_PORT
                 .parse()
@@ -122,6 +166,7 @@ impl From<NatsOpts> for NatsConfig {
             remote_url: other.nats_remote_url,
             credentials: other.nats_credsfile,
             websocket_port: other.nats_websocket_port,
+            cluster: None,
         }
     }
 }
@@ -262,6 +307,27 @@ pub struct WasmcloudOpts {
     /// If enabled, allows starting additional wasmCloud hosts on this machine
     #[clap(long = "multi-local")]
     pub multi_local: bool,
+
+    /// Maximum memory usage of the host and the providers it spawns, in bytes. Applied with a
+    /// transient cgroup scope, Linux only
+    #[clap(long = "limit-memory", value_name = "BYTES")]
+    pub limit_memory: Option<u64>,
+
+    /// CPU weight of the host and the providers it spawns, between 1 and 10000. Applied with a
+    /// transient cgroup scope, Linux only
+    #[clap(
+        long = "limit-cpu-weight",
+        value_parser = clap::value_parser!(u16).range(1..=10000)
+    )]
+    pub limit_cpu_weight: Option<u16>,
+
+    /// Maximum number of open files of the host and the providers it spawns, Linux only
+    #[clap(long = "limit-nofile")]
+    pub limit_nofile: Option<u64>,
+
+    /// Fail if a resource limit can't be applied, instead of ignoring it with a warning
+    #[clap(long = "strict-limits")]
+    pub strict_limits: bool,
 }
 
 impl WasmcloudOpts {
@@ -347,17 +413,26 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
         .context("failed to load context")?;
 
     // falling back to the context's ctl_ connection won't always be right, but we have to pick one, since the context values are not optional
-    let nats_host = cmd.nats_opts.nats_host.clone().unwrap_or(ctx.ctl_host);
+    let nats_host = cmd.nats_opts.listen_host(&ctx.ctl_host);
+    let nats_connect_host = cmd.nats_opts.connect_host(&ctx.ctl_host);
     let nats_port = cmd.nats_opts.nats_port.unwrap_or(ctx.ctl_port);
 
     let wasmcloud_opts = WasmcloudOpts {
         lattice: Some(cmd.wasmcloud_opts.lattice.unwrap_or(ctx.lattice)),
-        ctl_host: Some(cmd.wasmcloud_opts.ctl_host.unwrap_or(nats_host.clone())),
+        ctl_host: Some(
+            cmd.wasmcloud_opts
+                .ctl_host
+                .unwrap_or(nats_connect_host.clone()),
+        ),
         ctl_port: Some(cmd.wasmcloud_opts.ctl_port.unwrap_or(nats_port)),
         ctl_jwt: cmd.wasmcloud_opts.ctl_jwt.or(ctx.ctl_jwt),
         ctl_seed: cmd.wasmcloud_opts.ctl_seed.or(ctx.ctl_seed),
         ctl_credsfile: cmd.wasmcloud_opts.ctl_credsfile.or(ctx.ctl_credsfile),
-        rpc_host: Some(cmd.wasmcloud_opts.rpc_host.unwrap_or(nats_host.clone())),
+        rpc_host: Some(
+            cmd.wasmcloud_opts
+                .rpc_host
+                .unwrap_or(nats_connect_host.clone()),
+        ),
         rpc_port: Some(cmd.wasmcloud_opts.rpc_port.unwrap_or(nats_port)),
         rpc_timeout_ms: Some(cmd.wasmcloud_opts.rpc_timeout_ms.unwrap_or(ctx.rpc_timeout)),
         rpc_jwt: cmd.wasmcloud_opts.rpc_jwt.or(ctx.rpc_jwt),
@@ -371,7 +446,7 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
         ..cmd.wasmcloud_opts
     };
     let host_env = configure_host_env(wasmcloud_opts.clone()).await?;
-    let nats_listen_address = format!("{nats_host}:{nats_port}");
+    let nats_listen_address = host_port(&nats_connect_host, nats_port);
 
     let nats_client = nats_client_from_wasmcloud_opts(&wasmcloud_opts).await;
 
@@ -380,6 +455,10 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
     // Ignore connect_only if this server has a remote as we have to start a leafnode in that scenario
     let supplied_remote_credentials = cmd.nats_opts.nats_remote_url.is_some();
 
+    if !should_run_nats && cmd.nats_opts.nats_cluster_size.is_some() {
+        warn!("NATS is already running at {nats_listen_address}, ignoring --nats-cluster-size");
+    }
+    let mut nats_cluster_nodes = Vec::new();
     let nats_bin = if should_run_nats || supplied_remote_credentials {
         // Download NATS if not already installed
         spinner.update_spinner_message(" Downloading NATS ...".to_string());
@@ -387,16 +466,28 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
 
         spinner.update_spinner_message(" Starting NATS ...".to_string());
 
-        let nats_config = NatsConfig {
-            host: nats_host.clone(),
-            port: nats_port,
-            store_dir: std::env::temp_dir().join(format!("wash-jetstream-{nats_port}")),
-            js_domain: cmd.nats_opts.nats_js_domain,
-            remote_url: cmd.nats_opts.nats_remote_url,
-            credentials: cmd.nats_opts.nats_credsfile.clone(),
-            websocket_port: cmd.nats_opts.nats_websocket_port,
-        };
-        start_nats(&install_dir, &nats_binary, nats_config).await?;
+        if let Some(cluster_size) = cmd.nats_opts.nats_cluster_size {
+            let nats_configs = NatsConfig::new_cluster(
+                &nats_host,
+                nats_port,
+                cluster_size,
+                cmd.nats_opts.nats_js_domain,
+                cmd.nats_opts.nats_websocket_port,
+            )?;
+            nats_cluster_nodes = start_cluster(&install_dir, &nats_binary, nats_configs).await?;
+        } else {
+            let nats_config = NatsConfig {
+                host: nats_host.clone(),
+                port: nats_port,
+                store_dir: std::env::temp_dir().join(format!("wash-jetstream-{nats_port}")),
+                js_domain: cmd.nats_opts.nats_js_domain,
+                remote_url: cmd.nats_opts.nats_remote_url,
+                credentials: cmd.nats_opts.nats_credsfile.clone(),
+                websocket_port: cmd.nats_opts.nats_websocket_port,
+                cluster: None,
+            };
+            start_nats(&install_dir, &nats_binary, nats_config).await?;
+        }
         Some(nats_binary)
     } else {
         // The user is running their own NATS server, so we don't need to download or start one
@@ -417,6 +508,13 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
     let host_started = Arc::new(AtomicBool::new(false));
     let wasmcloud_log_path = install_dir.join("wasmcloud.log");
     let ctl_client = wasmcloud_opts.clone().into_ctl_client(None).await?;
+    let nats_cluster_ports = nats_cluster_nodes
+        .iter()
+        .map(|node| node.port)
+        .collect::<Vec<_>>();
+    if !nats_cluster_ports.is_empty() {
+        out_json.insert("nats_cluster_ports".to_string(), json!(nats_cluster_ports));
+    }
 
     if !cmd.wasmcloud_opts.multi_local
         && tokio::fs::try_exists(install_dir.join(WASMCLOUD_PID_FILE))
@@ -476,7 +574,7 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
 
     let wadm_process = if !cmd.wadm_opts.disable_wadm
         && !is_wadm_running(
-            &nats_host,
+            &nats_connect_host,
             nats_port,
             cmd.nats_opts.nats_credsfile.clone(),
             &lattice,
@@ -554,15 +652,22 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
     };
     let version = wasmcloud_opts.wasmcloud_version;
 
-    let mut wasmcloud_child = match start_wasmcloud_host(
+    let limits = ProcessLimits {
+        memory_bytes: wasmcloud_opts.limit_memory,
+        cpu_weight: wasmcloud_opts.limit_cpu_weight,
+        nofile: wasmcloud_opts.limit_nofile,
+    };
+    let (mut wasmcloud_child, applied_limits) = match start_wasmcloud_host_with_limits(
         &wasmcloud_executable,
         std::process::Stdio::null(),
         stderr,
         host_env,
+        &limits,
+        wasmcloud_opts.strict_limits,
     )
     .await
     {
-        Ok(child) => child,
+        Ok(started) => started,
         Err(e) => {
             // Ensure we clean up the NATS server and wadm if we can't start wasmCloud
             if let Some(child) = wadm_process {
@@ -578,7 +683,15 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
     spinner.finish_and_clear();
 
     out_json.insert("success".to_string(), json!(true));
+    out_json.insert("limits".to_string(), json!(applied_limits));
     out_text.push_str("🛁 wash up completed successfully");
+    if !applied_limits.is_empty() {
+        let _ = write!(
+            out_text,
+            "\n🔒 wasmCloud is running with resource limits {}",
+            json!(applied_limits)
+        );
+    }
 
     if let Some(ref manifest_path) = cmd.wadm_opts.wadm_manifest {
         out_json.insert("deployed_wadm_manifest_path".into(), json!(manifest_path));
@@ -636,6 +749,10 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
             // remove wadm pidfile, the process is stopped automatically by CTRL+c
             remove_wadm_pidfile(&install_dir).await?;
         }
+        if !nats_cluster_ports.is_empty() {
+            // NATS cluster nodes are stopped automatically by CTRL+c as well
+            let _ = tokio::fs::remove_file(install_dir.join(NATS_CLUSTER_FILE)).await;
+        }
 
         spinner.finish_and_clear();
     } else {
@@ -647,6 +764,17 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
             out_text,
             "\n🕸  NATS is running in the background at http://{nats_listen_address}"
         );
+        if !nats_cluster_ports.is_empty() {
+            let _ = write!(
+                out_text,
+                "\n🕸  NATS cluster nodes are listening on ports {}",
+                nats_cluster_ports
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
 
         let _ = write!(
             out_text,
@@ -778,7 +906,7 @@ async fn deploy_wadm_application(
 }
 
 /// Helper function to start the NATS binary, redirecting output to nats.log
-async fn start_nats(
+pub(crate) async fn start_nats(
     install_dir: &Path,
     nats_binary: &Path,
     nats_config: NatsConfig,
@@ -819,6 +947,22 @@ async fn start_nats(
     Ok(nats_process)
 }
 
+/// Helper function to start a NATS cluster, redirecting output of each node to nats-<port>.log and
+/// recording the nodes so that `wash down` can stop all of them
+async fn start_cluster(
+    install_dir: &Path,
+    nats_binary: &Path,
+    nats_configs: Vec<NatsConfig>,
+) -> Result<Vec<NatsClusterNode>> {
+    let nodes = start_nats_cluster(nats_binary, install_dir, nats_configs)
+        .await?
+        .into_iter()
+        .map(|(node, _child)| node)
+        .collect::<Vec<_>>();
+    write_nats_cluster_file(install_dir, &nodes).await?;
+    Ok(nodes)
+}
+
 /// Helper function to run wasmCloud in interactive mode
 async fn run_wasmcloud_interactive(
     wasmcloud_child: &mut Child,
@@ -913,7 +1057,7 @@ async fn run_wasmcloud_interactive(
 }
 
 #[cfg(unix)]
-async fn stop_wasmcloud(mut wasmcloud_child: Child) -> Result<()> {
+pub(crate) async fn stop_wasmcloud(mut wasmcloud_child: Child) -> Result<()> {
     use nix::sys::signal::{kill, Signal};
     use nix::unistd::Pid;
 
@@ -929,7 +1073,7 @@ async fn stop_wasmcloud(mut wasmcloud_child: Child) -> Result<()> {
 }
 
 #[cfg(target_family = "windows")]
-async fn stop_wasmcloud(mut wasmcloud_child: Child) -> Result<()> {
+pub(crate) async fn stop_wasmcloud(mut wasmcloud_child: Child) -> Result<()> {
     wasmcloud_child.kill().await?;
     Ok(())
 }
@@ -1172,4 +1316,31 @@ mod tests {
             "Invalid pid should not be running"
         );
     }
+
+    #[test]
+    fn test_nats_listen_and_connect_addrs() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from(["up", "--nats-listen-addr", "[::]"])?;
+        assert_eq!(up.nats_opts.listen_host("127.0.0.1"), "::");
+        assert_eq!(up.nats_opts.connect_host("127.0.0.1"), "::1");
+
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--nats-listen-addr",
+            "0.0.0.0",
+            "--nats-connect-addr",
+            "10.0.0.5",
+        ])?;
+        assert_eq!(up.nats_opts.listen_host("127.0.0.1"), "0.0.0.0");
+        assert_eq!(up.nats_opts.connect_host("127.0.0.1"), "10.0.0.5");
+
+        let up: UpCommand = Parser::try_parse_from(["up", "--nats-host", "127.0.0.2"])?;
+        assert_eq!(up.nats_opts.listen_host("127.0.0.1"), "127.0.0.2");
+        assert_eq!(up.nats_opts.connect_host("127.0.0.1"), "127.0.0.2");
+
+        assert!(
+            UpCommand::try_parse_from(["up", "--nats-listen-addr", "localhost"]).is_err(),
+            "listen addresses must be IP addresses"
+        );
+        Ok(())
+    }
 }
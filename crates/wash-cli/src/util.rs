@@ -7,8 +7,14 @@ use std::{
 use anyhow::{Context, Result};
 use term_table::{Table, TableStyle};
 use wash_lib::{
+    cli::CommandOutput,
     config::{cfg_dir, DEFAULT_NATS_TIMEOUT_MS},
-    plugin::{subcommand::SubcommandRunner, PLUGIN_DIR},
+    context::fs::ContextDir,
+    plugin::{
+        external::{CONTEXT_ENV, CONTEXT_JSON_ENV},
+        subcommand::{PluginOutput, SubcommandRunner},
+        PLUGIN_DIR,
+    },
 };
 
 pub fn format_optional(value: Option<String>) -> String {
@@ -39,6 +45,48 @@ pub fn json_str_to_msgpack_bytes(payload: &str) -> Result<Vec<u8>> {
     rmp_serde::to_vec_named(&json).context("failed to encode JSON as msgpack")
 }
 
+/// Environment variables describing the active wash context, passed to plugins so that they
+/// connect to the same lattice as wash. Connection variables already set in the environment are
+/// left as they are
+pub fn plugin_context_env() -> Vec<(String, String)> {
+    // Only the parts of the context that are not secret are passed to plugins, and the context is
+    // not decrypted so that starting a plugin never prompts for a passphrase
+    let ctx = match ContextDir::new().and_then(|dir| dir.load_default_plugin_context()) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            tracing::debug!(err = ?e, "Could not load the active context for plugins");
+            return Vec::new();
+        }
+    };
+    let mut env = vec![(CONTEXT_ENV.to_string(), ctx.name.clone())];
+    match serde_json::to_string(&ctx) {
+        Ok(json) => env.push((CONTEXT_JSON_ENV.to_string(), json)),
+        Err(e) => tracing::debug!(err = ?e, "Could not serialize the active context for plugins"),
+    }
+    env.extend(
+        [
+            ("WASMCLOUD_CTL_HOST", ctx.ctl_host),
+            ("WASMCLOUD_CTL_PORT", ctx.ctl_port.to_string()),
+            ("WASMCLOUD_LATTICE", ctx.lattice),
+        ]
+        .into_iter()
+        .filter(|(key, _)| std::env::var_os(key).is_none())
+        .map(|(key, value)| (key.to_string(), value)),
+    );
+    env
+}
+
+/// Convert the structured output returned by a plugin into the output of a wash command. The JSON
+/// output of the plugin must be a JSON object
+pub fn plugin_command_output(output: PluginOutput) -> Result<CommandOutput> {
+    let Some(json) = output.json else {
+        return Ok(CommandOutput::from(output.text));
+    };
+    let map = serde_json::from_str(&json)
+        .context("Plugin returned invalid JSON output, expected a JSON object")?;
+    Ok(CommandOutput::new(output.text, map))
+}
+
 /// Ensure that the given plugin directory exists or return the default plugin dir path
 pub async fn ensure_plugin_dir(dir: Option<impl AsRef<Path>>) -> Result<PathBuf> {
     let plugin_dir = match dir.map(|dir| dir.as_ref().to_path_buf()) {
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_json::json;
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
+use wash_lib::cli::whoami::{whoami, WhoamiCommand, WhoamiReport};
+use wash_lib::cli::CommandOutput;
+
+pub async fn handle_command(cmd: WhoamiCommand) -> Result<CommandOutput> {
+    let report = whoami(cmd).await?;
+    let mut map: HashMap<String, serde_json::Value> =
+        serde_json::from_value(json!(report)).unwrap_or_default();
+    map.insert("all_allowed".to_string(), json!(report.all_allowed()));
+    Ok(CommandOutput::new(whoami_tables(&report), map))
+}
+
+fn whoami_tables(report: &WhoamiReport) -> String {
+    let identity = &report.identity;
+    let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table);
+    let context_path = identity
+        .context_path
+        .as_ref()
+        .map_or_else(|| "-".to_string(), |path| path.display().to_string());
+    let server = if identity.server_name.is_empty() {
+        identity.server_id.clone()
+    } else {
+        identity.server_name.clone()
+    };
+    for (key, value) in [
+        ("Context", identity.context.clone()),
+        ("Context file", context_path),
+        ("Server", format!("{} ({})", identity.url, server)),
+        ("Server version", identity.server_version.clone()),
+        ("Cluster", unknown(&identity.cluster)),
+        ("Lattice", identity.lattice.clone()),
+        ("Credentials", identity.auth.clone()),
+        ("User", unknown(&identity.user)),
+        ("User name", unknown(&identity.user_name)),
+        ("Account", unknown(&identity.account)),
+    ] {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(key, 1, Alignment::Left),
+            TableCell::new_with_alignment(value, 1, Alignment::Left),
+        ]));
+    }
+
+    let mut permissions = Table::new();
+    crate::util::configure_table_style(&mut permissions);
+    permissions.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Capability", 1, Alignment::Left),
+        TableCell::new_with_alignment("Operation", 1, Alignment::Left),
+        TableCell::new_with_alignment("Subject", 1, Alignment::Left),
+        TableCell::new_with_alignment("Result", 1, Alignment::Left),
+    ]));
+    for probe in &report.permissions {
+        permissions.add_row(Row::new(vec![
+            TableCell::new_with_alignment(&probe.capability, 1, Alignment::Left),
+            TableCell::new_with_alignment(probe.operation.as_str(), 1, Alignment::Left),
+            TableCell::new_with_alignment(&probe.subject, 1, Alignment::Left),
+            TableCell::new_with_alignment(probe.result.as_str().to_uppercase(), 1, Alignment::Left),
+        ]));
+    }
+
+    format!("{}\n{}", table.render(), permissions.render())
+}
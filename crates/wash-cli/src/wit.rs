@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use serde_json::json;
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::wit::deps::{fetch_deps, outdated_deps, PackageResolver, WkgConfig};
+use wash_lib::wit::diff::WitDiff;
+use wash_lib::wit::{load_package, load_package_at_rev, DEFAULT_WIT_DIR};
+
+use crate::appearance::spinner::Spinner;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum WitCommand {
+    /// Fetch the dependencies of a WIT package into its deps directory
+    #[clap(name = "fetch")]
+    Fetch {
+        /// Directory of the WIT package
+        #[clap(long = "wit-dir", default_value = DEFAULT_WIT_DIR)]
+        wit_dir: PathBuf,
+    },
+    /// Show the changes to the interfaces, types and functions of a WIT package since another
+    /// version of it
+    #[clap(name = "diff")]
+    Diff {
+        /// Previous version of the WIT package, either a WIT directory or file, or a git revision
+        /// (branch, tag or commit) of the WIT directory
+        #[clap(name = "old")]
+        old: String,
+        /// Directory of the WIT package
+        #[clap(long = "wit-dir", default_value = DEFAULT_WIT_DIR)]
+        wit_dir: PathBuf,
+    },
+    /// List the vendored dependencies of a WIT package with newer versions available
+    #[clap(name = "outdated")]
+    Outdated {
+        /// Directory of the WIT package
+        #[clap(long = "wit-dir", default_value = DEFAULT_WIT_DIR)]
+        wit_dir: PathBuf,
+    },
+}
+
+pub async fn handle_command(command: WitCommand, output_kind: OutputKind) -> Result<CommandOutput> {
+    match command {
+        WitCommand::Fetch { wit_dir } => fetch(&wit_dir, output_kind).await,
+        WitCommand::Diff { old, wit_dir } => diff(&old, &wit_dir),
+        WitCommand::Outdated { wit_dir } => outdated(&wit_dir, output_kind).await,
+    }
+}
+
+async fn fetch(wit_dir: &Path, output_kind: OutputKind) -> Result<CommandOutput> {
+    let sp = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Fetching WIT dependencies ...".to_string());
+    let mut resolver = PackageResolver::new(WkgConfig::load()?);
+    let deps = fetch_deps(wit_dir, &mut resolver).await;
+    sp.finish_and_clear();
+    let deps = deps?;
+
+    let text = if deps.fetched.is_empty() {
+        "All dependencies are already vendored".to_string()
+    } else {
+        let fetched: Vec<_> = deps
+            .fetched
+            .iter()
+            .map(|package| match &package.version {
+                Some(version) => format!("  {}@{version}", package.name),
+                None => format!("  {}", package.name),
+            })
+            .collect();
+        format!(
+            "Fetched {} package(s) into [{}]:\n{}",
+            deps.fetched.len(),
+            wit_dir.join("deps").display(),
+            fetched.join("\n")
+        )
+    };
+    let map = HashMap::from([
+        ("fetched".to_string(), json!(deps.fetched)),
+        ("vendored".to_string(), json!(deps.vendored)),
+    ]);
+    Ok(CommandOutput::new(text, map))
+}
+
+fn diff(old: &str, wit_dir: &Path) -> Result<CommandOutput> {
+    let (new_resolve, _) = load_package(wit_dir)?;
+    let (old_resolve, _) = if Path::new(old).exists() {
+        load_package(Path::new(old))?
+    } else {
+        load_package_at_rev(wit_dir, old).with_context(|| {
+            format!("[{old}] is neither a WIT directory or file nor a git revision")
+        })?
+    };
+    let diff = WitDiff::new(&old_resolve, &new_resolve);
+    let text = if diff.is_empty() {
+        "No changes".to_string()
+    } else {
+        diff.to_text()
+    };
+    let mut map = HashMap::from([("changed".to_string(), json!(!diff.is_empty()))]);
+    map.insert("packages".to_string(), json!(diff.packages));
+    Ok(CommandOutput::new(text, map))
+}
+
+async fn outdated(wit_dir: &Path, output_kind: OutputKind) -> Result<CommandOutput> {
+    let sp = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Looking up versions of WIT dependencies ...".to_string());
+    let mut resolver = PackageResolver::new(WkgConfig::load()?);
+    let deps = outdated_deps(wit_dir, &mut resolver).await;
+    sp.finish_and_clear();
+    let deps = deps?;
+
+    let mut lines = if deps.outdated.is_empty() {
+        vec!["All dependencies are up to date".to_string()]
+    } else {
+        deps.outdated
+            .iter()
+            .map(|package| {
+                let compatible = package
+                    .latest_compatible
+                    .as_ref()
+                    .filter(|compatible| **compatible != package.latest)
+                    .map(|compatible| format!(" (latest compatible: {compatible})"))
+                    .unwrap_or_default();
+                format!(
+                    "{}: {} -> {}{compatible}",
+                    package.name, package.current, package.latest
+                )
+            })
+            .collect()
+    };
+    lines.extend(
+        deps.failed
+            .iter()
+            .map(|(package, err)| format!("{package}: failed to look up versions: {err}")),
+    );
+    let map = HashMap::from([
+        ("outdated".to_string(), json!(deps.outdated)),
+        ("failed".to_string(), json!(deps.failed)),
+    ]);
+    Ok(CommandOutput::new(lines.join("\n"), map))
+}
@@ -1,6 +1,6 @@
 use std::fs::read_to_string;
 use std::net::TcpListener;
-use std::net::{Ipv4Addr, SocketAddrV4};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::{
     env,
     fs::{create_dir_all, remove_dir_all},
@@ -155,7 +155,12 @@ pub async fn start_nats(port: u16, nats_install_dir: &PathBuf) -> Result<Child>
 
 /// Returns an open port on the interface, searching within the range endpoints, inclusive
 pub async fn find_open_port() -> Result<u16> {
-    TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+    find_open_port_on(Ipv4Addr::LOCALHOST.into()).await
+}
+
+/// Returns an open port on the interface with address `ip`, e.g. `::1` to test IPv6
+pub async fn find_open_port_on(ip: IpAddr) -> Result<u16> {
+    TcpListener::bind(SocketAddr::new(ip, 0))
         .context("failed to bind random port")?
         .local_addr()
         .map(|addr| addr.port())
@@ -464,6 +469,39 @@ impl TestWashInstance {
             .context("failed to parse output of `wash call` output")
     }
 
+    /// Trigger the equivalent of `wash call` on a [`TestWashInstance`] with load testing options,
+    /// e.g. `--repeat 50 --concurrency 5`
+    pub(crate) async fn call_component_load(
+        &self,
+        component_id: impl AsRef<str>,
+        operation: impl AsRef<str>,
+        load_args: &[&str],
+    ) -> Result<CallCommandOutput> {
+        let component_id = component_id.as_ref();
+        let operation = operation.as_ref();
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args([
+                "call",
+                component_id,
+                operation,
+                "--rpc-timeout-ms",
+                DEFAULT_WASH_INVOCATION_TIMEOUT_MS_ARG,
+                "--rpc-port",
+                &self.nats_port.to_string(),
+                "--output",
+                "json",
+            ])
+            .args(load_args)
+            .output()
+            .await
+            .with_context(|| {
+                format!("failed to load call operation [{operation}] on component [{component_id}]")
+            })?;
+        ensure!(output.status.success(), "wash call load test failed");
+        serde_json::from_slice(&output.stdout)
+            .context("failed to parse output of `wash call` output")
+    }
+
     /// Trigger the equivalent of `wash stop component` on a [`TestWashInstance`]
     pub(crate) async fn stop_component(
         &self,
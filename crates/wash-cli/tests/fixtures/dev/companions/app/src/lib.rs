@@ -0,0 +1,14 @@
+wit_bindgen::generate!();
+
+use exports::test::app::run::Guest;
+use test::greeter::greet::greet;
+
+struct App;
+
+impl Guest for App {
+    fn run() -> String {
+        greet("wasmCloud")
+    }
+}
+
+export!(App);
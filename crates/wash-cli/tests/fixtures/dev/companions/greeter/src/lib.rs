@@ -0,0 +1,13 @@
+wit_bindgen::generate!();
+
+use exports::test::greeter::greet::Guest;
+
+struct Greeter;
+
+impl Guest for Greeter {
+    fn greet(name: String) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+export!(Greeter);
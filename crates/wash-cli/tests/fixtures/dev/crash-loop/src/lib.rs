@@ -0,0 +1,32 @@
+wit_bindgen::generate!();
+
+use exports::wasi::http::incoming_handler::Guest;
+use wasi::http::types::*;
+
+/// Greeting of the component, which is never configured
+struct Greeting(String);
+
+impl Greeting {
+    fn new() -> Self {
+        panic!("missing required config `greeting`")
+    }
+}
+
+struct Crasher;
+
+impl Guest for Crasher {
+    fn handle(_request: IncomingRequest, response_out: ResponseOutparam) {
+        let Greeting(greeting) = Greeting::new();
+        let response = OutgoingResponse::new(Fields::new());
+        let response_body = response.body().unwrap();
+        ResponseOutparam::set(response_out, Ok(response));
+        response_body
+            .write()
+            .unwrap()
+            .blocking_write_and_flush(greeting.as_bytes())
+            .unwrap();
+        OutgoingBody::finish(response_body, None).expect("failed to finish response body");
+    }
+}
+
+export!(Crasher);
@@ -0,0 +1,28 @@
+wit_bindgen::generate!();
+
+use exports::wasi::http::incoming_handler::Guest;
+use wasi::http::types::*;
+
+struct Unlinked;
+
+impl Guest for Unlinked {
+    fn handle(_request: IncomingRequest, response_out: ResponseOutparam) {
+        // No keyvalue provider is ever linked to the component
+        let bucket = wasi::keyvalue::store::open("").expect("failed to open empty bucket");
+        let greeting = bucket
+            .get("greeting")
+            .expect("failed to get greeting")
+            .unwrap_or_else(|| b"hello".to_vec());
+        let response = OutgoingResponse::new(Fields::new());
+        let response_body = response.body().unwrap();
+        ResponseOutparam::set(response_out, Ok(response));
+        response_body
+            .write()
+            .unwrap()
+            .blocking_write_and_flush(&greeting)
+            .unwrap();
+        OutgoingBody::finish(response_body, None).expect("failed to finish response body");
+    }
+}
+
+export!(Unlinked);
@@ -1,6 +1,13 @@
 use anyhow::{Context, Result};
+use serial_test::serial;
 use tokio::process::Command;
-use wash_lib::cli::output::AppValidateOutput;
+use wash_lib::cli::output::{
+    AppHistoryCommandOutput, AppListCommandOutput, AppRollbackCommandOutput, AppValidateOutput,
+    GetHostInventoriesCommandOutput,
+};
+
+mod common;
+use common::{TestWashInstance, HELLO_OCI_REF, HTTP_JSONIFY_OCI_REF, LOCAL_REGISTRY};
 
 /// Ensure a simple WADM manifest passes validation
 #[tokio::test]
@@ -28,3 +35,761 @@ async fn app_validate_simple() -> Result<()> {
 
     Ok(())
 }
+
+/// Run `wash app validate` on a manifest fixture, returning the parsed output and exit status
+async fn app_validate(manifest: &str) -> Result<(AppValidateOutput, std::process::ExitStatus)> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "validate",
+            &format!("./tests/fixtures/wadm/manifests/{manifest}"),
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app validate")?;
+    let cmd_output =
+        serde_json::from_slice(&output.stdout).context("failed to build JSON from output")?;
+    Ok((cmd_output, output.status))
+}
+
+/// Ensure invalid WADM manifests fail validation with an error describing the problem
+#[tokio::test]
+async fn app_validate_invalid() -> Result<()> {
+    for (manifest, expected) in [
+        (
+            "duplicate-names.wadm.yaml",
+            "component name [http-component] is used by 2 components (lines 11, 19)",
+        ),
+        (
+            "missing-image.wadm.yaml",
+            "capability [httpserver] has no image (line 19)",
+        ),
+        (
+            "dangling-link.wadm.yaml",
+            "component [http-component] links to [keyvalue], which is not a component of the manifest (line 11)",
+        ),
+        (
+            "zero-spread-weights.wadm.yaml",
+            "spreadscaler of component [http-component] has spread weights summing to 0",
+        ),
+    ] {
+        let (cmd_output, status) = app_validate(manifest).await?;
+        assert!(!status.success(), "{manifest} should fail validation");
+        assert!(!cmd_output.valid, "{manifest} should be invalid");
+        assert!(
+            cmd_output
+                .errors
+                .iter()
+                .any(|error| error.msg.contains(expected)),
+            "{manifest} should fail with [{expected}], got {:?}",
+            cmd_output.errors
+        );
+    }
+
+    // Manifests that cannot be parsed fail without any lint output
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "validate",
+            "./tests/fixtures/wadm/manifests/invalid-schema.wadm.yaml",
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app validate")?;
+    assert!(!output.status.success(), "invalid schema should fail");
+
+    Ok(())
+}
+
+/// Ensure warnings are reported without failing validation
+#[tokio::test]
+async fn app_validate_warnings() -> Result<()> {
+    let (cmd_output, status) = app_validate("uneven-spread-weights.wadm.yaml").await?;
+    assert!(status.success(), "warnings should not fail validation");
+    assert!(cmd_output.valid, "valid output");
+    assert!(cmd_output.errors.is_empty(), "no errors");
+    assert!(
+        cmd_output
+            .warnings
+            .iter()
+            .any(|warning| warning.msg.contains("spread weights summing to 90")),
+        "uneven spread weights should be reported, got {:?}",
+        cmd_output.warnings
+    );
+
+    Ok(())
+}
+
+/// Run a `wash app` subcommand against the given [`TestWashInstance`]
+async fn wash_app(instance: &TestWashInstance, args: &[&str]) -> Result<std::process::Output> {
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .arg("app")
+        .args(args)
+        .args([
+            "--ctl-port",
+            &instance.nats_port.to_string(),
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("failed to execute wash app {}", args.join(" ")))
+}
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_rollback_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    for manifest in [
+        "./tests/fixtures/wadm/manifests/rollback-v1.wadm.yaml",
+        "./tests/fixtures/wadm/manifests/rollback-v2.wadm.yaml",
+    ] {
+        let output = wash_app(&wash_instance, &["deploy", manifest]).await?;
+        assert!(output.status.success(), "failed to deploy {manifest}");
+    }
+
+    let output = wash_app(&wash_instance, &["history", "rollback-sample"]).await?;
+    assert!(output.status.success(), "wash app history failed");
+    let history: AppHistoryCommandOutput =
+        serde_json::from_slice(&output.stdout).context("failed to parse history output")?;
+    assert_eq!(
+        history
+            .revisions
+            .iter()
+            .map(|r| (r.version.as_str(), r.deployed))
+            .collect::<Vec<_>>(),
+        vec![("v1", false), ("v2", true)]
+    );
+
+    let output = wash_app(&wash_instance, &["rollback", "rollback-sample"]).await?;
+    assert!(
+        output.status.success(),
+        "wash app rollback failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let rollback: AppRollbackCommandOutput =
+        serde_json::from_slice(&output.stdout).context("failed to parse rollback output")?;
+    assert!(rollback.success);
+    assert_eq!(rollback.model_version, "v1");
+    assert_eq!(rollback.previous_version.as_deref(), Some("v2"));
+    assert_eq!(rollback.status.version, "v1");
+
+    // The deployed version of the application should reference v1's image again
+    let output = wash_app(&wash_instance, &["get", "rollback-sample", "v1"]).await?;
+    assert!(output.status.success(), "wash app get failed");
+    let manifest = String::from_utf8_lossy(&output.stdout);
+    assert!(manifest.contains(HELLO_OCI_REF));
+    assert!(!manifest.contains(HTTP_JSONIFY_OCI_REF));
+
+    let output = wash_app(&wash_instance, &["status", "rollback-sample"]).await?;
+    let status: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse status output")?;
+    assert_eq!(status["status"]["version"], "v1");
+
+    // Rolling back to a version whose image cannot be pulled must not report success
+    let output = wash_app(
+        &wash_instance,
+        &[
+            "put",
+            "./tests/fixtures/wadm/manifests/rollback-v3.wadm.yaml",
+        ],
+    )
+    .await?;
+    assert!(output.status.success(), "failed to put v3");
+    let output = wash_app(
+        &wash_instance,
+        &[
+            "rollback",
+            "rollback-sample",
+            "--to-version",
+            "v3",
+            "--wait-timeout-ms",
+            "20000",
+        ],
+    )
+    .await?;
+    assert!(
+        !output.status.success(),
+        "rolling back to an unpullable image should fail"
+    );
+
+    Ok(())
+}
+
+/// Ensure manifest placeholders are resolved before anything is sent to wadm
+#[tokio::test]
+async fn app_deploy_unresolved_placeholders() -> Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "deploy",
+            "./tests/fixtures/wadm/manifests/templated.wadm.yaml",
+            // No NATS server listens on this port, so the error must come from rendering
+            "--ctl-port",
+            "1",
+        ])
+        .env_remove("WASH_TEST_APP_IMAGE")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app deploy")?;
+    assert!(!output.status.success(), "deploy should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unresolved placeholders in app manifest: WASH_TEST_APP_IMAGE"),
+        "unexpected error: {stderr}"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "deploy",
+            "./tests/fixtures/wadm/manifests/templated.wadm.yaml",
+            "--set",
+            &format!("WASH_TEST_APP_IMAGE={HELLO_OCI_REF}"),
+            "--set",
+            "APP_VERSION=v2",
+            "--show-rendered",
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app deploy --show-rendered")?;
+    assert!(output.status.success(), "failed to render manifest");
+    let rendered: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse rendered output")?;
+    let manifest: serde_yaml::Value = serde_yaml::from_str(
+        rendered["manifest"]
+            .as_str()
+            .context("rendered manifest should be a string")?,
+    )?;
+    assert_eq!(manifest["metadata"]["annotations"]["version"], "v2");
+    assert_eq!(
+        manifest["metadata"]["annotations"]["description"],
+        "Manifest with placeholders, keeping ${literal} placeholders as is"
+    );
+    assert_eq!(
+        manifest["spec"]["components"][0]["properties"]["image"],
+        HELLO_OCI_REF
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_deploy_placeholders_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    // Values set on the command line take precedence over the environment
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "deploy",
+            "./tests/fixtures/wadm/manifests/templated.wadm.yaml",
+            "--set",
+            &format!("WASH_TEST_APP_IMAGE={HELLO_OCI_REF}"),
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+            "--output",
+            "json",
+        ])
+        .env("WASH_TEST_APP_IMAGE", HTTP_JSONIFY_OCI_REF)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app deploy")?;
+    assert!(
+        output.status.success(),
+        "failed to deploy templated manifest: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The rendered manifest is the one stored and deployed
+    let output = wash_app(&wash_instance, &["get", "templated-sample", "v1"]).await?;
+    assert!(output.status.success(), "wash app get failed");
+    let manifest = String::from_utf8_lossy(&output.stdout);
+    assert!(manifest.contains(HELLO_OCI_REF));
+    assert!(!manifest.contains(HTTP_JSONIFY_OCI_REF));
+    assert!(!manifest.contains("WASH_TEST_APP_IMAGE"));
+
+    let output = wash_app(&wash_instance, &["status", "templated-sample"]).await?;
+    let status: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse status output")?;
+    assert_eq!(status["status"]["version"], "v1");
+
+    Ok(())
+}
+
+/// Ensure `wash app status --watch` emits a snapshot for every change of the status of an
+/// application, and exits once it is deployed
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_status_watch_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let output = wash_app(
+        &wash_instance,
+        &[
+            "put",
+            "./tests/fixtures/wadm/manifests/status-watch.wadm.yaml",
+        ],
+    )
+    .await?;
+    assert!(output.status.success(), "failed to put manifest");
+
+    // Watch the undeployed application, so that deploying it changes its status
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_wash"));
+    watch
+        .args([
+            "app",
+            "status",
+            "status-watch-sample",
+            "--watch",
+            "--until",
+            "Deployed",
+            "--until-failed-timeout-ms",
+            "30000",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true);
+    let deploy = async {
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        wash_app(&wash_instance, &["deploy", "status-watch-sample"]).await
+    };
+    let (output, deployed) = tokio::time::timeout(std::time::Duration::from_secs(120), async {
+        tokio::join!(watch.output(), deploy)
+    })
+    .await
+    .context("wash app status --watch did not exit once the application was deployed")?;
+    assert!(deployed?.status.success(), "failed to deploy application");
+    let output = output.context("failed to execute wash app status --watch")?;
+    assert!(
+        output.status.success(),
+        "wash app status --watch failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).context("output is not UTF-8")?;
+    let snapshots = stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()
+        .context("output is not NDJSON")?;
+    assert!(
+        snapshots.len() >= 2,
+        "expected at least two snapshots, got: {stdout}"
+    );
+    assert!(
+        snapshots.windows(2).all(|pair| pair[0] != pair[1]),
+        "snapshots should only be emitted on changes: {stdout}"
+    );
+    assert_eq!(
+        snapshots.last().context("missing snapshot")?["status_type"],
+        "Deployed"
+    );
+
+    Ok(())
+}
+
+/// Image references and max instances of the components in the inventory of `instance`, with
+/// whether they are managed by the application `app`
+async fn component_inventory(
+    instance: &TestWashInstance,
+    app: &str,
+) -> Result<(Vec<(String, u64)>, Vec<(String, u64)>)> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "inventory", "--output", "json", "--ctl-port"])
+        .arg(instance.nats_port.to_string())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to get inventory")?;
+    let inventory: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse inventory")?;
+    let (mut managed, mut unmanaged) = (Vec::new(), Vec::new());
+    for component in inventory["inventories"][0]["components"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let entry = (
+            component["image_ref"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            component["max_instances"].as_u64().unwrap_or_default(),
+        );
+        if component["annotations"]["wasmcloud.dev/appspec"] == app {
+            managed.push(entry);
+        } else {
+            unmanaged.push(entry);
+        }
+    }
+    managed.sort();
+    unmanaged.sort();
+    Ok((managed, unmanaged))
+}
+
+/// Ensure `wash get inventory` attributes components to the application managing them, and
+/// `wash app list --detailed` reports the desired and actual instances of deployed applications
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_list_detailed_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    wash_instance
+        .start_component(HELLO_OCI_REF, "imperative-hello")
+        .await?;
+    let output = wash_app(
+        &wash_instance,
+        &["deploy", "./tests/fixtures/wadm/manifests/rollup.wadm.yaml"],
+    )
+    .await?;
+    assert!(
+        output.status.success(),
+        "failed to deploy application: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let instances = tokio::time::timeout(std::time::Duration::from_secs(60), async {
+        loop {
+            let output = wash_app(&wash_instance, &["list", "--detailed"]).await?;
+            let list: AppListCommandOutput = serde_json::from_slice(&output.stdout)
+                .context("failed to parse wash app list output")?;
+            if let Some(instances) = list
+                .instances
+                .into_iter()
+                .find(|i| i.name == "rollup-sample" && !i.under_provisioned)
+            {
+                break Ok::<_, anyhow::Error>(instances);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("application was not fully provisioned in time")??;
+    assert_eq!(instances.version.as_deref(), Some("v1"));
+    assert_eq!((instances.desired, instances.actual), (2, 2));
+    let [component] = &instances.components[..] else {
+        panic!("expected a single component: {instances:?}");
+    };
+    assert_eq!(component.name, "http-component");
+    assert_eq!((component.desired, component.actual), (2, 2));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "inventory", "--output", "json", "--ctl-port"])
+        .arg(wash_instance.nats_port.to_string())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to get inventory")?;
+    let inventory: GetHostInventoriesCommandOutput =
+        serde_json::from_slice(&output.stdout).context("failed to parse inventory")?;
+    let attribution = |id: &str| {
+        inventory
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| (item.app.clone(), item.managed_by.clone()))
+    };
+    assert_eq!(
+        attribution(&component.id),
+        Some((Some("rollup-sample".to_string()), Some("wadm".to_string())))
+    );
+    assert_eq!(attribution("imperative-hello"), Some((None, None)));
+
+    Ok(())
+}
+
+/// Ensure a topology built imperatively is captured by `wash app snapshot` as a manifest that
+/// recreates it when deployed
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_snapshot_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let manifest_path = wash_instance.test_dir.join("captured.wadm.yaml");
+
+    wash_instance
+        .start_component(HELLO_OCI_REF, "hello")
+        .await?;
+    wash_instance
+        .start_component(HTTP_JSONIFY_OCI_REF, "jsonify")
+        .await?;
+    for args in [
+        &["config", "put", "snapshot-config", "greeting=hi"][..],
+        &[
+            "link",
+            "put",
+            "hello",
+            "jsonify",
+            "wasi",
+            "http",
+            "--interface",
+            "outgoing-handler",
+            "--source-config",
+            "snapshot-config",
+        ],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(args)
+            .args(["--ctl-port", &wash_instance.nats_port.to_string()])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("failed to execute wash {}", args.join(" ")))?;
+        assert!(output.status.success(), "wash {} failed", args.join(" "));
+    }
+
+    let output = wash_app(
+        &wash_instance,
+        &[
+            "snapshot",
+            "--name",
+            "captured-app",
+            "--file",
+            &manifest_path.to_string_lossy(),
+        ],
+    )
+    .await?;
+    assert!(
+        output.status.success(),
+        "wash app snapshot failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let snapshot: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse snapshot output")?;
+    assert!(snapshot["warnings"]
+        .as_array()
+        .context("warnings should be a list")?
+        .iter()
+        .any(|w| w.as_str().is_some_and(|w| w.contains("[snapshot-config]"))));
+
+    let manifest: serde_yaml::Value =
+        serde_yaml::from_str(&tokio::fs::read_to_string(&manifest_path).await?)?;
+    let components = manifest["spec"]["components"]
+        .as_sequence()
+        .context("manifest should list components")?;
+    assert_eq!(components.len(), 2);
+    let link = &components
+        .iter()
+        .find(|c| c["properties"]["image"] == HELLO_OCI_REF)
+        .context("hello component should be captured")?["traits"][1]["properties"];
+    assert_eq!(link["package"], "http");
+    assert_eq!(link["source_config"][0]["name"], "snapshot-config");
+    assert_eq!(
+        link["source_config"][0]["properties"]["greeting"],
+        serde_yaml::Value::from("hi")
+    );
+
+    // Deploying the snapshot to the same lattice duplicates the topology
+    let output = wash_app(
+        &wash_instance,
+        &["deploy", &manifest_path.to_string_lossy()],
+    )
+    .await?;
+    assert!(
+        output.status.success(),
+        "failed to deploy snapshot: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let (managed, unmanaged) = tokio::time::timeout(std::time::Duration::from_secs(60), async {
+        loop {
+            let (managed, unmanaged) = component_inventory(&wash_instance, "captured-app").await?;
+            if managed.len() == unmanaged.len() {
+                break Ok::<_, anyhow::Error>((managed, unmanaged));
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("snapshot was not deployed in time")??;
+    assert_eq!(managed, unmanaged);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "links", "--output", "json", "--ctl-port"])
+        .arg(wash_instance.nats_port.to_string())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to get links")?;
+    let links: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        links["links"].as_array().map(Vec::len),
+        Some(2),
+        "the link should be recreated between the deployed components"
+    );
+
+    Ok(())
+}
+
+/// Push [`HELLO_OCI_REF`] to the local registry as `preflight/hello:<tag>`, returning its reference
+async fn push_hello_to_local_registry(tag: &str) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    let wasm = dir.path().join("hello.wasm");
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["pull", HELLO_OCI_REF, "--destination"])
+        .arg(&wasm)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash pull")?;
+    assert!(output.status.success(), "failed to pull {HELLO_OCI_REF}");
+    let reference = format!("{LOCAL_REGISTRY}/preflight/hello:{tag}");
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["push", &reference])
+        .arg(&wasm)
+        .arg("--insecure")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash push")?;
+    assert!(output.status.success(), "failed to push {reference}");
+    Ok(reference)
+}
+
+/// Write a manifest deploying `image` as a component of the given `type`
+async fn write_preflight_manifest(
+    dir: &std::path::Path,
+    kind: &str,
+    image: &str,
+) -> Result<String> {
+    let path = dir.join("preflight.wadm.yaml");
+    tokio::fs::write(
+        &path,
+        format!(
+            r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: preflight-sample
+  annotations:
+    version: v1
+spec:
+  components:
+    - name: preflight
+      type: {kind}
+      properties:
+        image: {image}
+"#
+        ),
+    )
+    .await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Run `wash app deploy` on `manifest`, trusting the local registry
+async fn deploy_with_local_registry(manifest: &str, ctl_port: u16) -> Result<std::process::Output> {
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "app",
+            "deploy",
+            manifest,
+            "--ctl-port",
+            &ctl_port.to_string(),
+        ])
+        .env(
+            "WASH_REGISTRIES",
+            serde_json::json!({ LOCAL_REGISTRY: { "insecure": true } }).to_string(),
+        )
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app deploy")
+}
+
+/// Ensure deploying a manifest referencing a tag missing from its registry fails before anything is
+/// sent to wadm
+// NOTE: This test will fail without a local docker registry running
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_deploy_preflight_missing_tag_serial() -> Result<()> {
+    let reference = push_hello_to_local_registry("preflight").await?;
+    let missing = reference.replace(":preflight", ":does-not-exist");
+    let dir = tempfile::tempdir()?;
+    let manifest = write_preflight_manifest(dir.path(), "component", &missing).await?;
+
+    // No NATS server listens on this port, so the error must come from the pre-flight checks
+    let output = deploy_with_local_registry(&manifest, 1).await?;
+    assert!(!output.status.success(), "deploy should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "component [preflight] ({missing}): image not found"
+        )),
+        "unexpected error: {stderr}"
+    );
+    assert!(
+        stderr.contains("pre-flight checks failed for 1 image(s)"),
+        "unexpected error: {stderr}"
+    );
+    Ok(())
+}
+
+/// Ensure deploying a component image as a capability provider fails the pre-flight checks
+// NOTE: This test will fail without a local docker registry running
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_app_deploy_preflight_kind_mismatch_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let reference = push_hello_to_local_registry("preflight").await?;
+    let dir = tempfile::tempdir()?;
+    let manifest = write_preflight_manifest(dir.path(), "capability", &reference).await?;
+
+    let output = deploy_with_local_registry(&manifest, wash_instance.nats_port).await?;
+    assert!(!output.status.success(), "deploy should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "provider [preflight] ({reference}): image is a component, but is deployed as a provider"
+        )),
+        "unexpected error: {stderr}"
+    );
+
+    // Nothing was sent to wadm
+    let output = wash_app(&wash_instance, &["get", "preflight-sample"]).await?;
+    assert!(
+        !output.status.success(),
+        "the manifest should not be stored"
+    );
+
+    // The same image deploys as a component, reporting the checks
+    let manifest = write_preflight_manifest(dir.path(), "component", &reference).await?;
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["app", "deploy", &manifest, "--output", "json", "--ctl-port"])
+        .arg(wash_instance.nats_port.to_string())
+        .env(
+            "WASH_REGISTRIES",
+            serde_json::json!({ LOCAL_REGISTRY: { "insecure": true } }).to_string(),
+        )
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash app deploy")?;
+    assert!(
+        output.status.success(),
+        "failed to deploy: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let deployed: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(deployed["preflight"][0]["status"], "ok");
+    assert_eq!(deployed["preflight"][0]["kind"], "component");
+    Ok(())
+}
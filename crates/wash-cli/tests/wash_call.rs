@@ -48,3 +48,50 @@ async fn integration_call() -> Result<()> {
 
     Ok(())
 }
+
+/// Ensure that wash call can load test a component
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_github_com), ignore = "github.com is not reachable")]
+async fn integration_call_load() -> Result<()> {
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    let instance = TestWashInstance::create().await?;
+    let _ = instance
+        .pull(HTTP_JSONIFY_OCI_REF)
+        .await
+        .context("failed to pull component")?;
+    let StartCommandOutput { component_id, .. } = instance
+        .start_component(HTTP_JSONIFY_OCI_REF, "http-jsonify")
+        .await
+        .context("failed to start component")?;
+    let component_id = component_id.context("component ID not present after starting component")?;
+
+    let cmd_output = instance
+        .call_component_load(
+            &component_id,
+            "wasi:http/incoming-handler.handle",
+            &["--repeat", "50", "--concurrency", "5", "--warmup", "2"],
+        )
+        .await
+        .context("failed to load call component")?;
+
+    assert!(cmd_output.success, "call command succeeded");
+    let load = cmd_output.load.context("load report is missing")?;
+    assert_eq!(load.requests, 50);
+    assert_eq!(load.warmup, 2);
+    assert_eq!(load.successes + load.failures, load.requests);
+    assert_eq!(load.errors.values().sum::<u64>(), load.failures);
+    assert_eq!(load.successes, 50, "unexpected errors: {:?}", load.errors);
+    assert!(!load.interrupted);
+    assert!(load.throughput > 0.0);
+    let p50 = load.latency.p50_ms.context("p50 is missing")?;
+    let p90 = load.latency.p90_ms.context("p90 is missing")?;
+    let p99 = load.latency.p99_ms.context("p99 is missing")?;
+    let max = load.latency.max_ms.context("max is missing")?;
+    assert!(0.0 < p50 && p50 <= p90 && p90 <= p99 && p99 <= max);
+
+    Ok(())
+}
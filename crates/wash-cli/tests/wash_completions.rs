@@ -0,0 +1,62 @@
+mod common;
+
+use common::{TestWashInstance, HELLO_OCI_REF};
+
+use anyhow::{Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_complete_component_ids_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let component_id = wash_instance
+        .start_component(HELLO_OCI_REF, "hello_complete")
+        .await?
+        .component_id
+        .context("missing component_id from start command output")?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "__complete",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+            "stop",
+            "component",
+            "",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash __complete")?;
+    assert!(output.status.success(), "wash __complete succeeded");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.lines().any(|line| line == component_id),
+        "expected component ID [{component_id}] in completions, got: {stdout}"
+    );
+
+    // Completions are filtered by the word being completed
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "__complete",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+            "stop",
+            "component",
+            "does-not-exist",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash __complete")?;
+    assert!(output.status.success(), "wash __complete succeeded");
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    wash_instance.stop_component(&component_id, None).await?;
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::TestWashInstance;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+
+/// Create a provider archive with an embedded config schema in the given directory
+async fn create_par_with_schema(dir: &Path) -> Result<PathBuf> {
+    let schema = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("par")
+        .join("config-schema.json");
+    let binary = dir.join("provider.bin");
+    tokio::fs::write(&binary, b"not actually a provider").await?;
+    let archive = dir.join("schema.par.gz");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "par",
+            "create",
+            "--arch",
+            "x86_64-linux",
+            "--binary",
+            &binary.to_string_lossy(),
+            "--name",
+            "Schema Provider",
+            "--vendor",
+            "TestRunner",
+            "--schema",
+            &schema.to_string_lossy(),
+            "--directory",
+            &dir.to_string_lossy(),
+            "--destination",
+            &archive.to_string_lossy(),
+            "--compress",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to create provider archive")?;
+    assert!(output.status.success(), "created provider archive");
+    Ok(archive)
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_config_put_validate_against_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let dir = tempfile::tempdir()?;
+    let archive = create_par_with_schema(dir.path()).await?;
+
+    let put_config = |values: &'static [&'static str]| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_wash"));
+        cmd.args([
+            "config",
+            "put",
+            "schema-config",
+            "--validate-against",
+            &archive.to_string_lossy(),
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .args(values)
+        .kill_on_drop(true);
+        cmd
+    };
+
+    // Values matching the schema are accepted
+    let output = put_config(&["address=0.0.0.0", "port=8080", "tls=true"])
+        .output()
+        .await
+        .context("failed to execute wash config put")?;
+    assert!(output.status.success(), "valid config was accepted");
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(cmd_output["success"], true);
+    assert_eq!(cmd_output["schema_validated"], true);
+
+    // A misspelled key is rejected
+    let output = put_config(&["adddress=0.0.0.0"])
+        .output()
+        .await
+        .context("failed to execute wash config put")?;
+    assert!(!output.status.success(), "unknown config key was rejected");
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stderr)?;
+    let error = cmd_output["error"].as_str().context("error message")?;
+    assert!(error.contains("[adddress]"), "error names the key: {error}");
+
+    // A value of the wrong type is rejected
+    let output = put_config(&["address=0.0.0.0", "port=eighty"])
+        .output()
+        .await
+        .context("failed to execute wash config put")?;
+    assert!(
+        !output.status.success(),
+        "mistyped config value was rejected"
+    );
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stderr)?;
+    let error = cmd_output["error"].as_str().context("error message")?;
+    assert!(
+        error.contains("[port]") && error.contains("[integer]"),
+        "error names the key and expected type: {error}"
+    );
+
+    // The schema is displayed when inspecting the provider archive
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["inspect", &archive.to_string_lossy()])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash inspect")?;
+    assert!(output.status.success(), "inspected provider archive");
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("Config Schema") && text.contains("\"port\""));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_config_put_from_file_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let dir = tempfile::tempdir()?;
+    let values = dir.path().join("values.yaml");
+    tokio::fs::write(
+        &values,
+        "database:\n  host: db.internal\n  port: 5432\nregion: us-east-1\n",
+    )
+    .await?;
+    let ctl_port = wash_instance.nats_port.to_string();
+
+    let wash = |args: &[&str]| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_wash"));
+        cmd.args(args)
+            .args(["--output", "json", "--ctl-port", &ctl_port])
+            .kill_on_drop(true);
+        cmd
+    };
+    let get_config = move || async move {
+        let output = wash(&["config", "get", "file-config"])
+            .output()
+            .await
+            .context("failed to execute wash config get")?;
+        assert!(output.status.success(), "got config");
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .context("failed to parse config")
+    };
+
+    // Pre-existing configuration, of which the file updates one key and drops another
+    let output = wash(&[
+        "config",
+        "put",
+        "file-config",
+        "app.database.host=localhost",
+        "app.stale=true",
+    ])
+    .output()
+    .await
+    .context("failed to execute wash config put")?;
+    assert!(output.status.success(), "put existing config");
+
+    let values = values.to_string_lossy();
+    let put_from_file = |flags: &[&str]| {
+        let mut cmd = wash(&[
+            "config",
+            "put",
+            "file-config",
+            "--from-file",
+            &values,
+            "--prefix",
+            "app",
+        ]);
+        cmd.args(flags);
+        cmd
+    };
+
+    // A dry run reports the changes without applying them
+    let output = put_from_file(&["--dry-run"])
+        .output()
+        .await
+        .context("failed to execute wash config put --dry-run")?;
+    assert!(output.status.success(), "dry run succeeded");
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(cmd_output["dry_run"], true);
+    assert_eq!(
+        cmd_output["keys"],
+        serde_json::json!(["app.database.host", "app.database.port", "app.region"])
+    );
+    assert_eq!(
+        cmd_output["changes"]["updated"],
+        serde_json::json!(["app.database.host"])
+    );
+    assert_eq!(
+        cmd_output["changes"]["removed"],
+        serde_json::json!(["app.stale"])
+    );
+    assert_eq!(get_config().await?["app.database.host"], "localhost");
+
+    // Merging keeps existing keys not present in the file
+    let output = put_from_file(&["--merge"])
+        .output()
+        .await
+        .context("failed to execute wash config put --merge")?;
+    assert!(output.status.success(), "merged config");
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(cmd_output["added"], 2);
+    assert_eq!(cmd_output["updated"], 1);
+    assert_eq!(cmd_output["removed"], 0);
+    assert_eq!(
+        get_config().await?,
+        serde_json::json!({
+            "app.database.host": "db.internal",
+            "app.database.port": "5432",
+            "app.region": "us-east-1",
+            "app.stale": "true",
+        })
+    );
+
+    // Replacing removes existing keys not present in the file
+    let output = put_from_file(&[])
+        .output()
+        .await
+        .context("failed to execute wash config put --from-file")?;
+    assert!(output.status.success(), "replaced config");
+    let cmd_output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(cmd_output["added"], 0);
+    assert_eq!(cmd_output["updated"], 0);
+    assert_eq!(cmd_output["removed"], 1);
+    assert_eq!(
+        get_config().await?,
+        serde_json::json!({
+            "app.database.host": "db.internal",
+            "app.database.port": "5432",
+            "app.region": "us-east-1",
+        })
+    );
+
+    Ok(())
+}
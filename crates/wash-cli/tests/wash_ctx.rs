@@ -0,0 +1,214 @@
+mod common;
+
+use common::TestWashInstance;
+
+use anyhow::{Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+use wash_lib::config::{DEFAULT_CTX_DIR_NAME, WASH_DIR};
+use wash_lib::context::bundle::BUNDLE_PASSPHRASE_ENV;
+use wash_lib::context::encryption::PASSPHRASE_ENV;
+use wash_lib::context::fs::ContextDir;
+use wash_lib::context::{ContextManager, WashContext};
+use wash_lib::registry::RegistryConfig;
+
+const PASSPHRASE: &str = "integration test passphrase";
+
+/// Run `wash get hosts` with the given context, and the given passphrase if any
+async fn get_hosts_with_context(
+    context: &str,
+    passphrase: Option<&str>,
+) -> Result<std::process::Output> {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wash"));
+    cmd.args(["get", "hosts", "--context", context, "--output", "json"])
+        .env_remove(PASSPHRASE_ENV)
+        .kill_on_drop(true);
+    if let Some(passphrase) = passphrase {
+        cmd.env(PASSPHRASE_ENV, passphrase);
+    }
+    cmd.output().await.context("failed to execute get hosts")
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_ctx_encrypt_passphrase_serial() -> Result<()> {
+    let wash = TestWashInstance::create().await?;
+    let ctx_dir = ContextDir::new()?;
+    let name = format!("encrypted_{}", wash.nats_port);
+    let user = nkeys::KeyPair::new_user();
+    let seed = user.seed()?;
+    ctx_dir.save_context(&WashContext {
+        ctl_port: wash.nats_port,
+        ctl_jwt: Some("integration-test-jwt".to_string()),
+        ctl_seed: Some(seed.clone()),
+        ..WashContext::named(name.clone())
+    })?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["ctx", "encrypt", &name, "--passphrase", "--output", "json"])
+        .env(PASSPHRASE_ENV, PASSPHRASE)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute ctx encrypt")?;
+    assert!(output.status.success(), "encrypted context");
+
+    // Sensitive fields are sealed, while the others remain in plaintext
+    let path = ctx_dir
+        .get_context_path(&name)?
+        .context("missing context file")?;
+    let stored = tokio::fs::read_to_string(&path).await?;
+    assert!(!stored.contains(&seed), "seed is stored in plaintext");
+    assert!(!stored.contains("integration-test-jwt"));
+    assert!(stored.contains(&format!("\"ctl_port\":{}", wash.nats_port)));
+
+    // Credentials are resolved from the encrypted context with the passphrase
+    let output = get_hosts_with_context(&name, Some(PASSPHRASE)).await?;
+    assert!(
+        output.status.success(),
+        "get hosts failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(output["hosts"].as_array().map(Vec::len), Some(1));
+
+    let output = get_hosts_with_context(&name, Some("wrong passphrase")).await?;
+    assert!(
+        !output.status.success(),
+        "decrypted with a wrong passphrase"
+    );
+    let output = get_hosts_with_context(&name, None).await?;
+    assert!(!output.status.success(), "decrypted without a passphrase");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["ctx", "list", "--output", "json"])
+        .env_remove(PASSPHRASE_ENV)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute ctx list")?;
+    assert!(
+        output.status.success(),
+        "listed contexts without decrypting"
+    );
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert!(output["encrypted"]
+        .as_array()
+        .is_some_and(|encrypted| encrypted.contains(&name.clone().into())));
+
+    ctx_dir.delete_context(&name)?;
+    Ok(())
+}
+
+/// Ensure a context exported as a bundle and imported into an empty context directory can be used
+/// to reach the lattice, with its non-secret fields unchanged
+#[tokio::test]
+#[serial]
+async fn integration_ctx_export_import_bundle_serial() -> Result<()> {
+    let wash = TestWashInstance::create().await?;
+    // Contexts are managed in a separate home directory, so that it can be wiped
+    let home = tempfile::tempdir()?;
+    let contexts = home.path().join(WASH_DIR).join(DEFAULT_CTX_DIR_NAME);
+    let bundle = home.path().join("team-dev.washbundle");
+    let name = format!("bundled_{}", wash.nats_port);
+    let user = nkeys::KeyPair::new_user();
+    let seed = user.seed()?;
+    let ctx = WashContext {
+        ctl_port: wash.nats_port,
+        ctl_seed: Some(seed.clone()),
+        ctl_jwt: Some("integration-test-jwt".to_string()),
+        registries: [(
+            "ghcr.io".to_string(),
+            RegistryConfig {
+                username: Some("integration".to_string()),
+                password_env: Some("WASH_INTEGRATION_UNSET_PASSWORD".to_string()),
+                ..Default::default()
+            },
+        )]
+        .into(),
+        ..WashContext::named(name.clone())
+    };
+    ContextDir::from_dir(Some(&contexts))?.save_context(&ctx)?;
+
+    let wash_cmd = |args: &[&str]| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_wash"));
+        cmd.args(args)
+            .env("HOME", home.path())
+            .env_remove("WASH_CONTEXTS")
+            .env(BUNDLE_PASSPHRASE_ENV, PASSPHRASE)
+            .kill_on_drop(true);
+        cmd
+    };
+
+    let output = wash_cmd(&[
+        "ctx",
+        "export",
+        &name,
+        "--bundle",
+        &bundle.to_string_lossy(),
+        "--seal-secrets",
+        "--output",
+        "json",
+    ])
+    .output()
+    .await
+    .context("failed to execute ctx export")?;
+    assert!(
+        output.status.success(),
+        "ctx export failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let exported = tokio::fs::read_to_string(&bundle).await?;
+    assert!(!exported.contains(&seed), "seed is bundled in plaintext");
+
+    tokio::fs::remove_dir_all(&contexts).await?;
+
+    let output = wash_cmd(&[
+        "ctx",
+        "import",
+        &bundle.to_string_lossy(),
+        "--output",
+        "json",
+    ])
+    .output()
+    .await
+    .context("failed to execute ctx import")?;
+    assert!(
+        output.status.success(),
+        "ctx import failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(output["context"], name.as_str());
+    assert_eq!(
+        output["missing_env"],
+        serde_json::json!(["WASH_INTEGRATION_UNSET_PASSWORD"])
+    );
+
+    let imported = ContextDir::from_dir(Some(&contexts))?.load_context(&name)?;
+    assert_eq!(imported.ctl_port, ctx.ctl_port);
+    assert_eq!(imported.lattice, ctx.lattice);
+    assert_eq!(imported.registries, ctx.registries);
+    assert_eq!(imported.ctl_seed.as_deref(), Some(seed.as_str()));
+
+    let output = wash_cmd(&["get", "hosts", "--context", &name, "--output", "json"])
+        .output()
+        .await
+        .context("failed to execute get hosts")?;
+    assert!(
+        output.status.success(),
+        "get hosts failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(output["hosts"].as_array().map(Vec::len), Some(1));
+
+    // Importing over an existing context requires --force
+    let output = wash_cmd(&["ctx", "import", &bundle.to_string_lossy()])
+        .output()
+        .await
+        .context("failed to execute ctx import")?;
+    assert!(!output.status.success(), "overwrote an existing context");
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 #![cfg(target_family = "unix")]
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -8,6 +9,7 @@ use tokio::{process::Command, sync::RwLock, time::Duration};
 mod common;
 use common::{
     find_open_port, init, start_nats, test_dir_with_subfolder, wait_for_no_hosts, wait_for_no_nats,
+    PROVIDER_HTTPSERVER_OCI_REF,
 };
 
 #[tokio::test]
@@ -110,3 +112,1389 @@ async fn integration_dev_hello_component_serial() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_env_file_config_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+    let env_file = project_dir.join(".env.dev");
+    tokio::fs::write(
+        &env_file,
+        "GREETING=hello\nWASMCLOUD_LATTICE=should-be-skipped\n",
+    )
+    .await?;
+
+    let dir = test_dir_with_subfolder("dev_env_file");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--env-file",
+            &env_file.to_string_lossy(),
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+
+    // Fetch the `dev-env` config until it matches the expected greeting
+    let wait_for_greeting = |expected: &'static str| async move {
+        loop {
+            let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+                .args([
+                    "config",
+                    "get",
+                    "dev-env",
+                    "--output",
+                    "json",
+                    "--ctl-port",
+                    nats_port.to_string().as_ref(),
+                ])
+                .kill_on_drop(true)
+                .output()
+                .await
+                .context("failed to get config")?;
+            if let Ok(values) = serde_json::from_slice::<HashMap<String, String>>(&output.stdout) {
+                if values.get("GREETING").map(String::as_str) == Some(expected) {
+                    if values.contains_key("WASMCLOUD_LATTICE") {
+                        bail!("tool configuration should not be forwarded as config");
+                    }
+                    break Ok::<_, anyhow::Error>(());
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(1200), wait_for_greeting("hello"))
+        .await
+        .context("timed out waiting for initial env file config")??;
+
+    // Change a value mid-session, which should be reflected without a rebuild
+    tokio::fs::write(&env_file, "GREETING=goodbye\n").await?;
+    tokio::time::timeout(Duration::from_secs(60), wait_for_greeting("goodbye"))
+        .await
+        .context("timed out waiting for updated env file config")??;
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+/// Recursively copy a directory
+fn copy_dir(from: &std::path::Path, to: &std::path::Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_companion_component_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    // The fixture `app` component imports an interface exported by its `greeter` companion
+    let test_dir = tempfile::tempdir()?;
+    copy_dir(
+        &std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dev/companions"),
+        test_dir.path(),
+    )?;
+    let app_dir = test_dir.path().join("app");
+    let greeter_dir = test_dir.path().join("greeter");
+    let app_artifact = app_dir.join("build/app_s.wasm");
+    let greeter_artifact = greeter_dir.join("build/greeter_s.wasm");
+
+    let dir = test_dir_with_subfolder("dev_companion_component");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--work-dir",
+            &app_dir.to_string_lossy(),
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+
+    let wash_json = |args: &'static [&'static str]| async move {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(args)
+            .args(["--output", "json", "--ctl-port", &nats_port.to_string()])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("failed to run wash")?;
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .context("failed to parse wash output")
+    };
+
+    // Wait until both components are running and the app is linked to the greeter
+    tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            let inventory = wash_json(&["get", "inventory"]).await?;
+            let links = wash_json(&["get", "links"]).await?;
+            let components = inventory["inventories"][0]["components"]
+                .as_array()
+                .map_or(0, Vec::len);
+            let linked = links["links"].as_array().is_some_and(|links| {
+                links.iter().any(|link| {
+                    link["wit_namespace"] == "test"
+                        && link["wit_package"] == "greeter"
+                        && link["interfaces"] == serde_json::json!(["greet"])
+                })
+            });
+            if components == 2 && linked {
+                break Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for linked components")??;
+    assert!(app_artifact.exists() && greeter_artifact.exists());
+
+    // Editing the companion rebuilds only the companion
+    let modified = |path: &std::path::Path| std::fs::metadata(path).and_then(|m| m.modified());
+    let app_built = modified(&app_artifact)?;
+    let greeter_built = modified(&greeter_artifact)?;
+    let greeter_src = greeter_dir.join("src/lib.rs");
+    let src = tokio::fs::read_to_string(&greeter_src).await?;
+    tokio::fs::write(&greeter_src, src.replace("Hello", "Howdy")).await?;
+
+    tokio::time::timeout(Duration::from_secs(600), async {
+        while modified(&greeter_artifact)? == greeter_built {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await
+    .context("timed out waiting for the companion to be rebuilt")??;
+    assert_eq!(
+        modified(&app_artifact)?,
+        app_built,
+        "the dependent component should not be rebuilt"
+    );
+
+    // Every iteration records the duration of its phases in the project
+    let metrics_path = app_dir.join(".wash/dev-metrics.ndjson");
+    let wait_for_metrics = |count: usize| {
+        let metrics_path = metrics_path.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(600), async {
+                loop {
+                    let metrics = tokio::fs::read_to_string(&metrics_path)
+                        .await
+                        .unwrap_or_default();
+                    let records = metrics
+                        .lines()
+                        .map(serde_json::from_str::<serde_json::Value>)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if records.len() >= count {
+                        return Ok::<_, anyhow::Error>(records);
+                    }
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+            })
+            .await
+            .context("timed out waiting for dev metrics")?
+        }
+    };
+    wait_for_metrics(1).await?;
+    tokio::fs::write(&greeter_src, src.replace("Hello", "Hiya")).await?;
+    let records = wait_for_metrics(2).await?;
+    assert_eq!(records.len(), 2);
+    for record in &records {
+        assert_eq!(record["component"], "greeter");
+        for phase in ["debounce", "build", "sign", "deploy", "link", "total"] {
+            assert!(
+                record[format!("{phase}_ms")].is_u64(),
+                "missing {phase} duration in {record}"
+            );
+        }
+        assert!(record["build_ms"].as_u64().is_some_and(|ms| ms > 0));
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["dev", "--work-dir", &app_dir.to_string_lossy(), "stats"])
+        .args(["--output", "json"])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to run wash dev stats")?;
+    let stats: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse wash dev stats output")?;
+    assert_eq!(stats["count"], 2);
+    assert!(stats["phases"]["total"]["p50_ms"].is_u64());
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_crash_loop_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    // The fixture component panics while constructing its state, whenever it handles a request
+    let test_dir = tempfile::tempdir()?;
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    copy_dir(
+        &manifest_dir.join("tests/fixtures/dev/crash-loop"),
+        test_dir.path(),
+    )?;
+    copy_dir(
+        &manifest_dir.join("../../examples/rust/components/http-hello-world/wit/deps"),
+        &test_dir.path().join("wit/deps"),
+    )?;
+
+    let dir = test_dir_with_subfolder("dev_crash_loop");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--crash-loop-threshold",
+            "2",
+            "--work-dir",
+            &test_dir.path().to_string_lossy(),
+        ])
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+    let stderr = dev_cmd.stderr.take().context("missing wash dev stderr")?;
+    let dev_output = Arc::new(RwLock::new(String::new()));
+    tokio::spawn({
+        let dev_output = dev_output.clone();
+        async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("{line}");
+                let mut dev_output = dev_output.write().await;
+                dev_output.push_str(&line);
+                dev_output.push('\n');
+            }
+        }
+    });
+
+    let running_components = || async move {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(["get", "inventory", "--output", "json", "--ctl-port"])
+            .arg(nats_port.to_string())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("failed to get inventory")?;
+        let inventory =
+            serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap_or_default();
+        Ok::<_, anyhow::Error>(
+            inventory["inventories"][0]["components"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|c| c["id"].as_str().map(String::from))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let component_id = tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            if let Some(id) = running_components().await?.pop() {
+                break Ok(id);
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the component to start")??;
+
+    // Every call crashes the component, until `wash dev` detects the crash loop
+    let banner = "component is crash-looping — fix and save to retry";
+    tokio::time::timeout(Duration::from_secs(120), async {
+        while !dev_output.read().await.contains(banner) {
+            let _ = Command::new(env!("CARGO_BIN_EXE_wash"))
+                .args([
+                    "call",
+                    &component_id,
+                    "wasi:http/incoming-handler.handle",
+                    "--rpc-port",
+                    &nats_port.to_string(),
+                    "--http-body",
+                    "",
+                ])
+                .kill_on_drop(true)
+                .output()
+                .await
+                .context("failed to call component")?;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await
+    .context("timed out waiting for the crash loop to be detected")??;
+    let output = dev_output.read().await.clone();
+    assert!(output.contains("failed 2 times within 60s, redeploys are paused"));
+    assert!(output.contains("most recent failure:"));
+    assert!(
+        output.contains("panicked at") || output.contains("wasm trap"),
+        "the failure reason should be printed"
+    );
+
+    // The crash-looping component is stopped rather than redeployed
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while !running_components().await?.is_empty() {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await
+    .context("timed out waiting for the crash-looping component to stop")??;
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_missing_link_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    // The fixture component calls `wasi:keyvalue/store`, which nothing is linked to
+    let test_dir = tempfile::tempdir()?;
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    copy_dir(
+        &manifest_dir.join("tests/fixtures/dev/missing-link"),
+        test_dir.path(),
+    )?;
+    copy_dir(
+        &manifest_dir.join("../../examples/rust/components/http-keyvalue-counter/wit/deps"),
+        &test_dir.path().join("wit/deps"),
+    )?;
+
+    let dir = test_dir_with_subfolder("dev_missing_link");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--crash-loop-threshold",
+            "2",
+            "--work-dir",
+            &test_dir.path().to_string_lossy(),
+        ])
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+    let stderr = dev_cmd.stderr.take().context("missing wash dev stderr")?;
+    let dev_output = Arc::new(RwLock::new(String::new()));
+    tokio::spawn({
+        let dev_output = dev_output.clone();
+        async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("{line}");
+                let mut dev_output = dev_output.write().await;
+                dev_output.push_str(&line);
+                dev_output.push('\n');
+            }
+        }
+    });
+
+    let component_id = tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+                .args(["get", "inventory", "--output", "json", "--ctl-port"])
+                .arg(nats_port.to_string())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .context("failed to get inventory")?;
+            let inventory =
+                serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap_or_default();
+            if let Some(id) = inventory["inventories"][0]["components"][0]["id"].as_str() {
+                break Ok(id.to_string());
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the component to start")??;
+
+    // Every call fails on the missing link, which is reported once rather than as a crash loop
+    let call = || async {
+        Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args([
+                "call",
+                &component_id,
+                "wasi:http/incoming-handler.handle",
+                "--rpc-port",
+                &nats_port.to_string(),
+                "--http-body",
+                "",
+            ])
+            .kill_on_drop(true)
+            .output()
+            .await
+    };
+    call().await.context("failed to call component")?;
+    let diagnostic = "[unlinked] called wasi:keyvalue/store but no provider is linked";
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !dev_output.read().await.contains(diagnostic) {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the missing link to be reported")?;
+    for _ in 0..2 {
+        call().await.context("failed to call component")?;
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let output = dev_output.read().await.clone();
+    assert_eq!(
+        output.matches(diagnostic).count(),
+        1,
+        "the missing link should be reported once"
+    );
+    assert!(output.contains(&format!(
+        "wash link put {component_id} <provider-id> wasi keyvalue --interface store"
+    )));
+    assert!(
+        !output.contains("component is crash-looping"),
+        "missing links should not count towards crash loops"
+    );
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_provider_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    // Generate a provider to develop from the `wash new` fixture
+    let test_dir = tempfile::tempdir()?;
+    let wit =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/new/greeter.wit");
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["new", "provider", "dev-greeter", "--no-git-init", "--wit"])
+        .arg(&wit)
+        .args(["--world", "greeter-provider"])
+        .kill_on_drop(true)
+        .current_dir(&test_dir)
+        .status()
+        .await
+        .context("failed to generate provider")?;
+    assert!(status.success());
+    let project_dir = test_dir.path().join("dev-greeter");
+
+    let dir = test_dir_with_subfolder("dev_provider");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--work-dir",
+            &project_dir.to_string_lossy(),
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+
+    // Wait until a provider started by `wash dev` from a build other than `previous` is running
+    let wait_for_build = |previous: Option<String>| async move {
+        loop {
+            let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+                .args(["get", "inventory", "--output", "json", "--ctl-port"])
+                .arg(nats_port.to_string())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .context("failed to get inventory")?;
+            let inventory =
+                serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap_or_default();
+            let build = inventory["inventories"][0]["providers"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find_map(|p| p["annotations"]["wash_dev_build"].as_str());
+            match build {
+                Some(build) if previous.as_deref() != Some(build) => {
+                    break Ok::<_, anyhow::Error>(build.to_string())
+                }
+                _ => tokio::time::sleep(Duration::from_secs(5)).await,
+            }
+        }
+    };
+
+    let first_build = tokio::time::timeout(Duration::from_secs(1200), wait_for_build(None))
+        .await
+        .context("timed out waiting for the provider to start")??;
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    // Editing the provider rebuilds it and swaps the running instance
+    let provider_src = project_dir.join("src/provider.rs");
+    let src = tokio::fs::read_to_string(&provider_src).await?;
+    tokio::fs::write(
+        &provider_src,
+        src.replace("initializing provider", "initializing dev provider"),
+    )
+    .await?;
+    let second_build = tokio::time::timeout(
+        Duration::from_secs(600),
+        wait_for_build(Some(first_build.clone())),
+    )
+    .await
+    .context("timed out waiting for the provider to be swapped")??;
+    assert_ne!(first_build, second_build);
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+/// Ensure `wash dev --share` prints the URL of a tunnel routing to the component, using the local
+/// tunnel backend
+#[tokio::test]
+#[serial_test::serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_dev_share_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+    let mut project_cfg = tokio::fs::read_to_string(project_dir.join("wasmcloud.toml")).await?;
+    project_cfg.push_str("\n[dev.tunnel]\nbackend = \"local\"\n");
+    tokio::fs::write(project_dir.join("wasmcloud.toml"), project_cfg).await?;
+
+    let dir = test_dir_with_subfolder("dev_share");
+    let nats_port = find_open_port().await?;
+    let http_address = format!("127.0.0.1:{}", find_open_port().await?);
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--share",
+            "--share-address",
+            &http_address,
+        ])
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+    let stderr = dev_cmd.stderr.take().context("missing wash dev stderr")?;
+    let dev_output = Arc::new(RwLock::new(String::new()));
+    tokio::spawn({
+        let dev_output = dev_output.clone();
+        async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("{line}");
+                let mut dev_output = dev_output.write().await;
+                dev_output.push_str(&line);
+                dev_output.push('\n');
+            }
+        }
+    });
+
+    // The tunnel is opened once the component is deployed
+    let shared = format!("sharing [{http_address}] at ");
+    let public_url = tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            if let Some(url) = dev_output.read().await.lines().find_map(|line| {
+                line.split_once(&shared)
+                    .map(|(_, url)| url.trim().to_string())
+            }) {
+                break Ok(url);
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the tunnel URL")??;
+
+    // Serve the component on the shared address
+    let wash = |args: Vec<String>| async move {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(&args)
+            .args(["--ctl-port", &nats_port.to_string()])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("failed to execute wash {}", args.join(" ")))?;
+        if !output.status.success() {
+            bail!(
+                "wash {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(output)
+    };
+    let output = wash(
+        ["get", "inventory", "--output", "json"]
+            .map(String::from)
+            .to_vec(),
+    )
+    .await?;
+    let inventory: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let component_id = inventory["inventories"][0]["components"][0]["id"]
+        .as_str()
+        .context("missing component")?
+        .to_string();
+    wash(
+        [
+            "config",
+            "put",
+            "dev-share-http",
+            &format!("address={http_address}"),
+        ]
+        .map(String::from)
+        .to_vec(),
+    )
+    .await?;
+    wash(
+        [
+            "link",
+            "put",
+            "http-server",
+            &component_id,
+            "wasi",
+            "http",
+            "--interface",
+            "incoming-handler",
+            "--source-config",
+            "dev-share-http",
+        ]
+        .map(String::from)
+        .to_vec(),
+    )
+    .await?;
+    wash(
+        [
+            "start",
+            "provider",
+            PROVIDER_HTTPSERVER_OCI_REF,
+            "http-server",
+        ]
+        .map(String::from)
+        .to_vec(),
+    )
+    .await?;
+
+    // Requests to the public URL reach the component
+    let body = tokio::time::timeout(Duration::from_secs(60), async {
+        loop {
+            if let Ok(resp) = reqwest::get(&public_url).await {
+                if let Ok(body) = resp.text().await {
+                    break body;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the component to respond through the tunnel")?;
+    assert!(body.contains("Hello from Rust!"), "unexpected body: {body}");
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    // The tunnel is closed along with the dev loop
+    assert!(reqwest::get(&public_url).await.is_err());
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_wit_change_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    // Start from a variant of the `missing-link` fixture that doesn't import `wasi:keyvalue/store`
+    let test_dir = tempfile::tempdir()?;
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture = manifest_dir.join("tests/fixtures/dev/missing-link");
+    copy_dir(&fixture, test_dir.path())?;
+    copy_dir(
+        &manifest_dir.join("../../examples/rust/components/http-keyvalue-counter/wit/deps"),
+        &test_dir.path().join("wit/deps"),
+    )?;
+    let hello_world = r"package test:unlinked;
+
+world unlinked {
+    export wasi:http/incoming-handler@0.2.0;
+}
+";
+    let hello_lib = r#"wit_bindgen::generate!();
+
+use exports::wasi::http::incoming_handler::Guest;
+use wasi::http::types::*;
+
+struct Unlinked;
+
+impl Guest for Unlinked {
+    fn handle(_request: IncomingRequest, response_out: ResponseOutparam) {
+        let response = OutgoingResponse::new(Fields::new());
+        let response_body = response.body().unwrap();
+        ResponseOutparam::set(response_out, Ok(response));
+        response_body
+            .write()
+            .unwrap()
+            .blocking_write_and_flush(b"hello")
+            .unwrap();
+        OutgoingBody::finish(response_body, None).expect("failed to finish response body");
+    }
+}
+
+export!(Unlinked);
+"#;
+    let world_path = test_dir.path().join("wit/world.wit");
+    let lib_path = test_dir.path().join("src/lib.rs");
+    tokio::fs::write(&world_path, hello_world).await?;
+    tokio::fs::write(&lib_path, hello_lib).await?;
+
+    let dir = test_dir_with_subfolder("dev_wit_change");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--work-dir",
+            &test_dir.path().to_string_lossy(),
+        ])
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+    let stderr = dev_cmd.stderr.take().context("missing wash dev stderr")?;
+    let dev_output = Arc::new(RwLock::new(String::new()));
+    tokio::spawn({
+        let dev_output = dev_output.clone();
+        async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("{line}");
+                let mut dev_output = dev_output.write().await;
+                dev_output.push_str(&line);
+                dev_output.push('\n');
+            }
+        }
+    });
+
+    let wash = |args: Vec<String>| async move {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(&args)
+            .args(["--ctl-port", &nats_port.to_string()])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("failed to execute wash {}", args.join(" ")))?;
+        if !output.status.success() {
+            bail!(
+                "wash {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(output)
+    };
+    let component_id = tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            let output = wash(
+                ["get", "inventory", "--output", "json"]
+                    .map(String::from)
+                    .to_vec(),
+            )
+            .await?;
+            let inventory =
+                serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap_or_default();
+            if let Some(id) = inventory["inventories"][0]["components"][0]["id"].as_str() {
+                break Ok(id.to_string());
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the component to start")??;
+    let wait_for_output = |expected: String| {
+        let dev_output = dev_output.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(600), async {
+                while !dev_output.read().await.contains(&expected) {
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                }
+            })
+            .await
+            .with_context(|| format!("timed out waiting for `{expected}`"))
+        }
+    };
+
+    // Adding the import mid-session is reported with how to link it
+    tokio::fs::copy(fixture.join("wit/world.wit"), &world_path).await?;
+    tokio::fs::copy(fixture.join("src/lib.rs"), &lib_path).await?;
+    wait_for_output(format!(
+        "added import wasi:keyvalue/store → add an override with `wash link put {component_id} <provider-id> wasi keyvalue --interface store`"
+    ))
+    .await?;
+
+    // Removing it again deletes the link the component no longer uses
+    wash(
+        [
+            "link",
+            "put",
+            &component_id,
+            "keyvalue-provider",
+            "wasi",
+            "keyvalue",
+            "--interface",
+            "store",
+        ]
+        .map(String::from)
+        .to_vec(),
+    )
+    .await?;
+    tokio::fs::write(&world_path, hello_world).await?;
+    tokio::fs::write(&lib_path, hello_lib).await?;
+    wait_for_output("removed import wasi:keyvalue/store → unlinking [keyvalue-provider]".into())
+        .await?;
+    let output = wash(
+        ["get", "links", "--output", "json"]
+            .map(String::from)
+            .to_vec(),
+    )
+    .await?;
+    let links = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+    assert!(
+        links["links"]
+            .as_array()
+            .context("links should be an array")?
+            .iter()
+            .all(|link| link["wit_package"] != "keyvalue"),
+        "{links}"
+    );
+    if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+        bail!("dev command exited unexpectedly: {exit_status}");
+    }
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let _ = tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_ctrl_c_mid_build_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+
+    let dir = test_dir_with_subfolder("dev_ctrl_c_mid_build");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--work-dir",
+            &project_dir.to_string_lossy(),
+        ])
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+    let stderr = dev_cmd.stderr.take().context("missing wash dev stderr")?;
+    let dev_output = Arc::new(RwLock::new(String::new()));
+    tokio::spawn({
+        let dev_output = dev_output.clone();
+        async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("{line}");
+                let mut dev_output = dev_output.write().await;
+                dev_output.push_str(&line);
+                dev_output.push('\n');
+            }
+        }
+    });
+
+    // Cargo processes building the component
+    let build_processes = || async {
+        let output = Command::new("pgrep")
+            .args(["-f", "cargo build --release --target"])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("failed to list processes")?;
+        Ok::<_, anyhow::Error>(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    // Wait until the component is being built
+    tokio::time::timeout(Duration::from_secs(120), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            if dev_output
+                .read()
+                .await
+                .contains("Starting build of [hello]")
+                && !build_processes().await?.is_empty()
+            {
+                break Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the build to start")??;
+
+    // Send ctrl + c to wash only, as a terminal would not reach the build in its own process group
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    tokio::time::timeout(Duration::from_secs(60), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")??;
+
+    // The build was stopped and awaited rather than orphaned
+    let output = dev_output.read().await.clone();
+    assert!(output.contains("stopping build"), "{output}");
+    let orphans = build_processes().await?;
+    assert!(
+        orphans.is_empty(),
+        "cargo processes survived wash dev: {orphans:?}"
+    );
+    assert!(
+        !project_dir.join("build/http_hello_world_s.wasm").exists(),
+        "the interrupted build should not produce a component"
+    );
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_dev_attach_serial() -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use wash_lib::cli::dev_session::dev_session_lock_path;
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+    let work_dir = project_dir.to_string_lossy().to_string();
+
+    let dir = test_dir_with_subfolder("dev_attach");
+    let nats_port = find_open_port().await?;
+    let mut nats = start_nats(nats_port, &dir).await?;
+
+    let mut dev_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "dev",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-connect-only",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--use-host-subprocess",
+            "--disable-wadm",
+            "--work-dir",
+            &work_dir,
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev")?;
+
+    // Wait until the component was built and deployed
+    let signed_file_path = project_dir.join("build/http_hello_world_s.wasm");
+    tokio::time::timeout(Duration::from_secs(1200), async {
+        loop {
+            if let Ok(Some(exit_status)) = dev_cmd.try_wait() {
+                bail!("dev command exited unexpectedly: {exit_status}");
+            }
+            if signed_file_path.exists() {
+                break Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for the component to be built")??;
+    let lock_path = dev_session_lock_path(&project_dir);
+    assert!(lock_path.exists(), "dev session should be locked");
+
+    // A second dev loop for the same project is refused
+    let second = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["dev", "--work-dir", &work_dir])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed running second wash dev")?;
+    assert!(!second.status.success(), "second dev loop should fail");
+    let stderr = String::from_utf8_lossy(&second.stderr);
+    assert!(stderr.contains("--attach"), "{stderr}");
+
+    let mut attach_cmd = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["dev", "--attach", "--events", "--work-dir", &work_dir])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed running wash dev --attach")?;
+    let stdout = attach_cmd.stdout.take().context("missing attach stdout")?;
+    let stderr = attach_cmd.stderr.take().context("missing attach stderr")?;
+    let attach_output = Arc::new(RwLock::new(String::new()));
+    for stream in [
+        Box::pin(stdout) as std::pin::Pin<Box<dyn tokio::io::AsyncRead + Send>>,
+        Box::pin(stderr),
+    ] {
+        let attach_output = attach_output.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stream).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                eprintln!("[attached] {line}");
+                let mut attach_output = attach_output.write().await;
+                attach_output.push_str(&line);
+                attach_output.push('\n');
+            }
+        });
+    }
+    let wait_for_output = |expected: &'static str| {
+        let attach_output = attach_output.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(600), async {
+                while !attach_output.read().await.contains(expected) {
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                }
+            })
+            .await
+            .with_context(|| format!("timed out waiting for `{expected}`"))
+        }
+    };
+    wait_for_output("attached to the dev session").await?;
+
+    // Trigger a rebuild, which the attached session follows
+    let lib_path = project_dir.join("src/lib.rs");
+    let mut lib = tokio::fs::read_to_string(&lib_path).await?;
+    lib.push_str("\n// trigger a rebuild\n");
+    tokio::fs::write(&lib_path, lib).await?;
+    wait_for_output(r#"{"type":"build_started","component":"hello"}"#).await?;
+    wait_for_output(r#""type":"build_succeeded","component":"hello""#).await?;
+
+    let process_pid = dev_cmd.id().context("failed to get child process pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(process_pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    tokio::time::timeout(Duration::from_secs(15), dev_cmd.wait())
+        .await
+        .context("dev command did not exit")??;
+
+    // The attached session ends along with the primary session, which removes its lock
+    let status = tokio::time::timeout(Duration::from_secs(15), attach_cmd.wait())
+        .await
+        .context("attached dev session did not exit")??;
+    assert!(status.success(), "attached dev session failed: {status}");
+    wait_for_output(r#"{"type":"stopping"}"#).await?;
+    assert!(!lock_path.exists(), "dev session lock should be removed");
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    nats.kill().await.map_err(|e| anyhow!(e))?;
+    wait_for_no_nats()
+        .await
+        .context("nats instance failed to exit cleanly (processes still left over)")?;
+
+    Ok(())
+}
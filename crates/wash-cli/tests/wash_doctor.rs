@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+use wash_lib::start::WADM_PID;
+
+mod common;
+use common::find_open_port;
+
+/// Run `wash doctor` with the given home directory, returning the status of the check with the given name
+async fn doctor_check_status(home: &Path, check: &str, fix: bool) -> Result<String> {
+    // Point the connectivity check at a port nothing listens on, to keep the check fast
+    let port = find_open_port().await?.to_string();
+    let mut args = vec!["doctor", "--ctl-port", &port, "--output", "json"];
+    if fix {
+        args.push("--fix");
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(args)
+        .env("HOME", home)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to run wash doctor")?;
+    assert!(output.status.success(), "wash doctor failed");
+
+    let output: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse output of wash doctor")?;
+    let checks = output["checks"]
+        .as_array()
+        .context("wash doctor output should contain checks")?;
+    checks
+        .iter()
+        .find(|c| c["name"] == check)
+        .and_then(|c| c["status"].as_str())
+        .map(ToString::to_string)
+        .with_context(|| format!("wash doctor output should contain check [{check}]"))
+}
+
+#[tokio::test]
+async fn integration_doctor_fixes_stale_pid_file() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let install_dir = home.path().join(".wash").join("downloads");
+    tokio::fs::create_dir_all(&install_dir).await?;
+
+    // PIDs above the maximum PID on Linux (2^22) can never belong to a running process
+    let pid_file = install_dir.join(WADM_PID);
+    tokio::fs::write(&pid_file, "4294967").await?;
+
+    let check = format!("pid file {WADM_PID}");
+    assert_eq!(
+        doctor_check_status(home.path(), &check, false).await?,
+        "warn"
+    );
+    assert!(
+        pid_file.exists(),
+        "doctor should not remove files without --fix"
+    );
+
+    assert_eq!(
+        doctor_check_status(home.path(), &check, true).await?,
+        "fixed"
+    );
+    assert!(
+        !pid_file.exists(),
+        "doctor --fix should remove the stale PID file"
+    );
+
+    Ok(())
+}
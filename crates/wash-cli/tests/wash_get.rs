@@ -1,13 +1,17 @@
 mod common;
 
-use common::TestWashInstance;
+use common::{TestWashInstance, HELLO_OCI_REF};
+
+use std::process::Stdio;
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 use serial_test::serial;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use wash_lib::cli::output::{
-    GetClaimsCommandOutput, GetHostInventoriesCommandOutput, GetHostsCommandOutput,
-    LinkQueryCommandOutput,
+    GetClaimsCommandOutput, GetHostDetailsCommandOutput, GetHostInventoriesCommandOutput,
+    GetHostsCommandOutput, LinkQueryCommandOutput,
 };
 
 #[tokio::test]
@@ -42,6 +46,168 @@ async fn integration_get_hosts_serial() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn integration_get_hosts_columns_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let ctl_port = wash_instance.nats_port.to_string();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "hosts", "--output", "json", "--ctl-port", &ctl_port])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get hosts")?;
+    let cmd_output: GetHostsCommandOutput = serde_json::from_slice(&output.stdout)?;
+    let friendly_name = &cmd_output.hosts[0].friendly_name;
+
+    // Selected columns are printed in order, without the header row
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "hosts",
+            "--columns",
+            "name,id",
+            "--no-headers",
+            "--ctl-port",
+            &ctl_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get hosts --columns")?;
+    assert!(output.status.success(), "executed get hosts with columns");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("Friendly name"),
+        "headers should be omitted: {stdout}"
+    );
+    // The first run of wash may be followed by a suggestion to install shell completions
+    let rows: Vec<_> = stdout
+        .lines()
+        .filter(|line| line.contains(&wash_instance.host_id))
+        .collect();
+    let [line] = rows.as_slice() else {
+        bail!("expected a single host row, got {stdout}");
+    };
+    assert_eq!(
+        line.split_whitespace().collect::<Vec<_>>(),
+        [friendly_name.as_str(), wash_instance.host_id.as_str()]
+    );
+
+    // Wide output includes the columns requiring the host inventory
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "hosts", "--output", "wide", "--ctl-port", &ctl_port])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get hosts --output wide")?;
+    assert!(
+        output.status.success(),
+        "executed get hosts with wide output"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let header = stdout
+        .lines()
+        .find(|line| line.starts_with("Host ID"))
+        .context("wide output should have a header row")?;
+    for column in ["Friendly name", "Labels", "Components", "Max instances"] {
+        assert!(header.contains(column), "missing {column} in {header}");
+    }
+
+    // Unknown columns are rejected, listing the valid ones
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "hosts",
+            "--columns",
+            "bogus",
+            "--ctl-port",
+            &ctl_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get hosts --columns bogus")?;
+    assert!(!output.status.success(), "unknown columns should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown column `bogus`, valid columns are: id, name, uptime"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+async fn get_host_details(wash_instance: &TestWashInstance) -> Result<GetHostDetailsCommandOutput> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "hosts",
+            "--detailed",
+            "--sort",
+            "components",
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get hosts --detailed")?;
+    if !output.status.success() {
+        bail!(
+            "failed to execute `wash get hosts --detailed`, stdout: {} \nstderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    serde_json::from_slice(&output.stdout).context("failed to parse output of `wash get hosts`")
+}
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_get_hosts_detailed_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let cmd_output = get_host_details(&wash_instance).await?;
+    assert!(cmd_output.success, "command returned success");
+    assert_eq!(cmd_output.hosts.len(), 1, "hosts contains one host");
+    let host = &cmd_output.hosts[0];
+    assert_eq!(host.host.id, wash_instance.host_id);
+    assert!(host.os.is_some(), "os is taken from the host labels");
+    assert!(host.arch.is_some(), "arch is taken from the host labels");
+    assert_eq!(host.provider_count, Some(0), "host has no providers");
+    let components = host
+        .component_count
+        .context("detailed output should include the component count")?;
+
+    wash_instance
+        .start_component(HELLO_OCI_REF, "hello_hosts_detailed")
+        .await?;
+
+    // The component is reported once the host finished starting it
+    tokio::time::timeout(Duration::from_secs(15), async {
+        loop {
+            let host = get_host_details(&wash_instance).await?.hosts.remove(0);
+            if host.component_count == Some(components + 1) {
+                assert!(
+                    host.max_instances.is_some_and(|n| n > 0),
+                    "max instances include the new component"
+                );
+                return anyhow::Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    })
+    .await
+    .context("component count never incremented")??;
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn integration_get_links_serial() -> Result<()> {
@@ -135,6 +301,150 @@ async fn integration_get_host_inventory_serial() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_get_host_inventory_watch_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "inventory",
+            &wash_instance.host_id,
+            "--watch",
+            "--interval-ms",
+            "500",
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn get inventory --watch")?;
+    let mut documents = BufReader::new(watch.stdout.take().context("missing stdout")?).lines();
+
+    // Every poll is emitted as a single line of JSON
+    let first = documents
+        .next_line()
+        .await?
+        .context("watch exited before the first poll")?;
+    let first: GetHostInventoriesCommandOutput = serde_json::from_str(&first)?;
+    assert!(first.success, "first poll returned success");
+    assert!(
+        first.inventories[0].components.is_empty(),
+        "host inventory contains no components before starting one"
+    );
+
+    let component_id = wash_instance
+        .start_component(HELLO_OCI_REF, "hello_inventory_watch")
+        .await?
+        .component_id
+        .context("missing component_id from start command output")?;
+
+    // Subsequent polls pick up the new component and report it as added
+    tokio::time::timeout(Duration::from_secs(15), async {
+        loop {
+            let line = documents
+                .next_line()
+                .await?
+                .context("watch exited before the component appeared")?;
+            let doc: serde_json::Value = serde_json::from_str(&line)?;
+            let has_component = doc["inventories"][0]["components"]
+                .as_array()
+                .is_some_and(|c| c.iter().any(|c| c["id"] == component_id.as_str()));
+            if has_component {
+                assert!(
+                    doc["added"]
+                        .as_array()
+                        .is_some_and(|a| a.iter().any(|a| a["id"] == component_id.as_str())),
+                    "new component is reported as added"
+                );
+                return anyhow::Ok(());
+            }
+        }
+    })
+    .await
+    .context("component never appeared in watched inventory")??;
+
+    watch.kill().await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_get_events_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let dir = tempfile::tempdir()?;
+    let saved = dir.path().join("events.ndjson");
+
+    let mut events = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "events",
+            "--follow",
+            "--type",
+            "component_scaled",
+            "--save",
+            &saved.to_string_lossy(),
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn get events --follow")?;
+    let mut lines = BufReader::new(events.stdout.take().context("missing stdout")?).lines();
+    // Give wash time to subscribe before the component is started
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let component_id = wash_instance
+        .start_component(HELLO_OCI_REF, "hello_events")
+        .await?
+        .component_id
+        .context("missing component_id from start command output")?;
+
+    let expected = format!("component_scaled component_id={component_id} ");
+    let line = tokio::time::timeout(Duration::from_secs(15), async {
+        loop {
+            let line = lines
+                .next_line()
+                .await?
+                .context("get events exited before the component scaled")?;
+            // Only the requested event types are shown
+            assert!(
+                line.contains(" component_scaled "),
+                "unexpected event: {line}"
+            );
+            if line.contains(&expected) {
+                return anyhow::Ok(line);
+            }
+        }
+    })
+    .await
+    .context("component_scaled event was never shown")??;
+    events.kill().await?;
+
+    // Saved events are shown the same way when replayed
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["get", "events", "--replay", &saved.to_string_lossy()])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get events --replay")?;
+    assert!(output.status.success(), "executed get events --replay");
+    let replayed = String::from_utf8(output.stdout)?;
+    assert!(
+        replayed.lines().any(|replayed| replayed == line),
+        "replayed events contain [{line}]: {replayed}"
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 // TODO: reenable after #1649 merges and v1.0.0-alpha.2 is released
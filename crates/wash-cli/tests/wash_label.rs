@@ -5,7 +5,7 @@ use common::TestWashInstance;
 use anyhow::{Context, Result};
 use serial_test::serial;
 use tokio::process::Command;
-use wash_lib::cli::output::LabelHostCommandOutput;
+use wash_lib::cli::output::{GetHostInventoriesCommandOutput, LabelHostCommandOutput};
 
 #[tokio::test]
 #[serial]
@@ -37,3 +37,119 @@ async fn integration_label_host_serial() -> Result<()> {
     );
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn integration_label_host_from_file_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let dir = tempfile::tempdir()?;
+    let labels_file = dir.path().join("labels.toml");
+    tokio::fs::write(
+        &labels_file,
+        "\"team.name\" = \"platform\"\n\"team.tier\" = 1\nzone = \"us-east-1a\"\n",
+    )
+    .await?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "label",
+            &wash_instance.host_id,
+            "--from-file",
+            &labels_file.to_string_lossy(),
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash label")?;
+
+    let cmd_output: LabelHostCommandOutput = serde_json::from_slice(&output.stdout)?;
+    assert!(cmd_output.success, "command returned success");
+    assert!(!cmd_output.deleted);
+    assert_eq!(cmd_output.hosts.len(), 1);
+    assert_eq!(cmd_output.hosts[0].host_id, wash_instance.host_id);
+    assert!(cmd_output.hosts[0].labels.iter().all(|l| l.success));
+    assert_eq!(
+        cmd_output.processed,
+        vec![
+            (String::from("team.name"), String::from("platform")),
+            (String::from("team.tier"), String::from("1")),
+            (String::from("zone"), String::from("us-east-1a")),
+        ],
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_label_host_remove_prefix_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let ctl_port = wash_instance.nats_port.to_string();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "label",
+            &wash_instance.host_id,
+            "team.name=platform,team.tier=1,zone=us-east-1a",
+            "--output",
+            "json",
+            "--ctl-port",
+            &ctl_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash label")?;
+    let cmd_output: LabelHostCommandOutput = serde_json::from_slice(&output.stdout)?;
+    assert!(cmd_output.success, "labels were applied");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "label",
+            &wash_instance.host_id,
+            "--remove-prefix",
+            "team.",
+            "--output",
+            "json",
+            "--ctl-port",
+            &ctl_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash label")?;
+    let cmd_output: LabelHostCommandOutput = serde_json::from_slice(&output.stdout)?;
+    assert!(cmd_output.success, "command returned success");
+    assert!(cmd_output.deleted);
+    let mut removed = cmd_output
+        .processed
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    removed.sort();
+    assert_eq!(removed, vec!["team.name", "team.tier"]);
+
+    // The remaining label should be untouched
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "inventory",
+            &wash_instance.host_id,
+            "--output",
+            "json",
+            "--ctl-port",
+            &ctl_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash get inventory")?;
+    let inventory: GetHostInventoriesCommandOutput = serde_json::from_slice(&output.stdout)?;
+    let labels = &inventory.inventories[0].labels;
+    assert!(!labels.keys().any(|k| k.starts_with("team.")));
+    assert_eq!(labels.get("zone").map(String::as_str), Some("us-east-1a"));
+    Ok(())
+}
@@ -3,8 +3,10 @@ mod common;
 use common::TestWashInstance;
 
 use anyhow::{Context, Result};
+use serde_json::Value;
 use serial_test::serial;
 use tokio::process::Command;
+use wash_lib::cli::link::LinkExport;
 use wash_lib::cli::output::LinkQueryCommandOutput;
 
 #[tokio::test]
@@ -38,3 +40,145 @@ async fn integration_link_serial() -> Result<()> {
 
     Ok(())
 }
+
+/// Run a `wash` subcommand against the given [`TestWashInstance`], returning its JSON output
+async fn wash_json(instance: &TestWashInstance, args: &[&str]) -> Result<Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(args)
+        .args([
+            "--ctl-port",
+            &instance.nats_port.to_string(),
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("failed to execute wash {}", args.join(" ")))?;
+    assert!(
+        output.status.success(),
+        "wash {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("failed to parse output of wash {}", args.join(" ")))
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_link_export_import_serial() -> Result<()> {
+    let wash = TestWashInstance::create().await?;
+    let exported = wash.test_dir.join("links.json");
+    let reexported = wash.test_dir.join("links-reexported.json");
+    let empty = wash.test_dir.join("empty.json");
+    tokio::fs::write(&empty, r#"{"version":1,"links":[]}"#).await?;
+
+    wash_json(&wash, &["config", "put", "export-config", "key=value"]).await?;
+    wash_json(
+        &wash,
+        &[
+            "link",
+            "put",
+            "kv-component",
+            "kv-provider",
+            "wasi",
+            "keyvalue",
+            "--interface",
+            "store",
+            "--interface",
+            "atomics",
+            "--target-config",
+            "export-config",
+        ],
+    )
+    .await?;
+    wash_json(
+        &wash,
+        &[
+            "link",
+            "put",
+            "http-provider",
+            "http-component",
+            "wasi",
+            "http",
+            "--interface",
+            "incoming-handler",
+            "--source-config",
+            "export-config",
+            "--link-name",
+            "secondary",
+        ],
+    )
+    .await?;
+
+    let output = wash_json(
+        &wash,
+        &[
+            "link",
+            "export",
+            "--output-file",
+            &exported.to_string_lossy(),
+        ],
+    )
+    .await?;
+    assert_eq!(output["links"], 2);
+
+    // Empty the lattice of its links and configuration
+    let output = wash_json(
+        &wash,
+        &[
+            "link",
+            "import",
+            &empty.to_string_lossy(),
+            "--prune",
+            "--yes",
+        ],
+    )
+    .await?;
+    assert_eq!(output["deleted"], 2);
+    wash_json(&wash, &["config", "del", "export-config"]).await?;
+
+    // A dry run reports the changes without making them
+    let output = wash_json(
+        &wash,
+        &["link", "import", &exported.to_string_lossy(), "--dry-run"],
+    )
+    .await?;
+    assert_eq!(output["created"], 2);
+    let output = wash_json(&wash, &["link", "query"]).await?;
+    assert_eq!(output["links"], Value::Array(vec![]));
+
+    // Missing configuration is reported up front, and the links are created regardless
+    let output = wash_json(&wash, &["link", "import", &exported.to_string_lossy()]).await?;
+    assert_eq!(output["created"], 2);
+    assert_eq!(output["updated"], 0);
+    assert_eq!(output["skipped"], 0);
+    assert_eq!(output["warnings"].as_array().map(Vec::len), Some(1));
+
+    // Importing the same links again changes nothing
+    let output = wash_json(&wash, &["link", "import", &exported.to_string_lossy()]).await?;
+    assert_eq!(output["created"], 0);
+    assert_eq!(output["skipped"], 2);
+
+    wash_json(
+        &wash,
+        &[
+            "link",
+            "export",
+            "--output-file",
+            &reexported.to_string_lossy(),
+        ],
+    )
+    .await?;
+    let first: LinkExport = serde_json::from_slice(&tokio::fs::read(&exported).await?)?;
+    let second: LinkExport = serde_json::from_slice(&tokio::fs::read(&reexported).await?)?;
+    assert_eq!(first, second, "links were not recreated identically");
+    assert_eq!(first.links.len(), 2);
+    assert_eq!(
+        first.config_names().into_iter().collect::<Vec<_>>(),
+        ["export-config"]
+    );
+
+    Ok(())
+}
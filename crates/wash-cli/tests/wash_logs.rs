@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+
+mod common;
+use common::{TestWashInstance, PROVIDER_HTTPSERVER_OCI_REF};
+
+#[tokio::test]
+#[serial]
+async fn integration_logs_host_and_provider_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    wash_instance
+        .start_provider(PROVIDER_HTTPSERVER_OCI_REF, "httpserver_logs")
+        .await?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "logs",
+            "--since",
+            "1m",
+            "--host-id",
+            &wash_instance.host_id,
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to run wash logs")?;
+    assert!(output.status.success(), "wash logs failed");
+
+    let output: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse output of wash logs")?;
+    let entries = output["entries"]
+        .as_array()
+        .context("wash logs output should contain entries")?;
+    assert!(
+        entries.iter().any(|entry| entry["source"] == "host"),
+        "expected at least one log line from the host"
+    );
+    assert!(
+        entries.iter().any(|entry| entry["source"] == "provider"),
+        "expected at least one log line from the provider"
+    );
+
+    wash_instance.stop_provider("httpserver_logs", None).await?;
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+
+#[tokio::test]
+async fn integration_new_provider_from_wit() -> Result<()> {
+    let wit = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/new/greeter.wit");
+    // This tests runs against a temp directory since cargo gets confused
+    // about workspace projects if done from within wash
+    let root_dir = tempfile::tempdir()?;
+    let project_dir = root_dir.path().join("greeter");
+    let stdout = File::create(root_dir.path().join("wash-test.greeter.stdout.log"))?;
+
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["new", "provider", "greeter", "--no-git-init", "--wit"])
+        .arg(&wit)
+        .args(["--world", "greeter-provider"])
+        .kill_on_drop(true)
+        .current_dir(&root_dir)
+        .stdout(stdout.try_clone()?)
+        .status()
+        .await
+        .context("failed to generate provider")?;
+    assert!(status.success());
+    assert!(project_dir.join("wit/greeter.wit").exists());
+
+    // Stubs are generated for the exported functions
+    let provider = tokio::fs::read_to_string(project_dir.join("src/provider.rs")).await?;
+    assert!(provider.contains(
+        "impl exports::example::greeter::greet::Handler<Option<Context>> for GreeterProvider {"
+    ));
+    assert!(provider.contains("    /// Greet someone, returning the greeting\n    async fn greet("));
+    assert!(provider.contains("    /// Count the words of a text\n    async fn count_words("));
+    assert!(provider.contains("pub struct NotifyLinkConfig {"));
+
+    // The generated project compiles
+    let status = Command::new("cargo")
+        .args(["check"])
+        .kill_on_drop(true)
+        .current_dir(&project_dir)
+        .stdout(stdout)
+        .status()
+        .await
+        .context("failed to check generated provider")?;
+    assert!(status.success(), "generated provider does not compile");
+    Ok(())
+}
+
+#[tokio::test]
+async fn integration_new_provider_from_wit_requires_world() -> Result<()> {
+    let wit = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/new/greeter.wit");
+    let root_dir = tempfile::tempdir()?;
+
+    // The fixture contains multiple worlds
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["new", "provider", "greeter", "--no-git-init", "--wit"])
+        .arg(&wit)
+        .kill_on_drop(true)
+        .current_dir(&root_dir)
+        .output()
+        .await
+        .context("failed to run wash new")?;
+    assert!(!output.status.success());
+    assert!(!root_dir.path().join("greeter").exists());
+    Ok(())
+}
@@ -27,6 +27,114 @@ fn integration_par_create_and_insert() {
 
     remove_dir_all(test_dir).unwrap();
 }
+
+#[test]
+/// Validates an archive built from two binaries, and detects a corrupted claims signature
+fn integration_par_validate() {
+    const SUBFOLDER: &str = "par_validate";
+    const OTHER_ARCH: &str = "mips64-android";
+    let test_dir = test_dir_with_subfolder(SUBFOLDER);
+    let native = test_dir_file(SUBFOLDER, "native.bin");
+    File::create(&native)
+        .unwrap()
+        .write_all(b"native provider")
+        .unwrap();
+    let other = test_dir_file(SUBFOLDER, "android.bin");
+    File::create(&other)
+        .unwrap()
+        .write_all(b"android provider")
+        .unwrap();
+    let archive = test_dir_file(SUBFOLDER, "validate.par");
+
+    let create = wash()
+        .args([
+            "par",
+            "create",
+            "-b",
+            native.to_str().unwrap(),
+            "--target-binary",
+            &format!("{OTHER_ARCH}={}", other.to_str().unwrap()),
+            "-n",
+            "Validated",
+            "-v",
+            "TestRunner",
+            "--disable-keygen",
+            "--issuer",
+            "SAACTTUPKR55VUWUDK7GJ5SU5KGED455FR7BDO46RUVOTHUWKBLECLH2UU",
+            "--subject",
+            "SVAOZUSBWWFL65P255DOHIETPTXUQMM5ETLSYPITI5G4K4HI6M2CDAPWAU",
+            "--destination",
+            archive.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to create provider archive file");
+    assert!(create.status.success());
+
+    let validate = wash()
+        .args(["par", "validate", archive.to_str().unwrap(), "-o", "json"])
+        .output()
+        .expect("failed to validate provider archive file");
+    assert!(validate.status.success());
+    let output = get_json_output(validate).unwrap();
+    assert_eq!(output["validation"]["valid"], true);
+    let binaries = output["validation"]["archive"]["binaries"]
+        .as_array()
+        .unwrap();
+    assert_eq!(binaries.len(), 2);
+    assert!(binaries
+        .iter()
+        .any(|binary| binary["target"] == OTHER_ARCH && binary["size"] == 16));
+
+    // A binary is required for the architecture of the host
+    let validate = wash()
+        .args([
+            "par",
+            "validate",
+            archive.to_str().unwrap(),
+            "--arch",
+            "riscv64-freebsd",
+        ])
+        .output()
+        .expect("failed to validate provider archive file");
+    assert!(!validate.status.success());
+    let stderr = String::from_utf8_lossy(&validate.stderr);
+    assert!(
+        stderr.contains("no binary for freebsd/riscv64; archive contains"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("android/mips64"), "{stderr}");
+
+    // Corrupt the signature of the embedded claims, keeping its length
+    let mut bytes = std::fs::read(&archive).unwrap();
+    let start = bytes
+        .windows(3)
+        .position(|window| window == b"eyJ")
+        .expect("claims not found in archive");
+    let end = start
+        + bytes[start..]
+            .iter()
+            .position(|b| *b == 0)
+            .expect("claims are not terminated");
+    let signature = start
+        + bytes[start..end]
+            .iter()
+            .rposition(|b| *b == b'.')
+            .expect("claims have no signature")
+        + 1;
+    bytes[signature] = if bytes[signature] == b'A' { b'B' } else { b'A' };
+    std::fs::write(&archive, bytes).unwrap();
+
+    let validate = wash()
+        .args(["par", "validate", archive.to_str().unwrap()])
+        .output()
+        .expect("failed to validate provider archive file");
+    assert!(!validate.status.success());
+    let stderr = String::from_utf8_lossy(&validate.stderr);
+    assert!(stderr.contains("claims signature is invalid"), "{stderr}");
+
+    remove_dir_all(test_dir).unwrap();
+}
+
 #[test]
 fn integration_par_inspect() {
     const SUBFOLDER: &str = "par_inspect";
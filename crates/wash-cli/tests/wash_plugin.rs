@@ -0,0 +1,116 @@
+#![cfg(unix)]
+
+use std::ffi::OsString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+
+/// Build a `wash` command with the given home directory and an executable plugin directory
+/// prepended to the `PATH`
+fn wash(home: &Path, bin: &Path) -> Result<Command> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path: OsString = std::env::join_paths(
+        [bin.to_path_buf()]
+            .into_iter()
+            .chain(std::env::split_paths(&path)),
+    )?;
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wash"));
+    cmd.env("HOME", home)
+        .env("PATH", path)
+        .env("WASH_PLUGIN_DIR", home.join("plugins"))
+        .env_remove("WASMCLOUD_LATTICE")
+        .kill_on_drop(true);
+    Ok(cmd)
+}
+
+/// Write an executable plugin which prints its arguments and context, and exits with 3
+async fn write_plugin(bin: &Path, name: &str) -> Result<()> {
+    let path = bin.join(format!("wash-{name}"));
+    tokio::fs::write(
+        &path,
+        r#"#!/bin/sh
+echo "args: $*"
+echo "context: $WASH_CONTEXT"
+echo "lattice: $WASMCLOUD_LATTICE"
+echo "context json: $WASH_CONTEXT_JSON"
+exit 3
+"#,
+    )
+    .await?;
+    tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn integration_plugin_executable_passthrough() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let bin = tempfile::tempdir()?;
+    write_plugin(bin.path(), "acme-deploy").await?;
+
+    let output = wash(home.path(), bin.path())?
+        .args(["acme-deploy", "--env", "staging", "my-app"])
+        .output()
+        .await
+        .context("failed to run wash acme-deploy")?;
+    assert_eq!(output.status.code(), Some(3), "exit code is propagated");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("args: --env staging my-app"),
+        "arguments are passed through: {stdout}"
+    );
+    assert!(
+        stdout.contains("context: host_config"),
+        "context is passed: {stdout}"
+    );
+    assert!(
+        stdout.contains("lattice: default"),
+        "lattice is passed: {stdout}"
+    );
+    let json = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("context json: "))
+        .context("context JSON is missing")?;
+    let ctx: serde_json::Value = serde_json::from_str(json).context("invalid context JSON")?;
+    assert_eq!(ctx["name"], "host_config");
+    assert_eq!(ctx["lattice"], "default");
+    for secret in ["cluster_seed", "ctl_seed", "ctl_jwt", "rpc_seed", "rpc_jwt"] {
+        assert!(ctx.get(secret).is_none(), "{secret} is passed to plugins");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn integration_plugin_unknown_subcommand_suggestion() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let bin = tempfile::tempdir()?;
+    write_plugin(bin.path(), "acme-deploy").await?;
+
+    let output = wash(home.path(), bin.path())?
+        .arg("acme-deplyo")
+        .output()
+        .await
+        .context("failed to run wash acme-deplyo")?;
+    assert!(!output.status.success(), "unknown subcommand should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'acme-deploy'"),
+        "matching plugin should be suggested: {stderr}"
+    );
+
+    let output = wash(home.path(), bin.path())?
+        .args(["plugin", "list", "--output", "json"])
+        .output()
+        .await
+        .context("failed to run wash plugin list")?;
+    assert!(output.status.success(), "wash plugin list failed");
+    let plugins: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse plugin list")?;
+    assert_eq!(plugins["wash-acme-deploy"]["kind"], "executable");
+    assert_eq!(plugins["wash-acme-deploy"]["id"], "acme-deploy");
+
+    Ok(())
+}
@@ -163,6 +163,73 @@ fn integration_reg_push_basic() {
     remove_dir_all(push_dir).unwrap();
 }
 
+// NOTE: This test will fail without a local docker registry running
+#[test]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+fn integration_reg_pull_mirror() {
+    const SUBFOLDER: &str = "pull_mirror";
+    let pull_dir = test_dir_with_subfolder(SUBFOLDER);
+
+    let pull_echo_wasm = test_dir_file(SUBFOLDER, "echo.wasm");
+    let mirrored_echo_wasm = test_dir_file(SUBFOLDER, "echo_mirrored.wasm");
+
+    // Seed the mirror with a tag that does not exist upstream
+    let pull_echo = wash()
+        .args([
+            "pull",
+            ECHO_WASM,
+            "--destination",
+            pull_echo_wasm.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap_or_else(|_| panic!("failed to pull {ECHO_WASM} for pull mirror"));
+    assert!(pull_echo.status.success());
+    let push_echo = wash()
+        .args([
+            "push",
+            &format!("{LOCAL_REGISTRY}/wasmcloud/components/http-hello-world-rust:mirrored"),
+            pull_echo_wasm.to_str().unwrap(),
+            "--insecure",
+        ])
+        .output()
+        .expect("failed to push echo.wasm to local registry");
+    assert!(push_echo.status.success(), "failed to seed the mirror");
+
+    // Pulling the upstream reference only succeeds if the pull hits the mirror
+    let upstream_ref = "ghcr.io/wasmcloud/components/http-hello-world-rust:mirrored";
+    let pull_mirrored = wash()
+        .args([
+            "pull",
+            upstream_ref,
+            "--destination",
+            mirrored_echo_wasm.to_str().unwrap(),
+            "--output",
+            "json",
+        ])
+        .env(
+            "WASH_REGISTRIES",
+            json!({ "ghcr.io": { "mirror": LOCAL_REGISTRY, "insecure": true } }).to_string(),
+        )
+        .output()
+        .expect("failed to pull through the mirror");
+    assert!(
+        pull_mirrored.status.success(),
+        "failed to pull {upstream_ref} through the mirror"
+    );
+    let output = get_json_output(pull_mirrored).unwrap();
+    assert_eq!(output["original_url"], upstream_ref);
+    assert_eq!(
+        output["url"],
+        format!("{LOCAL_REGISTRY}/wasmcloud/components/http-hello-world-rust:mirrored")
+    );
+    assert_eq!(
+        std::fs::read(&pull_echo_wasm).unwrap(),
+        std::fs::read(&mirrored_echo_wasm).unwrap()
+    );
+
+    remove_dir_all(pull_dir).unwrap();
+}
+
 // NOTE: This test will fail without a local docker registry running
 #[tokio::test]
 #[cfg_attr(
@@ -405,3 +472,75 @@ async fn integration_reg_config() -> Result<()> {
 
     Ok(())
 }
+
+// NOTE: This test will fail without a local docker registry running
+#[tokio::test]
+#[cfg_attr(
+    not(can_reach_wasmcloud_azurecr_io),
+    ignore = "wasmcloud.azurecr.io is not reachable"
+)]
+async fn integration_reg_push_build_reproducible() -> Result<()> {
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+    env::set_current_dir(&project_dir)?;
+
+    let push = |tag: &str, extra_args: &[&str]| {
+        wash()
+            .args([
+                "push",
+                &format!("{LOCAL_REGISTRY}/hello-reproducible:{tag}"),
+                "--build",
+                "--reproducible",
+                "--insecure",
+                "--output",
+                "json",
+            ])
+            .args(extra_args)
+            .output()
+            .unwrap_or_else(|e| panic!("failed to push artifact {e}"))
+    };
+
+    //===== case: Building and pushing the same sources twice results in the same digest
+    let first = push("first", &[]);
+    assert!(first.status.success(), "failed to build and push");
+    let first_digest = get_json_output(first)?["digest"]
+        .as_str()
+        .context("digest should be part of the output")?
+        .to_string();
+    assert_eq!(
+        first_digest,
+        fetch_artifact_digest(&format!("{LOCAL_REGISTRY}/hello-reproducible:first")).await?
+    );
+
+    remove_dir_all(project_dir.join("build"))?;
+    let second = push("second", &[]);
+    assert!(second.status.success(), "failed to build and push");
+    assert_eq!(get_json_output(second)?["digest"], json!(first_digest));
+
+    //===== case: Pushes are aborted if the digest differs from the expected one
+    let expected = push("expected", &["--expect-digest", &first_digest]);
+    assert!(expected.status.success(), "failed to push expected digest");
+    let unexpected = push(
+        "unexpected",
+        &[
+            "--expect-digest",
+            "sha256:0000000000000000000000000000000000000000000000000000000000000000",
+        ],
+    );
+    assert!(
+        !unexpected.status.success(),
+        "push should have been aborted"
+    );
+    assert!(
+        fetch_artifact_digest(&format!("{LOCAL_REGISTRY}/hello-reproducible:unexpected"))
+            .await
+            .is_err(),
+        "aborted push should not have been uploaded"
+    );
+
+    Ok(())
+}
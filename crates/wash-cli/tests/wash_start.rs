@@ -1,8 +1,16 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
 use serial_test::serial;
+use tokio::process::Command;
+use wash_lib::cli::output::{GetHostInventoriesCommandOutput, StartCommandOutput};
 
 mod common;
-use common::{TestWashInstance, HELLO_OCI_REF, PROVIDER_HTTPSERVER_OCI_REF};
+use common::{
+    init, TestWashInstance, DEFAULT_WASH_INVOCATION_TIMEOUT_MS_ARG, HELLO_OCI_REF,
+    PROVIDER_HTTPSERVER_OCI_REF,
+};
 
 #[tokio::test]
 #[serial]
@@ -22,6 +30,90 @@ async fn integration_start_stop_component_serial() -> Result<()> {
     Ok(())
 }
 
+/// Build the hello world component in a new project, and start it from its `build` directory,
+/// relative to the project directory
+async fn start_built_component(
+    wash_instance: &TestWashInstance,
+    component_id: &str,
+) -> Result<StartCommandOutput> {
+    let test_setup = init("hello", "hello-world-rust").await?;
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args(["build"])
+        .kill_on_drop(true)
+        .status()
+        .await
+        .context("failed to build project")?;
+    assert!(status.success(), "built project");
+
+    let start = wash_instance.start_component("build", component_id).await?;
+    assert!(start.success, "started component from path");
+    assert_eq!(
+        start.local_path.map(PathBuf::from),
+        Some(
+            tokio::fs::canonicalize(test_setup.project_dir.join("build/http_hello_world_s.wasm"))
+                .await?
+        ),
+        "signed component in the build directory was started"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "inventory",
+            &wash_instance.host_id,
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute get inventory")?;
+    let inventory: GetHostInventoriesCommandOutput = serde_json::from_slice(&output.stdout)?;
+    assert!(
+        inventory.inventories[0]
+            .components
+            .iter()
+            .any(|component| component.id == component_id),
+        "component started from path is in the host inventory"
+    );
+    Ok(start)
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_start_component_from_path_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let start = start_built_component(&wash_instance, "hello_from_path").await?;
+    // Hosts allowing file loads load the component from the file directly
+    assert!(
+        start
+            .component_ref
+            .is_some_and(|component_ref| component_ref.starts_with("file://")),
+        "component was loaded from the file"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_start_component_from_path_registry_serial() -> Result<()> {
+    let wash_instance =
+        TestWashInstance::create_with_extra_args(["--allow-file-load", "false"]).await?;
+
+    let start = start_built_component(&wash_instance, "hello_from_registry").await?;
+    // Otherwise the component is pulled from the ephemeral registry
+    assert!(
+        start
+            .component_ref
+            .is_some_and(|component_ref| component_ref.starts_with("127.0.0.1:")),
+        "component was pulled from the ephemeral registry"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn integration_start_stop_provider_serial() -> Result<()> {
@@ -37,3 +129,86 @@ async fn integration_start_stop_provider_serial() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn integration_start_provider_inline_config_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let nats_port = wash_instance.nats_port.to_string();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "start",
+            "provider",
+            PROVIDER_HTTPSERVER_OCI_REF,
+            "httpserver_inline_config",
+            "--config-json",
+            r#"{"address":"127.0.0.1:8099"}"#,
+            "--output",
+            "json",
+            "--timeout-ms",
+            DEFAULT_WASH_INVOCATION_TIMEOUT_MS_ARG,
+            "--ctl-port",
+            &nats_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to start provider")?;
+    let StartCommandOutput { config, .. } = serde_json::from_slice(&output.stdout)
+        .context("failed to parse output of `wash start provider`")?;
+    assert_eq!(config, vec!["httpserver_inline_config-start-config"]);
+
+    // The inline values should have been stored as named configuration
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "config",
+            "get",
+            "httpserver_inline_config-start-config",
+            "--output",
+            "json",
+            "--ctl-port",
+            &nats_port,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to get config")?;
+    let values: HashMap<String, String> = serde_json::from_slice(&output.stdout)
+        .context("failed to parse output of `wash config get`")?;
+    assert_eq!(
+        values.get("address").map(String::as_str),
+        Some("127.0.0.1:8099")
+    );
+
+    wash_instance
+        .stop_provider("httpserver_inline_config", None)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_start_provider_rejects_malformed_config_serial() -> Result<()> {
+    // No lattice is needed, malformed configuration is rejected before connecting
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "start",
+            "provider",
+            PROVIDER_HTTPSERVER_OCI_REF,
+            "httpserver_bad_config",
+            "--config-json",
+            "{not json",
+            "--ctl-port",
+            "1",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to run wash start provider")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid --config-json"));
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+#![cfg(target_family = "unix")]
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+
+mod common;
+use common::{init, wait_for_no_hosts};
+
+/// Ensure `wash test` runs the test command against an ephemeral lattice the hello world
+/// component is deployed to, exits with the exit code of the tests, and tears the lattice down
+#[tokio::test]
+#[serial_test::serial]
+async fn integration_test_hello_component_serial() -> Result<()> {
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+    let test_setup = init(
+        /* component_name= */ "hello",
+        /* template_name= */ "hello-world-rust",
+    )
+    .await?;
+    let project_dir = test_setup.project_dir;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "test",
+            "--work-dir",
+            &project_dir.to_string_lossy(),
+            "--output",
+            "json",
+            "--",
+            "sh",
+            "-c",
+            r#"test -n "$WASH_TEST_NATS_URL" && test "$WASMCLOUD_LATTICE" = "$WASH_TEST_LATTICE""#,
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to run wash test")?;
+    assert!(
+        output.status.success(),
+        "wash test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse wash test output")?;
+    assert_eq!(json["exit_code"], 0);
+    assert!(json["lattice"]
+        .as_str()
+        .is_some_and(|lattice| lattice.starts_with("wash-test-")));
+    wait_for_no_hosts()
+        .await
+        .context("wash test did not stop its host")?;
+
+    // The exit code of failing tests is propagated
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "test",
+            "--work-dir",
+            &project_dir.to_string_lossy(),
+            "--",
+            "sh",
+            "-c",
+            "exit 3",
+        ])
+        .kill_on_drop(true)
+        .status()
+        .await
+        .context("failed to run wash test")?;
+    assert_eq!(status.code(), Some(3));
+    wait_for_no_hosts()
+        .await
+        .context("wash test did not stop its host")?;
+
+    Ok(())
+}
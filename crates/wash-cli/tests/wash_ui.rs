@@ -0,0 +1,74 @@
+use anyhow::{bail, Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+use tokio::time::Duration;
+
+mod common;
+use common::{find_open_port, TestWashInstance};
+
+/// Ensure `wash ui` proxies the API of the UI to the lattice it is pointed at
+#[tokio::test]
+#[serial]
+async fn integration_ui_api_proxy_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let assets = tempfile::tempdir()?;
+    tokio::fs::write(assets.path().join("index.html"), "washboard").await?;
+    let port = find_open_port().await?;
+
+    let mut ui = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "ui",
+            "--port",
+            &port.to_string(),
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+            "--washboard-dir",
+            &assets.path().to_string_lossy(),
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to run wash ui")?;
+
+    let hosts = tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            if let Ok(Some(status)) = ui.try_wait() {
+                bail!("wash ui exited unexpectedly: {status}");
+            }
+            if let Ok(resp) = reqwest::get(format!("http://localhost:{port}/api/hosts")).await {
+                break resp
+                    .json::<serde_json::Value>()
+                    .await
+                    .context("failed to parse hosts");
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await
+    .context("timed out waiting for wash ui to start")??;
+    let hosts = hosts.as_array().context("hosts should be an array")?;
+    assert!(
+        hosts
+            .iter()
+            .any(|host| host["id"] == wash_instance.host_id.as_str()),
+        "{hosts:?}"
+    );
+
+    // The UI assets are served next to the API
+    let index = reqwest::get(format!("http://localhost:{port}/index.html"))
+        .await?
+        .text()
+        .await?;
+    assert_eq!(index, "washboard");
+
+    let pid = ui.id().context("failed to get wash ui pid")?;
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(pid as i32),
+        nix::sys::signal::Signal::SIGINT,
+    )
+    .expect("cannot send ctrl-c");
+    let status = tokio::time::timeout(Duration::from_secs(10), ui.wait())
+        .await
+        .context("wash ui did not stop on ctrl-c")??;
+    assert!(status.success(), "wash ui exited with {status}");
+    Ok(())
+}
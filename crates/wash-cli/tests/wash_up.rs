@@ -1,5 +1,6 @@
 use std::{
     fs::{read_to_string, remove_dir_all},
+    net::Ipv6Addr,
     path::PathBuf,
 };
 
@@ -7,11 +8,14 @@ use anyhow::{anyhow, Context, Result};
 use regex::Regex;
 use serial_test::serial;
 use tokio::{process::Command, time::Duration};
+use wash_lib::cli::output::UpCommandOutput;
+use wash_lib::config::{downloads_dir, WASMCLOUD_PID_FILE};
+use wash_lib::start::read_nats_cluster_file;
 
 mod common;
 use common::{
-    find_open_port, start_nats, test_dir_with_subfolder, wait_for_nats_to_start, wait_for_no_hosts,
-    wait_for_single_host, TestWashInstance, HELLO_OCI_REF,
+    find_open_port, find_open_port_on, start_nats, test_dir_with_subfolder, wait_for_nats_to_start,
+    wait_for_no_hosts, wait_for_single_host, TestWashInstance, HELLO_OCI_REF,
 };
 
 const RGX_COMPONENT_START_MSG: &str = r"Component \[(?P<component_id>[^]]+)\] \(ref: \[(?P<component_ref>[^]]+)\]\) started on host \[(?P<host_id>[^]]+)\]";
@@ -186,6 +190,253 @@ async fn integration_up_can_stop_detached_host_serial() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn integration_up_ipv6_listen_addr_serial() -> Result<()> {
+    let dir = test_dir_with_subfolder("up_ipv6_listen_addr");
+    let path = dir.join("washup.log");
+    let stdout = std::fs::File::create(&path).expect("could not create log file for wash up test");
+    let Ok(nats_port) = find_open_port_on(Ipv6Addr::LOCALHOST.into()).await else {
+        eprintln!("IPv6 loopback is unavailable, skipping");
+        return Ok(());
+    };
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    let host_seed = nkeys::KeyPair::new_server();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "up",
+            "--nats-listen-addr",
+            "[::1]",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "-o",
+            "json",
+            "--detached",
+            "--host-seed",
+            &host_seed.seed().expect("Should have a seed for the host"),
+        ])
+        .kill_on_drop(true)
+        .stdout(stdout)
+        .status()
+        .await
+        .context("up command failed to complete")?;
+    assert!(status.success());
+    let out: serde_json::Value =
+        serde_json::from_str(&read_to_string(&path).expect("could not read output of wash up"))
+            .context("failed to parse wash up output")?;
+    assert_eq!(out["nats_url"], format!("[::1]:{nats_port}"));
+
+    // The host is reachable over IPv6
+    let hosts = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+                .args([
+                    "get",
+                    "hosts",
+                    "--ctl-host",
+                    "::1",
+                    "--ctl-port",
+                    nats_port.to_string().as_ref(),
+                    "--output",
+                    "json",
+                ])
+                .output()
+                .await
+                .context("failed to execute get hosts")?;
+            if output.status.success() {
+                let output: serde_json::Value = serde_json::from_slice(&output.stdout)
+                    .context("failed to parse get hosts output")?;
+                let hosts = output["hosts"].as_array().cloned().unwrap_or_default();
+                if !hosts.is_empty() {
+                    return anyhow::Ok(hosts);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+    .await
+    .context("host did not become reachable over IPv6")??;
+    assert_eq!(hosts.len(), 1);
+    assert_eq!(hosts[0]["id"], host_seed.public_key());
+
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "down",
+            "--ctl-host",
+            "::1",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--host-id",
+            &host_seed.public_key(),
+        ])
+        .output()
+        .await
+        .context("Could not spawn wash down process")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+
+    remove_dir_all(dir).unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_up_get_hosts_retries_serial() -> Result<()> {
+    let dir = test_dir_with_subfolder("get_hosts_retries");
+    let path = dir.join("washup.log");
+    let stdout = std::fs::File::create(&path).expect("could not create log file for wash up test");
+    let nats_port: u16 = find_open_port().await?;
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    let host_seed = nkeys::KeyPair::new_server();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "up",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "-o",
+            "json",
+            "--detached",
+            "--host-seed",
+            &host_seed.seed().expect("Should have a seed for the host"),
+        ])
+        .kill_on_drop(true)
+        .stdout(stdout)
+        .status()
+        .await
+        .context("up command failed to complete")?;
+    assert!(status.success());
+
+    // Query hosts right away, without waiting for the host to subscribe to queries. Retries should
+    // cover the time it takes, so that no query comes back empty
+    for i in 0..10 {
+        let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+            .args(["--timeout-ms", "1000", "get", "hosts", "--output", "json"])
+            .args(["--ctl-port", nats_port.to_string().as_ref()])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("get hosts command failed")?;
+        assert!(output.status.success(), "query {i} failed");
+        let output: serde_json::Value =
+            serde_json::from_slice(&output.stdout).context("failed to parse get hosts output")?;
+        assert_eq!(
+            output["hosts"].as_array().map(Vec::len),
+            Some(1),
+            "query {i} did not find the host: {output}"
+        );
+        // Retried queries report how many attempts they needed
+        if let Some(attempts) = output.get("attempts") {
+            assert!(attempts.as_u64().is_some_and(|attempts| attempts > 1));
+        }
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "down",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--host-id",
+            &host_seed.public_key(),
+        ])
+        .output()
+        .await
+        .context("Could not spawn wash down process")?;
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+
+    remove_dir_all(dir).unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[cfg(target_os = "linux")]
+#[ignore = "requires cgroups v2 with the memory controller delegated to the user's systemd manager"]
+async fn integration_up_limit_memory_serial() -> Result<()> {
+    const LIMIT_MEMORY: u64 = 512 * 1024 * 1024;
+
+    let dir = test_dir_with_subfolder("limit_memory");
+    let path = dir.join("washup.log");
+    let stdout = std::fs::File::create(&path).expect("could not create log file for wash up test");
+    let nats_port: u16 = find_open_port().await?;
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    let host_seed = nkeys::KeyPair::new_server();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "up",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "-o",
+            "json",
+            "--detached",
+            "--host-seed",
+            &host_seed.seed().expect("Should have a seed for the host"),
+            "--limit-memory",
+            &LIMIT_MEMORY.to_string(),
+            "--strict-limits",
+        ])
+        .kill_on_drop(true)
+        .stdout(stdout)
+        .status()
+        .await
+        .context("up command failed to complete")?;
+    assert!(status.success());
+
+    let out = read_to_string(&path).context("could not read output of wash up")?;
+    let UpCommandOutput { limits, .. } =
+        serde_json::from_str(&out).context("failed to parse wash up output")?;
+    assert_eq!(limits.memory_bytes, Some(LIMIT_MEMORY));
+
+    // The host runs in its own cgroup, limited to the requested memory
+    let pid_file = tokio::fs::read_to_string(downloads_dir()?.join(WASMCLOUD_PID_FILE)).await?;
+    let pid: serde_json::Value = serde_json::from_str(&pid_file)?;
+    let pid = pid["pid"].as_u64().context("missing host PID")?;
+    let cgroup = tokio::fs::read_to_string(format!("/proc/{pid}/cgroup")).await?;
+    let cgroup = cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .context("host is not in a cgroups v2 hierarchy")?;
+    let memory_max =
+        tokio::fs::read_to_string(format!("/sys/fs/cgroup{cgroup}/memory.max")).await?;
+    assert_eq!(memory_max.trim(), LIMIT_MEMORY.to_string());
+
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "down",
+            "--ctl-port",
+            nats_port.to_string().as_ref(),
+            "--host-id",
+            &host_seed.public_key(),
+        ])
+        .output()
+        .await
+        .context("Could not spawn wash down process")?;
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+
+    remove_dir_all(dir).unwrap();
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn integration_up_doesnt_kill_unowned_nats_serial() -> Result<()> {
@@ -297,3 +548,100 @@ async fn integration_up_works_with_wadm_manifest() -> Result<()> {
 
     Ok(())
 }
+
+/// Ensure that wash up can launch a NATS cluster and the host survives losing its NATS node
+#[tokio::test]
+#[serial]
+async fn integration_up_nats_cluster_failover_serial() -> Result<()> {
+    let dir = test_dir_with_subfolder("nats_cluster_failover");
+    let path = dir.join("washup.log");
+    let stdout = std::fs::File::create(&path).expect("could not create log file for wash up test");
+    let nats_port: u16 = find_open_port().await?;
+
+    wait_for_no_hosts()
+        .await
+        .context("unexpected wasmcloud instance(s) running")?;
+
+    let host_seed = nkeys::KeyPair::new_server();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "up",
+            "--nats-port",
+            nats_port.to_string().as_ref(),
+            "--nats-cluster-size",
+            "3",
+            "-o",
+            "json",
+            "--detached",
+            "--host-seed",
+            &host_seed.seed().expect("Should have a seed for the host"),
+        ])
+        .kill_on_drop(true)
+        .stdout(stdout)
+        .status()
+        .await
+        .context("up command failed to complete")?;
+    assert!(status.success(), "failed to complete up command");
+
+    let out = read_to_string(&path).expect("could not read output of wash up");
+    let UpCommandOutput {
+        nats_cluster_ports, ..
+    } = serde_json::from_str(&out).context("failed to parse wash up cmd output")?;
+    assert_eq!(
+        nats_cluster_ports,
+        vec![nats_port, nats_port + 1, nats_port + 2]
+    );
+
+    wait_for_single_host(nats_port, Duration::from_secs(10), Duration::from_secs(1)).await?;
+
+    // Kill the node the host is connected to
+    let nodes = read_nats_cluster_file(downloads_dir()?)
+        .await?
+        .context("NATS cluster file should have been written")?;
+    let pid = nodes
+        .iter()
+        .find(|node| node.port == nats_port)
+        .and_then(|node| node.pid)
+        .context("missing pid of first NATS cluster node")?;
+    Command::new("kill")
+        .arg(pid.to_string())
+        .output()
+        .await
+        .context("failed to kill first NATS cluster node")?;
+
+    // The host reconnects to a surviving node and still responds
+    let surviving_port = nats_port + 1;
+    let host = wait_for_single_host(
+        surviving_port,
+        Duration::from_secs(30),
+        Duration::from_secs(1),
+    )
+    .await
+    .context("host did not reconnect to a surviving NATS cluster node")?;
+    assert_eq!(host.id, host_seed.public_key());
+
+    Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "down",
+            "--ctl-port",
+            surviving_port.to_string().as_ref(),
+            "--host-id",
+            &host_seed.public_key(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Could not spawn wash down process")?;
+
+    wait_for_no_hosts()
+        .await
+        .context("wasmcloud instance failed to exit cleanly (processes still left over)")?;
+    assert!(
+        read_nats_cluster_file(downloads_dir()?).await?.is_none(),
+        "wash down should stop all NATS cluster nodes"
+    );
+
+    remove_dir_all(dir).unwrap();
+    Ok(())
+}
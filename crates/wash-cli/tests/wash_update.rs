@@ -6,6 +6,7 @@ use anyhow::{Context, Result};
 use serial_test::serial;
 use tokio::process::Command;
 use wash_lib::cli::output::{GetHostInventoriesCommandOutput, StartCommandOutput};
+use wash_lib::cli::update::{RollingUpdateDecision, RollingUpdateReport};
 
 const OLD_HELLO_OCI_REF: &str = "ghcr.io/brooksmtownsend/http-hello-world-rust:0.1.0";
 
@@ -178,3 +179,78 @@ async fn integration_update_component_serial() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(can_reach_ghcr_io), ignore = "ghcr.io is not reachable")]
+async fn integration_update_component_rolling_rollback_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let cmd_output = wash_instance
+        .start_component(HELLO_OCI_REF, "hello_rolling")
+        .await?;
+    assert!(cmd_output.success, "started component");
+
+    // The new version cannot be fetched, so it never gets to be probed
+    let broken_ref = "ghcr.io/wasmcloud/components/does-not-exist:0.0.0";
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "update",
+            "component",
+            "--host-id",
+            wash_instance.host_id.as_str(),
+            "hello_rolling",
+            broken_ref,
+            "--rolling",
+            "--health-invoke",
+            "wasmcloud:example/health.check",
+            "--health-timeout-ms",
+            "5000",
+            "--output",
+            "json",
+            "--timeout-ms",
+            "40000",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to update component")?;
+    assert!(
+        !output.status.success(),
+        "rolled back update should fail the command"
+    );
+
+    let report: RollingUpdateReport =
+        serde_json::from_slice(&output.stdout).context("failed to parse output")?;
+    assert_eq!(report.decision, RollingUpdateDecision::RolledBack);
+    assert_eq!(report.old_component_ref, HELLO_OCI_REF);
+    assert_eq!(report.new_component_ref, broken_ref);
+    assert!(report.error.is_some(), "rollback reason is reported");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "get",
+            "inventory",
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to get host inventory")?;
+    let cmd_output: GetHostInventoriesCommandOutput =
+        serde_json::from_slice(&output.stdout).context("failed to parse output")?;
+    let components = &cmd_output.inventories[0].components;
+    assert_eq!(components.len(), 1, "new version was removed");
+    assert_eq!(components[0].id, "hello_rolling");
+    assert_eq!(
+        components[0].image_ref, HELLO_OCI_REF,
+        "original version is still running"
+    );
+
+    Ok(())
+}
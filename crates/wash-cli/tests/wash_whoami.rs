@@ -0,0 +1,54 @@
+mod common;
+
+use common::TestWashInstance;
+
+use anyhow::{Context, Result};
+use serial_test::serial;
+use tokio::process::Command;
+use wash_lib::cli::output::WhoamiCommandOutput;
+use wash_lib::cli::whoami::ProbeResult;
+
+#[tokio::test]
+#[serial]
+async fn integration_whoami_serial() -> Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "whoami",
+            "--output",
+            "json",
+            "--ctl-port",
+            &wash_instance.nats_port.to_string(),
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash whoami")?;
+    assert!(output.status.success(), "wash whoami failed");
+
+    let cmd_output: WhoamiCommandOutput = serde_json::from_slice(&output.stdout)?;
+    assert!(cmd_output.success, "command returned success");
+    let report = cmd_output.report;
+    assert!(report
+        .identity
+        .url
+        .ends_with(&format!(":{}", wash_instance.nats_port)));
+    assert_eq!(report.identity.lattice, "default");
+    assert_eq!(report.identity.auth, "none");
+    assert!(!report.identity.server_id.is_empty());
+
+    // The test NATS server has no authorization configured, so every probe is allowed
+    assert!(!report.permissions.is_empty());
+    for probe in &report.permissions {
+        assert_eq!(
+            probe.result,
+            ProbeResult::Allowed,
+            "{} {} on {} should be allowed",
+            probe.capability,
+            probe.operation.as_str(),
+            probe.subject
+        );
+    }
+    Ok(())
+}
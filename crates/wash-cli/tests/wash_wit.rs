@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio::process::Command;
+
+/// Ensure `wash wit diff` reports the functions added to an interface, and the version bump of
+/// the package
+#[tokio::test]
+async fn wit_diff_added_function() -> Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "wit",
+            "diff",
+            "./tests/fixtures/wit/greeter-0.1.0",
+            "--wit-dir",
+            "./tests/fixtures/wit/greeter-0.2.0",
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash wit diff")?;
+    assert!(output.status.success(), "wash wit diff failed");
+
+    let output: Value =
+        serde_json::from_slice(&output.stdout).context("failed to build JSON from output")?;
+    assert_eq!(output["changed"], true);
+    let package = &output["packages"][0];
+    assert_eq!(package["name"], "wasmcloud:greeter");
+    assert_eq!(package["version"]["bump"], "minor");
+    assert_eq!(package["version"]["breaking"], true);
+    let functions = package["interfaces"][0]["functions"]
+        .as_array()
+        .context("missing functions")?;
+    assert_eq!(functions.len(), 1);
+    assert_eq!(functions[0]["name"], "greet-many");
+    assert_eq!(functions[0]["change"], "added");
+
+    // A package does not differ from itself
+    let output = Command::new(env!("CARGO_BIN_EXE_wash"))
+        .args([
+            "wit",
+            "diff",
+            "./tests/fixtures/wit/greeter-0.2.0",
+            "--wit-dir",
+            "./tests/fixtures/wit/greeter-0.2.0",
+            "--output",
+            "json",
+        ])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to execute wash wit diff")?;
+    let output: Value =
+        serde_json::from_slice(&output.stdout).context("failed to build JSON from output")?;
+    assert_eq!(output["changed"], false);
+    Ok(())
+}
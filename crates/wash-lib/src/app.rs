@@ -3,6 +3,7 @@
 //! This crate is essentially a wrapper around the wadm_client crate, and it's recommended to use
 //! that crate directly instead.
 
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -12,7 +13,7 @@ use async_nats::Client;
 use regex::Regex;
 use tracing::warn;
 use wadm_client::Result;
-use wadm_types::api::{ModelSummary, Status, VersionInfo};
+use wadm_types::api::{ModelSummary, Status, StatusType, VersionInfo};
 
 use tokio::io::{AsyncRead, AsyncReadExt};
 use url::Url;
@@ -105,6 +106,156 @@ fn resolve_relative_file_paths_in_yaml(
     Ok(())
 }
 
+/// Values of the `${NAME}` placeholders in app manifests
+///
+/// Placeholders are resolved, in order of precedence, from values set explicitly (e.g. with
+/// `--set`), values loaded from a values file, and the process environment. A placeholder may
+/// carry a default value used when it is not resolved, as in `${region:-us-east-1}`, and `$${...}`
+/// is rendered as a literal `${...}`.
+#[derive(Clone, Debug, Default)]
+pub struct ManifestVariables {
+    set: HashMap<String, String>,
+    values: HashMap<String, String>,
+    env: bool,
+}
+
+impl ManifestVariables {
+    /// Create variables resolving placeholders from the process environment
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self {
+            env: true,
+            ..Self::default()
+        }
+    }
+
+    /// Set values from `KEY=VALUE` pairs, taking precedence over all other sources
+    pub fn with_set_values(
+        mut self,
+        pairs: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> anyhow::Result<Self> {
+        for pair in pairs {
+            let pair = pair.as_ref();
+            let Some((key, value)) = pair.split_once('=') else {
+                bail!("invalid value [{pair}], expected KEY=VALUE");
+            };
+            if !is_placeholder_name(key) {
+                bail!("invalid placeholder name [{key}] in [{pair}]");
+            }
+            self.set.insert(key.to_string(), value.to_string());
+        }
+        Ok(self)
+    }
+
+    /// Load values from a YAML (or JSON) file containing a map of placeholder names to scalar
+    /// values, taking precedence over the process environment
+    pub fn with_values_file(mut self, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read values file [{}]", path.display()))?;
+        let values: HashMap<String, serde_yaml::Value> = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse values file [{}]", path.display()))?;
+        for (key, value) in values {
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                _ => bail!(
+                    "value of [{key}] in values file [{}] is not a string, number or boolean",
+                    path.display()
+                ),
+            };
+            self.values.insert(key, value);
+        }
+        Ok(self)
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.set
+            .get(name)
+            .or_else(|| self.values.get(name))
+            .cloned()
+            .or_else(|| self.env.then(|| std::env::var(name).ok()).flatten())
+    }
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Substitute the `${NAME}` and `${NAME:-default}` placeholders in `template` with `variables`,
+/// rendering `$${...}` as a literal `${...}`
+///
+/// # Errors
+///
+/// Returns an error listing all unresolved placeholders, if any, or if a placeholder is malformed
+pub fn render_manifest_template(
+    template: &str,
+    variables: &ManifestVariables,
+) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut unresolved = BTreeSet::new();
+    let mut rest = template;
+    while let Some(i) = rest.find('$') {
+        rendered.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            rendered.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(placeholder) = after.strip_prefix('{') else {
+            rendered.push('$');
+            rest = after;
+            continue;
+        };
+        let Some(end) = placeholder.find('}') else {
+            bail!("unterminated placeholder [${{{placeholder}]");
+        };
+        let (name, default) = match placeholder[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&placeholder[..end], None),
+        };
+        if !is_placeholder_name(name) {
+            bail!(
+                "invalid placeholder [${{{}}}], use $${{...}} for a literal ${{...}}",
+                &placeholder[..end]
+            );
+        }
+        match variables.get(name).or_else(|| default.map(String::from)) {
+            Some(value) => rendered.push_str(&value),
+            None => {
+                unresolved.insert(name);
+            }
+        }
+        rest = &placeholder[end + 1..];
+    }
+    rendered.push_str(rest);
+    if !unresolved.is_empty() {
+        bail!(
+            "unresolved placeholders in app manifest: {}",
+            unresolved.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(rendered)
+}
+
+/// Render `text` with `variables`, if any, and parse it as YAML
+fn parse_manifest_text(
+    text: &str,
+    variables: Option<&ManifestVariables>,
+) -> anyhow::Result<serde_yaml::Value> {
+    match variables {
+        Some(variables) => {
+            let rendered = render_manifest_template(text, variables)?;
+            serde_yaml::from_str(&rendered).context("failed to parse rendered yaml")
+        }
+        None => serde_yaml::from_str(text).map_err(anyhow::Error::from),
+    }
+}
+
 pub trait AsyncReadSource: AsyncRead + Unpin + Send + Sync {}
 impl<T: AsyncRead + Unpin + Send + Sync> AsyncReadSource for T {}
 pub enum AppManifestSource {
@@ -350,9 +501,242 @@ pub async fn get_models(client: &Client, lattice: Option<String>) -> Result<Vec<
     wadm_client.list_manifests().await
 }
 
+/// Default amount of time to wait for an application to converge after a rollback
+pub const DEFAULT_ROLLBACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Interval at which wadm is polled for the status of an application that is converging
+const MODEL_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Determine the version of an application to roll back to, given the version history returned by wadm
+///
+/// If `to_version` is specified, that version must be present in `versions` and must not be the
+/// currently deployed version. Otherwise, the version stored just before the currently deployed
+/// version is chosen.
+///
+/// # Arguments
+/// * `versions` - Stored versions of the application, in the order they were put
+/// * `to_version` - Optional version to roll back to
+pub fn rollback_target_version(
+    versions: &[VersionInfo],
+    to_version: Option<&str>,
+) -> anyhow::Result<String> {
+    let deployed = versions.iter().position(|v| v.deployed);
+    match (to_version, deployed) {
+        (Some(target), deployed) => {
+            let Some(idx) = versions.iter().position(|v| v.version == target) else {
+                bail!("version [{target}] was not found in the application history");
+            };
+            if deployed == Some(idx) {
+                bail!("version [{target}] is already deployed");
+            }
+            Ok(target.to_string())
+        }
+        (None, Some(0)) => {
+            bail!("no version was stored before the deployed version, nothing to roll back to")
+        }
+        (None, Some(idx)) => Ok(versions[idx - 1].version.clone()),
+        (None, None) => {
+            bail!("no version of the application is deployed, specify a version to roll back to")
+        }
+    }
+}
+
+/// Wait for wadm to report that the given version of a model has been deployed
+///
+/// Returns an error containing wadm's status message if the model fails to deploy, or if it
+/// has not converged before `timeout` elapses.
+///
+/// # Arguments
+/// * `client` - The [Client](async_nats::Client) to use in order to send the request message
+/// * `lattice` - Optional lattice name that the application is managed on, defaults to `default`
+/// * `model_name` - Name of the model to wait for
+/// * `version` - Version of the model that is expected to be deployed
+/// * `timeout` - Maximum amount of time to wait for the model to converge
+pub async fn wait_for_model_deployed(
+    client: &Client,
+    lattice: Option<String>,
+    model_name: &str,
+    version: &str,
+    timeout: Duration,
+) -> anyhow::Result<Status> {
+    let wadm_client = wadm_client::Client::from_nats_client(
+        &lattice.unwrap_or_else(|| DEFAULT_LATTICE.to_string()),
+        None,
+        client.clone(),
+    );
+
+    let wait = async {
+        loop {
+            let status = wadm_client
+                .get_manifest_status(model_name)
+                .await
+                .context("failed to get application status")?;
+            if status.version == version {
+                match status.info.status_type {
+                    StatusType::Deployed => return Ok(status),
+                    StatusType::Failed => bail!(
+                        "application [{model_name}] version [{version}] failed to deploy: {}",
+                        status.info.message
+                    ),
+                    _ => {}
+                }
+            }
+            tokio::time::sleep(MODEL_STATUS_POLL_INTERVAL).await;
+        }
+    };
+    tokio::time::timeout(timeout, wait).await.with_context(|| {
+        format!(
+            "timed out waiting for application [{model_name}] version [{version}] to be deployed"
+        )
+    })?
+}
+
+/// Name of the JetStream stream wadm publishes status events of applications to
+const WADM_STATUS_STREAM: &str = "wadm_status";
+
+/// How [`ModelStatusWatch`] learns about changes to the status of an application
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusWatchMode {
+    /// Status events published by wadm trigger a status query
+    Events,
+    /// wadm is polled for the status every second, for versions of wadm without status events
+    Polling,
+}
+
+/// Changes to the status of a model, see [`watch_model_status`]
+pub struct ModelStatusWatch {
+    mode: StatusWatchMode,
+    updates: tokio::sync::mpsc::Receiver<anyhow::Result<Status>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ModelStatusWatch {
+    /// How changes to the status are detected
+    #[must_use]
+    pub fn mode(&self) -> StatusWatchMode {
+        self.mode
+    }
+
+    /// Wait for the next status of the model which differs from the previous one. Errors querying
+    /// the status are returned without ending the watch
+    pub async fn next(&mut self) -> Option<anyhow::Result<Status>> {
+        self.updates.recv().await
+    }
+}
+
+impl Drop for ModelStatusWatch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Watch the status of a given model by name. The current status is returned first, followed by
+/// every change to it.
+///
+/// Changes are detected from the status events wadm publishes to its status stream, falling back
+/// to polling wadm when the stream does not exist (e.g. with older versions of wadm).
+///
+/// # Arguments
+/// * `client` - The [Client](async_nats::Client) to use in order to send the request message
+/// * `lattice` - Optional lattice name that the application is managed on, defaults to `default`
+/// * `model_name` - Name of the model to watch
+pub async fn watch_model_status(
+    client: &Client,
+    lattice: Option<String>,
+    model_name: &str,
+) -> anyhow::Result<ModelStatusWatch> {
+    let lattice = lattice.unwrap_or_else(|| DEFAULT_LATTICE.to_string());
+    let events = match async_nats::jetstream::new(client.clone())
+        .get_stream(WADM_STATUS_STREAM)
+        .await
+    {
+        Ok(_) => Some(
+            client
+                .subscribe(format!("wadm.status.{lattice}.{model_name}"))
+                .await
+                .context("failed to subscribe to application status events")?,
+        ),
+        Err(err) => {
+            warn!(%err, "wadm status events are unavailable, polling for the application status");
+            None
+        }
+    };
+    let mode = if events.is_some() {
+        StatusWatchMode::Events
+    } else {
+        StatusWatchMode::Polling
+    };
+
+    let wadm_client = wadm_client::Client::from_nats_client(&lattice, None, client.clone());
+    let model_name = model_name.to_string();
+    let (tx, updates) = tokio::sync::mpsc::channel(16);
+    let task = tokio::spawn(async move {
+        use futures::StreamExt as _;
+
+        let mut events = events;
+        let mut poll = tokio::time::interval(MODEL_STATUS_POLL_INTERVAL);
+        poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately, the current status is queried right away
+        poll.tick().await;
+        let mut previous: Option<serde_json::Value> = None;
+        let mut first = true;
+        loop {
+            // The payload of status events differs between wadm versions, so events only trigger a
+            // status query
+            if !std::mem::take(&mut first) {
+                match events.as_mut() {
+                    Some(events) => {
+                        if events.next().await.is_none() {
+                            return;
+                        }
+                    }
+                    None => {
+                        poll.tick().await;
+                    }
+                }
+            }
+            let update = match wadm_client.get_manifest_status(&model_name).await {
+                Ok(status) => {
+                    let current = serde_json::to_value(&status).ok();
+                    if current.is_some() && current == previous {
+                        continue;
+                    }
+                    previous = current;
+                    Ok(status)
+                }
+                Err(err) => Err(anyhow::anyhow!(err).context("failed to get application status")),
+            };
+            if tx.send(update).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(ModelStatusWatch {
+        mode,
+        updates,
+        task,
+    })
+}
+
 //  NOTE(ahmedtadde): This should probably be refactored at some point to account for cases where the source's input is unusually (or erroneously) large.
 //  For now, we'll just assume that the input is small enough to be a oneshot read into memory and that the default timeout of 1 sec is plenty sufficient (or even too generous?) for the desired/expected behavior.
 pub async fn load_app_manifest(source: AppManifestSource) -> anyhow::Result<AppManifest> {
+    load_manifest(source, None).await
+}
+
+/// Load an app manifest like [`load_app_manifest`], substituting the placeholders in manifests
+/// loaded from stdin, files and URLs with `variables` before parsing them
+pub async fn load_app_manifest_with_variables(
+    source: AppManifestSource,
+    variables: &ManifestVariables,
+) -> anyhow::Result<AppManifest> {
+    load_manifest(source, Some(variables)).await
+}
+
+async fn load_manifest(
+    source: AppManifestSource,
+    variables: Option<&ManifestVariables>,
+) -> anyhow::Result<AppManifest> {
     let load_from_source = || async {
         match source {
             AppManifestSource::AsyncReadSource(mut stdin) => {
@@ -366,16 +750,18 @@ pub async fn load_app_manifest(source: AppManifestSource) -> anyhow::Result<AppM
                 }
 
                 Ok(AppManifest::SerializedModel(
-                    serde_yaml::from_str(&buffer).context("failed to parse yaml from STDIN")?,
+                    parse_manifest_text(&buffer, variables)
+                        .context("failed to parse yaml from STDIN")?,
                 ))
             }
             AppManifestSource::File(path) => {
                 let mut manifest = AppManifest::SerializedModel(
-                    serde_yaml::from_str(
+                    parse_manifest_text(
                         tokio::fs::read_to_string(&path)
                             .await
                             .context("failed to read model from file")?
                             .as_str(),
+                        variables,
                     )
                     .with_context(|| {
                         format!("failed to parse yaml from file @ [{}]", path.display())
@@ -403,7 +789,7 @@ pub async fn load_app_manifest(source: AppManifestSource) -> anyhow::Result<AppM
                     .text()
                     .await
                     .context("failed to read model from remote file")?;
-                serde_yaml::from_str(&text)
+                parse_manifest_text(&text, variables)
                     .with_context(|| format!("failed to parse YAML from URL [{url}]"))
                     .map(AppManifest::SerializedModel)
             }
@@ -559,4 +945,95 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_render_manifest_template() -> Result<()> {
+        std::env::set_var("WASH_TEST_RENDER_TAG", "from-env");
+        std::env::set_var("WASH_TEST_RENDER_REGION", "from-env");
+        let values_dir = tempdir()?;
+        let values_file = values_dir.path().join("values.yaml");
+        std::fs::write(
+            &values_file,
+            "WASH_TEST_RENDER_TAG: from-file\nreplicas: 3\n",
+        )?;
+        let variables = ManifestVariables::from_env()
+            .with_values_file(&values_file)?
+            .with_set_values(["WASH_TEST_RENDER_TAG=from-set"])?;
+
+        let rendered = render_manifest_template(
+            "image: app:${WASH_TEST_RENDER_TAG}\nreplicas: ${replicas}\nregion: ${WASH_TEST_RENDER_REGION}\nzone: ${zone:-us-east-1}\nliteral: $${WASH_TEST_RENDER_TAG} costs $5\n",
+            &variables,
+        )?;
+        assert_eq!(
+            rendered,
+            "image: app:from-set\nreplicas: 3\nregion: from-env\nzone: us-east-1\nliteral: ${WASH_TEST_RENDER_TAG} costs $5\n"
+        );
+
+        // Values files take precedence over the environment
+        let rendered = render_manifest_template(
+            "${WASH_TEST_RENDER_TAG}",
+            &ManifestVariables::from_env().with_values_file(&values_file)?,
+        )?;
+        assert_eq!(rendered, "from-file");
+
+        // All unresolved placeholders are reported at once
+        let err = render_manifest_template(
+            "${WASH_TEST_UNSET_B} ${WASH_TEST_UNSET_A} ${WASH_TEST_UNSET_B}",
+            &variables,
+        )
+        .expect_err("unresolved placeholders should fail to render");
+        assert_eq!(
+            err.to_string(),
+            "unresolved placeholders in app manifest: WASH_TEST_UNSET_A, WASH_TEST_UNSET_B"
+        );
+
+        // Placeholders are not resolved from the environment unless requested
+        assert!(
+            render_manifest_template("${WASH_TEST_RENDER_TAG}", &ManifestVariables::default())
+                .is_err()
+        );
+        assert!(render_manifest_template("${unterminated", &variables).is_err());
+        assert!(ManifestVariables::default()
+            .with_set_values(["no-equals"])
+            .is_err());
+        Ok(())
+    }
+
+    fn version(version: &str, deployed: bool) -> VersionInfo {
+        VersionInfo {
+            version: version.to_string(),
+            deployed,
+        }
+    }
+
+    #[test]
+    fn test_rollback_target_version() {
+        let versions = vec![
+            version("v1", false),
+            version("v2", false),
+            version("v3", true),
+        ];
+        assert_eq!(
+            rollback_target_version(&versions, None).expect("should find previous version"),
+            "v2"
+        );
+        assert_eq!(
+            rollback_target_version(&versions, Some("v1")).expect("should find v1"),
+            "v1"
+        );
+        assert!(rollback_target_version(&versions, Some("v3")).is_err());
+        assert!(rollback_target_version(&versions, Some("v4")).is_err());
+
+        // Nothing stored before the deployed version
+        let versions = vec![version("v1", true), version("v2", false)];
+        assert!(rollback_target_version(&versions, None).is_err());
+        assert_eq!(
+            rollback_target_version(&versions, Some("v2")).expect("should find v2"),
+            "v2"
+        );
+
+        // Nothing deployed
+        let versions = vec![version("v1", false), version("v2", false)];
+        assert!(rollback_target_version(&versions, None).is_err());
+    }
 }
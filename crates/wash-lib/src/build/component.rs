@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     fs,
     io::ErrorKind,
     path::{Path, PathBuf},
@@ -15,12 +16,15 @@ use wit_bindgen_go::Opts as WitBindgenGoOpts;
 use wit_component::{ComponentEncoder, StringEncoding};
 
 use crate::{
-    build::{convert_wit_dir_to_world, SignConfig, WASMCLOUD_WASM_TAG_EXPERIMENTAL},
+    build::{convert_wit_dir_to_world, processes, SignConfig, WASMCLOUD_WASM_TAG_EXPERIMENTAL},
     cli::{
         claims::{sign_file, ComponentMetadata, GenerateCommon, SignCommand},
         OutputKind,
     },
-    parser::{CommonConfig, ComponentConfig, LanguageConfig, RustConfig, TinyGoConfig, WasmTarget},
+    parser::{
+        BuildConfig, CommonConfig, ComponentConfig, LanguageConfig, RustConfig, TinyGoConfig,
+        WasmTarget,
+    },
 };
 
 /// Builds a wasmCloud component using the installed language toolchain, then signs the component with
@@ -36,18 +40,36 @@ pub fn build_component(
     language_config: &LanguageConfig,
     common_config: &CommonConfig,
     signing_config: Option<&SignConfig>,
+) -> Result<PathBuf> {
+    build_component_with_env(
+        component_config,
+        language_config,
+        common_config,
+        &HashMap::new(),
+        signing_config,
+    )
+}
+
+/// Builds a wasmCloud component like [`build_component`], setting the environment variables in
+/// `env` for the language toolchain, see [`BuildConfig::env`]
+pub fn build_component_with_env(
+    component_config: &ComponentConfig,
+    language_config: &LanguageConfig,
+    common_config: &CommonConfig,
+    env: &HashMap<String, String>,
+    signing_config: Option<&SignConfig>,
 ) -> Result<PathBuf> {
     let component_wasm_path = if let Some(raw_command) = component_config.build_command.as_ref() {
-        build_custom_component(common_config, component_config, raw_command)?
+        build_custom_component(common_config, component_config, raw_command, env)?
     } else {
         // Build component based on language toolchain
         let component_wasm_path = match language_config {
             LanguageConfig::Rust(rust_config) => {
-                build_rust_component(common_config, rust_config, component_config)?
+                build_rust_component(common_config, rust_config, component_config, env)?
             }
             LanguageConfig::TinyGo(tinygo_config) => {
                 let component_wasm_path =
-                    build_tinygo_component(common_config, tinygo_config, component_config)?;
+                    build_tinygo_component(common_config, tinygo_config, component_config, env)?;
 
                 // Perform embedding, if necessary
                 if let WasmTarget::WasiPreview1 | WasmTarget::WasiPreview2 =
@@ -73,6 +95,7 @@ pub fn build_component(
                     common_config,
                     component_config,
                     component_config.build_command.as_ref().unwrap(),
+                    env,
                 )?
             }
             LanguageConfig::Go(_) => {
@@ -169,11 +192,67 @@ pub fn sign_component_wasm(
     })
 }
 
+/// Run the post-build commands of `build_config` in sequence on the (unsigned) component at
+/// `component_wasm_path`, with the environment variables of `build_config` set.
+///
+/// In each command, `{input}` is replaced with `component_wasm_path` and `{output}` with a temporary
+/// path, which replaces the component once the command succeeds. Commands that do not reference
+/// `{output}` are expected to modify the component in place.
+pub fn run_post_build_commands(
+    common_config: &CommonConfig,
+    build_config: &BuildConfig,
+    component_wasm_path: impl AsRef<Path>,
+) -> Result<()> {
+    let input = component_wasm_path.as_ref();
+    let output = input.with_extension("post.wasm");
+    for raw_command in &build_config.post_commands {
+        let (command, args) = parse_custom_command(raw_command)?;
+        let writes_output = args.iter().any(|arg| arg.contains("{output}"));
+        let args = args.iter().map(|arg| {
+            arg.replace("{input}", &input.to_string_lossy())
+                .replace("{output}", &output.to_string_lossy())
+        });
+        let mut command = process::Command::new(command);
+        command
+            .args(args)
+            .envs(&build_config.env)
+            .current_dir(&common_config.path)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+
+        info!("running post-build command [{raw_command}]");
+        let result = processes::output(&mut command).map_err(|e| {
+            if e.kind() == ErrorKind::NotFound {
+                anyhow!("`{:?}` was not found", command.get_program())
+            } else {
+                anyhow!(format!("failed to run `{:?}`: {e}", command.get_program()))
+            }
+        })?;
+        if !result.status.success() {
+            bail!(
+                "post-build command [{raw_command}] failed: {:?}",
+                String::from_utf8_lossy(&result.stderr)
+            );
+        }
+
+        if writes_output {
+            fs::rename(&output, input).with_context(|| {
+                format!(
+                    "post-build command [{raw_command}] did not write its output to [{}]",
+                    output.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
 /// Builds a rust component and returns the path to the file.
 fn build_rust_component(
     common_config: &CommonConfig,
     rust_config: &RustConfig,
     component_config: &ComponentConfig,
+    env: &HashMap<String, String>,
 ) -> Result<PathBuf> {
     let mut command = match rust_config.cargo_path.as_ref() {
         Some(path) => process::Command::new(path),
@@ -184,16 +263,18 @@ fn build_rust_component(
     std::env::set_current_dir(&common_config.path)?;
 
     let build_target: &str = rust_config.build_target(&component_config.wasm_target);
-    let result = command
-        .args(["build", "--release", "--target", build_target])
-        .status()
-        .map_err(|e| {
-            if e.kind() == ErrorKind::NotFound {
-                anyhow!("{:?} command is not found", command.get_program())
-            } else {
-                anyhow!(e)
-            }
-        })?;
+    let result = processes::status(
+        command
+            .args(["build", "--release", "--target", build_target])
+            .envs(env),
+    )
+    .map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            anyhow!("{:?} command is not found", command.get_program())
+        } else {
+            anyhow!(e)
+        }
+    })?;
 
     if !result.success() {
         bail!("Compiling component failed: {}", result.to_string())
@@ -245,6 +326,7 @@ fn build_tinygo_component(
     common_config: &CommonConfig,
     tinygo_config: &TinyGoConfig,
     component_config: &ComponentConfig,
+    env: &HashMap<String, String>,
 ) -> Result<PathBuf> {
     let filename = format!("build/{}.wasm", common_config.name);
     let file_path = PathBuf::from(&filename);
@@ -289,26 +371,28 @@ fn build_tinygo_component(
                 .context("generating golang bindgen code failed")?;
     }
 
-    let result = command
-        .args([
-            "build",
-            "-o",
-            filename.as_str(),
-            "-target",
-            tinygo_config.build_target(&component_config.wasm_target),
-            "-scheduler",
-            "none",
-            "-no-debug",
-            ".",
-        ])
-        .status()
-        .map_err(|e| {
-            if e.kind() == ErrorKind::NotFound {
-                anyhow!("{:?} command is not found", command.get_program())
-            } else {
-                anyhow!(e)
-            }
-        })?;
+    let result = processes::status(
+        command
+            .args([
+                "build",
+                "-o",
+                filename.as_str(),
+                "-target",
+                tinygo_config.build_target(&component_config.wasm_target),
+                "-scheduler",
+                "none",
+                "-no-debug",
+                ".",
+            ])
+            .envs(env),
+    )
+    .map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            anyhow!("{:?} command is not found", command.get_program())
+        } else {
+            anyhow!(e)
+        }
+    })?;
 
     if !result.success() {
         bail!("Compiling component failed: {}", result.to_string())
@@ -331,6 +415,7 @@ fn build_custom_component(
     common_config: &CommonConfig,
     component_config: &ComponentConfig,
     raw_command: &str,
+    env: &HashMap<String, String>,
 ) -> Result<PathBuf> {
     // Change directory into the project directory
     std::env::set_current_dir(&common_config.path)?;
@@ -339,10 +424,11 @@ fn build_custom_component(
     // All remaining elements of the split command are interpreted as arguments
     command
         .args(args)
+        .envs(env)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
 
-    let output = command.output().map_err(|e| {
+    let output = processes::output(&mut command).map_err(|e| {
         if e.kind() == ErrorKind::NotFound {
             anyhow!("`{:?}` was not found", command.get_program())
         } else {
@@ -576,7 +662,7 @@ fn parse_custom_command(command: &str) -> Result<(&str, Vec<&str>)> {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::fs;
     use std::fs::DirEntry;
     use std::path::Path;
@@ -590,11 +676,12 @@ mod tests {
     use crate::parser::RegistryConfig;
     use crate::{
         build::WASMCLOUD_WASM_TAG_EXPERIMENTAL,
-        parser::{CommonConfig, ComponentConfig, WasmTarget},
+        parser::{BuildConfig, CommonConfig, ComponentConfig, WasmTarget},
     };
 
     use super::{
-        embed_wasm_component_metadata, generate_tinygo_bindgen, sign_component_wasm, SignConfig,
+        embed_wasm_component_metadata, generate_tinygo_bindgen, run_post_build_commands,
+        sign_component_wasm, SignConfig,
     };
 
     const MODULE_WAT: &str = "(module)";
@@ -748,6 +835,109 @@ world downstream {
         Ok(())
     }
 
+    /// Ensure that post-build commands run before signing, and that the signed component
+    /// is derived from the output of the last command
+    #[test]
+    fn post_build_commands_run_before_signing() -> Result<()> {
+        let project_dir = tempfile::tempdir()?;
+        let wasm_path = setup_build_component(&project_dir)?;
+        fs::write(
+            project_dir.path().join("post.wasm"),
+            wat::parse_str(r#"(module (func (export "post")))"#)?,
+        )?;
+        let common_config = CommonConfig {
+            name: "test".into(),
+            version: Version::parse("0.1.0")?,
+            revision: 0,
+            path: project_dir.path().into(),
+            wasm_bin_name: Some("test.wasm".into()),
+            registry: RegistryConfig::default(),
+        };
+
+        // Relative paths are resolved from the project directory
+        run_post_build_commands(
+            &common_config,
+            &BuildConfig {
+                post_commands: vec![
+                    "cp {input} input-copy.wasm".into(),
+                    "cp post.wasm {output}".into(),
+                ],
+                ..Default::default()
+            },
+            &wasm_path,
+        )?;
+        assert!(project_dir.path().join("input-copy.wasm").exists());
+
+        let signed_wasm_path = sign_component_wasm(
+            &common_config,
+            &ComponentConfig::default(),
+            &SignConfig::default(),
+            &wasm_path,
+        )?;
+        let signed_wasm = fs::read(signed_wasm_path).context("failed to read signed wasm")?;
+        assert!(extract_claims(&signed_wasm)?.is_some());
+        let mut exports_post = false;
+        for payload in Parser::new(0).parse_all(&signed_wasm) {
+            if let Payload::ExportSection(exports) = payload? {
+                for export in exports {
+                    exports_post |= export?.name == "post";
+                }
+            }
+        }
+        assert!(
+            exports_post,
+            "signed component should be the output of the post command"
+        );
+
+        // Failing commands fail the build
+        let err = run_post_build_commands(
+            &common_config,
+            &BuildConfig {
+                post_commands: vec!["false".into()],
+                ..Default::default()
+            },
+            &wasm_path,
+        )
+        .expect_err("failing post command should fail");
+        assert!(err
+            .to_string()
+            .contains("post-build command [false] failed"));
+
+        Ok(())
+    }
+
+    /// Ensure that post-build commands inherit the environment of wash, with the variables of the
+    /// `[build]` section added
+    #[cfg(unix)]
+    #[test]
+    fn post_build_commands_inherit_env() -> Result<()> {
+        let project_dir = tempfile::tempdir()?;
+        let wasm_path = setup_build_component(&project_dir)?;
+        fs::write(
+            project_dir.path().join("check-env.sh"),
+            r#"test "$WASH_BUILD_ENV_TEST" = configured && test -n "$PATH""#,
+        )?;
+        let common_config = CommonConfig {
+            name: "test".into(),
+            version: Version::parse("0.1.0")?,
+            revision: 0,
+            path: project_dir.path().into(),
+            wasm_bin_name: Some("test.wasm".into()),
+            registry: RegistryConfig::default(),
+        };
+
+        let mut build_config = BuildConfig {
+            post_commands: vec!["sh check-env.sh".into()],
+            ..Default::default()
+        };
+        assert!(run_post_build_commands(&common_config, &build_config, &wasm_path).is_err());
+        build_config.env =
+            HashMap::from([("WASH_BUILD_ENV_TEST".to_string(), "configured".to_string())]);
+        run_post_build_commands(&common_config, &build_config, &wasm_path)?;
+
+        Ok(())
+    }
+
     /// Ensure that golang component generation works with a bindgen'd component
     #[test]
     fn golang_generate_bindgen_component_basic() -> Result<()> {
@@ -10,6 +10,7 @@ use crate::parser::{ProjectConfig, TypeConfig};
 
 mod component;
 pub use component::*;
+pub mod processes;
 mod provider;
 use provider::build_provider;
 
@@ -41,6 +42,8 @@ pub struct SignConfig {
 /// or [`build_provider`] when the project is a provider.
 ///
 /// This function returns the path to the compiled artifact, a signed Wasm component or signed provider archive.
+/// Any [`post_commands`](crate::parser::BuildConfig::post_commands) are run on a built component before it is signed.
+/// Build processes inherit the environment of wash, with the [`env`](crate::parser::BuildConfig::env) variables added.
 ///
 /// # Usage
 /// ```no_run
@@ -57,11 +60,38 @@ pub async fn build_project(
     signing: Option<&SignConfig>,
 ) -> Result<PathBuf> {
     match &config.project_type {
-        TypeConfig::Component(component_config) => {
-            build_component(component_config, &config.language, &config.common, signing)
+        TypeConfig::Component(component_config) if !config.build.post_commands.is_empty() => {
+            // Post commands operate on the unsigned component, signing happens on their final output
+            let component_wasm_path = build_component_with_env(
+                component_config,
+                &config.language,
+                &config.common,
+                &config.build.env,
+                None,
+            )?;
+            run_post_build_commands(&config.common, &config.build, &component_wasm_path)?;
+            if let Some(cfg) = signing {
+                sign_component_wasm(&config.common, component_config, cfg, component_wasm_path)
+            } else {
+                Ok(component_wasm_path)
+            }
         }
+        TypeConfig::Component(component_config) => build_component_with_env(
+            component_config,
+            &config.language,
+            &config.common,
+            &config.build.env,
+            signing,
+        ),
         TypeConfig::Provider(provider_config) => {
-            build_provider(provider_config, &config.language, &config.common, signing).await
+            build_provider(
+                provider_config,
+                &config.language,
+                &config.common,
+                &config.build.env,
+                signing,
+            )
+            .await
         }
     }
 }
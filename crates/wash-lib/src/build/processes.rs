@@ -0,0 +1,108 @@
+//! Processes spawned by builds, e.g. `cargo` or `tinygo`
+//!
+//! Build processes run in the process group of wash by default, so that signals sent from a
+//! terminal reach them as well. Callers handling signals themselves, like `wash dev`, call
+//! [`isolate_build_processes`] to run each build process in its own process group instead, then
+//! forward the signals they receive to the groups listed by [`running_builds`] and wait for the
+//! builds to exit rather than orphaning them.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::process::{Child, Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Whether build processes run in their own process group
+static ISOLATED: AtomicBool = AtomicBool::new(false);
+
+/// IDs of the build processes running in their own process group, which are also the IDs of
+/// their process groups
+static RUNNING: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// Run the build processes spawned from now on in their own process group, so that they do not
+/// receive the signals sent to wash. The caller is responsible for forwarding signals to the
+/// process groups listed by [`running_builds`]
+pub fn isolate_build_processes() {
+    ISOLATED.store(true, Ordering::SeqCst);
+}
+
+/// IDs of the process groups of the build processes still running, if build processes are
+/// isolated, see [`isolate_build_processes`]
+#[must_use]
+pub fn running_builds() -> Vec<u32> {
+    RUNNING
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .copied()
+        .collect()
+}
+
+/// Build process registered in [`RUNNING`] until dropped
+struct RunningBuild(u32);
+
+impl RunningBuild {
+    fn register(id: u32) -> Self {
+        RUNNING
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(id);
+        Self(id)
+    }
+}
+
+impl Drop for RunningBuild {
+    fn drop(&mut self) {
+        RUNNING
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(&self.0);
+    }
+}
+
+/// Spawn `command` and wait for it with `wait`, tracking it while it runs if build processes are
+/// isolated
+fn run<T>(command: &mut Command, wait: impl FnOnce(Child) -> io::Result<T>) -> io::Result<T> {
+    let isolated = ISOLATED.load(Ordering::SeqCst);
+    #[cfg(unix)]
+    if isolated {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let child = command.spawn()?;
+    let _running = isolated.then(|| RunningBuild::register(child.id()));
+    wait(child)
+}
+
+/// Run `command` to completion, like [`Command::status`]
+pub(crate) fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    run(command, |mut child| child.wait())
+}
+
+/// Run `command` to completion collecting its output, like [`Command::output`]. The output
+/// streams to collect must be piped
+pub(crate) fn output(command: &mut Command) -> io::Result<Output> {
+    run(command, Child::wait_with_output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn isolated_builds_are_tracked_until_they_exit() {
+        isolate_build_processes();
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo $$; ps -o pgid= -p $$"]);
+        command.stdout(std::process::Stdio::piped());
+        let output = output(&mut command).expect("build process should run");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("output should be UTF-8");
+        let ids: Vec<&str> = stdout.split_whitespace().collect();
+        // The build process leads its own process group
+        assert_eq!(ids.len(), 2, "unexpected output: {stdout}");
+        assert_eq!(ids[0], ids[1]);
+        assert!(!running_builds().contains(&ids[0].parse().expect("pid should be a number")));
+    }
+}
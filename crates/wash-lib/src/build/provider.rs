@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path::PathBuf;
 use std::process;
@@ -6,7 +7,7 @@ use anyhow::{anyhow, bail, Context, Result};
 use nkeys::KeyPairType;
 use tracing::{trace, warn};
 
-use crate::build::SignConfig;
+use crate::build::{processes, SignConfig};
 use crate::cli::par::{create_provider_archive, detect_arch, ParCreateArgs};
 use crate::cli::{extract_keypair, OutputKind};
 use crate::parser::{CommonConfig, GoConfig, LanguageConfig, ProviderConfig, RustConfig};
@@ -17,14 +18,15 @@ pub(crate) async fn build_provider(
     provider_config: &ProviderConfig,
     language_config: &LanguageConfig,
     common_config: &CommonConfig,
+    env: &HashMap<String, String>,
     signing_config: Option<&SignConfig>,
 ) -> Result<PathBuf> {
     let (provider_path_buf, bin_name) = match language_config {
         LanguageConfig::Rust(rust_config) => {
-            build_rust_provider(provider_config, rust_config, common_config)?
+            build_rust_provider(provider_config, rust_config, common_config, env)?
         }
         LanguageConfig::Go(go_config) => {
-            build_go_provider(provider_config, go_config, common_config)?
+            build_go_provider(provider_config, go_config, common_config, env)?
         }
         _ => bail!("Unsupported language for provider: {:?}", language_config),
     };
@@ -93,6 +95,7 @@ fn build_rust_provider(
     provider_config: &ProviderConfig,
     rust_config: &RustConfig,
     common_config: &CommonConfig,
+    env: &HashMap<String, String>,
 ) -> Result<(PathBuf, String)> {
     let mut command = match rust_config.cargo_path.as_ref() {
         Some(path) => process::Command::new(path),
@@ -110,7 +113,7 @@ fn build_rust_provider(
         build_args.extend_from_slice(&["--target", override_target]);
     };
 
-    let result = command.args(build_args).status().map_err(|e| {
+    let result = processes::status(command.args(build_args).envs(env)).map_err(|e| {
         if e.kind() == ErrorKind::NotFound {
             anyhow!("{:?} command is not found", command.get_program())
         } else {
@@ -161,6 +164,7 @@ fn build_go_provider(
     provider_config: &ProviderConfig,
     go_config: &GoConfig,
     common_config: &CommonConfig,
+    env: &HashMap<String, String>,
 ) -> Result<(PathBuf, String)> {
     let mut generate_command = match go_config.go_path.as_ref() {
         Some(path) => process::Command::new(path),
@@ -173,9 +177,7 @@ fn build_go_provider(
 
     // Generate interfaces, if not disabled
     if !go_config.disable_go_generate {
-        let result = generate_command
-            .args(["generate", "./..."])
-            .status()
+        let result = processes::status(generate_command.args(["generate", "./..."]).envs(env))
             .map_err(|e| {
                 if e.kind() == ErrorKind::NotFound {
                     anyhow!("{:?} command is not found", generate_command.get_program())
@@ -200,9 +202,7 @@ fn build_go_provider(
         None => process::Command::new("go"),
     };
     // Build for a specified target
-    let result = build_command
-        .args(["build", "-o", &bin_name])
-        .status()
+    let result = processes::status(build_command.args(["build", "-o", &bin_name]).envs(env))
         .map_err(|e| {
             if e.kind() == ErrorKind::NotFound {
                 anyhow!("{:?} command is not found", build_command.get_program())
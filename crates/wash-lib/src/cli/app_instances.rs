@@ -0,0 +1,334 @@
+//! Desired and actual instances of deployed applications, used by `wash app list --detailed`
+//!
+//! The desired instances of each component of an application are read from the scalers of its
+//! deployed manifest: a spread scaler runs its instances across the lattice, a daemon scaler runs
+//! them on every host matching its spread requirements. The actual instances are counted in host
+//! inventories, among the components and providers annotated by wadm as belonging to the
+//! application: the max instances of components, and the number of hosts running providers.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wadm_types::{api::Status, Manifest};
+use wasmcloud_control_interface::HostInventory;
+
+use super::get::{APP_SPEC_ANNOTATION, MANAGED_BY_ANNOTATION};
+
+/// Desired and actual instances of a component or provider of an application
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentInstances {
+    /// Name of the component in the manifest
+    pub name: String,
+    /// ID of the component or provider in the lattice
+    pub id: String,
+    /// Type of the component in the manifest, either `component` or `capability`
+    pub kind: String,
+    /// Instances requested by the scalers of the component
+    pub desired: u64,
+    /// Instances running in the lattice
+    pub actual: u64,
+}
+
+impl ComponentInstances {
+    /// Whether fewer instances are running than requested
+    #[must_use]
+    pub fn under_provisioned(&self) -> bool {
+        self.actual < self.desired
+    }
+}
+
+/// Desired and actual instances of a deployed application, per component and in total
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppInstances {
+    pub name: String,
+    /// Deployed version of the application
+    pub version: Option<String>,
+    /// Status of the application reported by wadm, `null` if it could not be queried
+    pub status: Option<String>,
+    pub components: Vec<ComponentInstances>,
+    /// Instances requested by the scalers of all components
+    pub desired: u64,
+    /// Instances of all components running in the lattice
+    pub actual: u64,
+    /// Whether any component runs fewer instances than requested
+    pub under_provisioned: bool,
+}
+
+impl AppInstances {
+    /// Instances of the application `name` deployed from `manifest`, counted in `inventories`
+    #[must_use]
+    pub fn new(
+        name: &str,
+        manifest: &Manifest,
+        status: Option<&Status>,
+        inventories: &[HostInventory],
+    ) -> Self {
+        // The manifest is read from its serialized form, like the status, so that the layouts of
+        // all wadm versions are supported
+        let manifest = serde_json::to_value(manifest).unwrap_or_default();
+        let version = manifest
+            .pointer("/metadata/annotations/version")
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+        let components: Vec<ComponentInstances> = manifest
+            .pointer("/spec/components")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|component| component_instances(name, component, inventories))
+            .collect();
+        Self {
+            name: name.to_string(),
+            version,
+            status: status.map(|status| format!("{:?}", status.info.status_type)),
+            desired: components.iter().map(|c| c.desired).sum(),
+            actual: components.iter().map(|c| c.actual).sum(),
+            under_provisioned: components.iter().any(ComponentInstances::under_provisioned),
+            components,
+        }
+    }
+}
+
+/// Instances of the manifest `component` of the application `app`
+fn component_instances(
+    app: &str,
+    component: &Value,
+    inventories: &[HostInventory],
+) -> ComponentInstances {
+    let field = |key: &str| {
+        component
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+    let name = field("name").to_string();
+    let kind = field("type").to_string();
+    let id = component
+        .pointer("/properties/id")
+        .and_then(Value::as_str)
+        .map_or_else(|| component_id(app, &name), ToString::to_string);
+
+    let desired = component
+        .get("traits")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|t| desired_instances(t, inventories))
+        .sum();
+
+    let belongs_to_app = |annotations: Option<&HashMap<String, String>>| {
+        annotations.is_some_and(|annotations| {
+            annotations.get(MANAGED_BY_ANNOTATION).map(String::as_str) == Some("wadm")
+                && annotations.get(APP_SPEC_ANNOTATION).map(String::as_str) == Some(app)
+        })
+    };
+    let actual = inventories
+        .iter()
+        .map(|inv| {
+            let components: u64 = inv
+                .components
+                .iter()
+                .filter(|c| c.id == id && belongs_to_app(c.annotations.as_ref()))
+                .map(|c| u64::from(c.max_instances))
+                .sum();
+            let providers = inv
+                .providers
+                .iter()
+                .filter(|p| p.id == id && belongs_to_app(p.annotations.as_ref()))
+                .count() as u64;
+            components + providers
+        })
+        .sum();
+
+    ComponentInstances {
+        name,
+        id,
+        kind,
+        desired,
+        actual,
+    }
+}
+
+/// ID assigned by wadm to the component `name` of the application `app` without an explicit ID
+fn component_id(app: &str, name: &str) -> String {
+    let normalize = |s: &str| s.to_lowercase().replace(' ', "_");
+    format!("{}-{}", normalize(app), normalize(name))
+}
+
+/// Instances requested by the trait `t`, zero if it is not a scaler
+fn desired_instances(t: &Value, inventories: &[HostInventory]) -> u64 {
+    let properties = t.get("properties");
+    // Older manifests name the number of instances `replicas`
+    let instances = properties
+        .and_then(|p| p.get("instances").or_else(|| p.get("replicas")))
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    match t.get("type").and_then(Value::as_str) {
+        Some("spreadscaler") => instances,
+        Some("daemonscaler") => {
+            let spread: Vec<&Value> = properties
+                .and_then(|p| p.get("spread"))
+                .and_then(Value::as_array)
+                .map(|spread| spread.iter().collect())
+                .unwrap_or_default();
+            let hosts = inventories
+                .iter()
+                .filter(|inv| {
+                    spread.is_empty() || spread.iter().any(|s| matches_requirements(s, &inv.labels))
+                })
+                .count() as u64;
+            instances * hosts
+        }
+        _ => 0,
+    }
+}
+
+/// Whether a host with `labels` satisfies the requirements of the `spread` entry of a scaler
+fn matches_requirements(spread: &Value, labels: &HashMap<String, String>) -> bool {
+    spread
+        .get("requirements")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key).map(String::as_str) == value.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use wasmcloud_control_interface::{ComponentDescription, ProviderDescription};
+
+    use super::*;
+
+    const MANIFEST: &str = r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: Rollup App
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: http-component
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 5
+    - name: httpserver
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/http-server:0.21.0
+        id: rollup-httpserver
+      traits:
+        - type: daemonscaler
+          properties:
+            instances: 1
+            spread:
+              - name: edge
+                requirements:
+                  zone: edge
+"#;
+
+    fn inventory(
+        zone: &str,
+        components: Vec<ComponentDescription>,
+        providers: Vec<ProviderDescription>,
+    ) -> HostInventory {
+        HostInventory {
+            labels: HashMap::from([("zone".to_string(), zone.to_string())]),
+            components,
+            providers,
+            ..Default::default()
+        }
+    }
+
+    fn managed_by(app: &str) -> Option<HashMap<String, String>> {
+        Some(HashMap::from([
+            (MANAGED_BY_ANNOTATION.to_string(), "wadm".to_string()),
+            (APP_SPEC_ANNOTATION.to_string(), app.to_string()),
+        ]))
+    }
+
+    #[test]
+    fn test_app_instances() {
+        let manifest: Manifest = serde_yaml::from_str(MANIFEST).expect("manifest should parse");
+        let component = |id: &str, max_instances, annotations| ComponentDescription {
+            id: id.to_string(),
+            max_instances,
+            annotations,
+            ..Default::default()
+        };
+        let provider = |id: &str, annotations| ProviderDescription {
+            id: id.to_string(),
+            annotations,
+            ..Default::default()
+        };
+        let inventories = [
+            inventory(
+                "edge",
+                vec![
+                    component("rollup_app-http-component", 2, managed_by("Rollup App")),
+                    // Started imperatively with the same ID
+                    component("rollup_app-http-component", 10, None),
+                ],
+                vec![provider("rollup-httpserver", managed_by("Rollup App"))],
+            ),
+            inventory(
+                "edge",
+                vec![component(
+                    "rollup_app-http-component",
+                    1,
+                    managed_by("Rollup App"),
+                )],
+                vec![],
+            ),
+            inventory(
+                "core",
+                vec![component("other", 1, managed_by("other"))],
+                vec![],
+            ),
+        ];
+
+        let instances = AppInstances::new("Rollup App", &manifest, None, &inventories);
+        assert_eq!(instances.version.as_deref(), Some("v0.0.1"));
+        assert_eq!(instances.status, None);
+        assert_eq!(
+            instances.components,
+            [
+                ComponentInstances {
+                    name: "http-component".to_string(),
+                    id: "rollup_app-http-component".to_string(),
+                    kind: "component".to_string(),
+                    desired: 5,
+                    actual: 3,
+                },
+                // The daemon scaler runs an instance on each of the two edge hosts
+                ComponentInstances {
+                    name: "httpserver".to_string(),
+                    id: "rollup-httpserver".to_string(),
+                    kind: "capability".to_string(),
+                    desired: 2,
+                    actual: 1,
+                },
+            ]
+        );
+        assert_eq!((instances.desired, instances.actual), (7, 4));
+        assert!(instances.under_provisioned);
+
+        let inventories = [inventory(
+            "core",
+            vec![component(
+                "rollup_app-http-component",
+                5,
+                managed_by("Rollup App"),
+            )],
+            vec![],
+        )];
+        let instances = AppInstances::new("Rollup App", &manifest, None, &inventories);
+        assert_eq!((instances.desired, instances.actual), (5, 5));
+        assert!(!instances.under_provisioned);
+    }
+}
@@ -0,0 +1,266 @@
+//! Validation of named configuration against the config schema embedded in provider archives
+//!
+//! Providers can embed a JSON schema describing the configuration keys they accept in the claims
+//! of their provider archive (see `wash par create --schema`). Since named configuration is a flat
+//! map of string keys to string values, only the subset of JSON schema that makes sense for such a
+//! map is supported: `properties` with their `type` and `enum`, and `additionalProperties`.
+//! Because the configuration of a provider or link is usually split across several named configs,
+//! `required` is not enforced, and any other schema keywords are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use provider_archive::ProviderArchive;
+use serde_json::Value;
+
+use super::cached_oci_file;
+use crate::common::get_all_inventories;
+use crate::registry::{get_oci_artifact, OciPullOptions};
+
+/// A violation of a provider config schema by a set of configuration values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSchemaViolation {
+    /// The key is not declared by the schema, and the schema does not allow additional keys
+    UnknownKey { key: String },
+    /// The value supplied for the key cannot be interpreted as the type the schema expects
+    InvalidType {
+        key: String,
+        expected: String,
+        value: String,
+    },
+    /// The value supplied for the key is not one of the values the schema allows
+    InvalidValue {
+        key: String,
+        allowed: Vec<String>,
+        value: String,
+    },
+}
+
+impl Display for ConfigSchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSchemaViolation::UnknownKey { key } => {
+                write!(f, "config key [{key}] is not accepted by the provider")
+            }
+            ConfigSchemaViolation::InvalidType {
+                key,
+                expected,
+                value,
+            } => write!(
+                f,
+                "config key [{key}] expects a value of type [{expected}], got [{value}]"
+            ),
+            ConfigSchemaViolation::InvalidValue {
+                key,
+                allowed,
+                value,
+            } => write!(
+                f,
+                "config key [{key}] expects one of [{}], got [{value}]",
+                allowed.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigSchemaViolation {}
+
+/// Validate configuration values against a provider config schema, returning the first violation
+/// found. Keys are checked in sorted order so the reported violation is deterministic.
+pub fn validate_config(
+    schema: &Value,
+    values: &HashMap<String, String>,
+) -> Result<(), ConfigSchemaViolation> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional_allowed =
+        !matches!(schema.get("additionalProperties"), Some(Value::Bool(false)));
+
+    let sorted: BTreeMap<_, _> = values.iter().collect();
+    for (key, value) in sorted {
+        let Some(property) = properties.and_then(|props| props.get(key)) else {
+            if additional_allowed {
+                continue;
+            }
+            return Err(ConfigSchemaViolation::UnknownKey { key: key.clone() });
+        };
+        validate_value(key, value, property)?;
+    }
+    Ok(())
+}
+
+/// Validate a single value against the schema of the property it was supplied for
+fn validate_value(key: &str, value: &str, property: &Value) -> Result<(), ConfigSchemaViolation> {
+    let types: Vec<&str> = match property.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(tys)) => tys.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|ty| value_has_type(value, ty)) {
+        return Err(ConfigSchemaViolation::InvalidType {
+            key: key.to_string(),
+            expected: types.join(" | "),
+            value: value.to_string(),
+        });
+    }
+
+    if let Some(allowed) = property.get("enum").and_then(Value::as_array) {
+        let allowed: Vec<String> = allowed
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect();
+        if !allowed.iter().any(|a| a == value) {
+            return Err(ConfigSchemaViolation::InvalidValue {
+                key: key.to_string(),
+                allowed,
+                value: value.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether a string config value can be interpreted as the given JSON schema type
+fn value_has_type(value: &str, ty: &str) -> bool {
+    match ty {
+        "integer" => value.parse::<i64>().is_ok(),
+        "number" => value.parse::<f64>().is_ok(),
+        "boolean" => matches!(value, "true" | "false"),
+        "null" => value == "null",
+        "array" => matches!(serde_json::from_str(value), Ok(Value::Array(_))),
+        "object" => matches!(serde_json::from_str(value), Ok(Value::Object(_))),
+        // Every config value is a string, and unknown types are not ours to reject
+        _ => true,
+    }
+}
+
+/// Load the config schema embedded in a provider archive, given either a path to the archive on
+/// disk or an OCI reference. Returns `None` if the provider does not declare a schema.
+pub async fn load_provider_schema(provider_ref: &str) -> Result<Option<Value>> {
+    let artifact = get_oci_artifact(
+        provider_ref.to_string(),
+        Some(cached_oci_file(provider_ref)),
+        OciPullOptions {
+            allow_latest: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .with_context(|| format!("failed to fetch provider archive [{provider_ref}]"))?;
+    let par = ProviderArchive::try_load(&artifact)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .with_context(|| format!("[{provider_ref}] is not a valid provider archive"))?;
+    Ok(par.schema())
+}
+
+/// Find the image reference of a provider running in the lattice by its ID. Returns `None` if no
+/// running provider has the given ID (for example, because it is a component).
+pub async fn find_provider_image_ref(
+    ctl_client: &wasmcloud_control_interface::Client,
+    provider_id: &str,
+) -> Result<Option<String>> {
+    Ok(get_all_inventories(ctl_client)
+        .await?
+        .into_iter()
+        .flat_map(|inventory| inventory.providers)
+        .find(|provider| provider.id == provider_id)
+        .and_then(|provider| provider.image_ref))
+}
+
+/// Resolve the config schema of a provider given either a path to a provider archive, an OCI
+/// reference or the ID of a provider running in the lattice.
+///
+/// Returns `Ok(None)` if the provider was found but does not declare a schema, and an error if the
+/// provider could not be found at all.
+pub async fn resolve_provider_schema(
+    ctl_client: &wasmcloud_control_interface::Client,
+    provider: &str,
+) -> Result<Option<Value>> {
+    if Path::new(provider).is_file() || provider.contains('/') {
+        return load_provider_schema(provider).await;
+    }
+    let image_ref = find_provider_image_ref(ctl_client, provider)
+        .await?
+        .with_context(|| {
+            format!("provider [{provider}] is not a provider archive, OCI reference or running provider")
+        })?;
+    load_provider_schema(&image_ref).await
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(values: &[(&str, &str)]) -> HashMap<String, String> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "address": { "type": "string" },
+                "port": { "type": "integer" },
+                "tls": { "type": "boolean" },
+                "mode": { "enum": ["fast", "safe"] },
+            },
+            "required": ["address"],
+            "additionalProperties": false,
+        });
+
+        assert_eq!(
+            validate_config(
+                &schema,
+                &config(&[("address", "0.0.0.0"), ("port", "8080"), ("mode", "safe")])
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate_config(&schema, &config(&[("adddress", "0.0.0.0")])),
+            Err(ConfigSchemaViolation::UnknownKey {
+                key: "adddress".to_string()
+            })
+        );
+        assert_eq!(
+            validate_config(&schema, &config(&[("address", "a"), ("port", "http")])),
+            Err(ConfigSchemaViolation::InvalidType {
+                key: "port".to_string(),
+                expected: "integer".to_string(),
+                value: "http".to_string(),
+            })
+        );
+        assert_eq!(
+            validate_config(&schema, &config(&[("address", "a"), ("tls", "yes")]))
+                .unwrap_err()
+                .to_string(),
+            "config key [tls] expects a value of type [boolean], got [yes]"
+        );
+        assert!(matches!(
+            validate_config(&schema, &config(&[("address", "a"), ("mode", "slow")])),
+            Err(ConfigSchemaViolation::InvalidValue { .. })
+        ));
+        // Required keys may be supplied by another named config
+        assert_eq!(
+            validate_config(&schema, &config(&[("port", "8080")])),
+            Ok(())
+        );
+
+        // Without additionalProperties set to false, unknown keys are allowed
+        let open = json!({ "properties": { "port": { "type": ["integer", "null"] } } });
+        assert_eq!(
+            validate_config(&open, &config(&[("port", "null"), ("other", "x")])),
+            Ok(())
+        );
+    }
+}
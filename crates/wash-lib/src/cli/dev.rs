@@ -1,36 +1,96 @@
-use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use cloudevents::event::{AttributesReader, Event};
+use cloudevents::{EventBuilder, EventBuilderV10};
 use console::style;
-use wasmcloud_control_interface::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use wasmcloud_control_interface::{Client, InterfaceLinkDefinition};
+use wit_parser::{Resolve, WorldItem};
 
 use crate::{
-    build::{build_project, SignConfig},
+    build::{build_project, sign_component_wasm, SignConfig},
+    common::boxed_err_to_anyhow,
     component::update_component,
     generate::emoji,
-    id::{ModuleId, ServerId},
-    parser::{ProjectConfig, TypeConfig},
+    id::ServerId,
+    parser::{DevLinkConfig, ProjectConfig, TypeConfig},
+    wait::{
+        wait_for_provider_start_event, wait_for_provider_stop_event, FindEventOutcome,
+        ProviderStartedInfo, ProviderStoppedInfo,
+    },
 };
 
-/// Perform a single execution of the dev loop for an artifact
+/// Duration of the phases of a single execution of the dev loop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DevLoopTimings {
+    pub build: Duration,
+    pub sign: Duration,
+    pub deploy: Duration,
+}
+
+/// Perform a single execution of the dev loop for an artifact, either a component or a provider
+/// started with the named configurations in `provider_config`
 pub async fn run_dev_loop(
     project_cfg: &ProjectConfig,
-    component_id: ModuleId,
+    component_id: &str,
     component_ref: &str,
     host_id: ServerId,
     ctl_client: &Client,
     sign_cfg: Option<SignConfig>,
-) -> Result<()> {
-    let built_artifact_path = build_project(project_cfg, sign_cfg.as_ref())
-        .await?
-        .canonicalize()?;
+    provider_config: Vec<String>,
+) -> Result<DevLoopTimings> {
+    let started = Instant::now();
+    let mut timings = DevLoopTimings::default();
+    let built_artifact_path = match (&project_cfg.project_type, sign_cfg) {
+        // Components are signed separately, so that signing is timed apart from the build
+        (TypeConfig::Component(component_cfg), Some(sign_cfg)) => {
+            let unsigned = build_project(project_cfg, None).await?;
+            timings.build = started.elapsed();
+            let signed =
+                sign_component_wasm(&project_cfg.common, component_cfg, &sign_cfg, unsigned)?;
+            timings.sign = started.elapsed() - timings.build;
+            signed
+        }
+        (_, sign_cfg) => {
+            let built = build_project(project_cfg, sign_cfg.as_ref()).await?;
+            timings.build = started.elapsed();
+            built
+        }
+    }
+    .canonicalize()?;
 
     // Restart the artifact so that changes can be observed
     match project_cfg.project_type {
         TypeConfig::Provider(_) => {
+            let build_id = dev_build_id(&built_artifact_path).await?;
             eprintln!(
                 "{} {}",
-                emoji::WARN,
-                style("`wash build` providers are not yet supported for dev, skipping...").bold(),
+                emoji::RECYCLE,
+                style(format!(
+                    "restarting provider @ [{}] (build {build_id})...",
+                    built_artifact_path.display()
+                ))
+                .bold(),
             );
+
+            let deploy_started = Instant::now();
+            swap_dev_provider(
+                ctl_client,
+                &host_id,
+                component_id,
+                component_ref,
+                &build_id,
+                provider_config,
+            )
+            .await?;
+            timings.deploy = deploy_started.elapsed();
         }
         TypeConfig::Component(_) => {
             eprintln!(
@@ -43,9 +103,1374 @@ pub async fn run_dev_loop(
                 .bold(),
             );
 
-            update_component(ctl_client, &host_id, &component_id, component_ref).await?;
+            let deploy_started = Instant::now();
+            update_component(ctl_client, &host_id, component_id, component_ref).await?;
+            timings.deploy = deploy_started.elapsed();
+        }
+    }
+
+    Ok(timings)
+}
+
+/// Annotation of providers started by `wash dev`, holding the ID of the build they were started
+/// from (see [`dev_build_id`])
+pub const DEV_BUILD_ANNOTATION: &str = "wash_dev_build";
+
+/// Maximum duration `wash dev` waits for a provider to stop or start
+pub const DEV_PROVIDER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// ID of the build of an artifact, the first 12 hexadecimal digits of its SHA-256 digest
+pub async fn dev_build_id(artifact_path: &Path) -> Result<String> {
+    let artifact = tokio::fs::read(artifact_path).await.with_context(|| {
+        format!(
+            "failed to read built artifact [{}]",
+            artifact_path.display()
+        )
+    })?;
+    let mut id = format!("{:x}", Sha256::digest(artifact));
+    id.truncate(12);
+    Ok(id)
+}
+
+/// Replace the provider with ID `provider_id` running on the host, if any, with a provider started
+/// from `provider_ref`, keeping its ID so that links to and from it still apply. The new provider
+/// is annotated with `build_id` and uses the named configurations in `config`.
+pub async fn swap_dev_provider(
+    ctl_client: &Client,
+    host_id: &str,
+    provider_id: &str,
+    provider_ref: &str,
+    build_id: &str,
+    config: Vec<String>,
+) -> Result<()> {
+    let inventory = ctl_client
+        .get_host_inventory(host_id)
+        .await
+        .map_err(boxed_err_to_anyhow)?
+        .response
+        .context("received control interface response with empty host inventory")?;
+    if inventory.providers.iter().any(|p| p.id == provider_id) {
+        let mut receiver = ctl_client
+            .events_receiver(vec![
+                "provider_stopped".to_string(),
+                "provider_stop_failed".to_string(),
+            ])
+            .await
+            .map_err(boxed_err_to_anyhow)?;
+        let ack = ctl_client
+            .stop_provider(host_id, provider_id)
+            .await
+            .map_err(boxed_err_to_anyhow)?;
+        if !ack.success {
+            bail!("failed to stop provider: {}", ack.message);
+        }
+        match wait_for_provider_stop_event(
+            &mut receiver,
+            DEV_PROVIDER_TIMEOUT,
+            host_id.to_string(),
+            provider_id.to_string(),
+        )
+        .await?
+        {
+            FindEventOutcome::Success(ProviderStoppedInfo { .. }) => {}
+            FindEventOutcome::Failure(err) => {
+                return Err(err.context("failed to stop provider"));
+            }
+        }
+    }
+
+    let mut receiver = ctl_client
+        .events_receiver(vec![
+            "provider_started".to_string(),
+            "provider_start_failed".to_string(),
+        ])
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    let ack = ctl_client
+        .start_provider(
+            host_id,
+            provider_ref,
+            provider_id,
+            Some(HashMap::from([
+                ("wash_dev".to_string(), "true".to_string()),
+                (DEV_BUILD_ANNOTATION.to_string(), build_id.to_string()),
+            ])),
+            config,
+        )
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !ack.success {
+        bail!("failed to start provider: {}", ack.message);
+    }
+    match wait_for_provider_start_event(
+        &mut receiver,
+        DEV_PROVIDER_TIMEOUT,
+        host_id.to_string(),
+        provider_ref.to_string(),
+    )
+    .await?
+    {
+        FindEventOutcome::Success(ProviderStartedInfo { .. }) => Ok(()),
+        FindEventOutcome::Failure(err) => Err(err.context("failed to start provider")),
+    }
+}
+
+/// Resolve the `[[dev.links]]` of a provider with ID `provider_id` into link definitions. Sources
+/// and targets default to the provider, and names of `companions`, given as `(name, ID)` pairs,
+/// are replaced with their IDs.
+pub fn dev_provider_links(
+    provider_id: &str,
+    links: &[DevLinkConfig],
+    companions: &[(&str, &str)],
+) -> Result<Vec<InterfaceLinkDefinition>> {
+    let resolve = |id: &Option<String>| match id {
+        None => provider_id.to_string(),
+        Some(id) => companions
+            .iter()
+            .find(|(name, _)| name == id)
+            .map_or_else(|| id.clone(), |(_, id)| id.to_string()),
+    };
+    links
+        .iter()
+        .map(|link| {
+            if link.source_id.is_some() && link.target.is_some() {
+                bail!(
+                    "dev link on {}:{}/{} must have the provider as its source or target, omit `source_id` or `target`",
+                    link.wit_namespace,
+                    link.wit_package,
+                    link.interfaces.join(",")
+                );
+            }
+            Ok(InterfaceLinkDefinition {
+                source_id: resolve(&link.source_id),
+                target: resolve(&link.target),
+                name: link.name.clone(),
+                wit_namespace: link.wit_namespace.clone(),
+                wit_package: link.wit_package.clone(),
+                interfaces: link.interfaces.clone(),
+                source_config: link.source_config.clone(),
+                target_config: link.target_config.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Path of the file `wash dev` appends the metrics of every iteration to, relative to the project
+pub const DEV_METRICS_PATH: &str = ".wash/dev-metrics.ndjson";
+
+/// Number of recent iterations the median total duration printed after each iteration is taken over
+pub const DEV_METRICS_RECENT_ITERATIONS: usize = 10;
+
+/// Durations of the phases of a single iteration of `wash dev`, recorded as a line of
+/// [`DEV_METRICS_PATH`]. All durations are in milliseconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevIterationMetrics {
+    /// Number of the iteration within its `wash dev` session, starting at 1
+    pub iteration: u64,
+    /// Name of the rebuilt component
+    pub component: String,
+    /// Unix timestamp of the change that triggered the iteration, in milliseconds
+    pub timestamp_ms: u64,
+    /// Time from detecting the change to starting the build
+    pub debounce_ms: u64,
+    pub build_ms: u64,
+    pub sign_ms: u64,
+    /// Time for the host to acknowledge the update of the component
+    pub deploy_ms: u64,
+    /// Time to relink the components under development, zero without companions
+    pub link_ms: u64,
+    /// Time from detecting the change to the end of the iteration
+    pub total_ms: u64,
+}
+
+impl DevIterationMetrics {
+    /// Phases recorded for every iteration, along with their durations
+    #[must_use]
+    pub fn phases(&self) -> [(&'static str, u64); 6] {
+        [
+            ("debounce", self.debounce_ms),
+            ("build", self.build_ms),
+            ("sign", self.sign_ms),
+            ("deploy", self.deploy_ms),
+            ("link", self.link_ms),
+            ("total", self.total_ms),
+        ]
+    }
+}
+
+/// Append `metrics` to the file at `path`, creating it and its parent directory if needed
+pub async fn append_dev_metrics(path: &Path, metrics: &DevIterationMetrics) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create [{}]", parent.display()))?;
+    }
+    let mut line = serde_json::to_vec(metrics).context("failed to serialize dev metrics")?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("failed to open [{}]", path.display()))?;
+    file.write_all(&line)
+        .await
+        .with_context(|| format!("failed to write [{}]", path.display()))
+}
+
+/// Read all metrics recorded in the file at `path`, skipping malformed lines. A missing file holds
+/// no metrics
+pub async fn load_dev_metrics(path: &Path) -> Result<Vec<DevIterationMetrics>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read [{}]", path.display())),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Percentile `p` of `values` using the nearest-rank method, zero if there are no values
+fn percentile(values: &mut [u64], p: usize) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let rank = (p * values.len()).div_ceil(100).max(1);
+    values[rank - 1]
+}
+
+/// Median and 95th percentile of the duration of a phase, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DevPhaseStats {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+}
+
+/// Summary of the metrics recorded by `wash dev`, see [`summarize_dev_metrics`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DevMetricsSummary {
+    /// Number of recorded iterations
+    pub count: usize,
+    /// Statistics of every phase, by name of the phase
+    pub phases: BTreeMap<&'static str, DevPhaseStats>,
+}
+
+/// Summarize the durations of every phase over all `metrics`
+#[must_use]
+pub fn summarize_dev_metrics(metrics: &[DevIterationMetrics]) -> DevMetricsSummary {
+    let mut durations: BTreeMap<&'static str, Vec<u64>> = BTreeMap::new();
+    for (phase, ms) in metrics.iter().flat_map(DevIterationMetrics::phases) {
+        durations.entry(phase).or_default().push(ms);
+    }
+    DevMetricsSummary {
+        count: metrics.len(),
+        phases: durations
+            .into_iter()
+            .map(|(phase, mut ms)| {
+                let stats = DevPhaseStats {
+                    p50_ms: percentile(&mut ms, 50),
+                    p95_ms: percentile(&mut ms, 95),
+                };
+                (phase, stats)
+            })
+            .collect(),
+    }
+}
+
+/// One-line summary of the latest iteration, given all `metrics` ending with it, e.g.
+/// `iteration 14: build 3.2s, deploy 1.1s, total 4.6s — p50 over last 10: 4.9s`
+#[must_use]
+pub fn format_dev_iteration(metrics: &[DevIterationMetrics]) -> Option<String> {
+    let latest = metrics.last()?;
+    let recent = &metrics[metrics.len().saturating_sub(DEV_METRICS_RECENT_ITERATIONS)..];
+    let mut totals: Vec<_> = recent.iter().map(|m| m.total_ms).collect();
+    let secs = |ms: u64| Duration::from_millis(ms).as_secs_f64();
+    Some(format!(
+        "iteration {}: build {:.1}s, deploy {:.1}s, total {:.1}s — p50 over last {}: {:.1}s",
+        latest.iteration,
+        secs(latest.build_ms),
+        secs(latest.deploy_ms),
+        secs(latest.total_ms),
+        recent.len(),
+        secs(percentile(&mut totals, 50)),
+    ))
+}
+
+/// Name of the configuration holding values from `wash dev` env files
+pub const DEV_ENV_CONFIG_NAME: &str = "dev-env";
+
+/// Prefixes of env file keys that are never forwarded, to avoid leaking tool configuration
+const SKIPPED_ENV_PREFIXES: [&str; 2] = ["WASH_", "WASMCLOUD_"];
+
+/// Parse dotenv-formatted content into key/value pairs.
+///
+/// Supports comments, blank lines, an optional `export` prefix and single/double quoted values.
+/// Keys starting with `WASH_` or `WASMCLOUD_` are skipped.
+pub fn parse_env_file(contents: &str) -> Result<HashMap<String, String>> {
+    let mut values = HashMap::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("invalid line {}, expected KEY=VALUE", idx + 1);
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            bail!("invalid key [{key}] on line {}", idx + 1);
+        }
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let Some((quoted, _)) = quoted.rsplit_once('"') else {
+                bail!("unterminated quoted value on line {}", idx + 1);
+            };
+            unescape(quoted)
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            let Some((quoted, _)) = quoted.rsplit_once('\'') else {
+                bail!("unterminated quoted value on line {}", idx + 1);
+            };
+            quoted.to_string()
+        } else {
+            // Unquoted values may be followed by a comment
+            value
+                .split_once(" #")
+                .map_or(value, |(value, _)| value)
+                .trim_end()
+                .to_string()
+        };
+        if SKIPPED_ENV_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+        {
+            continue;
         }
+        values.insert(key.to_string(), value);
     }
+    Ok(values)
+}
+
+/// Process escape sequences in a double quoted env file value
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped @ ('"' | '\\'))) => {
+                out.push(escaped);
+                chars.next();
+            }
+            (c, _) => out.push(c),
+        }
+    }
+    out
+}
+
+/// Resolve env file paths relative to the project directory
+#[must_use]
+pub fn resolve_env_files(project_dir: &Path, env_files: &[PathBuf]) -> Vec<PathBuf> {
+    resolve_paths(project_dir, env_files)
+}
+
+/// Resolve companion project directories relative to the project directory
+#[must_use]
+pub fn resolve_companions(project_dir: &Path, companions: &[PathBuf]) -> Vec<PathBuf> {
+    resolve_paths(project_dir, companions)
+}
+
+fn resolve_paths(project_dir: &Path, paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .map(|path| {
+            let path = if path.is_absolute() {
+                path.clone()
+            } else {
+                project_dir.join(path)
+            };
+            path.canonicalize().unwrap_or(path)
+        })
+        .collect()
+}
+
+/// Read the given env files (later files take precedence) and put their values into the
+/// [`DEV_ENV_CONFIG_NAME`] named configuration, returning the number of values put.
+///
+/// Running components that use the configuration observe the new values through the host's
+/// config update path, so no rebuild or restart is needed.
+pub async fn put_dev_env_config(ctl_client: &Client, env_files: &[PathBuf]) -> Result<usize> {
+    let mut values = HashMap::new();
+    for path in env_files {
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read env file [{}]", path.display()))?;
+        values.extend(
+            parse_env_file(&contents)
+                .with_context(|| format!("failed to parse env file [{}]", path.display()))?,
+        );
+    }
+    let count = values.len();
+    let ack = ctl_client
+        .put_config(DEV_ENV_CONFIG_NAME, values)
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !ack.success {
+        bail!("failed to put dev env config: {}", ack.message);
+    }
+    Ok(count)
+}
+
+/// Name of the configuration of providers under development, which enables forwarding their logs to
+/// the lattice so that `wash dev` can print them
+pub const DEV_PROVIDER_CONFIG_NAME: &str = "dev-provider";
 
+/// Put the [`DEV_PROVIDER_CONFIG_NAME`] named configuration
+pub async fn put_dev_provider_config(ctl_client: &Client) -> Result<()> {
+    let ack = ctl_client
+        .put_config(
+            DEV_PROVIDER_CONFIG_NAME,
+            HashMap::from([("forward_logs".to_string(), "true".to_string())]),
+        )
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !ack.success {
+        bail!("failed to put dev provider config: {}", ack.message);
+    }
     Ok(())
 }
+
+/// Address the HTTP server provider listens on when its link sets no address
+pub const DEV_HTTP_SERVER_DEFAULT_ADDRESS: &str = "127.0.0.1:8000";
+
+/// Local address of the HTTP server serving the component `component_id`, read from the source
+/// configuration of the `wasi:http/incoming-handler` link targeting it. Returns `None` if no such
+/// link exists
+pub async fn dev_http_address(
+    ctl_client: &Client,
+    component_id: &str,
+) -> Result<Option<SocketAddr>> {
+    let links = ctl_client
+        .get_links()
+        .await
+        .map_err(boxed_err_to_anyhow)?
+        .response
+        .unwrap_or_default();
+    let Some(link) = links.iter().find(|link| {
+        link.target == component_id
+            && link.wit_namespace == "wasi"
+            && link.wit_package == "http"
+            && link.interfaces.iter().any(|i| i == "incoming-handler")
+    }) else {
+        return Ok(None);
+    };
+    let mut values = HashMap::new();
+    for name in &link.source_config {
+        if let Some(config) = ctl_client
+            .get_config(name)
+            .await
+            .map_err(boxed_err_to_anyhow)?
+            .response
+        {
+            values.extend(config);
+        }
+    }
+    http_server_address(&values).map(Some)
+}
+
+/// Address the HTTP server provider listens on with the link configuration `values`, reachable
+/// from this machine. Like the provider, keys are case-insensitive and `port` takes precedence over
+/// `address`
+pub fn http_server_address(values: &HashMap<String, String>) -> Result<SocketAddr> {
+    let get = |key: &str| {
+        values
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    };
+    let mut addr: SocketAddr = match (get("port"), get("address")) {
+        (Some(port), _) => SocketAddr::new(
+            Ipv4Addr::LOCALHOST.into(),
+            port.parse()
+                .with_context(|| format!("invalid HTTP server port `{port}`"))?,
+        ),
+        (None, Some(address)) => address
+            .parse()
+            .with_context(|| format!("invalid HTTP server address `{address}`"))?,
+        (None, None) => DEV_HTTP_SERVER_DEFAULT_ADDRESS
+            .parse()
+            .context("invalid default HTTP server address")?,
+    };
+    // A server listening on all interfaces is reachable on loopback
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+        IpAddr::V6(ip) if ip.is_unspecified() => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+        _ => {}
+    }
+    Ok(addr)
+}
+
+/// A WIT interface, as `(namespace, package, interface)`
+pub type WitInterface = (String, String, String);
+
+/// Named WIT interfaces imported and exported by a component
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentInterfaces {
+    pub imports: BTreeSet<WitInterface>,
+    pub exports: BTreeSet<WitInterface>,
+}
+
+/// Decode the named WIT interfaces imported and exported by a component
+pub fn component_interfaces(wasm: &[u8]) -> Result<ComponentInterfaces> {
+    let (resolve, world) = match wit_component::decode(wasm).context("failed to decode WIT")? {
+        wit_component::DecodedWasm::Component(resolve, world) => (resolve, world),
+        wit_component::DecodedWasm::WitPackage(..) => {
+            bail!("binary-encoded WIT packages are not components")
+        }
+    };
+    let world = resolve
+        .worlds
+        .get(world)
+        .context("component world missing")?;
+    Ok(ComponentInterfaces {
+        imports: named_interfaces(&resolve, world.imports.values()),
+        exports: named_interfaces(&resolve, world.exports.values()),
+    })
+}
+
+/// Collect the named interfaces out of imported or exported world items
+fn named_interfaces<'a>(
+    resolve: &Resolve,
+    items: impl IntoIterator<Item = &'a WorldItem>,
+) -> BTreeSet<WitInterface> {
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let WorldItem::Interface(id) = item else {
+                return None;
+            };
+            let interface = resolve.interfaces.get(*id)?;
+            let package = resolve.packages.get(interface.package?)?;
+            Some((
+                package.name.namespace.clone(),
+                package.name.name.clone(),
+                interface.name.clone()?,
+            ))
+        })
+        .collect()
+}
+
+/// Infer the links between components developed together, linking each component to the
+/// components exporting the interfaces it imports.
+///
+/// Links are grouped per WIT package. If more than one component exports interfaces of the same
+/// package imported by a component, the first one (in the given order) is linked.
+#[must_use]
+pub fn infer_links(components: &[(&str, &ComponentInterfaces)]) -> Vec<InterfaceLinkDefinition> {
+    let mut links: Vec<InterfaceLinkDefinition> = Vec::new();
+    for (source_id, source) in components {
+        for (target_id, target) in components {
+            if source_id == target_id {
+                continue;
+            }
+            for (namespace, package, interface) in source.imports.intersection(&target.exports) {
+                let existing = links.iter_mut().find(|link| {
+                    link.source_id == *source_id
+                        && link.wit_namespace == *namespace
+                        && link.wit_package == *package
+                });
+                match existing {
+                    Some(link) if link.target == *target_id => {
+                        link.interfaces.push(interface.clone());
+                    }
+                    // The package is already linked to another component
+                    Some(_) => {}
+                    None => links.push(InterfaceLinkDefinition {
+                        source_id: source_id.to_string(),
+                        target: target_id.to_string(),
+                        name: "default".to_string(),
+                        wit_namespace: namespace.clone(),
+                        wit_package: package.clone(),
+                        interfaces: vec![interface.clone()],
+                        source_config: vec![],
+                        target_config: vec![],
+                    }),
+                }
+            }
+        }
+    }
+    links
+}
+
+/// A change to the interfaces of a component between two builds, e.g. after its WIT world changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceChange {
+    AddedImport(WitInterface),
+    RemovedImport(WitInterface),
+    AddedExport(WitInterface),
+    RemovedExport(WitInterface),
+}
+
+impl fmt::Display for InterfaceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (change, (namespace, package, interface)) = match self {
+            Self::AddedImport(i) => ("added import", i),
+            Self::RemovedImport(i) => ("removed import", i),
+            Self::AddedExport(i) => ("added export", i),
+            Self::RemovedExport(i) => ("removed export", i),
+        };
+        write!(f, "{change} {namespace}:{package}/{interface}")
+    }
+}
+
+/// Changes to the interfaces of a component from `before` to `after`, imports first
+#[must_use]
+pub fn interface_changes(
+    before: &ComponentInterfaces,
+    after: &ComponentInterfaces,
+) -> Vec<InterfaceChange> {
+    let diff = |old: &BTreeSet<WitInterface>,
+                new: &BTreeSet<WitInterface>,
+                added: fn(WitInterface) -> InterfaceChange,
+                removed: fn(WitInterface) -> InterfaceChange| {
+        new.difference(old)
+            .cloned()
+            .map(added)
+            .chain(old.difference(new).cloned().map(removed))
+            .collect::<Vec<_>>()
+    };
+    let mut changes = diff(
+        &before.imports,
+        &after.imports,
+        InterfaceChange::AddedImport,
+        InterfaceChange::RemovedImport,
+    );
+    changes.extend(diff(
+        &before.exports,
+        &after.exports,
+        InterfaceChange::AddedExport,
+        InterfaceChange::RemovedExport,
+    ));
+    changes
+}
+
+/// Links of the component with ID `component_id` that its `interfaces` no longer use: links from
+/// it none of whose interfaces it imports, and links to it none of whose interfaces it exports
+#[must_use]
+pub fn stale_links<'a>(
+    component_id: &str,
+    interfaces: &ComponentInterfaces,
+    links: &'a [InterfaceLinkDefinition],
+) -> Vec<&'a InterfaceLinkDefinition> {
+    let uses = |set: &BTreeSet<WitInterface>, link: &InterfaceLinkDefinition| {
+        link.interfaces.iter().any(|interface| {
+            set.contains(&(
+                link.wit_namespace.clone(),
+                link.wit_package.clone(),
+                interface.clone(),
+            ))
+        })
+    };
+    links
+        .iter()
+        .filter(|link| {
+            (link.source_id == component_id && !uses(&interfaces.imports, link))
+                || (link.target == component_id && !uses(&interfaces.exports, link))
+        })
+        .collect()
+}
+
+/// Order components for deployment so that the targets of links are deployed before the
+/// components linking to them, returning indices into `component_ids`. Components are otherwise
+/// kept in the given order, which is also used to break dependency cycles.
+#[must_use]
+pub fn deploy_order(component_ids: &[&str], links: &[InterfaceLinkDefinition]) -> Vec<usize> {
+    let mut order: Vec<usize> = Vec::with_capacity(component_ids.len());
+    while order.len() < component_ids.len() {
+        let pending = |idx: &usize| !order.contains(idx);
+        let ready = (0..component_ids.len()).filter(pending).find(|idx| {
+            links
+                .iter()
+                .filter(|link| link.source_id == component_ids[*idx])
+                .all(|link| {
+                    component_ids
+                        .iter()
+                        .position(|id| *id == link.target)
+                        .map_or(true, |target| target == *idx || order.contains(&target))
+                })
+        });
+        // A cycle remains, deploy the first remaining component
+        let next = ready.unwrap_or_else(|| {
+            (0..component_ids.len())
+                .find(pending)
+                .expect("a component should be pending")
+        });
+        order.push(next);
+    }
+    order
+}
+
+/// Host events reporting that a component or provider failed to start, or that a component crashed
+pub const DEV_FAILURE_EVENTS: [&str; 3] = [
+    "component_scale_failed",
+    "component_invocation_failed",
+    "provider_start_failed",
+];
+
+/// Type of the event `wash dev` publishes to the lattice when a component starts crash-looping,
+/// published on `wasmbus.evt.{lattice}.crash_loop` along with host events
+pub const DEV_CRASH_LOOP_EVENT: &str = "com.wasmcloud.dev.crash_loop";
+
+/// Type of the event `wash dev` publishes to the lattice when a component calls an interface no
+/// target is linked to, published on `wasmbus.evt.{lattice}.missing_link` along with host events
+pub const DEV_MISSING_LINK_EVENT: &str = "com.wasmcloud.dev.missing_link";
+
+/// Default number of failures within [`DEV_CRASH_LOOP_WINDOW`] after which a component is
+/// considered crash-looping
+pub const DEV_CRASH_LOOP_THRESHOLD: usize = 3;
+
+/// Default window [`DEV_CRASH_LOOP_THRESHOLD`] failures are counted over
+pub const DEV_CRASH_LOOP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Parts of host errors that best describe why a component failed, from the most to the least
+/// specific
+const FAILURE_MARKERS: [&str; 5] = [
+    "panicked at",
+    "unknown `",
+    "matching implementation was not found",
+    "missing import",
+    "wasm trap",
+];
+
+/// Returns the reason of the failure reported by `event` (one of [`DEV_FAILURE_EVENTS`]) if it is
+/// about the component or provider with ID `id`
+#[must_use]
+pub fn dev_failure_reason(event: &Event, id: &str) -> Option<String> {
+    let name = event.ty().strip_prefix("com.wasmcloud.lattice.")?;
+    if !DEV_FAILURE_EVENTS.contains(&name) {
+        return None;
+    }
+    let data: serde_json::Value = event.data()?.clone().try_into().ok()?;
+    let subject = data
+        .get("component_id")
+        .or_else(|| data.get("provider_id"))
+        .and_then(serde_json::Value::as_str)?;
+    if subject != id {
+        return None;
+    }
+    let error = data
+        .get("error")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("unknown error");
+    Some(failure_diagnostic(error))
+}
+
+/// Extract the most actionable part of a host error (e.g. a panic message or a missing import),
+/// falling back to its first line
+#[must_use]
+pub fn failure_diagnostic(error: &str) -> String {
+    let lines: Vec<&str> = error
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    FAILURE_MARKERS
+        .iter()
+        .find_map(|marker| {
+            lines.iter().find_map(|line| {
+                let idx = line.find(marker)?;
+                // Skip the context of the error, e.g. `failed to call component: `
+                let start = line[..idx].rfind(": ").map_or(0, |start| start + 2);
+                Some(line[start..].to_string())
+            })
+        })
+        .or_else(|| lines.first().map(ToString::to_string))
+        .unwrap_or_else(|| error.to_string())
+}
+
+/// Returns the interface a component called without being linked to a target for it (e.g.
+/// `wasi:keyvalue/store`), if `reason` as returned by [`dev_failure_reason`] reports such a call
+#[must_use]
+pub fn unlinked_interface(reason: &str) -> Option<&str> {
+    let (_, interface) = reason.split_once("unknown `")?;
+    let (interface, _) = interface.split_once("` target")?;
+    Some(interface)
+}
+
+/// Describe how to fix the component named `name` with ID `component_id` calling `interface`
+/// without a link, pointing at the `[[dev.links]]` of the project declaring a link for it if any
+#[must_use]
+pub fn missing_link_hint(
+    interface: &str,
+    name: &str,
+    component_id: &str,
+    links: &[DevLinkConfig],
+) -> String {
+    let (package, iface) = interface.split_once('/').unwrap_or((interface, ""));
+    let (namespace, package) = package.split_once(':').unwrap_or((package, ""));
+    let iface = iface.split_once('@').map_or(iface, |(iface, _)| iface);
+    let declared = links.iter().find(|link| {
+        link.source_id
+            .as_deref()
+            .is_some_and(|source| source == name || source == component_id)
+            && link.wit_namespace == namespace
+            && link.wit_package == package
+            && link.interfaces.iter().any(|i| i == iface)
+    });
+    match declared {
+        Some(link) if link.name != "default" => format!(
+            "wasmcloud.toml links it with the name `{}` in [[dev.links]], set that link name before the call or remove the call",
+            link.name
+        ),
+        Some(_) => "wasmcloud.toml links it in [[dev.links]], make sure the target of the link is running or remove the call".to_string(),
+        None => format!(
+            "add an override with `wash link put {component_id} <provider-id> {namespace} {package} --interface {iface}` or remove the call"
+        ),
+    }
+}
+
+/// Returns the most recent panic message in host logs, spanning the `panicked at` line and the
+/// message following it
+#[must_use]
+pub fn last_panic_message(log: &str) -> Option<String> {
+    let lines: Vec<&str> = log.lines().collect();
+    let idx = lines
+        .iter()
+        .rposition(|line| line.contains("panicked at"))?;
+    let mut message = lines[idx].trim().to_string();
+    if let Some(next) = lines.get(idx + 1).map(|line| line.trim()) {
+        if !next.is_empty() && !next.starts_with("note:") {
+            message.push(' ');
+            message.push_str(next);
+        }
+    }
+    Some(message)
+}
+
+/// Detects components failing repeatedly, by counting their failures over a sliding window
+#[derive(Debug, Clone)]
+pub struct CrashLoopDetector {
+    threshold: usize,
+    window: Duration,
+    failures: VecDeque<Instant>,
+    crash_looping: bool,
+}
+
+impl Default for CrashLoopDetector {
+    fn default() -> Self {
+        Self::new(DEV_CRASH_LOOP_THRESHOLD, DEV_CRASH_LOOP_WINDOW)
+    }
+}
+
+impl CrashLoopDetector {
+    /// Create a detector considering a component crash-looping once it failed `threshold` times
+    /// within `window`
+    #[must_use]
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            failures: VecDeque::new(),
+            crash_looping: false,
+        }
+    }
+
+    /// Record a failure at `at`, returning the number of failures within the window if the
+    /// component started crash-looping with it
+    pub fn record_failure(&mut self, at: Instant) -> Option<usize> {
+        self.failures.push_back(at);
+        while self
+            .failures
+            .front()
+            .is_some_and(|first| at.saturating_duration_since(*first) > self.window)
+        {
+            self.failures.pop_front();
+        }
+        if self.crash_looping || self.failures.len() < self.threshold {
+            return None;
+        }
+        self.crash_looping = true;
+        Some(self.failures.len())
+    }
+
+    /// Whether the component is crash-looping, until [`Self::reset`]
+    #[must_use]
+    pub fn is_crash_looping(&self) -> bool {
+        self.crash_looping
+    }
+
+    /// Forget previous failures, e.g. once a new build of the component was deployed
+    pub fn reset(&mut self) {
+        self.failures.clear();
+        self.crash_looping = false;
+    }
+}
+
+/// Publish a [`DEV_CRASH_LOOP_EVENT`] about the component with ID `component_id` to the lattice
+pub async fn publish_crash_loop_event(
+    ctl_client: &Client,
+    lattice: &str,
+    component_id: &str,
+    failures: usize,
+    reason: &str,
+) -> Result<()> {
+    publish_dev_event(
+        ctl_client,
+        lattice,
+        DEV_CRASH_LOOP_EVENT,
+        component_id,
+        serde_json::json!({
+            "component_id": component_id,
+            "failures": failures,
+            "reason": reason,
+        }),
+    )
+    .await
+    .context("failed to publish crash loop event")
+}
+
+/// Publish a [`DEV_MISSING_LINK_EVENT`] about the component with ID `component_id` calling
+/// `interface` to the lattice
+pub async fn publish_missing_link_event(
+    ctl_client: &Client,
+    lattice: &str,
+    component_id: &str,
+    interface: &str,
+    hint: &str,
+) -> Result<()> {
+    publish_dev_event(
+        ctl_client,
+        lattice,
+        DEV_MISSING_LINK_EVENT,
+        component_id,
+        serde_json::json!({
+            "component_id": component_id,
+            "interface": interface,
+            "hint": hint,
+        }),
+    )
+    .await
+    .context("failed to publish missing link event")
+}
+
+/// Publish an event of type `ty` (one of the `com.wasmcloud.dev.*` types) about the component with
+/// ID `component_id` on `wasmbus.evt.{lattice}.*`, named after the last segment of its type
+async fn publish_dev_event(
+    ctl_client: &Client,
+    lattice: &str,
+    ty: &str,
+    component_id: &str,
+    data: serde_json::Value,
+) -> Result<()> {
+    let now = chrono::Utc::now();
+    let event = EventBuilderV10::new()
+        .id(format!("{component_id}-{}", now.timestamp_millis()))
+        .ty(ty)
+        .source("wash-dev")
+        .time(now)
+        .data("application/json", data)
+        .build()
+        .context("failed to build event")?;
+    let payload = serde_json::to_vec(&event).context("failed to serialize event")?;
+    let name = ty.rsplit('.').next().unwrap_or(ty);
+    ctl_client
+        .nats_client()
+        .publish(format!("wasmbus.evt.{lattice}.{name}"), payload.into())
+        .await
+        .map_err(anyhow::Error::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dev_metrics(iteration: u64, total_ms: u64) -> DevIterationMetrics {
+        DevIterationMetrics {
+            iteration,
+            component: "hello".into(),
+            timestamp_ms: 1_700_000_000_000 + iteration,
+            debounce_ms: 5,
+            build_ms: total_ms / 2,
+            sign_ms: 20,
+            deploy_ms: total_ms / 4,
+            link_ms: 0,
+            total_ms,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dev_metrics_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(DEV_METRICS_PATH);
+        assert!(load_dev_metrics(&path).await?.is_empty());
+
+        let first = dev_metrics(1, 4000);
+        let second = dev_metrics(2, 6000);
+        append_dev_metrics(&path, &first).await?;
+        append_dev_metrics(&path, &second).await?;
+        // Lines which cannot be parsed, e.g. from an interrupted write, are skipped
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(b"{\"iteration\":").await?;
+
+        let metrics = load_dev_metrics(&path).await?;
+        assert_eq!(metrics, vec![first, second]);
+        let line: serde_json::Value = serde_json::from_str(
+            tokio::fs::read_to_string(&path)
+                .await?
+                .lines()
+                .next()
+                .unwrap(),
+        )?;
+        for phase in [
+            "debounce_ms",
+            "build_ms",
+            "sign_ms",
+            "deploy_ms",
+            "link_ms",
+            "total_ms",
+        ] {
+            assert!(line[phase].is_u64(), "phase {phase} is recorded");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_summarize_dev_metrics() {
+        let metrics: Vec<_> = (1..=20).map(|i| dev_metrics(i, i * 1000)).collect();
+        let summary = summarize_dev_metrics(&metrics);
+        assert_eq!(summary.count, 20);
+        assert_eq!(
+            summary.phases["total"],
+            DevPhaseStats {
+                p50_ms: 10_000,
+                p95_ms: 19_000
+            }
+        );
+        assert_eq!(summary.phases["sign"].p95_ms, 20);
+        assert_eq!(summary.phases.len(), 6);
+        assert!(summarize_dev_metrics(&[]).phases.is_empty());
+
+        assert_eq!(
+            format_dev_iteration(&metrics).unwrap(),
+            "iteration 20: build 10.0s, deploy 5.0s, total 20.0s — p50 over last 10: 15.0s"
+        );
+        assert_eq!(format_dev_iteration(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let values = parse_env_file(
+            r#"
+# service endpoints
+API_URL=http://localhost:8080
+export FEATURE_FLAG = true
+QUOTED="hello world"
+SINGLE='it''s # literal'
+ESCAPED="line1\nline2"
+COMMENTED=value # trailing comment
+EMPTY=
+WASH_CTL_PORT=4222
+WASMCLOUD_LATTICE=default
+"#,
+        )
+        .expect("env file should parse");
+
+        assert_eq!(values.get("API_URL").unwrap(), "http://localhost:8080");
+        assert_eq!(values.get("FEATURE_FLAG").unwrap(), "true");
+        assert_eq!(values.get("QUOTED").unwrap(), "hello world");
+        assert_eq!(values.get("SINGLE").unwrap(), "it''s # literal");
+        assert_eq!(values.get("ESCAPED").unwrap(), "line1\nline2");
+        assert_eq!(values.get("COMMENTED").unwrap(), "value");
+        assert_eq!(values.get("EMPTY").unwrap(), "");
+        assert!(!values.contains_key("WASH_CTL_PORT"));
+        assert!(!values.contains_key("WASMCLOUD_LATTICE"));
+        assert_eq!(values.len(), 7);
+    }
+
+    #[test]
+    fn test_parse_env_file_invalid() {
+        assert!(parse_env_file("NO_EQUALS").is_err());
+        assert!(parse_env_file("=value").is_err());
+        assert!(parse_env_file("KEY=\"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_http_server_address() -> Result<()> {
+        let values = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            http_server_address(&values(&[]))?,
+            DEV_HTTP_SERVER_DEFAULT_ADDRESS.parse()?
+        );
+        assert_eq!(
+            http_server_address(&values(&[("ADDRESS", "0.0.0.0:8080")]))?,
+            "127.0.0.1:8080".parse()?
+        );
+        assert_eq!(
+            http_server_address(&values(&[("address", "[::]:8080")]))?,
+            "[::1]:8080".parse()?
+        );
+        assert_eq!(
+            http_server_address(&values(&[("address", "10.0.0.1:8080"), ("port", "9000")]))?,
+            "127.0.0.1:9000".parse()?
+        );
+        assert!(http_server_address(&values(&[("address", "localhost")])).is_err());
+        Ok(())
+    }
+
+    fn interfaces(imports: &[&str], exports: &[&str]) -> ComponentInterfaces {
+        let parse = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| {
+                    let (namespace, rest) = name.split_once(':').unwrap();
+                    let (package, interface) = rest.split_once('/').unwrap();
+                    (namespace.into(), package.into(), interface.into())
+                })
+                .collect()
+        };
+        ComponentInterfaces {
+            imports: parse(imports),
+            exports: parse(exports),
+        }
+    }
+
+    #[test]
+    fn test_infer_links() {
+        let app = interfaces(
+            &["test:auth/verify", "test:auth/tokens", "wasi:io/streams"],
+            &["wasi:http/incoming-handler"],
+        );
+        let auth = interfaces(
+            &["wasi:io/streams"],
+            &["test:auth/tokens", "test:auth/verify"],
+        );
+        let other_auth = interfaces(&[], &["test:auth/verify"]);
+
+        let links = infer_links(&[("app", &app), ("auth", &auth), ("other-auth", &other_auth)]);
+        assert_eq!(
+            links,
+            vec![InterfaceLinkDefinition {
+                source_id: "app".into(),
+                target: "auth".into(),
+                name: "default".into(),
+                wit_namespace: "test".into(),
+                wit_package: "auth".into(),
+                interfaces: vec!["tokens".into(), "verify".into()],
+                source_config: vec![],
+                target_config: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_interface_changes() {
+        let before = interfaces(
+            &["wasi:keyvalue/store", "wasi:logging/logging"],
+            &["wasi:http/incoming-handler"],
+        );
+        let after = interfaces(
+            &["wasi:logging/logging", "wasi:keyvalue/atomics"],
+            &["wasi:http/incoming-handler", "test:auth/verify"],
+        );
+        assert!(interface_changes(&before, &before).is_empty());
+        assert_eq!(
+            interface_changes(&before, &after)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "added import wasi:keyvalue/atomics",
+                "removed import wasi:keyvalue/store",
+                "added export test:auth/verify",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_links() {
+        let link = |source: &str, target: &str, package: &str, interfaces: &[&str]| {
+            InterfaceLinkDefinition {
+                source_id: source.into(),
+                target: target.into(),
+                name: "default".into(),
+                wit_namespace: "wasi".into(),
+                wit_package: package.into(),
+                interfaces: interfaces.iter().map(|i| i.to_string()).collect(),
+                source_config: vec![],
+                target_config: vec![],
+            }
+        };
+        let links = [
+            link("app", "kv", "keyvalue", &["store", "atomics"]),
+            link("app", "blobstore", "blobstore", &["blobstore"]),
+            link("http", "app", "http", &["incoming-handler"]),
+            link("http", "other", "http", &["incoming-handler"]),
+        ];
+
+        // Links are kept while any of their interfaces is used
+        let app = interfaces(&["wasi:keyvalue/store"], &["wasi:http/incoming-handler"]);
+        assert_eq!(stale_links("app", &app, &links), vec![&links[1]]);
+
+        let app = interfaces(&[], &[]);
+        assert_eq!(
+            stale_links("app", &app, &links),
+            vec![&links[0], &links[1], &links[2]]
+        );
+    }
+
+    #[test]
+    fn test_deploy_order() {
+        let link = |source: &str, target: &str| InterfaceLinkDefinition {
+            source_id: source.into(),
+            target: target.into(),
+            ..Default::default()
+        };
+
+        // Dependencies are deployed before their dependents
+        let ids = ["app", "auth", "db"];
+        let links = [link("app", "auth"), link("auth", "db")];
+        assert_eq!(deploy_order(&ids, &links), vec![2, 1, 0]);
+
+        // Unlinked components keep their order, and cycles don't prevent deployment
+        assert_eq!(deploy_order(&ids, &[]), vec![0, 1, 2]);
+        let cycle = [link("app", "auth"), link("auth", "app")];
+        assert_eq!(deploy_order(&ids[..2], &cycle), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_dev_provider_links() -> Result<()> {
+        let link = |source_id: Option<&str>, target: Option<&str>| DevLinkConfig {
+            source_id: source_id.map(String::from),
+            target: target.map(String::from),
+            name: "default".into(),
+            wit_namespace: "wasi".into(),
+            wit_package: "keyvalue".into(),
+            interfaces: vec!["store".into()],
+            ..Default::default()
+        };
+        let companions = [("app", "app_s_wasm")];
+
+        // The provider is the default source and target, and companions are referred to by name
+        let links = dev_provider_links(
+            "provider",
+            &[link(Some("app"), None), link(None, Some("other-component"))],
+            &companions,
+        )?;
+        assert_eq!(
+            links
+                .iter()
+                .map(|l| (l.source_id.as_str(), l.target.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("app_s_wasm", "provider"), ("provider", "other-component")]
+        );
+        assert_eq!(links[0].interfaces, vec!["store".to_string()]);
+
+        // Links must involve the provider
+        assert!(
+            dev_provider_links("provider", &[link(Some("a"), Some("b"))], &companions).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_crash_loop_detector() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut detector = CrashLoopDetector::new(3, Duration::from_secs(10));
+
+        // Failures spread over more than the window are not a crash loop
+        assert_eq!(detector.record_failure(at(0)), None);
+        assert_eq!(detector.record_failure(at(8)), None);
+        assert_eq!(detector.record_failure(at(15)), None);
+        assert!(!detector.is_crash_looping());
+
+        // The crash loop is only reported once
+        assert_eq!(detector.record_failure(at(16)), Some(3));
+        assert!(detector.is_crash_looping());
+        assert_eq!(detector.record_failure(at(17)), None);
+        assert!(detector.is_crash_looping());
+
+        detector.reset();
+        assert!(!detector.is_crash_looping());
+        assert_eq!(detector.record_failure(at(18)), None);
+    }
+
+    #[test]
+    fn test_failure_diagnostic() {
+        assert_eq!(
+            failure_diagnostic(
+                "failed to initialize component: component imports instance `wasi:keyvalue/store`, but a matching implementation was not found in the linker"
+            ),
+            "component imports instance `wasi:keyvalue/store`, but a matching implementation was not found in the linker"
+        );
+        assert_eq!(
+            failure_diagnostic(
+                "failed to call component: error while executing at wasm backtrace:\n    0: 0x1c2e - crasher.wasm!rust_panic\n\nCaused by:\n    wasm trap: wasm `unreachable` instruction executed"
+            ),
+            "wasm trap: wasm `unreachable` instruction executed"
+        );
+        assert_eq!(
+            failure_diagnostic("failed to fetch component\nno such file"),
+            "failed to fetch component"
+        );
+        assert_eq!(
+            last_panic_message(
+                "INFO starting\nthread '<unnamed>' panicked at src/lib.rs:9:9:\nmissing required config `greeting`\nnote: run with `RUST_BACKTRACE=1`\n"
+            )
+            .as_deref(),
+            Some("thread '<unnamed>' panicked at src/lib.rs:9:9: missing required config `greeting`")
+        );
+        assert_eq!(last_panic_message("INFO all good"), None);
+    }
+
+    #[test]
+    fn test_missing_link() {
+        let reason = failure_diagnostic(
+            "failed to call `wasi:http/incoming-handler.handle`: error while executing at wasm backtrace:\n    0: 0x2f1a - app.wasm!handle\n\nCaused by:\n    unknown `wasi:keyvalue/store` target",
+        );
+        assert_eq!(reason, "unknown `wasi:keyvalue/store` target");
+        assert_eq!(unlinked_interface(&reason), Some("wasi:keyvalue/store"));
+        assert_eq!(unlinked_interface("wasm trap: out of bounds"), None);
+
+        assert_eq!(
+            missing_link_hint("wasi:keyvalue/store", "app", "dev-app", &[]),
+            "add an override with `wash link put dev-app <provider-id> wasi keyvalue --interface store` or remove the call"
+        );
+        let mut link = DevLinkConfig {
+            source_id: Some("app".into()),
+            name: "cache".into(),
+            wit_namespace: "wasi".into(),
+            wit_package: "keyvalue".into(),
+            interfaces: vec!["store".into(), "atomics".into()],
+            ..Default::default()
+        };
+        assert!(
+            missing_link_hint("wasi:keyvalue/store", "app", "dev-app", &[link.clone()])
+                .contains("with the name `cache`")
+        );
+        link.name = "default".into();
+        assert!(
+            missing_link_hint("wasi:keyvalue/store", "app", "dev-app", &[link.clone()])
+                .contains("make sure the target of the link is running")
+        );
+        link.source_id = Some("other".into());
+        assert!(
+            missing_link_hint("wasi:keyvalue/store", "app", "dev-app", &[link])
+                .starts_with("add an override")
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! An ephemeral OCI registry, served in-process on the loopback interface, through which
+//! `wash start component` serves local components to hosts which cannot load them from files
+//!
+//! The registry only serves pulls of the components pushed to it with
+//! [`DevRegistry::push_component`], and stops once dropped.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use oci_distribution::manifest::OciImageManifest;
+use oci_wasm::{ToConfig, WasmConfig, WASM_MANIFEST_MEDIA_TYPE};
+use sha2::Digest as _;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+const DIGEST_HEADER: &str = "Docker-Content-Digest";
+
+#[derive(Debug)]
+struct Manifest {
+    digest: String,
+    media_type: String,
+    data: Bytes,
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    /// Manifests keyed by repository and reference, i.e. tag or digest
+    manifests: HashMap<(String, String), Arc<Manifest>>,
+    /// Blobs keyed by digest
+    blobs: HashMap<String, Bytes>,
+}
+
+/// Ephemeral OCI registry serving components on `127.0.0.1`
+#[derive(Debug)]
+pub struct DevRegistry {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    server: JoinHandle<()>,
+}
+
+impl Drop for DevRegistry {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl DevRegistry {
+    /// Start serving an empty registry on a random port of `127.0.0.1`
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .context("failed to bind ephemeral registry")?;
+        let addr = listener
+            .local_addr()
+            .context("failed to get address of ephemeral registry")?;
+        let store = Arc::<Mutex<Store>>::default();
+        let server = tokio::spawn({
+            let store = Arc::clone(&store);
+            async move {
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            debug!(?err, "failed to accept ephemeral registry connection");
+                            continue;
+                        }
+                    };
+                    let store = Arc::clone(&store);
+                    tokio::spawn(async move {
+                        let service = service_fn(move |req| {
+                            let res = handle_request(&store, &req);
+                            async move { Ok::<_, Infallible>(res) }
+                        });
+                        if let Err(err) = http1::Builder::new()
+                            .serve_connection(TokioIo::new(stream), service)
+                            .await
+                        {
+                            debug!(?err, "failed to serve ephemeral registry connection");
+                        }
+                    });
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            store,
+            server,
+        })
+    }
+
+    /// Address the registry is served on
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Push the `component` to `repository`, returning its reference, tagged with the digest of
+    /// its manifest
+    pub fn push_component(&self, repository: &str, component: Vec<u8>) -> Result<String> {
+        let (config, layer) =
+            WasmConfig::from_raw_component(component, None).context("failed to parse component")?;
+        let config = config
+            .to_config()
+            .context("failed to create component config")?;
+        let mut manifest = OciImageManifest::build(std::slice::from_ref(&layer), &config, None);
+        manifest.media_type = Some(WASM_MANIFEST_MEDIA_TYPE.to_string());
+        let data = serde_json::to_vec(&manifest).context("failed to serialize manifest")?;
+        let digest = format!("sha256:{:x}", sha2::Sha256::digest(&data));
+        let tag = digest
+            .strip_prefix("sha256:")
+            .map_or(digest.as_str(), |hex| &hex[..12])
+            .to_string();
+        let repository = repository.to_lowercase();
+
+        let manifest = Arc::new(Manifest {
+            digest: digest.clone(),
+            media_type: WASM_MANIFEST_MEDIA_TYPE.to_string(),
+            data: data.into(),
+        });
+        let mut store = self.store.lock().unwrap_or_else(|err| err.into_inner());
+        store
+            .blobs
+            .insert(config.sha256_digest(), config.data.into());
+        store.blobs.insert(layer.sha256_digest(), layer.data.into());
+        store
+            .manifests
+            .insert((repository.clone(), digest), Arc::clone(&manifest));
+        store
+            .manifests
+            .insert((repository.clone(), tag.clone()), manifest);
+        Ok(format!("{}/{repository}:{tag}", self.addr))
+    }
+}
+
+fn empty_response(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::default());
+    *res.status_mut() = status;
+    res
+}
+
+/// Serve pulls of manifests and blobs, as specified by the OCI distribution spec
+fn handle_request<B>(store: &Mutex<Store>, req: &Request<B>) -> Response<Full<Bytes>> {
+    let head = req.method() == Method::HEAD;
+    if !head && req.method() != Method::GET {
+        return empty_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let Some(path) = req.uri().path().strip_prefix("/v2/") else {
+        return empty_response(StatusCode::NOT_FOUND);
+    };
+    // API version check
+    if path.is_empty() {
+        return empty_response(StatusCode::OK);
+    }
+
+    let store = store.lock().unwrap_or_else(|err| err.into_inner());
+    let found = if let Some((repository, reference)) = path.rsplit_once("/manifests/") {
+        store
+            .manifests
+            .get(&(repository.to_string(), reference.to_string()))
+            .map(|manifest| {
+                (
+                    manifest.media_type.as_str(),
+                    manifest.digest.as_str(),
+                    manifest.data.clone(),
+                )
+            })
+    } else if let Some((_, digest)) = path.rsplit_once("/blobs/") {
+        store
+            .blobs
+            .get_key_value(digest)
+            .map(|(digest, data)| ("application/octet-stream", digest.as_str(), data.clone()))
+    } else {
+        None
+    };
+    let Some((media_type, digest, data)) = found else {
+        return empty_response(StatusCode::NOT_FOUND);
+    };
+    let len = data.len();
+    Response::builder()
+        .header(CONTENT_TYPE, media_type)
+        .header(CONTENT_LENGTH, len)
+        .header(DIGEST_HEADER, digest)
+        .body(if head {
+            Full::default()
+        } else {
+            Full::new(data)
+        })
+        .unwrap_or_else(|_| empty_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+#[cfg(test)]
+mod test {
+    use oci_distribution::client::{ClientConfig, ClientProtocol};
+    use oci_distribution::secrets::RegistryAuth;
+    use oci_distribution::{Client, Reference};
+    use oci_wasm::WASM_LAYER_MEDIA_TYPE;
+
+    use super::*;
+
+    /// Smallest valid component, i.e. the component preamble
+    const EMPTY_COMPONENT: &[u8] = b"\0asm\x0d\0\x01\0";
+
+    #[tokio::test]
+    async fn test_pull_pushed_component() -> Result<()> {
+        let registry = DevRegistry::start().await?;
+        let reference = registry.push_component("local/Hello", EMPTY_COMPONENT.to_vec())?;
+        assert!(reference.starts_with(&format!("{}/local/hello:", registry.addr())));
+
+        let image: Reference = reference.parse()?;
+        let client = Client::new(ClientConfig {
+            protocol: ClientProtocol::Http,
+            ..Default::default()
+        });
+        let pulled = client
+            .pull(
+                &image,
+                &RegistryAuth::Anonymous,
+                vec![WASM_LAYER_MEDIA_TYPE],
+            )
+            .await?;
+        assert_eq!(pulled.layers.len(), 1);
+        assert_eq!(pulled.layers[0].data, EMPTY_COMPONENT);
+
+        let missing: Reference = format!("{}/local/missing:0.1.0", registry.addr()).parse()?;
+        assert!(client
+            .pull(
+                &missing,
+                &RegistryAuth::Anonymous,
+                vec![WASM_LAYER_MEDIA_TYPE]
+            )
+            .await
+            .is_err());
+        Ok(())
+    }
+}
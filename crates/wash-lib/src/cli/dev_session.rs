@@ -0,0 +1,614 @@
+//! Sessions of `wash dev`, which other terminals follow with `wash dev --attach`
+//!
+//! A running `wash dev` serves its session on a local TCP port and writes a
+//! [`DevSessionDescriptor`] to [`DEV_SESSION_LOCK_PATH`] in the project. The lock keeps a second dev
+//! loop from being started for the same project. Locks left behind by crashed sessions are detected
+//! by the liveness of their process and replaced.
+//!
+//! Attached clients send the [`DevSessionChannel`] they follow as a line of JSON, then receive the
+//! [`DevSessionMessage`]s of that channel as NDJSON until the session ends. Attached clients are
+//! read-only, nothing else they send is read.
+
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Path of the descriptor of the running dev session, relative to the project
+pub const DEV_SESSION_LOCK_PATH: &str = ".wash/dev-session.lock";
+
+/// Number of messages an attached client may lag behind before it misses messages
+const DEV_SESSION_CHANNEL_CAPACITY: usize = 256;
+
+/// Descriptor of a running dev session, written to [`DEV_SESSION_LOCK_PATH`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevSessionDescriptor {
+    /// ID of the `wash dev` process
+    pub pid: u32,
+    /// Local address the session is served on
+    pub addr: SocketAddr,
+    /// Path of the project under development
+    pub project: PathBuf,
+    /// Time the session started, in milliseconds since the UNIX epoch
+    pub started_at_ms: u64,
+}
+
+/// Channel of a dev session followed by an attached client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DevSessionChannel {
+    /// Human-readable lines describing what the dev loop does
+    #[default]
+    Logs,
+    /// Structured [`DevSessionEvent`]s
+    Events,
+    /// [`DevSessionStatus`] snapshots, the current one first and then one after every event
+    Status,
+}
+
+/// Event of a dev session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DevSessionEvent {
+    /// A change was detected and the component is being rebuilt
+    BuildStarted { component: String },
+    /// The component was rebuilt and redeployed
+    BuildSucceeded {
+        component: String,
+        iteration: u64,
+        total_ms: u64,
+    },
+    /// The component failed to build, ending the session
+    BuildFailed { component: String, error: String },
+    /// The component failed at runtime
+    ComponentFailed { component: String, reason: String },
+    /// The component failed too often, its redeploys are paused until its next successful build
+    CrashLoop { component: String, failures: usize },
+    /// The component called an interface without a link
+    MissingLink {
+        component: String,
+        interface: String,
+        hint: String,
+    },
+    /// Config from env files was refreshed
+    ConfigRefreshed { values: usize },
+    /// The session is stopping
+    Stopping,
+}
+
+impl DevSessionEvent {
+    /// Human-readable description of the event, sent on the [`DevSessionChannel::Logs`] channel
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self {
+            Self::BuildStarted { component } => {
+                format!("change detected in [{component}], rebuilding...")
+            }
+            Self::BuildSucceeded {
+                component,
+                iteration,
+                total_ms,
+            } => format!(
+                "[{component}] rebuilt and redeployed in {total_ms}ms (iteration {iteration})"
+            ),
+            Self::BuildFailed { component, error } => {
+                format!("[{component}] failed to build: {error}")
+            }
+            Self::ComponentFailed { component, reason } => {
+                format!("[{component}] failed: {reason}")
+            }
+            Self::CrashLoop {
+                component,
+                failures,
+            } => format!("[{component}] failed {failures} times, redeploys are paused"),
+            Self::MissingLink {
+                component,
+                interface,
+                hint,
+            } => format!("[{component}] called {interface} but no provider is linked — {hint}"),
+            Self::ConfigRefreshed { values } => {
+                format!("refreshed config with {values} value(s) from env file(s)")
+            }
+            Self::Stopping => "dev session stopping".to_string(),
+        }
+    }
+
+    /// State of the session after the event
+    fn state(&self) -> Option<DevSessionState> {
+        match self {
+            Self::BuildStarted { .. } => Some(DevSessionState::Building),
+            Self::BuildSucceeded { .. } => Some(DevSessionState::Watching),
+            Self::CrashLoop { .. } => Some(DevSessionState::CrashLooping),
+            Self::BuildFailed { .. } | Self::Stopping => Some(DevSessionState::Stopping),
+            Self::ComponentFailed { .. }
+            | Self::MissingLink { .. }
+            | Self::ConfigRefreshed { .. } => None,
+        }
+    }
+}
+
+/// State of the dev loop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DevSessionState {
+    /// Building and deploying the project for the first time
+    #[default]
+    Starting,
+    Building,
+    /// Waiting for changes
+    Watching,
+    /// A component is crash-looping, its redeploys are paused
+    CrashLooping,
+    Stopping,
+}
+
+/// Status of a dev session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevSessionStatus {
+    pub pid: u32,
+    pub project: PathBuf,
+    pub state: DevSessionState,
+    /// Number of iterations completed since the session started
+    pub iteration: u64,
+    /// Most recent event of the session
+    pub last_event: Option<DevSessionEvent>,
+}
+
+/// Message sent to clients attached to a dev session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "channel", rename_all = "lowercase")]
+pub enum DevSessionMessage {
+    Logs { line: String },
+    Events { event: DevSessionEvent },
+    Status { status: DevSessionStatus },
+}
+
+impl DevSessionMessage {
+    #[must_use]
+    pub fn channel(&self) -> DevSessionChannel {
+        match self {
+            Self::Logs { .. } => DevSessionChannel::Logs,
+            Self::Events { .. } => DevSessionChannel::Events,
+            Self::Status { .. } => DevSessionChannel::Status,
+        }
+    }
+}
+
+/// Path of the dev session lock of the project at `project`
+#[must_use]
+pub fn dev_session_lock_path(project: &Path) -> PathBuf {
+    project.join(DEV_SESSION_LOCK_PATH)
+}
+
+/// Reads the descriptor of the dev session of the project at `project`, `None` if no session is
+/// running. Locks of sessions whose process is no longer alive according to `is_alive` are removed
+pub async fn read_dev_session(
+    project: &Path,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<Option<DevSessionDescriptor>> {
+    let path = dev_session_lock_path(project);
+    let contents = match tokio::fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read [{}]", path.display()));
+        }
+    };
+    match serde_json::from_slice::<DevSessionDescriptor>(&contents) {
+        Ok(descriptor) if is_alive(descriptor.pid) => Ok(Some(descriptor)),
+        res => {
+            warn!(path = %path.display(), pid = ?res.ok().map(|d| d.pid), "removing stale dev session lock");
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => Ok(None),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).with_context(|| format!("failed to remove [{}]", path.display())),
+            }
+        }
+    }
+}
+
+/// A running dev session, which holds the lock of the project until dropped
+pub struct DevSession {
+    lock_path: PathBuf,
+    descriptor: DevSessionDescriptor,
+    tx: broadcast::Sender<DevSessionMessage>,
+    status: Arc<Mutex<DevSessionStatus>>,
+    server: JoinHandle<()>,
+}
+
+impl DevSession {
+    /// Start serving the dev session of the project at `project`, failing if another session whose
+    /// process is alive according to `is_alive` is running for the project
+    pub async fn start(project: &Path, is_alive: impl Fn(u32) -> bool) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .context("failed to listen for attached dev sessions")?;
+        let descriptor = DevSessionDescriptor {
+            pid: std::process::id(),
+            addr: listener
+                .local_addr()
+                .context("failed to get dev session address")?,
+            project: project.to_path_buf(),
+            started_at_ms: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |t| t.as_millis() as u64),
+        };
+        acquire_lock(project, &descriptor, is_alive).await?;
+
+        let (tx, _) = broadcast::channel(DEV_SESSION_CHANNEL_CAPACITY);
+        let status = Arc::new(Mutex::new(DevSessionStatus {
+            pid: descriptor.pid,
+            project: descriptor.project.clone(),
+            ..Default::default()
+        }));
+        let server = tokio::spawn(serve(listener, tx.clone(), status.clone()));
+        Ok(Self {
+            lock_path: dev_session_lock_path(project),
+            descriptor,
+            tx,
+            status,
+            server,
+        })
+    }
+
+    #[must_use]
+    pub fn descriptor(&self) -> &DevSessionDescriptor {
+        &self.descriptor
+    }
+
+    #[must_use]
+    pub fn status(&self) -> DevSessionStatus {
+        self.lock_status().clone()
+    }
+
+    /// Send a line on the [`DevSessionChannel::Logs`] channel
+    pub fn log(&self, line: impl Into<String>) {
+        let _ = self.tx.send(DevSessionMessage::Logs { line: line.into() });
+    }
+
+    /// Send `event` on the [`DevSessionChannel::Events`] channel, its message on the
+    /// [`DevSessionChannel::Logs`] channel and the status it results in on the
+    /// [`DevSessionChannel::Status`] channel
+    pub fn event(&self, event: DevSessionEvent) {
+        let status = {
+            let mut status = self.lock_status();
+            if let Some(state) = event.state() {
+                status.state = state;
+            }
+            if let DevSessionEvent::BuildSucceeded { iteration, .. } = event {
+                status.iteration = iteration;
+            }
+            status.last_event = Some(event.clone());
+            status.clone()
+        };
+        self.log(event.message());
+        let _ = self.tx.send(DevSessionMessage::Events { event });
+        let _ = self.tx.send(DevSessionMessage::Status { status });
+    }
+
+    /// Set the state of the session, e.g. once the project was first deployed
+    pub fn set_state(&self, state: DevSessionState) {
+        let status = {
+            let mut status = self.lock_status();
+            status.state = state;
+            status.clone()
+        };
+        let _ = self.tx.send(DevSessionMessage::Status { status });
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, DevSessionStatus> {
+        self.status
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Drop for DevSession {
+    fn drop(&mut self) {
+        self.server.abort();
+        // The lock is only removed if it was not replaced, e.g. by a session which considered this
+        // one stale
+        let ours = std::fs::read(&self.lock_path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<DevSessionDescriptor>(&contents).ok())
+            .is_some_and(|descriptor| descriptor == self.descriptor);
+        if ours {
+            if let Err(e) = std::fs::remove_file(&self.lock_path) {
+                warn!(?e, path = %self.lock_path.display(), "failed to remove dev session lock");
+            }
+        }
+    }
+}
+
+/// Write `descriptor` to the lock of the project at `project`, replacing it if it belongs to a
+/// session that is no longer alive
+async fn acquire_lock(
+    project: &Path,
+    descriptor: &DevSessionDescriptor,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<()> {
+    let path = &dev_session_lock_path(project);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create directory [{}]", parent.display()))?;
+    }
+    let contents = serde_json::to_vec(descriptor).context("failed to serialize dev session")?;
+    // The descriptor is written to a file of its own which is then linked into place, so that the
+    // lock is never seen without its complete descriptor and mistaken for a stale one
+    let tmp = path.with_extension(format!(
+        "lock.{}.{}.tmp",
+        descriptor.pid,
+        descriptor.addr.port()
+    ));
+    tokio::fs::write(&tmp, &contents)
+        .await
+        .with_context(|| format!("failed to write [{}]", tmp.display()))?;
+    let res = link_lock(project, &tmp, is_alive).await;
+    if let Err(e) = tokio::fs::remove_file(&tmp).await {
+        warn!(?e, path = %tmp.display(), "failed to remove temporary dev session lock");
+    }
+    res
+}
+
+/// Link the complete descriptor at `tmp` to the lock of the project at `project`, which fails if
+/// the lock exists
+async fn link_lock(project: &Path, tmp: &Path, is_alive: impl Fn(u32) -> bool) -> Result<()> {
+    let path = &dev_session_lock_path(project);
+    // The lock is replaced at most once, so that two sessions starting at once do not both win
+    for _ in 0..2 {
+        match tokio::fs::hard_link(tmp, path).await {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if let Some(existing) = read_dev_session(project, &is_alive).await? {
+                    bail!(
+                        "a dev session (pid {}) is already running for [{}], run `wash dev --attach` to follow it instead",
+                        existing.pid,
+                        existing.project.display()
+                    );
+                }
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to create [{}]", path.display()));
+            }
+        }
+    }
+    bail!("failed to acquire dev session lock [{}]", path.display())
+}
+
+/// Accept attached clients until the session is dropped
+async fn serve(
+    listener: TcpListener,
+    tx: broadcast::Sender<DevSessionMessage>,
+    status: Arc<Mutex<DevSessionStatus>>,
+) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                debug!(?e, "failed to accept attached dev session");
+                continue;
+            }
+        };
+        // Subscribe before the channel is read, so that no message is missed
+        let rx = tx.subscribe();
+        let status = status
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_attached(stream, rx, status).await {
+                debug!(?e, "attached dev session ended");
+            }
+        });
+    }
+}
+
+/// Stream the messages of the channel requested by an attached client
+async fn serve_attached(
+    stream: TcpStream,
+    mut rx: broadcast::Receiver<DevSessionMessage>,
+    status: DevSessionStatus,
+) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let channel: DevSessionChannel = match lines.next_line().await? {
+        Some(line) => serde_json::from_str(&line).context("invalid dev session channel")?,
+        None => return Ok(()),
+    };
+    if channel == DevSessionChannel::Status {
+        write
+            .write_all(&encode(&DevSessionMessage::Status { status }))
+            .await?;
+    }
+    loop {
+        match rx.recv().await {
+            Ok(message) if message.channel() == channel => {
+                write.write_all(&encode(&message)).await?;
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                debug!(missed, "attached dev session lagged behind");
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// `message` as a line of JSON
+fn encode(message: &DevSessionMessage) -> Vec<u8> {
+    let mut line = serde_json::to_vec(message).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+/// Client attached to a dev session
+pub struct AttachedDevSession {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    // Kept open until the client is dropped, as the session ends attached clients which close it
+    _write: OwnedWriteHalf,
+}
+
+impl AttachedDevSession {
+    /// Attach to the session described by `descriptor`, following `channel`
+    pub async fn connect(
+        descriptor: &DevSessionDescriptor,
+        channel: DevSessionChannel,
+    ) -> Result<Self> {
+        let stream = TcpStream::connect(descriptor.addr)
+            .await
+            .with_context(|| format!("failed to attach to dev session at [{}]", descriptor.addr))?;
+        let (read, mut write) = stream.into_split();
+        let mut request = serde_json::to_vec(&channel).context("failed to serialize channel")?;
+        request.push(b'\n');
+        write
+            .write_all(&request)
+            .await
+            .context("failed to request dev session channel")?;
+        Ok(Self {
+            lines: BufReader::new(read).lines(),
+            _write: write,
+        })
+    }
+
+    /// Next message of the followed channel, `None` once the session ended
+    pub async fn next(&mut self) -> Result<Option<DevSessionMessage>> {
+        match self.lines.next_line().await {
+            Ok(Some(line)) => serde_json::from_str(&line)
+                .map(Some)
+                .context("invalid dev session message"),
+            Ok(None) => Ok(None),
+            // The session ending abruptly ends the attached session as well
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => Ok(None),
+            Err(e) => Err(e).context("failed to read from dev session"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_attach_and_stale_lock() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        let session = DevSession::start(project.path(), |_| true).await?;
+        let descriptor = read_dev_session(project.path(), |_| true)
+            .await?
+            .context("session should be running")?;
+        assert_eq!(&descriptor, session.descriptor());
+
+        // A second session is refused while the first one is alive
+        let err = DevSession::start(project.path(), |_| true)
+            .await
+            .err()
+            .context("second session should be refused")?;
+        assert!(err.to_string().contains("--attach"), "{err}");
+
+        let mut events =
+            AttachedDevSession::connect(&descriptor, DevSessionChannel::Events).await?;
+        let mut status =
+            AttachedDevSession::connect(&descriptor, DevSessionChannel::Status).await?;
+        // The current status is sent first, which also shows that the status client is attached
+        assert!(matches!(
+            status.next().await?,
+            Some(DevSessionMessage::Status { status }) if status.state == DevSessionState::Starting
+        ));
+        let mut logs = AttachedDevSession::connect(&descriptor, DevSessionChannel::Logs).await?;
+        // Wait for the clients to be subscribed
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        session.event(DevSessionEvent::BuildStarted {
+            component: "hello".to_string(),
+        });
+        session.event(DevSessionEvent::BuildSucceeded {
+            component: "hello".to_string(),
+            iteration: 1,
+            total_ms: 42,
+        });
+        assert_eq!(
+            events.next().await?,
+            Some(DevSessionMessage::Events {
+                event: DevSessionEvent::BuildStarted {
+                    component: "hello".to_string()
+                }
+            })
+        );
+        assert!(matches!(
+            events.next().await?,
+            Some(DevSessionMessage::Events {
+                event: DevSessionEvent::BuildSucceeded { iteration: 1, .. }
+            })
+        ));
+        assert_eq!(
+            logs.next().await?,
+            Some(DevSessionMessage::Logs {
+                line: "change detected in [hello], rebuilding...".to_string()
+            })
+        );
+        let _building = status.next().await?;
+        match status.next().await? {
+            Some(DevSessionMessage::Status { status }) => {
+                assert_eq!(status.state, DevSessionState::Watching);
+                assert_eq!(status.iteration, 1);
+            }
+            other => bail!("unexpected message {other:?}"),
+        }
+
+        // Attached clients end with the session, which removes its lock
+        drop(session);
+        assert_eq!(events.next().await?, None);
+        assert!(!dev_session_lock_path(project.path()).exists());
+
+        // Locks of sessions that are no longer alive are replaced
+        let stale = DevSession::start(project.path(), |_| true).await?;
+        let stale_descriptor = stale.descriptor().clone();
+        // The process of the stale session crashed without removing its lock
+        std::mem::forget(stale);
+        let session = DevSession::start(project.path(), |_| false).await?;
+        assert_ne!(session.descriptor(), &stale_descriptor);
+        assert_eq!(
+            read_dev_session(project.path(), |_| true).await?.as_ref(),
+            Some(session.descriptor())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_sessions() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        for _ in 0..20 {
+            let (first, second) = tokio::join!(
+                DevSession::start(project.path(), |_| true),
+                DevSession::start(project.path(), |_| true),
+            );
+            // Exactly one session wins, the other never sees a partially written lock
+            let session = match (first, second) {
+                (Ok(session), Err(err)) | (Err(err), Ok(session)) => {
+                    assert!(err.to_string().contains("--attach"), "{err}");
+                    session
+                }
+                (Ok(_), Ok(_)) => bail!("both sessions acquired the lock"),
+                (Err(first), Err(second)) => {
+                    bail!("no session acquired the lock: {first}, {second}")
+                }
+            };
+            drop(session);
+        }
+        // Only the lock directory remains, without temporary descriptors
+        let dir = dev_session_lock_path(project.path());
+        let dir = dir.parent().context("lock should have a parent")?;
+        assert_eq!(std::fs::read_dir(dir)?.count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! Receiving, filtering and rendering the CloudEvents hosts publish on the lattice event subject,
+//! used by `wash get events`
+//!
+//! Events are received live from `wasmbus.evt.{lattice}.>`, from a durable consumer of the
+//! [`EVENTS_STREAM`] JetStream stream (so that a named consumer resumes where it left off), or
+//! replayed from a file of NDJSON envelopes previously saved with `--save`.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+use async_nats::jetstream::consumer::pull::Config as ConsumerConfig;
+use async_nats::jetstream::consumer::{AckPolicy, DeliverPolicy};
+use cloudevents::event::{AttributesReader, Event};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt as _, TryStreamExt as _};
+use serde_json::Value;
+
+use super::get::GetEventsCommand;
+
+/// Prefix of the type of the CloudEvents published by hosts
+pub const LATTICE_EVENT_TYPE_PREFIX: &str = "com.wasmcloud.lattice.";
+
+/// JetStream stream capturing lattice events, created by wadm
+pub const EVENTS_STREAM: &str = "wadm_events";
+
+/// Subject hosts publish the events of `lattice` on
+#[must_use]
+pub fn lattice_event_subject(lattice: &str) -> String {
+    format!("wasmbus.evt.{lattice}.>")
+}
+
+/// Name of an event type without the [`LATTICE_EVENT_TYPE_PREFIX`], e.g. `component_scaled`
+#[must_use]
+pub fn event_name(ty: &str) -> &str {
+    ty.strip_prefix(LATTICE_EVENT_TYPE_PREFIX).unwrap_or(ty)
+}
+
+/// Filter of the events shown by `wash get events`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// Event types to show, with or without the [`LATTICE_EVENT_TYPE_PREFIX`]. All types are
+    /// shown if empty
+    pub types: Vec<String>,
+    /// Source (i.e. host ID) of the events to show
+    pub source: Option<String>,
+}
+
+impl From<&GetEventsCommand> for EventFilter {
+    fn from(cmd: &GetEventsCommand) -> Self {
+        Self {
+            types: cmd.types.clone(),
+            source: cmd.source.clone(),
+        }
+    }
+}
+
+impl EventFilter {
+    /// Whether `event` passes the filter
+    #[must_use]
+    pub fn matches(&self, event: &Event) -> bool {
+        let ty = event.ty();
+        let type_matches = self.types.is_empty()
+            || self
+                .types
+                .iter()
+                .any(|t| t == ty || event_name(t) == event_name(ty));
+        let source_matches = self
+            .source
+            .as_ref()
+            .map_or(true, |source| event.source() == source.as_str());
+        type_matches && source_matches
+    }
+}
+
+/// JSON data of `event`, `null` if it has none or it is not JSON
+#[must_use]
+pub fn event_data(event: &Event) -> Value {
+    event
+        .data()
+        .cloned()
+        .and_then(|data| Value::try_from(data).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Fields of the data of known event types shown on their line, in order
+fn known_fields(name: &str) -> Option<&'static [&'static str]> {
+    Some(match name {
+        "component_scaled" => &["component_id", "image_ref", "max_instances", "host_id"],
+        "component_scale_failed" => &[
+            "component_id",
+            "image_ref",
+            "max_instances",
+            "host_id",
+            "error",
+        ],
+        "component_invocation_failed" => &["component_id", "image_ref", "error"],
+        "provider_started" => &["provider_id", "image_ref", "host_id"],
+        "provider_start_failed" => &["provider_id", "provider_ref", "error"],
+        "provider_stopped" => &["provider_id", "reason", "host_id"],
+        "health_check_passed" | "health_check_failed" | "health_check_status" => {
+            &["provider_id", "host_id"]
+        }
+        "linkdef_set" => &[
+            "source_id",
+            "target",
+            "name",
+            "wit_namespace",
+            "wit_package",
+            "interfaces",
+        ],
+        "linkdef_deleted" => &["source_id", "name", "wit_namespace", "wit_package"],
+        "config_set" | "config_deleted" => &["config_name"],
+        "labels_changed" | "host_started" | "host_stopped" => &["labels"],
+        "host_heartbeat" => &["friendly_name", "components", "providers", "uptime_human"],
+        _ => return None,
+    })
+}
+
+/// Render a value of an event field on a single line. Lists of strings are joined with commas,
+/// other lists (e.g. the components of a heartbeat) are rendered as their count
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_string) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(","),
+        Value::Array(items) => items.len().to_string(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| {
+                format!(
+                    "{k}={}",
+                    v.as_str().map_or_else(|| v.to_string(), str::to_string)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
+
+/// Render a concise line describing `event`: its time, type and the key fields of its data.
+/// Events of unknown types are rendered with all of their top-level fields
+#[must_use]
+pub fn render_event(event: &Event) -> String {
+    let name = event_name(event.ty());
+    let time = event
+        .time()
+        .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_else(|| "-".to_string());
+    let mut line = format!("{time} {name}");
+    let data = event_data(event);
+    let fields: Vec<(&str, &Value)> = match (known_fields(name), data.as_object()) {
+        (Some(known), Some(map)) => known
+            .iter()
+            .filter_map(|&key| map.get(key).map(|value| (key, value)))
+            .collect(),
+        (None, Some(map)) => map.iter().map(|(k, v)| (k.as_str(), v)).collect(),
+        (_, None) => Vec::new(),
+    };
+    for (key, value) in fields {
+        if value.is_null() {
+            continue;
+        }
+        let _ = write!(line, " {key}={}", render_value(value));
+    }
+    // Host events are identified by their source
+    if !line.contains(" host_id=") {
+        let _ = write!(line, " source={}", event.source());
+    }
+    line
+}
+
+/// Parse a CloudEvent envelope
+pub fn parse_event(payload: &[u8]) -> Result<Event> {
+    serde_json::from_slice(payload).context("failed to parse lattice event")
+}
+
+/// Stream of the events of `lattice` to show for `cmd`, live, from a durable consumer or replayed
+/// from a file
+pub async fn event_stream(
+    cmd: &GetEventsCommand,
+    nats: Option<async_nats::Client>,
+    lattice: &str,
+    js_domain: Option<String>,
+) -> Result<BoxStream<'static, Result<Event>>> {
+    if let Some(path) = &cmd.replay {
+        return replay_events(path).await;
+    }
+    let nats = nats.context("a NATS connection is required to receive events")?;
+    let subject = lattice_event_subject(lattice);
+    let Some(durable) = &cmd.since_durable else {
+        let sub = nats
+            .subscribe(subject.clone())
+            .await
+            .with_context(|| format!("failed to subscribe to `{subject}`"))?;
+        return Ok(sub.map(|msg| parse_event(&msg.payload)).boxed());
+    };
+    let js = if let Some(domain) = js_domain {
+        async_nats::jetstream::with_domain(nats, domain)
+    } else {
+        async_nats::jetstream::new(nats)
+    };
+    let stream = js.get_stream(EVENTS_STREAM).await.map_err(|e| {
+        anyhow::anyhow!("unable to find the `{EVENTS_STREAM}` stream, is wadm running? Error: {e}")
+    })?;
+    let consumer = stream
+        .get_or_create_consumer(
+            durable,
+            ConsumerConfig {
+                durable_name: Some(durable.clone()),
+                description: Some("wash get events consumer".to_string()),
+                filter_subject: subject,
+                deliver_policy: DeliverPolicy::All,
+                ack_policy: AckPolicy::Explicit,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to create durable consumer `{durable}`: {e}"))?;
+    let messages = consumer
+        .messages()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to receive events: {e}"))?;
+    Ok(messages
+        .map_err(|e| anyhow::anyhow!("failed to receive event: {e}"))
+        .and_then(|msg| async move {
+            // Acknowledge events once received, so that the consumer resumes after them
+            msg.ack()
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to acknowledge event: {e}"))?;
+            parse_event(&msg.payload)
+        })
+        .boxed())
+}
+
+/// Read the events saved as NDJSON in the file at `path`
+async fn replay_events(path: &Path) -> Result<BoxStream<'static, Result<Event>>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read events from [{}]", path.display()))?;
+    let events: Vec<_> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_event(line.as_bytes()))
+        .collect();
+    Ok(stream::iter(events).boxed())
+}
+
+#[cfg(test)]
+mod test {
+    use cloudevents::{EventBuilder as _, EventBuilderV10};
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(name: &str, source: &str, data: Value) -> Event {
+        EventBuilderV10::new()
+            .id("01HZ0000000000000000000000")
+            .source(source)
+            .ty(format!("{LATTICE_EVENT_TYPE_PREFIX}{name}"))
+            .time(
+                chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+                    .expect("valid time")
+                    .with_timezone(&chrono::Utc),
+            )
+            .data("application/json", data)
+            .build()
+            .expect("valid event")
+    }
+
+    #[test]
+    fn test_render_known_event() {
+        let scaled = event(
+            "component_scaled",
+            "NHOST",
+            json!({
+                "component_id": "hello",
+                "image_ref": "ghcr.io/wasmcloud/components/hello:0.1.0",
+                "max_instances": 2,
+                "host_id": "NHOST",
+                "annotations": {},
+            }),
+        );
+        assert_eq!(
+            render_event(&scaled),
+            "2024-05-01T12:00:00.000Z component_scaled component_id=hello image_ref=ghcr.io/wasmcloud/components/hello:0.1.0 max_instances=2 host_id=NHOST"
+        );
+
+        let heartbeat = event(
+            "host_heartbeat",
+            "NHOST",
+            json!({
+                "friendly_name": "quiet-sun",
+                "components": [{"id": "a"}, {"id": "b"}],
+                "providers": [],
+                "uptime_human": "1m",
+            }),
+        );
+        assert_eq!(
+            render_event(&heartbeat),
+            "2024-05-01T12:00:00.000Z host_heartbeat friendly_name=quiet-sun components=2 providers=0 uptime_human=1m source=NHOST"
+        );
+    }
+
+    #[test]
+    fn test_render_unknown_event() {
+        let custom = event("custom_thing", "someone", json!({ "count": 3 }));
+        assert_eq!(
+            render_event(&custom),
+            "2024-05-01T12:00:00.000Z custom_thing count=3 source=someone"
+        );
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let scaled = event("component_scaled", "NHOST", json!({}));
+        assert!(EventFilter::default().matches(&scaled));
+        let filter = EventFilter {
+            types: vec![
+                "provider_started".to_string(),
+                "com.wasmcloud.lattice.component_scaled".to_string(),
+            ],
+            source: None,
+        };
+        assert!(filter.matches(&scaled));
+        let filter = EventFilter {
+            types: vec!["component_scaled".to_string()],
+            source: Some("NOTHER".to_string()),
+        };
+        assert!(!filter.matches(&scaled));
+    }
+
+    #[test]
+    fn test_parse_saved_event() {
+        let scaled = event(
+            "component_scaled",
+            "NHOST",
+            json!({ "component_id": "hello" }),
+        );
+        let line = serde_json::to_string(&scaled).expect("serializable event");
+        assert_eq!(parse_event(line.as_bytes()).expect("valid event"), scaled);
+    }
+}
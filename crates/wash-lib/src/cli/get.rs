@@ -1,6 +1,12 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
 use clap::Parser;
-use wasmcloud_control_interface::{Host, HostInventory};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use wasmcloud_control_interface::{Client as CtlClient, Host, HostInventory};
 
 use crate::{
     common::{boxed_err_to_anyhow, get_all_inventories},
@@ -8,7 +14,16 @@ use crate::{
     id::ServerId,
 };
 
-use super::CliConnectionOpts;
+use super::{table::TableOpts, CliConnectionOpts};
+
+/// Default interval between inventory queries when watching, in milliseconds
+pub const DEFAULT_INVENTORY_WATCH_INTERVAL_MS: u64 = 2000;
+
+/// Annotation set by wadm on the components and providers it manages
+pub const MANAGED_BY_ANNOTATION: &str = "wasmcloud.dev/managed-by";
+
+/// Annotation set by wadm to the name of the application of a component or provider
+pub const APP_SPEC_ANNOTATION: &str = "wasmcloud.dev/appspec";
 
 #[derive(Debug, Clone, Parser)]
 pub struct GetClaimsCommand {
@@ -24,23 +39,217 @@ pub struct GetHostInventoriesCommand {
     /// Host ID to retrieve inventory for. If not provided, wash will query the inventories of all running hosts.
     #[clap(name = "host-id", value_parser)]
     pub host_id: Option<ServerId>,
+
+    /// Continuously re-query the inventory until interrupted, highlighting components and providers that
+    /// appeared or disappeared since the previous query. With `--output json`, one JSON document is printed per query
+    #[clap(long = "watch")]
+    pub watch: bool,
+
+    /// Interval between inventory queries when watching, in milliseconds
+    #[clap(
+        long = "interval-ms",
+        default_value_t = DEFAULT_INVENTORY_WATCH_INTERVAL_MS,
+        requires = "watch"
+    )]
+    pub interval_ms: u64,
+
+    #[clap(flatten)]
+    pub table: TableOpts,
 }
 
 #[derive(Debug, Clone, Parser)]
 pub struct GetLinksCommand {
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
+
+    #[clap(flatten)]
+    pub table: TableOpts,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct GetEventsCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// Only show events of the given types, separated by commas, e.g. `component_scaled,provider_started`
+    #[clap(long = "type", value_delimiter = ',')]
+    pub types: Vec<String>,
+
+    /// Only show events published by the given source, i.e. host ID
+    #[clap(long = "source")]
+    pub source: Option<String>,
+
+    /// Receive events through the durable JetStream consumer with the given name, created on first use,
+    /// showing the events published since the consumer last received events. Requires wadm's event stream
+    #[clap(long = "since-durable", conflicts_with = "replay")]
+    pub since_durable: Option<String>,
+
+    /// Keep showing events until interrupted. Otherwise, wash exits once no event was received for a second
+    #[clap(short = 'f', long = "follow", conflicts_with = "replay")]
+    pub follow: bool,
+
+    /// Append the received events to the given file as NDJSON, to replay them later with `--replay`
+    #[clap(long = "save")]
+    pub save: Option<PathBuf>,
+
+    /// Show the events saved in the given file with `--save` instead of receiving them from the lattice
+    #[clap(long = "replay")]
+    pub replay: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Parser)]
 pub struct GetHostsCommand {
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
+
+    /// Query the inventory of every host to include the number of running components and providers
+    /// and the total max instances of its components
+    #[clap(long = "detailed")]
+    pub detailed: bool,
+
+    /// Sort hosts by name (ascending), uptime or number of components (both descending). Sorting by
+    /// components queries the inventory of every host, as with `--detailed`
+    #[clap(long = "sort", value_enum)]
+    pub sort: Option<HostSortKey>,
+
+    #[clap(flatten)]
+    pub table: TableOpts,
+}
+
+/// Key by which the output of `wash get hosts` is sorted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HostSortKey {
+    Name,
+    Uptime,
+    Components,
+}
+
+/// A host along with details gathered from its labels and, if queried, its inventory.
+///
+/// Details which are not available (e.g. because the inventory was not queried) are serialized as
+/// `null`, so the shape of the output does not depend on the host version or command flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostDetails {
+    #[serde(flatten)]
+    pub host: Host,
+    /// Operating system of the host, from the `hostcore.os` label
+    pub os: Option<String>,
+    /// CPU architecture of the host, from the `hostcore.arch` label
+    pub arch: Option<String>,
+    /// Number of components running on the host
+    pub component_count: Option<usize>,
+    /// Number of providers running on the host
+    pub provider_count: Option<usize>,
+    /// Sum of the max instances of all components running on the host
+    pub max_instances: Option<u64>,
+}
+
+impl HostDetails {
+    /// Gather the details of `host`, including counts from its `inventory` if available
+    #[must_use]
+    pub fn new(host: Host, inventory: Option<&HostInventory>) -> Self {
+        Self {
+            os: host.labels.get("hostcore.os").cloned(),
+            arch: host.labels.get("hostcore.arch").cloned(),
+            component_count: inventory.map(|inv| inv.components.len()),
+            provider_count: inventory.map(|inv| inv.providers.len()),
+            max_instances: inventory.map(|inv| {
+                inv.components
+                    .iter()
+                    .map(|c| u64::from(c.max_instances))
+                    .sum()
+            }),
+            host,
+        }
+    }
+}
+
+/// A component or provider running on a host, as listed by `wash get inventory`.
+///
+/// The application and manager are taken from the annotations set by wadm, and serialized as
+/// `null` for components and providers started imperatively.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    /// ID of the host running the component or provider
+    pub host_id: String,
+    /// Either `component` or `provider`
+    pub kind: String,
+    pub id: String,
+    pub name: Option<String>,
+    pub image_ref: Option<String>,
+    /// Max instances of a component, `null` for providers
+    pub max_instances: Option<u32>,
+    pub revision: i32,
+    /// Name of the application the component or provider belongs to, from the
+    /// `wasmcloud.dev/appspec` annotation
+    pub app: Option<String>,
+    /// Manager of the component or provider, e.g. `wadm`, from the `wasmcloud.dev/managed-by`
+    /// annotation
+    pub managed_by: Option<String>,
+}
+
+impl InventoryEntry {
+    /// Entries of all components and providers in `invs`, in inventory order
+    #[must_use]
+    pub fn from_inventories(invs: &[HostInventory]) -> Vec<Self> {
+        let annotation = |annotations: Option<&HashMap<String, String>>, key: &str| {
+            annotations
+                .and_then(|annotations| annotations.get(key))
+                .cloned()
+        };
+        invs.iter()
+            .flat_map(|inv| {
+                let components = inv.components.iter().map(|c| Self {
+                    host_id: inv.host_id.clone(),
+                    kind: "component".to_string(),
+                    id: c.id.clone(),
+                    name: c.name.clone(),
+                    image_ref: Some(c.image_ref.clone()),
+                    max_instances: Some(c.max_instances),
+                    revision: c.revision,
+                    app: annotation(c.annotations.as_ref(), APP_SPEC_ANNOTATION),
+                    managed_by: annotation(c.annotations.as_ref(), MANAGED_BY_ANNOTATION),
+                });
+                let providers = inv.providers.iter().map(|p| Self {
+                    host_id: inv.host_id.clone(),
+                    kind: "provider".to_string(),
+                    id: p.id.clone(),
+                    name: p.name.clone(),
+                    image_ref: p.image_ref.clone(),
+                    max_instances: None,
+                    revision: p.revision,
+                    app: annotation(p.annotations.as_ref(), APP_SPEC_ANNOTATION),
+                    managed_by: annotation(p.annotations.as_ref(), MANAGED_BY_ANNOTATION),
+                });
+                components.chain(providers).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Sort `hosts` by `key`, breaking ties by host ID
+pub fn sort_hosts(hosts: &mut [HostDetails], key: HostSortKey) {
+    match key {
+        HostSortKey::Name => hosts.sort_by(|a, b| {
+            (&a.host.friendly_name, &a.host.id).cmp(&(&b.host.friendly_name, &b.host.id))
+        }),
+        HostSortKey::Uptime => {
+            hosts.sort_by(|a, b| {
+                (Reverse(a.host.uptime_seconds), &a.host.id)
+                    .cmp(&(Reverse(b.host.uptime_seconds), &b.host.id))
+            });
+        }
+        HostSortKey::Components => hosts.sort_by(|a, b| {
+            (Reverse(a.component_count), &a.host.id).cmp(&(Reverse(b.component_count), &b.host.id))
+        }),
+    }
 }
 
 #[derive(Debug, Clone, Parser)]
 pub enum GetCommand {
     /// Retrieve all known links in the lattice
+    ///
+    /// Columns: source, target, name, wit, interfaces, source-config, target-config
     #[clap(name = "links")]
     Links(GetLinksCommand),
 
@@ -49,22 +258,38 @@ pub enum GetCommand {
     Claims(GetClaimsCommand),
 
     /// Retrieve all responsive hosts in the lattice
+    ///
+    /// Columns: id, name, uptime, uptime-seconds, version, platform, labels, components, providers,
+    /// max-instances. The last three query the inventory of every host, as with `--detailed`
     #[clap(name = "hosts")]
     Hosts(GetHostsCommand),
 
     /// Retrieve inventory a given host on in the lattice
+    ///
+    /// Columns: host, kind, id, name, image, max-instances, revision, app, managed-by
     #[clap(name = "inventory", alias = "inventories")]
     HostInventories(GetHostInventoriesCommand),
+
+    /// Show the events published on the lattice, e.g. components scaling and providers starting
+    #[clap(name = "events")]
+    Events(GetEventsCommand),
 }
 
 /// Retrieve host inventory
 pub async fn get_host_inventories(cmd: GetHostInventoriesCommand) -> Result<Vec<HostInventory>> {
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let client = wco.into_ctl_client(None).await?;
+    query_host_inventories(&client, cmd.host_id.as_ref()).await
+}
 
-    if let Some(host_id) = cmd.host_id {
+/// Retrieve the inventory of `host_id`, or of all running hosts if not provided, using an existing client
+pub async fn query_host_inventories(
+    client: &CtlClient,
+    host_id: Option<&ServerId>,
+) -> Result<Vec<HostInventory>> {
+    if let Some(host_id) = host_id {
         if let Some(inventory) = client
-            .get_host_inventory(&host_id)
+            .get_host_inventory(host_id)
             .await
             .map(|inventory| inventory.response)
             .map_err(boxed_err_to_anyhow)?
@@ -74,7 +299,7 @@ pub async fn get_host_inventories(cmd: GetHostInventoriesCommand) -> Result<Vec<
             Ok(vec![])
         }
     } else {
-        let hosts = get_all_inventories(&client)
+        let hosts = get_all_inventories(client)
             .await
             .context("unable to fetch all inventory")?;
         match hosts.len() {
@@ -86,11 +311,12 @@ pub async fn get_host_inventories(cmd: GetHostInventoriesCommand) -> Result<Vec<
     }
 }
 
-/// Retrieve hosts
-pub async fn get_hosts(cmd: GetHostsCommand) -> Result<Vec<Host>> {
+/// Retrieve hosts, querying their inventories if `--detailed` or `--sort components` was passed
+pub async fn get_hosts(cmd: GetHostsCommand) -> Result<Vec<HostDetails>> {
+    let with_inventory = cmd.detailed || cmd.sort == Some(HostSortKey::Components);
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let client = wco.into_ctl_client(None).await?;
-    client
+    let hosts = client
         .get_hosts()
         .await
         .map_err(boxed_err_to_anyhow)
@@ -100,5 +326,149 @@ pub async fn get_hosts(cmd: GetHostsCommand) -> Result<Vec<Host>> {
                 .filter_map(|h| h.response)
                 .collect::<Vec<_>>()
         })
-        .context("Was able to connect to NATS, but failed to get hosts.")
+        .context("Was able to connect to NATS, but failed to get hosts.")?;
+    let mut hosts = if with_inventory {
+        join_all(hosts.into_iter().map(|host| async {
+            // Hosts which do not respond to the inventory query are listed without counts
+            let inventory = client
+                .get_host_inventory(&host.id)
+                .await
+                .ok()
+                .and_then(|inventory| inventory.response);
+            HostDetails::new(host, inventory.as_ref())
+        }))
+        .await
+    } else {
+        hosts
+            .into_iter()
+            .map(|host| HostDetails::new(host, None))
+            .collect()
+    };
+    if let Some(key) = cmd.sort {
+        sort_hosts(&mut hosts, key);
+    }
+    Ok(hosts)
+}
+
+#[cfg(test)]
+mod test {
+    use wasmcloud_control_interface::{ComponentDescription, ProviderDescription};
+
+    use super::*;
+
+    fn host(id: &str, friendly_name: &str, uptime_seconds: u64) -> Host {
+        Host {
+            id: id.into(),
+            friendly_name: friendly_name.into(),
+            uptime_seconds,
+            ..Default::default()
+        }
+    }
+
+    fn inventory(max_instances: &[u32], providers: usize) -> HostInventory {
+        HostInventory {
+            components: max_instances
+                .iter()
+                .map(|&max_instances| ComponentDescription {
+                    max_instances,
+                    ..Default::default()
+                })
+                .collect(),
+            providers: vec![ProviderDescription::default(); providers],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_host_details() {
+        let mut labeled = host("NA", "alpha", 10);
+        labeled.labels = HashMap::from([
+            ("hostcore.os".to_string(), "linux".to_string()),
+            ("hostcore.arch".to_string(), "aarch64".to_string()),
+        ]);
+        let details = HostDetails::new(labeled, Some(&inventory(&[1, 20], 3)));
+        assert_eq!(details.os.as_deref(), Some("linux"));
+        assert_eq!(details.arch.as_deref(), Some("aarch64"));
+        assert_eq!(details.component_count, Some(2));
+        assert_eq!(details.provider_count, Some(3));
+        assert_eq!(details.max_instances, Some(21));
+
+        // Absent details are serialized as null
+        let details = HostDetails::new(host("NB", "beta", 5), None);
+        let json = serde_json::to_value(&details).expect("details should serialize");
+        assert_eq!(json["id"], "NB");
+        assert!(json["os"].is_null());
+        assert!(json["component_count"].is_null());
+        assert!(json["max_instances"].is_null());
+        let parsed: HostDetails = serde_json::from_value(json).expect("details should parse");
+        assert_eq!(parsed, details);
+    }
+
+    #[test]
+    fn test_inventory_entries() {
+        let wadm = HashMap::from([
+            (MANAGED_BY_ANNOTATION.to_string(), "wadm".to_string()),
+            (APP_SPEC_ANNOTATION.to_string(), "petclinic".to_string()),
+        ]);
+        let inventory = HostInventory {
+            host_id: "NA".to_string(),
+            components: vec![
+                ComponentDescription {
+                    id: "petclinic-ui".to_string(),
+                    max_instances: 4,
+                    annotations: Some(wadm.clone()),
+                    ..Default::default()
+                },
+                ComponentDescription {
+                    id: "hello".to_string(),
+                    max_instances: 1,
+                    ..Default::default()
+                },
+            ],
+            providers: vec![ProviderDescription {
+                id: "petclinic-httpserver".to_string(),
+                annotations: Some(wadm),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let entries = InventoryEntry::from_inventories(&[inventory]);
+        let attribution = entries
+            .iter()
+            .map(|e| (e.id.as_str(), e.app.as_deref(), e.managed_by.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attribution,
+            [
+                ("petclinic-ui", Some("petclinic"), Some("wadm")),
+                ("hello", None, None),
+                ("petclinic-httpserver", Some("petclinic"), Some("wadm")),
+            ]
+        );
+        assert_eq!(entries[2].max_instances, None);
+
+        // Absent annotations are serialized as null
+        let json = serde_json::to_value(&entries[1]).expect("entry should serialize");
+        assert!(json["app"].is_null());
+        assert!(json["managed_by"].is_null());
+    }
+
+    #[test]
+    fn test_sort_hosts() {
+        let mut hosts = vec![
+            HostDetails::new(host("NA", "gamma", 5), Some(&inventory(&[1], 0))),
+            HostDetails::new(host("NB", "alpha", 50), None),
+            HostDetails::new(host("NC", "beta", 20), Some(&inventory(&[1, 1, 1], 0))),
+        ];
+        let ids =
+            |hosts: &[HostDetails]| hosts.iter().map(|h| h.host.id.clone()).collect::<Vec<_>>();
+
+        sort_hosts(&mut hosts, HostSortKey::Name);
+        assert_eq!(ids(&hosts), ["NB", "NC", "NA"]);
+        sort_hosts(&mut hosts, HostSortKey::Uptime);
+        assert_eq!(ids(&hosts), ["NB", "NC", "NA"]);
+        // Hosts without a known component count are listed last
+        sort_hosts(&mut hosts, HostSortKey::Components);
+        assert_eq!(ids(&hosts), ["NC", "NA", "NB"]);
+    }
 }
@@ -98,6 +98,7 @@ pub async fn handle_command(
                 password: command.password.clone(),
                 insecure: command.insecure,
                 insecure_skip_tls_verify: command.insecure_skip_tls_verify,
+                ca_file: None,
             },
         )
         .await?;
@@ -313,6 +314,8 @@ pub(crate) async fn render_provider_claims(
     map.insert("version".to_string(), json!(friendly_ver));
     map.insert("revision".to_string(), json!(friendly_rev));
     map.insert("targets".to_string(), json!(artifact.targets()));
+    let binaries = super::par::provider_archive_binaries(&artifact);
+    map.insert("binaries".to_string(), json!(binaries));
     if let Some(schema) = artifact.schema() {
         map.insert("schema".to_string(), json!(schema));
     }
@@ -362,15 +365,34 @@ pub(crate) async fn render_provider_claims(
             Alignment::Left,
         )]));
 
-        if artifact.schema().is_some() {
+        table.add_row(Row::new(vec![TableCell::new_with_alignment(
+            "Binaries",
+            2,
+            Alignment::Center,
+        )]));
+
+        for binary in binaries {
+            table.add_row(Row::new(vec![
+                TableCell::new(format!("{} ({} bytes)", binary.target, binary.size)),
+                TableCell::new_with_alignment(
+                    binary
+                        .digest
+                        .map_or_else(|| "None".to_string(), |digest| format!("sha256:{digest}")),
+                    1,
+                    Alignment::Right,
+                ),
+            ]));
+        }
+
+        if let Some(schema) = artifact.schema() {
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "\nLink Definition Schema",
+                "Config Schema",
                 2,
                 Alignment::Center,
             )]));
 
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                "\nUse the JSON output option to extract the schema",
+                serde_json::to_string_pretty(&schema)?,
                 2,
                 Alignment::Left,
             )]));
@@ -1,16 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Parser;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table,
+};
 use tracing::{error, warn};
+use wasmcloud_control_interface::Client as CtlClient;
 
 use crate::{
     common::{boxed_err_to_anyhow, find_host_id},
     config::WashConnectionOptions,
 };
 
-use super::{CliConnectionOpts, CommandOutput};
+use super::{configure_table_style, CliConnectionOpts, CommandOutput};
 
 #[derive(Debug, Clone, Parser)]
 pub struct LabelHostCommand {
@@ -20,97 +28,329 @@ pub struct LabelHostCommand {
     /// ID of host to update the component on. If a non-ID is provided, the host will be selected based
     /// on matching the prefix of the ID or the friendly name and will return an error if more than
     /// one host matches.
-    #[clap(name = "host-id")]
-    pub host_id: String,
+    #[clap(name = "host-id", required_unless_present = "all_hosts")]
+    pub host_id: Option<String>,
+
+    /// Apply the labels to every host in the lattice (optionally narrowed down with `--filter-label`)
+    #[clap(long = "all-hosts", conflicts_with = "host-id")]
+    pub all_hosts: bool,
+
+    /// Only label hosts that have this label, in the form of a `[key]=[value]` pair or just a `[key]`
+    /// to match any value. May be specified multiple times, hosts must match all filters
+    #[clap(long = "filter-label", requires = "all_hosts", value_delimiter = ',')]
+    pub filter_labels: Vec<String>,
 
     /// Delete the label, instead of adding it
     #[clap(long = "delete", default_value = "false")]
     pub delete: bool,
 
+    /// Path to a TOML file containing a table of labels to apply, e.g. `cloud = "aws"`
+    #[clap(long = "from-file", conflicts_with_all = ["delete", "remove_prefix"])]
+    pub from_file: Option<PathBuf>,
+
+    /// Remove every label whose key starts with this prefix
+    #[clap(long = "remove-prefix", conflicts_with_all = ["delete", "label"])]
+    pub remove_prefix: Option<String>,
+
     /// Host label in the form of a `[key]=[value]` pair, e.g. "cloud=aws". When `--delete` is set, only the key is provided
     #[clap(name = "label", alias = "label", value_delimiter = ',')]
     pub labels: Vec<String>,
 }
 
+/// Result of setting or deleting a single label on a host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelResult {
+    pub key: String,
+    pub value: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of labeling a single host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostLabelResult {
+    pub host_id: String,
+    pub friendly_name: String,
+    pub success: bool,
+    /// Error that prevented the host from being labeled at all, e.g. the host went away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub labels: Vec<LabelResult>,
+}
+
+/// The label operation to perform on every selected host
+enum LabelOperation {
+    Put(Vec<(String, String)>),
+    Delete(Vec<String>),
+    RemovePrefix(String),
+}
+
 pub async fn handle_label_host(cmd: LabelHostCommand) -> Result<CommandOutput> {
+    // Resolve the labels to apply before touching any host so that a bad file or argument
+    // doesn't leave hosts partially labeled
+    let operation = if let Some(prefix) = cmd.remove_prefix {
+        if prefix.is_empty() {
+            bail!("--remove-prefix must not be empty");
+        }
+        LabelOperation::RemovePrefix(prefix)
+    } else if cmd.delete {
+        if cmd.labels.is_empty() {
+            bail!("at least one label key must be provided");
+        }
+        LabelOperation::Delete(parse_labels(&cmd.labels).into_keys().collect())
+    } else {
+        let mut labels = match cmd.from_file {
+            Some(ref path) => labels_from_file(path).await?,
+            None => BTreeMap::new(),
+        };
+        labels.extend(parse_labels(&cmd.labels));
+        if labels.is_empty() {
+            bail!("at least one label must be provided, either as an argument or with --from-file");
+        }
+        LabelOperation::Put(labels.into_iter().collect())
+    };
+
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let client = wco.into_ctl_client(None).await?;
 
-    let (host_id, friendly_name) = find_host_id(&cmd.host_id, &client).await?;
-
-    let friendly_name = if friendly_name.is_empty() {
-        host_id.to_string()
+    let hosts = if cmd.all_hosts {
+        let filters = parse_labels(&cmd.filter_labels);
+        client
+            .get_hosts()
+            .await
+            .map_err(boxed_err_to_anyhow)
+            .context("unable to fetch hosts")?
+            .into_iter()
+            .filter_map(|h| h.response)
+            .filter(|h| {
+                filters.iter().all(|(k, v)| {
+                    h.labels
+                        .get(k)
+                        .is_some_and(|value| v.is_empty() || value == v)
+                })
+            })
+            .map(|h| (h.id, h.friendly_name))
+            .collect::<Vec<_>>()
     } else {
-        friendly_name
+        let host_id = cmd.host_id.as_deref().context("host ID is required")?;
+        let (host_id, friendly_name) = find_host_id(host_id, &client).await?;
+        vec![(host_id.to_string(), friendly_name)]
     };
+    if hosts.is_empty() {
+        bail!("no hosts matched the provided filters");
+    }
 
-    let labels = cmd
-        .labels
+    let mut results = Vec::with_capacity(hosts.len());
+    for (host_id, friendly_name) in hosts {
+        let friendly_name = if friendly_name.is_empty() {
+            host_id.clone()
+        } else {
+            friendly_name
+        };
+        results.push(label_host(&client, host_id, friendly_name, &operation).await);
+    }
+
+    let succeeded = results.iter().all(|host| host.success);
+    let deleted = !matches!(operation, LabelOperation::Put(_));
+    let processed = results
         .iter()
-        .map(|orig| match orig.split_once('=') {
-            Some((k, v)) => (k, v),
-            None => (orig.as_str(), ""),
-        })
-        .collect::<Vec<(&str, &str)>>();
+        .flat_map(|host| host.labels.iter())
+        .filter(|label| label.success)
+        .map(|label| (label.key.clone(), label.value.clone()))
+        .collect::<Vec<_>>();
 
-    // Set/Delete the provided labels
-    let mut succeeded = true;
-    let mut processed: Vec<(&str, &str)> = Vec::new();
-    for (key, value) in &labels {
-        let op = if cmd.delete {
+    let output = format!(
+        "{} {} host(s)\n\n{}",
+        if deleted { "Unlabeled" } else { "Labeled" },
+        results.len(),
+        label_results_table(&results)
+    );
+
+    Ok(CommandOutput::new(
+        output,
+        HashMap::from([
+            ("success".into(), json!(succeeded)),
+            ("deleted".into(), json!(deleted)),
+            ("processed".into(), json!(processed)),
+            ("hosts".into(), json!(results)),
+        ]),
+    ))
+}
+
+/// Apply the label operation to a single host, recording the result of each key
+async fn label_host(
+    client: &CtlClient,
+    host_id: String,
+    friendly_name: String,
+    operation: &LabelOperation,
+) -> HostLabelResult {
+    let mut result = HostLabelResult {
+        host_id,
+        friendly_name,
+        success: true,
+        error: None,
+        labels: Vec::new(),
+    };
+
+    let (labels, delete) = match operation {
+        LabelOperation::Put(labels) => (labels.clone(), false),
+        LabelOperation::Delete(keys) => (
+            keys.iter().map(|k| (k.clone(), String::new())).collect(),
+            true,
+        ),
+        LabelOperation::RemovePrefix(prefix) => {
+            match client
+                .get_host_inventory(&result.host_id)
+                .await
+                .map_err(boxed_err_to_anyhow)
+            {
+                Ok(inventory) => {
+                    let Some(inventory) = inventory.response else {
+                        result.success = false;
+                        result.error = Some(inventory.message);
+                        return result;
+                    };
+                    let mut labels = inventory
+                        .labels
+                        .into_iter()
+                        .filter(|(k, _)| k.starts_with(prefix.as_str()))
+                        .collect::<Vec<_>>();
+                    labels.sort();
+                    (labels, true)
+                }
+                Err(e) => {
+                    error!(?e, host_id = %result.host_id, "failed to fetch host labels");
+                    result.success = false;
+                    result.error = Some(e.to_string());
+                    return result;
+                }
+            }
+        }
+    };
+
+    for (key, value) in labels {
+        let op = if delete {
             client
-                .delete_label(&host_id, key)
+                .delete_label(&result.host_id, &key)
                 .await
                 .map_err(boxed_err_to_anyhow)
         } else {
             client
-                .put_label(&host_id, key, value)
+                .put_label(&result.host_id, &key, &value)
                 .await
                 .map_err(boxed_err_to_anyhow)
         };
 
-        match op {
+        let error = match op {
+            Ok(ack) if ack.success => None,
             Ok(ack) => {
-                if !ack.success {
-                    warn!(message = ack.message, "operation failed");
-                    succeeded = false;
-                    break;
-                }
-                processed.push((key, value));
+                warn!(message = ack.message, key, "operation failed");
+                Some(ack.message)
             }
-            Err(error) => {
-                error!(?error, "failed to set/delete label");
-                succeeded = false;
-                break;
+            Err(e) => {
+                error!(?e, key, "failed to set/delete label");
+                Some(e.to_string())
             }
-        }
+        };
+        result.success &= error.is_none();
+        result.labels.push(LabelResult {
+            key,
+            value,
+            success: error.is_none(),
+            error,
+        });
     }
 
-    let output = format!(
-        "Host `{friendly_name}` {} with `{}`",
-        if cmd.delete { "unlabeled" } else { "labeled" },
-        labels
-            .iter()
-            .map(|(key, value)| format!("{key}={value}"))
-            .collect::<Vec<String>>()
-            .join(",")
-    );
+    result
+}
 
-    Ok(CommandOutput::new(
-        output,
-        HashMap::from([
-            ("success".into(), json!(succeeded)),
-            ("deleted".into(), json!(cmd.delete)),
-            ("processed".into(), json!(processed)),
-        ]),
-    ))
+/// Split `[key]=[value]` pairs, keys without a value map to an empty string
+fn parse_labels(labels: &[String]) -> BTreeMap<String, String> {
+    labels
+        .iter()
+        .map(|orig| match orig.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (orig.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Read a flat TOML table of labels from a file
+async fn labels_from_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read labels file [{}]", path.display()))?;
+    parse_labels_toml(&contents)
+        .with_context(|| format!("failed to parse labels file [{}]", path.display()))
+}
+
+fn parse_labels_toml(contents: &str) -> Result<BTreeMap<String, String>> {
+    let table: toml::Table = toml::from_str(contents)?;
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => bail!("label `{key}` must have a string, number or boolean value"),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+fn label_results_table(results: &[HostLabelResult]) -> String {
+    let mut table = Table::new();
+    configure_table_style(&mut table);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Host", 1, Alignment::Left),
+        TableCell::new_with_alignment("Label", 1, Alignment::Left),
+        TableCell::new_with_alignment("Result", 1, Alignment::Left),
+    ]));
+    for host in results {
+        if let Some(ref error) = host.error {
+            table.add_row(Row::new(vec![
+                TableCell::new_with_alignment(&host.friendly_name, 1, Alignment::Left),
+                TableCell::new_with_alignment("-", 1, Alignment::Left),
+                TableCell::new_with_alignment(format!("failed: {error}"), 1, Alignment::Left),
+            ]));
+        } else if host.labels.is_empty() {
+            table.add_row(Row::new(vec![
+                TableCell::new_with_alignment(&host.friendly_name, 1, Alignment::Left),
+                TableCell::new_with_alignment("-", 1, Alignment::Left),
+                TableCell::new_with_alignment("no matching labels", 1, Alignment::Left),
+            ]));
+        }
+        for label in &host.labels {
+            let label_text = if label.value.is_empty() {
+                label.key.clone()
+            } else {
+                format!("{}={}", label.key, label.value)
+            };
+            let status = match label.error {
+                Some(ref error) => format!("failed: {error}"),
+                None => "ok".to_string(),
+            };
+            table.add_row(Row::new(vec![
+                TableCell::new_with_alignment(&host.friendly_name, 1, Alignment::Left),
+                TableCell::new_with_alignment(label_text, 1, Alignment::Left),
+                TableCell::new_with_alignment(status, 1, Alignment::Left),
+            ]));
+        }
+    }
+
+    table.render()
 }
 
 #[cfg(test)]
 mod tests {
     use clap::Parser;
 
-    use super::LabelHostCommand;
+    use super::{parse_labels_toml, LabelHostCommand};
 
     const HOST_ID: &str = "host-id";
 
@@ -132,7 +372,7 @@ mod tests {
             labels,
             ..
         } = cmd.command;
-        assert_eq!(host_id, HOST_ID);
+        assert_eq!(host_id.as_deref(), Some(HOST_ID));
         assert!(!delete);
         assert_eq!(labels, expected_labels);
     }
@@ -153,8 +393,94 @@ mod tests {
             labels,
             ..
         } = cmd.command;
-        assert_eq!(host_id, HOST_ID);
+        assert_eq!(host_id.as_deref(), Some(HOST_ID));
         assert!(!delete);
         assert_eq!(labels, vec!["key1=value1"]);
     }
+
+    /// Ensure labels can be applied to all hosts matching a filter
+    #[test]
+    fn test_label_all_hosts() {
+        #[derive(Parser, Debug)]
+        struct Cmd {
+            #[clap(flatten)]
+            command: LabelHostCommand,
+        }
+
+        let cmd: Cmd = Parser::try_parse_from([
+            "label",
+            "--all-hosts",
+            "--filter-label",
+            "region=us,zone",
+            "key1=value1",
+        ])
+        .unwrap();
+        let LabelHostCommand {
+            host_id,
+            all_hosts,
+            filter_labels,
+            labels,
+            ..
+        } = cmd.command;
+        assert!(host_id.is_none());
+        assert!(all_hosts);
+        assert_eq!(filter_labels, vec!["region=us", "zone"]);
+        assert_eq!(labels, vec!["key1=value1"]);
+
+        // A host ID or --all-hosts is required, but not both
+        assert!(Cmd::try_parse_from(["label", "key1=value1"]).is_err());
+        assert!(Cmd::try_parse_from(["label", HOST_ID, "--all-hosts", "key1=value1"]).is_err());
+        assert!(Cmd::try_parse_from(["label", HOST_ID, "--filter-label", "a=b"]).is_err());
+    }
+
+    /// Ensure file and prefix options parse and conflict appropriately
+    #[test]
+    fn test_label_from_file_and_remove_prefix() {
+        #[derive(Parser, Debug)]
+        struct Cmd {
+            #[clap(flatten)]
+            command: LabelHostCommand,
+        }
+
+        let cmd: Cmd =
+            Parser::try_parse_from(["label", HOST_ID, "--from-file", "labels.toml"]).unwrap();
+        assert_eq!(
+            cmd.command.from_file.as_deref(),
+            Some(std::path::Path::new("labels.toml"))
+        );
+
+        let cmd: Cmd =
+            Parser::try_parse_from(["label", HOST_ID, "--remove-prefix", "team."]).unwrap();
+        assert_eq!(cmd.command.remove_prefix.as_deref(), Some("team."));
+
+        assert!(
+            Cmd::try_parse_from(["label", HOST_ID, "--remove-prefix", "team.", "key1=value1"])
+                .is_err()
+        );
+        assert!(
+            Cmd::try_parse_from(["label", HOST_ID, "--from-file", "labels.toml", "--delete"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_labels_toml() {
+        let labels = parse_labels_toml(
+            r#"
+team = "platform"
+"team.owner" = "alice"
+replicas = 3
+gpu = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(labels.get("team").map(String::as_str), Some("platform"));
+        assert_eq!(labels.get("team.owner").map(String::as_str), Some("alice"));
+        assert_eq!(labels.get("replicas").map(String::as_str), Some("3"));
+        assert_eq!(labels.get("gpu").map(String::as_str), Some("true"));
+
+        assert!(parse_labels_toml("[nested]\nkey = \"value\"").is_err());
+        assert!(parse_labels_toml("list = [1, 2]").is_err());
+        assert!(parse_labels_toml("not toml").is_err());
+    }
 }
@@ -1,8 +1,16 @@
-use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use wasmcloud_control_interface::{CtlResponse, InterfaceLinkDefinition};
+use serde::{Deserialize, Serialize};
+use wasmcloud_control_interface::{Client as CtlClient, CtlResponse, InterfaceLinkDefinition};
 
-use crate::{cli::CliConnectionOpts, common::boxed_err_to_anyhow, config::WashConnectionOptions};
+use crate::{
+    cli::{table::TableOpts, CliConnectionOpts},
+    common::boxed_err_to_anyhow,
+    config::WashConnectionOptions,
+};
 
 use super::validate_component_id;
 
@@ -72,11 +80,48 @@ pub struct LinkPutCommand {
 pub struct LinkQueryCommand {
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
+
+    #[clap(flatten)]
+    pub table: TableOpts,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct LinkExportCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// File to write the exported links to, the links are written to stdout if not specified
+    #[clap(long = "output-file")]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct LinkImportCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// File containing the links to import, as written by `wash link export`
+    #[clap(name = "file")]
+    pub file: PathBuf,
+
+    /// Report the changes that would be made to the lattice without making them
+    #[clap(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Delete the links present in the lattice but absent from the file
+    #[clap(long = "prune")]
+    pub prune: bool,
+
+    /// Delete pruned links without asking for confirmation
+    #[clap(short = 'y', long = "yes")]
+    pub yes: bool,
 }
 
 #[derive(Debug, Clone, Parser)]
 pub enum LinkCommand {
     /// Query all links, same as `wash get links`
+    ///
+    /// Columns: source, target, name, wit, interfaces, source-config, target-config
     #[clap(name = "query", alias = "get")]
     Query(LinkQueryCommand),
 
@@ -87,6 +132,197 @@ pub enum LinkCommand {
     /// Delete a link
     #[clap(name = "del", alias = "delete")]
     Del(LinkDelCommand),
+
+    /// Export all links of the lattice, to recreate them in another lattice
+    #[clap(name = "export")]
+    Export(LinkExportCommand),
+
+    /// Import links exported with `wash link export`
+    #[clap(name = "import")]
+    Import(LinkImportCommand),
+}
+
+/// Version of the [`LinkExport`] document format
+pub const LINK_EXPORT_VERSION: u32 = 1;
+
+/// Document listing all the links of a lattice, as written by `wash link export`.
+///
+/// Links reference named configuration by name only, configuration values are never exported.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LinkExport {
+    /// Version of the document format
+    pub version: u32,
+    /// Links of the lattice, ordered by source, WIT namespace and package, name and target
+    pub links: Vec<InterfaceLinkDefinition>,
+}
+
+impl LinkExport {
+    /// Create a document listing `links`, in a stable order
+    #[must_use]
+    pub fn new(mut links: Vec<InterfaceLinkDefinition>) -> Self {
+        links.sort_by(|a, b| {
+            link_key(a)
+                .cmp(&link_key(b))
+                .then_with(|| a.target.cmp(&b.target))
+        });
+        Self {
+            version: LINK_EXPORT_VERSION,
+            links,
+        }
+    }
+
+    /// Names of the configuration referenced by the links, sorted and deduplicated
+    #[must_use]
+    pub fn config_names(&self) -> BTreeSet<&str> {
+        self.links
+            .iter()
+            .flat_map(|link| link.source_config.iter().chain(&link.target_config))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Key identifying a link in a lattice: its source, WIT namespace and package, and name
+fn link_key(link: &InterfaceLinkDefinition) -> (&str, &str, &str, &str) {
+    (
+        &link.source_id,
+        &link.wit_namespace,
+        &link.wit_package,
+        &link.name,
+    )
+}
+
+/// Changes to make to a lattice to import links
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkImportPlan {
+    /// Links absent from the lattice
+    pub create: Vec<InterfaceLinkDefinition>,
+    /// Links differing from the existing link with the same key, along with that existing link
+    pub update: Vec<(InterfaceLinkDefinition, InterfaceLinkDefinition)>,
+    /// Links identical to an existing link
+    pub skip: Vec<InterfaceLinkDefinition>,
+    /// Existing links absent from the import, only deleted when pruning
+    pub prune: Vec<InterfaceLinkDefinition>,
+}
+
+impl LinkImportPlan {
+    /// Plan the import of `imported` links into a lattice containing `existing` links, deleting
+    /// the existing links absent from `imported` if `prune` is set
+    pub fn new(
+        existing: &[InterfaceLinkDefinition],
+        imported: &[InterfaceLinkDefinition],
+        prune: bool,
+    ) -> Result<Self> {
+        let existing_by_key: HashMap<_, _> =
+            existing.iter().map(|link| (link_key(link), link)).collect();
+        let mut imported_keys = BTreeSet::new();
+        let mut plan = Self::default();
+        for link in imported {
+            let key = link_key(link);
+            if !imported_keys.insert(key) {
+                bail!(
+                    "links to import contain multiple links from {} on {}:{} with link name {}",
+                    key.0,
+                    key.1,
+                    key.2,
+                    key.3
+                );
+            }
+            match existing_by_key.get(&key) {
+                None => plan.create.push(link.clone()),
+                Some(&current) if current == link => plan.skip.push(link.clone()),
+                Some(&current) => plan.update.push((current.clone(), link.clone())),
+            }
+        }
+        if prune {
+            plan.prune = existing
+                .iter()
+                .filter(|link| !imported_keys.contains(&link_key(link)))
+                .cloned()
+                .collect();
+        }
+        Ok(plan)
+    }
+
+    /// Apply the planned changes using `ctl_client`
+    pub async fn apply(&self, ctl_client: &CtlClient) -> Result<()> {
+        for link in &self.prune {
+            delete_link_with(ctl_client, link).await?;
+        }
+        for (current, link) in &self.update {
+            // Links are only replaced by the host if the target is the same
+            if current.target != link.target {
+                delete_link_with(ctl_client, current).await?;
+            }
+            put_link_with(ctl_client, link.clone()).await?;
+        }
+        for link in &self.create {
+            put_link_with(ctl_client, link.clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Names of the configuration in `names` which do not exist in the lattice
+pub async fn missing_configs<'a>(
+    ctl_client: &CtlClient,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    for name in names {
+        let config = ctl_client
+            .get_config(name)
+            .await
+            .map_err(boxed_err_to_anyhow)
+            .with_context(|| format!("Failed to get configuration {name}"))?;
+        if config.response.is_none() {
+            missing.push(name.to_string());
+        }
+    }
+    Ok(missing)
+}
+
+async fn delete_link_with(ctl_client: &CtlClient, link: &InterfaceLinkDefinition) -> Result<()> {
+    let res = ctl_client
+        .delete_link(
+            &link.source_id,
+            &link.name,
+            &link.wit_namespace,
+            &link.wit_package,
+        )
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !res.success {
+        bail!(
+            "Failed to remove link from {} on {}:{} with link name {}: {}",
+            link.source_id,
+            link.wit_namespace,
+            link.wit_package,
+            link.name,
+            res.message
+        );
+    }
+    Ok(())
+}
+
+async fn put_link_with(ctl_client: &CtlClient, link: InterfaceLinkDefinition) -> Result<()> {
+    let res = ctl_client
+        .put_link(link.clone())
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !res.success {
+        bail!(
+            "Failed to create link between {} and {} on {}:{}/{:?}. Link name: {}: {}",
+            link.source_id,
+            link.target,
+            link.wit_namespace,
+            link.wit_package,
+            link.interfaces,
+            link.name,
+            res.message
+        );
+    }
+    Ok(())
 }
 
 /// Query links for a given Wash instance
@@ -197,3 +433,74 @@ pub async fn put_link(
             )
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use wasmcloud_control_interface::InterfaceLinkDefinition;
+
+    use super::{LinkExport, LinkImportPlan};
+
+    fn link(source_id: &str, target: &str, name: &str) -> InterfaceLinkDefinition {
+        InterfaceLinkDefinition {
+            source_id: source_id.to_string(),
+            target: target.to_string(),
+            name: name.to_string(),
+            wit_namespace: "wasi".to_string(),
+            wit_package: "keyvalue".to_string(),
+            interfaces: vec!["store".to_string()],
+            source_config: vec![],
+            target_config: vec!["kv-config".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_link_export_order() {
+        let export = LinkExport::new(vec![
+            link("b", "kv", "default"),
+            link("a", "kv", "secondary"),
+            link("a", "kv", "default"),
+        ]);
+        let sources: Vec<_> = export
+            .links
+            .iter()
+            .map(|l| (l.source_id.as_str(), l.name.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            [("a", "default"), ("a", "secondary"), ("b", "default")]
+        );
+        assert_eq!(
+            export.config_names().into_iter().collect::<Vec<_>>(),
+            ["kv-config"]
+        );
+    }
+
+    #[test]
+    fn test_link_import_plan() {
+        let existing = [
+            link("a", "kv", "default"),
+            link("b", "kv", "default"),
+            link("c", "kv", "default"),
+        ];
+        let imported = [
+            link("a", "kv", "default"),
+            link("b", "other-kv", "default"),
+            link("d", "kv", "default"),
+        ];
+
+        let plan = LinkImportPlan::new(&existing, &imported, false).unwrap();
+        assert_eq!(plan.skip, [link("a", "kv", "default")]);
+        assert_eq!(
+            plan.update,
+            [(link("b", "kv", "default"), link("b", "other-kv", "default"))]
+        );
+        assert_eq!(plan.create, [link("d", "kv", "default")]);
+        assert!(plan.prune.is_empty());
+
+        let plan = LinkImportPlan::new(&existing, &imported, true).unwrap();
+        assert_eq!(plan.prune, [link("c", "kv", "default")]);
+
+        let duplicated = [link("a", "kv", "default"), link("a", "other-kv", "default")];
+        assert!(LinkImportPlan::new(&existing, &duplicated, false).is_err());
+    }
+}
@@ -0,0 +1,515 @@
+//! Offline checks of application manifests, complementing the validation performed by wadm
+//!
+//! wadm validates the structure of a manifest (including links to components that do not exist),
+//! but some mistakes only surface once the manifest is deployed. The checks in this module catch
+//! those without a lattice:
+//!
+//! - component names used more than once
+//! - links to components that are not part of the manifest
+//! - components and capability providers without an image
+//! - spread scaler weights that cannot be satisfied or are likely wrong
+//! - links between components whose target does not export the linked interfaces, when the images
+//!   of both components are available locally (as `file://` references or in the wash OCI cache)
+//!
+//! Failures refer to the line of the affected component in the manifest where it can be found.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use wadm_types::validation::{ValidationFailure, ValidationFailureLevel};
+
+use super::cached_oci_file;
+use super::dev::{component_interfaces, ComponentInterfaces};
+
+/// Lint the application manifest found at `path`, see the [module documentation](self)
+pub async fn lint_manifest_file(path: impl AsRef<Path>) -> Result<Vec<ValidationFailure>> {
+    let path = path.as_ref();
+    let manifest = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read manifest [{}]", path.display()))?;
+    lint_manifest(&manifest, path.parent()).await
+}
+
+/// Lint an application manifest, resolving relative `file://` images against `base_dir`
+pub async fn lint_manifest(
+    manifest: &str,
+    base_dir: Option<&Path>,
+) -> Result<Vec<ValidationFailure>> {
+    let parsed: Value = serde_yaml::from_str(manifest).context("failed to parse manifest")?;
+    let components = parsed
+        .get("spec")
+        .and_then(|spec| spec.get("components"))
+        .and_then(Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let lines = ManifestLines(manifest);
+
+    let mut failures = Vec::new();
+    check_duplicate_names(components, &lines, &mut failures);
+    check_link_targets(components, &lines, &mut failures);
+    check_images(components, &lines, &mut failures);
+    check_spread_weights(components, &lines, &mut failures);
+    let interfaces = local_component_interfaces(components, base_dir, &lines, &mut failures).await;
+    check_link_interfaces(components, &interfaces, &lines, &mut failures);
+    Ok(failures)
+}
+
+fn error(msg: String) -> ValidationFailure {
+    ValidationFailure {
+        level: ValidationFailureLevel::Error,
+        msg,
+    }
+}
+
+fn warning(msg: String) -> ValidationFailure {
+    ValidationFailure {
+        level: ValidationFailureLevel::Warning,
+        msg,
+    }
+}
+
+/// Raw manifest text, used to find the lines components are declared on
+struct ManifestLines<'a>(&'a str);
+
+impl ManifestLines<'_> {
+    /// 1-based numbers of the lines declaring a component (or other item) named `name`
+    fn of(&self, name: &str) -> Vec<usize> {
+        self.0
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| {
+                let line = line.trim_start().trim_start_matches("- ").trim();
+                line.strip_prefix("name:")
+                    .map(|value| value.trim().trim_matches(['"', '\'']) == name)
+                    .unwrap_or(false)
+            })
+            .map(|(idx, _)| idx + 1)
+            .collect()
+    }
+
+    /// Suffix pointing at the first line declaring `name`, if it can be found
+    fn at(&self, name: &str) -> String {
+        self.of(name)
+            .first()
+            .map(|line| format!(" (line {line})"))
+            .unwrap_or_default()
+    }
+}
+
+fn component_name(component: &Value) -> &str {
+    component
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn component_type(component: &Value) -> &str {
+    component
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn component_traits<'a>(
+    component: &'a Value,
+    trait_type: &'a str,
+) -> impl Iterator<Item = &'a Value> + 'a {
+    component
+        .get("traits")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter(move |t| t.get("type").and_then(Value::as_str) == Some(trait_type))
+        .filter_map(|t| t.get("properties"))
+}
+
+fn check_duplicate_names(
+    components: &[Value],
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for component in components {
+        *counts.entry(component_name(component)).or_default() += 1;
+    }
+    for (name, count) in counts {
+        if count < 2 || name.is_empty() {
+            continue;
+        }
+        let at = lines
+            .of(name)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let at = if at.is_empty() {
+            String::new()
+        } else {
+            format!(" (lines {})", at.join(", "))
+        };
+        failures.push(error(format!(
+            "component name [{name}] is used by {count} components{at}, component names must be unique"
+        )));
+    }
+}
+
+fn link_target(link: &Value) -> &str {
+    link.get("target")
+        .and_then(|target| target.as_str().or_else(|| target.get("name")?.as_str()))
+        .unwrap_or_default()
+}
+
+fn check_link_targets(
+    components: &[Value],
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) {
+    let names: BTreeSet<&str> = components.iter().map(component_name).collect();
+    for component in components {
+        let source = component_name(component);
+        for link in component_traits(component, "link") {
+            let target = link_target(link);
+            if !names.contains(target) {
+                failures.push(error(format!(
+                    "component [{source}] links to [{target}], which is not a component of the manifest{}",
+                    lines.at(source)
+                )));
+            }
+        }
+    }
+}
+
+fn check_images(
+    components: &[Value],
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) {
+    for component in components {
+        let properties = component.get("properties");
+        // Components may reference another application instead of specifying an image
+        if properties.and_then(|p| p.get("application")).is_some() {
+            continue;
+        }
+        let image = properties
+            .and_then(|p| p.get("image"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if image.trim().is_empty() {
+            let name = component_name(component);
+            failures.push(error(format!(
+                "{} [{name}] has no image{}",
+                component_type(component),
+                lines.at(name)
+            )));
+        }
+    }
+}
+
+fn check_spread_weights(
+    components: &[Value],
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) {
+    for component in components {
+        let name = component_name(component);
+        for scaler in ["spreadscaler", "daemonscaler"] {
+            for properties in component_traits(component, scaler) {
+                let Some(spread) = properties.get("spread").and_then(Value::as_sequence) else {
+                    continue;
+                };
+                let weights: Vec<u64> = spread
+                    .iter()
+                    .filter_map(|s| s.get("weight").and_then(Value::as_u64))
+                    .collect();
+                if weights.is_empty() {
+                    continue;
+                }
+                let total: u64 = weights.iter().sum();
+                if total == 0 {
+                    failures.push(error(format!(
+                        "{scaler} of component [{name}] has spread weights summing to 0, no instances can be placed{}",
+                        lines.at(name)
+                    )));
+                } else if weights.len() < spread.len() {
+                    failures.push(warning(format!(
+                        "{scaler} of component [{name}] only sets a weight on some of its spreads, spreads without a weight default to 100{}",
+                        lines.at(name)
+                    )));
+                } else if total != 100 {
+                    failures.push(warning(format!(
+                        "{scaler} of component [{name}] has spread weights summing to {total} rather than 100{}",
+                        lines.at(name)
+                    )));
+                }
+            }
+        }
+    }
+}
+
+/// Resolve the path of a component image available without pulling it, if any
+fn local_image_path(image: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
+    let path = if let Some(path) = image.strip_prefix("file://") {
+        let path = PathBuf::from(path);
+        match base_dir {
+            Some(base_dir) if path.is_relative() => base_dir.join(path),
+            _ => path,
+        }
+    } else {
+        cached_oci_file(image)
+    };
+    path.is_file().then_some(path)
+}
+
+/// Decode the interfaces of all components whose images are available locally
+async fn local_component_interfaces<'a>(
+    components: &'a [Value],
+    base_dir: Option<&Path>,
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) -> HashMap<&'a str, ComponentInterfaces> {
+    let mut interfaces = HashMap::new();
+    for component in components {
+        if component_type(component) != "component" {
+            continue;
+        }
+        let Some(path) = component
+            .get("properties")
+            .and_then(|p| p.get("image"))
+            .and_then(Value::as_str)
+            .and_then(|image| local_image_path(image, base_dir))
+        else {
+            continue;
+        };
+        let name = component_name(component);
+        match tokio::fs::read(&path)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|wasm| component_interfaces(&wasm))
+        {
+            Ok(component_interfaces) => {
+                interfaces.insert(name, component_interfaces);
+            }
+            Err(err) => failures.push(warning(format!(
+                "failed to read WIT of component [{name}] from [{}], skipping interface checks: {err:#}{}",
+                path.display(),
+                lines.at(name)
+            ))),
+        }
+    }
+    interfaces
+}
+
+fn check_link_interfaces(
+    components: &[Value],
+    interfaces: &HashMap<&str, ComponentInterfaces>,
+    lines: &ManifestLines<'_>,
+    failures: &mut Vec<ValidationFailure>,
+) {
+    for component in components {
+        let source = component_name(component);
+        let Some(source_interfaces) = interfaces.get(source) else {
+            continue;
+        };
+        for link in component_traits(component, "link") {
+            let target = link_target(link);
+            let Some(target_interfaces) = interfaces.get(target) else {
+                continue;
+            };
+            let namespace = link
+                .get("namespace")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let package = link
+                .get("package")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            for interface in link
+                .get("interfaces")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                let wit = (
+                    namespace.to_string(),
+                    package.to_string(),
+                    interface.to_string(),
+                );
+                if !target_interfaces.exports.contains(&wit) {
+                    failures.push(error(format!(
+                        "component [{source}] links to [{target}] on [{namespace}:{package}/{interface}], which [{target}] does not export{}",
+                        lines.at(source)
+                    )));
+                } else if !source_interfaces.imports.contains(&wit) {
+                    failures.push(warning(format!(
+                        "component [{source}] links to [{target}] on [{namespace}:{package}/{interface}], which [{source}] does not import{}",
+                        lines.at(source)
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Whether each failure is an error, along with its message
+    fn summarize(failures: Vec<ValidationFailure>) -> Vec<(bool, String)> {
+        failures
+            .into_iter()
+            .map(|failure| {
+                (
+                    matches!(failure.level, ValidationFailureLevel::Error),
+                    failure.msg,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_lint_manifest() {
+        let failures = lint_manifest(
+            r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: lint
+spec:
+  components:
+    - name: http-component
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 4
+            spread:
+              - name: east
+                weight: 60
+                requirements:
+                  zone: us-east-1
+              - name: west
+                weight: 30
+                requirements:
+                  zone: us-west-1
+        - type: link
+          properties:
+            target: keyvalue
+            namespace: wasi
+            package: keyvalue
+            interfaces: [store]
+    - name: http-component
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+    - name: httpserver
+      type: capability
+      properties:
+        image: ""
+"#,
+        )
+        .await
+        .expect("manifest should be linted");
+        assert_eq!(
+            summarize(failures),
+            vec![
+                (
+                    true,
+                    "component name [http-component] is used by 2 components (lines 8, 31), component names must be unique".to_string()
+                ),
+                (
+                    true,
+                    "component [http-component] links to [keyvalue], which is not a component of the manifest (line 8)".to_string()
+                ),
+                (
+                    true,
+                    "capability [httpserver] has no image (line 35)".to_string()
+                ),
+                (
+                    false,
+                    "spreadscaler of component [http-component] has spread weights summing to 90 rather than 100 (line 8)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_link_interfaces() {
+        let manifest = r#"
+spec:
+  components:
+    - name: app
+      type: component
+      properties:
+        image: file://app.wasm
+      traits:
+        - type: link
+          properties:
+            target: auth
+            namespace: test
+            package: auth
+            interfaces: [verify, tokens]
+        - type: link
+          properties:
+            target: httpclient
+            namespace: wasi
+            package: http
+            interfaces: [outgoing-handler]
+    - name: auth
+      type: component
+      properties:
+        image: file://auth.wasm
+"#;
+        let parsed: Value = serde_yaml::from_str(manifest).unwrap();
+        let components = parsed["spec"]["components"].as_sequence().unwrap();
+        let wit = |namespace: &str, package: &str, interface: &str| {
+            (
+                namespace.to_string(),
+                package.to_string(),
+                interface.to_string(),
+            )
+        };
+        let interfaces = HashMap::from([
+            (
+                "app",
+                ComponentInterfaces {
+                    imports: [wit("test", "auth", "verify")].into(),
+                    exports: [wit("wasi", "http", "incoming-handler")].into(),
+                },
+            ),
+            (
+                "auth",
+                ComponentInterfaces {
+                    imports: Default::default(),
+                    exports: [wit("test", "auth", "tokens")].into(),
+                },
+            ),
+        ]);
+
+        let mut failures = Vec::new();
+        check_link_interfaces(
+            components,
+            &interfaces,
+            &ManifestLines(manifest),
+            &mut failures,
+        );
+        // Links to components without known interfaces (here a provider) are not checked
+        assert_eq!(
+            summarize(failures),
+            vec![
+                (
+                    true,
+                    "component [app] links to [auth] on [test:auth/verify], which [auth] does not export (line 4)".to_string()
+                ),
+                (
+                    false,
+                    "component [app] links to [auth] on [test:auth/tokens], which [app] does not import (line 4)".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -22,8 +22,8 @@ use tracing::info;
 
 use crate::{
     config::{
-        cfg_dir, WashConnectionOptions, DEFAULT_LATTICE, DEFAULT_NATS_HOST, DEFAULT_NATS_PORT,
-        DEFAULT_NATS_TIMEOUT_MS,
+        cfg_dir, WashConnectionOptions, DEFAULT_CTL_RETRIES, DEFAULT_LATTICE, DEFAULT_NATS_HOST,
+        DEFAULT_NATS_PORT, DEFAULT_NATS_TIMEOUT_MS,
     },
     context::{default_timeout_ms, fs::ContextDir, ContextManager},
     keys::{
@@ -32,26 +32,39 @@ use crate::{
     },
 };
 
+pub mod app_instances;
 pub mod capture;
 pub mod claims;
+pub mod config_schema;
 pub mod dev;
+pub mod dev_registry;
+pub mod dev_session;
+pub mod events;
 pub mod get;
 pub mod inspect;
 pub mod label;
 pub mod link;
+pub mod manifest_lint;
 pub mod output;
 pub mod par;
+pub mod preflight;
 pub mod registry;
 pub mod scale;
+pub mod snapshot;
 pub mod spy;
 pub mod start;
 pub mod stop;
+pub mod table;
+pub mod tunnel;
 pub mod update;
+pub mod whoami;
 
 /// Used for displaying human-readable output vs JSON format
 #[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize, PartialEq)]
 pub enum OutputKind {
     Text,
+    /// Text including every available column of tables
+    Wide,
     Json,
 }
 
@@ -62,6 +75,7 @@ impl FromStr for OutputKind {
         match s {
             "json" => Ok(OutputKind::Json),
             "text" => Ok(OutputKind::Text),
+            "wide" => Ok(OutputKind::Wide),
             _ => Err(OutputParseErr),
         }
     }
@@ -182,6 +196,10 @@ pub struct CliConnectionOpts {
     )]
     pub timeout_ms: u64,
 
+    /// Do not retry control interface requests when no host responds to them or they time out
+    #[clap(long = "no-retry", env = "WASH_NO_RETRY")]
+    pub no_retry: bool,
+
     /// Name of a context to use for CTL connection and authentication
     #[clap(long = "context")]
     pub context: Option<String>,
@@ -199,6 +217,7 @@ impl Default for CliConnectionOpts {
             js_domain: None,
             lattice: Some(DEFAULT_LATTICE.to_string()),
             timeout_ms: DEFAULT_NATS_TIMEOUT_MS,
+            no_retry: false,
             context: None,
         }
     }
@@ -218,6 +237,7 @@ impl TryFrom<CliConnectionOpts> for WashConnectionOptions {
             js_domain,
             lattice,
             timeout_ms,
+            no_retry,
             context,
         }: CliConnectionOpts,
     ) -> Result<WashConnectionOptions> {
@@ -243,6 +263,7 @@ impl TryFrom<CliConnectionOpts> for WashConnectionOptions {
             js_domain,
             lattice,
             timeout_ms,
+            retries: if no_retry { 0 } else { DEFAULT_CTL_RETRIES },
             ctx,
         })
     }
@@ -293,7 +314,7 @@ pub fn extract_keypair(
             // No default key, generating for user
             None if !disable_keygen => {
                 match output_kind {
-                    OutputKind::Text => info!(
+                    OutputKind::Text | OutputKind::Wide => info!(
                         "No keypair found in \"{}\".
                     We will generate one for you and place it there.
                     If you'd like to use an existing key, you can supply it on the CLI as a flag.\n",
@@ -4,18 +4,32 @@ use serde::Deserialize;
 use wasmcloud_control_interface::{Host, HostInventory};
 use wasmcloud_core::{InterfaceLinkDefinition, LinkName};
 
+use wadm_types::api::{ModelSummary, Status, VersionInfo};
 use wadm_types::validation::ValidationFailure;
 
+use crate::start::ProcessLimits;
+
+use super::app_instances::AppInstances;
+use super::get::{HostDetails, InventoryEntry};
+use super::label::HostLabelResult;
+use super::whoami::WhoamiReport;
+
 /// JSON Output of the `wash start` command
 #[derive(Debug, Deserialize)]
 pub struct StartCommandOutput {
     pub component_id: Option<String>,
     pub component_ref: Option<String>,
+    /// Local file a started component was loaded from
+    #[serde(default)]
+    pub local_path: Option<String>,
 
     pub provider_id: Option<String>,
     pub provider_ref: Option<String>,
 
     pub host_id: Option<String>,
+    /// Named configuration applied to a started provider
+    #[serde(default)]
+    pub config: Vec<String>,
     pub success: bool,
 }
 
@@ -48,11 +62,22 @@ pub struct GetHostsCommandOutput {
     pub hosts: Vec<Host>,
 }
 
+/// JSON output representation of the `wash get hosts --detailed` command
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetHostDetailsCommandOutput {
+    pub success: bool,
+    pub hosts: Vec<HostDetails>,
+}
+
 /// JSON output representation of the `wash get inventory` command
 #[derive(Debug, Clone, Deserialize)]
 pub struct GetHostInventoriesCommandOutput {
     pub success: bool,
     pub inventories: Vec<HostInventory>,
+    /// Components and providers of all inventories, attributed to their application if managed
+    /// by wadm
+    #[serde(default)]
+    pub items: Vec<InventoryEntry>,
 }
 
 /// JSON output representation of the `wash get claims` command
@@ -79,7 +104,34 @@ pub struct ScaleCommandOutput {
 #[derive(Debug, Deserialize)]
 pub struct CallCommandOutput {
     pub success: bool,
+    #[serde(default)]
     pub response: serde_json::Value,
+    /// Report of the calls made with `--repeat` or `--duration`
+    #[serde(default)]
+    pub load: Option<CallLoadOutput>,
+}
+
+/// JSON output representation of the load test report of the `wash call` command
+#[derive(Debug, Deserialize)]
+pub struct CallLoadOutput {
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub errors: HashMap<String, u64>,
+    pub warmup: u64,
+    pub elapsed_secs: f64,
+    pub throughput: f64,
+    pub latency: CallLatencyOutput,
+    pub interrupted: bool,
+}
+
+/// JSON output representation of the latency percentiles of a `wash call` load test
+#[derive(Debug, Deserialize)]
+pub struct CallLatencyOutput {
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
 }
 
 /// JSON output representation of the `wash pull` command
@@ -95,6 +147,9 @@ pub struct LabelHostCommandOutput {
     pub success: bool,
     pub deleted: bool,
     pub processed: Vec<(String, String)>,
+    /// Per-host, per-label results
+    #[serde(default)]
+    pub hosts: Vec<HostLabelResult>,
 }
 
 /// JSON output representation of the `wash up` command
@@ -105,6 +160,44 @@ pub struct UpCommandOutput {
     pub wasmcloud_log: String,
     pub nats_url: String,
     pub deployed_wadm_manifest_path: Option<String>,
+    /// Client ports of the nodes of the NATS cluster started with `--nats-cluster-size`
+    #[serde(default)]
+    pub nats_cluster_ports: Vec<u16>,
+    /// Resource limits applied to the wasmCloud host with `--limit-*`
+    #[serde(default)]
+    pub limits: ProcessLimits,
+}
+
+/// JSON output representation of the `wash app history` command
+#[derive(Debug, Deserialize)]
+pub struct AppHistoryCommandOutput {
+    pub success: bool,
+    pub revisions: Vec<VersionInfo>,
+    /// Status of the deployed version of the application, if any
+    #[serde(default)]
+    pub status: Option<Status>,
+}
+
+/// JSON output representation of the `wash app rollback` command
+#[derive(Debug, Deserialize)]
+pub struct AppRollbackCommandOutput {
+    pub success: bool,
+    pub deployed: bool,
+    pub model_name: String,
+    pub model_version: String,
+    /// Version that was deployed before the rollback
+    pub previous_version: Option<String>,
+    pub status: Status,
+}
+
+/// JSON output representation of the `wash app list` command
+#[derive(Debug, Deserialize)]
+pub struct AppListCommandOutput {
+    pub success: bool,
+    pub applications: Vec<ModelSummary>,
+    /// Instances of the deployed applications, listed with `--detailed`
+    #[serde(default)]
+    pub instances: Vec<AppInstances>,
 }
 
 /// JSON output representation of the `wash app validate` command
@@ -114,3 +207,11 @@ pub struct AppValidateOutput {
     pub warnings: Vec<ValidationFailure>,
     pub errors: Vec<ValidationFailure>,
 }
+
+/// JSON output representation of the `wash whoami` command
+#[derive(Debug, Deserialize)]
+pub struct WhoamiCommandOutput {
+    pub success: bool,
+    #[serde(flatten)]
+    pub report: WhoamiReport,
+}
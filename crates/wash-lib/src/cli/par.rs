@@ -1,11 +1,17 @@
 use anyhow::{anyhow, Context, Result};
-use provider_archive::ProviderArchive;
+use provider_archive::{native_target, ProviderArchive};
+use serde::Serialize;
 use std::path::PathBuf;
+use wascap::jwt::{validate_token, CapabilityProvider};
 
-/// Helper function for detecting the arch used by the current machine
+/// Default limit of the size of provider archives checked by [`validate_provider_archive`]
+pub const DEFAULT_MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Helper function for detecting the arch used by the current machine, in the format of the
+/// targets hosts load from provider archives
 #[must_use]
 pub fn detect_arch() -> String {
-    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+    native_target()
 }
 
 pub struct ParCreateArgs {
@@ -57,6 +63,254 @@ pub async fn insert_provider_binary(
     Ok(par)
 }
 
+/// A binary embedded in a provider archive
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ParBinary {
+    /// Target of the binary, in the format `ARCH-OS`
+    pub target: String,
+    pub arch: String,
+    pub os: String,
+    /// Size of the binary in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256 digest of the binary, as signed in the claims of the archive
+    pub digest: Option<String>,
+    /// Mode of the binary file in the archive
+    pub mode: Option<u32>,
+}
+
+/// Contents of a provider archive, see [`inspect_provider_archive`]
+#[derive(Clone, Debug, Serialize)]
+pub struct ParInspection {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    pub revision: Option<i32>,
+    /// Account which signed the claims of the archive
+    pub issuer: Option<String>,
+    /// Service key of the provider
+    pub subject: Option<String>,
+    /// Size of the archive in bytes
+    pub size: u64,
+    /// Binaries of the archive, sorted by target
+    pub binaries: Vec<ParBinary>,
+}
+
+/// Severity of an issue found by [`validate_provider_archive`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParIssueSeverity {
+    /// The archive will be rejected by hosts
+    Error,
+    /// The archive is accepted by hosts, but may not behave as expected
+    Warning,
+}
+
+/// An issue found by [`validate_provider_archive`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ParIssue {
+    pub severity: ParIssueSeverity,
+    pub message: String,
+}
+
+/// Limits checked by [`validate_provider_archive`]
+#[derive(Clone, Copy, Debug)]
+pub struct ParValidationLimits {
+    /// Largest size of the archive in bytes
+    pub max_archive_bytes: u64,
+}
+
+impl Default for ParValidationLimits {
+    fn default() -> Self {
+        Self {
+            max_archive_bytes: DEFAULT_MAX_ARCHIVE_BYTES,
+        }
+    }
+}
+
+/// Outcome of [`validate_provider_archive`]
+#[derive(Clone, Debug, Serialize)]
+pub struct ParValidation {
+    /// Target the archive was validated for
+    pub target: String,
+    /// Whether no error was found
+    pub valid: bool,
+    pub issues: Vec<ParIssue>,
+    /// Contents of the archive, if it could be loaded
+    pub archive: Option<ParInspection>,
+}
+
+impl ParValidation {
+    /// Messages of the issues of the given severity
+    #[must_use]
+    pub fn messages(&self, severity: ParIssueSeverity) -> Vec<&str> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .map(|issue| issue.message.as_str())
+            .collect()
+    }
+}
+
+/// Formats a target of a provider archive as `os/arch`
+fn display_target(target: &str) -> String {
+    match target.split_once('-') {
+        Some((arch, os)) => format!("{os}/{arch}"),
+        None => target.to_string(),
+    }
+}
+
+/// Binaries of a loaded provider archive, sorted by target
+#[must_use]
+pub fn provider_archive_binaries(par: &ProviderArchive) -> Vec<ParBinary> {
+    let hashes = par
+        .claims()
+        .and_then(|claims| claims.metadata)
+        .map(|metadata| metadata.target_hashes)
+        .unwrap_or_default();
+    let mut targets = par.targets();
+    targets.sort();
+    targets
+        .into_iter()
+        .map(|target| {
+            let (arch, os) = target
+                .split_once('-')
+                .map(|(arch, os)| (arch.to_string(), os.to_string()))
+                .unwrap_or_else(|| (target.clone(), String::new()));
+            ParBinary {
+                size: par
+                    .target_bytes(&target)
+                    .map_or(0, |bytes| bytes.len() as u64),
+                digest: hashes.get(&target).map(|hash| hash.to_lowercase()),
+                mode: par.target_mode(&target),
+                arch,
+                os,
+                target,
+            }
+        })
+        .collect()
+}
+
+/// Loads a provider archive and lists its contents
+pub async fn inspect_provider_archive(archive: &[u8]) -> Result<ParInspection> {
+    let par = ProviderArchive::try_load(archive)
+        .await
+        .map_err(convert_error)
+        .context("failed to load provider archive")?;
+    Ok(inspection(&par, archive.len() as u64))
+}
+
+fn inspection(par: &ProviderArchive, size: u64) -> ParInspection {
+    let claims = par.claims();
+    let metadata = claims.as_ref().and_then(|claims| claims.metadata.clone());
+    ParInspection {
+        name: claims
+            .as_ref()
+            .map_or_else(|| "Anonymous".to_string(), |claims| claims.name()),
+        vendor: metadata.as_ref().map(|metadata| metadata.vendor.clone()),
+        version: metadata.as_ref().and_then(|metadata| metadata.ver.clone()),
+        revision: metadata.as_ref().and_then(|metadata| metadata.rev),
+        issuer: claims.as_ref().map(|claims| claims.issuer.clone()),
+        subject: claims.as_ref().map(|claims| claims.subject.clone()),
+        size,
+        binaries: provider_archive_binaries(par),
+    }
+}
+
+/// Validates a provider archive the way a host running on `target` (see [`detect_arch`]) loads
+/// it: the archive must load with the hashes of its binaries matching its claims, its claims must
+/// be validly signed and usable, and it must contain a binary for `target`. Binaries without
+/// executable bits and archives larger than the `limits` are reported as well
+pub async fn validate_provider_archive(
+    archive: &[u8],
+    limits: ParValidationLimits,
+    target: &str,
+) -> ParValidation {
+    let mut issues = Vec::new();
+    let mut error = |message: String| {
+        issues.push(ParIssue {
+            severity: ParIssueSeverity::Error,
+            message,
+        });
+    };
+
+    let size = archive.len() as u64;
+    if size > limits.max_archive_bytes {
+        error(format!(
+            "archive is {size} bytes, larger than the limit of {} bytes",
+            limits.max_archive_bytes
+        ));
+    }
+
+    let par = match ProviderArchive::try_load(archive).await {
+        Ok(par) => par,
+        Err(e) => {
+            error(format!("invalid provider archive: {e}"));
+            return ParValidation {
+                target: target.to_string(),
+                valid: false,
+                issues,
+                archive: None,
+            };
+        }
+    };
+
+    match par.claims_token().map(validate_token::<CapabilityProvider>) {
+        None => error("no claims found in the archive".to_string()),
+        Some(Err(e)) => error(format!("invalid claims: {e}")),
+        Some(Ok(validation)) => {
+            let issuer = par.claims().map(|claims| claims.issuer).unwrap_or_default();
+            if !validation.signature_valid {
+                error(format!(
+                    "claims signature is invalid, the claims were not signed by issuer {issuer}"
+                ));
+            }
+            if validation.expired {
+                error(format!("claims expired {}", validation.expires_human));
+            }
+            if validation.cannot_use_yet {
+                error(format!(
+                    "claims cannot be used until {}",
+                    validation.not_before_human
+                ));
+            }
+        }
+    }
+
+    let binaries = provider_archive_binaries(&par);
+    if !binaries.iter().any(|binary| binary.target == target) {
+        error(format!(
+            "no binary for {}; archive contains {}",
+            display_target(target),
+            binaries
+                .iter()
+                .map(|binary| display_target(&binary.target))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    for binary in &binaries {
+        if binary.mode.is_some_and(|mode| mode & 0o111 == 0) {
+            issues.push(ParIssue {
+                severity: ParIssueSeverity::Warning,
+                message: format!(
+                    "binary for {} is not executable (mode {:o}), hosts make it executable when extracting it but other tools may not",
+                    display_target(&binary.target),
+                    binary.mode.unwrap_or_default()
+                ),
+            });
+        }
+    }
+
+    ParValidation {
+        target: target.to_string(),
+        valid: !issues
+            .iter()
+            .any(|issue| issue.severity == ParIssueSeverity::Error),
+        issues,
+        archive: Some(inspection(&par, size)),
+    }
+}
+
 /// Converts error from Send + Sync error to standard anyhow error
 #[must_use]
 pub fn convert_error(e: Box<dyn ::std::error::Error + Send + Sync>) -> anyhow::Error {
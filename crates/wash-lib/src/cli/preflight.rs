@@ -0,0 +1,357 @@
+//! Pre-flight checks of the images of an application manifest, run by `wash app deploy` before the
+//! manifest is sent to wadm
+//!
+//! A typo in an image reference otherwise only surfaces minutes after deploying, deep in wadm and
+//! host reconciliation. For each component and capability provider of the manifest, the checks:
+//!
+//! - fetch the manifest of the image, with the authentication configured for its registry, to
+//!   confirm the reference resolves
+//! - compare the media type of the image with the kind of the component (a provider archive
+//!   deployed as a component, or the other way around, fails)
+//! - for providers, confirm the archive has a binary for at least one of the architectures of the
+//!   hosts of the lattice
+//!
+//! Images of registries requiring authentication wash has no credentials for are reported as
+//! warnings rather than failures, since the hosts may have the credentials wash lacks.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use oci_distribution::Reference;
+use provider_archive::ProviderArchive;
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::registry::{
+    fetch_oci_manifest, manifest_artifact_type, pull_oci_artifact, resolve_reference, ArtifactType,
+    OciPullOptions, RegistryConfig, ResolvedReference,
+};
+
+/// Label of hosts holding the architecture they run on
+const ARCH_LABEL: &str = "hostcore.arch";
+/// Label of hosts holding the operating system they run on
+const OS_LABEL: &str = "hostcore.os";
+
+/// Kind of a component of an application manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageKind {
+    Component,
+    Provider,
+}
+
+impl fmt::Display for ImageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Component => write!(f, "component"),
+            Self::Provider => write!(f, "provider"),
+        }
+    }
+}
+
+/// Outcome of the pre-flight checks of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of the pre-flight checks of the image of a component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImagePreflight {
+    /// Name of the component in the manifest
+    pub name: String,
+    /// Image of the component
+    pub image: String,
+    pub kind: ImageKind,
+    pub status: PreflightStatus,
+    /// Why the checks failed or warned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ImagePreflight {
+    fn with_status(&self, status: PreflightStatus, message: String) -> Self {
+        Self {
+            status,
+            message: Some(message),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for ImagePreflight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] ({})", self.kind, self.name, self.image)?;
+        match &self.message {
+            Some(message) => write!(f, ": {message}"),
+            None => write!(f, ": ok"),
+        }
+    }
+}
+
+/// Images of the components and capability providers of an application manifest, skipping
+/// components without an image or with a local (`file://`) image
+#[must_use]
+pub fn manifest_images(manifest: &Value) -> Vec<ImagePreflight> {
+    let components = manifest
+        .get("spec")
+        .and_then(|spec| spec.get("components"))
+        .and_then(Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    components
+        .iter()
+        .filter_map(|component| {
+            let kind = match component.get("type").and_then(Value::as_str)? {
+                "component" => ImageKind::Component,
+                "capability" => ImageKind::Provider,
+                _ => return None,
+            };
+            let image = component
+                .get("properties")
+                .and_then(|p| p.get("image"))
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|image| !image.is_empty() && !image.starts_with("file://"))?;
+            Some(ImagePreflight {
+                name: component
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                image: image.to_string(),
+                kind,
+                status: PreflightStatus::Ok,
+                message: None,
+            })
+        })
+        .collect()
+}
+
+/// Targets (`<arch>-<os>`, as named in provider archives) of hosts with the given labels
+#[must_use]
+pub fn host_targets<'a>(
+    host_labels: impl IntoIterator<Item = &'a HashMap<String, String>>,
+) -> BTreeSet<String> {
+    host_labels
+        .into_iter()
+        .filter_map(|labels| {
+            Some(format!(
+                "{}-{}",
+                labels.get(ARCH_LABEL)?,
+                labels.get(OS_LABEL)?
+            ))
+        })
+        .collect()
+}
+
+/// Check the images of an application manifest, see the [module documentation](self). Images
+/// are resolved against the configuration of their registry in `registries`, and provider
+/// archives are checked against the `targets` of the hosts of the lattice, if any.
+pub async fn preflight_images(
+    manifest: &Value,
+    registries: &HashMap<String, RegistryConfig>,
+    targets: &BTreeSet<String>,
+) -> Vec<ImagePreflight> {
+    let mut results = Vec::new();
+    for image in manifest_images(manifest) {
+        let result = match check_image(&image, registries, targets).await {
+            Ok(()) => image,
+            Err((status, message)) => image.with_status(status, message),
+        };
+        results.push(result);
+    }
+    results
+}
+
+async fn check_image(
+    image: &ImagePreflight,
+    registries: &HashMap<String, RegistryConfig>,
+    targets: &BTreeSet<String>,
+) -> Result<(), (PreflightStatus, String)> {
+    let error = |message: String| (PreflightStatus::Error, message);
+    let reference: Reference = image
+        .image
+        .parse()
+        .map_err(|e| error(format!("invalid image reference: {e}")))?;
+    let resolved =
+        resolve_reference(&reference, registries).map_err(|e| error(format!("{e:#}")))?;
+    let has_credentials = resolved.user.is_some() && resolved.password.is_some();
+
+    let manifest = fetch_oci_manifest(&resolved.reference, pull_options(&resolved))
+        .await
+        .map_err(|e| classify_fetch_error(&e, has_credentials))?;
+    let kind = match manifest_artifact_type(&manifest) {
+        Some(ArtifactType::Wasm) => ImageKind::Component,
+        Some(ArtifactType::Par) => ImageKind::Provider,
+        None => {
+            return Err((
+                PreflightStatus::Warning,
+                "image is neither a component nor a provider archive".to_string(),
+            ))
+        }
+    };
+    if kind != image.kind {
+        return Err(error(format!(
+            "image is a {kind}, but is deployed as a {}",
+            image.kind
+        )));
+    }
+
+    if kind == ImageKind::Provider && !targets.is_empty() {
+        let artifact = pull_oci_artifact(&resolved.reference, pull_options(&resolved))
+            .await
+            .map_err(|e| classify_fetch_error(&e, has_credentials))?;
+        let archive = ProviderArchive::try_load(&artifact)
+            .await
+            .map_err(|e| error(format!("invalid provider archive: {e}")))?;
+        let available = archive.targets();
+        if !available.iter().any(|target| targets.contains(target)) {
+            return Err(error(format!(
+                "no binary for the hosts of the lattice ({}), the archive has {}",
+                targets.iter().cloned().collect::<Vec<_>>().join(", "),
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Options pulling from the registry of a resolved reference. Tags are not checked, wadm accepts
+/// the same references as hosts do
+fn pull_options(resolved: &ResolvedReference) -> OciPullOptions {
+    OciPullOptions {
+        allow_latest: true,
+        user: resolved.user.clone(),
+        password: resolved.password.clone(),
+        insecure: resolved.insecure,
+        ca_file: resolved.ca_file.clone(),
+        ..Default::default()
+    }
+}
+
+/// Status and message of an error fetching an image: missing credentials are a warning, since
+/// hosts may have credentials wash doesn't
+fn classify_fetch_error(err: &anyhow::Error, has_credentials: bool) -> (PreflightStatus, String) {
+    let message = format!("{err:#}");
+    let lower = message.to_ascii_lowercase();
+    if ["401", "403", "unauthorized", "authentication", "denied"]
+        .iter()
+        .any(|needle| lower.contains(needle))
+    {
+        if has_credentials {
+            (
+                PreflightStatus::Error,
+                format!("registry rejected the configured credentials: {message}"),
+            )
+        } else {
+            (
+                PreflightStatus::Warning,
+                "registry requires authentication, image not checked (configure credentials for the registry in the wash context)".to_string(),
+            )
+        }
+    } else if ["manifest unknown", "not found", "404", "name unknown"]
+        .iter()
+        .any(|needle| lower.contains(needle))
+    {
+        (
+            PreflightStatus::Error,
+            format!("image not found: {message}"),
+        )
+    } else {
+        (
+            PreflightStatus::Error,
+            format!("failed to resolve image: {message}"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_manifest_images() {
+        let manifest: Value = serde_yaml::from_str(
+            r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: hello
+spec:
+  components:
+    - name: http-component
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+    - name: local
+      type: component
+      properties:
+        image: file://./build/local.wasm
+    - name: httpserver
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/http-server:0.21.0
+    - name: shared
+      type: component
+      properties:
+        application:
+          name: other
+          component: shared
+"#,
+        )
+        .expect("manifest should parse");
+        let images: Vec<_> = manifest_images(&manifest)
+            .into_iter()
+            .map(|i| (i.name, i.kind))
+            .collect();
+        assert_eq!(
+            images,
+            vec![
+                ("http-component".to_string(), ImageKind::Component),
+                ("httpserver".to_string(), ImageKind::Provider),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_host_targets() {
+        let labels = |arch: &str, os: &str| {
+            HashMap::from([
+                (ARCH_LABEL.to_string(), arch.to_string()),
+                (OS_LABEL.to_string(), os.to_string()),
+            ])
+        };
+        let hosts = [
+            labels("x86_64", "linux"),
+            labels("aarch64", "macos"),
+            labels("x86_64", "linux"),
+            HashMap::new(),
+        ];
+        assert_eq!(
+            host_targets(&hosts).into_iter().collect::<Vec<_>>(),
+            vec!["aarch64-macos", "x86_64-linux"]
+        );
+    }
+
+    #[test]
+    fn test_classify_fetch_error() {
+        let err = anyhow::anyhow!("Not authorized: url https://ghcr.io/token 401");
+        assert_eq!(
+            classify_fetch_error(&err, false).0,
+            PreflightStatus::Warning
+        );
+        assert_eq!(classify_fetch_error(&err, true).0, PreflightStatus::Error);
+        let err = anyhow::anyhow!("Registry error: MANIFEST_UNKNOWN manifest unknown");
+        let (status, message) = classify_fetch_error(&err, false);
+        assert_eq!(status, PreflightStatus::Error);
+        assert!(message.starts_with("image not found"), "{message}");
+    }
+}
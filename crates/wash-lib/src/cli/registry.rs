@@ -73,9 +73,9 @@ pub struct RegistryPushCommand {
     #[clap(name = "url")]
     pub url: String,
 
-    /// Path to artifact to push
-    #[clap(name = "artifact")]
-    pub artifact: String,
+    /// Path to artifact to push, required unless `--build` is given
+    #[clap(name = "artifact", required_unless_present = "build")]
+    pub artifact: Option<String>,
 
     /// Registry of artifact. This is only needed if the URL is not a full (OCI) artifact URL (ie, missing the registry fragment)
     #[clap(short = 'r', long = "registry", env = "WASH_REG_URL")]
@@ -93,6 +93,20 @@ pub struct RegistryPushCommand {
     #[clap(short = 'a', long = "annotation", name = "annotations")]
     pub annotations: Option<Vec<String>>,
 
+    /// Build the project in the current directory and push the built artifact
+    #[clap(long = "build", conflicts_with = "artifact")]
+    pub build: bool,
+
+    /// Normalize the artifact so that pushing the same component always results in the same
+    /// digest. Timestamps are set to $SOURCE_DATE_EPOCH (or the Unix epoch) and embedded claims,
+    /// which contain their time of issue, are removed
+    #[clap(long = "reproducible")]
+    pub reproducible: bool,
+
+    /// Abort the push if the digest of the artifact differs from this one, e.g. `sha256:...`
+    #[clap(long = "expect-digest", value_name = "DIGEST")]
+    pub expect_digest: Option<String>,
+
     #[clap(flatten)]
     pub opts: AuthOpts,
 }
@@ -0,0 +1,556 @@
+//! Reconstruction of an application manifest from the running state of a lattice, used by
+//! `wash app snapshot`
+//!
+//! Components and capability providers found in host inventories become the components of the
+//! manifest, named after their current names, with a spread scaler matching their current number
+//! of instances. Links become link traits on their source. Some state cannot be captured
+//! faithfully and is reported as warnings instead:
+//!
+//! - named configuration referenced by links is inlined in the manifest with its current values,
+//!   so deploying the manifest writes it
+//! - values of that configuration that look like secrets are replaced by `${NAME}` placeholders,
+//!   to be supplied with `wash app deploy --set NAME=...`
+//! - named configuration attached to components and providers is not visible in host inventories
+//! - links whose source or target is not running are skipped
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::{Context as _, Result};
+use serde_yaml::{Mapping, Value};
+use wasmcloud_control_interface::{Client as CtlClient, HostInventory, InterfaceLinkDefinition};
+
+use crate::common::boxed_err_to_anyhow;
+use crate::id::ServerId;
+
+use super::get::{query_host_inventories, APP_SPEC_ANNOTATION, MANAGED_BY_ANNOTATION};
+
+/// Substrings of configuration keys whose values are treated as secrets
+const SECRET_KEY_PATTERNS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "credential",
+    "api_key",
+    "apikey",
+    "private_key",
+];
+
+/// Running state of a lattice, as reported by its hosts
+#[derive(Debug, Clone, Default)]
+pub struct LatticeSnapshot {
+    /// Inventories of the hosts the snapshot was taken from
+    pub inventories: Vec<HostInventory>,
+    /// Links defined in the lattice
+    pub links: Vec<InterfaceLinkDefinition>,
+    /// Values of the named configuration referenced by the links, absent if it does not exist
+    pub configs: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl LatticeSnapshot {
+    /// Capture the state of the lattice from the inventory of `host_id`, or of all hosts
+    pub async fn capture(client: &CtlClient, host_id: Option<&ServerId>) -> Result<Self> {
+        let inventories = query_host_inventories(client, host_id).await?;
+        let links = client
+            .get_links()
+            .await
+            .map(|res| res.response.unwrap_or_default())
+            .map_err(boxed_err_to_anyhow)?;
+        let mut configs = BTreeMap::new();
+        for name in links
+            .iter()
+            .flat_map(|link| link.source_config.iter().chain(&link.target_config))
+        {
+            if configs.contains_key(name) {
+                continue;
+            }
+            let config = client
+                .get_config(name)
+                .await
+                .map_err(boxed_err_to_anyhow)
+                .with_context(|| format!("Failed to get configuration {name}"))?;
+            if let Some(values) = config.response {
+                configs.insert(name.clone(), values);
+            }
+        }
+        Ok(Self {
+            inventories,
+            links,
+            configs,
+        })
+    }
+}
+
+/// Application manifest reconstructed from a [`LatticeSnapshot`]
+#[derive(Debug, Clone)]
+pub struct SnapshotManifest {
+    /// The manifest
+    pub manifest: Value,
+    /// State of the lattice that could not be captured faithfully
+    pub warnings: Vec<String>,
+}
+
+/// Kind of a captured component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Component,
+    Capability,
+}
+
+/// A component or provider running in the lattice
+#[derive(Debug)]
+struct Captured {
+    kind: Kind,
+    current_name: Option<String>,
+    image: Option<String>,
+    /// Total max instances for components, number of hosts for providers
+    instances: u32,
+    managed_by: Option<String>,
+}
+
+/// Name derived from the current name (or ID) of a component, valid as a manifest component name
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars().map(|c| c.to_ascii_lowercase()) {
+        if c.is_ascii_alphanumeric() {
+            sanitized.push(c);
+        } else if !sanitized.ends_with('-') {
+            sanitized.push('-');
+        }
+    }
+    let sanitized = sanitized.trim_matches('-');
+    if sanitized.is_empty() {
+        "component".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Whether the configuration `key` likely holds a secret
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase().replace('-', "_");
+    SECRET_KEY_PATTERNS
+        .iter()
+        .any(|pattern| key.contains(pattern))
+}
+
+/// Name of the manifest placeholder standing in for the secret `key` of a configuration
+fn secret_placeholder(config_name: &str, key: &str) -> String {
+    format!("{config_name}_{key}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// YAML mapping of `entries`, in order
+fn mapping<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Mapping(
+        entries
+            .into_iter()
+            .map(|(key, value)| (Value::from(key), value))
+            .collect::<Mapping>(),
+    )
+}
+
+/// Reconstruct an application manifest named `name` from `snapshot`. If `include` is not empty,
+/// only the listed components (by ID) and the components they are linked with are captured
+#[must_use]
+pub fn snapshot_manifest(
+    name: &str,
+    snapshot: &LatticeSnapshot,
+    include: &[String],
+) -> SnapshotManifest {
+    let mut warnings = Vec::new();
+
+    let mut captured: BTreeMap<&str, Captured> = BTreeMap::new();
+    for inventory in &snapshot.inventories {
+        for component in &inventory.components {
+            let entry = captured
+                .entry(component.id.as_str())
+                .or_insert_with(|| Captured {
+                    kind: Kind::Component,
+                    current_name: component.name.clone(),
+                    image: Some(component.image_ref.clone()).filter(|image| !image.is_empty()),
+                    instances: 0,
+                    managed_by: managed_by(component.annotations.as_ref()),
+                });
+            entry.instances += component.max_instances;
+        }
+        for provider in &inventory.providers {
+            let entry = captured
+                .entry(provider.id.as_str())
+                .or_insert_with(|| Captured {
+                    kind: Kind::Capability,
+                    current_name: provider.name.clone(),
+                    image: provider.image_ref.clone().filter(|image| !image.is_empty()),
+                    instances: 0,
+                    managed_by: managed_by(provider.annotations.as_ref()),
+                });
+            entry.instances += 1;
+        }
+    }
+
+    // Restrict the snapshot to the included components and their link partners
+    if !include.is_empty() {
+        let mut selected: BTreeSet<&str> = include.iter().map(String::as_str).collect();
+        for id in include {
+            if !captured.contains_key(id.as_str()) {
+                warnings.push(format!("component [{id}] is not running in the lattice"));
+            }
+        }
+        for link in &snapshot.links {
+            if include.contains(&link.source_id) {
+                selected.insert(link.target.as_str());
+            }
+            if include.contains(&link.target) {
+                selected.insert(link.source_id.as_str());
+            }
+        }
+        captured.retain(|id, _| selected.contains(id));
+    }
+
+    // Derive unique names, in order of ID so that they are deterministic
+    let mut names: HashMap<&str, String> = HashMap::new();
+    let mut used: BTreeSet<String> = BTreeSet::new();
+    for (id, component) in &captured {
+        let base = sanitize_name(component.current_name.as_deref().unwrap_or(id));
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while !used.insert(name.clone()) {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        names.insert(*id, name);
+    }
+
+    let mut traits: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut inlined_configs = BTreeSet::new();
+    let mut omitted_secrets = BTreeSet::new();
+    for link in &snapshot.links {
+        let Some(target) = names
+            .get(link.target.as_str())
+            .filter(|_| names.contains_key(link.source_id.as_str()))
+        else {
+            if include.is_empty() {
+                warnings.push(format!(
+                    "skipped link from [{}] to [{}] ({}:{}/{}), its source or target is not running",
+                    link.source_id,
+                    link.target,
+                    link.wit_namespace,
+                    link.wit_package,
+                    link.interfaces.join(",")
+                ));
+            }
+            continue;
+        };
+        let mut properties = Mapping::new();
+        properties.insert("target".into(), target.as_str().into());
+        properties.insert("namespace".into(), link.wit_namespace.as_str().into());
+        properties.insert("package".into(), link.wit_package.as_str().into());
+        properties.insert(
+            "interfaces".into(),
+            Value::Sequence(
+                link.interfaces
+                    .iter()
+                    .map(|i| Value::from(i.as_str()))
+                    .collect(),
+            ),
+        );
+        if link.name != "default" {
+            properties.insert("name".into(), link.name.as_str().into());
+        }
+        for (side, config_names) in [
+            ("source", &link.source_config),
+            ("target", &link.target_config),
+        ] {
+            if config_names.is_empty() {
+                continue;
+            }
+            let configs = config_names
+                .iter()
+                .map(|config_name| {
+                    let Some(values) = snapshot.configs.get(config_name) else {
+                        warnings.push(format!(
+                            "configuration [{config_name}] referenced by link from [{}] to [{}] does not exist, it must be created before deploying",
+                            link.source_id, link.target
+                        ));
+                        return mapping([("name", config_name.as_str().into())]);
+                    };
+                    if inlined_configs.insert(config_name.as_str()) {
+                        warnings.push(format!(
+                            "configuration [{config_name}] was inlined with its current values, deploying overwrites it"
+                        ));
+                    }
+                    let values: Mapping = values
+                        .iter()
+                        .collect::<BTreeMap<_, _>>()
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let value = if is_secret_key(key) {
+                                let placeholder = secret_placeholder(config_name, key);
+                                if omitted_secrets.insert(placeholder.clone()) {
+                                    warnings.push(format!(
+                                        "value of [{key}] in configuration [{config_name}] was omitted, supply it with `--set {placeholder}=...` when deploying"
+                                    ));
+                                }
+                                format!("${{{placeholder}}}")
+                            } else {
+                                value.clone()
+                            };
+                            (Value::from(key.as_str()), Value::from(value))
+                        })
+                        .collect();
+                    mapping([
+                        ("name", config_name.as_str().into()),
+                        ("properties", Value::Mapping(values)),
+                    ])
+                })
+                .collect();
+            properties.insert(format!("{side}_config").into(), Value::Sequence(configs));
+        }
+        traits
+            .entry(link.source_id.as_str())
+            .or_default()
+            .push(mapping([
+                ("type", "link".into()),
+                ("properties", Value::Mapping(properties)),
+            ]));
+    }
+
+    let mut components = Vec::with_capacity(captured.len());
+    for (id, component) in &captured {
+        let name = &names[id];
+        let Some(image) = &component.image else {
+            warnings.push(format!(
+                "skipped [{id}], the image it was started from is unknown"
+            ));
+            continue;
+        };
+        if let Some(app) = &component.managed_by {
+            warnings.push(format!(
+                "[{id}] is managed by application [{app}], deploying the snapshot alongside it runs it twice"
+            ));
+        }
+        let kind = match component.kind {
+            Kind::Component => "component",
+            Kind::Capability => "capability",
+        };
+        let mut component_traits = vec![mapping([
+            ("type", "spreadscaler".into()),
+            (
+                "properties",
+                mapping([("instances", component.instances.max(1).into())]),
+            ),
+        ])];
+        component_traits.extend(traits.remove(id).unwrap_or_default());
+        components.push(mapping([
+            ("name", name.as_str().into()),
+            ("type", kind.into()),
+            ("properties", mapping([("image", image.as_str().into())])),
+            ("traits", Value::Sequence(component_traits)),
+        ]));
+    }
+    if !components.is_empty() {
+        warnings.push(
+            "named configuration attached to components and providers is not visible in host inventories and was not captured".to_string(),
+        );
+    }
+
+    let manifest = mapping([
+        ("apiVersion", "core.oam.dev/v1beta1".into()),
+        ("kind", "Application".into()),
+        (
+            "metadata",
+            mapping([
+                ("name", name.into()),
+                (
+                    "annotations",
+                    mapping([(
+                        "description",
+                        "Captured from a running lattice with `wash app snapshot`".into(),
+                    )]),
+                ),
+            ]),
+        ),
+        (
+            "spec",
+            mapping([("components", Value::Sequence(components))]),
+        ),
+    ]);
+    SnapshotManifest { manifest, warnings }
+}
+
+/// Name of the application managing a component or provider, from its annotations
+fn managed_by(annotations: Option<&HashMap<String, String>>) -> Option<String> {
+    let annotations = annotations?;
+    if annotations.get(MANAGED_BY_ANNOTATION).map(String::as_str) != Some("wadm") {
+        return None;
+    }
+    annotations.get(APP_SPEC_ANNOTATION).cloned()
+}
+
+#[cfg(test)]
+mod test {
+    use wasmcloud_control_interface::{ComponentDescription, ProviderDescription};
+
+    use super::*;
+
+    fn snapshot() -> LatticeSnapshot {
+        let inventory = |components: Vec<ComponentDescription>,
+                         providers: Vec<ProviderDescription>| {
+            HostInventory {
+                components,
+                providers,
+                ..Default::default()
+            }
+        };
+        let component = |id: &str, name: &str, max_instances| ComponentDescription {
+            id: id.to_string(),
+            image_ref: format!("ghcr.io/wasmcloud/components/{id}:0.1.0"),
+            name: Some(name.to_string()),
+            max_instances,
+            ..Default::default()
+        };
+        let httpserver = ProviderDescription {
+            id: "http_server".to_string(),
+            image_ref: Some("ghcr.io/wasmcloud/http-server:0.21.0".to_string()),
+            name: Some("HTTP Server".to_string()),
+            ..Default::default()
+        };
+        LatticeSnapshot {
+            inventories: vec![
+                inventory(
+                    vec![component("hello", "Hello World", 2)],
+                    vec![httpserver.clone()],
+                ),
+                inventory(
+                    vec![
+                        component("hello", "Hello World", 3),
+                        component("other", "other", 1),
+                    ],
+                    vec![httpserver],
+                ),
+            ],
+            links: vec![
+                InterfaceLinkDefinition {
+                    source_id: "http_server".to_string(),
+                    target: "hello".to_string(),
+                    name: "default".to_string(),
+                    wit_namespace: "wasi".to_string(),
+                    wit_package: "http".to_string(),
+                    interfaces: vec!["incoming-handler".to_string()],
+                    source_config: vec!["http-settings".to_string()],
+                    target_config: vec![],
+                },
+                InterfaceLinkDefinition {
+                    source_id: "hello".to_string(),
+                    target: "stopped".to_string(),
+                    name: "default".to_string(),
+                    wit_namespace: "wasi".to_string(),
+                    wit_package: "keyvalue".to_string(),
+                    interfaces: vec!["store".to_string()],
+                    source_config: vec![],
+                    target_config: vec![],
+                },
+            ],
+            configs: BTreeMap::from([(
+                "http-settings".to_string(),
+                HashMap::from([
+                    ("address".to_string(), "0.0.0.0:8080".to_string()),
+                    ("tls_password".to_string(), "hunter2".to_string()),
+                ]),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_manifest() {
+        let SnapshotManifest { manifest, warnings } =
+            snapshot_manifest("captured-app", &snapshot(), &[]);
+        let expected: Value = serde_yaml::from_str(
+            r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: captured-app
+  annotations:
+    description: Captured from a running lattice with `wash app snapshot`
+spec:
+  components:
+    - name: hello-world
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/hello:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 5
+    - name: http-server
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/http-server:0.21.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 2
+        - type: link
+          properties:
+            target: hello-world
+            namespace: wasi
+            package: http
+            interfaces: [incoming-handler]
+            source_config:
+              - name: http-settings
+                properties:
+                  address: 0.0.0.0:8080
+                  tls_password: ${HTTP_SETTINGS_TLS_PASSWORD}
+    - name: other
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/other:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 1
+"#,
+        )
+        .expect("valid manifest");
+        assert_eq!(manifest, expected);
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("[http-settings] was inlined")));
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("--set HTTP_SETTINGS_TLS_PASSWORD=")));
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("skipped link from [hello] to [stopped]")));
+    }
+
+    #[test]
+    fn test_snapshot_manifest_include() {
+        let SnapshotManifest { manifest, .. } =
+            snapshot_manifest("captured-app", &snapshot(), &["hello".to_string()]);
+        let names: Vec<_> = manifest["spec"]["components"]
+            .as_sequence()
+            .expect("components")
+            .iter()
+            .filter_map(|c| c["name"].as_str())
+            .collect();
+        // Link partners of included components are captured along with them
+        assert_eq!(names, ["hello-world", "http-server"]);
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("Hello World"), "hello-world");
+        assert_eq!(sanitize_name("http_server"), "http-server");
+        assert_eq!(sanitize_name("--"), "component");
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use clap::Parser;
+use serde_json::Value;
 use tokio::time::Duration;
 
 use crate::{
@@ -13,11 +15,19 @@ use crate::{
         DEFAULT_START_PROVIDER_TIMEOUT_MS,
     },
     context::default_timeout_ms,
+    registry::{mirror_artifact_ref, registries_with_env},
     wait::{wait_for_provider_start_event, FindEventOutcome, ProviderStartedInfo},
 };
 
+use super::dev_registry::DevRegistry;
 use super::validate_component_id;
 
+/// Suffix of the components signed by `wash build`
+const SIGNED_COMPONENT_SUFFIX: &str = "_s.wasm";
+
+/// How long the ephemeral registry serving a local component keeps running once it started
+const DEV_REGISTRY_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Clone, Parser)]
 pub enum StartCommand {
     /// Launch a component in a host
@@ -41,7 +51,10 @@ pub struct StartComponentCommand {
     #[clap(long = "host-id")]
     pub host_id: Option<String>,
 
-    /// Component reference, e.g. the absolute file path or OCI URL.
+    /// Component reference, e.g. an OCI URL or the path of a component file or of a directory
+    /// (e.g. `build/`) containing a single signed component. Relative paths are resolved against
+    /// the current directory. Local components are loaded from the file by hosts which allow it, and
+    /// are otherwise served to the host through an ephemeral local registry
     #[clap(name = "component-ref")]
     pub component_ref: String,
 
@@ -85,15 +98,21 @@ pub async fn handle_start_component(cmd: StartComponentCommand) -> Result<Comman
     } else {
         cmd.opts.timeout_ms
     };
-    let client = <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?
-        .into_ctl_client(Some(cmd.auction_timeout_ms))
-        .await?;
-
-    // TODO: absolutize the path if it's a relative file
-    let component_ref = if cmd.component_ref.starts_with('/') {
-        format!("file://{}", &cmd.component_ref) // prefix with file:// if it's an absolute path
-    } else {
-        cmd.component_ref.to_string()
+    let local_path = resolve_local_component(&cmd.component_ref).await?;
+    let opts = <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let registries = registries_with_env(&opts.ctx.registries)?;
+    let client = opts.into_ctl_client(Some(cmd.auction_timeout_ms)).await?;
+
+    let component_ref = match &local_path {
+        Some(path) => format!("file://{}", path.display()),
+        // Absolute paths which do not exist locally may still exist on the host
+        None if cmd.component_ref.starts_with('/') => format!("file://{}", &cmd.component_ref),
+        None => cmd.component_ref.to_string(),
+    };
+    // Pull the component through the mirror of its registry, if one is configured
+    let (component_ref, original_ref) = match mirror_artifact_ref(&component_ref, &registries)? {
+        Some(mirrored) => (mirrored, Some(component_ref)),
+        None => (component_ref, None),
     };
 
     let host = match cmd.host_id {
@@ -129,22 +148,52 @@ pub async fn handle_start_component(cmd: StartComponentCommand) -> Result<Comman
     };
 
     // Start the component
+    let scale = |component_ref: String| {
+        let client = &client;
+        let host = &host;
+        let component_id = &cmd.component_id;
+        let (max_instances, skip_wait) = (cmd.max_instances, cmd.skip_wait);
+        let config = cmd.config.clone();
+        async move {
+            scale_component(ScaleComponentArgs {
+                client,
+                host_id: host,
+                component_ref: &component_ref,
+                component_id,
+                max_instances,
+                skip_wait,
+                timeout_ms: Some(timeout_ms),
+                annotations: None,
+                config,
+            })
+            .await
+        }
+    };
+    let scaled = scale(component_ref).await;
+    // Hosts which do not allow loading files pull local components from an ephemeral registry
+    // instead. Failures are not reported when skipping the wait, so the file is used regardless
+    let scaled = match (scaled, &local_path) {
+        (Err(err), Some(path)) if format!("{err:#}").contains("file loading is disabled") => {
+            let component = tokio::fs::read(path)
+                .await
+                .with_context(|| format!("failed to read component [{}]", path.display()))?;
+            let registry = DevRegistry::start().await?;
+            let dev_ref = registry
+                .push_component(&format!("local/{}", cmd.component_id), component)
+                .context("failed to push component to ephemeral registry")?;
+            let scaled = scale(dev_ref).await;
+            // Let the host finish any pull in progress before the registry stops
+            tokio::time::sleep(DEV_REGISTRY_GRACE_PERIOD).await;
+            drop(registry);
+            scaled
+        }
+        (scaled, _) => scaled,
+    };
     let ComponentScaledInfo {
         host_id,
         component_ref,
         component_id,
-    } = scale_component(ScaleComponentArgs {
-        client: &client,
-        host_id: &host,
-        component_ref: &cmd.component_ref,
-        component_id: &cmd.component_id,
-        max_instances: cmd.max_instances,
-        skip_wait: cmd.skip_wait,
-        timeout_ms: Some(timeout_ms),
-        annotations: None,
-        config: cmd.config,
-    })
-    .await?;
+    } = scaled?;
 
     let text = if cmd.skip_wait {
         format!("Start component [{component_ref}] request received on host [{host_id}]",)
@@ -152,15 +201,71 @@ pub async fn handle_start_component(cmd: StartComponentCommand) -> Result<Comman
         format!("Component [{component_id}] (ref: [{component_ref}]) started on host [{host_id}]",)
     };
 
-    Ok(CommandOutput::new(
-        text.clone(),
-        HashMap::from([
-            ("result".into(), text.into()),
-            ("component_ref".into(), component_ref.into()),
-            ("component_id".into(), component_id.into()),
-            ("host_id".into(), host_id.into()),
-        ]),
-    ))
+    let mut map = HashMap::from([
+        ("result".into(), text.clone().into()),
+        ("component_ref".into(), component_ref.into()),
+        ("component_id".into(), component_id.into()),
+        ("host_id".into(), host_id.into()),
+    ]);
+    if let Some(original_ref) = original_ref {
+        map.insert("original_ref".into(), original_ref.into());
+    }
+    if let Some(local_path) = local_path {
+        map.insert("local_path".into(), local_path.display().to_string().into());
+    }
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Resolve the local component file `component_ref` refers to, if it is the path of a component
+/// file or of a directory containing a single signed component. Relative paths are resolved
+/// against the current directory
+async fn resolve_local_component(component_ref: &str) -> Result<Option<PathBuf>> {
+    let path = Path::new(
+        component_ref
+            .strip_prefix("file://")
+            .unwrap_or(component_ref),
+    );
+    if !tokio::fs::try_exists(path).await.unwrap_or(false) {
+        return Ok(None);
+    }
+    let path = tokio::fs::canonicalize(path)
+        .await
+        .with_context(|| format!("failed to resolve absolute path: {component_ref}"))?;
+    if !tokio::fs::metadata(&path).await?.is_dir() {
+        return Ok(Some(path));
+    }
+
+    let mut signed = Vec::new();
+    let mut entries = tokio::fs::read_dir(&path)
+        .await
+        .with_context(|| format!("failed to read directory [{}]", path.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let entry = entry.path();
+        if entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(SIGNED_COMPONENT_SUFFIX))
+        {
+            signed.push(entry);
+        }
+    }
+    signed.sort();
+    match signed.as_slice() {
+        [component] => Ok(Some(component.clone())),
+        [] => bail!(
+            "no signed component (`*{SIGNED_COMPONENT_SUFFIX}`) found in [{}], build one with `wash build`",
+            path.display()
+        ),
+        components => bail!(
+            "multiple signed components found in [{}], specify one of [{}]",
+            path.display(),
+            components
+                .iter()
+                .map(|component| component.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -199,6 +304,17 @@ pub struct StartProviderCommand {
     #[clap(long = "config")]
     pub config: Vec<String>,
 
+    /// Inline JSON object of configuration values to apply to the provider at startup. The values
+    /// are stored as an anonymous named configuration and applied after any `--config` names
+    #[clap(long = "config-json", conflicts_with = "config_file")]
+    pub config_json: Option<String>,
+
+    /// Path to a file containing a JSON object of configuration values to apply to the provider at
+    /// startup. The values are stored as an anonymous named configuration and applied after any
+    /// `--config` names
+    #[clap(long = "config-file")]
+    pub config_file: Option<PathBuf>,
+
     /// By default, the command will wait until the provider has been started.
     /// If this flag is passed, the command will return immediately after acknowledgement from the host, without waiting for the provider to start.
     /// If this flag is omitted, the timeout will be adjusted to 30 seconds to account for provider download times
@@ -213,9 +329,25 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
     } else {
         cmd.opts.timeout_ms
     };
-    let client = <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?
-        .into_ctl_client(Some(cmd.auction_timeout_ms))
-        .await?;
+
+    // Validate any inline configuration before touching the lattice
+    let inline_config = match (&cmd.config_json, &cmd.config_file) {
+        (Some(json), _) => Some(parse_config_json(json).context("invalid --config-json")?),
+        (None, Some(path)) => {
+            let contents = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("failed to read config file {}", path.display()))?;
+            Some(
+                parse_config_json(&contents)
+                    .with_context(|| format!("invalid config file {}", path.display()))?,
+            )
+        }
+        (None, None) => None,
+    };
+
+    let opts = <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let registries = registries_with_env(&opts.ctx.registries)?;
+    let client = opts.into_ctl_client(Some(cmd.auction_timeout_ms)).await?;
 
     // Attempt to parse the provider_ref from strings that may look lke paths or be OCI references
     let provider_ref = match cmd.provider_ref {
@@ -248,6 +380,11 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
         }
         _ => cmd.provider_ref.to_string(),
     };
+    // Pull the provider through the mirror of its registry, if one is configured
+    let (provider_ref, original_ref) = match mirror_artifact_ref(&provider_ref, &registries)? {
+        Some(mirrored) => (mirrored, Some(provider_ref)),
+        None => (provider_ref, None),
+    };
 
     let host = match cmd.host_id {
         Some(host) => find_host_id(&host, &client).await?.0,
@@ -290,8 +427,26 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
         .map_err(boxed_err_to_anyhow)
         .context("Failed to get lattice event channel")?;
 
+    let mut config = cmd.config;
+    if let Some(values) = inline_config {
+        let config_name = anonymous_config_name(&cmd.provider_id);
+        let put = client
+            .put_config(&config_name, values)
+            .await
+            .map_err(boxed_err_to_anyhow)
+            .with_context(|| format!("Failed to put configuration {config_name}"))?;
+        if !put.success {
+            bail!(
+                "Put configuration {config_name} not accepted: {}",
+                put.message
+            );
+        }
+        // Named configuration is merged in order, so inline values take precedence
+        config.push(config_name);
+    }
+
     let ack = client
-        .start_provider(&host, &provider_ref, &cmd.provider_id, None, cmd.config)
+        .start_provider(&host, &provider_ref, &cmd.provider_id, None, config.clone())
         .await
         .map_err(boxed_err_to_anyhow)
         .with_context(|| {
@@ -307,15 +462,17 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
 
     if cmd.skip_wait {
         let text = format!("Start provider request received: {}", &provider_ref);
-        return Ok(CommandOutput::new(
-            text.clone(),
-            HashMap::from([
-                ("result".into(), text.into()),
-                ("provider_ref".into(), provider_ref.into()),
-                ("link_name".into(), cmd.link_name.into()),
-                ("host_id".into(), host.to_string().into()),
-            ]),
-        ));
+        let mut map = HashMap::from([
+            ("result".into(), text.clone().into()),
+            ("provider_ref".into(), provider_ref.into()),
+            ("link_name".into(), cmd.link_name.into()),
+            ("host_id".into(), host.to_string().into()),
+            ("config".into(), config.into()),
+        ]);
+        if let Some(original_ref) = original_ref {
+            map.insert("original_ref".into(), original_ref.into());
+        }
+        return Ok(CommandOutput::new(text, map));
     }
 
     let event = wait_for_provider_start_event(
@@ -338,19 +495,24 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
             provider_ref,
             host_id,
         }) => {
-            let text = format!(
+            let mut text = format!(
                 "Provider [{}] (ref: [{}]) started on host [{}]",
                 &provider_id, &provider_ref, &host_id
             );
-            Ok(CommandOutput::new(
-                text.clone(),
-                HashMap::from([
-                    ("result".into(), text.into()),
-                    ("provider_ref".into(), provider_ref.into()),
-                    ("provider_id".into(), provider_id.into()),
-                    ("host_id".into(), host_id.into()),
-                ]),
-            ))
+            if !config.is_empty() {
+                text.push_str(&format!(" with config [{}]", config.join(", ")));
+            }
+            let mut map = HashMap::from([
+                ("result".into(), text.clone().into()),
+                ("provider_ref".into(), provider_ref.into()),
+                ("provider_id".into(), provider_id.into()),
+                ("host_id".into(), host_id.into()),
+                ("config".into(), config.into()),
+            ]);
+            if let Some(original_ref) = original_ref {
+                map.insert("original_ref".into(), original_ref.into());
+            }
+            Ok(CommandOutput::new(text, map))
         }
         FindEventOutcome::Failure(err) => Err(err).with_context(|| {
             format!(
@@ -360,3 +522,85 @@ pub async fn handle_start_provider(cmd: StartProviderCommand) -> Result<CommandO
         }),
     }
 }
+
+/// Name of the configuration created for values passed inline to `wash start provider`. The name
+/// is stable per provider ID so restarting a provider replaces its previous inline configuration
+/// rather than accumulating new ones
+#[must_use]
+pub fn anonymous_config_name(provider_id: &str) -> String {
+    format!("{provider_id}-start-config")
+}
+
+/// Parse a JSON object of configuration values. Strings are used as-is and numbers and booleans
+/// are converted to their string representation, anything else is rejected since configuration
+/// values are always strings
+fn parse_config_json(json: &str) -> Result<HashMap<String, String>> {
+    let values: serde_json::Map<String, Value> =
+        serde_json::from_str(json).context("configuration must be a JSON object")?;
+    values
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(s) => Ok((key, s)),
+            Value::Number(_) | Value::Bool(_) => Ok((key, value.to_string())),
+            _ => bail!("value for configuration key [{key}] must be a string, number or boolean"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_local_component() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let build = dir.path().join("build");
+        tokio::fs::create_dir(&build).await?;
+        let build_ref = build.display().to_string();
+        assert!(
+            resolve_local_component(&build_ref).await.is_err(),
+            "directories without a signed component are rejected"
+        );
+
+        tokio::fs::write(build.join("hello.wasm"), b"").await?;
+        tokio::fs::write(build.join("hello_s.wasm"), b"").await?;
+        assert_eq!(
+            resolve_local_component(&build_ref).await?,
+            Some(tokio::fs::canonicalize(build.join("hello_s.wasm")).await?)
+        );
+        let unsigned = tokio::fs::canonicalize(build.join("hello.wasm")).await?;
+        assert_eq!(
+            resolve_local_component(&format!("file://{}", unsigned.display())).await?,
+            Some(unsigned)
+        );
+
+        tokio::fs::write(build.join("other_s.wasm"), b"").await?;
+        assert!(
+            resolve_local_component(&build_ref).await.is_err(),
+            "directories with multiple signed components are rejected"
+        );
+
+        assert_eq!(
+            resolve_local_component("ghcr.io/wasmcloud/components/hello:0.1.0").await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_config_json() {
+        let values =
+            parse_config_json(r#"{"address": "0.0.0.0:8081", "port": 8081, "tls": false}"#)
+                .expect("should parse valid config");
+        assert_eq!(
+            values.get("address").map(String::as_str),
+            Some("0.0.0.0:8081")
+        );
+        assert_eq!(values.get("port").map(String::as_str), Some("8081"));
+        assert_eq!(values.get("tls").map(String::as_str), Some("false"));
+
+        assert!(parse_config_json("{not json").is_err());
+        assert!(parse_config_json(r#"["address"]"#).is_err());
+        assert!(parse_config_json(r#"{"nested": {"a": "b"}}"#).is_err());
+    }
+}
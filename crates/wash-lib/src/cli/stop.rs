@@ -2,8 +2,9 @@ use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 use std::collections::HashMap;
 use tokio::time::Duration;
-use tracing::error;
+use tracing::{debug, error};
 use wasmcloud_control_interface::HostInventory;
+use wasmcloud_core::DrainReport;
 
 use crate::{
     cli::{CliConnectionOpts, CommandOutput},
@@ -17,6 +18,9 @@ use crate::{
 
 use super::validate_component_id;
 
+/// Default time in milliseconds a provider is given to complete in-flight invocations when draining
+pub const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 30_000;
+
 #[derive(Debug, Clone, Parser)]
 pub enum StopCommand {
     /// Stop a component running in a host
@@ -80,6 +84,19 @@ pub struct StopProviderCommand {
     /// waiting for the provider to stop.
     #[clap(long = "skip-wait")]
     pub skip_wait: bool,
+
+    /// Ask the provider to stop accepting new invocations and to complete in-flight ones before it
+    /// is stopped. Providers which do not support draining are stopped right away
+    #[clap(long = "drain")]
+    pub drain: bool,
+
+    /// The timeout in ms for how much time to give the provider to complete in-flight invocations
+    #[clap(
+        long = "drain-timeout-ms",
+        default_value_t = DEFAULT_DRAIN_TIMEOUT_MS,
+        requires = "drain"
+    )]
+    pub drain_timeout_ms: u64,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -99,6 +116,89 @@ pub struct StopHostCommand {
         default_value_t = default_timeout_ms()
     )]
     pub host_shutdown_timeout: u64,
+
+    /// Drain all providers running on the host before stopping it, see `wash stop provider --drain`
+    #[clap(long = "drain")]
+    pub drain: bool,
+
+    /// The timeout in ms for how much time to give each provider to complete in-flight invocations
+    #[clap(
+        long = "drain-timeout-ms",
+        default_value_t = DEFAULT_DRAIN_TIMEOUT_MS,
+        requires = "drain"
+    )]
+    pub drain_timeout_ms: u64,
+}
+
+/// Outcome of asking a provider to drain in-flight invocations before it is stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainOutcome {
+    /// All in-flight invocations completed
+    Completed,
+    /// The drain timeout elapsed with in-flight invocations left, if the provider reported them
+    TimedOut { abandoned: Option<usize> },
+    /// The provider does not support draining
+    Unsupported,
+}
+
+impl DrainOutcome {
+    /// Short status of the drain, as reported in JSON output
+    #[must_use]
+    pub const fn status(&self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::TimedOut { .. } => "timed_out",
+            Self::Unsupported => "unsupported",
+        }
+    }
+
+    /// Add the outcome to command output of a stopped provider and describe it
+    fn describe(&self, map: &mut HashMap<String, serde_json::Value>) -> String {
+        map.insert("drain".into(), self.status().into());
+        match self {
+            Self::Completed => "in-flight invocations completed".into(),
+            Self::TimedOut {
+                abandoned: Some(abandoned),
+            } => {
+                map.insert("abandoned".into(), (*abandoned).into());
+                format!("drain timed out, {abandoned} in-flight invocation(s) abandoned")
+            }
+            Self::TimedOut { abandoned: None } => "drain timed out".into(),
+            Self::Unsupported => "provider does not support draining".into(),
+        }
+    }
+}
+
+/// Ask a provider to stop accepting new invocations and wait at most `timeout_ms` for in-flight
+/// ones to complete. The provider keeps running until it is stopped.
+///
+/// The request is relayed by the host running the provider, which signs it so that providers
+/// verifying control messages accept it. Hosts and providers predating draining do not handle the
+/// request, which is reported as [`DrainOutcome::Unsupported`] so the provider can be stopped as
+/// usual.
+pub async fn drain_provider(
+    client: &wasmcloud_control_interface::Client,
+    host_id: &str,
+    provider_id: &str,
+    timeout_ms: u64,
+) -> Result<DrainOutcome> {
+    let ack = client
+        .drain_provider(host_id, provider_id, timeout_ms)
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    match ack.response {
+        Some(DrainReport { drained: true, .. }) if ack.success => Ok(DrainOutcome::Completed),
+        Some(DrainReport { abandoned, .. }) if ack.success => {
+            Ok(DrainOutcome::TimedOut { abandoned })
+        }
+        _ => {
+            debug!(
+                message = ack.message,
+                provider_id, "host did not drain provider"
+            );
+            Ok(DrainOutcome::Unsupported)
+        }
+    }
 }
 
 pub async fn stop_provider(cmd: StopProviderCommand) -> Result<CommandOutput> {
@@ -120,6 +220,12 @@ pub async fn stop_provider(cmd: StopProviderCommand) -> Result<CommandOutput> {
         find_host_with_provider(&cmd.provider_id, &client).await?
     };
 
+    let drain = if cmd.drain {
+        Some(drain_provider(&client, &host_id, &cmd.provider_id, cmd.drain_timeout_ms).await?)
+    } else {
+        None
+    };
+
     let ack = client
         .stop_provider(&host_id, &cmd.provider_id)
         .await
@@ -129,15 +235,16 @@ pub async fn stop_provider(cmd: StopProviderCommand) -> Result<CommandOutput> {
         bail!("Operation failed: {}", ack.message);
     }
     if cmd.skip_wait {
-        let text = format!("Provider {} stop request received", cmd.provider_id);
-        return Ok(CommandOutput::new(
-            text.clone(),
-            HashMap::from([
-                ("result".into(), text.into()),
-                ("provider_id".into(), cmd.provider_id.to_string().into()),
-                ("host_id".into(), host_id.to_string().into()),
-            ]),
-        ));
+        let mut text = format!("Provider {} stop request received", cmd.provider_id);
+        let mut map = HashMap::from([
+            ("provider_id".into(), cmd.provider_id.to_string().into()),
+            ("host_id".into(), host_id.to_string().into()),
+        ]);
+        if let Some(drain) = drain {
+            text = format!("{text} ({})", drain.describe(&mut map));
+        }
+        map.insert("result".into(), text.clone().into());
+        return Ok(CommandOutput::new(text, map));
     }
 
     let event = wait_for_provider_stop_event(
@@ -153,15 +260,16 @@ pub async fn stop_provider(cmd: StopProviderCommand) -> Result<CommandOutput> {
             host_id,
             provider_id,
         }) => {
-            let text = format!("Provider [{}] stopped successfully", &cmd.provider_id);
-            Ok(CommandOutput::new(
-                text.clone(),
-                HashMap::from([
-                    ("result".into(), text.into()),
-                    ("provider_id".into(), provider_id.into()),
-                    ("host_id".into(), host_id.into()),
-                ]),
-            ))
+            let mut text = format!("Provider [{}] stopped successfully", &cmd.provider_id);
+            let mut map = HashMap::from([
+                ("provider_id".into(), provider_id.into()),
+                ("host_id".into(), host_id.into()),
+            ]);
+            if let Some(drain) = drain {
+                text = format!("{text} ({})", drain.describe(&mut map));
+            }
+            map.insert("result".into(), text.clone().into());
+            Ok(CommandOutput::new(text, map))
         }
         FindEventOutcome::Failure(err) => bail!("{}", err),
     }
@@ -243,15 +351,57 @@ pub async fn stop_host(cmd: StopHostCommand) -> Result<CommandOutput> {
     let client = wco.into_ctl_client(None).await?;
     let install_dir = downloads_dir()?;
 
+    let drained = if cmd.drain {
+        drain_host_providers(&client, &cmd.host_id, cmd.drain_timeout_ms).await?
+    } else {
+        Vec::new()
+    };
+
     let (_, hosts_remain) = stop_hosts(client, Some(&cmd.host_id), false).await?;
     if !hosts_remain {
         tokio::fs::remove_file(install_dir.join(WASMCLOUD_PID_FILE)).await?;
     }
 
-    Ok(CommandOutput::from_key_and_text(
-        "result",
-        format!("Host {} acknowledged stop request", cmd.host_id),
-    ))
+    let mut text = format!("Host {} acknowledged stop request", cmd.host_id);
+    let mut providers = serde_json::Map::new();
+    for (provider_id, drain) in drained {
+        let mut map = HashMap::new();
+        text.push_str(&format!(
+            "\n  Provider [{provider_id}]: {}",
+            drain.describe(&mut map)
+        ));
+        providers.insert(provider_id, serde_json::to_value(map)?);
+    }
+    let mut map = HashMap::from([("result".into(), text.clone().into())]);
+    if cmd.drain {
+        map.insert("providers".into(), providers.into());
+    }
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Drain all providers running on a host concurrently, returning the outcome for each provider
+async fn drain_host_providers(
+    client: &wasmcloud_control_interface::Client,
+    host_id: &str,
+    timeout_ms: u64,
+) -> Result<Vec<(String, DrainOutcome)>> {
+    let inventory = client
+        .get_host_inventory(host_id)
+        .await
+        .map(|inventory| inventory.response)
+        .map_err(boxed_err_to_anyhow)?
+        .context("Supplied host did not respond to inventory query")?;
+    let drains = inventory.providers.into_iter().map(|provider| {
+        let host_id = &inventory.host_id;
+        async move {
+            let outcome = drain_provider(client, host_id, &provider.id, timeout_ms).await?;
+            Ok((provider.id, outcome))
+        }
+    });
+    futures::future::join_all(drains)
+        .await
+        .into_iter()
+        .collect()
 }
 
 async fn find_host_with_provider(
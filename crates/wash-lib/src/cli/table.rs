@@ -0,0 +1,306 @@
+//! Tables printed by wash commands, with controls shared by all of them: `--no-headers`,
+//! `--columns` and `--output wide`
+//!
+//! Each command describes its columns with stable identifiers (e.g. `id`, `name`, `uptime`) used to
+//! select them with `--columns`, and the columns it shows by default. `--output wide` shows every
+//! column. When printing to a terminal too narrow for the table, values are truncated with an
+//! ellipsis, except in identifier columns which are left intact for scripts.
+
+use std::borrow::Cow;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use console::{measure_text_width, pad_str, truncate_str, Alignment, Term};
+
+use super::OutputKind;
+
+/// Separator between the columns of a table
+const COLUMN_SEPARATOR: &str = "  ";
+
+/// Width below which columns are not truncated further
+const MIN_TRUNCATED_WIDTH: usize = 8;
+
+/// Table controls of commands printing tables
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableOpts {
+    /// Do not print the header row of the table
+    #[clap(long = "no-headers")]
+    pub no_headers: bool,
+
+    /// Comma-separated identifiers of the columns to show, in order, e.g. `name,id`. See the help of
+    /// the command for the valid identifiers
+    #[clap(long = "columns", value_delimiter = ',')]
+    pub columns: Vec<String>,
+}
+
+/// A column of a table
+pub struct Column<T> {
+    id: &'static str,
+    header: &'static str,
+    value: fn(&T) -> String,
+    truncate: bool,
+}
+
+impl<T> Column<T> {
+    /// A column identified by `id` and titled `header`, whose values are produced by `value`
+    #[must_use]
+    pub fn new(id: &'static str, header: &'static str, value: fn(&T) -> String) -> Self {
+        Self {
+            id,
+            header,
+            value,
+            truncate: true,
+        }
+    }
+
+    /// Mark the column as holding identifiers, which are never truncated
+    #[must_use]
+    pub fn identifier(mut self) -> Self {
+        self.truncate = false;
+        self
+    }
+
+    /// Identifier of the column, used with `--columns`
+    #[must_use]
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+}
+
+/// Comma-separated identifiers of `columns`
+#[must_use]
+pub fn column_ids<T>(columns: &[Column<T>]) -> String {
+    columns
+        .iter()
+        .map(Column::id)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Select the columns to show: the columns listed with `--columns`, every column with
+/// `--output wide`, otherwise the columns identified by `defaults`
+///
+/// # Errors
+///
+/// Returns an error listing the valid identifiers if an unknown column is listed with `--columns`
+pub fn select_columns<'a, T>(
+    columns: &'a [Column<T>],
+    defaults: &[&str],
+    opts: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<Vec<&'a Column<T>>> {
+    let find = |id: &str| columns.iter().find(|column| column.id == id);
+    if !opts.columns.is_empty() {
+        return opts
+            .columns
+            .iter()
+            .map(|id| {
+                find(id.trim()).ok_or_else(|| {
+                    anyhow!(
+                        "unknown column `{id}`, valid columns are: {}",
+                        column_ids(columns)
+                    )
+                })
+            })
+            .collect();
+    }
+    if output_kind == OutputKind::Wide {
+        return Ok(columns.iter().collect());
+    }
+    Ok(defaults.iter().filter_map(|id| find(id)).collect())
+}
+
+/// Render `rows` as a table of the selected `columns` (see [`select_columns`]), fitted to the width
+/// of the terminal when printing to one
+///
+/// # Errors
+///
+/// Returns an error if an unknown column is listed with `--columns`
+pub fn render_table<T>(
+    rows: &[T],
+    columns: &[Column<T>],
+    defaults: &[&str],
+    opts: &TableOpts,
+    output_kind: OutputKind,
+) -> Result<String> {
+    let columns = select_columns(columns, defaults, opts, output_kind)?;
+    let width = Term::stdout()
+        .size_checked()
+        .map(|(_, width)| usize::from(width));
+    Ok(render(rows, &columns, !opts.no_headers, width))
+}
+
+/// Render `rows` as a table of `columns`, truncating values to fit in `max_width` if set
+fn render<T>(
+    rows: &[T],
+    columns: &[&Column<T>],
+    headers: bool,
+    max_width: Option<usize>,
+) -> String {
+    let header = headers.then(|| columns.iter().map(|c| c.header.to_string()).collect());
+    let lines: Vec<Vec<String>> = header
+        .into_iter()
+        .chain(
+            rows.iter()
+                .map(|row| columns.iter().map(|c| (c.value)(row)).collect()),
+        )
+        .collect();
+    let mut widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            lines
+                .iter()
+                .map(|line| measure_text_width(&line[i]))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    if let Some(max_width) = max_width {
+        shrink_columns(&mut widths, columns, max_width);
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            let cells: Vec<_> = line
+                .iter()
+                .zip(columns)
+                .zip(&widths)
+                .map(|((value, column), &width)| {
+                    let value = if column.truncate {
+                        truncate_str(value, width, "…")
+                    } else {
+                        Cow::Borrowed(value.as_str())
+                    };
+                    pad_str(&value, width, Alignment::Left, None).into_owned()
+                })
+                .collect();
+            cells.join(COLUMN_SEPARATOR).trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Shrink the widest truncatable columns until the table fits in `max_width`, or no column can be
+/// shrunk further
+fn shrink_columns<T>(widths: &mut [usize], columns: &[&Column<T>], max_width: usize) {
+    let separators = COLUMN_SEPARATOR.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + separators > max_width {
+        let Some(widest) = (0..widths.len())
+            .filter(|&i| columns[i].truncate && widths[i] > MIN_TRUNCATED_WIDTH)
+            .max_by_key(|&i| widths[i])
+        else {
+            break;
+        };
+        widths[widest] -= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Host {
+        id: &'static str,
+        name: &'static str,
+        description: &'static str,
+    }
+
+    fn columns() -> Vec<Column<Host>> {
+        vec![
+            Column::new("id", "ID", |h: &Host| h.id.to_string()).identifier(),
+            Column::new("name", "Name", |h: &Host| h.name.to_string()),
+            Column::new("description", "Description", |h: &Host| {
+                h.description.to_string()
+            }),
+        ]
+    }
+
+    const HOSTS: [Host; 2] = [
+        Host {
+            id: "NAAAAAAAAAAAAAAAAAAAA",
+            name: "quiet-sun",
+            description: "the first host of the lattice",
+        },
+        Host {
+            id: "NBBBBBBBBBBBBBBBBBBBB",
+            name: "loud-moon",
+            description: "second",
+        },
+    ];
+
+    fn ids(columns: &[&Column<Host>]) -> Vec<&'static str> {
+        columns.iter().map(|c| c.id()).collect()
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let columns = columns();
+        let defaults = ["id", "name"];
+        let opts = TableOpts::default();
+        let selected = select_columns(&columns, &defaults, &opts, OutputKind::Text).unwrap();
+        assert_eq!(ids(&selected), ["id", "name"]);
+        let selected = select_columns(&columns, &defaults, &opts, OutputKind::Wide).unwrap();
+        assert_eq!(ids(&selected), ["id", "name", "description"]);
+
+        let opts = TableOpts {
+            columns: vec!["description".to_string(), "id".to_string()],
+            ..Default::default()
+        };
+        let selected = select_columns(&columns, &defaults, &opts, OutputKind::Wide).unwrap();
+        assert_eq!(ids(&selected), ["description", "id"]);
+
+        let opts = TableOpts {
+            columns: vec!["id".to_string(), "uptime".to_string()],
+            ..Default::default()
+        };
+        let err = select_columns(&columns, &defaults, &opts, OutputKind::Text)
+            .err()
+            .expect("unknown columns should be rejected");
+        assert_eq!(
+            err.to_string(),
+            "unknown column `uptime`, valid columns are: id, name, description"
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let columns = columns();
+        let selected: Vec<_> = columns.iter().collect();
+        assert_eq!(
+            render(&HOSTS, &selected, true, None),
+            [
+                format!("{:<21}  {:<9}  Description", "ID", "Name"),
+                "NAAAAAAAAAAAAAAAAAAAA  quiet-sun  the first host of the lattice".to_string(),
+                "NBBBBBBBBBBBBBBBBBBBB  loud-moon  second".to_string(),
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            render(&HOSTS, &selected[..2], false, None),
+            "NAAAAAAAAAAAAAAAAAAAA  quiet-sun\nNBBBBBBBBBBBBBBBBBBBB  loud-moon"
+        );
+    }
+
+    #[test]
+    fn test_render_narrow_table() {
+        let columns = columns();
+        let selected: Vec<_> = columns.iter().collect();
+        let table = render(&HOSTS, &selected, true, Some(45));
+        assert!(
+            table.lines().all(|line| measure_text_width(line) <= 45),
+            "{table}"
+        );
+        assert!(table.contains("NAAAAAAAAAAAAAAAAAAAA  quiet-sun  the first"));
+        assert!(table.contains('…'));
+        assert!(table.ends_with("NBBBBBBBBBBBBBBBBBBBB  loud-moon  second"));
+
+        // Columns are truncated down to a minimum width, identifiers are kept intact
+        let table = render(&HOSTS, &selected, false, Some(10));
+        let first = table.lines().next().expect("table should have rows");
+        assert!(
+            first.starts_with("NAAAAAAAAAAAAAAAAAAAA  quiet-s…  "),
+            "{first}"
+        );
+        assert_eq!(measure_text_width(first), 21 + 2 + 8 + 2 + 8);
+    }
+}
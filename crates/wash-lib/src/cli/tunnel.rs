@@ -0,0 +1,300 @@
+//! Tunnels exposing the HTTP server of a component under development to other networks, used by
+//! `wash dev --share`
+//!
+//! Tunnels are established through the backend configured in the `[dev.tunnel]` section of the
+//! project's wasmcloud.toml (see [`TunnelConfig`]): a self-hosted wstunnel or frp server, whose
+//! client is run as a subprocess, or a listener forwarding connections from a local address.
+
+use std::fmt::Write as _;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{bail, Context as _, Result};
+use tokio::io::AsyncReadExt as _;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::parser::TunnelConfig;
+
+/// Duration a tunnel client must keep running for the tunnel to be considered established
+pub const TUNNEL_STARTUP_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// What keeps a tunnel open, closed when dropped
+#[derive(Debug)]
+enum TunnelGuard {
+    /// Tunnel client subprocess, along with its configuration file, if any
+    Process(Child, Option<tempfile::NamedTempFile>),
+    /// Task forwarding connections accepted on a local address
+    Forward(JoinHandle<()>),
+}
+
+impl Drop for TunnelGuard {
+    fn drop(&mut self) {
+        match self {
+            Self::Process(child, _) => {
+                let _ = child.start_kill();
+            }
+            Self::Forward(task) => task.abort(),
+        }
+    }
+}
+
+/// An open tunnel to a local address
+#[derive(Debug)]
+pub struct Tunnel {
+    public_url: String,
+    local_addr: SocketAddr,
+    _guard: TunnelGuard,
+}
+
+impl Tunnel {
+    /// Open a tunnel to `local_addr` through the backend configured in `config`
+    pub async fn open(config: &TunnelConfig, local_addr: SocketAddr) -> Result<Self> {
+        let (public_url, guard) = match config {
+            TunnelConfig::Wstunnel {
+                server,
+                remote_port,
+                public_url,
+                binary,
+            } => {
+                let mut cmd = Command::new(binary.as_deref().unwrap_or(Path::new("wstunnel")));
+                cmd.arg("client")
+                    .arg("-R")
+                    .arg(format!("tcp://[::]:{remote_port}:{local_addr}"))
+                    .arg(server);
+                let child = spawn_client(cmd).await?;
+                (public_url.clone(), TunnelGuard::Process(child, None))
+            }
+            TunnelConfig::Frp {
+                server_addr,
+                server_port,
+                token,
+                remote_port,
+                public_url,
+                binary,
+            } => {
+                let config = frpc_config(
+                    server_addr,
+                    *server_port,
+                    token.as_deref(),
+                    *remote_port,
+                    local_addr,
+                );
+                let mut file = tempfile::Builder::new()
+                    .prefix("wash-dev-frpc")
+                    .suffix(".toml")
+                    .tempfile()
+                    .context("failed to create frpc configuration file")?;
+                std::io::Write::write_all(&mut file, config.as_bytes())
+                    .context("failed to write frpc configuration file")?;
+                let mut cmd = Command::new(binary.as_deref().unwrap_or(Path::new("frpc")));
+                cmd.arg("-c").arg(file.path());
+                let child = spawn_client(cmd).await?;
+                (public_url.clone(), TunnelGuard::Process(child, Some(file)))
+            }
+            TunnelConfig::Local { listen, public_url } => {
+                let listen: SocketAddr = match listen {
+                    Some(listen) => listen
+                        .parse()
+                        .with_context(|| format!("invalid tunnel listen address `{listen}`"))?,
+                    None => (Ipv4Addr::LOCALHOST, 0).into(),
+                };
+                let listener = TcpListener::bind(listen)
+                    .await
+                    .with_context(|| format!("failed to listen on [{listen}]"))?;
+                let bound = listener
+                    .local_addr()
+                    .context("failed to get tunnel listen address")?;
+                let public_url = public_url
+                    .clone()
+                    .unwrap_or_else(|| format!("http://{bound}"));
+                let task = tokio::spawn(forward_connections(listener, local_addr));
+                (public_url, TunnelGuard::Forward(task))
+            }
+        };
+        debug!(%public_url, %local_addr, "opened tunnel");
+        Ok(Self {
+            public_url,
+            local_addr,
+            _guard: guard,
+        })
+    }
+
+    /// URL the tunnel is reachable at
+    #[must_use]
+    pub fn public_url(&self) -> &str {
+        &self.public_url
+    }
+
+    /// Local address the tunnel forwards connections to
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+/// Spawn a tunnel client, failing if it exits within the [`TUNNEL_STARTUP_GRACE_PERIOD`]
+async fn spawn_client(mut cmd: Command) -> Result<Child> {
+    let program = PathBuf::from(cmd.as_std().get_program());
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run tunnel client [{}]", program.display()))?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("failed to take tunnel client stderr")?;
+    if let Ok(status) = tokio::time::timeout(TUNNEL_STARTUP_GRACE_PERIOD, child.wait()).await {
+        let status = status.context("failed to wait for tunnel client")?;
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output).await;
+        bail!(
+            "tunnel client [{}] exited with {status}: {}",
+            program.display(),
+            output.trim()
+        );
+    }
+    // The client blocks once the pipe is full, so its output must keep being read
+    tokio::spawn(async move {
+        let _ = tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await;
+    });
+    Ok(child)
+}
+
+/// Configuration of `frpc` exposing `local_addr` on `remote_port` of the frp server
+fn frpc_config(
+    server_addr: &str,
+    server_port: u16,
+    token: Option<&str>,
+    remote_port: u16,
+    local_addr: SocketAddr,
+) -> String {
+    let mut config = format!("serverAddr = {server_addr:?}\nserverPort = {server_port}\n");
+    if let Some(token) = token {
+        let _ = writeln!(config, "auth.token = {token:?}");
+    }
+    let _ = write!(
+        config,
+        "\n[[proxies]]\nname = \"wash-dev-{remote_port}\"\ntype = \"tcp\"\nlocalIP = \"{}\"\nlocalPort = {}\nremotePort = {remote_port}\n",
+        local_addr.ip(),
+        local_addr.port()
+    );
+    config
+}
+
+/// Forward the connections accepted by `listener` to `local_addr`
+async fn forward_connections(listener: TcpListener, local_addr: SocketAddr) {
+    loop {
+        let (mut inbound, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                debug!(%err, "failed to accept tunnel connection");
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            match TcpStream::connect(local_addr).await {
+                Ok(mut outbound) => {
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                }
+                Err(err) => debug!(%peer, %local_addr, %err, "failed to forward tunnel connection"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::*;
+
+    #[test]
+    fn test_frpc_config() {
+        let config = frpc_config(
+            "tunnel.example.com",
+            7000,
+            Some("s3cr3t"),
+            6000,
+            "127.0.0.1:8000".parse().expect("valid address"),
+        );
+        assert_eq!(
+            config,
+            r#"serverAddr = "tunnel.example.com"
+serverPort = 7000
+auth.token = "s3cr3t"
+
+[[proxies]]
+name = "wash-dev-6000"
+type = "tcp"
+localIP = "127.0.0.1"
+localPort = 8000
+remotePort = 6000
+"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_tunnel() {
+        let server = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("failed to bind");
+        let server_addr = server.local_addr().expect("failed to get address");
+        tokio::spawn(async move {
+            let (mut conn, _) = server.accept().await.expect("failed to accept");
+            let mut buf = [0; 4];
+            conn.read_exact(&mut buf).await.expect("failed to read");
+            assert_eq!(&buf, b"ping");
+            conn.write_all(b"pong").await.expect("failed to write");
+        });
+
+        let tunnel = Tunnel::open(
+            &TunnelConfig::Local {
+                listen: None,
+                public_url: None,
+            },
+            server_addr,
+        )
+        .await
+        .expect("failed to open tunnel");
+        assert_eq!(tunnel.local_addr(), server_addr);
+        let addr = tunnel
+            .public_url()
+            .strip_prefix("http://")
+            .expect("public URL should be an HTTP URL");
+
+        let mut conn = TcpStream::connect(addr).await.expect("failed to connect");
+        conn.write_all(b"ping").await.expect("failed to write");
+        let mut buf = [0; 4];
+        conn.read_exact(&mut buf).await.expect("failed to read");
+        assert_eq!(&buf, b"pong");
+
+        // Closing the tunnel stops forwarding
+        drop(tunnel);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failing_client() {
+        let err = Tunnel::open(
+            &TunnelConfig::Wstunnel {
+                server: "wss://tunnel.example.com".to_string(),
+                remote_port: 8443,
+                public_url: "https://dev.example.com".to_string(),
+                binary: Some(PathBuf::from("/nonexistent/wstunnel")),
+            },
+            "127.0.0.1:8000".parse().expect("valid address"),
+        )
+        .await
+        .expect_err("tunnel should fail to open");
+        assert!(err.to_string().contains("failed to run tunnel client"));
+    }
+}
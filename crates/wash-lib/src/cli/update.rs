@@ -1,10 +1,19 @@
+use core::fmt;
+use core::future::Future;
+use core::time::Duration;
+
+use std::collections::HashMap;
+
 use anyhow::{bail, Context, Result};
 use clap::Parser;
-use wasmcloud_control_interface::HostInventory;
+use serde::{Deserialize, Serialize};
+use wasmcloud_control_interface::{
+    Client as CtlClient, ComponentDescription, HostInventory, InterfaceLinkDefinition,
+};
 
 use crate::{
     common::{boxed_err_to_anyhow, get_all_inventories},
-    component::update_component,
+    component::{scale_component, update_component, ScaleComponentArgs},
     config::WashConnectionOptions,
 };
 
@@ -37,15 +46,402 @@ pub struct UpdateComponentCommand {
     /// Component reference to replace the current running comonent with, e.g. the absolute file path or OCI URL.
     #[clap(name = "new-component-ref")]
     pub new_component_ref: String,
+
+    /// Start the new version alongside the running component under a temporary ID and only update
+    /// the running component once the new version passed a health probe. If the new version fails
+    /// to start or to pass the probe, it is removed and the running component is left untouched
+    #[clap(long = "rolling")]
+    pub rolling: bool,
+
+    /// Path requested from the HTTP handler of the new version to probe its health, for components
+    /// linked to an HTTP server. Any successful status passes the probe
+    #[clap(long = "health-path", default_value = "/", requires = "rolling")]
+    pub health_path: String,
+
+    /// Maximum time for the new version to pass its health probe, in milliseconds
+    #[clap(
+        long = "health-timeout-ms",
+        default_value_t = DEFAULT_HEALTH_TIMEOUT_MS,
+        requires = "rolling"
+    )]
+    pub health_timeout_ms: u64,
+
+    /// Function invoked on the new version to probe its health instead of an HTTP request, in the
+    /// form of `namespace:package/interface.function`. The function must take no arguments and
+    /// return a string
+    #[clap(long = "health-invoke", requires = "rolling")]
+    pub health_invoke: Option<String>,
+}
+
+/// Default maximum time for the new version of a component to pass its health probe during a
+/// rolling update, in milliseconds
+pub const DEFAULT_HEALTH_TIMEOUT_MS: u64 = 10_000;
+
+/// Health probe of the new version of a component during a rolling update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HealthProbe {
+    /// Send a `GET` request for `path` to the HTTP handler of the component, like the HTTP server
+    /// provider would
+    Http { path: String },
+    /// Invoke `function` of `instance` on the component
+    Invoke { instance: String, function: String },
+}
+
+impl fmt::Display for HealthProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http { path } => write!(f, "GET {path}"),
+            Self::Invoke { instance, function } => write!(f, "invoke {instance}.{function}"),
+        }
+    }
+}
+
+/// Outcome of a rolling update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollingUpdateDecision {
+    /// The new version passed its health probe and replaced the running component
+    Promoted,
+    /// The new version was removed, leaving the running component untouched
+    RolledBack,
+}
+
+/// Report of a rolling update, which is the JSON output of `wash update component --rolling`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingUpdateReport {
+    pub host_id: String,
+    pub component_id: String,
+    /// ID the new version was started under while it was probed
+    pub temporary_component_id: String,
+    pub old_component_ref: String,
+    pub new_component_ref: String,
+    pub probe: HealthProbe,
+    pub decision: RollingUpdateDecision,
+    /// Description of every phase of the update, in order
+    pub phases: Vec<String>,
+    /// Reason the update was rolled back
+    pub error: Option<String>,
 }
 
 pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<CommandOutput> {
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let client = wco.into_ctl_client(None).await?;
 
-    let inventory = if let Some(host_id) = cmd.host_id {
+    let (host_id, component) =
+        find_component(&client, cmd.host_id.as_deref(), &cmd.component_id).await?;
+    let component_ref = component.image_ref;
+
+    if component_ref == cmd.new_component_ref {
+        return Ok(CommandOutput::from_key_and_text(
+            "result",
+            format!(
+                "Component {} already updated to {} on host [{}]",
+                cmd.component_id, cmd.new_component_ref, host_id
+            ),
+        ));
+    }
+
+    let ack =
+        update_component(&client, &host_id, &cmd.component_id, &cmd.new_component_ref).await?;
+    if !ack.success {
+        bail!("Operation failed on host [{}]: {}", host_id, ack.message);
+    }
+
+    let message = match ack.message {
+        message if message.is_empty() => format!(
+            "component {} updating from {} to {}",
+            cmd.component_id, component_ref, cmd.new_component_ref
+        ),
+        message => message,
+    };
+
+    Ok(CommandOutput::from_key_and_text(
+        "result",
+        format!("Host [{}]: {}", host_id, message),
+    ))
+}
+
+/// Update a component with health gating, see [`UpdateComponentCommand::rolling`].
+///
+/// `run_probe` is called with the probe and the ID of the component to probe, and must succeed
+/// within the health timeout for the new version to replace the running component.
+pub async fn handle_rolling_update_component<F, Fut>(
+    cmd: UpdateComponentCommand,
+    run_probe: F,
+) -> Result<CommandOutput>
+where
+    F: FnOnce(HealthProbe, String) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let wco: WashConnectionOptions = cmd.opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+
+    let (host_id, component) =
+        find_component(&client, cmd.host_id.as_deref(), &cmd.component_id).await?;
+    if component.image_ref == cmd.new_component_ref {
+        return Ok(CommandOutput::from_key_and_text(
+            "result",
+            format!(
+                "Component {} already updated to {} on host [{}]",
+                cmd.component_id, cmd.new_component_ref, host_id
+            ),
+        ));
+    }
+
+    let links = client
+        .get_links()
+        .await
+        .map_err(boxed_err_to_anyhow)
+        .context("failed to query links")?
+        .response
+        .unwrap_or_default();
+    let probe = health_probe(
+        &cmd.component_id,
+        &links,
+        &cmd.health_path,
+        cmd.health_invoke.as_deref(),
+    )?;
+
+    let temporary_component_id = format!("{}_rolling", cmd.component_id);
+    let mut report = RollingUpdateReport {
+        host_id,
+        component_id: cmd.component_id,
+        temporary_component_id,
+        old_component_ref: component.image_ref,
+        new_component_ref: cmd.new_component_ref,
+        probe,
+        decision: RollingUpdateDecision::RolledBack,
+        phases: Vec::new(),
+        error: None,
+    };
+    // Links from the component are copied, so the new version can reach the same providers
+    let temporary_links = links
+        .into_iter()
+        .filter(|link| link.source_id == report.component_id)
+        .map(|link| InterfaceLinkDefinition {
+            source_id: report.temporary_component_id.clone(),
+            ..link
+        })
+        .collect::<Vec<_>>();
+
+    let probed = start_and_probe(
+        &client,
+        &mut report,
+        component.max_instances,
+        &temporary_links,
+        Duration::from_millis(cmd.health_timeout_ms),
+        run_probe,
+    )
+    .await;
+    remove_temporary_component(&client, &mut report, &temporary_links).await;
+
+    let promoted = match probed {
+        Ok(()) => update_component(
+            &client,
+            &report.host_id,
+            &report.component_id,
+            &report.new_component_ref,
+        )
+        .await
+        .and_then(|ack| {
+            if ack.success {
+                Ok(())
+            } else {
+                bail!(
+                    "Operation failed on host [{}]: {}",
+                    report.host_id,
+                    ack.message
+                )
+            }
+        })
+        .context("failed to update the running component"),
+        Err(e) => Err(e),
+    };
+    match promoted {
+        Ok(()) => {
+            report.decision = RollingUpdateDecision::Promoted;
+            report.phases.push(format!(
+                "Updated component {} from {} to {}",
+                report.component_id, report.old_component_ref, report.new_component_ref
+            ));
+        }
+        Err(e) => {
+            report.phases.push(format!(
+                "Rolled back, component {} still runs {}",
+                report.component_id, report.old_component_ref
+            ));
+            report.error = Some(format!("{e:#}"));
+        }
+    }
+
+    let mut text = report.phases.join("\n");
+    if let Some(error) = &report.error {
+        text.push_str(&format!("\nUpdate failed: {error}"));
+    }
+    let map: HashMap<String, serde_json::Value> = serde_json::from_value(
+        serde_json::to_value(&report).context("failed to serialize rolling update report")?,
+    )
+    .context("failed to convert rolling update report")?;
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Select the health probe of a rolling update of the component with ID `component_id`.
+///
+/// Invoking `health_invoke` takes precedence, otherwise the component must be linked to an HTTP
+/// server, which is probed by requesting `health_path`.
+fn health_probe(
+    component_id: &str,
+    links: &[InterfaceLinkDefinition],
+    health_path: &str,
+    health_invoke: Option<&str>,
+) -> Result<HealthProbe> {
+    if let Some(health_invoke) = health_invoke {
+        let Some((instance, function)) = health_invoke
+            .rsplit_once('.')
+            .filter(|(instance, function)| instance.contains('/') && !function.is_empty())
+        else {
+            bail!("Invalid health probe function [{health_invoke}], must be in the form of `namespace:package/interface.function`");
+        };
+        return Ok(HealthProbe::Invoke {
+            instance: instance.to_string(),
+            function: function.to_string(),
+        });
+    }
+    let serves_http = links.iter().any(|link| {
+        link.target == component_id
+            && link.wit_namespace == "wasi"
+            && link.wit_package == "http"
+            && link.interfaces.iter().any(|i| i == "incoming-handler")
+    });
+    if !serves_http {
+        bail!("Component [{component_id}] is not linked to an HTTP server, use --health-invoke to probe its health");
+    }
+    Ok(HealthProbe::Http {
+        path: health_path.to_string(),
+    })
+}
+
+/// Start the new version under the temporary ID with the links of the running component, and
+/// probe its health
+async fn start_and_probe<F, Fut>(
+    client: &CtlClient,
+    report: &mut RollingUpdateReport,
+    max_instances: u32,
+    temporary_links: &[InterfaceLinkDefinition],
+    health_timeout: Duration,
+    run_probe: F,
+) -> Result<()>
+where
+    F: FnOnce(HealthProbe, String) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    report.phases.push(format!(
+        "Starting {} as component {} on host [{}]",
+        report.new_component_ref, report.temporary_component_id, report.host_id
+    ));
+    scale_component(ScaleComponentArgs {
+        client,
+        host_id: &report.host_id,
+        component_id: &report.temporary_component_id,
+        component_ref: &report.new_component_ref,
+        max_instances,
+        annotations: None,
+        config: Vec::new(),
+        skip_wait: false,
+        timeout_ms: None,
+    })
+    .await
+    .context("new version failed to start")?;
+
+    for link in temporary_links {
+        report.phases.push(format!(
+            "Linking component {} to {} on {}:{}/{}",
+            link.source_id,
+            link.target,
+            link.wit_namespace,
+            link.wit_package,
+            link.interfaces.join(",")
+        ));
+        let ack = client
+            .put_link(link.clone())
+            .await
+            .map_err(boxed_err_to_anyhow)
+            .context("failed to link new version")?;
+        if !ack.success {
+            bail!("failed to link new version: {}", ack.message);
+        }
+    }
+
+    report.phases.push(format!(
+        "Probing health of component {} ({})",
+        report.temporary_component_id, report.probe
+    ));
+    tokio::time::timeout(
+        health_timeout,
+        run_probe(report.probe.clone(), report.temporary_component_id.clone()),
+    )
+    .await
+    .context("health probe timed out")?
+    .context("health probe failed")
+}
+
+/// Remove the links and the new version started under the temporary ID. Failures are recorded as
+/// phases, since they do not affect the running component
+async fn remove_temporary_component(
+    client: &CtlClient,
+    report: &mut RollingUpdateReport,
+    temporary_links: &[InterfaceLinkDefinition],
+) {
+    report.phases.push(format!(
+        "Removing component {}",
+        report.temporary_component_id
+    ));
+    for link in temporary_links {
+        if let Err(e) = client
+            .delete_link(
+                &link.source_id,
+                &link.name,
+                &link.wit_namespace,
+                &link.wit_package,
+            )
+            .await
+        {
+            report.phases.push(format!(
+                "Failed to delete link from {} to {}: {e}",
+                link.source_id, link.target
+            ));
+        }
+    }
+    if let Err(e) = scale_component(ScaleComponentArgs {
+        client,
+        host_id: &report.host_id,
+        component_id: &report.temporary_component_id,
+        component_ref: &report.new_component_ref,
+        max_instances: 0,
+        annotations: None,
+        config: Vec::new(),
+        skip_wait: true,
+        timeout_ms: None,
+    })
+    .await
+    {
+        report.phases.push(format!(
+            "Failed to stop component {}: {e:#}",
+            report.temporary_component_id
+        ));
+    }
+}
+
+/// Find the host running the component with ID `component_id`, on `host_id` if provided
+async fn find_component(
+    client: &CtlClient,
+    host_id: Option<&str>,
+    component_id: &str,
+) -> Result<(String, ComponentDescription)> {
+    let inventory = if let Some(host_id) = host_id {
         client
-            .get_host_inventory(&host_id)
+            .get_host_inventory(host_id)
             .await
             .map(|inventory| inventory.response)
             .map_err(boxed_err_to_anyhow)?
@@ -54,20 +450,20 @@ pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<Comm
                 host_id
             ))?
     } else {
-        let mut inventories = get_all_inventories(&client)
+        let mut inventories = get_all_inventories(client)
             .await?
             .into_iter()
             .filter(|inv| {
                 inv.components
                     .iter()
-                    .any(|component| component.id == cmd.component_id)
+                    .any(|component| component.id == component_id)
             })
             .collect::<Vec<HostInventory>>();
 
         match inventories[..] {
             // No hosts
             [] => {
-                bail!("No host found running component [{}]", cmd.component_id)
+                bail!("No host found running component [{}]", component_id)
             }
             // Single host
             [_] => inventories.remove(0),
@@ -75,7 +471,7 @@ pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<Comm
             _ => {
                 bail!(
                     "Component [{}] cannot be updated because multiple hosts are running it: [{}]",
-                    cmd.component_id,
+                    component_id,
                     inventories
                         .iter()
                         .map(|h| h.host_id.to_string())
@@ -86,45 +482,70 @@ pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<Comm
         }
     };
 
-    let Some((host_id, component_ref)) = inventory
+    let Some(component) = inventory
         .components
-        .iter()
-        .find(|component| component.id == cmd.component_id)
-        .map(|component| (inventory.host_id.clone(), component.image_ref.clone()))
+        .into_iter()
+        .find(|component| component.id == component_id)
     else {
         bail!(
             "Component {} not found on host [{}]",
-            cmd.component_id,
+            component_id,
             inventory.host_id,
         );
     };
 
-    if component_ref == cmd.new_component_ref {
-        return Ok(CommandOutput::from_key_and_text(
-            "result",
-            format!(
-                "Component {} already updated to {} on host [{}]",
-                cmd.component_id, cmd.new_component_ref, host_id
-            ),
-        ));
-    }
+    Ok((inventory.host_id, component))
+}
 
-    let ack =
-        update_component(&client, &host_id, &cmd.component_id, &cmd.new_component_ref).await?;
-    if !ack.success {
-        bail!("Operation failed on host [{}]: {}", host_id, ack.message);
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    let message = match ack.message {
-        message if message.is_empty() => format!(
-            "component {} updating from {} to {}",
-            cmd.component_id, component_ref, cmd.new_component_ref
-        ),
-        message => message,
-    };
+    fn link(
+        source_id: &str,
+        target: &str,
+        namespace: &str,
+        package: &str,
+    ) -> InterfaceLinkDefinition {
+        InterfaceLinkDefinition {
+            source_id: source_id.into(),
+            target: target.into(),
+            name: "default".into(),
+            wit_namespace: namespace.into(),
+            wit_package: package.into(),
+            interfaces: vec!["incoming-handler".into()],
+            ..Default::default()
+        }
+    }
 
-    Ok(CommandOutput::from_key_and_text(
-        "result",
-        format!("Host [{}]: {}", host_id, message),
-    ))
+    #[test]
+    fn test_health_probe() {
+        let links = vec![
+            link("http_server", "hello", "wasi", "http"),
+            link("hello", "keyvalue", "wasi", "keyvalue"),
+        ];
+        assert_eq!(
+            health_probe("hello", &links, "/healthz", None).unwrap(),
+            HealthProbe::Http {
+                path: "/healthz".into()
+            }
+        );
+        assert_eq!(
+            health_probe(
+                "hello",
+                &links,
+                "/",
+                Some("wasmcloud:example/health@0.1.0.check")
+            )
+            .unwrap(),
+            HealthProbe::Invoke {
+                instance: "wasmcloud:example/health@0.1.0".into(),
+                function: "check".into(),
+            }
+        );
+        // Components which are not served over HTTP require an explicit function to invoke
+        assert!(health_probe("keyvalue", &links, "/", None).is_err());
+        assert!(health_probe("hello", &links, "/", Some("check")).is_err());
+        assert!(health_probe("hello", &links, "/", Some("wasi:cli/run.")).is_err());
+    }
 }
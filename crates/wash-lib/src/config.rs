@@ -1,4 +1,7 @@
 //! Common config constants and functions for loading, finding, and consuming configuration data
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::{fs, path::PathBuf};
 
 use anyhow::{Context, Result};
@@ -16,11 +19,63 @@ pub const DEFAULT_NATS_HOST: &str = "127.0.0.1";
 pub const DEFAULT_NATS_PORT: &str = "4222";
 pub const DEFAULT_LATTICE: &str = "default";
 pub const DEFAULT_NATS_TIMEOUT_MS: u64 = 2_000;
+pub const DEFAULT_CTL_RETRIES: u32 = 3;
 pub const DEFAULT_START_COMPONENT_TIMEOUT_MS: u64 = 5_000;
 pub const DEFAULT_COMPONENT_OPERATION_TIMEOUT_MS: u64 = 5_000;
 pub const DEFAULT_START_PROVIDER_TIMEOUT_MS: u64 = 60_000;
 pub const DEFAULT_CTX_DIR_NAME: &str = "contexts";
 
+/// Counter of the most attempts any control interface request made by this process needed
+static CTL_ATTEMPTS: OnceLock<Arc<AtomicU32>> = OnceLock::new();
+
+/// Most attempts any control interface request made by a client created with
+/// [`WashConnectionOptions::into_ctl_client`] needed, 0 if no request was made
+pub fn ctl_attempts() -> u32 {
+    CTL_ATTEMPTS
+        .get()
+        .map_or(0, |attempts| attempts.load(Ordering::Relaxed))
+}
+
+/// Strip the brackets of a bracketed IPv6 literal, e.g. `[::1]`
+#[must_use]
+pub fn unbracket_host(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+/// Join `host` and `port` into an address, bracketing IPv6 literals, e.g. `[::1]:4222`
+#[must_use]
+pub fn host_port(host: &str, port: impl std::fmt::Display) -> String {
+    let host = unbracket_host(host);
+    if host.parse::<Ipv6Addr>().is_ok() {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+/// Host to connect to a server listening on `listen`. Servers listening on all interfaces are
+/// reached on the loopback address of the same IP version
+#[must_use]
+pub fn connect_host(listen: &str) -> String {
+    match unbracket_host(listen).parse() {
+        Ok(IpAddr::V4(ip)) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.to_string(),
+        Ok(IpAddr::V6(ip)) if ip.is_unspecified() => Ipv6Addr::LOCALHOST.to_string(),
+        _ => unbracket_host(listen).to_string(),
+    }
+}
+
+/// Parse an IPv4 or IPv6 address to listen on, accepting bracketed IPv6 literals, e.g. `[::1]`
+pub fn parse_listen_addr(addr: &str) -> Result<String> {
+    unbracket_host(addr)
+        .parse::<IpAddr>()
+        .map(|ip| ip.to_string())
+        .with_context(|| {
+            format!("invalid listen address `{addr}`, expected an IPv4 or IPv6 address")
+        })
+}
+
 /// Get the path to the `.wash` configuration directory. Creates the directory if it does not exist.
 pub fn cfg_dir() -> Result<PathBuf> {
     let home = dirs::home_dir().context("no home directory found. Please set $HOME")?;
@@ -71,6 +126,10 @@ pub struct WashConnectionOptions {
     /// Timeout length to await a control interface response, defaults to 2000 milliseconds
     pub timeout_ms: u64,
 
+    /// Number of times control interface requests are retried when no host responds to them or
+    /// they time out
+    pub retries: u32,
+
     /// Wash context
     pub ctx: WashContext,
 }
@@ -109,7 +168,9 @@ impl WashConnectionOptions {
         let mut builder = CtlClientBuilder::new(nc)
             .lattice(lattice)
             .timeout(tokio::time::Duration::from_millis(self.timeout_ms))
-            .auction_timeout(tokio::time::Duration::from_millis(auction_timeout_ms));
+            .auction_timeout(tokio::time::Duration::from_millis(auction_timeout_ms))
+            .retries(self.retries)
+            .attempts(Arc::clone(CTL_ATTEMPTS.get_or_init(Arc::default)));
 
         if let Ok(topic_prefix) = std::env::var("WASMCLOUD_CTL_TOPIC_PREFIX") {
             builder = builder.topic_prefix(topic_prefix);
@@ -122,6 +183,15 @@ impl WashConnectionOptions {
 
     /// Create a NATS client from `WashConnectionOptions`
     pub async fn into_nats_client(self) -> Result<Client> {
+        self.into_nats_client_with(|opts| opts).await
+    }
+
+    /// Create a NATS client from `WashConnectionOptions`, applying `configure` to the options of
+    /// the connection first
+    pub async fn into_nats_client_with(
+        self,
+        configure: impl FnOnce(async_nats::ConnectOptions) -> async_nats::ConnectOptions,
+    ) -> Result<Client> {
         let ctl_host = self.ctl_host.unwrap_or_else(|| self.ctx.ctl_host.clone());
         let ctl_port = self
             .ctl_port
@@ -135,13 +205,14 @@ impl WashConnectionOptions {
             .ctl_tls_ca_file
             .or_else(|| self.ctx.ctl_tls_ca_file.clone());
 
-        let nc = create_nats_client_from_opts(
+        let nc = connect_nats(
             &ctl_host,
             &ctl_port,
             ctl_jwt,
             ctl_seed,
             ctl_credsfile,
             ctl_tls_ca_file,
+            configure,
         )
         .await?;
 
@@ -158,7 +229,7 @@ impl WashConnectionOptions {
 }
 
 /// Reads the content of a string if it is a valid file path, otherwise returning the string
-async fn extract_arg_value(arg: &str) -> Result<String> {
+pub(crate) async fn extract_arg_value(arg: &str) -> Result<String> {
     match tokio::fs::File::open(arg).await {
         Ok(mut f) => {
             let mut value = String::new();
@@ -180,10 +251,55 @@ pub async fn create_nats_client_from_opts(
     credsfile: Option<PathBuf>,
     tls_ca_file: Option<PathBuf>,
 ) -> Result<async_nats::Client> {
-    let nats_url = format!("{host}:{port}");
+    connect_nats(host, port, jwt, seed, credsfile, tls_ca_file, |opts| opts).await
+}
+
+/// Create a NATS client from NATS-related options, applying `configure` to the options of the
+/// connection first
+async fn connect_nats(
+    host: &str,
+    port: &str,
+    jwt: Option<String>,
+    seed: Option<String>,
+    credsfile: Option<PathBuf>,
+    tls_ca_file: Option<PathBuf>,
+    configure: impl FnOnce(async_nats::ConnectOptions) -> async_nats::ConnectOptions,
+) -> Result<async_nats::Client> {
+    let nats_url = host_port(host, port);
+    let has_jwt = jwt.is_some();
+    let opts = configure(nats_connect_options(jwt, seed, credsfile.clone(), tls_ca_file).await?);
+
+    let nc = if has_jwt {
+        opts.connect(&nats_url).await.with_context(|| {
+            format!(
+                "Failed to connect to NATS server {}:{} while creating client",
+                &host, &port
+            )
+        })?
+    } else if let Some(credsfile_path) = credsfile {
+        opts.connect(&nats_url).await.with_context(|| {
+            format!(
+                "Failed to connect to NATS {} with credentials file {:?}",
+                &nats_url, &credsfile_path
+            )
+        })?
+    } else {
+        opts.connect(&nats_url).await.with_context(|| format!("Failed to connect to NATS {}\nNo credentials file was provided, you may need one to connect.", &nats_url))?
+    };
+    Ok(nc)
+}
+
+/// Options to connect to NATS with the given credentials, a JWT taking precedence over a
+/// credentials file
+async fn nats_connect_options(
+    jwt: Option<String>,
+    seed: Option<String>,
+    credsfile: Option<PathBuf>,
+    tls_ca_file: Option<PathBuf>,
+) -> Result<async_nats::ConnectOptions> {
     use async_nats::ConnectOptions;
 
-    let nc = if let Some(jwt_file) = jwt {
+    let mut opts = if let Some(jwt_file) = jwt {
         let jwt_contents = extract_arg_value(&jwt_file)
             .await
             .with_context(|| format!("Failed to extract jwt contents from {}", &jwt_file))?;
@@ -199,49 +315,56 @@ pub async fn create_nats_client_from_opts(
         });
 
         // You must provide the JWT via a closure
-        let mut opts = async_nats::ConnectOptions::with_jwt(jwt_contents, move |nonce| {
+        ConnectOptions::with_jwt(jwt_contents, move |nonce| {
             let key_pair = kp.clone();
             async move { key_pair.sign(&nonce).map_err(async_nats::AuthError::new) }
-        });
-
-        if let Some(ca_file) = tls_ca_file {
-            opts = opts.add_root_certificates(ca_file).require_tls(true);
-        }
-
-        opts.connect(&nats_url).await.with_context(|| {
-            format!(
-                "Failed to connect to NATS server {}:{} while creating client",
-                &host, &port
-            )
-        })?
+        })
     } else if let Some(credsfile_path) = credsfile {
-        let mut opts = ConnectOptions::with_credentials_file(credsfile_path.clone())
+        ConnectOptions::with_credentials_file(credsfile_path.clone())
             .await
             .with_context(|| {
                 format!(
                     "Failed to authenticate to NATS with credentials file {:?}",
                     &credsfile_path
                 )
-            })?;
+            })?
+    } else {
+        ConnectOptions::new()
+    };
 
-        if let Some(ca_file) = tls_ca_file {
-            opts = opts.add_root_certificates(ca_file).require_tls(true);
-        }
+    if let Some(ca_file) = tls_ca_file {
+        opts = opts.add_root_certificates(ca_file).require_tls(true);
+    }
+    Ok(opts)
+}
 
-        opts.connect(&nats_url).await.with_context(|| {
-            format!(
-                "Failed to connect to NATS {} with credentials file {:?}",
-                &nats_url, &credsfile_path
-            )
-        })?
-    } else {
-        let mut opts = ConnectOptions::new();
+#[cfg(test)]
+mod test {
+    use super::*;
 
-        if let Some(ca_file) = tls_ca_file {
-            opts = opts.add_root_certificates(ca_file).require_tls(true);
-        }
+    #[test]
+    fn test_host_port() {
+        assert_eq!(host_port("127.0.0.1", 4222), "127.0.0.1:4222");
+        assert_eq!(host_port("nats.internal", "4222"), "nats.internal:4222");
+        assert_eq!(host_port("::1", 4222), "[::1]:4222");
+        assert_eq!(host_port("[fd00::5]", 4222), "[fd00::5]:4222");
+    }
 
-        opts.connect(&nats_url).await.with_context(|| format!("Failed to connect to NATS {}\nNo credentials file was provided, you may need one to connect.", &nats_url))?
-    };
-    Ok(nc)
+    #[test]
+    fn test_connect_host() {
+        assert_eq!(connect_host("0.0.0.0"), "127.0.0.1");
+        assert_eq!(connect_host("::"), "::1");
+        assert_eq!(connect_host("[::]"), "::1");
+        assert_eq!(connect_host("10.0.0.5"), "10.0.0.5");
+        assert_eq!(connect_host("[fd00::5]"), "fd00::5");
+    }
+
+    #[test]
+    fn test_parse_listen_addr() {
+        assert_eq!(parse_listen_addr("0.0.0.0").unwrap(), "0.0.0.0");
+        assert_eq!(parse_listen_addr("[::1]").unwrap(), "::1");
+        assert_eq!(parse_listen_addr("::").unwrap(), "::");
+        assert!(parse_listen_addr("localhost").is_err());
+        assert!(parse_listen_addr("[127.0.0.1").is_err());
+    }
 }
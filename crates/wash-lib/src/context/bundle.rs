@@ -0,0 +1,264 @@
+//! Bundles of a wash context, shareable as a single file to onboard someone to a lattice
+//!
+//! A bundle holds a context, including the configuration of its registries, and the versions of
+//! wasmCloud and wadm the lattice runs. The seeds and JWTs of the context are either left out of
+//! the bundle or sealed with a passphrase, read from [`BUNDLE_PASSPHRASE_ENV`] or prompted for.
+//! Bundles are versioned with [`BUNDLE_FORMAT_VERSION`], and bundles of a newer format are
+//! rejected rather than partially imported.
+
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::encryption::{new_salt, open_fields, passphrase_key, seal_fields, SensitiveFields};
+use super::WashContext;
+
+/// Version of the bundle format written by this version of wash
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Environment variable holding the passphrase sealing the secrets of a bundle
+pub const BUNDLE_PASSPHRASE_ENV: &str = "WASH_BUNDLE_PASSPHRASE";
+
+/// Versions of the lattice components the context is used with
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PinnedVersions {
+    /// Version of the wasmCloud host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasmcloud: Option<String>,
+    /// Version of wadm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wadm: Option<String>,
+}
+
+/// Seeds and JWTs of a bundled context, sealed with AES-256-GCM and a key derived from the
+/// passphrase of the bundle
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SealedSecrets {
+    /// Base64-encoded salt the key is derived with
+    pub salt: String,
+    /// Base64-encoded nonce
+    pub nonce: String,
+    /// Base64-encoded ciphertext of the secrets, followed by the authentication tag
+    pub ciphertext: String,
+}
+
+/// A context bundled with the versions of the lattice components it is used with, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContextBundle {
+    /// Version of the bundle format, see [`BUNDLE_FORMAT_VERSION`]
+    pub format_version: u32,
+    /// The context, without its seeds and JWTs
+    pub context: WashContext,
+    /// Versions of the lattice components the context is used with
+    #[serde(default)]
+    pub versions: PinnedVersions,
+    /// Seeds and JWTs of the context, if they were sealed into the bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SealedSecrets>,
+}
+
+impl ContextBundle {
+    /// Bundle `ctx` with `versions`. The seeds and JWTs of the context are sealed with
+    /// `passphrase` if set, and left out of the bundle otherwise
+    pub fn new(
+        ctx: &WashContext,
+        versions: PinnedVersions,
+        passphrase: Option<&str>,
+    ) -> Result<Self> {
+        let mut context = ctx.clone();
+        // The key of an encrypted context is local to the machine it was encrypted on
+        context.sealed = None;
+        let fields = SensitiveFields::take(&mut context);
+        let secrets = match passphrase {
+            Some(passphrase) if !fields.is_empty() => {
+                if passphrase.is_empty() {
+                    bail!("bundle passphrase cannot be empty");
+                }
+                let salt = new_salt()?;
+                let (nonce, ciphertext) =
+                    seal_fields(&passphrase_key(&salt, passphrase)?, &fields)?;
+                Some(SealedSecrets {
+                    salt,
+                    nonce,
+                    ciphertext,
+                })
+            }
+            _ => None,
+        };
+        Ok(Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            context,
+            versions,
+            secrets,
+        })
+    }
+
+    /// Parse a bundle, rejecting bundles of a newer format than [`BUNDLE_FORMAT_VERSION`]
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Header {
+            format_version: u32,
+        }
+
+        let Header { format_version } =
+            serde_json::from_slice(data).context("file is not a wash context bundle")?;
+        if format_version > BUNDLE_FORMAT_VERSION {
+            bail!(
+                "bundle format version {format_version} is not supported by this version of wash, which supports versions up to {BUNDLE_FORMAT_VERSION}. Upgrade wash to import this bundle"
+            );
+        }
+        serde_json::from_slice(data).context("failed to parse wash context bundle")
+    }
+
+    /// Serialize the bundle
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self).context("failed to serialize wash context bundle")
+    }
+
+    /// Whether the seeds and JWTs of the context were sealed into the bundle
+    #[must_use]
+    pub fn has_secrets(&self) -> bool {
+        self.secrets.is_some()
+    }
+
+    /// Names of the environment variables the context reads registry credentials from which are
+    /// not set
+    #[must_use]
+    pub fn missing_env(&self) -> BTreeSet<String> {
+        self.context
+            .registries
+            .values()
+            .filter_map(|registry| registry.password_env.as_ref())
+            .filter(|env| std::env::var_os(env).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the bundled context, with its seeds and JWTs unsealed with `passphrase` if they
+    /// were sealed into the bundle
+    pub fn into_context(self, passphrase: Option<&str>) -> Result<WashContext> {
+        let mut context = self.context;
+        if let Some(secrets) = self.secrets {
+            let passphrase = passphrase
+                .context("bundle secrets are sealed, a passphrase is required to import them")?;
+            let fields = open_fields(
+                &passphrase_key(&secrets.salt, passphrase)?,
+                &secrets.nonce,
+                &secrets.ciphertext,
+            )?
+            .context("failed to unseal bundle secrets, the passphrase is incorrect")?;
+            fields.restore(&mut context);
+        }
+        Ok(context)
+    }
+}
+
+/// Passphrase sealing the secrets of a bundle, read from [`BUNDLE_PASSPHRASE_ENV`] or prompted
+/// for. The passphrase is confirmed when prompted for to seal a bundle
+#[cfg_attr(not(feature = "cli"), allow(unused_variables))]
+pub fn bundle_passphrase(confirm: bool) -> Result<String> {
+    let passphrase = match std::env::var(BUNDLE_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        #[cfg(feature = "cli")]
+        Err(_) if console::user_attended_stderr() => {
+            let mut prompt =
+                dialoguer::Password::new().with_prompt("Passphrase of the context bundle");
+            if confirm {
+                prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+            }
+            prompt.interact().context("failed to read passphrase")?
+        }
+        Err(_) => bail!("set {BUNDLE_PASSPHRASE_ENV} to the passphrase of the context bundle"),
+    };
+    if passphrase.is_empty() {
+        bail!("bundle passphrase cannot be empty");
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::registry::RegistryConfig;
+
+    fn context() -> WashContext {
+        WashContext {
+            ctl_host: "nats.example.com".to_string(),
+            ctl_port: 4223,
+            ctl_jwt: Some("jwt".to_string()),
+            ctl_seed: Some("seed".to_string()),
+            lattice: "team-dev".to_string(),
+            js_domain: Some("hub".to_string()),
+            registries: HashMap::from([(
+                "ghcr.io".to_string(),
+                RegistryConfig {
+                    mirror: Some("mirror.example.com/ghcr".to_string()),
+                    username: Some("dev".to_string()),
+                    password_env: Some("WASH_BUNDLE_TEST_UNSET_PASSWORD".to_string()),
+                    ..Default::default()
+                },
+            )]),
+            ..WashContext::named("team-dev".to_string())
+        }
+    }
+
+    /// Fields of the context other than its secrets, as serialized
+    fn non_secret_fields(ctx: &WashContext) -> serde_json::Value {
+        let mut ctx = ctx.clone();
+        SensitiveFields::take(&mut ctx);
+        serde_json::to_value(ctx).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_sealed() {
+        let versions = PinnedVersions {
+            wasmcloud: Some("v1.0.4".to_string()),
+            wadm: Some("v0.12.1".to_string()),
+        };
+        let bundle = ContextBundle::new(&context(), versions.clone(), Some("passphrase")).unwrap();
+        let data = bundle.to_vec().unwrap();
+        let serialized = String::from_utf8(data.clone()).unwrap();
+        assert!(!serialized.contains("\"jwt\"") && !serialized.contains("\"seed\""));
+
+        let bundle = ContextBundle::from_slice(&data).unwrap();
+        assert!(bundle.has_secrets());
+        assert_eq!(bundle.versions, versions);
+        assert_eq!(
+            bundle.missing_env(),
+            BTreeSet::from(["WASH_BUNDLE_TEST_UNSET_PASSWORD".to_string()])
+        );
+        assert!(bundle.clone().into_context(None).is_err());
+        assert!(bundle.clone().into_context(Some("wrong")).is_err());
+        let imported = bundle.into_context(Some("passphrase")).unwrap();
+        assert_eq!(non_secret_fields(&imported), non_secret_fields(&context()));
+        assert_eq!(imported.ctl_jwt.as_deref(), Some("jwt"));
+        assert_eq!(imported.ctl_seed.as_deref(), Some("seed"));
+    }
+
+    #[test]
+    fn test_round_trip_without_secrets() {
+        let bundle = ContextBundle::new(&context(), PinnedVersions::default(), None).unwrap();
+        let bundle = ContextBundle::from_slice(&bundle.to_vec().unwrap()).unwrap();
+        assert!(!bundle.has_secrets());
+        let imported = bundle.into_context(None).unwrap();
+        assert_eq!(non_secret_fields(&imported), non_secret_fields(&context()));
+        assert_eq!(imported.ctl_jwt, None);
+        assert_eq!(imported.ctl_seed, None);
+    }
+
+    #[test]
+    fn test_reject_future_version() {
+        let mut bundle: serde_json::Value = serde_json::to_value(
+            ContextBundle::new(&context(), PinnedVersions::default(), None).unwrap(),
+        )
+        .unwrap();
+        bundle["format_version"] = (BUNDLE_FORMAT_VERSION + 1).into();
+        let err = ContextBundle::from_slice(&serde_json::to_vec(&bundle).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Upgrade wash"), "{err}");
+        assert!(ContextBundle::from_slice(b"{\"name\":\"context\"}").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Encryption at rest of the sensitive fields of wash contexts
+//!
+//! Contexts encrypted with `wash ctx encrypt` store their seeds and JWTs sealed with AES-256-GCM in
+//! [`SealedFields`], while the other fields remain in plaintext so that context files can still be
+//! read and diffed. The encryption key is either:
+//! - a random key stored in the OS keychain (macOS Keychain, Secret Service or Windows Credential
+//!   Manager), with the `cli` feature
+//! - derived from a passphrase, read from [`PASSPHRASE_ENV`] or prompted for once per process
+//!
+//! Contexts are transparently decrypted when loaded, and sealed again with the same key when saved.
+
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2::{self, PBKDF2_HMAC_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::id::ClusterSeed;
+
+use super::WashContext;
+
+/// Environment variable holding the passphrase of contexts encrypted with a passphrase
+pub const PASSPHRASE_ENV: &str = "WASH_CTX_PASSPHRASE";
+
+#[cfg(feature = "cli")]
+const KEYCHAIN_SERVICE: &str = "wash";
+#[cfg(feature = "cli")]
+const KEYCHAIN_USER: &str = "context-encryption-key";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Passphrase entered by the user, so that it is only prompted for once per process
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keys derived from the passphrase, by salt, so that they are only derived once per process
+static DERIVED_KEYS: Mutex<Vec<(String, [u8; KEY_LEN])>> = Mutex::new(Vec::new());
+
+/// Source of the key sealing the sensitive fields of a context
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeySource {
+    /// Random key stored in the OS keychain
+    Keychain,
+    /// Key derived from a passphrase with PBKDF2-HMAC-SHA256 and the base64-encoded `salt`
+    Passphrase { salt: String },
+}
+
+/// Sensitive fields of a context, sealed with AES-256-GCM
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SealedFields {
+    /// Source of the key the fields are sealed with
+    pub key: KeySource,
+    /// Base64-encoded nonce
+    pub nonce: String,
+    /// Base64-encoded ciphertext of the fields, followed by the authentication tag
+    pub ciphertext: String,
+}
+
+/// Fields of a context which are sealed when the context is encrypted
+#[derive(Default, Deserialize, Serialize)]
+pub(super) struct SensitiveFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cluster_seed: Option<ClusterSeed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ctl_jwt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ctl_seed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rpc_jwt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rpc_seed: Option<String>,
+}
+
+impl SensitiveFields {
+    /// Move the sensitive fields out of `ctx`
+    pub(super) fn take(ctx: &mut WashContext) -> Self {
+        Self {
+            cluster_seed: ctx.cluster_seed.take(),
+            ctl_jwt: ctx.ctl_jwt.take(),
+            ctl_seed: ctx.ctl_seed.take(),
+            rpc_jwt: ctx.rpc_jwt.take(),
+            rpc_seed: ctx.rpc_seed.take(),
+        }
+    }
+
+    /// Whether none of the sensitive fields are set
+    pub(super) fn is_empty(&self) -> bool {
+        self.cluster_seed.is_none()
+            && self.ctl_jwt.is_none()
+            && self.ctl_seed.is_none()
+            && self.rpc_jwt.is_none()
+            && self.rpc_seed.is_none()
+    }
+
+    /// Move the sensitive fields into `ctx`
+    pub(super) fn restore(self, ctx: &mut WashContext) {
+        ctx.cluster_seed = self.cluster_seed;
+        ctx.ctl_jwt = self.ctl_jwt;
+        ctx.ctl_seed = self.ctl_seed;
+        ctx.rpc_jwt = self.rpc_jwt;
+        ctx.rpc_seed = self.rpc_seed;
+    }
+}
+
+impl KeySource {
+    /// Create a key source deriving the key from a passphrase, with a new random salt
+    pub fn new_passphrase() -> Result<Self> {
+        Ok(Self::Passphrase { salt: new_salt()? })
+    }
+
+    /// Retrieve the key, creating it in the keychain if it doesn't exist and `create` is set
+    fn key(&self, create: bool) -> Result<LessSafeKey> {
+        let mut key = [0; KEY_LEN];
+        match self {
+            #[cfg(feature = "cli")]
+            Self::Keychain => {
+                let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+                    .context("failed to access the OS keychain")?;
+                match entry.get_password() {
+                    Ok(encoded) => {
+                        let decoded = BASE64
+                            .decode(encoded)
+                            .context("invalid context encryption key in the OS keychain")?;
+                        if decoded.len() != KEY_LEN {
+                            bail!("invalid context encryption key in the OS keychain");
+                        }
+                        key.copy_from_slice(&decoded);
+                    }
+                    Err(keyring::Error::NoEntry) if create => {
+                        SystemRandom::new()
+                            .fill(&mut key)
+                            .map_err(|_| anyhow!("failed to generate context encryption key"))?;
+                        entry
+                            .set_password(&BASE64.encode(key))
+                            .context("failed to store context encryption key in the OS keychain")?;
+                    }
+                    Err(e) => {
+                        return Err(e)
+                            .context("failed to read context encryption key from the OS keychain")
+                    }
+                }
+            }
+            #[cfg(not(feature = "cli"))]
+            Self::Keychain => {
+                let _ = create;
+                bail!("the OS keychain is only supported with the `cli` feature of wash-lib")
+            }
+            Self::Passphrase { salt } => {
+                let mut derived = DERIVED_KEYS
+                    .lock()
+                    .map_err(|_| anyhow!("derived keys lock poisoned"))?;
+                if let Some((_, derived_key)) = derived.iter().find(|(s, _)| s == salt) {
+                    key = *derived_key;
+                } else {
+                    key = derive_key(salt, &passphrase()?)?;
+                    derived.push((salt.clone(), key));
+                }
+            }
+        }
+        let key = UnboundKey::new(&AES_256_GCM, &key)
+            .map_err(|_| anyhow!("invalid context encryption key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+/// Generate a new random, base64-encoded salt
+pub(super) fn new_salt() -> Result<String> {
+    let mut salt = [0; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("failed to generate salt"))?;
+    Ok(BASE64.encode(salt))
+}
+
+/// Derive a key from `passphrase` with PBKDF2-HMAC-SHA256 and the base64-encoded `salt`
+fn derive_key(salt: &str, passphrase: &str) -> Result<[u8; KEY_LEN]> {
+    let decoded_salt = BASE64.decode(salt).context("invalid passphrase salt")?;
+    let mut key = [0; KEY_LEN];
+    pbkdf2::derive(
+        PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations are not zero"),
+        &decoded_salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    Ok(key)
+}
+
+/// Key derived from `passphrase` and the base64-encoded `salt`, see [`derive_key`]
+pub(super) fn passphrase_key(salt: &str, passphrase: &str) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, &derive_key(salt, passphrase)?)
+        .map_err(|_| anyhow!("invalid passphrase key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Seal `fields` with `key`, returning the base64-encoded nonce and ciphertext
+pub(super) fn seal_fields(key: &LessSafeKey, fields: &SensitiveFields) -> Result<(String, String)> {
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("failed to generate nonce"))?;
+    let mut data = serde_json::to_vec(fields).context("failed to serialize context fields")?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow!("failed to encrypt context fields"))?;
+    Ok((BASE64.encode(nonce), BASE64.encode(data)))
+}
+
+/// Open the fields sealed with `key` from the base64-encoded `nonce` and `ciphertext`, returning
+/// `None` if the key is incorrect or the ciphertext was tampered with
+pub(super) fn open_fields(
+    key: &LessSafeKey,
+    nonce: &str,
+    ciphertext: &str,
+) -> Result<Option<SensitiveFields>> {
+    let nonce: [u8; NONCE_LEN] = BASE64
+        .decode(nonce)
+        .ok()
+        .and_then(|nonce| nonce.try_into().ok())
+        .context("invalid context nonce")?;
+    let mut data = BASE64
+        .decode(ciphertext)
+        .context("invalid context ciphertext")?;
+    let Ok(plaintext) =
+        key.open_in_place(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+    else {
+        return Ok(None);
+    };
+    serde_json::from_slice(plaintext)
+        .map(Some)
+        .context("failed to parse decrypted context")
+}
+
+/// Whether the OS keychain can be used to store the context encryption key
+#[must_use]
+#[cfg(feature = "cli")]
+pub fn keychain_available() -> bool {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+        .is_ok_and(|entry| matches!(entry.get_password(), Ok(_) | Err(keyring::Error::NoEntry)))
+}
+
+/// Whether the OS keychain can be used to store the context encryption key, which requires the
+/// `cli` feature
+#[must_use]
+#[cfg(not(feature = "cli"))]
+pub fn keychain_available() -> bool {
+    false
+}
+
+/// Passphrase of contexts encrypted with a passphrase, read from [`PASSPHRASE_ENV`] or prompted for
+fn passphrase() -> Result<String> {
+    let mut cached = PASSPHRASE
+        .lock()
+        .map_err(|_| anyhow!("passphrase lock poisoned"))?;
+    if let Some(passphrase) = cached.as_ref() {
+        return Ok(passphrase.clone());
+    }
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        #[cfg(feature = "cli")]
+        Err(_) if console::user_attended_stderr() => dialoguer::Password::new()
+            .with_prompt("Passphrase of the encrypted wash context")
+            .interact()
+            .context("failed to read passphrase")?,
+        Err(_) => {
+            bail!("context is encrypted with a passphrase, set {PASSPHRASE_ENV} to decrypt it")
+        }
+    };
+    if passphrase.is_empty() {
+        bail!("context passphrase cannot be empty");
+    }
+    *cached = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+impl WashContext {
+    /// Whether the sensitive fields of the context are encrypted
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.sealed.is_some()
+    }
+
+    /// Encrypt the context with the key from `key_source`, creating the key in the OS keychain if
+    /// needed. The sensitive fields are sealed when the context is saved
+    pub fn encrypt(&mut self, key_source: KeySource) -> Result<()> {
+        let mut stored = self.clone();
+        stored.seal(key_source)?;
+        self.sealed = stored.sealed;
+        Ok(())
+    }
+
+    /// Seal the sensitive fields of the context with the key from `key_source`, clearing them
+    fn seal(&mut self, key_source: KeySource) -> Result<()> {
+        let key = key_source.key(true)?;
+        let (nonce, ciphertext) = seal_fields(&key, &SensitiveFields::take(self))?;
+        self.sealed = Some(SealedFields {
+            key: key_source,
+            nonce,
+            ciphertext,
+        });
+        Ok(())
+    }
+
+    /// Decrypt the sealed fields of the context into the sensitive fields. The sealed fields are
+    /// kept, so that the context is sealed again with the same key when saved
+    pub fn unseal(&mut self) -> Result<()> {
+        let Some(sealed) = &self.sealed else {
+            return Ok(());
+        };
+        let key = sealed.key.key(false)?;
+        let fields = open_fields(&key, &sealed.nonce, &sealed.ciphertext)?.with_context(|| {
+            format!(
+                "failed to decrypt context `{}`, the key or passphrase is incorrect",
+                self.name
+            )
+        })?;
+        fields.restore(self);
+        Ok(())
+    }
+
+    /// Returns a copy of the context as stored on disk, with its sensitive fields sealed again if
+    /// the context is encrypted
+    pub fn to_stored(&self) -> Result<Self> {
+        let mut stored = self.clone();
+        if let Some(sealed) = stored.sealed.take() {
+            stored.seal(sealed.key)?;
+        }
+        Ok(stored)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seal_unseal_passphrase() {
+        std::env::set_var(PASSPHRASE_ENV, "correct horse battery staple");
+        let mut ctx = WashContext {
+            name: "sealed".to_string(),
+            ctl_jwt: Some("jwt".to_string()),
+            ctl_seed: Some("seed".to_string()),
+            lattice: "plain".to_string(),
+            ..Default::default()
+        };
+
+        ctx.encrypt(KeySource::new_passphrase().unwrap()).unwrap();
+        assert!(ctx.is_encrypted());
+        assert_eq!(ctx.ctl_jwt.as_deref(), Some("jwt"));
+        let sealed = ctx.to_stored().unwrap();
+        assert_eq!(sealed.ctl_jwt, None);
+        assert_eq!(sealed.ctl_seed, None);
+        assert_eq!(sealed.lattice, "plain");
+        let stored = serde_json::to_string(&sealed).unwrap();
+        assert!(
+            !stored.contains("\"jwt\"") && !stored.contains("\"seed\""),
+            "sensitive fields are stored in plaintext"
+        );
+
+        let mut loaded: WashContext = serde_json::from_str(&stored).unwrap();
+        loaded.unseal().unwrap();
+        assert_eq!(loaded.ctl_jwt.as_deref(), Some("jwt"));
+        assert_eq!(loaded.ctl_seed.as_deref(), Some("seed"));
+
+        // Saving the decrypted context seals it again
+        let resealed = loaded.to_stored().unwrap();
+        assert_eq!(resealed.ctl_jwt, None);
+        assert_ne!(resealed.sealed, sealed.sealed, "nonce was reused");
+
+        let mut tampered = sealed.clone();
+        if let Some(sealed) = tampered.sealed.as_mut() {
+            sealed.ciphertext = BASE64.encode(b"tampered ciphertext with a tag");
+        }
+        assert!(tampered.unseal().is_err());
+    }
+}
@@ -9,7 +9,7 @@ use anyhow::{Context, Result};
 
 use crate::config::{cfg_dir, DEFAULT_CTX_DIR_NAME};
 
-use super::{ContextManager, WashContext, HOST_CONFIG_NAME};
+use super::{ContextManager, PluginContext, WashContext, HOST_CONFIG_NAME};
 
 const DEFAULT: &str = "default";
 
@@ -91,6 +91,27 @@ impl ContextDir {
         Ok(paths)
     }
 
+    /// Whether the named context is encrypted, without decrypting it
+    pub fn is_context_encrypted(&self, name: &str) -> Result<bool> {
+        Ok(self.load_stored_context(name)?.is_encrypted())
+    }
+
+    /// Loads the parts of the default context passed to plugins. The context is not decrypted, so
+    /// this never prompts for a passphrase
+    pub fn load_default_plugin_context(&self) -> Result<PluginContext> {
+        let ctx = self.load_stored_context(&self.default_context_name()?)?;
+        Ok(PluginContext::from(&ctx))
+    }
+
+    /// Loads the named context as stored on disk, without decrypting its sealed fields
+    fn load_stored_context(&self, name: &str) -> Result<WashContext> {
+        let path = context_path_from_name(&self.0, name);
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("failed to open context file [{}]", path.display()))?;
+        let reader = BufReader::new(file);
+        serde_json::from_reader(reader).context("failed to parse context")
+    }
+
     /// Returns the full path on disk for the named context
     pub fn get_context_path(&self, name: &str) -> Result<Option<PathBuf>> {
         Ok(self
@@ -158,7 +179,8 @@ impl ContextManager for ContextDir {
 
     /// Sets the current default context to the given name
     fn set_default_context(&self, name: &str) -> Result<()> {
-        self.load_context(name).context("context does not exist")?;
+        self.load_stored_context(name)
+            .context("context does not exist")?;
 
         let default_path = self.0.join(DEFAULT);
         std::fs::write(&default_path, name.as_bytes()).with_context(|| {
@@ -169,12 +191,13 @@ impl ContextManager for ContextDir {
         })
     }
 
-    /// Saves the given context to the context directory. The file will be named `{ctx.name}.json`
+    /// Saves the given context to the context directory. The file will be named `{ctx.name}.json`.
+    /// Sensitive fields of encrypted contexts are sealed again before being written
     fn save_context(&self, ctx: &WashContext) -> Result<()> {
         let filepath = context_path_from_name(&self.0, &ctx.name);
         std::fs::write(
             &filepath,
-            serde_json::to_vec(&ctx).context("failed to serialize context")?,
+            serde_json::to_vec(&ctx.to_stored()?).context("failed to serialize context")?,
         )
         .with_context(|| {
             format!(
@@ -199,13 +222,12 @@ impl ContextManager for ContextDir {
         self.load_context(&self.default_context_name()?)
     }
 
-    /// Loads the named context from disk
+    /// Loads the named context from disk, decrypting its sealed fields if it is encrypted
     fn load_context(&self, name: &str) -> Result<WashContext> {
-        let path = context_path_from_name(&self.0, name);
-        let file = std::fs::File::open(&path)
-            .with_context(|| format!("failed to open context file [{}]", path.display()))?;
-        let reader = BufReader::new(file);
-        serde_json::from_reader(reader).context("failed to parse context")
+        let mut ctx = self.load_stored_context(name)?;
+        ctx.unseal()
+            .with_context(|| format!("failed to decrypt context `{name}`"))?;
+        Ok(ctx)
     }
 
     fn list_contexts(&self) -> Result<Vec<String>> {
@@ -231,6 +253,7 @@ fn context_path_from_name(dir: impl AsRef<Path>, name: &str) -> PathBuf {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::context::encryption::{KeySource, SealedFields};
 
     #[test]
     fn round_trip_happy_path() {
@@ -364,6 +387,56 @@ mod test {
         );
     }
 
+    #[test]
+    fn plugin_context_is_redacted() {
+        let tempdir = tempfile::tempdir().expect("Unable to create tempdir");
+        let ctx_dir =
+            ContextDir::from_dir(Some(&tempdir)).expect("Should be able to create context dir");
+
+        // The sealed fields can't be opened, so loading the plugin context must not decrypt them
+        let ctx = WashContext {
+            name: "sealed".to_string(),
+            ctl_seed: Some("SUCTLSEED".to_string()),
+            rpc_jwt: Some("rpc.jwt".to_string()),
+            lattice: "plugins".to_string(),
+            sealed: Some(SealedFields {
+                key: KeySource::Keychain,
+                nonce: "AAAA".to_string(),
+                ciphertext: "AAAA".to_string(),
+            }),
+            ..Default::default()
+        };
+        std::fs::write(
+            context_path_from_name(&ctx_dir, "sealed"),
+            serde_json::to_vec(&ctx).unwrap(),
+        )
+        .unwrap();
+        ctx_dir
+            .set_default_context("sealed")
+            .expect("Should be able to set default context");
+
+        let plugin_ctx = ctx_dir
+            .load_default_plugin_context()
+            .expect("Should be able to load the plugin context without decrypting");
+        assert_eq!(plugin_ctx.name, "sealed");
+        assert_eq!(plugin_ctx.lattice, "plugins");
+        let json = serde_json::to_value(&plugin_ctx).unwrap();
+        let mut fields: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            [
+                "ctl_host",
+                "ctl_port",
+                "ctl_timeout",
+                "lattice",
+                "name",
+                "rpc_timeout"
+            ],
+            "plugin context must not contain seeds or JWTs"
+        );
+    }
+
     #[test]
     fn load_non_existent_contexts() {
         let tempdir = tempfile::tempdir().expect("Unable to create tempdir");
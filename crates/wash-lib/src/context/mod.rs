@@ -1,20 +1,25 @@
 //! Types and methods for handling wash contexts, the configuration files for interacting with
 //! lattices
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, NoneAsEmptyString};
 
+use self::encryption::SealedFields;
 use crate::{
     config::{
         DEFAULT_COMPONENT_OPERATION_TIMEOUT_MS, DEFAULT_LATTICE, DEFAULT_NATS_HOST,
         DEFAULT_NATS_PORT, DEFAULT_NATS_TIMEOUT_MS,
     },
     id::ClusterSeed,
+    registry::RegistryConfig,
 };
 
+pub mod bundle;
+pub mod encryption;
 pub mod fs;
 
 pub const HOST_CONFIG_NAME: &str = "host_config";
@@ -90,6 +95,16 @@ pub struct WashContext {
     pub rpc_timeout: u64,
     /// TLS CA file to use for RPC calls
     pub rpc_tls_ca_file: Option<PathBuf>,
+
+    /// Configuration of OCI registries used by artifacts pulled and pushed by wash, keyed by
+    /// registry hostname
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub registries: HashMap<String, RegistryConfig>,
+
+    /// Seeds and JWTs of the context, sealed with `wash ctx encrypt`. When the context is loaded,
+    /// these are decrypted into their respective fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed: Option<SealedFields>,
 }
 
 impl WashContext {
@@ -124,6 +139,35 @@ impl Default for WashContext {
             rpc_credsfile: None,
             rpc_timeout: DEFAULT_NATS_TIMEOUT_MS,
             rpc_tls_ca_file: None,
+            registries: HashMap::new(),
+            sealed: None,
+        }
+    }
+}
+
+/// The parts of a [`WashContext`] passed to plugins, which plugins need to connect to the same
+/// lattice as wash. Seeds, JWTs and credentials are never passed to plugins
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PluginContext {
+    pub name: String,
+    pub ctl_host: String,
+    pub ctl_port: u16,
+    /// timeout in milliseconds
+    pub ctl_timeout: u64,
+    pub lattice: String,
+    /// rpc timeout in milliseconds
+    pub rpc_timeout: u64,
+}
+
+impl From<&WashContext> for PluginContext {
+    fn from(ctx: &WashContext) -> Self {
+        Self {
+            name: ctx.name.clone(),
+            ctl_host: ctx.ctl_host.clone(),
+            ctl_port: ctx.ctl_port,
+            ctl_timeout: ctx.ctl_timeout,
+            lattice: ctx.lattice.clone(),
+            rpc_timeout: ctx.rpc_timeout,
         }
     }
 }
@@ -41,6 +41,14 @@ pub fn user_question(prompt: &str, default: &Option<String>) -> Result<String> {
     i.interact().map_err(anyhow::Error::from)
 }
 
+pub fn user_confirm(prompt: &str) -> Result<bool> {
+    dialoguer::Confirm::new()
+        .with_prompt(prompt.to_string())
+        .default(false)
+        .interact()
+        .map_err(anyhow::Error::from)
+}
+
 fn extract_default(variable: &VarInfo) -> Option<String> {
     match variable {
         VarInfo::Bool {
@@ -24,6 +24,7 @@ pub mod interactive;
 pub mod project_variables;
 use project_variables::fill_project_variables;
 mod template;
+mod wit_provider;
 
 type TomlMap = std::collections::BTreeMap<String, toml::Value>;
 type ParamMap = std::collections::BTreeMap<String, serde_json::Value>;
@@ -89,6 +90,13 @@ pub struct Project {
 
     /// Optional github branch. Defaults to "main"
     pub branch: Option<String>,
+
+    /// Optional path to a WIT file or directory to generate a provider from, instead of a template
+    pub wit: Option<PathBuf>,
+
+    /// WIT world implemented by the provider generated from `wit`, required if it has multiple
+    /// worlds
+    pub world: Option<String>,
 }
 
 /// From a [Project] specification, generate a project of kind [`ProjectKind`]
@@ -101,6 +109,10 @@ pub struct Project {
 pub async fn generate_project(project: Project) -> Result<PathBuf> {
     validate(&project)?;
 
+    if project.wit.is_some() {
+        return make_provider_from_wit(project).await;
+    }
+
     // if user did not specify path to template dir or path to git repo,
     // pick one of the favorites for this kind
     let project = if project.path.is_none() && project.git.is_none() {
@@ -133,6 +145,26 @@ fn validate(project: &Project) -> Result<()> {
         );
     }
 
+    if let Some(wit) = &project.wit {
+        if !matches!(project.kind, ProjectKind::Provider) {
+            bail!(
+                "error in 'new {}' options: --wit is only supported for providers",
+                project.kind
+            );
+        }
+        if project.path.is_some() || project.git.is_some() || project.template_name.is_some() {
+            bail!("error in 'new {}' options: --wit generates the project from WIT and cannot be used with a template (--path, --git or --template-name)",
+                project.kind
+            );
+        }
+        if !wit.exists() {
+            bail!(
+                "error in --wit option: '{}' is not an existing file or directory",
+                &wit.display()
+            );
+        }
+    }
+
     if project.git.is_some() || !project.no_git_init {
         if let Err(err) = std::process::Command::new("git")
             .args(["version"])
@@ -285,21 +317,7 @@ async fn make_project(project: Project) -> std::result::Result<PathBuf, anyhow::
     .map_err(|e| any_msg("generating project from templates:", &e.to_string()))?;
 
     if !project.no_git_init {
-        let cmd_out = Command::new("git")
-            .args(["init", "--initial-branch", "main", "."])
-            .current_dir(tokio::fs::canonicalize(&project_dir).await?)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?
-            .wait_with_output()
-            .await?;
-        if !cmd_out.status.success() {
-            bail!(
-                "git init error: {}",
-                String::from_utf8_lossy(&cmd_out.stderr)
-            );
-        }
+        git_init(&project_dir).await?;
     }
 
     pbar.clear().ok();
@@ -315,6 +333,61 @@ async fn make_project(project: Project) -> std::result::Result<PathBuf, anyhow::
     Ok(project_dir)
 }
 
+/// Generate a provider project implementing the world of the WIT at `project.wit`, with stubs for
+/// the exported functions and parsing of link configuration for the imported interfaces
+async fn make_provider_from_wit(project: Project) -> Result<PathBuf> {
+    // validated to be present by the caller
+    let wit = project.wit.as_ref().context("missing WIT path")?;
+    let project_name = resolve_project_name(&None, &project.project_name.as_ref())?;
+    let project_dir = resolve_project_dir(&project_name)?;
+
+    println!(
+        "{} {} {}{}",
+        emoji::WRENCH,
+        style("Generating provider from WIT").bold(),
+        style(wit.display()).bold().yellow(),
+        style("...").bold()
+    );
+
+    let (resolve, world) = wit_provider::load_world(wit, project.world.as_deref())?;
+    let files = wit_provider::ProviderFiles::render(&resolve, world, &project_name.kebab_case())
+        .map_err(|e| any_msg("generating provider from WIT:", &e.to_string()))?;
+    files.write(&project_dir, wit)?;
+
+    if !project.no_git_init {
+        git_init(&project_dir).await?;
+    }
+
+    println!(
+        "{} {} {} {}",
+        emoji::SPARKLE,
+        style("Done!").bold().green(),
+        style("New project created").bold(),
+        style(&project_dir.display()).underlined()
+    );
+
+    Ok(project_dir)
+}
+
+async fn git_init(project_dir: &Path) -> Result<()> {
+    let cmd_out = Command::new("git")
+        .args(["init", "--initial-branch", "main", "."])
+        .current_dir(tokio::fs::canonicalize(project_dir).await?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .wait_with_output()
+        .await?;
+    if !cmd_out.status.success() {
+        bail!(
+            "git init error: {}",
+            String::from_utf8_lossy(&cmd_out.stderr)
+        );
+    }
+    Ok(())
+}
+
 // convert from TOML map to JSON map
 fn toml_to_json<T: Serialize>(map: &T) -> Result<ParamMap> {
     let s = serde_json::to_string(map)?;
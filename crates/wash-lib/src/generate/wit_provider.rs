@@ -0,0 +1,761 @@
+//! wit_provider.rs
+//! Generate a capability provider project from a WIT world, with stubs for its exports and link
+//! configuration parsing for its imports
+//!
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use heck::{ToKebabCase as _, ToSnakeCase as _, ToUpperCamelCase as _};
+use wit_parser::{
+    Docs, Function, InterfaceId, Resolve, Results, Type, TypeDefKind, TypeOwner, UnresolvedPackage,
+    WorldId, WorldItem, WorldKey,
+};
+
+/// Version of `wasmcloud-provider-sdk` used by generated providers
+const PROVIDER_SDK_VERSION: &str = "0.5.0";
+/// Version of `wit-bindgen-wrpc` used by generated providers
+const WIT_BINDGEN_WRPC_VERSION: &str = "0.3.7";
+
+/// Load the WIT package at `path`, either a single WIT file or a directory of WIT files with
+/// dependencies in `deps/`, and select `world` from it. The world may be omitted if the package
+/// contains a single world.
+pub(crate) fn load_world(path: &Path, world: Option<&str>) -> Result<(Resolve, WorldId)> {
+    let mut resolve = Resolve::default();
+    let package = if path.is_dir() {
+        resolve
+            .push_dir(path)
+            .with_context(|| format!("failed to load WIT directory [{}]", path.display()))?
+            .0
+    } else {
+        let unresolved = UnresolvedPackage::parse_file(path)
+            .with_context(|| format!("failed to parse WIT file [{}]", path.display()))?;
+        resolve
+            .push(unresolved)
+            .with_context(|| format!("failed to resolve WIT file [{}]", path.display()))?
+    };
+    let world = resolve
+        .select_world(package, world)
+        .context("failed to select WIT world, use --world to choose one")?;
+    Ok((resolve, world))
+}
+
+/// Files of a generated provider project, relative to the project directory
+pub(crate) struct ProviderFiles {
+    pub files: Vec<(PathBuf, String)>,
+}
+
+impl ProviderFiles {
+    /// Render the project of a provider named `project_name` implementing `world`
+    pub(crate) fn render(resolve: &Resolve, world: WorldId, project_name: &str) -> Result<Self> {
+        let generator = Generator {
+            resolve,
+            world,
+            provider: provider_type_name(project_name),
+        };
+        let world = &resolve.worlds[world];
+        let world_name = match world.package.map(|id| &resolve.packages[id].name) {
+            Some(package) => format!("{}:{}/{}", package.namespace, package.name, world.name),
+            None => world.name.clone(),
+        };
+        Ok(Self {
+            files: vec![
+                ("Cargo.toml".into(), render_cargo_toml(project_name)),
+                (
+                    "wasmcloud.toml".into(),
+                    render_wasmcloud_toml(project_name, &world_name),
+                ),
+                (".gitignore".into(), "/target\nCargo.lock\n".into()),
+                ("src/main.rs".into(), generator.render_main(project_name)),
+                (
+                    "src/provider.rs".into(),
+                    generator.render_provider(&world_name)?,
+                ),
+            ],
+        })
+    }
+
+    /// Write the files to `project_dir`, along with the WIT at `wit` in the `wit` directory
+    pub(crate) fn write(&self, project_dir: &Path, wit: &Path) -> Result<()> {
+        let wit_dir = project_dir.join("wit");
+        if wit.is_dir() {
+            super::copy_dir_all(wit, &wit_dir)
+                .with_context(|| format!("failed to copy WIT directory [{}]", wit.display()))?;
+        } else {
+            fs::create_dir_all(&wit_dir)?;
+            let file_name = wit.file_name().context("WIT path has no file name")?;
+            fs::copy(wit, wit_dir.join(file_name))
+                .with_context(|| format!("failed to copy WIT file [{}]", wit.display()))?;
+        }
+        for (path, contents) in &self.files {
+            let path = project_dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, contents)
+                .with_context(|| format!("failed to write [{}]", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+struct Generator<'a> {
+    resolve: &'a Resolve,
+    world: WorldId,
+    /// Name of the provider type
+    provider: String,
+}
+
+/// Interface exported by the world
+struct WorldInterface {
+    id: InterfaceId,
+    /// Path of the module generated for the interface by `wit-bindgen-wrpc`
+    module: String,
+}
+
+impl Generator<'_> {
+    fn exports(&self) -> Result<Vec<WorldInterface>> {
+        let world = &self.resolve.worlds[self.world];
+        let mut exports = Vec::new();
+        for (key, item) in &world.exports {
+            match item {
+                WorldItem::Interface(id) => exports.push(WorldInterface {
+                    id: *id,
+                    module: format!("exports::{}", self.interface_module(key)),
+                }),
+                WorldItem::Function(func) => bail!(
+                    "exported function `{}` must be part of an exported interface",
+                    func.name
+                ),
+                WorldItem::Type(_) => {}
+            }
+        }
+        Ok(exports)
+    }
+
+    /// Imported interfaces with functions, i.e. excluding interfaces only providing types to
+    /// exported interfaces
+    fn imports(&self) -> Vec<InterfaceId> {
+        let world = &self.resolve.worlds[self.world];
+        world
+            .imports
+            .values()
+            .filter_map(|item| match item {
+                WorldItem::Interface(id) if !self.resolve.interfaces[*id].functions.is_empty() => {
+                    Some(*id)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Module path of an interface relative to the bindings root
+    fn interface_module(&self, key: &WorldKey) -> String {
+        match key {
+            WorldKey::Name(name) => rust_ident(name),
+            WorldKey::Interface(id) => {
+                let interface = &self.resolve.interfaces[*id];
+                let name = interface.name.as_deref().unwrap_or_default();
+                match interface.package.map(|id| &self.resolve.packages[id].name) {
+                    Some(package) => format!(
+                        "{}::{}::{}",
+                        rust_ident(&package.namespace),
+                        rust_ident(&package.name),
+                        rust_ident(name)
+                    ),
+                    None => rust_ident(name),
+                }
+            }
+        }
+    }
+
+    /// Module path of an interface owning types used in the world
+    fn owner_module(&self, id: InterfaceId) -> Result<String> {
+        let world = &self.resolve.worlds[self.world];
+        if let Some((key, _)) = world
+            .exports
+            .iter()
+            .find(|(_, item)| matches!(item, WorldItem::Interface(i) if *i == id))
+        {
+            return Ok(format!("exports::{}", self.interface_module(key)));
+        }
+        if let Some((key, _)) = world
+            .imports
+            .iter()
+            .find(|(_, item)| matches!(item, WorldItem::Interface(i) if *i == id))
+        {
+            return Ok(self.interface_module(key));
+        }
+        bail!("interface is neither imported nor exported by the world")
+    }
+
+    /// Rust type generated by `wit-bindgen-wrpc` for a WIT type
+    fn rust_type(&self, ty: &Type) -> Result<String> {
+        Ok(match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 => "u8".into(),
+            Type::U16 => "u16".into(),
+            Type::U32 => "u32".into(),
+            Type::U64 => "u64".into(),
+            Type::S8 => "i8".into(),
+            Type::S16 => "i16".into(),
+            Type::S32 => "i32".into(),
+            Type::S64 => "i64".into(),
+            Type::Float32 => "f32".into(),
+            Type::Float64 => "f64".into(),
+            Type::Char => "char".into(),
+            Type::String => "String".into(),
+            Type::Id(id) => {
+                let def = &self.resolve.types[*id];
+                if let Some(name) = &def.name {
+                    let name = name.to_upper_camel_case();
+                    return match def.owner {
+                        TypeOwner::Interface(owner) => {
+                            Ok(format!("{}::{name}", self.owner_module(owner)?))
+                        }
+                        TypeOwner::World(_) => Ok(name),
+                        TypeOwner::None => bail!("type `{name}` has no owner"),
+                    };
+                }
+                match &def.kind {
+                    TypeDefKind::List(ty) => format!("Vec<{}>", self.rust_type(ty)?),
+                    TypeDefKind::Option(ty) => format!("Option<{}>", self.rust_type(ty)?),
+                    TypeDefKind::Result(result) => format!(
+                        "Result<{}, {}>",
+                        self.optional_type(result.ok.as_ref())?,
+                        self.optional_type(result.err.as_ref())?
+                    ),
+                    TypeDefKind::Tuple(tuple) => self.tuple_type(&tuple.types)?,
+                    TypeDefKind::Type(ty) => self.rust_type(ty)?,
+                    TypeDefKind::Handle(_) | TypeDefKind::Resource => {
+                        bail!("resources are not supported by generated providers")
+                    }
+                    kind => bail!("unsupported WIT type {kind:?}"),
+                }
+            }
+        })
+    }
+
+    fn optional_type(&self, ty: Option<&Type>) -> Result<String> {
+        ty.map_or_else(|| Ok("()".into()), |ty| self.rust_type(ty))
+    }
+
+    fn tuple_type<'a>(&self, types: impl IntoIterator<Item = &'a Type>) -> Result<String> {
+        let types = types
+            .into_iter()
+            .map(|ty| self.rust_type(ty))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(match types.as_slice() {
+            [ty] => format!("({ty},)"),
+            types => format!("({})", types.join(", ")),
+        })
+    }
+
+    fn result_type(&self, results: &Results) -> Result<String> {
+        match results {
+            Results::Anon(ty) => self.rust_type(ty),
+            Results::Named(results) => match results.as_slice() {
+                [(_, ty)] => self.rust_type(ty),
+                results => self.tuple_type(results.iter().map(|(_, ty)| ty)),
+            },
+        }
+    }
+
+    fn render_main(&self, project_name: &str) -> String {
+        format!(
+            r#"//! The {project_name} capability provider, generated from WIT by `wash new provider --wit`.
+//!
+//! The provider is implemented in `./provider.rs`.
+
+mod provider;
+
+use provider::{provider};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {{
+    {provider}::run().await?;
+    eprintln!("{project_name} provider exiting");
+    Ok(())
+}}
+"#,
+            provider = self.provider,
+        )
+    }
+
+    fn render_provider(&self, world_name: &str) -> Result<String> {
+        let provider = &self.provider;
+        let exports = self.exports()?;
+        let imports = self.imports();
+
+        let mut out = String::new();
+        writeln!(out, "use std::collections::HashMap;")?;
+        if !imports.is_empty() {
+            writeln!(out, "use std::sync::Arc;")?;
+        }
+        writeln!(out)?;
+        writeln!(out, "use anyhow::Context as _;")?;
+        if !imports.is_empty() {
+            writeln!(out, "use tokio::sync::RwLock;")?;
+        }
+        if imports.is_empty() {
+            writeln!(out, "use tracing::info;")?;
+        } else {
+            writeln!(out, "use tracing::{{debug, info}};")?;
+        }
+        let mut sdk_imports = vec!["run_provider"];
+        if !exports.is_empty() {
+            sdk_imports.push("Context");
+        }
+        if !imports.is_empty() {
+            sdk_imports.push("LinkConfig");
+        }
+        sdk_imports.extend(["Provider", "ProviderInitConfig"]);
+        writeln!(
+            out,
+            "use wasmcloud_provider_sdk::{{{}}};",
+            sdk_imports.join(", ")
+        )?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "wit_bindgen_wrpc::generate!({{ world: \"{world_name}\" }});"
+        )?;
+
+        for import in &imports {
+            let interface = &self.resolve.interfaces[*import];
+            let name = interface.name.as_deref().unwrap_or_default();
+            writeln!(out)?;
+            writeln!(
+                out,
+                "/// Configuration of a link from this provider to a component exporting `{}`",
+                self.qualified_name(*import)
+            )?;
+            writeln!(out, "#[derive(Clone, Debug, Default)]")?;
+            writeln!(
+                out,
+                "pub struct {}LinkConfig {{",
+                name.to_upper_camel_case()
+            )?;
+            writeln!(out, "    pub values: HashMap<String, String>,")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+            writeln!(out, "impl {}LinkConfig {{", name.to_upper_camel_case())?;
+            writeln!(
+                out,
+                "    /// Parse the configuration of the link, e.g. to validate it and extract the values needed to"
+            )?;
+            writeln!(out, "    /// invoke the linked component")?;
+            writeln!(
+                out,
+                "    pub fn parse(config: &HashMap<String, String>) -> anyhow::Result<Self> {{"
+            )?;
+            writeln!(out, "        Ok(Self {{")?;
+            writeln!(out, "            values: config.clone(),")?;
+            writeln!(out, "        }})")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "#[derive(Default, Clone)]")?;
+        writeln!(out, "pub struct {provider} {{")?;
+        for import in &imports {
+            let name = self.resolve.interfaces[*import]
+                .name
+                .as_deref()
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "    /// Links to components exporting `{}`, by component ID",
+                self.qualified_name(*import)
+            )?;
+            writeln!(
+                out,
+                "    {}_links: Arc<RwLock<HashMap<String, {}LinkConfig>>>,",
+                name.to_snake_case(),
+                name.to_upper_camel_case()
+            )?;
+        }
+        writeln!(out, "}}")?;
+
+        writeln!(out)?;
+        writeln!(out, "impl {provider} {{")?;
+        writeln!(
+            out,
+            "    /// Run the provider and serve its exports until it is shut down"
+        )?;
+        writeln!(out, "    pub async fn run() -> anyhow::Result<()> {{")?;
+        if exports.is_empty() {
+            writeln!(
+                out,
+                "        let shutdown = run_provider(Self::default(), \"{}\")",
+                provider.to_kebab_case()
+            )?;
+            writeln!(out, "            .await")?;
+            writeln!(out, "            .context(\"failed to run provider\")?;")?;
+            writeln!(
+                out,
+                "        // The world has no exports, run until the provider is shut down"
+            )?;
+            writeln!(out, "        shutdown.await;")?;
+            writeln!(out, "        Ok(())")?;
+        } else {
+            writeln!(out, "        let provider = Self::default();")?;
+            writeln!(
+                out,
+                "        let shutdown = run_provider(provider.clone(), \"{}\")",
+                provider.to_kebab_case()
+            )?;
+            writeln!(out, "            .await")?;
+            writeln!(out, "            .context(\"failed to run provider\")?;")?;
+            writeln!(
+                out,
+                "        let connection = wasmcloud_provider_sdk::get_connection();"
+            )?;
+            writeln!(out, "        serve(")?;
+            writeln!(
+                out,
+                "            &connection.get_wrpc_client(connection.provider_key()),"
+            )?;
+            writeln!(out, "            provider,")?;
+            writeln!(out, "            shutdown,")?;
+            writeln!(out, "        )")?;
+            writeln!(out, "        .await")?;
+        }
+        writeln!(out, "    }}")?;
+        writeln!(out, "}}")?;
+
+        for export in &exports {
+            let interface = &self.resolve.interfaces[export.id];
+            writeln!(out)?;
+            write_docs(&mut out, "", &interface.docs)?;
+            writeln!(out, "#[allow(unused_variables)]")?;
+            writeln!(
+                out,
+                "impl {}::Handler<Option<Context>> for {provider} {{",
+                export.module
+            )?;
+            for (i, func) in interface.functions.values().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                self.write_handler_stub(&mut out, func)?;
+            }
+            writeln!(out, "}}")?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "impl Provider for {provider} {{")?;
+        writeln!(
+            out,
+            "    async fn init(&self, config: impl ProviderInitConfig) -> anyhow::Result<()> {{"
+        )?;
+        writeln!(out, "        let provider_id = config.get_provider_id();")?;
+        writeln!(
+            out,
+            "        let initial_config: &HashMap<String, String> = config.get_config();"
+        )?;
+        writeln!(
+            out,
+            "        info!(provider_id, ?initial_config, \"initializing provider\");"
+        )?;
+        writeln!(out, "        Ok(())")?;
+        writeln!(out, "    }}")?;
+        if !imports.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "    async fn receive_link_config_as_source(")?;
+            writeln!(out, "        &self,")?;
+            writeln!(out, "        link_config: LinkConfig<'_>,")?;
+            writeln!(out, "    ) -> anyhow::Result<()> {{")?;
+            writeln!(
+                out,
+                "        let (namespace, package, interfaces) = link_config.wit_metadata;"
+            )?;
+            writeln!(out, "        for interface in interfaces {{")?;
+            writeln!(
+                out,
+                "            match (namespace.as_str(), package.as_str(), interface.as_str()) {{"
+            )?;
+            for import in &imports {
+                let interface = &self.resolve.interfaces[*import];
+                let name = interface.name.as_deref().unwrap_or_default();
+                let Some(package) = interface.package.map(|id| &self.resolve.packages[id].name)
+                else {
+                    continue;
+                };
+                writeln!(
+                    out,
+                    "                (\"{}\", \"{}\", \"{name}\") => {{",
+                    package.namespace, package.name
+                )?;
+                writeln!(
+                    out,
+                    "                    let config = {}LinkConfig::parse(link_config.config)?;",
+                    name.to_upper_camel_case()
+                )?;
+                writeln!(
+                    out,
+                    "                    self.{}_links",
+                    name.to_snake_case()
+                )?;
+                writeln!(out, "                        .write()")?;
+                writeln!(out, "                        .await")?;
+                writeln!(
+                    out,
+                    "                        .insert(link_config.target_id.to_string(), config);"
+                )?;
+                writeln!(out, "                }}")?;
+            }
+            writeln!(out, "                _ => debug!(")?;
+            writeln!(out, "                    %namespace,")?;
+            writeln!(out, "                    %package,")?;
+            writeln!(out, "                    %interface,")?;
+            writeln!(
+                out,
+                "                    \"ignoring link to unknown interface\""
+            )?;
+            writeln!(out, "                ),")?;
+            writeln!(out, "            }}")?;
+            writeln!(out, "        }}")?;
+            writeln!(out, "        Ok(())")?;
+            writeln!(out, "    }}")?;
+            writeln!(out)?;
+            writeln!(
+                out,
+                "    async fn delete_link_as_source(&self, target: &str) -> anyhow::Result<()> {{"
+            )?;
+            for import in &imports {
+                let name = self.resolve.interfaces[*import]
+                    .name
+                    .as_deref()
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "        self.{}_links.write().await.remove(target);",
+                    name.to_snake_case()
+                )?;
+            }
+            writeln!(out, "        Ok(())")?;
+            writeln!(out, "    }}")?;
+        }
+        writeln!(out, "}}")?;
+        Ok(out)
+    }
+
+    fn write_handler_stub(&self, out: &mut String, func: &Function) -> Result<()> {
+        write_docs(out, "    ", &func.docs)?;
+        let mut params = vec!["&self".to_string(), "ctx: Option<Context>".to_string()];
+        for (name, ty) in &func.params {
+            params.push(format!("{}: {}", rust_ident(name), self.rust_type(ty)?));
+        }
+        let name = rust_ident(&func.name);
+        let result = self.result_type(&func.results)?;
+        let signature = format!(
+            "    async fn {name}({}) -> anyhow::Result<{result}> {{",
+            params.join(", ")
+        );
+        if signature.len() <= 100 {
+            writeln!(out, "{signature}")?;
+        } else {
+            writeln!(out, "    async fn {name}(")?;
+            for param in params {
+                writeln!(out, "        {param},")?;
+            }
+            writeln!(out, "    ) -> anyhow::Result<{result}> {{")?;
+        }
+        writeln!(out, "        todo!(\"implement `{}`\")", func.name)?;
+        writeln!(out, "    }}")?;
+        Ok(())
+    }
+
+    fn qualified_name(&self, id: InterfaceId) -> String {
+        let interface = &self.resolve.interfaces[id];
+        let name = interface.name.as_deref().unwrap_or_default();
+        match interface.package.map(|id| &self.resolve.packages[id].name) {
+            Some(package) => format!("{}:{}/{name}", package.namespace, package.name),
+            None => name.to_string(),
+        }
+    }
+}
+
+fn write_docs(out: &mut String, indent: &str, docs: &Docs) -> Result<()> {
+    if let Some(contents) = &docs.contents {
+        for line in contents.trim().lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                writeln!(out, "{indent}///")?;
+            } else {
+                writeln!(out, "{indent}/// {line}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn render_cargo_toml(project_name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{project_name}"
+version = "0.1.0"
+edition = "2021"
+
+[workspace]
+
+[dependencies]
+anyhow = "1"
+async-nats = "0.33"
+serde = {{ version = "1", features = ["derive"] }}
+serde_json = "1"
+tokio = {{ version = "1", features = ["full"] }}
+tracing = "0.1"
+wasmcloud-provider-sdk = "{PROVIDER_SDK_VERSION}"
+wit-bindgen-wrpc = "{WIT_BINDGEN_WRPC_VERSION}"
+"#
+    )
+}
+
+fn render_wasmcloud_toml(project_name: &str, world_name: &str) -> String {
+    format!(
+        r#"name = "{project_name}"
+language = "rust"
+type = "provider"
+
+[provider]
+vendor = "Example Vendor"
+wit_world = "{world_name}"
+"#
+    )
+}
+
+/// Name of the provider type for a project, e.g. `MessagingProvider` for `messaging` and
+/// `messaging-provider`
+fn provider_type_name(project_name: &str) -> String {
+    let name = project_name.to_upper_camel_case();
+    if name.ends_with("Provider") {
+        name
+    } else {
+        format!("{name}Provider")
+    }
+}
+
+/// Rust identifier generated by `wit-bindgen-wrpc` for a WIT identifier
+fn rust_ident(name: &str) -> String {
+    let ident = name.to_snake_case();
+    match ident.as_str() {
+        "as" | "async" | "await" | "break" | "const" | "continue" | "crate" | "dyn" | "else"
+        | "enum" | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop"
+        | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "self" | "static"
+        | "struct" | "super" | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while"
+        | "abstract" | "become" | "box" | "do" | "final" | "macro" | "override" | "priv"
+        | "typeof" | "unsized" | "virtual" | "yield" | "try" => format!("{ident}_"),
+        _ => ident,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREETER_WIT: &str = r#"
+package example:greeter;
+
+interface greet {
+    record greeting {
+        name: string,
+        excited: bool,
+    }
+
+    /// Greet someone
+    ///
+    /// Returns the greeting
+    greet: func(greeting: greeting) -> string;
+
+    /// Count the words of a text
+    count-words: func(text: string, ignore: list<string>) -> result<u32, string>;
+}
+
+interface notify {
+    greeted: func(name: string);
+}
+
+world greeter-provider {
+    import notify;
+    export greet;
+}
+"#;
+
+    fn render_greeter() -> String {
+        let mut resolve = Resolve::default();
+        let package = resolve
+            .push(
+                UnresolvedPackage::parse(Path::new("greeter.wit"), GREETER_WIT)
+                    .expect("fixture should parse"),
+            )
+            .expect("fixture should resolve");
+        let world = resolve
+            .select_world(package, Some("greeter-provider"))
+            .expect("world should exist");
+        let files =
+            ProviderFiles::render(&resolve, world, "greeter").expect("provider should be rendered");
+        files
+            .files
+            .into_iter()
+            .find_map(|(path, contents)| (path == Path::new("src/provider.rs")).then_some(contents))
+            .expect("provider source should be rendered")
+    }
+
+    #[test]
+    fn test_export_stubs() {
+        let provider = render_greeter();
+        assert!(provider.contains(
+            "wit_bindgen_wrpc::generate!({ world: \"example:greeter/greeter-provider\" });"
+        ));
+        assert!(provider.contains(
+            "impl exports::example::greeter::greet::Handler<Option<Context>> for GreeterProvider {"
+        ));
+        assert!(provider.contains(
+            r#"    /// Greet someone
+    ///
+    /// Returns the greeting
+    async fn greet(
+        &self,
+        ctx: Option<Context>,
+        greeting: exports::example::greeter::greet::Greeting,
+    ) -> anyhow::Result<String> {
+        todo!("implement `greet`")
+    }"#
+        ));
+        assert!(provider.contains(
+            r#"    /// Count the words of a text
+    async fn count_words(
+        &self,
+        ctx: Option<Context>,
+        text: String,
+        ignore: Vec<String>,
+    ) -> anyhow::Result<Result<u32, String>> {
+        todo!("implement `count-words`")
+    }"#
+        ));
+        // Stubs are generated for exports only
+        assert!(!provider.contains("async fn greeted("));
+    }
+
+    #[test]
+    fn test_import_link_config() {
+        let provider = render_greeter();
+        assert!(provider.contains("pub struct NotifyLinkConfig {"));
+        assert!(provider.contains("notify_links: Arc<RwLock<HashMap<String, NotifyLinkConfig>>>,"));
+        assert!(provider.contains("(\"example\", \"greeter\", \"notify\") => {"));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(provider_type_name("greeter"), "GreeterProvider");
+        assert_eq!(provider_type_name("greeter-provider"), "GreeterProvider");
+        assert_eq!(rust_ident("count-words"), "count_words");
+        assert_eq!(rust_ident("type"), "type_");
+    }
+}
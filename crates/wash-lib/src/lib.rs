@@ -46,6 +46,8 @@ pub mod registry;
 pub mod spier;
 #[cfg(feature = "nats")]
 pub mod wait;
+#[cfg(feature = "cli")]
+pub mod wit;
 
 #[cfg(feature = "plugin")]
 pub mod plugin;
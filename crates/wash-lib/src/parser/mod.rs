@@ -44,6 +44,148 @@ pub struct ProjectConfig {
     pub project_type: TypeConfig,
     /// Configuration common among all project types & languages.
     pub common: CommonConfig,
+    /// Configuration for `wash dev`
+    #[serde(default)]
+    pub dev: DevConfig,
+    /// Configuration for `wash build`
+    #[serde(default)]
+    pub build: BuildConfig,
+    /// Configuration for `wash test`
+    #[serde(default)]
+    pub test: TestConfig,
+}
+
+/// Configuration for `wash dev`, specified in the `[dev]` section of a wasmcloud.toml file
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct DevConfig {
+    /// Dotenv-formatted files whose values are exposed to the developed component as named configuration.
+    /// Relative paths are resolved against the project directory.
+    #[serde(default)]
+    pub env_files: Vec<PathBuf>,
+    /// Directories of other component projects this project depends on. `wash dev` builds, deploys and
+    /// watches them alongside the project, and links them to it based on matching WIT imports and exports.
+    /// Relative paths are resolved against the project directory.
+    #[serde(default)]
+    pub companions: Vec<PathBuf>,
+    /// Links put by `wash dev` when developing a provider, and put again every time the provider is
+    /// restarted with a new build.
+    #[serde(default)]
+    pub links: Vec<DevLinkConfig>,
+    /// Tunnel `wash dev --share` exposes the HTTP server of the component under development through
+    #[serde(default)]
+    pub tunnel: Option<TunnelConfig>,
+}
+
+/// Tunnel exposing the HTTP server of a component under development to other networks, specified
+/// in the `[dev.tunnel]` section of a wasmcloud.toml file
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum TunnelConfig {
+    /// Self-hosted [wstunnel](https://github.com/erebe/wstunnel) server, reached with the
+    /// `wstunnel` client
+    Wstunnel {
+        /// URL of the wstunnel server, e.g. `wss://tunnel.example.com`
+        server: String,
+        /// Port the server exposes the tunnel on
+        remote_port: u16,
+        /// URL the tunnel is reachable at, e.g. `https://dev.example.com`
+        public_url: String,
+        /// Path of the `wstunnel` binary, defaults to `wstunnel` on the `PATH`
+        #[serde(default)]
+        binary: Option<PathBuf>,
+    },
+    /// Self-hosted [frp](https://github.com/fatedier/frp) server, reached with the `frpc` client
+    Frp {
+        /// Address of the frp server, e.g. `tunnel.example.com`
+        server_addr: String,
+        /// Port of the frp server, defaults to 7000
+        #[serde(default = "default_frp_server_port")]
+        server_port: u16,
+        /// Token to authenticate to the frp server with
+        #[serde(default)]
+        token: Option<String>,
+        /// Port the server exposes the tunnel on
+        remote_port: u16,
+        /// URL the tunnel is reachable at, e.g. `http://tunnel.example.com:6000`
+        public_url: String,
+        /// Path of the `frpc` binary, defaults to `frpc` on the `PATH`
+        #[serde(default)]
+        binary: Option<PathBuf>,
+    },
+    /// Connections accepted on a local address are forwarded by wash itself, e.g. to share the
+    /// component on a local network
+    Local {
+        /// Address to listen on, defaults to a random port on the loopback interface
+        #[serde(default)]
+        listen: Option<String>,
+        /// URL the tunnel is reachable at, defaults to `http://` followed by the address listened on
+        #[serde(default)]
+        public_url: Option<String>,
+    },
+}
+
+fn default_frp_server_port() -> u16 {
+    7000
+}
+
+/// A link of a provider under development, specified in the `[[dev.links]]` section of a
+/// wasmcloud.toml file
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct DevLinkConfig {
+    /// Source of the link, either an ID or the name of a companion project. Defaults to the provider.
+    pub source_id: Option<String>,
+    /// Target of the link, either an ID or the name of a companion project. Defaults to the provider.
+    pub target: Option<String>,
+    /// Name of the link, defaults to `default`
+    #[serde(default = "default_link_name")]
+    pub name: String,
+    /// WIT namespace of the link, e.g. `wasi`
+    pub wit_namespace: String,
+    /// WIT package of the link, e.g. `keyvalue`
+    pub wit_package: String,
+    /// WIT interfaces of the link, e.g. `["store", "atomics"]`
+    pub interfaces: Vec<String>,
+    /// Names of the configurations of the source of the link
+    #[serde(default)]
+    pub source_config: Vec<String>,
+    /// Names of the configurations of the target of the link
+    #[serde(default)]
+    pub target_config: Vec<String>,
+}
+
+fn default_link_name() -> String {
+    "default".to_string()
+}
+
+/// Configuration for `wash build`, specified in the `[build]` section of a wasmcloud.toml file
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct BuildConfig {
+    /// Commands run in sequence on the built component before it is signed, e.g. `wasm-opt -O2 {input} -o {output}`.
+    /// `{input}` is replaced with the path to the built component and `{output}` with the path the command
+    /// should write the processed component to. Commands run from the project directory.
+    #[serde(default)]
+    pub post_commands: Vec<String>,
+    /// Environment variables set for the build toolchain and post-build commands, e.g.
+    /// `env = { PROTOC = "/opt/protoc/bin/protoc" }`. Build processes inherit the environment of
+    /// wash, these variables are added to it, overriding inherited variables of the same name.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Configuration for `wash test`, specified in the `[test]` section of a wasmcloud.toml file
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct TestConfig {
+    /// Application manifest deployed to the ephemeral lattice the tests run against. Without a manifest,
+    /// the project is built and deployed the way `wash dev` does. Relative paths are resolved against the
+    /// project directory.
+    #[serde(default)]
+    pub manifest: Option<PathBuf>,
+    /// Command running the tests, defaults to `cargo test -- --ignored`. Runs from the project directory.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// How long to wait for the deployment to converge before running the tests, in milliseconds
+    #[serde(default)]
+    pub deploy_timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
@@ -349,6 +491,10 @@ struct RawProjectConfig {
     pub tinygo: Option<RawTinyGoConfig>,
     pub go: Option<RawGoConfig>,
     pub registry: Option<RawRegistryConfig>,
+
+    pub dev: Option<DevConfig>,
+    pub build: Option<BuildConfig>,
+    pub test: Option<TestConfig>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
@@ -620,6 +766,9 @@ impl RawProjectConfig {
             language: language_config,
             project_type: project_type_config,
             common: common_config_result?,
+            dev: self.dev.unwrap_or_default(),
+            build: self.build.unwrap_or_default(),
+            test: self.test.unwrap_or_default(),
         })
     }
 }
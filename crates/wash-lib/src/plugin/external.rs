@@ -0,0 +1,241 @@
+//! Plugins shipped as executables named `wash-<name>` on the `PATH`, which wash runs git-style
+//! for subcommands it doesn't know
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// Prefix of the name of the executables on the `PATH` that are run as wash subcommands
+pub const EXTERNAL_PLUGIN_PREFIX: &str = "wash-";
+
+/// Environment variable set to the name of the active wash context when running plugins
+pub const CONTEXT_ENV: &str = "WASH_CONTEXT";
+
+/// Environment variable set to the active wash context, serialized as JSON, when running plugins.
+/// Seeds and JWTs of the context are never passed to plugins
+pub const CONTEXT_JSON_ENV: &str = "WASH_CONTEXT_JSON";
+
+/// Maximum edit distance between an unknown subcommand and a plugin name for the plugin to be
+/// suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Find the executable of the external plugin providing the `name` subcommand on the `PATH`
+#[must_use]
+pub fn find_external_plugin(name: &str) -> Option<PathBuf> {
+    find_external_plugin_in(name, std::env::var_os("PATH")?)
+}
+
+/// Same as [`find_external_plugin`], but looking in the directories of the given `PATH` value
+#[must_use]
+pub fn find_external_plugin_in(name: &str, path: impl Into<OsString>) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(std::path::is_separator) {
+        return None;
+    }
+    let file_name = format!(
+        "{EXTERNAL_PLUGIN_PREFIX}{name}{}",
+        std::env::consts::EXE_SUFFIX
+    );
+    std::env::split_paths(&path.into())
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+/// List the external plugins on the `PATH`, by subcommand name. Plugins earlier on the `PATH`
+/// shadow plugins of the same name later on
+#[must_use]
+pub fn external_plugins() -> BTreeMap<String, PathBuf> {
+    std::env::var_os("PATH")
+        .map(external_plugins_in)
+        .unwrap_or_default()
+}
+
+/// Same as [`external_plugins`], but looking in the directories of the given `PATH` value
+#[must_use]
+pub fn external_plugins_in(path: impl Into<OsString>) -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    for dir in std::env::split_paths(&path.into()) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(EXTERNAL_PLUGIN_PREFIX))
+                .map(|name| {
+                    name.strip_suffix(std::env::consts::EXE_SUFFIX)
+                        .unwrap_or(name)
+                })
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            if is_executable(&path) {
+                plugins.entry(name.to_string()).or_insert(path);
+            }
+        }
+    }
+    plugins
+}
+
+/// Run the external plugin at `path` with `args`, inheriting stdio and the environment of wash
+/// along with `env`. Returns the exit code of the plugin
+pub async fn run_external_plugin(
+    path: &Path,
+    args: impl IntoIterator<Item = impl AsRef<std::ffi::OsStr>>,
+    env: impl IntoIterator<Item = (String, String)>,
+) -> anyhow::Result<i32> {
+    let status = tokio::process::Command::new(path)
+        .args(args)
+        .envs(env)
+        .status()
+        .await
+        .with_context(|| format!("failed to run plugin [{}]", path.display()))?;
+    if let Some(code) = status.code() {
+        return Ok(code);
+    }
+    // The plugin was killed by a signal, which shells report as 128 + the signal number
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return Ok(128 + signal);
+    }
+    Ok(1)
+}
+
+/// Names among `candidates` close enough to the unknown subcommand `name` to be suggested,
+/// closest first
+#[must_use]
+pub fn suggest_plugins<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let mut suggestions: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = edit_distance(name, candidate);
+            (distance <= MAX_SUGGESTION_DISTANCE
+                || (name.len() >= 3 && candidate.starts_with(name)))
+            .then_some((distance, candidate))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions.dedup();
+    suggestions
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    fn write_executable(dir: &Path, name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_external_plugins() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let deploy = write_executable(first.path(), "wash-acme-deploy");
+        write_executable(second.path(), "wash-acme-deploy");
+        let lint = write_executable(second.path(), "wash-acme-lint");
+        // Neither executable nor prefixed files are plugins
+        std::fs::write(second.path().join("wash-readme"), "not a plugin").unwrap();
+        write_executable(second.path(), "acme-build");
+
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(
+            find_external_plugin_in("acme-deploy", &path),
+            Some(deploy.clone())
+        );
+        assert_eq!(find_external_plugin_in("readme", &path), None);
+        assert_eq!(find_external_plugin_in("../wash-acme-lint", &path), None);
+        assert_eq!(
+            external_plugins_in(&path),
+            BTreeMap::from([
+                ("acme-deploy".to_string(), deploy),
+                ("acme-lint".to_string(), lint),
+            ])
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_external_plugin() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wash-exit");
+        std::fs::write(&path, "#!/bin/sh\nexit \"$WASH_TEST_EXIT_CODE\"\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let code = run_external_plugin(
+            &path,
+            ["--flag"],
+            [("WASH_TEST_EXIT_CODE".to_string(), "3".to_string())],
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 3);
+    }
+
+    #[test]
+    fn test_suggest_plugins() {
+        let candidates = ["acme-deploy", "acme-lint", "hello", "dev"];
+        assert_eq!(
+            suggest_plugins("acme-deplyo", candidates),
+            vec!["acme-deploy"]
+        );
+        assert_eq!(
+            suggest_plugins("acme", candidates),
+            vec!["acme-lint", "acme-deploy"]
+        );
+        assert_eq!(suggest_plugins("helo", candidates), vec!["hello"]);
+        assert!(suggest_plugins("something-else", candidates).is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("deploy", "deploy"), 0);
+    }
+}
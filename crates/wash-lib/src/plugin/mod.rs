@@ -1,6 +1,11 @@
+use anyhow::Context as _;
+use wasmtime::component::Resource;
 use wasmtime_wasi::{WasiCtx, WasiView};
-use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::types::{HostFutureIncomingResponse, OutgoingRequest};
+use wasmtime_wasi_http::{HttpError, HttpResult, WasiHttpCtx, WasiHttpView};
 
+pub mod external;
 pub mod subcommand;
 
 /// The directory where plugins are stored.
@@ -10,6 +15,8 @@ struct Data {
     table: wasmtime::component::ResourceTable,
     ctx: WasiCtx,
     http: WasiHttpCtx,
+    /// Whether the plugin was granted outgoing HTTP access
+    allow_http: bool,
 }
 
 impl WasiView for Data {
@@ -30,4 +37,23 @@ impl WasiHttpView for Data {
     fn ctx(&mut self) -> &mut WasiHttpCtx {
         &mut self.http
     }
+
+    fn send_request(
+        &mut self,
+        request: OutgoingRequest,
+    ) -> HttpResult<Resource<HostFutureIncomingResponse>>
+    where
+        Self: Sized,
+    {
+        if self.allow_http {
+            return wasmtime_wasi_http::types::default_send_request(self, request);
+        }
+        let res = HostFutureIncomingResponse::new(wasmtime_wasi::runtime::spawn(async {
+            Ok(Err(ErrorCode::HttpRequestDenied))
+        }));
+        WasiHttpView::table(self)
+            .push(res)
+            .context("failed to push response")
+            .map_err(HttpError::trap)
+    }
 }
@@ -5,25 +5,78 @@ mod bindings {
     });
 }
 
-pub use bindings::exports::wasmcloud::wash::subcommand::Metadata;
+mod plugin_bindings {
+    wasmtime::component::bindgen!({
+        world: "plugin",
+        async: true,
+    });
+}
+
+pub use bindings::exports::wasmcloud::wash::subcommand::{Argument, Metadata};
 use bindings::Subcommands;
+use plugin_bindings::exports::wasmcloud::wash::subcommand as plugin_subcommand;
+pub use plugin_bindings::exports::wasmcloud::wash_plugin::plugin::Output as PluginOutput;
+use plugin_bindings::Plugin;
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Ok};
+use serde::Deserialize;
 use wasmtime::component::{Component, Linker};
 use wasmtime::{Config, Engine};
 use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
 use wasmtime_wasi_http::WasiHttpCtx;
 
+use super::external::CONTEXT_JSON_ENV;
 use super::Data;
 
 const DIRECTORY_ALLOW: DirPerms = DirPerms::all();
 const DIRECTORY_DENY: DirPerms = DirPerms::READ;
 
+/// Name of the directory, within the plugin directory, holding the permissions of plugins
+pub const PERMISSIONS_DIR: &str = "permissions";
+
+/// An instantiated plugin of either kind
+enum PluginInstance {
+    /// A plugin of the `subcommands` world, run through `wasi:cli/run`
+    Subcommand(Subcommands),
+    /// A plugin of the `plugin` world, run through `wasmcloud:wash-plugin/plugin` and returning
+    /// structured output
+    Plugin(Plugin),
+}
+
+impl From<plugin_subcommand::Argument> for Argument {
+    fn from(arg: plugin_subcommand::Argument) -> Self {
+        Self {
+            description: arg.description,
+            is_path: arg.is_path,
+            required: arg.required,
+        }
+    }
+}
+
+impl From<plugin_subcommand::Metadata> for Metadata {
+    fn from(metadata: plugin_subcommand::Metadata) -> Self {
+        let convert = |args: Vec<(String, plugin_subcommand::Argument)>| {
+            args.into_iter()
+                .map(|(name, arg)| (name, arg.into()))
+                .collect()
+        };
+        Self {
+            name: metadata.name,
+            id: metadata.id,
+            version: metadata.version,
+            author: metadata.author,
+            description: metadata.description,
+            flags: convert(metadata.flags),
+            arguments: convert(metadata.arguments),
+        }
+    }
+}
+
 struct InstanceData {
-    instance: Subcommands,
+    instance: PluginInstance,
     metadata: Metadata,
     loaded_path: PathBuf,
     store: wasmtime::Store<Data>,
@@ -45,6 +98,61 @@ pub struct DirMapping {
     pub component_path: Option<String>,
 }
 
+/// Access granted to a plugin beyond its scratch directory and the paths passed to it as arguments.
+/// Plugins have no network access and no access to other files unless granted in
+/// `<plugin dir>/permissions/<plugin id>.toml`, e.g.
+///
+/// ```toml
+/// http = true
+/// dirs = ["/home/me/projects"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginPermissions {
+    /// Whether the plugin may make outgoing HTTP requests
+    pub http: bool,
+    /// Whether the plugin may open network sockets
+    pub sockets: bool,
+    /// Host directories the plugin may read and write, available at the same path in the plugin
+    pub dirs: Vec<PathBuf>,
+}
+
+impl PluginPermissions {
+    /// Path of the permissions file of the plugin with the given ID
+    pub fn path(plugin_dir: impl AsRef<Path>, plugin_id: &str) -> PathBuf {
+        plugin_dir
+            .as_ref()
+            .join(PERMISSIONS_DIR)
+            .join(format!("{plugin_id}.toml"))
+    }
+
+    /// Load the permissions of the plugin with the given ID, granting nothing if the plugin has no
+    /// permissions file
+    pub async fn load(plugin_dir: impl AsRef<Path>, plugin_id: &str) -> anyhow::Result<Self> {
+        let path = Self::path(plugin_dir, plugin_id);
+        let raw = match tokio::fs::read_to_string(&path).await {
+            Result::Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Unable to read plugin permissions at {}", path.display())
+                })
+            }
+        };
+        toml::from_str(&raw)
+            .with_context(|| format!("Invalid plugin permissions at {}", path.display()))
+    }
+}
+
+/// Options for running a plugin with [`SubcommandRunner::run_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Access granted to the plugin
+    pub permissions: PluginPermissions,
+    /// Environment variables passed to the plugin, such as the active context
+    pub env: Vec<(String, String)>,
+}
+
 impl SubcommandRunner {
     /// Creates a new subcommand runner with no plugins loaded.
     pub fn new() -> anyhow::Result<Self> {
@@ -103,6 +211,7 @@ impl SubcommandRunner {
             table: wasmtime::component::ResourceTable::default(),
             ctx,
             http: WasiHttpCtx,
+            allow_http: false,
         };
 
         let mut store = wasmtime::Store::new(&self.engine, ctx);
@@ -119,11 +228,27 @@ impl SubcommandRunner {
             |state: &mut Data| state,
         )?;
 
-        let (instance, _) = Subcommands::instantiate_async(&mut store, &component, &linker).await?;
-        let metadata = instance
-            .wasmcloud_wash_subcommand()
-            .call_register(&mut store)
-            .await?;
+        let instance = linker.instantiate_async(&mut store, &component).await?;
+        // Plugins returning structured output are preferred, plugins only exporting
+        // `wasi:cli/run` are still supported
+        let (instance, metadata) = match Plugin::new(&mut store, &instance) {
+            Result::Ok(plugin) => {
+                let metadata = plugin
+                    .wasmcloud_wash_subcommand()
+                    .call_register(&mut store)
+                    .await?;
+                (PluginInstance::Plugin(plugin), metadata.into())
+            }
+            Err(_) => {
+                let subcommand = Subcommands::new(&mut store, &instance)
+                    .context("Component is neither a subcommand plugin nor a plugin")?;
+                let metadata = subcommand
+                    .wasmcloud_wash_subcommand()
+                    .call_register(&mut store)
+                    .await?;
+                (PluginInstance::Subcommand(subcommand), metadata)
+            }
+        };
         let maybe_existing = self.plugins.insert(
             metadata.id.clone(),
             InstanceData {
@@ -162,6 +287,14 @@ impl SubcommandRunner {
         self.plugins.get(id).map(|p| p.loaded_path.as_path())
     }
 
+    /// Returns whether the plugin with the given ID returns structured output when run, i.e.
+    /// implements the `plugin` world rather than the `subcommands` world
+    pub fn returns_output(&self, id: &str) -> bool {
+        self.plugins
+            .get(id)
+            .is_some_and(|p| matches!(p.instance, PluginInstance::Plugin(_)))
+    }
+
     /// Run a subcommand with the given name and args. The plugin will inherit all
     /// stdout/stderr/stdin/env. The given plugin_dirs will be mapped into the plugin after
     /// canonicalizing all paths and normalizing them to use `/` instead of `\`. An error will only
@@ -170,13 +303,34 @@ impl SubcommandRunner {
     ///
     /// All plugins will be passed environment variables starting with
     /// `WASH_PLUGIN_${plugin_id.to_upper()}_` from the current process. Other vars will be ignored
+    ///
+    /// Plugins run this way are granted no access beyond their plugin dir and the given dirs, see
+    /// [`run_with_options`](Self::run_with_options) to grant more
+    ///
+    /// Plugins implementing the `plugin` world return their structured output, which the caller is
+    /// expected to print. Other plugins print their output themselves and return `None`
     pub async fn run(
+        &mut self,
+        plugin_id: &str,
+        plugin_dir: PathBuf,
+        dirs: Vec<DirMapping>,
+        args: Vec<String>,
+    ) -> anyhow::Result<Option<PluginOutput>> {
+        self.run_with_options(plugin_id, plugin_dir, dirs, args, RunOptions::default())
+            .await
+    }
+
+    /// Same as [`run`](Self::run), additionally granting the plugin the given permissions and
+    /// passing it the given environment variables. Plugins returning structured output are passed
+    /// the context in [`CONTEXT_JSON_ENV`] as their context JSON
+    pub async fn run_with_options(
         &mut self,
         plugin_id: &str,
         plugin_dir: PathBuf,
         dirs: Vec<DirMapping>,
         mut args: Vec<String>,
-    ) -> anyhow::Result<()> {
+        options: RunOptions,
+    ) -> anyhow::Result<Option<PluginOutput>> {
         let plugin = self
             .plugins
             .get_mut(plugin_id)
@@ -252,21 +406,51 @@ impl SubcommandRunner {
                 })?;
             *matching = str_canonical;
         }
-        // Disable socket connections for now. We may gradually open this up later
-        ctx.socket_addr_check(|_, _| false)
+        for dir in &options.permissions.dirs {
+            let canonicalized = tokio::fs::canonicalize(dir).await.with_context(|| {
+                format!("Error when canonicalizing permitted dir {}", dir.display())
+            })?;
+            let guest_path = canonicalized.to_str().ok_or_else(|| anyhow::anyhow!("Permitted dir cannot be converted to a string for use in a plugin. This is a limitation of the WASI API"))?.to_string();
+            #[cfg(target_family = "windows")]
+            let guest_path = guest_path.replace('\\', "/");
+            ctx.preopened_dir(canonicalized, guest_path, DIRECTORY_ALLOW, FilePerms::all())
+                .context("Error when preopening permitted dir")?;
+        }
+        // Socket connections are denied unless granted in the plugin permissions
+        let allow_sockets = options.permissions.sockets;
+        ctx.socket_addr_check(move |_, _| allow_sockets)
+            .allow_ip_name_lookup(allow_sockets)
             .inherit_stdio()
             .preopened_dir(plugin_dir, "/", DIRECTORY_ALLOW, FilePerms::all())
             .context("Error when preopening plugin dir")?
             .args(&args)
-            .envs(&vars);
+            .envs(&vars)
+            .envs(&options.env);
 
-        plugin.store.data_mut().ctx = ctx.build();
-        plugin
-            .instance
-            .wasi_cli_run()
-            .call_run(&mut plugin.store)
-            .await
-            .context("Error when running wasm component")?
-            .map_err(|_| anyhow::anyhow!("Error when running subcommand"))
+        let context_json = options
+            .env
+            .iter()
+            .find(|(key, _)| key == CONTEXT_JSON_ENV)
+            .map_or_else(|| "null".to_string(), |(_, json)| json.clone());
+
+        let data = plugin.store.data_mut();
+        data.ctx = ctx.build();
+        data.allow_http = options.permissions.http;
+        match &plugin.instance {
+            PluginInstance::Subcommand(instance) => instance
+                .wasi_cli_run()
+                .call_run(&mut plugin.store)
+                .await
+                .context("Error when running wasm component")?
+                .map(|()| None)
+                .map_err(|_| anyhow::anyhow!("Error when running subcommand")),
+            PluginInstance::Plugin(instance) => instance
+                .wasmcloud_wash_plugin_plugin()
+                .call_run(&mut plugin.store, &args, &context_json)
+                .await
+                .context("Error when running wasm component")?
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("Error when running plugin: {e}")),
+        }
     }
 }
@@ -6,14 +6,18 @@ use std::{
 };
 
 use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
 use oci_distribution::manifest::OciImageManifest;
 use oci_distribution::{
-    client::{Client, ClientConfig, ClientProtocol, Config, ImageLayer},
+    client::{
+        Certificate, CertificateEncoding, Client, ClientConfig, ClientProtocol, Config, ImageLayer,
+    },
     secrets::RegistryAuth,
     Reference,
 };
 use oci_wasm::{ToConfig, WasmConfig, WASM_LAYER_MEDIA_TYPE, WASM_MANIFEST_MEDIA_TYPE};
 use provider_archive::ProviderArchive;
+use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
@@ -25,6 +29,154 @@ const PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE: &str =
 const WASM_MEDIA_TYPE: &str = "application/vnd.module.wasm.content.layer.v1+wasm";
 const OCI_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
 
+/// Environment variable holding a JSON object of [`RegistryConfig`]s keyed by registry, which
+/// take precedence over the `registries` of the wash context
+pub const REGISTRIES_ENV: &str = "WASH_REGISTRIES";
+
+/// Configuration of a registry, set in the `registries` section of a wash context (keyed by the
+/// hostname of the registry, including the port if any) or in [`REGISTRIES_ENV`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegistryConfig {
+    /// Registry, optionally followed by a repository prefix, to pull and push artifacts of this
+    /// registry through instead, e.g. `mirror.example.com/ghcr`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    /// Username to authenticate with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Name of the environment variable holding the password to authenticate with, so that the
+    /// password itself is not stored in the configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>,
+    /// Whether to allow insecure (HTTP) connections
+    #[serde(default)]
+    pub insecure: bool,
+    /// Path to a PEM-encoded CA certificate to trust in addition to the native root certificates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<PathBuf>,
+}
+
+/// An artifact reference resolved against the configuration of its registry, see
+/// [`resolve_reference`]
+#[derive(Clone, Debug)]
+pub struct ResolvedReference {
+    /// The reference to pull or push, pointing to the mirror of the registry if one is configured
+    pub reference: Reference,
+    /// The reference as given, if it was rewritten to point to a mirror
+    pub original: Option<Reference>,
+    /// Username configured for the registry
+    pub user: Option<String>,
+    /// Password configured for the registry
+    pub password: Option<String>,
+    /// Whether insecure (HTTP) connections are allowed for the registry
+    pub insecure: bool,
+    /// CA certificate configured for the registry
+    pub ca_file: Option<PathBuf>,
+}
+
+/// Returns the `registries` configuration of a wash context, overridden by the registries set in
+/// [`REGISTRIES_ENV`]
+pub fn registries_with_env(
+    registries: &HashMap<String, RegistryConfig>,
+) -> Result<HashMap<String, RegistryConfig>> {
+    let mut registries = registries.clone();
+    if let Ok(env) = std::env::var(REGISTRIES_ENV) {
+        let overrides: HashMap<String, RegistryConfig> = serde_json::from_str(&env)
+            .with_context(|| format!("failed to parse registries from {REGISTRIES_ENV}"))?;
+        registries.extend(overrides);
+    }
+    Ok(registries)
+}
+
+/// Resolve `reference` against the configuration of its registry in `registries`: the reference is
+/// rewritten to point to the mirror of the registry, if any, and the credentials and connection
+/// settings of the registry are returned along with it.
+///
+/// The settings of the registry of the original reference apply when connecting to its mirror.
+pub fn resolve_reference(
+    reference: &Reference,
+    registries: &HashMap<String, RegistryConfig>,
+) -> Result<ResolvedReference> {
+    let Some(config) = registries.get(reference.registry()) else {
+        return Ok(ResolvedReference {
+            reference: reference.clone(),
+            original: None,
+            user: None,
+            password: None,
+            insecure: false,
+            ca_file: None,
+        });
+    };
+    let password = config
+        .password_env
+        .as_ref()
+        .map(|var| {
+            std::env::var(var).with_context(|| {
+                format!(
+                    "environment variable [{var}] holding the password of registry [{}] is not set",
+                    reference.registry()
+                )
+            })
+        })
+        .transpose()?;
+    let (resolved, original) = match &config.mirror {
+        Some(mirror) => (
+            mirror_reference(reference, mirror)?,
+            Some(reference.clone()),
+        ),
+        None => (reference.clone(), None),
+    };
+    Ok(ResolvedReference {
+        reference: resolved,
+        original,
+        user: config.username.clone(),
+        password,
+        insecure: config.insecure,
+        ca_file: config.ca_file.clone(),
+    })
+}
+
+/// Rewrite the artifact reference `artifact_ref` to point to the mirror of its registry, returning
+/// `None` if it is not an OCI reference or no mirror is configured for its registry
+pub fn mirror_artifact_ref(
+    artifact_ref: &str,
+    registries: &HashMap<String, RegistryConfig>,
+) -> Result<Option<String>> {
+    if artifact_ref.starts_with("file://") {
+        return Ok(None);
+    }
+    let Ok(reference) = artifact_ref.parse::<Reference>() else {
+        return Ok(None);
+    };
+    match registries
+        .get(reference.registry())
+        .and_then(|config| config.mirror.as_ref())
+    {
+        Some(mirror) => Ok(Some(mirror_reference(&reference, mirror)?.whole())),
+        None => Ok(None),
+    }
+}
+
+/// Rewrite `reference` to point to the same repository, tag and digest in `mirror`
+fn mirror_reference(reference: &Reference, mirror: &str) -> Result<Reference> {
+    let mut mirrored = format!(
+        "{}/{}",
+        mirror.trim().trim_end_matches('/'),
+        reference.repository()
+    );
+    if let Some(tag) = reference.tag() {
+        mirrored.push(':');
+        mirrored.push_str(tag);
+    }
+    if let Some(digest) = reference.digest() {
+        mirrored.push('@');
+        mirrored.push_str(digest);
+    }
+    mirrored
+        .parse()
+        .with_context(|| format!("invalid mirror [{mirror}] for [{}]", reference.whole()))
+}
+
 /// Additional options for pulling an OCI artifact
 #[derive(Default)]
 pub struct OciPullOptions {
@@ -40,6 +192,8 @@ pub struct OciPullOptions {
     pub insecure: bool,
     /// Whether or not OCI registry's certificate will be checked for validity. This will make your HTTPS connections insecure.
     pub insecure_skip_tls_verify: bool,
+    /// An optional PEM-encoded CA certificate to trust in addition to the native root certificates
+    pub ca_file: Option<PathBuf>,
 }
 
 /// Additional options for pushing an OCI artifact
@@ -57,8 +211,15 @@ pub struct OciPushOptions {
     pub insecure: bool,
     /// Whether or not OCI registry's certificate will be checked for validity. This will make your HTTPS connections insecure.
     pub insecure_skip_tls_verify: bool,
+    /// An optional PEM-encoded CA certificate to trust in addition to the native root certificates
+    pub ca_file: Option<PathBuf>,
     /// Optional annotations you'd like to add to the pushed artifact
     pub annotations: Option<HashMap<String, String>>,
+    /// Whether to normalize the artifact so that pushing the same component always results in the
+    /// same digest, see [`push_oci_artifact`]
+    pub reproducible: bool,
+    /// The digest the artifact is expected to have. The push is aborted if the digest differs
+    pub expected_digest: Option<String>,
 }
 
 /// The types of artifacts that wash supports
@@ -70,6 +231,7 @@ pub enum SupportedArtifacts {
 }
 
 /// An enum indicating the type of artifact that was pulled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArtifactType {
     Par,
     Wasm,
@@ -129,16 +291,12 @@ pub async fn pull_oci_artifact(image_ref: &Reference, options: OciPullOptions) -
         }
     }
 
-    let client = Client::new(ClientConfig {
-        protocol: if options.insecure {
-            ClientProtocol::Http
-        } else {
-            ClientProtocol::Https
-        },
-        extra_root_certificates: tls::NATIVE_ROOTS_OCI.to_vec(),
-        accept_invalid_certificates: options.insecure_skip_tls_verify,
-        ..Default::default()
-    });
+    let client = oci_client(
+        options.insecure,
+        options.insecure_skip_tls_verify,
+        options.ca_file.as_deref(),
+    )
+    .await?;
 
     let auth = match (options.user, options.password) {
         (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
@@ -179,7 +337,121 @@ pub async fn pull_oci_artifact(image_ref: &Reference, options: OciPullOptions) -
         .collect::<Vec<_>>())
 }
 
+/// Fetch the manifest of the artifact at `reference` without pulling its layers, using the
+/// authentication and connection settings of `options`
+pub async fn fetch_oci_manifest(
+    reference: &Reference,
+    options: OciPullOptions,
+) -> Result<OciImageManifest> {
+    let client = oci_client(
+        options.insecure,
+        options.insecure_skip_tls_verify,
+        options.ca_file.as_deref(),
+    )
+    .await?;
+    let auth = match (options.user, options.password) {
+        (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
+        _ => RegistryAuth::Anonymous,
+    };
+    let (manifest, _digest) = client.pull_image_manifest(reference, &auth).await?;
+    Ok(manifest)
+}
+
+/// Type of the artifact described by an OCI manifest, from the media types of its config and
+/// layers, or `None` if it is neither a component nor a provider archive
+#[must_use]
+pub fn manifest_artifact_type(manifest: &OciImageManifest) -> Option<ArtifactType> {
+    let media_types: Vec<&str> = std::iter::once(manifest.config.media_type.as_str())
+        .chain(
+            manifest
+                .layers
+                .iter()
+                .map(|layer| layer.media_type.as_str()),
+        )
+        .collect();
+    if media_types.iter().any(|media_type| {
+        *media_type == PROVIDER_ARCHIVE_MEDIA_TYPE
+            || *media_type == PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE
+    }) {
+        Some(ArtifactType::Par)
+    } else if media_types
+        .iter()
+        .any(|media_type| *media_type == WASM_MEDIA_TYPE || *media_type == WASM_LAYER_MEDIA_TYPE)
+    {
+        Some(ArtifactType::Wasm)
+    } else {
+        None
+    }
+}
+
+/// List the tags of the repository of `reference`, using the authentication and connection settings
+/// of `options`
+pub async fn list_oci_tags(reference: &Reference, options: OciPullOptions) -> Result<Vec<String>> {
+    let client = oci_client(
+        options.insecure,
+        options.insecure_skip_tls_verify,
+        options.ca_file.as_deref(),
+    )
+    .await?;
+    let auth = match (options.user, options.password) {
+        (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
+        _ => RegistryAuth::Anonymous,
+    };
+    let response = client
+        .list_tags(reference, &auth, None, None)
+        .await
+        .with_context(|| format!("failed to list tags of [{}]", reference.repository()))?;
+    Ok(response.tags)
+}
+
+/// Returns an OCI client, trusting the certificate in `ca_file` in addition to the native roots
+async fn oci_client(
+    insecure: bool,
+    insecure_skip_tls_verify: bool,
+    ca_file: Option<&Path>,
+) -> Result<Client> {
+    let mut extra_root_certificates = tls::NATIVE_ROOTS_OCI.to_vec();
+    if let Some(ca_file) = ca_file {
+        let data = tokio::fs::read(ca_file)
+            .await
+            .with_context(|| format!("failed to read CA file [{}]", ca_file.display()))?;
+        extra_root_certificates.push(Certificate {
+            encoding: CertificateEncoding::Pem,
+            data,
+        });
+    }
+    Ok(Client::new(ClientConfig {
+        protocol: if insecure {
+            ClientProtocol::Http
+        } else {
+            ClientProtocol::Https
+        },
+        extra_root_certificates,
+        accept_invalid_certificates: insecure_skip_tls_verify,
+        ..Default::default()
+    }))
+}
+
+/// Returns the creation time of reproducible artifacts, taken from `SOURCE_DATE_EPOCH` if set and
+/// the Unix epoch otherwise
+fn reproducible_created_at() -> Result<DateTime<Utc>> {
+    let secs = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid SOURCE_DATE_EPOCH [{epoch}]"))?,
+        Err(_) => 0,
+    };
+    DateTime::from_timestamp(secs, 0)
+        .with_context(|| format!("SOURCE_DATE_EPOCH [{secs}] is out of range"))
+}
+
 /// Pushes the artifact to the given repo and returns a tuple containing the tag (if one was set) and the digest
+///
+/// If [`OciPushOptions::reproducible`] is set, components are normalized before pushing: the creation
+/// time of their configuration is set to `SOURCE_DATE_EPOCH` (or the Unix epoch) and embedded
+/// claims, which contain their time of issue, are removed. Annotations never affect the digest
+/// through their ordering, since the manifest digest is computed with sorted keys.
 pub async fn push_oci_artifact(
     url: String,
     artifact: impl AsRef<Path>,
@@ -197,7 +469,17 @@ pub async fn push_oci_artifact(
         .with_context(|| format!("failed to open artifact [{}]", artifact.as_ref().display()))?;
     f.read_to_end(&mut artifact_buf).await?;
 
-    let (config, layer, is_wasm) = match parse_and_validate_artifact(&artifact_buf).await? {
+    let created_at = if options.reproducible {
+        if wasmparser::Parser::is_component(&artifact_buf) {
+            artifact_buf = wascap::wasm::strip_claims(&artifact_buf)
+                .context("failed to strip claims from component")?;
+        }
+        Some(reproducible_created_at()?)
+    } else {
+        None
+    };
+
+    let (config, layer, is_wasm) = match parse_artifact(&artifact_buf, created_at).await? {
         SupportedArtifacts::Wasm(conf, layer) => (conf, layer, true),
         SupportedArtifacts::Par(mut conf, layer) => {
             let mut config_buf = vec![];
@@ -220,16 +502,12 @@ pub async fn push_oci_artifact(
 
     let layers = vec![layer];
 
-    let client = Client::new(ClientConfig {
-        protocol: if options.insecure {
-            ClientProtocol::Http
-        } else {
-            ClientProtocol::Https
-        },
-        extra_root_certificates: tls::NATIVE_ROOTS_OCI.to_vec(),
-        accept_invalid_certificates: options.insecure_skip_tls_verify,
-        ..Default::default()
-    });
+    let client = oci_client(
+        options.insecure,
+        options.insecure_skip_tls_verify,
+        options.ca_file.as_deref(),
+    )
+    .await?;
 
     let auth = match (options.user, options.password) {
         (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
@@ -252,6 +530,11 @@ pub async fn push_oci_artifact(
     // is/are the prevailing implementation.
     let digest =
         serde_json::to_value(&manifest).map(|value| sha256_digest(value.to_string().as_bytes()))?;
+    if let Some(expected_digest) = options.expected_digest {
+        if expected_digest != digest {
+            bail!("digest of artifact [{digest}] does not match expected digest [{expected_digest}], aborting push");
+        }
+    }
 
     client
         .push(&image, &layers, config, &auth, Some(manifest))
@@ -262,10 +545,19 @@ pub async fn push_oci_artifact(
 /// Helper function to determine artifact type and parse it into a config and layer ready for use in
 /// pushing to OCI
 pub async fn parse_and_validate_artifact(artifact: &[u8]) -> Result<SupportedArtifacts> {
+    parse_artifact(artifact, None).await
+}
+
+/// Parses an artifact like [`parse_and_validate_artifact`], overriding the creation time of
+/// component configuration if `created_at` is set
+async fn parse_artifact(
+    artifact: &[u8],
+    created_at: Option<DateTime<Utc>>,
+) -> Result<SupportedArtifacts> {
     // NOTE(thomastaylor312): I don't like having to clone here, but we need to either clone here or
     // later when calling parse_component/parse_provider_archive. If this gets to be a
     // problem, we can always change this, but it is a CLI, so _shrug_
-    match parse_component(artifact.to_owned()) {
+    match parse_component(artifact.to_owned(), created_at) {
         Ok(art) => Ok(art),
         Err(_) => match parse_provider_archive(artifact).await {
             Ok(art) => Ok(art),
@@ -287,8 +579,14 @@ pub async fn identify_artifact(artifact: &[u8]) -> Result<ArtifactType> {
 }
 
 /// Attempts to parse the wit from a component. Fails if it isn't a component
-fn parse_component(artifact: Vec<u8>) -> Result<SupportedArtifacts> {
-    let (conf, layer) = WasmConfig::from_raw_component(artifact, None)?;
+fn parse_component(
+    artifact: Vec<u8>,
+    created_at: Option<DateTime<Utc>>,
+) -> Result<SupportedArtifacts> {
+    let (mut conf, layer) = WasmConfig::from_raw_component(artifact, None)?;
+    if let Some(created_at) = created_at {
+        conf.created = created_at;
+    }
     Ok(SupportedArtifacts::Wasm(conf.to_config()?, layer))
 }
 
@@ -310,3 +608,103 @@ async fn parse_provider_archive(artifact: &[u8]) -> Result<SupportedArtifacts> {
         Err(e) => bail!("Invalid provider archive: {}", e),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn registries() -> HashMap<String, RegistryConfig> {
+        HashMap::from([
+            (
+                "ghcr.io".to_string(),
+                RegistryConfig {
+                    mirror: Some("localhost:5001/ghcr/".to_string()),
+                    username: Some("mirror-user".to_string()),
+                    password_env: Some("WASH_TEST_MIRROR_PASSWORD".to_string()),
+                    insecure: true,
+                    ca_file: None,
+                },
+            ),
+            (
+                "registry.example.com".to_string(),
+                RegistryConfig {
+                    password_env: Some("WASH_TEST_UNSET_PASSWORD".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_resolve_reference() -> Result<()> {
+        std::env::set_var("WASH_TEST_MIRROR_PASSWORD", "s3cr3t");
+        let registries = registries();
+
+        let reference: Reference = "ghcr.io/wasmcloud/components/echo:0.1.0".parse()?;
+        let resolved = resolve_reference(&reference, &registries)?;
+        assert_eq!(
+            resolved.reference.whole(),
+            "localhost:5001/ghcr/wasmcloud/components/echo:0.1.0"
+        );
+        assert_eq!(
+            resolved.original.map(|r| r.whole()),
+            Some(reference.whole())
+        );
+        assert_eq!(resolved.user.as_deref(), Some("mirror-user"));
+        assert_eq!(resolved.password.as_deref(), Some("s3cr3t"));
+        assert!(resolved.insecure);
+
+        // Digests are kept when rewriting
+        let digest = "sha256:a17a163afa8447622055deb049587641a9e23243a6cc4411eb33bd4267214cf3";
+        let reference: Reference = format!("ghcr.io/wasmcloud/echo@{digest}").parse()?;
+        let resolved = resolve_reference(&reference, &registries)?;
+        assert_eq!(resolved.reference.digest(), Some(digest));
+        assert_eq!(resolved.reference.registry(), "localhost:5001");
+
+        // Registries without configuration are left as-is
+        let reference: Reference = "wasmcloud.azurecr.io/echo:0.2.0".parse()?;
+        let resolved = resolve_reference(&reference, &registries)?;
+        assert_eq!(resolved.reference.whole(), reference.whole());
+        assert!(resolved.original.is_none() && resolved.password.is_none());
+
+        // Passwords referencing unset environment variables are an error
+        let reference: Reference = "registry.example.com/echo:0.2.0".parse()?;
+        assert!(resolve_reference(&reference, &registries).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mirror_artifact_ref() -> Result<()> {
+        let registries = registries();
+        assert_eq!(
+            mirror_artifact_ref("ghcr.io/wasmcloud/echo:0.1.0", &registries)?.as_deref(),
+            Some("localhost:5001/ghcr/wasmcloud/echo:0.1.0")
+        );
+        assert_eq!(
+            mirror_artifact_ref("file:///tmp/echo.wasm", &registries)?,
+            None
+        );
+        assert_eq!(
+            mirror_artifact_ref("registry.example.com/echo:0.2.0", &registries)?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_config_parsing() -> Result<()> {
+        let registries: HashMap<String, RegistryConfig> = serde_json::from_str(
+            r#"{"ghcr.io": {"mirror": "localhost:5001", "insecure": true}, "docker.io": {}}"#,
+        )?;
+        assert_eq!(
+            registries["ghcr.io"],
+            RegistryConfig {
+                mirror: Some("localhost:5001".to_string()),
+                insecure: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(registries["docker.io"], RegistryConfig::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Resource limits applied to a wasmCloud host, and the providers it spawns.
+//!
+//! Limits are only supported on Linux:
+//! - the memory and CPU weight limits are applied to a transient cgroup scope created with
+//!   `systemd-run --scope` for the host process tree, which requires cgroups v2 and a systemd
+//!   manager which delegates the `memory` and `cpu` controllers
+//! - the open files limit is set on the host process with `setrlimit` before it is executed, and
+//!   inherited by the providers it spawns
+
+#[cfg(target_os = "linux")]
+use std::process::Stdio;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use tokio::process::Command;
+use tracing::warn;
+
+/// Resource limits of a wasmCloud host process tree
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProcessLimits {
+    /// Maximum memory usage in bytes, the cgroup `memory.max`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    /// CPU weight between 1 and 10000, the cgroup `cpu.weight`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_weight: Option<u16>,
+    /// Maximum number of open files, the `RLIMIT_NOFILE` resource limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nofile: Option<u64>,
+}
+
+impl ProcessLimits {
+    /// Whether no limit is set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a limit requiring a cgroup is set
+    #[must_use]
+    pub fn requires_cgroup(&self) -> bool {
+        self.memory_bytes.is_some() || self.cpu_weight.is_some()
+    }
+}
+
+/// Report a limit that could not be applied, failing if `strict` is set
+#[cfg(target_os = "linux")]
+fn limit_failure(strict: bool, message: String) -> Result<()> {
+    if strict {
+        anyhow::bail!("{message}");
+    }
+    warn!("{message}, ignoring");
+    Ok(())
+}
+
+/// Command wrapping the host command to apply limits, and the limits which will be applied
+pub(crate) struct LimitsPlan {
+    /// Program and arguments to execute the host command with, if any
+    pub wrapper: Option<Vec<String>>,
+    /// Limits which will be applied
+    pub applied: ProcessLimits,
+}
+
+/// Plan how to apply `limits`, failing if a limit cannot be applied and `strict` is set
+#[cfg(target_os = "linux")]
+pub(crate) async fn plan_limits(limits: &ProcessLimits, strict: bool) -> Result<LimitsPlan> {
+    use nix::sys::resource::{getrlimit, Resource};
+
+    let mut applied = ProcessLimits::default();
+    let is_root = nix::unistd::geteuid().is_root();
+
+    if let Some(nofile) = limits.nofile {
+        let (_, hard) = getrlimit(Resource::RLIMIT_NOFILE)?;
+        if nofile > hard && !is_root {
+            limit_failure(
+                strict,
+                format!("open files limit {nofile} is above the hard limit of {hard}"),
+            )?;
+        } else {
+            applied.nofile = Some(nofile);
+        }
+    }
+
+    if !limits.requires_cgroup() {
+        return Ok(LimitsPlan {
+            wrapper: None,
+            applied,
+        });
+    }
+
+    let mut wrapper = vec!["systemd-run".to_string()];
+    // Unprivileged users can only create scopes in their own service manager
+    if !is_root {
+        wrapper.push("--user".to_string());
+    }
+    wrapper.extend(["--scope", "--quiet", "--collect"].map(String::from));
+    if let Some(memory_bytes) = limits.memory_bytes {
+        wrapper.extend(["-p".to_string(), format!("MemoryMax={memory_bytes}")]);
+    }
+    if let Some(cpu_weight) = limits.cpu_weight {
+        wrapper.extend(["-p".to_string(), format!("CPUWeight={cpu_weight}")]);
+    }
+    wrapper.push("--".to_string());
+
+    // Ensure the scope can be created before starting the host in it
+    let probe = Command::new(&wrapper[0])
+        .args(&wrapper[1..])
+        .arg("true")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await;
+    let probe_error = match probe {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(e) = probe_error {
+        limit_failure(
+            strict,
+            format!("failed to create a cgroup scope for memory and CPU limits: {e}"),
+        )?;
+        return Ok(LimitsPlan {
+            wrapper: None,
+            applied,
+        });
+    }
+
+    applied.memory_bytes = limits.memory_bytes;
+    applied.cpu_weight = limits.cpu_weight;
+    Ok(LimitsPlan {
+        wrapper: Some(wrapper),
+        applied,
+    })
+}
+
+/// Resource limits are only supported on Linux, any limit is ignored with a warning
+#[cfg(not(target_os = "linux"))]
+pub(crate) async fn plan_limits(limits: &ProcessLimits, _strict: bool) -> Result<LimitsPlan> {
+    if !limits.is_empty() {
+        warn!("resource limits are only supported on Linux, ignoring");
+    }
+    Ok(LimitsPlan {
+        wrapper: None,
+        applied: ProcessLimits::default(),
+    })
+}
+
+/// Set the open files limit of the process spawned by `cmd` before it is executed
+#[cfg(target_os = "linux")]
+pub(crate) fn set_nofile(cmd: &mut Command, nofile: u64) {
+    use nix::sys::resource::{setrlimit, Resource};
+
+    // SAFETY: `setrlimit` is async-signal-safe, and no memory is allocated in the closure
+    unsafe {
+        cmd.pre_exec(move || {
+            setrlimit(Resource::RLIMIT_NOFILE, nofile, nofile).map_err(Into::into)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProcessLimits;
+
+    #[test]
+    fn test_process_limits_serialization() {
+        assert_eq!(
+            serde_json::to_value(ProcessLimits::default()).unwrap(),
+            serde_json::json!({})
+        );
+        let limits = ProcessLimits {
+            memory_bytes: Some(512 * 1024 * 1024),
+            cpu_weight: Some(50),
+            nofile: None,
+        };
+        assert!(!limits.is_empty());
+        assert!(limits.requires_cgroup());
+        assert_eq!(
+            serde_json::to_value(limits).unwrap(),
+            serde_json::json!({ "memory_bytes": 536870912, "cpu_weight": 50 })
+        );
+    }
+}
@@ -77,6 +77,8 @@ pub async fn wait_for_server(url: &str, service: &str) -> Result<()> {
 
 mod github;
 pub(crate) use github::*;
+mod limits;
+pub use limits::ProcessLimits;
 mod nats;
 pub use nats::*;
 mod wadm;
@@ -1,11 +1,13 @@
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Context as _, Result};
+use serde::{Deserialize, Serialize};
 use tokio::fs::{metadata, write};
 use tokio::process::{Child, Command};
 use tracing::warn;
 
+use crate::config::{connect_host, host_port, unbracket_host};
 use crate::start::wait_for_server;
 
 use super::download_binary_from_github;
@@ -13,6 +15,18 @@ use super::download_binary_from_github;
 const NATS_GITHUB_RELEASE_URL: &str = "https://github.com/nats-io/nats-server/releases/download";
 pub const NATS_SERVER_CONF: &str = "nats.conf";
 pub const NATS_SERVER_PID: &str = "nats.pid";
+/// File recording the nodes of a NATS cluster started with [`start_nats_cluster`]
+pub const NATS_CLUSTER_FILE: &str = "nats-cluster.json";
+/// Name of the NATS cluster configured by [`NatsConfig::new_cluster`]
+pub const NATS_CLUSTER_NAME: &str = "wash";
+/// Minimum number of nodes in a local NATS cluster
+pub const NATS_CLUSTER_MIN_SIZE: u16 = 2;
+/// Maximum number of nodes in a local NATS cluster
+pub const NATS_CLUSTER_MAX_SIZE: u16 = 5;
+/// Offset from a node's client port to the port it listens on for cluster routes
+const NATS_CLUSTER_ROUTE_PORT_OFFSET: u16 = 2000;
+/// Offset from a node's client port to its websocket port, for all but the first node
+const NATS_CLUSTER_WEBSOCKET_PORT_OFFSET: u16 = 3000;
 #[cfg(target_family = "unix")]
 pub const NATS_SERVER_BINARY: &str = "nats-server";
 #[cfg(target_family = "windows")]
@@ -112,8 +126,22 @@ where
     .await
 }
 
-/// Configuration for a NATS server that supports running either in "standalone" or "leaf" mode.
-/// See the respective [`NatsConfig::new_standalone`] and [`NatsConfig::new_leaf`] implementations below for more information.
+/// Configuration of a single node in a local NATS cluster, see [`NatsConfig::new_cluster`]
+#[derive(Clone, Debug)]
+pub struct NatsClusterConfig {
+    /// Name of the cluster, shared by all nodes
+    pub name: String,
+    /// Unique name of this node within the cluster
+    pub server_name: String,
+    /// Port to listen on for routes from other nodes
+    pub port: u16,
+    /// Route URLs of all nodes in the cluster, e.g. `nats-route://127.0.0.1:6222`
+    pub routes: Vec<String>,
+}
+
+/// Configuration for a NATS server that supports running either in "standalone", "leaf" or "cluster" mode.
+/// See the respective [`NatsConfig::new_standalone`], [`NatsConfig::new_leaf`] and [`NatsConfig::new_cluster`]
+/// implementations below for more information.
 #[derive(Clone)]
 pub struct NatsConfig {
     pub host: String,
@@ -125,6 +153,8 @@ pub struct NatsConfig {
     pub remote_url: Option<String>,
     pub credentials: Option<PathBuf>,
     pub websocket_port: u16,
+    /// Cluster configuration, if this server is a node of a NATS cluster
+    pub cluster: Option<NatsClusterConfig>,
 }
 
 /// Returns a standalone NATS config with the following values:
@@ -134,6 +164,7 @@ pub struct NatsConfig {
 /// * `remote_url`: `None`
 /// * `credentials`: `None`
 /// * `websocket_port`: `4223`
+/// * `cluster`: `None`
 impl Default for NatsConfig {
     fn default() -> Self {
         NatsConfig {
@@ -144,6 +175,7 @@ impl Default for NatsConfig {
             remote_url: None,
             credentials: None,
             websocket_port: 4223,
+            cluster: None,
         }
     }
 }
@@ -177,6 +209,7 @@ impl NatsConfig {
             remote_url: Some(remote_url),
             credentials: Some(credentials),
             websocket_port,
+            cluster: None,
         }
     }
     /// Instantiates config for a standalone NATS server. Unless you're looking to extend
@@ -199,6 +232,63 @@ impl NatsConfig {
         }
     }
 
+    /// Instantiates config for each node of a local NATS cluster, useful for testing lattice behavior
+    /// across multiple NATS servers. Nodes listen on sequential client ports starting at `port`, and
+    /// on the client port + 2000 for routes to the other nodes. Only the first node uses `websocket_port`,
+    /// the remaining nodes listen for websocket connections on their client port + 3000.
+    ///
+    /// # Arguments
+    /// * `host`: NATS host to listen on, e.g. `127.0.0.1`
+    /// * `port`: NATS port of the first node in the cluster, e.g. `4222`
+    /// * `size`: Number of nodes in the cluster, between [`NATS_CLUSTER_MIN_SIZE`] and [`NATS_CLUSTER_MAX_SIZE`]
+    /// * `js_domain`: Jetstream domain to use, defaults to `core`. See [Configuring Jetstream](https://wasmcloud.dev/reference/lattice/jetstream/) for more information
+    /// * `websocket_port`: Websocket port of the first node in the cluster
+    pub fn new_cluster(
+        host: &str,
+        port: u16,
+        size: u16,
+        js_domain: Option<String>,
+        websocket_port: u16,
+    ) -> Result<Vec<Self>> {
+        ensure!(
+            (NATS_CLUSTER_MIN_SIZE..=NATS_CLUSTER_MAX_SIZE).contains(&size),
+            "NATS cluster size must be between {NATS_CLUSTER_MIN_SIZE} and {NATS_CLUSTER_MAX_SIZE}, got {size}"
+        );
+        ensure!(
+            port.checked_add(size - 1 + NATS_CLUSTER_WEBSOCKET_PORT_OFFSET)
+                .is_some(),
+            "NATS cluster ports starting at {port} are out of range"
+        );
+        let ports = (0..size).map(|i| port + i).collect::<Vec<_>>();
+        let routes = ports
+            .iter()
+            .map(|port| {
+                format!(
+                    "nats-route://{}",
+                    host_port(&connect_host(host), port + NATS_CLUSTER_ROUTE_PORT_OFFSET)
+                )
+            })
+            .collect::<Vec<_>>();
+        Ok(ports
+            .iter()
+            .enumerate()
+            .map(|(i, &node_port)| NatsConfig {
+                websocket_port: if i == 0 {
+                    websocket_port
+                } else {
+                    node_port + NATS_CLUSTER_WEBSOCKET_PORT_OFFSET
+                },
+                cluster: Some(NatsClusterConfig {
+                    name: NATS_CLUSTER_NAME.to_string(),
+                    server_name: format!("{NATS_CLUSTER_NAME}-{node_port}"),
+                    port: node_port + NATS_CLUSTER_ROUTE_PORT_OFFSET,
+                    routes: routes.clone(),
+                }),
+                ..NatsConfig::new_standalone(host, node_port, js_domain.clone())
+            })
+            .collect())
+    }
+
     async fn write_to_path<P>(self, path: P) -> Result<()>
     where
         P: AsRef<Path>,
@@ -235,6 +325,31 @@ websocket {{
 }}
                 "#
         );
+        let cluster_section = if let Some(cluster) = self.cluster {
+            let routes = cluster
+                .routes
+                .iter()
+                .map(|route| format!("        {route:?}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                r#"
+server_name: {:?}
+cluster {{
+    name: {:?}
+    listen: "{}"
+    routes = [
+{routes}
+    ]
+}}
+                "#,
+                cluster.server_name,
+                cluster.name,
+                host_port(&self.host, cluster.port),
+            )
+        } else {
+            String::new()
+        };
         let config = format!(
             r#"
 jetstream {{
@@ -243,7 +358,7 @@ jetstream {{
 }}
 {leafnode_section}
 {websocket_section}
-"#,
+{cluster_section}"#,
             self.js_domain.unwrap_or_else(|| "core".to_string()),
             self.store_dir.as_os_str().to_string_lossy()
         );
@@ -262,19 +377,30 @@ where
     P: AsRef<Path>,
     T: Into<Stdio>,
 {
-    let host_addr = format!("{}:{}", config.host, config.port);
+    // Servers listening on all interfaces are probed and waited for on the loopback address
+    let host_addr = host_port(&connect_host(&config.host), config.port);
     // If we can connect to the local port, NATS won't be able to listen on that port
     if tokio::net::TcpStream::connect(&host_addr).await.is_ok() {
         bail!(
-            "could not start NATS server, a process is already listening on {}:{}",
-            config.host,
-            config.port
+            "could not start NATS server, a process is already listening on {}",
+            host_port(&config.host, config.port)
         );
     }
     let child = if let Some(parent_path) = bin_path.as_ref().parent() {
-        let config_path = parent_path.join(NATS_SERVER_CONF);
-        let host = config.host.clone();
+        let host = unbracket_host(&config.host).to_string();
         let port = config.port;
+        // Nodes of a cluster share the install directory, so each needs its own config and pid file
+        let (config_path, pid_path) = if config.cluster.is_some() {
+            (
+                parent_path.join(format!("nats-{port}.conf")),
+                nats_node_pid_path(parent_path, port),
+            )
+        } else {
+            (
+                parent_path.join(NATS_SERVER_CONF),
+                parent_path.join(NATS_SERVER_PID),
+            )
+        };
         config.write_to_path(&config_path).await?;
         Command::new(bin_path.as_ref())
             .stderr(stderr)
@@ -287,7 +413,7 @@ where
             .arg("--port")
             .arg(port.to_string())
             .arg("--pid")
-            .arg(parent_path.join(NATS_SERVER_PID))
+            .arg(pid_path)
             .spawn()
             .map_err(anyhow::Error::from)
     } else {
@@ -298,6 +424,68 @@ where
         .map(|()| child)
 }
 
+/// A running node of a NATS cluster started with [`start_nats_cluster`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NatsClusterNode {
+    /// Client port of the node
+    pub port: u16,
+    /// Process ID of the node
+    pub pid: Option<u32>,
+}
+
+/// Helper function to start a NATS cluster, one nats-server process per config. Nodes are started
+/// in order and already started nodes are killed if any node fails to start. Each node writes its
+/// logs to `nats-<port>.log` in `log_dir`.
+///
+/// # Arguments
+///
+/// * `bin_path` - Path to the nats-server binary to execute
+/// * `log_dir` - Directory to write the NATS logs for each node to
+/// * `configs` - Configuration for each node of the cluster, see [`NatsConfig::new_cluster`]
+pub async fn start_nats_cluster<P, L>(
+    bin_path: P,
+    log_dir: L,
+    configs: Vec<NatsConfig>,
+) -> Result<Vec<(NatsClusterNode, Child)>>
+where
+    P: AsRef<Path>,
+    L: AsRef<Path>,
+{
+    let mut nodes: Vec<(NatsClusterNode, Child)> = Vec::with_capacity(configs.len());
+    for config in configs {
+        let port = config.port;
+        let started = async {
+            let log_file =
+                tokio::fs::File::create(log_dir.as_ref().join(format!("nats-{port}.log")))
+                    .await?
+                    .into_std()
+                    .await;
+            start_nats_server(bin_path.as_ref(), log_file, config).await
+        }
+        .await;
+        match started {
+            Ok(child) => nodes.push((
+                NatsClusterNode {
+                    port,
+                    pid: child.id(),
+                },
+                child,
+            )),
+            Err(err) => {
+                for (_, mut child) in nodes {
+                    if let Err(err) = child.kill().await {
+                        warn!(?err, "failed to kill NATS cluster node");
+                    }
+                }
+                return Err(
+                    err.context(format!("failed to start NATS cluster node on port {port}"))
+                );
+            }
+        }
+    }
+    Ok(nodes)
+}
+
 /// Helper function to get the path to the NATS server pid file
 pub fn nats_pid_path<P>(install_dir: P) -> PathBuf
 where
@@ -306,6 +494,42 @@ where
     install_dir.as_ref().join(NATS_SERVER_PID)
 }
 
+/// Helper function to get the path to the pid file of a NATS cluster node listening on `port`
+pub fn nats_node_pid_path<P>(install_dir: P, port: u16) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    install_dir.as_ref().join(format!("nats-{port}.pid"))
+}
+
+/// Helper function to record the nodes of a running NATS cluster in the install directory
+pub async fn write_nats_cluster_file<P>(install_dir: P, nodes: &[NatsClusterNode]) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    write(
+        install_dir.as_ref().join(NATS_CLUSTER_FILE),
+        serde_json::to_vec(nodes)?,
+    )
+    .await
+    .context("failed to write NATS cluster file")
+}
+
+/// Helper function to read the nodes of a running NATS cluster from the install directory,
+/// returning `None` if no cluster was started
+pub async fn read_nats_cluster_file<P>(install_dir: P) -> Result<Option<Vec<NatsClusterNode>>>
+where
+    P: AsRef<Path>,
+{
+    match tokio::fs::read(install_dir.as_ref().join(NATS_CLUSTER_FILE)).await {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map(Some)
+            .context("failed to parse NATS cluster file"),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(anyhow::Error::from(err).context("failed to read NATS cluster file")),
+    }
+}
+
 /// Helper function to determine the NATS server release path given an os/arch and version
 fn nats_url(os: &str, arch: &str, version: &str) -> String {
     // Replace "macos" with "darwin" to match NATS release scheme
@@ -322,7 +546,8 @@ fn nats_url(os: &str, arch: &str, version: &str) -> String {
 #[cfg(test)]
 mod test {
     use crate::start::{
-        ensure_nats_server, is_bin_installed, start_nats_server, NatsConfig, NATS_SERVER_BINARY,
+        ensure_nats_server, is_bin_installed, read_nats_cluster_file, start_nats_server,
+        write_nats_cluster_file, NatsClusterNode, NatsConfig, NATS_SERVER_BINARY,
     };
     use anyhow::Result;
     use std::env::temp_dir;
@@ -460,4 +685,92 @@ mod test {
         let _ = remove_dir_all(install_dir).await;
         Ok(())
     }
+
+    #[test]
+    fn can_configure_nats_cluster() -> Result<()> {
+        assert!(NatsConfig::new_cluster("127.0.0.1", 4222, 1, None, 4223).is_err());
+        assert!(NatsConfig::new_cluster("127.0.0.1", 4222, 6, None, 4223).is_err());
+        assert!(NatsConfig::new_cluster("127.0.0.1", 65534, 3, None, 4223).is_err());
+
+        let configs = NatsConfig::new_cluster("127.0.0.1", 4222, 3, None, 4223)?;
+        assert_eq!(
+            configs.iter().map(|c| c.port).collect::<Vec<_>>(),
+            vec![4222, 4223, 4224]
+        );
+        assert_eq!(
+            configs.iter().map(|c| c.websocket_port).collect::<Vec<_>>(),
+            vec![4223, 7223, 7224]
+        );
+        assert_eq!(
+            configs
+                .iter()
+                .map(|c| c.store_dir.clone())
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            3
+        );
+        for config in &configs {
+            let cluster = config
+                .cluster
+                .as_ref()
+                .expect("cluster config should be set");
+            assert_eq!(cluster.port, config.port + 2000);
+            assert_eq!(
+                cluster.routes,
+                vec![
+                    "nats-route://127.0.0.1:6222",
+                    "nats-route://127.0.0.1:6223",
+                    "nats-route://127.0.0.1:6224"
+                ]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cluster_routes_bracket_ipv6_hosts() -> Result<()> {
+        let configs = NatsConfig::new_cluster("::", 4222, 2, None, 4223)?;
+        let cluster = configs[0]
+            .cluster
+            .as_ref()
+            .expect("cluster config should be set");
+        assert_eq!(
+            cluster.routes,
+            vec!["nats-route://[::1]:6222", "nats-route://[::1]:6223"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn can_write_cluster_config_and_file() -> Result<()> {
+        let install_dir = temp_dir().join("can_write_cluster_config_and_file");
+        let _ = remove_dir_all(&install_dir).await;
+        create_dir_all(&install_dir).await?;
+
+        assert_eq!(read_nats_cluster_file(&install_dir).await?, None);
+
+        let config = NatsConfig::new_cluster("127.0.0.1", 5222, 2, None, 5223)?.remove(1);
+        let config_path = install_dir.join("nats-5223.conf");
+        config.write_to_path(&config_path).await?;
+        let contents = tokio::fs::read_to_string(&config_path).await?;
+        assert!(contents.contains(r#"server_name: "wash-5223""#));
+        assert!(contents.contains(r#"listen: "127.0.0.1:7223""#));
+        assert!(contents.contains(r#""nats-route://127.0.0.1:7222""#));
+
+        let nodes = vec![
+            NatsClusterNode {
+                port: 5222,
+                pid: Some(1),
+            },
+            NatsClusterNode {
+                port: 5223,
+                pid: None,
+            },
+        ];
+        write_nats_cluster_file(&install_dir, &nodes).await?;
+        assert_eq!(read_nats_cluster_file(&install_dir).await?, Some(nodes));
+
+        let _ = remove_dir_all(install_dir).await;
+        Ok(())
+    }
 }
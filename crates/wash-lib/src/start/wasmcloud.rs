@@ -17,6 +17,9 @@ use std::process::Stdio;
 use command_group::AsyncCommandGroup;
 
 use super::get_download_client;
+#[cfg(target_os = "linux")]
+use super::limits::set_nofile;
+use super::limits::{plan_limits, LimitsPlan, ProcessLimits};
 
 const WASMCLOUD_GITHUB_RELEASE_URL: &str =
     "https://github.com/wasmCloud/wasmCloud/releases/download";
@@ -209,8 +212,62 @@ where
     T: Into<Stdio>,
     S: Into<Stdio>,
 {
+    start_wasmcloud_host_with_limits(
+        bin_path,
+        stdout,
+        stderr,
+        env_vars,
+        &ProcessLimits::default(),
+        false,
+    )
+    .await
+    .map(|(child, _)| child)
+}
+
+/// Helper function to start a wasmCloud host given the path to the burrito release application,
+/// constraining the host and the providers it spawns with `limits`
+///
+/// Limits which cannot be applied are ignored with a warning, or fail the start if `strict_limits`
+/// is set. Returns the host process along with the limits which were applied.
+///
+/// # Arguments
+///
+/// * `bin_path` - Path to the `wasmcloud_host` burrito application
+/// * `stdout` - Specify where wasmCloud stdout logs should be written to
+/// * `stderr` - Specify where wasmCloud stderr logs should be written to
+/// * `env_vars` - Environment variables to pass to the host
+/// * `limits` - Resource limits of the host process tree, only supported on Linux
+/// * `strict_limits` - Whether failing to apply a limit is an error
+pub async fn start_wasmcloud_host_with_limits<P, T, S>(
+    bin_path: P,
+    stdout: T,
+    stderr: S,
+    env_vars: HashMap<String, String>,
+    limits: &ProcessLimits,
+    strict_limits: bool,
+) -> Result<(Child, ProcessLimits)>
+where
+    P: AsRef<Path>,
+    T: Into<Stdio>,
+    S: Into<Stdio>,
+{
+    let LimitsPlan { wrapper, applied } = plan_limits(limits, strict_limits).await?;
+
     // Constructing this object in one step results in a temporary value that's dropped
-    let mut cmd = Command::new(bin_path.as_ref());
+    let mut cmd = match wrapper {
+        Some(wrapper) => {
+            let mut cmd = Command::new(&wrapper[0]);
+            cmd.args(&wrapper[1..]).arg(bin_path.as_ref());
+            cmd
+        }
+        None => Command::new(bin_path.as_ref()),
+    };
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(nofile) = applied.nofile {
+            set_nofile(&mut cmd, nofile);
+        }
+    }
     let cmd = cmd
         // wasmCloud host logs are sent to stderr as of https://github.com/wasmCloud/wasmcloud-otp/pull/418
         .stderr(stderr)
@@ -222,11 +279,11 @@ where
 
     #[cfg(target_family = "unix")]
     {
-        Ok(cmd.group_spawn()?.into_inner())
+        Ok((cmd.group_spawn()?.into_inner(), applied))
     }
     #[cfg(target_family = "windows")]
     {
-        Ok(cmd.spawn()?)
+        Ok((cmd.spawn()?, applied))
     }
 }
 
@@ -0,0 +1,514 @@
+//! Vendoring of the dependencies of WIT packages from the registries configured for `wkg`
+//!
+//! Packages are located the way `wkg` locates them: the registry of a package is looked up by its
+//! namespace in the `wkg` configuration, read from [`WKG_CONFIG_FILE_ENV`] if set and from the
+//! default location otherwise. The OCI repository holding the packages of a registry is then
+//! discovered from the metadata it serves at `/.well-known/wasm-pkg/registry.json`, unless the
+//! configuration already provides that metadata.
+//!
+//! Dependencies are vendored in WIT form to `deps/<namespace>-<name>-<version>/package.wit`, the
+//! layout used by `wkg wit fetch`. Credentials and connection settings of OCI registries are taken
+//! from [`REGISTRIES_ENV`](crate::registry::REGISTRIES_ENV).
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _, Result};
+use oci_distribution::Reference;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use wit_component::{DecodedWasm, WitPrinter};
+use wit_parser::{PackageName, Resolve, UnresolvedPackage};
+
+use crate::registry::{
+    list_oci_tags, pull_oci_artifact, registries_with_env, resolve_reference, OciPullOptions,
+};
+
+/// Environment variable holding the path of the `wkg` configuration file
+pub const WKG_CONFIG_FILE_ENV: &str = "WKG_CONFIG_FILE";
+
+/// Path of the metadata served by registries of WIT packages
+const REGISTRY_METADATA_PATH: &str = ".well-known/wasm-pkg/registry.json";
+
+/// Registry of the `wasi` namespace when none is configured, as for `wkg`
+const WASI_REGISTRY: &str = "wasi.dev";
+
+/// Configuration of `wkg`, of which only the registries of packages are used
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct WkgConfig {
+    /// Registry of packages of namespaces without a configured registry
+    #[serde(default)]
+    default_registry: Option<String>,
+    /// Registries by namespace
+    #[serde(default)]
+    namespace_registries: HashMap<String, RegistryMapping>,
+    /// Registries by package, taking precedence over the registries of their namespace
+    #[serde(default)]
+    package_registry_overrides: HashMap<String, RegistryMapping>,
+}
+
+/// Registry of a namespace or package, optionally along with its metadata
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum RegistryMapping {
+    Registry(String),
+    Custom {
+        registry: String,
+        metadata: RegistryMetadata,
+    },
+}
+
+/// Metadata of a registry, as served at [`REGISTRY_METADATA_PATH`]
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryMetadata {
+    #[serde(default)]
+    preferred_protocol: Option<String>,
+    #[serde(default)]
+    oci: Option<OciMetadata>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciMetadata {
+    #[serde(default)]
+    registry: Option<String>,
+    #[serde(default)]
+    namespace_prefix: Option<String>,
+}
+
+impl WkgConfig {
+    /// Load the configuration from [`WKG_CONFIG_FILE_ENV`] if set, or from the default location of
+    /// the `wkg` configuration if it exists
+    pub fn load() -> Result<Self> {
+        if let Some(path) = std::env::var_os(WKG_CONFIG_FILE_ENV) {
+            return Self::from_file(Path::new(&path));
+        }
+        match dirs::config_dir().map(|dir| dir.join("wasm-pkg").join("config.toml")) {
+            Some(path) if path.is_file() => Self::from_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from the file at `path`
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read wkg configuration [{}]", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse wkg configuration [{}]", path.display()))
+    }
+
+    fn registry(&self, package: &PackageName) -> Result<RegistryMapping> {
+        let name = format!("{}:{}", package.namespace, package.name);
+        if let Some(mapping) = self
+            .package_registry_overrides
+            .get(&name)
+            .or_else(|| self.namespace_registries.get(&package.namespace))
+        {
+            return Ok(mapping.clone());
+        }
+        if package.namespace == "wasi" {
+            return Ok(RegistryMapping::Registry(WASI_REGISTRY.to_string()));
+        }
+        self.default_registry
+            .clone()
+            .map(RegistryMapping::Registry)
+            .with_context(|| {
+                format!(
+                    "no registry is configured for package [{name}], set one for namespace [{}] in the wkg configuration",
+                    package.namespace
+                )
+            })
+    }
+}
+
+/// OCI registry and repository prefix holding the packages of a registry
+#[derive(Clone, Debug)]
+struct OciLocation {
+    registry: String,
+    namespace_prefix: String,
+}
+
+impl OciLocation {
+    fn from_metadata(registry: &str, metadata: &RegistryMetadata) -> Result<Self> {
+        if let Some(protocol) = metadata
+            .preferred_protocol
+            .as_deref()
+            .filter(|protocol| *protocol != "oci")
+        {
+            bail!("registry [{registry}] uses the [{protocol}] protocol, only OCI is supported");
+        }
+        let oci = metadata.oci.clone().unwrap_or_default();
+        Ok(Self {
+            registry: oci.registry.unwrap_or_else(|| registry.to_string()),
+            namespace_prefix: oci.namespace_prefix.unwrap_or_default(),
+        })
+    }
+}
+
+/// Locates, lists and pulls packages in their registries
+pub struct PackageResolver {
+    config: WkgConfig,
+    http: reqwest::Client,
+    /// Locations of registries, by registry
+    locations: HashMap<String, OciLocation>,
+}
+
+impl PackageResolver {
+    /// Create a resolver locating packages with the `wkg` configuration `config`
+    #[must_use]
+    pub fn new(config: WkgConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    async fn location(&mut self, package: &PackageName) -> Result<OciLocation> {
+        let registry = match self.config.registry(package)? {
+            RegistryMapping::Custom { registry, metadata } => {
+                return OciLocation::from_metadata(&registry, &metadata)
+            }
+            RegistryMapping::Registry(registry) => registry,
+        };
+        if let Some(location) = self.locations.get(&registry) {
+            return Ok(location.clone());
+        }
+        let url = format!("https://{registry}/{REGISTRY_METADATA_PATH}");
+        let metadata = match self.http.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response
+                .json::<RegistryMetadata>()
+                .await
+                .with_context(|| format!("failed to parse registry metadata at [{url}]"))?,
+            res => {
+                // Registries without metadata are OCI registries holding packages at their root
+                debug!(?res, registry, "failed to fetch registry metadata");
+                RegistryMetadata::default()
+            }
+        };
+        let location = OciLocation::from_metadata(&registry, &metadata)?;
+        self.locations.insert(registry, location.clone());
+        Ok(location)
+    }
+
+    /// Reference of `package` at `version`, or `latest` if no version is given
+    async fn reference(
+        &mut self,
+        package: &PackageName,
+        version: Option<&Version>,
+    ) -> Result<Reference> {
+        let OciLocation {
+            registry,
+            namespace_prefix,
+        } = self.location(package).await?;
+        let tag = version.map_or_else(|| "latest".to_string(), ToString::to_string);
+        format!(
+            "{registry}/{namespace_prefix}{}/{}:{tag}",
+            package.namespace, package.name
+        )
+        .parse()
+        .with_context(|| format!("invalid OCI reference for package [{package}]"))
+    }
+
+    /// Versions of `package` available in its registry, sorted in ascending order. Tags which are
+    /// not semantic versions are ignored
+    pub async fn versions(&mut self, package: &PackageName) -> Result<Vec<Version>> {
+        let reference = self.reference(package, None).await?;
+        let (reference, options) = pull_options(&reference)?;
+        let mut versions: Vec<Version> = list_oci_tags(&reference, options)
+            .await?
+            .iter()
+            .filter_map(|tag| tag.parse().ok())
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Pull the Wasm-encoded WIT of `package` at `version`
+    pub async fn pull(&mut self, package: &PackageName, version: &Version) -> Result<Vec<u8>> {
+        let reference = self.reference(package, Some(version)).await?;
+        let (reference, options) = pull_options(&reference)?;
+        pull_oci_artifact(&reference, options)
+            .await
+            .with_context(|| format!("failed to pull package [{package}] from [{reference}]"))
+    }
+}
+
+/// Reference to pull and list `reference` through, and the settings of its registry
+fn pull_options(reference: &Reference) -> Result<(Reference, OciPullOptions)> {
+    let registries = registries_with_env(&HashMap::new())?;
+    let resolved = resolve_reference(reference, &registries)?;
+    Ok((
+        resolved.reference,
+        OciPullOptions {
+            user: resolved.user,
+            password: resolved.password,
+            insecure: resolved.insecure,
+            ca_file: resolved.ca_file,
+            ..Default::default()
+        },
+    ))
+}
+
+/// A package vendored in the `deps` directory of a WIT package
+#[derive(Clone, Debug, Serialize)]
+pub struct VendoredPackage {
+    /// Namespace and name of the package
+    pub name: String,
+    /// Version of the package, if versioned
+    pub version: Option<String>,
+    /// Path of the package
+    pub path: PathBuf,
+}
+
+/// Vendored packages along with the packages they depend on
+struct Vendored(Vec<(PackageName, PathBuf, Vec<PackageName>)>);
+
+impl Vendored {
+    /// Parse the packages vendored in `deps_dir`, as directories or single WIT files
+    fn load(deps_dir: &Path) -> Result<Self> {
+        let mut packages = Vec::new();
+        if !deps_dir.is_dir() {
+            return Ok(Self(packages));
+        }
+        let mut entries = std::fs::read_dir(deps_dir)
+            .with_context(|| format!("failed to read [{}]", deps_dir.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(std::fs::DirEntry::path);
+        for entry in entries {
+            let path = entry.path();
+            let package = if path.is_dir() {
+                UnresolvedPackage::parse_dir(&path)
+            } else if path.extension().is_some_and(|ext| ext == "wit") {
+                UnresolvedPackage::parse_file(&path)
+            } else {
+                continue;
+            }
+            .with_context(|| format!("failed to parse vendored package [{}]", path.display()))?;
+            let deps = package.foreign_deps.keys().cloned().collect();
+            packages.push((package.name, path, deps));
+        }
+        Ok(Self(packages))
+    }
+
+    /// Whether `package` is vendored, in any version if it is unversioned
+    fn contains(&self, package: &PackageName) -> bool {
+        self.0.iter().any(|(name, ..)| {
+            name.namespace == package.namespace
+                && name.name == package.name
+                && (package.version.is_none() || name.version == package.version)
+        })
+    }
+
+    fn to_vec(&self) -> Vec<VendoredPackage> {
+        self.0
+            .iter()
+            .map(|(name, path, _)| vendored_package(name, path))
+            .collect()
+    }
+}
+
+fn vendored_package(name: &PackageName, path: &Path) -> VendoredPackage {
+    VendoredPackage {
+        name: format!("{}:{}", name.namespace, name.name),
+        version: name.version.as_ref().map(ToString::to_string),
+        path: path.to_path_buf(),
+    }
+}
+
+/// Packages fetched by [`fetch_deps`]
+#[derive(Clone, Debug, Serialize)]
+pub struct FetchedDeps {
+    /// Packages fetched from registries
+    pub fetched: Vec<VendoredPackage>,
+    /// All vendored packages, including the ones fetched
+    pub vendored: Vec<VendoredPackage>,
+}
+
+/// Fetch the dependencies of the WIT package in `wit_dir` which are not vendored in its `deps`
+/// directory yet, along with their own dependencies. Dependencies without a version are fetched
+/// at the latest version available.
+pub async fn fetch_deps(wit_dir: &Path, resolver: &mut PackageResolver) -> Result<FetchedDeps> {
+    let root = UnresolvedPackage::parse_dir(wit_dir)
+        .with_context(|| format!("failed to parse WIT directory [{}]", wit_dir.display()))?;
+    let deps_dir = wit_dir.join("deps");
+    let mut vendored = Vendored::load(&deps_dir)?;
+    let mut pending: Vec<PackageName> = root.foreign_deps.keys().cloned().collect();
+    pending.extend(vendored.0.iter().flat_map(|(_, _, deps)| deps.clone()));
+    let mut fetched = Vec::new();
+
+    while let Some(dep) = pending.pop() {
+        if vendored.contains(&dep) {
+            continue;
+        }
+        let version = match &dep.version {
+            Some(version) => version.clone(),
+            None => resolver
+                .versions(&dep)
+                .await?
+                .into_iter()
+                .filter(|version| version.pre.is_empty())
+                .last()
+                .with_context(|| format!("no versions of package [{dep}] found"))?,
+        };
+        let wasm = resolver.pull(&dep, &version).await?;
+        let (resolve, _) = match wit_component::decode(&wasm)
+            .with_context(|| format!("failed to decode package [{dep}]"))?
+        {
+            DecodedWasm::WitPackage(resolve, package) => (resolve, package),
+            DecodedWasm::Component(..) => {
+                bail!("artifact of package [{dep}] is a component, not a WIT package")
+            }
+        };
+        // The pulled package contains the packages it depends on
+        for (id, package) in &resolve.packages {
+            if vendored.contains(&package.name) {
+                continue;
+            }
+            let wit = WitPrinter::default()
+                .print(&resolve, id)
+                .with_context(|| format!("failed to print package [{}]", package.name))?;
+            let path = deps_dir.join(dep_dir_name(&package.name));
+            tokio::fs::create_dir_all(&path)
+                .await
+                .with_context(|| format!("failed to create [{}]", path.display()))?;
+            tokio::fs::write(path.join("package.wit"), wit)
+                .await
+                .with_context(|| format!("failed to write package [{}]", package.name))?;
+            fetched.push(vendored_package(&package.name, &path));
+            vendored.0.push((package.name.clone(), path, Vec::new()));
+        }
+        if !vendored.contains(&dep) {
+            bail!("artifact of package [{dep}] does not contain it");
+        }
+    }
+
+    Resolve::default().push_dir(wit_dir).with_context(|| {
+        format!(
+            "WIT directory [{}] does not resolve with its vendored dependencies",
+            wit_dir.display()
+        )
+    })?;
+    Ok(FetchedDeps {
+        fetched,
+        vendored: vendored.to_vec(),
+    })
+}
+
+/// Name of the directory a package is vendored to, as named by `wkg`
+fn dep_dir_name(package: &PackageName) -> String {
+    match &package.version {
+        Some(version) => format!("{}-{}-{version}", package.namespace, package.name),
+        None => format!("{}-{}", package.namespace, package.name),
+    }
+}
+
+/// A vendored package with newer versions available in its registry
+#[derive(Clone, Debug, Serialize)]
+pub struct OutdatedPackage {
+    /// Namespace and name of the package
+    pub name: String,
+    /// Vendored version
+    pub current: String,
+    /// Latest version available
+    pub latest: String,
+    /// Latest version available which is compatible with the vendored version according to semver
+    /// rules, if newer than the vendored version
+    pub latest_compatible: Option<String>,
+    /// Path of the vendored package
+    pub path: PathBuf,
+}
+
+/// Outcome of [`outdated_deps`]
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OutdatedDeps {
+    pub outdated: Vec<OutdatedPackage>,
+    /// Errors looking up the versions of packages, by package
+    pub failed: BTreeMap<String, String>,
+}
+
+/// Find the packages vendored in the `deps` directory of the WIT package in `wit_dir` with newer
+/// versions available in their registry. Prereleases are only considered for packages vendored at
+/// a prerelease, and unversioned packages are skipped.
+pub async fn outdated_deps(wit_dir: &Path, resolver: &mut PackageResolver) -> Result<OutdatedDeps> {
+    let vendored = Vendored::load(&wit_dir.join("deps"))?;
+    let mut outdated = OutdatedDeps::default();
+    for (name, path, _) in vendored.0 {
+        let Some(current) = name.version.clone() else {
+            debug!(package = %name, "skipping unversioned package");
+            continue;
+        };
+        let versions = match resolver.versions(&name).await {
+            Ok(versions) => versions,
+            Err(err) => {
+                outdated.failed.insert(name.to_string(), format!("{err:#}"));
+                continue;
+            }
+        };
+        let versions: Vec<_> = versions
+            .into_iter()
+            .filter(|version| version.pre.is_empty() || !current.pre.is_empty())
+            .filter(|version| *version > current)
+            .collect();
+        let Some(latest) = versions.last() else {
+            continue;
+        };
+        let compatible = VersionReq::parse(&format!("^{current}"))
+            .with_context(|| format!("invalid version of package [{name}]"))?;
+        outdated.outdated.push(OutdatedPackage {
+            name: format!("{}:{}", name.namespace, name.name),
+            current: current.to_string(),
+            latest: latest.to_string(),
+            latest_compatible: versions
+                .iter()
+                .filter(|version| compatible.matches(version))
+                .last()
+                .map(ToString::to_string),
+            path,
+        });
+    }
+    Ok(outdated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wkg_config_registries() {
+        let config: WkgConfig = toml::from_str(
+            r#"
+            default_registry = "example.com"
+
+            [namespace_registries]
+            wasmcloud = { registry = "wasmcloud.com", metadata = { preferredProtocol = "oci", oci = { registry = "ghcr.io", namespacePrefix = "wasmcloud/interfaces/" } } }
+            "#,
+        )
+        .expect("failed to parse configuration");
+        let package = |namespace: &str| PackageName {
+            namespace: namespace.into(),
+            name: "pkg".into(),
+            version: None,
+        };
+
+        let RegistryMapping::Custom { registry, metadata } =
+            config.registry(&package("wasmcloud")).unwrap()
+        else {
+            panic!("expected registry metadata");
+        };
+        let location = OciLocation::from_metadata(&registry, &metadata).unwrap();
+        assert_eq!(location.registry, "ghcr.io");
+        assert_eq!(location.namespace_prefix, "wasmcloud/interfaces/");
+        assert!(matches!(
+            config.registry(&package("wasi")).unwrap(),
+            RegistryMapping::Registry(registry) if registry == WASI_REGISTRY
+        ));
+        assert!(matches!(
+            config.registry(&package("other")).unwrap(),
+            RegistryMapping::Registry(registry) if registry == "example.com"
+        ));
+        assert!(WkgConfig::default().registry(&package("other")).is_err());
+    }
+}
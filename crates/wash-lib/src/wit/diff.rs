@@ -0,0 +1,732 @@
+//! Structural comparison of two versions of WIT packages
+//!
+//! Packages are matched by namespace and name, regardless of their version, so that upgrading a
+//! dependency (e.g. `wasi:http@0.2.0` to `wasi:http@0.2.1`) is reported as a change of its version
+//! along with the changes of its interfaces, functions and types. Functions and types are compared
+//! by their signature and definition, rendered in WIT syntax, so that formatting changes and
+//! comments are not reported.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wit_parser::{
+    Function, Handle, InterfaceId, PackageId, PackageName, Resolve, Results, Type, TypeDefKind,
+    TypeId, WorldId, WorldItem, WorldKey,
+};
+
+/// How an item differs between two versions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// The item only exists in the new version
+    Added,
+    /// The item only exists in the old version
+    Removed,
+    /// The item exists in both versions, with differences
+    Changed,
+}
+
+impl Change {
+    /// Symbol prefixing the item in text output
+    #[must_use]
+    pub fn symbol(&self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Changed => '~',
+        }
+    }
+}
+
+/// Leftmost component of a semantic version which changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+    Prerelease,
+}
+
+/// Change of the version of a package
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VersionChange {
+    /// Old version, if the old package is versioned
+    pub old: Option<String>,
+    /// New version, if the new package is versioned
+    pub new: Option<String>,
+    /// Leftmost component of the version which changed, if both packages are versioned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump: Option<VersionBump>,
+    /// Whether the new version is older than the old one
+    pub downgrade: bool,
+    /// Whether the versions are incompatible according to semver rules, i.e. their leftmost
+    /// nonzero component or their prerelease differs
+    pub breaking: bool,
+}
+
+impl VersionChange {
+    /// The change between the versions of two packages, if any
+    fn new(old: &PackageName, new: &PackageName) -> Option<Self> {
+        if old.version == new.version {
+            return None;
+        }
+        let (bump, downgrade, breaking) = match (&old.version, &new.version) {
+            (Some(old), Some(new)) => {
+                let bump = if old.major != new.major {
+                    VersionBump::Major
+                } else if old.minor != new.minor {
+                    VersionBump::Minor
+                } else if old.patch != new.patch {
+                    VersionBump::Patch
+                } else {
+                    VersionBump::Prerelease
+                };
+                let compatibility = |v: &semver::Version| match (v.major, v.minor) {
+                    (0, 0) => (0, 0, v.patch),
+                    (0, minor) => (0, minor, 0),
+                    (major, _) => (major, 0, 0),
+                };
+                let breaking = compatibility(old) != compatibility(new) || old.pre != new.pre;
+                (Some(bump), new < old, breaking)
+            }
+            _ => (None, false, true),
+        };
+        Some(Self {
+            old: old.version.as_ref().map(ToString::to_string),
+            new: new.version.as_ref().map(ToString::to_string),
+            bump,
+            downgrade,
+            breaking,
+        })
+    }
+}
+
+/// Difference of a function or type, with its signature or definition in each version
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ItemDiff {
+    pub name: String,
+    pub change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+/// Difference of an interface
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InterfaceDiff {
+    pub name: String,
+    pub change: Change,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<ItemDiff>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<ItemDiff>,
+}
+
+/// Difference of a world, by the interfaces and functions it imports and exports
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WorldDiff {
+    pub name: String,
+    pub change: Change,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<ItemDiff>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exports: Vec<ItemDiff>,
+}
+
+/// Difference of a package, identified by its namespace and name
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PackageDiff {
+    pub name: String,
+    pub change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<VersionChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub interfaces: Vec<InterfaceDiff>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub worlds: Vec<WorldDiff>,
+}
+
+/// Differences between all packages of two versions of a WIT package and its dependencies
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct WitDiff {
+    pub packages: Vec<PackageDiff>,
+}
+
+impl WitDiff {
+    /// Compare all packages of `old` and `new`
+    #[must_use]
+    pub fn new(old: &Resolve, new: &Resolve) -> Self {
+        let old_packages = packages_by_name(old);
+        let new_packages = packages_by_name(new);
+        let old = Printer(old);
+        let new = Printer(new);
+        let packages = diff_maps(
+            &old_packages,
+            &new_packages,
+            |name, id| old.package(name, *id, Change::Removed),
+            |name, id| new.package(name, *id, Change::Added),
+            |name, old_id, new_id| diff_packages(name, &old, *old_id, &new, *new_id),
+        );
+        Self { packages }
+    }
+
+    /// Whether the packages are identical
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// Render the differences for text output, one item per line
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        let item = |lines: &mut Vec<String>, indent: &str, kind: &str, item: &ItemDiff| {
+            let signature = match (&item.old, &item.new) {
+                (Some(old), Some(new)) => format!(": {old} => {new}"),
+                (Some(sig), None) | (None, Some(sig)) => format!(": {sig}"),
+                (None, None) => String::new(),
+            };
+            lines.push(format!(
+                "{indent}{} {kind} {}{signature}",
+                item.change.symbol(),
+                item.name
+            ));
+        };
+        for package in &self.packages {
+            let version = match &package.version {
+                Some(VersionChange { old, new, bump, .. }) => format!(
+                    " ({} => {}{})",
+                    old.as_deref().unwrap_or("unversioned"),
+                    new.as_deref().unwrap_or("unversioned"),
+                    bump.map(|bump| format!(", {bump:?} bump").to_lowercase())
+                        .unwrap_or_default()
+                ),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "{} package {}{version}",
+                package.change.symbol(),
+                package.name
+            ));
+            for interface in &package.interfaces {
+                lines.push(format!(
+                    "  {} interface {}",
+                    interface.change.symbol(),
+                    interface.name
+                ));
+                for function in &interface.functions {
+                    item(&mut lines, "    ", "func", function);
+                }
+                for ty in &interface.types {
+                    item(&mut lines, "    ", "type", ty);
+                }
+            }
+            for world in &package.worlds {
+                lines.push(format!("  {} world {}", world.change.symbol(), world.name));
+                for import in &world.imports {
+                    item(&mut lines, "    ", "import", import);
+                }
+                for export in &world.exports {
+                    item(&mut lines, "    ", "export", export);
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Packages of `resolve` by namespace and name. Of several versions of a package, the latest is
+/// compared
+fn packages_by_name(resolve: &Resolve) -> BTreeMap<String, PackageId> {
+    let mut packages = BTreeMap::<String, PackageId>::new();
+    for (id, package) in &resolve.packages {
+        let name = format!("{}:{}", package.name.namespace, package.name.name);
+        match packages.get(&name) {
+            Some(other) if resolve.packages[*other].name.version >= package.name.version => {}
+            _ => {
+                packages.insert(name, id);
+            }
+        }
+    }
+    packages
+}
+
+/// Compare the items of two maps sorted by name, returning the differences
+fn diff_maps<K: Ord, A, B, T>(
+    old: &BTreeMap<K, A>,
+    new: &BTreeMap<K, B>,
+    removed: impl Fn(&K, &A) -> T,
+    added: impl Fn(&K, &B) -> T,
+    changed: impl Fn(&K, &A, &B) -> Option<T>,
+) -> Vec<T> {
+    let mut diffs = Vec::new();
+    for (name, old) in old {
+        match new.get(name) {
+            Some(new) => diffs.extend(changed(name, old, new)),
+            None => diffs.push(removed(name, old)),
+        }
+    }
+    for (name, new) in new {
+        if !old.contains_key(name) {
+            diffs.push(added(name, new));
+        }
+    }
+    diffs
+}
+
+/// Compare the rendered signatures or definitions of items
+fn diff_items(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Vec<ItemDiff> {
+    diff_maps(
+        old,
+        new,
+        |name, old| ItemDiff {
+            name: name.clone(),
+            change: Change::Removed,
+            old: Some(old.clone()),
+            new: None,
+        },
+        |name, new| ItemDiff {
+            name: name.clone(),
+            change: Change::Added,
+            old: None,
+            new: Some(new.clone()),
+        },
+        |name, old, new| {
+            (old != new).then(|| ItemDiff {
+                name: name.clone(),
+                change: Change::Changed,
+                old: Some(old.clone()),
+                new: Some(new.clone()),
+            })
+        },
+    )
+}
+
+fn diff_packages(
+    name: &str,
+    old: &Printer<'_>,
+    old_id: PackageId,
+    new: &Printer<'_>,
+    new_id: PackageId,
+) -> Option<PackageDiff> {
+    let old_package = &old.0.packages[old_id];
+    let new_package = &new.0.packages[new_id];
+    let interfaces = diff_maps(
+        &by_name(&old_package.interfaces),
+        &by_name(&new_package.interfaces),
+        |name, id| old.interface(name, *id, Change::Removed),
+        |name, id| new.interface(name, *id, Change::Added),
+        |name, old_id, new_id| {
+            let functions = diff_items(&old.functions(*old_id), &new.functions(*new_id));
+            let types = diff_items(&old.types(*old_id), &new.types(*new_id));
+            (!functions.is_empty() || !types.is_empty()).then(|| InterfaceDiff {
+                name: name.clone(),
+                change: Change::Changed,
+                functions,
+                types,
+            })
+        },
+    );
+    let worlds = diff_maps(
+        &by_name(&old_package.worlds),
+        &by_name(&new_package.worlds),
+        |name, id| old.world(name, *id, Change::Removed),
+        |name, id| new.world(name, *id, Change::Added),
+        |name, old_id, new_id| {
+            let (old_imports, old_exports) = old.world_items(*old_id);
+            let (new_imports, new_exports) = new.world_items(*new_id);
+            let imports = diff_items(&old_imports, &new_imports);
+            let exports = diff_items(&old_exports, &new_exports);
+            (!imports.is_empty() || !exports.is_empty()).then(|| WorldDiff {
+                name: name.clone(),
+                change: Change::Changed,
+                imports,
+                exports,
+            })
+        },
+    );
+    let version = VersionChange::new(&old_package.name, &new_package.name);
+    (version.is_some() || !interfaces.is_empty() || !worlds.is_empty()).then(|| PackageDiff {
+        name: name.to_string(),
+        change: Change::Changed,
+        version,
+        interfaces,
+        worlds,
+    })
+}
+
+/// Items of a package sorted by name
+fn by_name<'a, T: Copy + 'a>(
+    items: impl IntoIterator<Item = (&'a String, &'a T)>,
+) -> BTreeMap<String, T> {
+    items
+        .into_iter()
+        .map(|(name, item)| (name.clone(), *item))
+        .collect()
+}
+
+/// Renders items of a [`Resolve`] in WIT syntax
+struct Printer<'a>(&'a Resolve);
+
+impl Printer<'_> {
+    /// A package which only exists in one version, with all of its items
+    fn package(&self, name: &str, id: PackageId, change: Change) -> PackageDiff {
+        let package = &self.0.packages[id];
+        let version = package.name.version.as_ref().map(ToString::to_string);
+        PackageDiff {
+            name: name.to_string(),
+            change,
+            version: Some(VersionChange {
+                old: version.clone().filter(|_| change == Change::Removed),
+                new: version.filter(|_| change == Change::Added),
+                bump: None,
+                downgrade: false,
+                breaking: change == Change::Removed,
+            }),
+            interfaces: package
+                .interfaces
+                .iter()
+                .map(|(name, id)| self.interface(name, *id, change))
+                .collect(),
+            worlds: package
+                .worlds
+                .iter()
+                .map(|(name, id)| self.world(name, *id, change))
+                .collect(),
+        }
+    }
+
+    /// An interface which only exists in one version, with all of its items
+    fn interface(&self, name: &str, id: InterfaceId, change: Change) -> InterfaceDiff {
+        InterfaceDiff {
+            name: name.to_string(),
+            change,
+            functions: self.whole_items(self.functions(id), change),
+            types: self.whole_items(self.types(id), change),
+        }
+    }
+
+    /// A world which only exists in one version, with all of its items
+    fn world(&self, name: &str, id: WorldId, change: Change) -> WorldDiff {
+        let (imports, exports) = self.world_items(id);
+        WorldDiff {
+            name: name.to_string(),
+            change,
+            imports: self.whole_items(imports, change),
+            exports: self.whole_items(exports, change),
+        }
+    }
+
+    fn whole_items(&self, items: BTreeMap<String, String>, change: Change) -> Vec<ItemDiff> {
+        items
+            .into_iter()
+            .map(|(name, signature)| ItemDiff {
+                name,
+                change,
+                old: (change == Change::Removed).then(|| signature.clone()),
+                new: (change == Change::Added).then_some(signature),
+            })
+            .collect()
+    }
+
+    /// Signatures of the functions of an interface
+    fn functions(&self, id: InterfaceId) -> BTreeMap<String, String> {
+        self.0.interfaces[id]
+            .functions
+            .iter()
+            .map(|(name, func)| (name.clone(), self.function(func)))
+            .collect()
+    }
+
+    /// Definitions of the types of an interface
+    fn types(&self, id: InterfaceId) -> BTreeMap<String, String> {
+        self.0.interfaces[id]
+            .types
+            .iter()
+            .map(|(name, id)| (name.clone(), self.type_definition(*id)))
+            .collect()
+    }
+
+    /// Imports and exports of a world, with the signature of functions and the name of interfaces
+    fn world_items(&self, id: WorldId) -> (BTreeMap<String, String>, BTreeMap<String, String>) {
+        let world = &self.0.worlds[id];
+        (
+            self.world_item_signatures(&world.imports),
+            self.world_item_signatures(&world.exports),
+        )
+    }
+
+    fn world_item_signatures<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b WorldKey, &'b WorldItem)>,
+    ) -> BTreeMap<String, String> {
+        items
+            .into_iter()
+            .filter_map(|(key, item)| {
+                let name = self.world_key(key);
+                match item {
+                    WorldItem::Interface(id) => {
+                        Some((name, format!("interface {}", self.interface_name(*id))))
+                    }
+                    WorldItem::Function(func) => Some((name, self.function(func))),
+                    WorldItem::Type(_) => None,
+                }
+            })
+            .collect()
+    }
+
+    fn world_key(&self, key: &WorldKey) -> String {
+        match key {
+            WorldKey::Name(name) => name.clone(),
+            WorldKey::Interface(id) => self.interface_name(*id),
+        }
+    }
+
+    /// Name of an interface qualified by its package, without the version of the package
+    fn interface_name(&self, id: InterfaceId) -> String {
+        let interface = &self.0.interfaces[id];
+        let name = interface.name.as_deref().unwrap_or("<anonymous>");
+        match interface.package.map(|id| &self.0.packages[id].name) {
+            Some(package) => format!("{}:{}/{name}", package.namespace, package.name),
+            None => name.to_string(),
+        }
+    }
+
+    fn function(&self, func: &Function) -> String {
+        let params = self.params(&func.params);
+        match &func.results {
+            Results::Anon(ty) => format!("func({params}) -> {}", self.ty(ty)),
+            Results::Named(results) if results.is_empty() => format!("func({params})"),
+            Results::Named(results) => format!("func({params}) -> ({})", self.params(results)),
+        }
+    }
+
+    fn params(&self, params: &[(String, Type)]) -> String {
+        params
+            .iter()
+            .map(|(name, ty)| format!("{name}: {}", self.ty(ty)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn ty(&self, ty: &Type) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 => "u8".into(),
+            Type::U16 => "u16".into(),
+            Type::U32 => "u32".into(),
+            Type::U64 => "u64".into(),
+            Type::S8 => "s8".into(),
+            Type::S16 => "s16".into(),
+            Type::S32 => "s32".into(),
+            Type::S64 => "s64".into(),
+            Type::Float32 => "f32".into(),
+            Type::Float64 => "f64".into(),
+            Type::Char => "char".into(),
+            Type::String => "string".into(),
+            Type::Id(id) => match &self.0.types[*id].name {
+                Some(name) => name.clone(),
+                None => self.kind(&self.0.types[*id].kind),
+            },
+        }
+    }
+
+    fn optional_ty(&self, ty: Option<&Type>) -> String {
+        ty.map_or_else(|| "_".to_string(), |ty| self.ty(ty))
+    }
+
+    /// Definition of a named type
+    fn type_definition(&self, id: TypeId) -> String {
+        let def = &self.0.types[id];
+        match &def.kind {
+            TypeDefKind::Type(ty) => format!("type = {}", self.ty(ty)),
+            kind => self.kind(kind),
+        }
+    }
+
+    fn kind(&self, kind: &TypeDefKind) -> String {
+        match kind {
+            TypeDefKind::Record(record) => format!(
+                "record {{ {} }}",
+                record
+                    .fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, self.ty(&field.ty)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Variant(variant) => format!(
+                "variant {{ {} }}",
+                variant
+                    .cases
+                    .iter()
+                    .map(|case| match &case.ty {
+                        Some(ty) => format!("{}({})", case.name, self.ty(ty)),
+                        None => case.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Enum(enum_) => format!(
+                "enum {{ {} }}",
+                enum_
+                    .cases
+                    .iter()
+                    .map(|case| case.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Flags(flags) => format!(
+                "flags {{ {} }}",
+                flags
+                    .flags
+                    .iter()
+                    .map(|flag| flag.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Resource => "resource".into(),
+            TypeDefKind::Handle(Handle::Own(id)) => format!("own<{}>", self.ty(&Type::Id(*id))),
+            TypeDefKind::Handle(Handle::Borrow(id)) => {
+                format!("borrow<{}>", self.ty(&Type::Id(*id)))
+            }
+            TypeDefKind::Tuple(tuple) => format!(
+                "tuple<{}>",
+                tuple
+                    .types
+                    .iter()
+                    .map(|ty| self.ty(ty))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Option(ty) => format!("option<{}>", self.ty(ty)),
+            TypeDefKind::Result(result) => match (&result.ok, &result.err) {
+                (None, None) => "result".into(),
+                (ok, err) => format!(
+                    "result<{}, {}>",
+                    self.optional_ty(ok.as_ref()),
+                    self.optional_ty(err.as_ref())
+                ),
+            },
+            TypeDefKind::List(ty) => format!("list<{}>", self.ty(ty)),
+            TypeDefKind::Future(ty) => match ty {
+                Some(ty) => format!("future<{}>", self.ty(ty)),
+                None => "future".into(),
+            },
+            TypeDefKind::Type(ty) => self.ty(ty),
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use wit_parser::UnresolvedPackage;
+
+    use super::*;
+
+    fn resolve(wit: &str) -> Resolve {
+        let mut resolve = Resolve::default();
+        resolve
+            .push(
+                UnresolvedPackage::parse(Path::new("test.wit"), wit).expect("failed to parse WIT"),
+            )
+            .expect("failed to resolve WIT");
+        resolve
+    }
+
+    #[test]
+    fn test_diff_functions_and_versions() {
+        let old = resolve(
+            "package example:greeter@0.1.0;
+            interface greet {
+                record options { loud: bool }
+                greet: func(name: string) -> string;
+                farewell: func(name: string);
+            }
+            world greeter { export greet; }",
+        );
+        let new = resolve(
+            "package example:greeter@0.2.0;
+            interface greet {
+                record options { loud: bool, language: option<string> }
+                greet: func(name: string) -> string;
+                greet-many: func(names: list<string>) -> list<string>;
+                farewell: func(name: string, opts: options);
+            }
+            world greeter { export greet; }",
+        );
+        assert!(WitDiff::new(&old, &old).is_empty());
+
+        let diff = WitDiff::new(&old, &new);
+        let [package] = diff.packages.as_slice() else {
+            panic!("expected a single changed package, got {diff:?}");
+        };
+        assert_eq!(package.name, "example:greeter");
+        assert_eq!(
+            package.version,
+            Some(VersionChange {
+                old: Some("0.1.0".into()),
+                new: Some("0.2.0".into()),
+                bump: Some(VersionBump::Minor),
+                downgrade: false,
+                breaking: true,
+            })
+        );
+        assert!(package.worlds.is_empty());
+        let [interface] = package.interfaces.as_slice() else {
+            panic!("expected a single changed interface");
+        };
+        assert_eq!(
+            interface.functions,
+            [
+                ItemDiff {
+                    name: "farewell".into(),
+                    change: Change::Changed,
+                    old: Some("func(name: string)".into()),
+                    new: Some("func(name: string, opts: options)".into()),
+                },
+                ItemDiff {
+                    name: "greet-many".into(),
+                    change: Change::Added,
+                    old: None,
+                    new: Some("func(names: list<string>) -> list<string>".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            interface.types,
+            [ItemDiff {
+                name: "options".into(),
+                change: Change::Changed,
+                old: Some("record { loud: bool }".into()),
+                new: Some("record { loud: bool, language: option<string> }".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_version_change() {
+        let name = |version: &str| PackageName {
+            namespace: "wasi".into(),
+            name: "http".into(),
+            version: Some(version.parse().unwrap()),
+        };
+        let patch = VersionChange::new(&name("0.2.0"), &name("0.2.1")).unwrap();
+        assert_eq!(patch.bump, Some(VersionBump::Patch));
+        assert!(!patch.breaking && !patch.downgrade);
+        let minor = VersionChange::new(&name("1.2.0"), &name("1.3.0")).unwrap();
+        assert_eq!(minor.bump, Some(VersionBump::Minor));
+        assert!(!minor.breaking);
+        let downgrade = VersionChange::new(&name("2.0.0"), &name("1.0.0")).unwrap();
+        assert!(downgrade.breaking && downgrade.downgrade);
+        let pre = VersionChange::new(&name("0.2.0-rc.1"), &name("0.2.0")).unwrap();
+        assert_eq!(pre.bump, Some(VersionBump::Prerelease));
+        assert!(pre.breaking);
+        assert_eq!(VersionChange::new(&name("0.2.0"), &name("0.2.0")), None);
+    }
+}
@@ -0,0 +1,75 @@
+//! Management of the WIT packages of projects, behind the `wash wit` subcommands
+//!
+//! - [`deps`] vendors the dependencies of a WIT package from the registries configured for `wkg`,
+//!   and finds newer versions of the vendored packages
+//! - [`diff`] compares the interfaces and functions of two versions of a WIT package
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context as _, Result};
+use wit_parser::{PackageId, Resolve, UnresolvedPackage};
+
+pub mod deps;
+pub mod diff;
+
+/// Directory holding the WIT package of a project, relative to the project
+pub const DEFAULT_WIT_DIR: &str = "wit";
+
+/// Load the WIT package at `path`, either a directory of WIT files with dependencies in `deps/`
+/// or a single WIT file
+pub fn load_package(path: &Path) -> Result<(Resolve, PackageId)> {
+    let mut resolve = Resolve::default();
+    let package = if path.is_dir() {
+        resolve
+            .push_dir(path)
+            .with_context(|| format!("failed to load WIT directory [{}]", path.display()))?
+            .0
+    } else {
+        let unresolved = UnresolvedPackage::parse_file(path)
+            .with_context(|| format!("failed to parse WIT file [{}]", path.display()))?;
+        resolve
+            .push(unresolved)
+            .with_context(|| format!("failed to resolve WIT file [{}]", path.display()))?
+    };
+    Ok((resolve, package))
+}
+
+/// Load the WIT package in the directory `wit_dir` of a git repository as of the revision `rev`
+/// (e.g. a branch, tag or commit)
+pub fn load_package_at_rev(wit_dir: &Path, rev: &str) -> Result<(Resolve, PackageId)> {
+    let files = git(wit_dir, &["ls-tree", "-r", "--name-only", rev, "--", "."])
+        .with_context(|| format!("failed to list WIT files at revision [{rev}]"))?;
+    let files = String::from_utf8(files).context("WIT file names are not valid UTF-8")?;
+    if files.trim().is_empty() {
+        bail!(
+            "no WIT files found in [{}] at revision [{rev}]",
+            wit_dir.display()
+        );
+    }
+    let checkout = tempfile::tempdir().context("failed to create temporary directory")?;
+    for file in files.lines() {
+        let contents = git(wit_dir, &["show", &format!("{rev}:./{file}")])
+            .with_context(|| format!("failed to read [{file}] at revision [{rev}]"))?;
+        let path = checkout.path().join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write [{}]", path.display()))?;
+    }
+    load_package(checkout.path())
+}
+
+/// Run git in `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
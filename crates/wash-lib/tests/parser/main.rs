@@ -1,10 +1,15 @@
-use std::{collections::HashSet, fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
 
 use claims::{assert_err, assert_ok};
 use semver::Version;
 use wash_lib::parser::{
-    get_config, CommonConfig, ComponentConfig, LanguageConfig, RegistryConfig, RustConfig,
-    TinyGoConfig, TypeConfig, WasmTarget,
+    get_config, BuildConfig, CommonConfig, ComponentConfig, DevConfig, DevLinkConfig,
+    LanguageConfig, RegistryConfig, RustConfig, TestConfig, TinyGoConfig, TunnelConfig, TypeConfig,
+    WasmTarget,
 };
 
 #[test]
@@ -508,3 +513,182 @@ fn tags() {
         }) if tags == Some(HashSet::from(["test".into(), "wasmcloud.com/experimental".into()])),
     ));
 }
+
+/// `wash dev` env files are parsed from the `[dev]` section
+#[test]
+fn dev_env_files() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/dev_env_files.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.dev,
+        DevConfig {
+            env_files: vec![PathBuf::from(".env"), PathBuf::from("config/.env.dev")],
+            companions: vec![],
+            links: vec![],
+            tunnel: None,
+        }
+    );
+
+    // The section is optional
+    let config = assert_ok!(get_config(
+        Some(PathBuf::from("./tests/parser/files/tags.toml")),
+        None
+    ));
+    assert_eq!(config.dev, DevConfig::default());
+}
+
+/// `wash dev` companion components are parsed from the `[dev]` section
+#[test]
+fn dev_companions() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/dev_companions.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.dev.companions,
+        vec![
+            PathBuf::from("../auth-component"),
+            PathBuf::from("/opt/greeter")
+        ]
+    );
+}
+
+/// `wash dev --share` tunnels are parsed from the `[dev.tunnel]` section
+#[test]
+fn dev_tunnel() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/dev_tunnel.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.dev.tunnel,
+        Some(TunnelConfig::Wstunnel {
+            server: "wss://tunnel.example.com".into(),
+            remote_port: 8443,
+            public_url: "https://dev.example.com".into(),
+            binary: None,
+        })
+    );
+}
+
+/// `wash dev` provider links are parsed from the `[[dev.links]]` sections
+#[test]
+fn dev_links() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/dev_links.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert!(matches!(config.project_type, TypeConfig::Provider(_)));
+    assert_eq!(
+        config.dev.links,
+        vec![
+            DevLinkConfig {
+                source_id: Some("http-component".into()),
+                target: None,
+                name: "default".into(),
+                wit_namespace: "wasi".into(),
+                wit_package: "keyvalue".into(),
+                interfaces: vec!["store".into(), "atomics".into()],
+                source_config: vec![],
+                target_config: vec!["redis-url".into()],
+            },
+            DevLinkConfig {
+                source_id: None,
+                target: Some("handler".into()),
+                name: "events".into(),
+                wit_namespace: "wasmcloud".into(),
+                wit_package: "messaging".into(),
+                interfaces: vec!["handler".into()],
+                source_config: vec![],
+                target_config: vec![],
+            },
+        ]
+    );
+}
+
+/// `wash build` post commands are parsed from the `[build]` section
+#[test]
+fn build_post_commands() {
+    let result = get_config(
+        Some(PathBuf::from(
+            "./tests/parser/files/build_post_commands.toml",
+        )),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.build,
+        BuildConfig {
+            post_commands: vec![
+                "wasm-opt -O2 {input} -o {output}".into(),
+                "wasm-tools strip {input} -o {output}".into(),
+            ],
+            ..Default::default()
+        }
+    );
+
+    // The section is optional
+    let config = assert_ok!(get_config(
+        Some(PathBuf::from("./tests/parser/files/tags.toml")),
+        None
+    ));
+    assert_eq!(config.build, BuildConfig::default());
+}
+
+/// Environment variables of build processes are parsed from the `[build]` section
+#[test]
+fn build_env() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/build_env.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.build.env,
+        HashMap::from([
+            ("PROTOC".to_string(), "/opt/protoc/bin/protoc".to_string()),
+            (
+                "PKG_CONFIG_PATH".to_string(),
+                "/opt/protoc/lib/pkgconfig".to_string()
+            ),
+        ])
+    );
+    assert!(config.build.post_commands.is_empty());
+}
+
+#[test]
+fn test_config() {
+    let result = get_config(
+        Some(PathBuf::from("./tests/parser/files/test_config.toml")),
+        None,
+    );
+
+    let config = assert_ok!(result);
+    assert_eq!(
+        config.test,
+        TestConfig {
+            manifest: Some(PathBuf::from("./wadm.yaml")),
+            command: Some("cargo test --test integration -- --ignored".into()),
+            deploy_timeout_ms: Some(60000),
+        }
+    );
+
+    // The section is optional
+    let config = assert_ok!(get_config(
+        Some(PathBuf::from("./tests/parser/files/tags.toml")),
+        None
+    ));
+    assert_eq!(config.test, TestConfig::default());
+}
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
 use tokio::process::Command;
-use wash_lib::plugin::subcommand::DirMapping;
+use wash_lib::plugin::external::CONTEXT_JSON_ENV;
+use wash_lib::plugin::subcommand::{DirMapping, PluginPermissions, RunOptions, SubcommandRunner};
 
 /// Builds the plugin in the given directory. It must exist inside of tests/plugins/ and the
 /// directory name must match the name of the built binary. Returns the path to the built binary.
@@ -50,6 +51,10 @@ async fn test_subcommand() {
     assert_eq!(metadata.name, "Hello Plugin");
     assert_eq!(metadata.version, "0.1.0");
     assert_eq!(metadata.id, "hello");
+    assert!(
+        !subcommand.returns_output("hello"),
+        "plugins only exporting wasi:cli/run print their own output"
+    );
 
     let temp = tempfile::tempdir().unwrap();
     let extra_dir = tempfile::tempdir().unwrap();
@@ -65,7 +70,7 @@ async fn test_subcommand() {
     tokio::fs::write(&file, "Hello from a file").await.unwrap();
 
     // TODO: allow configuration of stdout/stderr so we can check for output
-    subcommand
+    let output = subcommand
         .run(
             "hello",
             temp.path().to_path_buf(),
@@ -88,6 +93,7 @@ async fn test_subcommand() {
         )
         .await
         .expect("Should be able to run plugin");
+    assert!(output.is_none());
 
     // Check that the file was written
     let file = tokio::fs::read_to_string(temp.path().join("hello.txt"))
@@ -95,3 +101,171 @@ async fn test_subcommand() {
         .unwrap();
     assert_eq!(file, "Hello from the plugin");
 }
+
+#[tokio::test]
+async fn test_subcommand_permissions() {
+    let plugin_path = build_plugin("hello_plugin").await;
+
+    let mut subcommand = wash_lib::plugin::subcommand::SubcommandRunner::new().unwrap();
+    subcommand
+        .add_plugin(&plugin_path)
+        .await
+        .expect("Should be able to add plugin");
+
+    let plugin_dir = tempfile::tempdir().unwrap();
+    assert_eq!(
+        PluginPermissions::load(plugin_dir.path(), "hello")
+            .await
+            .unwrap(),
+        PluginPermissions::default(),
+        "plugins without a permissions file should be granted nothing"
+    );
+
+    let granted = tempfile::tempdir().unwrap();
+    let permissions_path = PluginPermissions::path(plugin_dir.path(), "hello");
+    tokio::fs::create_dir_all(permissions_path.parent().unwrap())
+        .await
+        .unwrap();
+    tokio::fs::write(
+        &permissions_path,
+        format!("dirs = [{:?}]\n", granted.path().to_str().unwrap()),
+    )
+    .await
+    .unwrap();
+    let permissions = PluginPermissions::load(plugin_dir.path(), "hello")
+        .await
+        .unwrap();
+    assert_eq!(
+        permissions,
+        PluginPermissions {
+            http: false,
+            sockets: false,
+            dirs: vec![granted.path().to_path_buf()],
+        }
+    );
+
+    tokio::fs::write(&permissions_path, "filesystem = true\n")
+        .await
+        .unwrap();
+    PluginPermissions::load(plugin_dir.path(), "hello")
+        .await
+        .expect_err("unknown permissions should be rejected");
+
+    let scratch = tempfile::tempdir().unwrap();
+    let file_dir = tempfile::tempdir().unwrap();
+    let file = file_dir.path().join("hello.txt");
+    tokio::fs::write(&file, "Hello from a file").await.unwrap();
+
+    // The plugin fails gracefully on its denied HTTP request and keeps going
+    subcommand
+        .run_with_options(
+            "hello",
+            scratch.path().to_path_buf(),
+            vec![DirMapping {
+                host_path: file.clone(),
+                component_path: None,
+            }],
+            vec![
+                "hello".to_string(),
+                "--foo".to_string(),
+                granted.path().to_str().unwrap().to_string(),
+                file.to_str().unwrap().to_string(),
+            ],
+            RunOptions {
+                permissions,
+                env: vec![(
+                    "WASH_CONTEXT_JSON".to_string(),
+                    r#"{"name":"default"}"#.to_string(),
+                )],
+            },
+        )
+        .await
+        .expect("Should be able to run plugin with granted dirs");
+
+    let file = tokio::fs::read_to_string(scratch.path().join("hello.txt"))
+        .await
+        .unwrap();
+    assert_eq!(file, "Hello from the plugin");
+}
+
+#[tokio::test]
+async fn test_plugin_structured_output() {
+    let plugin_path = build_plugin("structured_plugin").await;
+
+    let mut plugins = SubcommandRunner::new().unwrap();
+    let metadata = plugins
+        .add_plugin(&plugin_path)
+        .await
+        .expect("Should be able to add plugin");
+    assert_eq!(metadata.name, "Structured Plugin");
+    assert_eq!(metadata.id, "greet");
+    assert_eq!(metadata.flags.len(), 2);
+    assert!(plugins.returns_output("greet"));
+
+    let scratch = tempfile::tempdir().unwrap();
+    let output = plugins
+        .run_with_options(
+            "greet",
+            scratch.path().to_path_buf(),
+            Vec::new(),
+            vec![
+                "greet".to_string(),
+                "--name".to_string(),
+                "wasmCloud".to_string(),
+            ],
+            RunOptions {
+                permissions: PluginPermissions::default(),
+                env: vec![(
+                    CONTEXT_JSON_ENV.to_string(),
+                    r#"{"name":"default","lattice":"test-lattice"}"#.to_string(),
+                )],
+            },
+        )
+        .await
+        .expect("Should be able to run plugin")
+        .expect("Plugin should return structured output");
+    assert_eq!(output.text, "Hello, wasmCloud!");
+    let json: serde_json::Value = serde_json::from_str(
+        output
+            .json
+            .as_deref()
+            .expect("Plugin should return JSON output"),
+    )
+    .expect("Plugin should return valid JSON");
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "greeting": "Hello, wasmCloud!",
+            "name": "wasmCloud",
+            "context": "default",
+            "lattice": "test-lattice",
+        })
+    );
+
+    // Without an active context, the plugin is passed `null`
+    let output = plugins
+        .run(
+            "greet",
+            scratch.path().to_path_buf(),
+            Vec::new(),
+            vec!["greet".to_string()],
+        )
+        .await
+        .expect("Should be able to run plugin without a context")
+        .expect("Plugin should return structured output");
+    assert_eq!(output.text, "Hello, world!");
+
+    let err = plugins
+        .run(
+            "greet",
+            scratch.path().to_path_buf(),
+            Vec::new(),
+            vec!["greet".to_string(), "--fail".to_string()],
+        )
+        .await
+        .expect_err("Errors returned by the plugin should be returned");
+    assert!(
+        err.to_string().contains("asked to fail"),
+        "unexpected error: {err}"
+    );
+}
@@ -0,0 +1,60 @@
+wit_bindgen::generate!();
+
+use exports::wasmcloud::wash::subcommand::{Argument, Guest as SubcommandGuest, Metadata};
+use exports::wasmcloud::wash_plugin::plugin::{Guest as PluginGuest, Output};
+
+/// A plugin greeting the user, returning structured output rather than printing it
+struct StructuredPlugin;
+
+impl SubcommandGuest for StructuredPlugin {
+    fn register() -> Metadata {
+        let flag = |description: &str| Argument {
+            description: description.to_string(),
+            is_path: false,
+            required: false,
+        };
+        Metadata {
+            name: "Structured Plugin".to_string(),
+            id: "greet".to_string(),
+            description: "A simple plugin that greets and returns structured output".to_string(),
+            author: "WasmCloud".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            flags: vec![
+                ("name".to_string(), flag("Who to greet")),
+                ("fail".to_string(), flag("Fail instead of greeting")),
+            ],
+            arguments: vec![],
+        }
+    }
+}
+
+impl PluginGuest for StructuredPlugin {
+    fn run(args: Vec<String>, context_json: String) -> Result<Output, String> {
+        if args.iter().any(|arg| arg == "--fail") {
+            return Err("asked to fail".to_string());
+        }
+        let name = args
+            .iter()
+            .skip_while(|arg| *arg != "--name")
+            .nth(1)
+            .cloned()
+            .unwrap_or_else(|| "world".to_string());
+        let context: serde_json::Value = serde_json::from_str(&context_json)
+            .map_err(|e| format!("invalid context JSON: {e}"))?;
+        let greeting = format!("Hello, {name}!");
+        Ok(Output {
+            json: Some(
+                serde_json::json!({
+                    "greeting": greeting,
+                    "name": name,
+                    "context": context["name"],
+                    "lattice": context["lattice"],
+                })
+                .to_string(),
+            ),
+            text: greeting,
+        })
+    }
+}
+
+export!(StructuredPlugin);
@@ -286,16 +286,14 @@ async fn main() -> anyhow::Result<()> {
         eprintln!("Failed to configure observability: {e}");
     };
 
-    let ctl_nats_url = Url::parse(&format!(
-        "nats://{}:{}",
-        args.ctl_host.unwrap_or_else(|| args.nats_host.clone()),
-        args.ctl_port.unwrap_or(args.nats_port)
+    let ctl_nats_url = Url::parse(&nats_url(
+        &args.ctl_host.unwrap_or_else(|| args.nats_host.clone()),
+        args.ctl_port.unwrap_or(args.nats_port),
     ))
     .context("failed to construct a valid `ctl_nats_url` using `ctl-host` and `ctl-port`")?;
-    let rpc_nats_url = Url::parse(&format!(
-        "nats://{}:{}",
-        args.rpc_host.unwrap_or_else(|| args.nats_host.clone()),
-        args.rpc_port.unwrap_or(args.nats_port)
+    let rpc_nats_url = Url::parse(&nats_url(
+        &args.rpc_host.unwrap_or_else(|| args.nats_host.clone()),
+        args.rpc_port.unwrap_or(args.nats_port),
     ))
     .context("failed to construct a valid `rpc_nats_url` using `rpc-host` and `rpc-port`")?;
 
@@ -421,6 +419,15 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Format a NATS URL, bracketing IPv6 literal hosts
+fn nats_url(host: &str, port: u16) -> String {
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("nats://[{host}]:{port}")
+    } else {
+        format!("nats://{host}:{port}")
+    }
+}
+
 fn parse_duration(arg: &str) -> anyhow::Result<Duration> {
     arg.parse()
         .map(Duration::from_millis)
@@ -10,6 +10,46 @@ use url::Url;
 use super::{free_port, tempdir, BackgroundServer};
 
 pub async fn start_nats() -> Result<(BackgroundServer, Url, NatsClient)> {
+    start_nats_with(&[], async_nats::ConnectOptions::new()).await
+}
+
+/// Start a NATS server that requires clients to authenticate with the given token
+pub async fn start_nats_with_token(token: &str) -> Result<(BackgroundServer, Url, NatsClient)> {
+    start_nats_with(
+        &["--auth", token],
+        async_nats::ConnectOptions::with_token(token.to_string()),
+    )
+    .await
+}
+
+/// Start a NATS server accepting messages of at most `max_payload` bytes
+pub async fn start_nats_with_max_payload(
+    max_payload: usize,
+) -> Result<(BackgroundServer, Url, NatsClient)> {
+    start_nats_with_config(
+        &format!("max_payload: {max_payload}\n"),
+        async_nats::ConnectOptions::new(),
+    )
+    .await
+}
+
+/// Start a NATS server with the given configuration file contents, connecting to it with `opts`
+pub async fn start_nats_with_config(
+    config: &str,
+    opts: async_nats::ConnectOptions,
+) -> Result<(BackgroundServer, Url, NatsClient)> {
+    let config_dir = tempdir()?;
+    let config_path = config_dir.path().join("nats.conf");
+    tokio::fs::write(&config_path, config)
+        .await
+        .context("failed to write NATS config")?;
+    start_nats_with(&["-c", config_path.display().to_string().as_str()], opts).await
+}
+
+async fn start_nats_with(
+    extra_args: &[&str],
+    opts: async_nats::ConnectOptions,
+) -> Result<(BackgroundServer, Url, NatsClient)> {
     let port = free_port().await?;
     let url =
         Url::parse(&format!("nats://localhost:{port}")).context("failed to parse NATS URL")?;
@@ -28,18 +68,17 @@ pub async fn start_nats() -> Result<(BackgroundServer, Url, NatsClient)> {
             &port.to_string(),
             "-sd",
             jetstream_dir.path().display().to_string().as_str(),
-        ]),
+        ])
+        .args(extra_args),
     )
     .await
     .context("failed to start NATS")?;
 
     // Wait until nats is ready to take connections
-    let nats_client = async_nats::connect_with_options(
-        url.as_str(),
-        async_nats::ConnectOptions::new().retry_on_initial_connect(),
-    )
-    .await
-    .context("failed to build nats client")?;
+    let nats_client =
+        async_nats::connect_with_options(url.as_str(), opts.retry_on_initial_connect())
+            .await
+            .context("failed to build nats client")?;
     let nats_client = timeout(Duration::from_secs(3), async move {
         loop {
             if nats_client.connection_state() == State::Connected {
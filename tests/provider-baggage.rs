@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{ensure, Context as _, Result};
+use async_nats::HeaderMap;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context as LayerContext, SubscriberExt};
+use tracing_subscriber::Layer;
+use wasmcloud_provider_sdk::baggage::BAGGAGE_HEADER;
+use wasmcloud_provider_sdk::provider::invocation_context;
+use wasmcloud_provider_sdk::{SdkSpanSampler, WrpcClient};
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+/// Ensure baggage set by a component reaches the provider, and is forwarded to the components it
+/// invokes along with the entries added by the provider
+#[tokio::test]
+async fn provider_baggage_round_trip() -> Result<()> {
+    // Component -> provider
+    let mut headers = HeaderMap::new();
+    headers.insert("traceparent", TRACEPARENT);
+    headers.insert("source-id", "component");
+    headers.insert("target-id", "provider");
+    headers.insert(BAGGAGE_HEADER, "tenant=acme,request-id=42;ttl=60");
+    let cx = invocation_context(&headers);
+    ensure!(cx.component.as_deref() == Some("component"));
+    ensure!(cx.baggage.len() == 2);
+    ensure!(cx.baggage["tenant"] == "acme");
+    ensure!(cx.baggage["request-id"] == "42");
+
+    // Provider -> component, only the trace context and baggage are propagated
+    let cx = cx.with_baggage("provider-region", "eu west");
+    let propagated = cx.propagation_headers();
+    ensure!(propagated.get("traceparent").map(|v| v.as_str()) == Some(TRACEPARENT));
+    ensure!(propagated.get("source-id").is_none() && propagated.get("target-id").is_none());
+
+    let nats = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect("127.0.0.1:1")
+        .await
+        .context("failed to build NATS client")?;
+    let mut client_headers = HeaderMap::new();
+    client_headers.insert("source-id", "provider");
+    client_headers.insert("target-id", "other-component");
+    let mut client = WrpcClient(wasmcloud_provider_sdk::core::wrpc::Client::new(
+        nats,
+        "default",
+        "other-component",
+        client_headers,
+        Duration::from_secs(1),
+    ))
+    .with_context(&cx);
+    let outgoing = client.0.headers_mut();
+    ensure!(outgoing.get("source-id").map(|v| v.as_str()) == Some("provider"));
+    ensure!(outgoing.get("target-id").map(|v| v.as_str()) == Some("other-component"));
+
+    let received = invocation_context(outgoing);
+    ensure!(received.component.as_deref() == Some("provider"));
+    ensure!(received.tracing.get("traceparent").map(String::as_str) == Some(TRACEPARENT));
+    ensure!(received.baggage.len() == 3);
+    ensure!(received.baggage["tenant"] == "acme");
+    ensure!(received.baggage["request-id"] == "42");
+    ensure!(received.baggage["provider-region"] == "eu west");
+    Ok(())
+}
+
+/// Layer counting the spans it sees
+#[derive(Clone, Default)]
+struct SpanCounter(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for SpanCounter {
+    fn on_new_span(&self, _attrs: &Attributes<'_>, _id: &Id, _ctx: LayerContext<'_, S>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Create 100 SDK spans and 100 provider spans with `sampler`, returning the number of spans
+/// recorded
+fn record_spans(sampler: SdkSpanSampler) -> usize {
+    let counter = SpanCounter::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(sampler)
+        .with(counter.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        for _ in 0..100 {
+            let _sdk = tracing::info_span!(target: "wasmcloud_provider_sdk::serve", "serve");
+            let _provider = tracing::info_span!(target: "my_provider", "handle");
+        }
+    });
+    counter.0.load(Ordering::Relaxed)
+}
+
+/// Ensure the sampling ratio applies to the spans of the SDK only
+#[test]
+fn provider_sdk_span_sampling() -> Result<()> {
+    let none = record_spans(SdkSpanSampler::new(0.0));
+    ensure!(none == 100, "recorded {none} spans with a ratio of 0");
+    let all = record_spans(SdkSpanSampler::new(1.0));
+    ensure!(all == 200, "recorded {all} spans with a ratio of 1");
+    let some = record_spans(SdkSpanSampler::new(0.5));
+    ensure!(
+        (110..=190).contains(&some),
+        "recorded {some} spans with a ratio of 0.5"
+    );
+    Ok(())
+}
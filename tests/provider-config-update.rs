@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use futures::StreamExt;
+use tokio::time::timeout;
+use wasmcloud_core::{config_update_subject, ConfigUpdateRequest};
+use wasmcloud_provider_sdk::REQUIRE_SIGNED_CONTROL_CONFIG_KEY;
+use wasmcloud_test_util::host::WasmCloudTestHost;
+use wasmcloud_test_util::lattice::config::assert_config_put;
+use wasmcloud_test_util::provider::{assert_start_provider, StartProviderArgs};
+
+pub mod common;
+use common::nats::start_nats;
+use common::providers;
+
+const LATTICE: &str = "provider-config-update";
+const CONFIG_NAME: &str = "http-client-config";
+
+/// Ensure hosts push changes of the named config of a provider to it, signed with the host key,
+/// and that providers requiring signed control messages ignore unsigned config updates
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_config_update() -> Result<()> {
+    let (nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let ctl_client = wasmcloud_control_interface::ClientBuilder::new(nats_client.clone())
+        .lattice(LATTICE.to_string())
+        .build();
+    let host = WasmCloudTestHost::start(&nats_url, LATTICE)
+        .await
+        .context("failed to start test host")?;
+    let host_key = host.host_key();
+
+    let http_client = providers::rust_http_client().await;
+    let provider_id = http_client.subject.public_key();
+    let signed_config = (
+        REQUIRE_SIGNED_CONTROL_CONFIG_KEY.to_string(),
+        "true".to_string(),
+    );
+    assert_config_put(&ctl_client, CONFIG_NAME, [signed_config.clone()]).await?;
+    assert_start_provider(StartProviderArgs {
+        client: &ctl_client,
+        lattice: LATTICE,
+        host_key: &host_key,
+        provider_key: &http_client.subject,
+        provider_id: &provider_id,
+        url: &http_client.url(),
+        config: vec![CONFIG_NAME.to_string()],
+    })
+    .await?;
+
+    // Unsigned updates are dropped by the provider, so they are never answered
+    let payload = serde_json::to_vec(&ConfigUpdateRequest {
+        host_id: host_key.public_key(),
+        config: HashMap::from([signed_config.clone()]),
+    })?;
+    let req = async_nats::Request::new()
+        .payload(payload.into())
+        .timeout(Some(Duration::from_secs(1)));
+    ensure!(
+        nats_client
+            .send_request(config_update_subject(LATTICE, &provider_id, "default"), req)
+            .await
+            .is_err(),
+        "unsigned config update was answered"
+    );
+
+    // Changes of the named config are pushed to the provider by the host
+    let mut events = nats_client
+        .subscribe(format!("wasmbus.evt.{LATTICE}.>"))
+        .await
+        .context("failed to subscribe to lattice events")?;
+    assert_config_put(
+        &ctl_client,
+        CONFIG_NAME,
+        [signed_config, ("timeout".to_string(), "10".to_string())],
+    )
+    .await?;
+    let event = timeout(Duration::from_secs(10), async {
+        while let Some(msg) = events.next().await {
+            let event: serde_json::Value =
+                serde_json::from_slice(&msg.payload).context("failed to decode event")?;
+            match event["type"].as_str() {
+                Some("com.wasmcloud.lattice.provider_config_updated") => return Ok(event),
+                Some("com.wasmcloud.lattice.provider_config_update_rejected") => {
+                    bail!("provider rejected config update: {event}")
+                }
+                _ => {}
+            }
+        }
+        bail!("lattice event subscription ended")
+    })
+    .await
+    .context("provider did not apply config update")??;
+    ensure!(event["data"]["provider_id"] == provider_id.as_str());
+    ensure!(event["data"]["host_id"] == host_key.public_key().as_str());
+    ensure!(event["data"]["accepted"] == true);
+
+    host.stop().await.context("failed to stop host")?;
+    nats_server.stop().await.context("failed to stop NATS")?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use tokio::sync::Barrier;
+use tokio::time::sleep;
+use wasmcloud_provider_sdk::{DedupFailureMode, DedupOutcome, DedupWindow};
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "dedup";
+const PROVIDER_ID: &str = "messaging-provider";
+
+/// Ensure that of two provider instances racing on the same ID, exactly one sees it first
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_dedup_concurrent() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let first = DedupWindow::new(nats_client.clone(), LATTICE, PROVIDER_ID);
+    let second = DedupWindow::new(nats_client.clone(), LATTICE, PROVIDER_ID);
+    for i in 0..20 {
+        let id = format!("message-{i}");
+        let barrier = Arc::new(Barrier::new(2));
+        let tasks = [first.clone(), second.clone()].map(|window| {
+            let id = id.clone();
+            let barrier = Arc::clone(&barrier);
+            tokio::spawn(async move {
+                barrier.wait().await;
+                window.check_and_set(&id, Duration::from_secs(60)).await
+            })
+        });
+        let mut first_seen = 0;
+        for task in tasks {
+            if task.await?? == DedupOutcome::FirstSeen {
+                first_seen += 1;
+            }
+        }
+        ensure!(first_seen == 1, "{id} was seen first {first_seen} times");
+    }
+
+    // IDs are namespaced by provider
+    let other = DedupWindow::new(nats_client, LATTICE, "other-provider");
+    ensure!(
+        other
+            .check_and_set("message-0", Duration::from_secs(60))
+            .await?
+            == DedupOutcome::FirstSeen
+    );
+    Ok(())
+}
+
+/// Ensure IDs are seen first again once their TTL has elapsed
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_dedup_ttl() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let window = DedupWindow::new(nats_client, LATTICE, PROVIDER_ID);
+    let ttl = Duration::from_millis(500);
+    ensure!(window.check_and_set("request", ttl).await? == DedupOutcome::FirstSeen);
+    ensure!(window.check_and_set("request", ttl).await? == DedupOutcome::Duplicate);
+    sleep(ttl + Duration::from_millis(100)).await;
+    ensure!(window.check_and_set("request", ttl).await? == DedupOutcome::FirstSeen);
+    ensure!(window.check_and_set("request", ttl).await? == DedupOutcome::Duplicate);
+    Ok(())
+}
+
+/// Ensure the configured failure mode applies when JetStream is unavailable
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_dedup_unavailable() -> Result<()> {
+    let nats_client = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect("127.0.0.1:1")
+        .await
+        .context("failed to build NATS client")?;
+
+    let closed = DedupWindow::new(nats_client.clone(), LATTICE, PROVIDER_ID);
+    ensure!(closed
+        .check_and_set("request", Duration::from_secs(60))
+        .await
+        .is_err());
+
+    let open = DedupWindow::new(nats_client, LATTICE, PROVIDER_ID)
+        .with_failure_mode(DedupFailureMode::Open);
+    ensure!(
+        open.check_and_set("request", Duration::from_secs(60))
+            .await?
+            == DedupOutcome::FirstSeen
+    );
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{ensure, Context as _, Result};
+use bytes::{Bytes, BytesMut};
+use futures::{stream, Future, StreamExt as _, TryStreamExt as _};
+use tokio::time::sleep;
+use wasmcloud_provider_sdk::interfaces::blobstore::BlobstoreClient;
+use wasmcloud_provider_sdk::interfaces::keyvalue::{KeyvalueClient, StoreError};
+use wasmcloud_provider_sdk::{Context, ProviderInvocationError, WrpcClient};
+use wrpc_interface_blobstore::BlobstoreInvocations;
+use wrpc_transport::{AcceptedInvocation, Transmitter as _};
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "interface-clients";
+
+mod mock {
+    wit_bindgen_wrpc::generate!({
+        inline: "
+            package wasmcloud:provider-sdk-test;
+
+            world mock-keyvalue {
+                export wrpc:keyvalue/store@0.2.0-draft;
+            }
+        ",
+        path: "crates/provider-sdk/wit",
+    });
+}
+
+use mock::exports::wrpc::keyvalue::store;
+
+/// In-memory `wrpc:keyvalue/store`, with a single `mock` bucket
+#[derive(Clone, Default)]
+struct MockStore(Arc<Mutex<HashMap<String, Vec<u8>>>>);
+
+impl MockStore {
+    fn check_bucket(bucket: &str) -> Result<(), store::Error> {
+        if bucket == "mock" {
+            Ok(())
+        } else {
+            Err(store::Error::NoSuchStore)
+        }
+    }
+}
+
+impl store::Handler<Option<Context>> for MockStore {
+    async fn delete(
+        &self,
+        _: Option<Context>,
+        bucket: String,
+        key: String,
+    ) -> Result<Result<(), store::Error>> {
+        Ok(Self::check_bucket(&bucket).map(|()| {
+            self.0.lock().unwrap().remove(&key);
+        }))
+    }
+
+    async fn exists(
+        &self,
+        _: Option<Context>,
+        bucket: String,
+        key: String,
+    ) -> Result<Result<bool, store::Error>> {
+        Ok(Self::check_bucket(&bucket).map(|()| self.0.lock().unwrap().contains_key(&key)))
+    }
+
+    async fn get(
+        &self,
+        _: Option<Context>,
+        bucket: String,
+        key: String,
+    ) -> Result<Result<Option<Vec<u8>>, store::Error>> {
+        Ok(Self::check_bucket(&bucket).map(|()| self.0.lock().unwrap().get(&key).cloned()))
+    }
+
+    async fn set(
+        &self,
+        _: Option<Context>,
+        bucket: String,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<Result<(), store::Error>> {
+        Ok(Self::check_bucket(&bucket).map(|()| {
+            self.0.lock().unwrap().insert(key, value);
+        }))
+    }
+
+    async fn list_keys(
+        &self,
+        _: Option<Context>,
+        bucket: String,
+        _cursor: Option<u64>,
+    ) -> Result<Result<store::KeyResponse, store::Error>> {
+        Ok(Self::check_bucket(&bucket).map(|()| {
+            let mut keys: Vec<_> = self.0.lock().unwrap().keys().cloned().collect();
+            keys.sort();
+            store::KeyResponse { keys, cursor: None }
+        }))
+    }
+}
+
+/// Serve the object data functions of `wrpc:blobstore/blobstore` from memory
+async fn serve_mock_blobstore(wrpc: WrpcClient) -> Result<()> {
+    let objects: Mutex<HashMap<(String, String), Bytes>> = Mutex::default();
+    let BlobstoreInvocations {
+        mut get_container_data,
+        mut write_container_data,
+        ..
+    } = wrpc_interface_blobstore::serve_blobstore(&wrpc).await?;
+    loop {
+        tokio::select! {
+            Some(invocation) = write_container_data.next() => {
+                let AcceptedInvocation {
+                    params: (id, data),
+                    result_subject,
+                    transmitter,
+                    ..
+                } = invocation?;
+                let data: BytesMut = data.try_collect().await?;
+                objects
+                    .lock()
+                    .unwrap()
+                    .insert((id.container, id.object), data.freeze());
+                transmitter
+                    .transmit_static(result_subject, anyhow::Ok(()))
+                    .await?;
+            }
+            Some(invocation) = get_container_data.next() => {
+                let AcceptedInvocation {
+                    params: (id, start, end),
+                    result_subject,
+                    transmitter,
+                    ..
+                } = invocation?;
+                let data = objects
+                    .lock()
+                    .unwrap()
+                    .get(&(id.container, id.object))
+                    .cloned()
+                    .context("object not found")
+                    .map(|data| {
+                        let end = usize::try_from(end).unwrap_or(usize::MAX).min(data.len().saturating_sub(1));
+                        let data = data.slice(usize::try_from(start).unwrap_or(usize::MAX).min(end)..=end);
+                        let chunks: Vec<anyhow::Result<Vec<Option<wrpc_transport::Value>>>> = data
+                            .chunks(64 * 1024)
+                            .map(|chunk| {
+                                Ok(chunk
+                                    .iter()
+                                    .copied()
+                                    .map(wrpc_transport::Value::U8)
+                                    .map(Some)
+                                    .collect())
+                            })
+                            .collect();
+                        wrpc_transport::Value::Stream(Box::pin(stream::iter(chunks)))
+                    });
+                transmitter.transmit_static(result_subject, data).await?;
+            }
+            else => return Ok(()),
+        }
+    }
+}
+
+/// Retry `f` until the mock it invokes has subscribed
+async fn retry<T, Fut>(mut f: impl FnMut() -> Fut) -> Result<T, ProviderInvocationError>
+where
+    Fut: Future<Output = Result<T, ProviderInvocationError>>,
+{
+    let mut attempts = 0;
+    loop {
+        match f().await {
+            Err(err) if attempts < 50 && err.message().contains("no responders") => {
+                attempts += 1;
+                sleep(Duration::from_millis(100)).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+fn wrpc_client(nats: &async_nats::Client, target: &str) -> WrpcClient {
+    let mut headers = async_nats::HeaderMap::new();
+    headers.insert("source-id", "provider");
+    headers.insert("target-id", target);
+    WrpcClient(wasmcloud_provider_sdk::core::wrpc::Client::new(
+        nats.clone(),
+        LATTICE,
+        target,
+        headers,
+        Duration::from_secs(10),
+    ))
+}
+
+/// Ensure a provider can use the typed keyvalue client against another provider's store
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_keyvalue_client() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+    let server = tokio::spawn({
+        let wrpc = wrpc_client(&nats_client, "mock-keyvalue");
+        async move { mock::serve(&wrpc, MockStore::default(), std::future::pending()).await }
+    });
+
+    let keyvalue = KeyvalueClient::new(wrpc_client(&nats_client, "mock-keyvalue"));
+    retry(|| keyvalue.set("mock", "greeting", b"hello")).await?;
+    ensure!(keyvalue.get("mock", "greeting").await? == Some(b"hello".to_vec()));
+    ensure!(keyvalue.exists("mock", "greeting").await?);
+    ensure!(keyvalue.list_keys("mock", None).await?.keys == ["greeting"]);
+    keyvalue.delete("mock", "greeting").await?;
+    ensure!(keyvalue.get("mock", "greeting").await?.is_none());
+
+    // Store errors map to structured errors
+    let err = keyvalue
+        .get("unknown", "greeting")
+        .await
+        .expect_err("unknown bucket should fail");
+    ensure!(err == ProviderInvocationError::from(StoreError::NoSuchStore));
+    ensure!(err.code() == ProviderInvocationError::INVALID_INPUT);
+
+    server.abort();
+    Ok(())
+}
+
+/// Ensure a provider can stream a large object through the typed blobstore client
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_blobstore_client() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+    let server = tokio::spawn(serve_mock_blobstore(wrpc_client(
+        &nats_client,
+        "mock-blobstore",
+    )));
+
+    let blob: Bytes = (0..5 * 1024 * 1024)
+        .map(|i: usize| (i % 251) as u8)
+        .collect::<Vec<_>>()
+        .into();
+    let blobstore = BlobstoreClient::new(wrpc_client(&nats_client, "mock-blobstore"));
+    retry(|| {
+        let chunks: Vec<_> = blob
+            .chunks(256 * 1024)
+            .map(Bytes::copy_from_slice)
+            .collect();
+        blobstore.write("container", "blob", stream::iter(chunks))
+    })
+    .await?;
+
+    let data: BytesMut = blobstore
+        .read("container", "blob", 0, blob.len() as u64 - 1)
+        .await?
+        .try_collect()
+        .await?;
+    ensure!(data.len() == blob.len(), "read {} bytes", data.len());
+    ensure!(data.freeze() == blob, "object data differs");
+
+    let err = match blobstore.read("container", "missing", 0, 10).await {
+        Ok(_) => anyhow::bail!("missing object should fail"),
+        Err(err) => err,
+    };
+    ensure!(err.code() == ProviderInvocationError::INTERNAL);
+
+    server.abort();
+    Ok(())
+}
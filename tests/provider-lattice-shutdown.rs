@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use futures::StreamExt;
+use nkeys::KeyPair;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::time::{sleep, timeout, Instant};
+use wasmcloud_core::{health_subject, shutdown_subject, HostData, SHUTDOWN_ALL_HOSTS};
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "lattice-shutdown";
+
+/// Start an instance of the provider `provider_key` on the host `host_id`
+async fn start_provider(nats_url: &url::Url, host_id: &str, provider_key: &str) -> Result<Child> {
+    let host_data = HostData {
+        host_id: host_id.to_string(),
+        lattice_rpc_prefix: LATTICE.to_string(),
+        lattice_rpc_url: nats_url.to_string(),
+        provider_key: provider_key.to_string(),
+        instance_id: format!("{host_id}-instance"),
+        link_name: "default".to_string(),
+        ..Default::default()
+    };
+    let host_data = base64::engine::general_purpose::STANDARD
+        .encode(serde_json::to_vec(&host_data).context("failed to serialize host data")?);
+
+    let mut provider = Command::new(env!("CARGO_BIN_EXE_http-client-provider"))
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider")?;
+    let mut stdin = provider
+        .stdin
+        .take()
+        .context("failed to take provider stdin")?;
+    stdin.write_all(host_data.as_bytes()).await?;
+    stdin.write_all(b"\r\n").await?;
+    stdin.flush().await?;
+    drop(stdin);
+    Ok(provider)
+}
+
+/// Publish a request on `subject`, collecting every reply received within `window`
+async fn request_all(
+    nats: &async_nats::Client,
+    subject: &str,
+    payload: Vec<u8>,
+    window: Duration,
+) -> Result<Vec<async_nats::Message>> {
+    let inbox = nats.new_inbox();
+    let mut replies = nats.subscribe(inbox.clone()).await?;
+    nats.publish_with_reply(subject.to_string(), inbox, payload.into())
+        .await?;
+    let deadline = Instant::now() + window;
+    let mut received = Vec::new();
+    while let Ok(Some(msg)) = tokio::time::timeout_at(deadline, replies.next()).await {
+        received.push(msg);
+    }
+    Ok(received)
+}
+
+/// Ensure a lattice-wide shutdown request is acknowledged by, and stops, every instance of a
+/// provider, each identifying itself in its acknowledgement
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_lattice_wide_shutdown() -> Result<()> {
+    let (nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let provider_key = KeyPair::new_service().public_key();
+    let hosts = [
+        KeyPair::new_server().public_key(),
+        KeyPair::new_server().public_key(),
+    ];
+    let mut providers = Vec::with_capacity(hosts.len());
+    for host_id in &hosts {
+        providers.push(start_provider(&nats_url, host_id, &provider_key).await?);
+    }
+
+    let health = health_subject(LATTICE, &provider_key);
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let replies =
+                request_all(&nats_client, &health, vec![], Duration::from_millis(250)).await?;
+            if replies.len() == hosts.len() {
+                break Ok::<_, anyhow::Error>(());
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .context("providers did not respond to health checks")??;
+
+    let subject = shutdown_subject(LATTICE, &provider_key, "default");
+
+    // Requests scoped to another link name are ignored
+    let acks = request_all(
+        &nats_client,
+        &subject,
+        serde_json::to_vec(&serde_json::json!({
+            "host_id": SHUTDOWN_ALL_HOSTS,
+            "link_name": "other",
+        }))?,
+        Duration::from_secs(1),
+    )
+    .await?;
+    ensure!(
+        acks.is_empty(),
+        "request for another link name was acknowledged"
+    );
+
+    let acks = request_all(
+        &nats_client,
+        &subject,
+        serde_json::to_vec(&serde_json::json!({ "host_id": SHUTDOWN_ALL_HOSTS }))?,
+        Duration::from_secs(5),
+    )
+    .await?;
+    let acked = acks
+        .iter()
+        .map(|ack| {
+            let ack: serde_json::Value =
+                serde_json::from_slice(&ack.payload).context("failed to parse shutdown ack")?;
+            ensure!(ack["reason"] == "shutdown requested for the lattice");
+            let host_id = ack["host_id"]
+                .as_str()
+                .context("ack should name its host")?;
+            ensure!(ack["instance_id"] == format!("{host_id}-instance"));
+            Ok(host_id.to_string())
+        })
+        .collect::<Result<BTreeSet<_>>>()?;
+    ensure!(
+        acked == BTreeSet::from(hosts.clone()),
+        "every instance should acknowledge the shutdown: {acked:?}"
+    );
+
+    for mut provider in providers {
+        timeout(Duration::from_secs(10), provider.wait())
+            .await
+            .context("provider did not exit after lattice-wide shutdown request")??;
+    }
+
+    nats_server.stop().await.context("failed to stop NATS")?;
+    Ok(())
+}
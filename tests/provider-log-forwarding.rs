@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use futures::StreamExt;
+use tokio::time::timeout;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use wasmcloud_core::rpc::provider_log_subject;
+use wasmcloud_provider_sdk::log_forwarding::LOG_BATCH_SIZE;
+use wasmcloud_provider_sdk::{log_forwarding, LogForwardingLayer, ProviderLogEvent};
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "log-forwarding";
+const PROVIDER_ID: &str = "log-provider";
+const HOST_ID: &str = "log-host";
+const EVENTS: usize = 150;
+
+/// Emit [`EVENTS`] info events in a span, and a debug event, through `layer`
+fn emit_events(layer: LogForwardingLayer) {
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        let span = tracing::info_span!("request", request_id = 42);
+        let _guard = span.enter();
+        for i in 0..EVENTS {
+            tracing::info!(i, "event {i}");
+        }
+        tracing::debug!("below the forwarded level");
+    });
+}
+
+/// Ensure log events are forwarded in order and in batches, with none lost
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_log_forwarding() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+    let mut sub = nats_client
+        .subscribe(provider_log_subject(LATTICE))
+        .await
+        .context("failed to subscribe to provider logs")?;
+    nats_client.flush().await?;
+
+    let (layer, forwarder) = log_forwarding(PROVIDER_ID, HOST_ID, Level::INFO);
+    let dropped = forwarder.dropped_events();
+    let forwarder = tokio::spawn(forwarder.run(nats_client.clone(), LATTICE.to_string()));
+    emit_events(layer);
+    // The layer was dropped along with the subscriber, so the forwarder publishes the remaining
+    // events and stops
+    timeout(Duration::from_secs(5), forwarder)
+        .await
+        .context("forwarder did not stop")??;
+
+    let mut events = Vec::with_capacity(EVENTS);
+    let mut batches = 0;
+    while events.len() < EVENTS {
+        let msg = timeout(Duration::from_secs(5), sub.next())
+            .await
+            .with_context(|| format!("received only {} events", events.len()))?
+            .context("subscription ended")?;
+        let batch: Vec<ProviderLogEvent> =
+            serde_json::from_slice(&msg.payload).context("failed to parse batch")?;
+        ensure!(!batch.is_empty() && batch.len() <= LOG_BATCH_SIZE);
+        batches += 1;
+        events.extend(batch);
+    }
+    ensure!(batches > 1, "events were not batched");
+    ensure!(events.len() == EVENTS, "received {} events", events.len());
+    for (i, event) in events.iter().enumerate() {
+        ensure!(
+            event.message == format!("event {i}"),
+            "event {i} is out of order"
+        );
+        ensure!(event.fields["i"] == i);
+        ensure!(event.provider_id == PROVIDER_ID);
+        ensure!(event.host_id == HOST_ID);
+        ensure!(event.level == "INFO");
+        ensure!(event.spans.len() == 1);
+        ensure!(event.spans[0].name == "request");
+        ensure!(event.spans[0].fields["request_id"] == 42);
+    }
+    ensure!(dropped.get() == 0);
+    Ok(())
+}
+
+/// Ensure log events are dropped and counted when they cannot be published
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_log_forwarding_dropped() -> Result<()> {
+    let nats_client = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect("127.0.0.1:1")
+        .await
+        .context("failed to build NATS client")?;
+
+    let (layer, forwarder) = log_forwarding(PROVIDER_ID, HOST_ID, Level::INFO);
+    let dropped = forwarder.dropped_events();
+    let forwarder = tokio::spawn(forwarder.run(nats_client, LATTICE.to_string()));
+    emit_events(layer);
+    timeout(Duration::from_secs(10), forwarder)
+        .await
+        .context("forwarder did not stop")??;
+    ensure!(
+        dropped.get() == EVENTS as u64,
+        "dropped {} events",
+        dropped.get()
+    );
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context as _, Result};
+use futures::Future;
+use tokio::time::sleep;
+use wasmcloud_provider_sdk::interfaces::keyvalue::KeyvalueClient;
+use wasmcloud_provider_sdk::{Context, ProviderInvocationError, WrpcClient};
+
+pub mod common;
+use common::nats::start_nats_with_max_payload;
+
+const LATTICE: &str = "max-payload";
+
+/// Max payload of the NATS server, in bytes
+const MAX_PAYLOAD: usize = 32 * 1024;
+
+mod mock {
+    wit_bindgen_wrpc::generate!({
+        inline: "
+            package wasmcloud:provider-sdk-test;
+
+            world mock-keyvalue {
+                export wrpc:keyvalue/store@0.2.0-draft;
+            }
+        ",
+        path: "crates/provider-sdk/wit",
+    });
+}
+
+use mock::exports::wrpc::keyvalue::store;
+
+/// Value larger than [`MAX_PAYLOAD`]
+fn large_value() -> Vec<u8> {
+    (0..4 * MAX_PAYLOAD).map(|i| (i % 251) as u8).collect()
+}
+
+/// `wrpc:keyvalue/store` returning [`large_value`] for every key
+#[derive(Clone, Default)]
+struct LargeValues;
+
+impl store::Handler<Option<Context>> for LargeValues {
+    async fn delete(
+        &self,
+        _: Option<Context>,
+        _bucket: String,
+        _key: String,
+    ) -> Result<Result<(), store::Error>> {
+        Ok(Ok(()))
+    }
+
+    async fn exists(
+        &self,
+        _: Option<Context>,
+        _bucket: String,
+        _key: String,
+    ) -> Result<Result<bool, store::Error>> {
+        Ok(Ok(true))
+    }
+
+    async fn get(
+        &self,
+        _: Option<Context>,
+        _bucket: String,
+        _key: String,
+    ) -> Result<Result<Option<Vec<u8>>, store::Error>> {
+        Ok(Ok(Some(large_value())))
+    }
+
+    async fn set(
+        &self,
+        _: Option<Context>,
+        _bucket: String,
+        _key: String,
+        _value: Vec<u8>,
+    ) -> Result<Result<(), store::Error>> {
+        Ok(Ok(()))
+    }
+
+    async fn list_keys(
+        &self,
+        _: Option<Context>,
+        _bucket: String,
+        _cursor: Option<u64>,
+    ) -> Result<Result<store::KeyResponse, store::Error>> {
+        Ok(Ok(store::KeyResponse {
+            keys: vec![],
+            cursor: None,
+        }))
+    }
+}
+
+/// Retry `f` until the mock it invokes has subscribed
+async fn retry<T, Fut>(mut f: impl FnMut() -> Fut) -> Result<T, ProviderInvocationError>
+where
+    Fut: Future<Output = Result<T, ProviderInvocationError>>,
+{
+    let mut attempts = 0;
+    loop {
+        match f().await {
+            Err(err) if attempts < 50 && err.message().contains("no responders") => {
+                attempts += 1;
+                sleep(Duration::from_millis(100)).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+fn wrpc_client(nats: &async_nats::Client, target: &str) -> WrpcClient {
+    let mut headers = async_nats::HeaderMap::new();
+    headers.insert("source-id", "provider");
+    headers.insert("target-id", target);
+    WrpcClient(wasmcloud_provider_sdk::core::wrpc::Client::new(
+        nats.clone(),
+        LATTICE,
+        target,
+        headers,
+        Duration::from_secs(10),
+    ))
+}
+
+/// Ensure payloads exceeding the max payload of the NATS server are either transmitted in chunks
+/// or rejected with a descriptive error
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_max_payload() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) = start_nats_with_max_payload(MAX_PAYLOAD)
+        .await
+        .context("failed to start NATS")?;
+    let server = tokio::spawn({
+        let wrpc = wrpc_client(&nats_client, "mock-keyvalue");
+        async move { mock::serve(&wrpc, LargeValues, std::future::pending()).await }
+    });
+
+    let wrpc = wrpc_client(&nats_client, "mock-keyvalue");
+    ensure!(wrpc.max_payload() == MAX_PAYLOAD);
+    let keyvalue = KeyvalueClient::new(wrpc);
+
+    // Results exceeding the max payload fall back to being transmitted in chunks
+    let value = retry(|| keyvalue.get("mock", "large")).await?;
+    ensure!(value == Some(large_value()), "large value differs");
+
+    // Parameters are sent along with the invocation, which cannot be chunked
+    let err = keyvalue
+        .set("mock", "large", &large_value())
+        .await
+        .expect_err("oversized parameters should fail");
+    ensure!(
+        err.code() == ProviderInvocationError::INVALID_INPUT,
+        "{err}"
+    );
+    ensure!(
+        err.message()
+            .contains("exceeds lattice max payload 32KiB — consider streaming"),
+        "error should be descriptive: {err}"
+    );
+
+    // Payloads within the max payload are unaffected
+    keyvalue.set("mock", "small", b"small").await?;
+
+    server.abort();
+    Ok(())
+}
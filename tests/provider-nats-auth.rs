@@ -0,0 +1,71 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::{sleep, timeout};
+use wasmcloud_core::{health_subject, HealthCheckResponse, HostData};
+
+pub mod common;
+use common::nats::start_nats_with_token;
+
+const LATTICE: &str = "nats-auth";
+const TOKEN: &str = "s3cr3t-lattice-token";
+
+/// Ensure providers can connect to a lattice that requires token authentication when the host
+/// supplies the token in the host data
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_nats_token_auth() -> Result<()> {
+    let (nats_server, nats_url, nats_client) = start_nats_with_token(TOKEN)
+        .await
+        .context("failed to start NATS")?;
+
+    let provider_key = nkeys::KeyPair::new_service().public_key();
+    let host_data = HostData {
+        host_id: nkeys::KeyPair::new_server().public_key(),
+        lattice_rpc_prefix: LATTICE.to_string(),
+        lattice_rpc_url: nats_url.to_string(),
+        lattice_rpc_user_token: Some(TOKEN.to_string()),
+        provider_key: provider_key.clone(),
+        link_name: "default".to_string(),
+        ..Default::default()
+    };
+    let host_data = base64::engine::general_purpose::STANDARD
+        .encode(serde_json::to_vec(&host_data).context("failed to serialize host data")?);
+
+    let mut provider = Command::new(env!("CARGO_BIN_EXE_http-client-provider"))
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider")?;
+    let mut stdin = provider
+        .stdin
+        .take()
+        .context("failed to take provider stdin")?;
+    stdin.write_all(host_data.as_bytes()).await?;
+    stdin.write_all(b"\r\n").await?;
+    stdin.flush().await?;
+    drop(stdin);
+
+    // The provider only answers health checks once it has authenticated to NATS
+    let subject = health_subject(LATTICE, &provider_key);
+    let response = timeout(Duration::from_secs(10), async {
+        loop {
+            match nats_client.request(subject.clone(), "".into()).await {
+                Ok(msg) => return msg,
+                Err(_) => sleep(Duration::from_millis(100)).await,
+            }
+        }
+    })
+    .await
+    .context("provider did not respond to health check")?;
+    let response: HealthCheckResponse = serde_json::from_slice(&response.payload)
+        .context("failed to parse health check response")?;
+    ensure!(response.healthy, "provider should be healthy");
+
+    provider.kill().await.context("failed to stop provider")?;
+    nats_server.stop().await.context("failed to stop NATS")?;
+    Ok(())
+}
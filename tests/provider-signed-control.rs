@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use nkeys::KeyPair;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::time::{sleep, timeout};
+use wasmcloud_core::control_auth::sign_control_message;
+use wasmcloud_core::{
+    health_subject, prepare_shutdown_subject, shutdown_subject, HostData, PrepareShutdownRequest,
+};
+use wasmcloud_provider_sdk::REQUIRE_SIGNED_CONTROL_CONFIG_KEY;
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "signed-control";
+
+/// Start a provider which requires control messages to be signed by `host_key`
+async fn start_provider(
+    nats_url: &url::Url,
+    host_key: &KeyPair,
+    provider_key: &str,
+) -> Result<Child> {
+    let host_data = HostData {
+        host_id: host_key.public_key(),
+        host_signing_key: Some(host_key.public_key()),
+        lattice_rpc_prefix: LATTICE.to_string(),
+        lattice_rpc_url: nats_url.to_string(),
+        provider_key: provider_key.to_string(),
+        link_name: "default".to_string(),
+        config: HashMap::from([(
+            REQUIRE_SIGNED_CONTROL_CONFIG_KEY.to_string(),
+            "true".to_string(),
+        )]),
+        ..Default::default()
+    };
+    let host_data = base64::engine::general_purpose::STANDARD
+        .encode(serde_json::to_vec(&host_data).context("failed to serialize host data")?);
+
+    let mut provider = Command::new(env!("CARGO_BIN_EXE_http-client-provider"))
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider")?;
+    let mut stdin = provider
+        .stdin
+        .take()
+        .context("failed to take provider stdin")?;
+    stdin.write_all(host_data.as_bytes()).await?;
+    stdin.write_all(b"\r\n").await?;
+    stdin.flush().await?;
+    drop(stdin);
+    Ok(provider)
+}
+
+/// Send a control request to the provider with the given headers, returning whether it answered it
+async fn send_control_request(
+    nats: &async_nats::Client,
+    subject: &str,
+    payload: &[u8],
+    headers: async_nats::HeaderMap,
+) -> bool {
+    let req = async_nats::Request::new()
+        .payload(payload.to_vec().into())
+        .headers(headers)
+        .timeout(Some(Duration::from_secs(1)));
+    nats.send_request(subject.to_string(), req).await.is_ok()
+}
+
+/// Ensure providers requiring signed control messages ignore unsigned, forged and redirected
+/// shutdown and drain requests, and act on requests signed by their host
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_signed_control_messages() -> Result<()> {
+    let (nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let host_key = KeyPair::new_server();
+    let provider_key = KeyPair::new_service().public_key();
+    let mut provider = start_provider(&nats_url, &host_key, &provider_key).await?;
+
+    let health = health_subject(LATTICE, &provider_key);
+    timeout(Duration::from_secs(10), async {
+        while nats_client
+            .request(health.clone(), "".into())
+            .await
+            .is_err()
+        {
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .context("provider did not respond to health check")?;
+
+    let subject = shutdown_subject(LATTICE, &provider_key, "default");
+    let payload = serde_json::to_vec(&serde_json::json!({ "host_id": host_key.public_key() }))?;
+
+    // Unsigned requests and requests signed by another key are rejected
+    ensure!(
+        !send_control_request(
+            &nats_client,
+            &subject,
+            &payload,
+            async_nats::HeaderMap::new()
+        )
+        .await,
+        "unsigned shutdown request was acknowledged"
+    );
+    let mut forged = async_nats::HeaderMap::new();
+    sign_control_message(&KeyPair::new_server(), &subject, &payload, &mut forged)?;
+    ensure!(
+        !send_control_request(&nats_client, &subject, &payload, forged).await,
+        "forged shutdown request was acknowledged"
+    );
+    ensure!(
+        nats_client.request(health.clone(), "".into()).await.is_ok(),
+        "provider should still be running"
+    );
+
+    // A signed request for another subject cannot be redirected to the provider
+    let mut redirected = async_nats::HeaderMap::new();
+    sign_control_message(
+        &host_key,
+        &shutdown_subject(LATTICE, "other-provider", "default"),
+        &payload,
+        &mut redirected,
+    )?;
+    ensure!(
+        !send_control_request(&nats_client, &subject, &payload, redirected).await,
+        "redirected shutdown request was acknowledged"
+    );
+
+    // Drain requests would stop the provider serving, so are verified as well
+    let drain_subject = prepare_shutdown_subject(LATTICE, &provider_key, "default");
+    let drain_payload = serde_json::to_vec(&PrepareShutdownRequest {
+        host_id: host_key.public_key(),
+        timeout_ms: 100,
+    })?;
+    ensure!(
+        !send_control_request(
+            &nats_client,
+            &drain_subject,
+            &drain_payload,
+            async_nats::HeaderMap::new()
+        )
+        .await,
+        "unsigned drain request was acknowledged"
+    );
+    let mut forged = async_nats::HeaderMap::new();
+    sign_control_message(
+        &KeyPair::new_server(),
+        &drain_subject,
+        &drain_payload,
+        &mut forged,
+    )?;
+    ensure!(
+        !send_control_request(&nats_client, &drain_subject, &drain_payload, forged).await,
+        "forged drain request was acknowledged"
+    );
+    let mut signed = async_nats::HeaderMap::new();
+    sign_control_message(&host_key, &drain_subject, &drain_payload, &mut signed)?;
+    ensure!(
+        send_control_request(&nats_client, &drain_subject, &drain_payload, signed).await,
+        "signed drain request was not acknowledged"
+    );
+
+    let mut signed = async_nats::HeaderMap::new();
+    sign_control_message(&host_key, &subject, &payload, &mut signed)?;
+    ensure!(
+        send_control_request(&nats_client, &subject, &payload, signed).await,
+        "signed shutdown request was not acknowledged"
+    );
+    timeout(Duration::from_secs(10), provider.wait())
+        .await
+        .context("provider did not exit after signed shutdown request")??;
+
+    nats_server.stop().await.context("failed to stop NATS")?;
+    Ok(())
+}
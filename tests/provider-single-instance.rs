@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use base64::Engine;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::sync::Barrier;
+use tokio::time::{sleep, timeout, Instant};
+use wasmcloud_core::{health_subject, HostData};
+use wasmcloud_provider_sdk::single_instance::SINGLE_INSTANCE_CONFIG_KEY;
+use wasmcloud_provider_sdk::{LockAcquisition, ProviderLock};
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "single-instance";
+const PROVIDER_ID: &str = "messaging-provider";
+const TTL: Duration = Duration::from_secs(1);
+
+async fn acquire(nats: &async_nats::Client, instance_id: &str) -> Result<LockAcquisition> {
+    ProviderLock::acquire(nats.clone(), LATTICE, PROVIDER_ID, instance_id, TTL).await
+}
+
+/// Ensure that of two instances of a provider starting at the same time, exactly one serves, and
+/// keeps serving for longer than the TTL of the lock
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_single_instance_concurrent() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let barrier = Arc::new(Barrier::new(2));
+    let tasks = ["first", "second"].map(|instance_id| {
+        let nats = nats_client.clone();
+        let barrier = Arc::clone(&barrier);
+        tokio::spawn(async move {
+            barrier.wait().await;
+            acquire(&nats, instance_id).await
+        })
+    });
+    let mut holders = Vec::new();
+    let mut held_by = Vec::new();
+    for task in tasks {
+        match task.await?? {
+            LockAcquisition::Acquired(lock) => holders.push(lock),
+            LockAcquisition::HeldBy(holder) => held_by.push(holder),
+        }
+    }
+    ensure!(
+        holders.len() == 1,
+        "{} instances acquired the lock",
+        holders.len()
+    );
+    let holder = holders.pop().context("missing holder")?;
+    ensure!(held_by == [holder.instance_id()]);
+
+    // The holder refreshes the lock, so that it is not taken over after its TTL
+    sleep(TTL * 3).await;
+    ensure!(!holder.is_lost());
+    ensure!(matches!(
+        acquire(&nats_client, "third").await?,
+        LockAcquisition::HeldBy(id) if id == holder.instance_id()
+    ));
+
+    // Locks are namespaced by provider
+    let other = ProviderLock::acquire(nats_client, LATTICE, "other-provider", "third", TTL).await?;
+    ensure!(matches!(other, LockAcquisition::Acquired(_)));
+    Ok(())
+}
+
+/// Ensure a replacement instance takes over within the TTL of the lock after its holder dies, and
+/// right away after its holder releases it
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_single_instance_failover() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let LockAcquisition::Acquired(first) = acquire(&nats_client, "first").await? else {
+        bail!("first instance did not acquire the lock");
+    };
+    ensure!(matches!(
+        acquire(&nats_client, "second").await?,
+        LockAcquisition::HeldBy(id) if id == "first"
+    ));
+
+    // Simulate the death of the holder, which stops refreshing the lock
+    drop(first);
+    let died = Instant::now();
+    let second = loop {
+        match acquire(&nats_client, "second").await? {
+            LockAcquisition::Acquired(lock) => break lock,
+            LockAcquisition::HeldBy(id) => ensure!(id == "first"),
+        }
+        ensure!(
+            died.elapsed() < TTL * 2,
+            "lock was not taken over after its TTL"
+        );
+        sleep(TTL / 10).await;
+    };
+
+    second.release().await?;
+    ensure!(matches!(
+        acquire(&nats_client, "third").await?,
+        LockAcquisition::Acquired(_)
+    ));
+    Ok(())
+}
+
+/// Ensure the holder notices when its lock is taken over, so that it shuts down
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_single_instance_lock_lost() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let LockAcquisition::Acquired(first) = acquire(&nats_client, "first").await? else {
+        bail!("first instance did not acquire the lock");
+    };
+    // Simulate the lock being taken over while the holder is partitioned, by overwriting it
+    let store = async_nats::jetstream::new(nats_client.clone())
+        .get_key_value(wasmcloud_provider_sdk::single_instance::PROVIDER_LOCK_BUCKET)
+        .await?;
+    store
+        .put(
+            format!("provider_lock.single-instance.{PROVIDER_ID}"),
+            r#"{"instance_id":"second","held_until":0}"#.into(),
+        )
+        .await?;
+    timeout(TTL, first.lost())
+        .await
+        .context("holder did not notice that its lock was taken over")?;
+    ensure!(first.is_lost());
+    Ok(())
+}
+
+/// Start an instance of a provider configured to run as a single instance
+async fn start_provider(nats_url: &url::Url, instance_id: &str) -> Result<Child> {
+    let host_data = HostData {
+        host_id: "single-instance-host".to_string(),
+        lattice_rpc_prefix: LATTICE.to_string(),
+        lattice_rpc_url: nats_url.to_string(),
+        provider_key: PROVIDER_ID.to_string(),
+        instance_id: instance_id.to_string(),
+        link_name: "default".to_string(),
+        config: HashMap::from([(SINGLE_INSTANCE_CONFIG_KEY.to_string(), "true".to_string())]),
+        ..Default::default()
+    };
+    let host_data = base64::engine::general_purpose::STANDARD
+        .encode(serde_json::to_vec(&host_data).context("failed to serialize host data")?);
+
+    let mut provider = Command::new(env!("CARGO_BIN_EXE_http-client-provider"))
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider")?;
+    let mut stdin = provider
+        .stdin
+        .take()
+        .context("failed to take provider stdin")?;
+    stdin.write_all(host_data.as_bytes()).await?;
+    stdin.write_all(b"\r\n").await?;
+    stdin.flush().await?;
+    drop(stdin);
+    Ok(provider)
+}
+
+/// Ensure a provider process finding its lock held by another live instance exits cleanly without
+/// serving, while the holder keeps serving
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_single_instance_duplicate_exits() -> Result<()> {
+    let (_nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let mut first = start_provider(&nats_url, "first").await?;
+    let health = health_subject(LATTICE, PROVIDER_ID);
+    timeout(Duration::from_secs(10), async {
+        while nats_client
+            .request(health.clone(), "".into())
+            .await
+            .is_err()
+        {
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .context("first instance did not respond to health check")?;
+
+    let mut second = start_provider(&nats_url, "second").await?;
+    let status = timeout(Duration::from_secs(10), second.wait())
+        .await
+        .context("second instance did not exit")??;
+    ensure!(status.success(), "second instance exited with {status}");
+
+    ensure!(
+        first.try_wait()?.is_none(),
+        "first instance should still be running"
+    );
+    ensure!(
+        nats_client.request(health, "".into()).await.is_ok(),
+        "first instance should still be serving"
+    );
+    first.kill().await?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use tokio::time::sleep;
+use wasmcloud_provider_sdk::StateSnapshotStore;
+
+pub mod common;
+use common::nats::start_nats;
+
+const LATTICE: &str = "state-snapshot";
+const PROVIDER_ID: &str = "messaging-provider";
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+async fn open(nats: &async_nats::Client, provider_id: &str) -> Result<StateSnapshotStore> {
+    StateSnapshotStore::open(nats.clone(), LATTICE, provider_id).await
+}
+
+/// Ensure the snapshot stored by the instance of a provider being updated is restored once by the
+/// instance replacing it, over separate connections
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_state_snapshot_round_trip() -> Result<()> {
+    let (_nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let previous = open(&nats_client, PROVIDER_ID).await?;
+    previous.store(b"first").await?;
+    // Only the latest snapshot is kept
+    previous.store(b"cursor=42").await?;
+    drop(previous);
+
+    let nats = async_nats::connect(nats_url.as_str())
+        .await
+        .context("failed to connect to NATS")?;
+    let next = open(&nats, PROVIDER_ID).await?;
+    ensure!(open(&nats, "other-provider")
+        .await?
+        .take(MAX_AGE)
+        .await?
+        .is_none());
+    let state = next.take(MAX_AGE).await?;
+    ensure!(
+        state.as_deref() == Some(b"cursor=42".as_slice()),
+        "{state:?}"
+    );
+
+    // Snapshots are deleted once taken
+    ensure!(next.take(MAX_AGE).await?.is_none());
+    Ok(())
+}
+
+/// Ensure snapshots older than the max age are discarded
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_state_snapshot_expired() -> Result<()> {
+    let (_nats_server, _nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let store = open(&nats_client, PROVIDER_ID).await?;
+    store.store(b"stale").await?;
+    sleep(Duration::from_millis(200)).await;
+    ensure!(store.take(Duration::from_millis(100)).await?.is_none());
+
+    // Expired snapshots are deleted too
+    ensure!(store.take(MAX_AGE).await?.is_none());
+    Ok(())
+}
+
+/// Ensure snapshots are not available when JetStream is unreachable, so that providers start cold
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_state_snapshot_unavailable() -> Result<()> {
+    let nats_client = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect("127.0.0.1:1")
+        .await
+        .context("failed to build NATS client")?;
+    ensure!(open(&nats_client, PROVIDER_ID).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::time::timeout;
+use wasmcloud_core::{health_subject, link_del_subject, HostData};
+
+pub mod common;
+use common::nats::start_nats_with_config;
+
+const LATTICE: &str = "subscription-failure";
+
+/// Ensure providers denied one of their command subscriptions fail to start, naming the denied
+/// subject, instead of running without it
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_denied_subscription() -> Result<()> {
+    let provider_key = nkeys::KeyPair::new_service().public_key();
+    let denied = link_del_subject(LATTICE, &provider_key);
+    let config = format!(
+        r#"
+authorization {{
+  users = [
+    {{ user: "admin", password: "admin" }}
+    {{
+      user: "provider"
+      password: "provider"
+      permissions: {{
+        publish: ">"
+        subscribe: {{ allow: ">", deny: "{denied}" }}
+      }}
+    }}
+  ]
+}}
+"#
+    );
+    let (nats_server, nats_url, nats_client) = start_nats_with_config(
+        &config,
+        async_nats::ConnectOptions::with_user_and_password("admin".into(), "admin".into()),
+    )
+    .await
+    .context("failed to start NATS")?;
+
+    let host_data = HostData {
+        host_id: nkeys::KeyPair::new_server().public_key(),
+        lattice_rpc_prefix: LATTICE.to_string(),
+        lattice_rpc_url: nats_url.to_string(),
+        lattice_rpc_user_name: Some("provider".to_string()),
+        lattice_rpc_user_password: Some("provider".to_string()),
+        provider_key: provider_key.clone(),
+        link_name: "default".to_string(),
+        ..Default::default()
+    };
+    let host_data = base64::engine::general_purpose::STANDARD
+        .encode(serde_json::to_vec(&host_data).context("failed to serialize host data")?);
+
+    let mut provider = Command::new(env!("CARGO_BIN_EXE_http-client-provider"))
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to spawn provider")?;
+    let mut stdin = provider
+        .stdin
+        .take()
+        .context("failed to take provider stdin")?;
+    stdin.write_all(host_data.as_bytes()).await?;
+    stdin.write_all(b"\r\n").await?;
+    stdin.flush().await?;
+    drop(stdin);
+
+    let status = timeout(Duration::from_secs(10), provider.wait())
+        .await
+        .context("provider did not exit after its subscription was denied")??;
+    ensure!(!status.success(), "provider should fail to start");
+    let mut stderr = String::new();
+    provider
+        .stderr
+        .take()
+        .context("failed to take provider stderr")?
+        .read_to_string(&mut stderr)
+        .await?;
+    ensure!(
+        stderr.contains(&format!("failed to subscribe to [{denied}]")),
+        "the denied subject should be reported: {stderr}"
+    );
+
+    // The subscriptions made before the denied one were torn down
+    ensure!(nats_client
+        .request(health_subject(LATTICE, &provider_key), "".into())
+        .await
+        .is_err());
+
+    nats_server.stop().await.context("failed to stop NATS")?;
+    Ok(())
+}